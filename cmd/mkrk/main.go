@@ -1,13 +1,16 @@
 package main
 
 import (
+	"errors"
 	"fmt"
 	"os"
 	"path/filepath"
+	"slices"
 	"strings"
 
 	"go.foia.dev/muckrake/internal/cli"
 	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
 	"go.foia.dev/muckrake/internal/generator"
 	"go.foia.dev/muckrake/internal/reference"
 )
@@ -20,25 +23,89 @@ type command struct {
 var commands = map[string]command{
 	"sync":     {cli.RunSync, "scan filesystem, track new files, verify integrity"},
 	"status":   {cli.RunStatus, "show project or file status"},
+	"verify":   {cli.RunVerify, "check integrity (--watch), accept intended changes (--accept)"},
 	"list":     {cli.RunList, "list files, optionally filtered by reference"},
 	"tag":      {cli.RunTag, "add or remove tags (--remove)"},
-	"sign":     {cli.RunSign, "create or revoke pipeline attestations (--remove)"},
-	"pipeline": {cli.RunPipeline, "create or remove pipelines (--remove)"},
-	"read":     {cli.RunRead, "output file contents to stdout"},
-	"open":     {cli.RunOpen, "open file in $PAGER"},
+	"tags":     {cli.RunTags, "list tags in use (tree groups namespaces)"},
+	"attr":     {cli.RunAttr, "set, get, list or remove key/value attributes on files"},
+	"sign":     {cli.RunSign, "create, revoke (--remove) or verify pipeline attestations"},
+	"pipeline": {cli.RunPipeline, "create or remove pipelines (--remove), or push workspace defaults"},
+	"state":    {cli.RunState, "show pipeline states and missing signs or signers"},
+	"rule":     {cli.RunRule, "add, list, test, export, import, remove, enable or disable event rules"},
+	"read":     {cli.RunRead, "output file contents to stdout (--version N for a past revision)"},
+	"versions": {cli.RunVersions, "list the recorded revisions of a file"},
+	"derive":   {cli.RunDerive, "record that a file was produced from another (--via)"},
+	"fetch":    {cli.RunFetch, "download a URL into a category through the privacy proxy and track it"},
+	"grep":     {cli.RunGrep, "search tracked file contents by regex"},
+	"foreach":  {cli.RunForeach, "run a command or tool once per matching file (-j N)"},
+	"open":     {cli.RunOpen, "open file in its registered viewer or $PAGER"},
+	"viewer":   {cli.RunViewer, "set, list or remove viewers used by open"},
 	"edit":     {cli.RunEdit, "open file in $EDITOR"},
+	"copy-out": {cli.RunCopyOut, "copy files out as editable working copies"},
+	"rm":       {cli.RunRm, "move files to the trash (--reason, --force for protected files)"},
+	"trash":    {cli.RunTrash, "list, restore or purge files removed with rm"},
+	"report":   {cli.RunReport, "render a Markdown or HTML project summary"},
+	"config":   {cli.RunConfig, "show or change project settings"},
+	"encrypt":  {cli.RunEncrypt, "encrypt a category's files or the database at rest (--remove)"},
+	"audit":    {cli.RunAudit, "check the audit log's hash chain (verify)"},
+	"proof":    {cli.RunProof, "export a Merkle proof that an excerpt belongs to a file (check)"},
+	"manifest": {cli.RunManifest, "export or verify SHA256SUMS manifests for sharing outside mkrk"},
+	"anchor":   {cli.RunAnchor, "anchor the project's state in Bitcoin with OpenTimestamps (upgrade, verify)"},
+	"storage":  {cli.RunStorage, "move file content to S3-compatible storage and back (offload, fetch)"},
+	"db":       {cli.RunDb, "back up (checked) or vacuum the project database"},
+
+	// Snapshots are taken into the project, unlike archive, which acts on
+	// whole projects from the workspace.
+	"archive-url": {cli.RunArchiveURL, "snapshot web pages into an immutable category (--wayback)"},
+}
+
+// workspaceCommands operate on the workspace as a whole rather than once
+// per project.
+var workspaceCommands = map[string]command{
+	"inbox":     {cli.RunInbox, "triage files staged in the workspace inbox"},
+	"clone":     {cli.RunClone, "start a new project with another's structure"},
+	"merge":     {cli.RunMerge, "move one project's files and history into another"},
+	"archive":   {cli.RunArchive, "freeze a project (--compress to cold storage)"},
+	"unarchive": {cli.RunUnarchive, "restore an archived project"},
+	"push":      {cli.RunPush, "copy projects and their history to a workspace or replica store"},
+	"pull":      {cli.RunPull, "copy projects and their history from a workspace or replica store"},
+	"find":      {cli.RunFind, "find files across projects by hash or name (--hash, --name)"},
+}
+
+// workspaceFlagCommands manage workspace defaults instead of a project's
+// own when given --workspace.
+var workspaceFlagCommands = map[string]func([]string) error{
+	"rule":     cli.RunWorkspaceRule,
+	"pipeline": cli.RunWorkspacePipeline,
+	"tool":     cli.RunWorkspaceTool,
+	"encrypt":  cli.RunWorkspaceEncrypt,
+	"db":       cli.RunWorkspaceDb,
+}
+
+// lockCommands seal and restore the project database.
+var lockCommands = map[string]func([]string) error{
+	"lock":   cli.RunLock,
+	"unlock": cli.RunUnlock,
 }
 
 const helpText = `mkrk — investigative journalism research management
 
-usage: mkrk [<subject>] <command> [args...]
+usage: mkrk [global flags] [<subject>] <command> [args...]
 
   subject is an optional :reference prefix naming what the command should
   operate on. Without one, commands run in the current working directory.
 
+global flags:
+  --workspace <name>    run in a workspace from mkrk registry, from anywhere
+  --include-archived    include archived projects in workspace iteration
+  --dry-run             show what sync, verify, merge, inbox, push, pull,
+                        foreach, rm and trash purge would do without
+                        changing anything
+
 subjects:
   :                     workspace-wide, iterate all projects
   :project              a specific project
+  :team/project         a project in the nested workspace team
   :project.category     a category within a project
   :.category            category across all projects in workspace
 
@@ -46,13 +113,55 @@ commands:
   init       initialize a project or workspace
   sync       scan filesystem, track new files, verify integrity
   status     show project or file status
+  verify     check integrity (--watch), accept intended changes (--accept)
   list       list files, optionally filtered by reference
   tag        add or remove tags (--remove)
-  sign       create or revoke pipeline attestations (--remove)
-  pipeline   create or remove pipelines (--remove)
-  read       output file contents to stdout
-  open       open file in $PAGER
+  tags       list tags in use (tree groups namespaces)
+  attr       set, get, list or remove key/value attributes on files
+  sign       create, revoke (--remove) or verify pipeline attestations
+  pipeline   create or remove pipelines (--remove), or push workspace defaults
+  state      show pipeline states and missing signs or signers
+  rule       add, list, test, export, import, remove, enable or disable event rules
+  read       output file contents to stdout (--version N for a past revision)
+  versions   list the recorded revisions of a file
+  derive     record that a file was produced from another (--via)
+  fetch      download a URL into a category through the privacy proxy and track it
+  archive-url
+             snapshot web pages into an immutable category (--wayback)
+  grep       search tracked file contents by regex
+  foreach    run a command or tool once per matching file (-j N)
+  open       open file in its registered viewer or $PAGER
+  viewer     set, list or remove viewers used by open
   edit       open file in $EDITOR
+  copy-out   copy files out as editable working copies
+  rm         move files to the trash (--reason, --force for protected files)
+  trash      list, restore or purge files removed with rm
+  report     render a Markdown or HTML project summary
+  config     show or change project settings
+  encrypt    encrypt a category's files or the database at rest (--remove)
+  audit      check the audit log's hash chain (verify)
+  proof      export a Merkle proof that an excerpt belongs to a file (check)
+  manifest   export or verify SHA256SUMS manifests for sharing outside mkrk
+  anchor     anchor the project's state in Bitcoin with OpenTimestamps (upgrade, verify)
+  storage    move file content to S3-compatible storage and back (offload, fetch)
+  db         back up (checked) or vacuum the database (--workspace)
+  ref        save, list or remove named references (:@name)
+  lock       seal the project database under a passphrase
+  unlock     restore a locked project database
+  secret     set, list or remove secrets for tool settings (${secret:NAME})
+  registry   add, list or remove workspaces reachable with --workspace
+  tor        ask Tor for new circuits (new-identity)
+  doctor     report database schema versions and pending migrations
+
+workspace commands:
+  inbox      triage files staged in the workspace inbox
+  clone      start a new project with another's structure
+  merge      move one project's files and history into another
+  archive    freeze a project (--compress to cold storage)
+  unarchive  restore an archived project
+  push       copy projects and their history to a workspace or replica store
+  pull       copy projects and their history from a workspace or replica store
+  find       find files across projects by hash or name (--hash, --name)
 
 references:
   :project              all files in a project (workspace scope)
@@ -65,12 +174,22 @@ references:
   :scope!tag            filter by tag (AND across ! groups)
   :scope!t1,t2          OR within a tag group
   :scope!t1!t2          AND across tag groups
+  :scope!ns/*           any tag in namespace ns (ns/a, ns/b/c)
+  :scope!~tag           files without the tag
+  :scope!^draft         leave out files whose names start with draft
+
+  :scope[size>10M]      filter by size, ingested date, mime type or attribute
+  :[ingested>=2025-01-01]
+  :scope[mime=image/*]
+  :scope[attr.key=val]
 
   scope/*.pdf           glob filter on filenames
   scope/filename.ext    specific file (/ needed when name has .)
   scope.Makefile        file without extension (. separator ok)
   /file.pdf             file at project root
 
+  :@name                a reference saved with mkrk ref save
+
   :                     all files (widest available scope)
   :!tag                 all files matching a tag
   :/*.pdf               all files matching a glob
@@ -79,12 +198,46 @@ references:
 `
 
 func main() {
-	args := os.Args[1:]
+	// Databases encrypted at rest ask for their passphrase as they are
+	// opened, wherever that happens.
+	db.Passphrase = cli.DatabasePassphrase
+
+	opts, args, err := parseGlobalFlags(os.Args[1:])
+	if err != nil {
+		fmt.Fprintf(os.Stderr, "error: %v\n", err)
+		os.Exit(1)
+	}
 	if len(args) == 0 {
 		fmt.Fprint(os.Stderr, helpText)
 		os.Exit(1)
 	}
 
+	// Refuse --dry-run before any command can run for real, including
+	// those dispatched ahead of the subject below.
+	if opts.dryRun {
+		verb := args[0]
+		if strings.HasPrefix(verb, ":") && len(args) > 1 {
+			verb = args[1]
+		}
+		if !dryRunCommands[verb] {
+			fmt.Fprintf(os.Stderr, "error: --dry-run is not supported by '%s'\n", verb)
+			os.Exit(1)
+		}
+	}
+
+	// --workspace runs everything below as if started at the root of the
+	// workspace it names, wherever the working directory is.
+	if opts.workspace != "" {
+		root, err := context.ResolveWorkspace(opts.workspace)
+		if err == nil {
+			err = os.Chdir(root)
+		}
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+	}
+
 	// Init creates context rather than consuming it.
 	if args[0] == "init" {
 		if err := cli.RunInit(args[1:]); err != nil {
@@ -94,10 +247,77 @@ func main() {
 		return
 	}
 
+	// Ref works once on the project or workspace at the working
+	// directory, not once per project.
+	if args[0] == "ref" {
+		if err := cli.RunRef(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+
+	// Workspace rules, default pipelines and tools, like refs, are
+	// managed once on the workspace.
+	if run, ok := workspaceFlagCommands[args[0]]; ok && slices.Contains(args[1:], "--workspace") {
+		if err := run(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+
+	// Secrets and the workspace registry belong to the user rather than
+	// any project.
+	if args[0] == "secret" {
+		if err := cli.RunSecret(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+	if args[0] == "tor" {
+		if err := cli.RunTor(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+	if args[0] == "registry" {
+		if err := cli.RunRegistry(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+
+	// Doctor inspects the databases as they are, before discovery
+	// migrates them.
+	if args[0] == "doctor" {
+		if err := cli.RunDoctor(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+
+	// Lock and unlock also run before discovery, which a locked project
+	// would refuse.
+	if lock, ok := lockCommands[args[0]]; ok {
+		if err := lock(args[1:]); err != nil {
+			fmt.Fprintf(os.Stderr, "error: %v\n", err)
+			os.Exit(1)
+		}
+		return
+	}
+
 	// Optional :ref prefix becomes the command subject.
 	var subject *reference.Reference
 	if strings.HasPrefix(args[0], ":") {
 		r, err := reference.ParseReference(args[0])
+		if err == nil && r.Kind == reference.KindSaved {
+			r, err = expandSavedSubject(r)
+		}
 		if err != nil {
 			fmt.Fprintf(os.Stderr, "error: %v\n", err)
 			os.Exit(1)
@@ -114,48 +334,123 @@ func main() {
 	verb := args[0]
 	cmdArgs := args[1:]
 
-	if err := run(verb, cmdArgs, subject); err != nil {
+	if err := run(verb, cmdArgs, subject, opts); err != nil {
 		fmt.Fprintf(os.Stderr, "error: %v\n", err)
-		os.Exit(1)
+		os.Exit(cli.ExitStatus(err))
 	}
 }
 
-func run(verb string, args []string, subject *reference.Reference) error {
+// expandSavedSubject replaces a :@name subject with the reference saved
+// under name in the project or workspace containing the working directory,
+// before dispatch decides which projects the command runs against.
+func expandSavedSubject(r *reference.Reference) (*reference.Reference, error) {
 	cwd, err := os.Getwd()
 	if err != nil {
-		return err
+		return nil, err
 	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return nil, err
+	}
+	defer ctx.Close()
+	return reference.ExpandSaved(r, ctx.SavedRefs()...)
+}
 
-	d, err := resolveDispatch(cwd, subject)
+// globalOptions are flags accepted before the subject and command.
+type globalOptions struct {
+	workspace       string
+	includeArchived bool
+	dryRun          bool
+}
+
+// dryRunCommands are the verbs that honor --dry-run. Any other verb is
+// refused under --dry-run rather than run for real.
+var dryRunCommands = map[string]bool{
+	"sync":    true,
+	"verify":  true,
+	"merge":   true,
+	"inbox":   true,
+	"push":    true,
+	"pull":    true,
+	"foreach": true,
+	"rm":      true,
+	"trash":   true,
+}
+
+// parseGlobalFlags strips leading global flags from args.
+func parseGlobalFlags(args []string) (globalOptions, []string, error) {
+	var opts globalOptions
+	for len(args) > 0 {
+		switch args[0] {
+		case "--workspace":
+			if len(args) < 2 {
+				return opts, nil, fmt.Errorf("--workspace needs a workspace name")
+			}
+			opts.workspace = args[1]
+			args = args[1:]
+		case "--include-archived":
+			opts.includeArchived = true
+		case "--dry-run":
+			opts.dryRun = true
+		default:
+			return opts, args, nil
+		}
+		args = args[1:]
+	}
+	return opts, args, nil
+}
+
+func run(verb string, args []string, subject *reference.Reference, opts globalOptions) error {
+	cwd, err := os.Getwd()
 	if err != nil {
 		return err
 	}
-	defer d.close()
 
+	if c, ok := workspaceCommands[verb]; ok {
+		return runWorkspace(c, cwd, subject, args, opts)
+	}
+
+	d, err := resolveDispatch(cwd, subject, opts)
+	if err != nil {
+		return err
+	}
 	if c, ok := commands[verb]; ok {
-		return runBuiltin(c, d, args)
+		err = runBuiltin(c, d, args, opts)
+	} else {
+		err = runGenerated(verb, d, args)
+	}
+	// Databases encrypted at rest are written back as they close.
+	if closeErr := d.close(); err == nil {
+		err = closeErr
 	}
-	return runGenerated(verb, d, args)
+	return err
 }
 
 // dispatch holds the set of project contexts a command should run against,
-// plus an optional workspace context that owns the shared workspace DB.
+// plus an optional workspace context that owns the shared workspace DB and
+// the nested workspaces opened beneath it.
 type dispatch struct {
-	workspace *context.Context
-	projects  []*context.Context
-	fallback  *context.Context
+	workspace     *context.Context
+	subWorkspaces []*context.WorkspaceContext
+	projects      []*context.Context
+	fallback      *context.Context
 }
 
-func (d *dispatch) close() {
+func (d *dispatch) close() error {
+	var errs []error
 	for _, p := range d.projects {
-		p.Close()
+		errs = append(errs, p.Close())
+	}
+	for _, ws := range d.subWorkspaces {
+		errs = append(errs, ws.Db.Close())
 	}
 	if d.workspace != nil {
-		d.workspace.Close()
+		errs = append(errs, d.workspace.Close())
 	}
 	if d.fallback != nil {
-		d.fallback.Close()
+		errs = append(errs, d.fallback.Close())
 	}
+	return errors.Join(errs...)
 }
 
 func (d *dispatch) contexts() []*context.Context {
@@ -170,9 +465,9 @@ func (d *dispatch) contexts() []*context.Context {
 
 // resolveDispatch picks the contexts to run the verb against based on the
 // subject ref (if any) and the current working directory.
-func resolveDispatch(cwd string, subject *reference.Reference) (*dispatch, error) {
+func resolveDispatch(cwd string, subject *reference.Reference, opts globalOptions) (*dispatch, error) {
 	if subject != nil && subject.Kind == reference.KindWorkspace {
-		return dispatchFromWorkspaceSubject(cwd, subject)
+		return dispatchFromWorkspaceSubject(cwd, subject, opts)
 	}
 
 	ctx, err := context.Discover(cwd)
@@ -185,84 +480,187 @@ func resolveDispatch(cwd string, subject *reference.Reference) (*dispatch, error
 		ctx.Subject = subject
 		return &dispatch{fallback: ctx}, nil
 	case context.ContextWorkspace:
-		return iterateWorkspaceProjects(ctx, subject)
+		return iterateWorkspaceProjects(ctx, subject, opts)
 	default:
 		ctx.Subject = subject
 		return &dispatch{fallback: ctx}, nil
 	}
 }
 
-func dispatchFromWorkspaceSubject(cwd string, subject *reference.Reference) (*dispatch, error) {
+func dispatchFromWorkspaceSubject(cwd string, subject *reference.Reference, opts globalOptions) (*dispatch, error) {
 	wsCtx, err := context.DiscoverWorkspace(cwd)
 	if err != nil {
 		return nil, err
 	}
+	d := &dispatch{workspace: wsCtx}
+
+	// :team/project descends into the nested workspace registered as team
+	// and reads the rest as a subject there, as many levels as it names.
+	ws, prefix := wsCtx.Workspace, ""
+	for !subject.WorkspaceWide && len(subject.Scope) > 0 {
+		name := subject.Scope[0].Names[0]
+		sub, err := context.OpenSubWorkspace(ws, name)
+		if err != nil {
+			d.close()
+			return nil, err
+		}
+		if sub == nil {
+			break
+		}
+		d.subWorkspaces = append(d.subWorkspaces, sub)
+		ws, prefix = sub, prefix+name+"/"
+		if len(subject.Scope) > 1 || len(subject.Tags) > 0 || len(subject.Predicates) > 0 || len(subject.Exclude) > 0 {
+			d.close()
+			return nil, fmt.Errorf("%q is a workspace: name a project in it as :%s<project>", name, prefix)
+		}
+		if subject.Glob == nil {
+			subject = &reference.Reference{Kind: reference.KindWorkspace}
+			break
+		}
+		if subject, err = reference.ParseReference(":" + *subject.Glob); err != nil {
+			d.close()
+			return nil, err
+		}
+	}
 
 	if !subject.WorkspaceWide && len(subject.Scope) > 0 {
 		projName := subject.Scope[0].Names[0]
-		proj, err := wsCtx.Workspace.Db.GetProjectByName(projName)
+		proj, err := ws.Db.GetProjectByName(projName)
 		if err != nil {
-			wsCtx.Close()
+			d.close()
 			return nil, err
 		}
 		if proj == nil {
-			wsCtx.Close()
-			return nil, fmt.Errorf("project %q not found in workspace", projName)
+			d.close()
+			return nil, fmt.Errorf("project %q not found in workspace", prefix+projName)
 		}
-		projRoot := filepath.Join(wsCtx.Workspace.Root, proj.Path)
-		pctx, err := context.OpenProjectContext(projRoot, projName, wsCtx.Workspace)
+		projRoot := filepath.Join(ws.Root, proj.Path)
+		pctx, err := context.OpenProjectContext(projRoot, prefix+projName, ws)
 		if err != nil {
-			wsCtx.Close()
+			d.close()
 			return nil, err
 		}
 		pctx.Subject = subject
-		return &dispatch{workspace: wsCtx, projects: []*context.Context{pctx}}, nil
+		d.projects = []*context.Context{pctx}
+		return d, nil
 	}
 
-	return iterateWorkspaceProjects(wsCtx, subject)
+	registered, err := d.addWorkspaceProjects(ws, prefix, subject, opts)
+	if err != nil {
+		d.close()
+		return nil, err
+	}
+	if registered == 0 {
+		d.close()
+		return nil, fmt.Errorf("no projects registered in workspace")
+	}
+	return d, nil
 }
 
-func iterateWorkspaceProjects(wsCtx *context.Context, subject *reference.Reference) (*dispatch, error) {
-	projects, err := wsCtx.Workspace.Db.ListProjects()
+// iterateWorkspaceProjects opens a context for every registered project,
+// including those of nested workspaces.
+func iterateWorkspaceProjects(wsCtx *context.Context, subject *reference.Reference, opts globalOptions) (*dispatch, error) {
+	d := &dispatch{workspace: wsCtx}
+	registered, err := d.addWorkspaceProjects(wsCtx.Workspace, "", subject, opts)
 	if err != nil {
-		wsCtx.Close()
+		d.close()
 		return nil, err
 	}
-	if len(projects) == 0 {
-		wsCtx.Close()
+	if registered == 0 {
+		d.close()
 		return nil, fmt.Errorf("no projects registered in workspace")
 	}
+	return d, nil
+}
+
+// addWorkspaceProjects opens a context for every project registered in ws
+// and, in turn, in the workspaces nested inside it, naming each by its path
+// from ws (team/alpha). Archived projects are skipped unless
+// --include-archived was given; naming one explicitly as the subject always
+// reaches it. It returns how many projects are registered, archived or not.
+func (d *dispatch) addWorkspaceProjects(ws *context.WorkspaceContext, prefix string, subject *reference.Reference, opts globalOptions) (int, error) {
+	projects, err := ws.Db.ListProjects()
+	if err != nil {
+		return 0, err
+	}
+	archived, err := ws.Db.ArchivedProjectNames()
+	if err != nil {
+		return 0, err
+	}
+	registered := len(projects)
 
-	var pctxs []*context.Context
 	for _, p := range projects {
-		projRoot := filepath.Join(wsCtx.Workspace.Root, p.Path)
+		if archived[p.Name] && !opts.includeArchived {
+			continue
+		}
+		projRoot := filepath.Join(ws.Root, p.Path)
 		if !fileExists(filepath.Join(projRoot, ".mkrk")) {
 			continue
 		}
-		pctx, err := context.OpenProjectContext(projRoot, p.Name, wsCtx.Workspace)
+		pctx, err := context.OpenProjectContext(projRoot, prefix+p.Name, ws)
 		if err != nil {
 			continue
 		}
 		pctx.Subject = subject
-		pctxs = append(pctxs, pctx)
+		d.projects = append(d.projects, pctx)
 	}
-	return &dispatch{workspace: wsCtx, projects: pctxs}, nil
+
+	subs, err := ws.Db.ListSubWorkspaces()
+	if err != nil {
+		return 0, err
+	}
+	for _, w := range subs {
+		if !fileExists(filepath.Join(ws.Root, w.Path, ".mksp")) {
+			continue
+		}
+		sub, err := context.OpenSubWorkspace(ws, w.Name)
+		if err != nil {
+			continue
+		}
+		d.subWorkspaces = append(d.subWorkspaces, sub)
+		n, err := d.addWorkspaceProjects(sub, prefix+w.Name+"/", subject, opts)
+		if err != nil {
+			return 0, err
+		}
+		registered += n
+	}
+	return registered, nil
 }
 
-func runBuiltin(c command, d *dispatch, args []string) error {
+func runBuiltin(c command, d *dispatch, args []string, opts globalOptions) error {
 	ctxs := d.contexts()
 	if len(ctxs) == 0 {
 		return fmt.Errorf("no context available")
 	}
 	var lastErr error
+	status := 0
 	for _, ctx := range ctxs {
+		ctx.DryRun = opts.dryRun
 		if err := c.run(ctx, args); err != nil {
 			lastErr = err
+			status |= cli.ExitStatus(err)
 		}
 	}
+	if lastErr != nil && status != cli.ExitStatus(lastErr) {
+		return &cli.StatusError{Code: status, Err: lastErr}
+	}
 	return lastErr
 }
 
+func runWorkspace(c command, cwd string, subject *reference.Reference, args []string, opts globalOptions) error {
+	ctx, err := context.DiscoverWorkspace(cwd)
+	if err != nil {
+		return err
+	}
+	ctx.Subject = subject
+	ctx.DryRun = opts.dryRun
+	err = c.run(ctx, args)
+	if closeErr := ctx.Close(); err == nil {
+		err = closeErr
+	}
+	return err
+}
+
 func runGenerated(verb string, d *dispatch, args []string) error {
 	ctxs := d.contexts()
 	if len(ctxs) == 0 {
@@ -285,7 +683,10 @@ func runGenerated(verb string, d *dispatch, args []string) error {
 
 func checkVerbCollisions(gens []generator.Generator) error {
 	for _, g := range gens {
-		if _, ok := commands[g.Verb]; ok {
+		_, builtin := commands[g.Verb]
+		_, wsBuiltin := workspaceCommands[g.Verb]
+		_, lockBuiltin := lockCommands[g.Verb]
+		if builtin || wsBuiltin || lockBuiltin {
 			return fmt.Errorf("generator verb %q (from scope %q in project %q) collides with built-in command",
 				g.Verb, g.Scope.Name, g.ProjectName)
 		}
@@ -0,0 +1,51 @@
+// Package builtins holds the tools muckrake provides itself. They run as
+// `mkrk tool <name>` like tools found in a project's tools category, and
+// as @name from rules. Each reads one file and writes a text derivative.
+package builtins
+
+import "io"
+
+// Tool is a built-in tool.
+type Tool struct {
+	Name string
+	Desc string
+	// Ext is appended to the input's name to name the derivative.
+	Ext string
+	// Tag is added to the input once its derivative is tracked.
+	Tag string
+	// Run writes the derivative of the file at input to w. Programs it
+	// starts get env, which carries the same proxy settings as the
+	// environment of project tools.
+	Run func(input string, w io.Writer, env []string) error
+}
+
+var registry = []Tool{
+	{
+		Name: "ocr",
+		Desc: "recognize the text in scanned PDFs and images (needs tesseract)",
+		Ext:  ".ocr.txt",
+		Run:  ocr,
+	},
+	{
+		Name: "transcribe",
+		Desc: "transcribe recordings with timestamps (needs whisper.cpp or a transcription API)",
+		Ext:  ".transcript.txt",
+		Tag:  "transcribed",
+		Run:  transcribe,
+	},
+}
+
+// Lookup returns the built-in tool with the given name, or nil.
+func Lookup(name string) *Tool {
+	for i := range registry {
+		if registry[i].Name == name {
+			return &registry[i]
+		}
+	}
+	return nil
+}
+
+// All returns every built-in tool.
+func All() []Tool {
+	return registry
+}
@@ -0,0 +1,91 @@
+package builtins
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"sort"
+	"strings"
+)
+
+// ocr recognizes the text of an image with tesseract. PDFs are rendered to
+// one image per page with pdftoppm first; tesseract ends each page with a
+// form feed, so page breaks survive in the output.
+func ocr(input string, w io.Writer, env []string) error {
+	if _, err := exec.LookPath("tesseract"); err != nil {
+		return fmt.Errorf("ocr needs tesseract on PATH")
+	}
+	if !strings.EqualFold(filepath.Ext(input), ".pdf") {
+		return tesseract(input, w, env)
+	}
+
+	if _, err := exec.LookPath("pdftoppm"); err != nil {
+		return fmt.Errorf("ocr of PDFs needs pdftoppm (poppler) on PATH")
+	}
+	dir, err := os.MkdirTemp("", "mkrk-ocr-")
+	if err != nil {
+		return err
+	}
+	defer os.RemoveAll(dir)
+
+	if err := run(command(env, "pdftoppm", "-r", "300", "-png", input, filepath.Join(dir, "page")), nil); err != nil {
+		return fmt.Errorf("pdftoppm: %w", err)
+	}
+	// Page numbers are zero-padded to a common width, so names sort in
+	// page order.
+	pages, err := filepath.Glob(filepath.Join(dir, "page-*.png"))
+	if err != nil {
+		return err
+	}
+	sort.Strings(pages)
+	for _, page := range pages {
+		if err := tesseract(page, w, env); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func tesseract(image string, w io.Writer, env []string) error {
+	if err := run(command(env, "tesseract", image, "stdout"), w); err != nil {
+		return fmt.Errorf("tesseract: %w", err)
+	}
+	return nil
+}
+
+func command(env []string, name string, args ...string) *exec.Cmd {
+	cmd := exec.Command(name, args...)
+	cmd.Env = env
+	return cmd
+}
+
+// getenv returns the last value env gives key, as exec would see it. The
+// environment built for tools carries project settings, with their
+// secrets filled in, that the process's own environment lacks.
+func getenv(env []string, key string) string {
+	prefix := key + "="
+	for i := len(env) - 1; i >= 0; i-- {
+		if v, ok := strings.CutPrefix(env[i], prefix); ok {
+			return v
+		}
+	}
+	return ""
+}
+
+// run runs cmd with its output going to w, reporting the command's own
+// error message when it fails.
+func run(cmd *exec.Cmd, w io.Writer) error {
+	var stderr bytes.Buffer
+	cmd.Stdout = w
+	cmd.Stderr = &stderr
+	if err := cmd.Run(); err != nil {
+		if msg := strings.TrimSpace(stderr.String()); msg != "" {
+			return fmt.Errorf("%s", msg)
+		}
+		return err
+	}
+	return nil
+}
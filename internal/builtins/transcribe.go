@@ -0,0 +1,120 @@
+package builtins
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"io"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+)
+
+// whisperBinaries are the names whisper.cpp's command-line program has
+// been installed under, newest first.
+var whisperBinaries = []string{"whisper-cli", "whisper-cpp"}
+
+// transcribe writes a transcript of a recording, one timestamped segment
+// per line. It posts the recording to the transcription API at
+// MKRK_TRANSCRIBE_URL when that is set, and otherwise runs whisper.cpp
+// locally with the model at MKRK_WHISPER_MODEL.
+func transcribe(input string, w io.Writer, env []string) error {
+	if url := getenv(env, "MKRK_TRANSCRIBE_URL"); url != "" {
+		return transcribeAPI(url, input, w, env)
+	}
+
+	var bin string
+	for _, name := range whisperBinaries {
+		if path, err := exec.LookPath(name); err == nil {
+			bin = path
+			break
+		}
+	}
+	if bin == "" {
+		return fmt.Errorf("transcribe needs whisper.cpp (%s) on PATH, or MKRK_TRANSCRIBE_URL", strings.Join(whisperBinaries, " or "))
+	}
+	model := getenv(env, "MKRK_WHISPER_MODEL")
+	if model == "" {
+		return fmt.Errorf("set MKRK_WHISPER_MODEL to a whisper.cpp model file")
+	}
+
+	// whisper.cpp reads 16 kHz WAV reliably; convert anything else when
+	// ffmpeg is around and let whisper.cpp try the original otherwise.
+	audio := input
+	if !strings.EqualFold(filepath.Ext(input), ".wav") {
+		if _, err := exec.LookPath("ffmpeg"); err == nil {
+			dir, err := os.MkdirTemp("", "mkrk-transcribe-")
+			if err != nil {
+				return err
+			}
+			defer os.RemoveAll(dir)
+			audio = filepath.Join(dir, "audio.wav")
+			if err := run(command(env, "ffmpeg", "-nostdin", "-i", input, "-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le", audio), nil); err != nil {
+				return fmt.Errorf("ffmpeg: %w", err)
+			}
+		}
+	}
+
+	// With no-prints set, whisper.cpp writes only the timestamped
+	// segments to stdout.
+	if err := run(command(env, bin, "-m", model, "-f", audio, "-np"), w); err != nil {
+		return fmt.Errorf("%s: %w", filepath.Base(bin), err)
+	}
+	return nil
+}
+
+// transcribeAPI posts a recording to an OpenAI-compatible transcription
+// endpoint. The request goes through curl so that it follows the same
+// proxy settings as any other tool; the key is passed on stdin rather than
+// the command line.
+func transcribeAPI(url, input string, w io.Writer, env []string) error {
+	if _, err := exec.LookPath("curl"); err != nil {
+		return fmt.Errorf("transcription through an API needs curl on PATH")
+	}
+	model := getenv(env, "MKRK_TRANSCRIBE_MODEL")
+	if model == "" {
+		model = "whisper-1"
+	}
+
+	cmd := command(env, "curl", "-sS", "--fail-with-body", "-H", "@-",
+		"-F", "file=@"+input, "-F", "model="+model, "-F", "response_format=verbose_json",
+		"-F", "timestamp_granularities[]=segment", url)
+	header := ""
+	if key := getenv(env, "MKRK_TRANSCRIBE_KEY"); key != "" {
+		header = "Authorization: Bearer " + key + "\n"
+	}
+	cmd.Stdin = strings.NewReader(header)
+	var body bytes.Buffer
+	if err := run(cmd, &body); err != nil {
+		return fmt.Errorf("transcription API: %w", err)
+	}
+
+	var resp struct {
+		Text     string `json:"text"`
+		Segments []struct {
+			Start float64 `json:"start"`
+			End   float64 `json:"end"`
+			Text  string  `json:"text"`
+		} `json:"segments"`
+	}
+	if err := json.Unmarshal(body.Bytes(), &resp); err != nil {
+		return fmt.Errorf("transcription API: unexpected response: %w", err)
+	}
+	if len(resp.Segments) == 0 {
+		_, err := fmt.Fprintln(w, strings.TrimSpace(resp.Text))
+		return err
+	}
+	for _, seg := range resp.Segments {
+		if _, err := fmt.Fprintf(w, "[%s --> %s]  %s\n", timestamp(seg.Start), timestamp(seg.End), strings.TrimSpace(seg.Text)); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// timestamp formats seconds the way whisper.cpp prints segment bounds.
+func timestamp(sec float64) string {
+	ms := int64(sec*1000 + 0.5)
+	return fmt.Sprintf("%02d:%02d:%02d.%03d", ms/3600000, ms/60000%60, ms/1000%60, ms%1000)
+}
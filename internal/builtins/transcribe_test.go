@@ -0,0 +1,17 @@
+package builtins
+
+import "testing"
+
+func TestTimestamp(t *testing.T) {
+	cases := map[float64]string{
+		0:       "00:00:00.000",
+		4.5:     "00:00:04.500",
+		61.0004: "00:01:01.000",
+		3725.25: "01:02:05.250",
+	}
+	for sec, want := range cases {
+		if got := timestamp(sec); got != want {
+			t.Errorf("timestamp(%v) = %s, want %s", sec, got, want)
+		}
+	}
+}
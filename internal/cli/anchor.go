@@ -0,0 +1,409 @@
+package cli
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"net/http"
+	"os"
+	"path/filepath"
+	"slices"
+	"strconv"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/ots"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+const anchorUsage = `usage: mkrk anchor [--calendar <url>]         commit the project's state to OpenTimestamps
+       mkrk anchor list
+       mkrk anchor upgrade                        fetch Bitcoin attestations for pending anchors
+       mkrk anchor verify <id> [<reference>...]
+       mkrk anchor export <id> -o <file>          write the statement and <file>.ots`
+
+// RunAnchor commits a digest of the project's state (every tracked hash
+// and the head of the audit chain) to an OpenTimestamps calendar, which
+// anchors it in Bitcoin. The statement and its proof are kept in the
+// project database; once upgraded, the proof shows the state existed
+// before the block that holds it.
+func RunAnchor(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(args) > 0 {
+		switch args[0] {
+		case "list", "ls":
+			return anchorList(ctx)
+		case "upgrade":
+			return anchorUpgrade(ctx)
+		case "verify":
+			return anchorVerify(ctx, args[1:])
+		case "export":
+			return anchorExport(ctx, args[1:])
+		}
+	}
+
+	fs := flag.NewFlagSet("anchor", flag.ExitOnError)
+	calendar := fs.String("calendar", "", "OpenTimestamps calendar URL (default: the ots-calendar setting, or "+ots.DefaultCalendar+")")
+	if len(parseInterspersed(fs, args)) > 0 {
+		return fmt.Errorf("%s", anchorUsage)
+	}
+	if *calendar == "" {
+		*calendar = ots.DefaultCalendar
+		if v, _ := ctx.ProjectDb.GetConfig("ots-calendar"); v != nil && *v != "" {
+			*calendar = *v
+		}
+	}
+
+	statement, files, audits, err := anchorStatement(ctx)
+	if err != nil {
+		return err
+	}
+	digest := sha256.Sum256(statement)
+
+	client, err := anchorClient(ctx, *calendar)
+	if err != nil {
+		return err
+	}
+	proof, err := ots.Stamp(client, *calendar, digest[:])
+	if err != nil {
+		return err
+	}
+
+	anchor := &models.Anchor{
+		CreatedAt: time.Now().UTC().Format(time.RFC3339),
+		Digest:    hex.EncodeToString(digest[:]),
+		Statement: statement,
+		Calendar:  *calendar,
+		Proof:     proof.Bytes(),
+	}
+	id, err := ctx.ProjectDb.InsertAnchor(anchor)
+	if err != nil {
+		return err
+	}
+	user := whoami()
+	detail, _ := json.Marshal(map[string]any{"anchor": id, "digest": anchor.Digest, "calendar": *calendar})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("anchor", nil, &user, &detailStr)
+
+	fmt.Println(anchor.Digest)
+	fmt.Fprintf(os.Stderr, "Anchored %d file(s) and %d audit entries as anchor %d\n", files, audits, id)
+	for _, a := range proof.Attestations() {
+		if a.Calendar != "" {
+			fmt.Fprintf(os.Stderr, "  pending at %s; run 'mkrk anchor upgrade' in a few hours\n", a.Calendar)
+		}
+	}
+	return nil
+}
+
+// anchorStatement describes the project's state: every tracked hash,
+// sorted, and the length and head of the audit chain. It names no paths.
+// A broken audit chain is refused, since anchoring it would vouch for it.
+func anchorStatement(ctx *context.Context) ([]byte, int, int64, error) {
+	files, err := ctx.ProjectDb.ListAllFiles()
+	if err != nil {
+		return nil, 0, 0, err
+	}
+	lines := make([]string, 0, len(files))
+	for _, f := range files {
+		lines = append(lines, f.HashAlgorithm+" "+f.SHA256)
+	}
+	slices.Sort(lines)
+	lines = slices.Compact(lines)
+
+	count, head, breaks, err := ctx.ProjectDb.VerifyAuditChain()
+	if err != nil {
+		return nil, 0, 0, err
+	}
+	if len(breaks) > 0 {
+		return nil, 0, 0, fmt.Errorf("audit log chain is broken in %d place(s) (run mkrk audit verify)", len(breaks))
+	}
+
+	var b bytes.Buffer
+	fmt.Fprintf(&b, "mkrk-anchor v1\nfiles %d\n", len(lines))
+	for _, line := range lines {
+		b.WriteString(line + "\n")
+	}
+	fmt.Fprintf(&b, "audit %d %s\n", count, head)
+	return b.Bytes(), len(lines), count, nil
+}
+
+// parseAnchorStatement reads back what anchorStatement wrote: the set of
+// "<algorithm> <hash>" lines and the audit chain's length and head.
+func parseAnchorStatement(statement []byte) (map[string]bool, int64, string, error) {
+	lines := strings.Split(strings.TrimSuffix(string(statement), "\n"), "\n")
+	if len(lines) < 3 || lines[0] != "mkrk-anchor v1" {
+		return nil, 0, "", fmt.Errorf("unrecognized anchor statement")
+	}
+	n, err := strconv.Atoi(strings.TrimPrefix(lines[1], "files "))
+	if err != nil || n != len(lines)-3 {
+		return nil, 0, "", fmt.Errorf("malformed anchor statement")
+	}
+	hashes := make(map[string]bool, n)
+	for _, line := range lines[2 : 2+n] {
+		hashes[line] = true
+	}
+	fields := strings.Fields(lines[len(lines)-1])
+	if len(fields) < 2 || fields[0] != "audit" {
+		return nil, 0, "", fmt.Errorf("malformed anchor statement")
+	}
+	count, err := strconv.ParseInt(fields[1], 10, 64)
+	if err != nil {
+		return nil, 0, "", fmt.Errorf("malformed anchor statement")
+	}
+	head := ""
+	if len(fields) > 2 {
+		head = fields[2]
+	}
+	return hashes, count, head, nil
+}
+
+// anchorClient returns a client for talking to calendars, through the
+// privacy proxy unless privacy is off.
+func anchorClient(ctx *context.Context, calendar string) (*http.Client, error) {
+	privacy := privacySettings(ctx)
+	client, err := privacy.httpClient()
+	if err != nil {
+		return nil, err
+	}
+	if via := privacy.via(calendar); via != "" {
+		fmt.Fprintf(os.Stderr, "privacy: calendar requests to %s go through %s\n", calendar, via)
+	} else {
+		fmt.Fprintf(os.Stderr, "privacy: DISABLED — calendar requests to %s go direct\n", calendar)
+	}
+	return client, nil
+}
+
+func anchorList(ctx *context.Context) error {
+	anchors, err := ctx.ProjectDb.ListAnchors()
+	if err != nil {
+		return err
+	}
+	if len(anchors) == 0 {
+		fmt.Println("(no anchors)")
+	}
+	for _, a := range anchors {
+		fmt.Printf("%d  %s  %s  %s\n", a.ID, a.CreatedAt, a.Digest[:16], anchorStatus(a))
+	}
+	return nil
+}
+
+// anchorStatus says where an anchor's proof stands.
+func anchorStatus(a models.Anchor) string {
+	proof, err := ots.Parse(a.Proof)
+	if err != nil {
+		return "\033[31munreadable proof\033[0m"
+	}
+	var blocks []string
+	for _, att := range proof.Attestations() {
+		if att.Height > 0 {
+			blocks = append(blocks, fmt.Sprintf("Bitcoin block %d", att.Height))
+		}
+	}
+	if len(blocks) > 0 {
+		return strings.Join(blocks, ", ")
+	}
+	return "pending"
+}
+
+// anchorUpgrade asks calendars for the Bitcoin attestations of pending
+// anchors. Calendars only have them once the transaction committing their
+// digests is mined, a few hours after the anchor was made.
+func anchorUpgrade(ctx *context.Context) error {
+	anchors, err := ctx.ProjectDb.ListAnchors()
+	if err != nil {
+		return err
+	}
+	var upgraded, pending, failed int
+	var client *http.Client
+	for _, a := range anchors {
+		proof, err := ots.Parse(a.Proof)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! anchor %d: %v\n", a.ID, err)
+			failed++
+			continue
+		}
+		if proof.Confirmed() {
+			continue
+		}
+		if client == nil {
+			if client, err = anchorClient(ctx, a.Calendar); err != nil {
+				return err
+			}
+		}
+		// A proof names the calendars to ask. Only the one the anchor was
+		// submitted to, and those it hands off to over https, are asked.
+		allowed := func(uri string) bool {
+			return uri == a.Calendar || strings.HasPrefix(uri, "https://")
+		}
+		changed, err := proof.Upgrade(client, allowed)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! anchor %d: %v\n", a.ID, err)
+			failed++
+		}
+		if changed {
+			if err := ctx.ProjectDb.UpdateAnchorProof(a.ID, proof.Bytes()); err != nil {
+				return err
+			}
+		}
+		if proof.Confirmed() {
+			a.Proof = proof.Bytes()
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m anchor %d: %s\n", a.ID, anchorStatus(a))
+			upgraded++
+		} else {
+			pending++
+		}
+	}
+	fmt.Fprintf(os.Stderr, "Anchors: %d upgraded, %d still pending, %d failed\n", upgraded, pending, failed)
+	if failed > 0 {
+		return fmt.Errorf("%d anchor(s) could not be upgraded", failed)
+	}
+	return nil
+}
+
+func findAnchor(ctx *context.Context, raw string) (*models.Anchor, error) {
+	id, err := strconv.ParseInt(raw, 10, 64)
+	if err != nil {
+		return nil, fmt.Errorf("expected an anchor id, got '%s'", raw)
+	}
+	anchors, err := ctx.ProjectDb.ListAnchors()
+	if err != nil {
+		return nil, err
+	}
+	for _, a := range anchors {
+		if a.ID == id {
+			return &a, nil
+		}
+	}
+	return nil, fmt.Errorf("no anchor %d", id)
+}
+
+// anchorVerify checks an anchor against itself and against the project
+// as it is now: the statement must hash to the digest the proof commits
+// to, and the audit chain must still pass through the head it recorded.
+// Referenced files are reported as anchored or not.
+func anchorVerify(ctx *context.Context, args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", anchorUsage)
+	}
+	a, err := findAnchor(ctx, args[0])
+	if err != nil {
+		return err
+	}
+
+	var failures []string
+	fail := func(format string, v ...any) {
+		msg := fmt.Sprintf(format, v...)
+		fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s\n", msg)
+		failures = append(failures, msg)
+	}
+	ok := func(format string, v ...any) {
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", fmt.Sprintf(format, v...))
+	}
+
+	if sum := sha256.Sum256(a.Statement); hex.EncodeToString(sum[:]) != a.Digest {
+		fail("statement does not hash to the anchored digest")
+	}
+	proof, err := ots.Parse(a.Proof)
+	switch {
+	case err != nil:
+		fail("proof: %v", err)
+	case hex.EncodeToString(proof.Digest) != a.Digest:
+		fail("proof is for another digest")
+	default:
+		for _, att := range proof.Attestations() {
+			if att.Height > 0 {
+				ok("Bitcoin block %d attests to the digest (its merkle root must be %s)", att.Height, att.MerkleRoot)
+			} else {
+				fmt.Fprintf(os.Stderr, "  - pending at %s\n", att.Calendar)
+			}
+		}
+	}
+
+	hashes, count, head, err := parseAnchorStatement(a.Statement)
+	if err != nil {
+		return err
+	}
+	if count > 0 {
+		if now, err := ctx.ProjectDb.AuditHash(count); err != nil {
+			return err
+		} else if now != head {
+			fail("audit entry %d no longer has the anchored hash; the log was rewritten", count)
+		} else {
+			ok("audit log still passes through entry %d as anchored", count)
+		}
+	}
+
+	tracked := 0
+	for h := range hashes {
+		alg, hash, _ := strings.Cut(h, " ")
+		if f, _ := ctx.ProjectDb.GetFileByHash(hash); f != nil && f.HashAlgorithm == alg {
+			tracked++
+		}
+	}
+	fmt.Fprintf(os.Stderr, "  %d of %d anchored hashes are still tracked\n", tracked, len(hashes))
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	for _, raw := range args[1:] {
+		rels, err := resolve.RefRelPaths(ctx, raw)
+		if err != nil {
+			return err
+		}
+		for _, rel := range rels {
+			ref := reference.FormatRef(rel, projectName, ctx.ProjectDb)
+			alg, err := ctx.HashAlgorithm()
+			if err != nil {
+				return err
+			}
+			hash, err := ctx.HashFile(filepath.Join(ctx.ProjectRoot, rel))
+			if err != nil {
+				fail("%s: %v", ref, err)
+			} else if hashes[string(alg)+" "+hash] {
+				ok("%s: anchored with this content", ref)
+			} else {
+				fail("%s: its current content was not anchored", ref)
+			}
+		}
+	}
+
+	fmt.Fprintf(os.Stderr, "Anchor %d (%s): %s\n", a.ID, a.CreatedAt, anchorStatus(*a))
+	if len(failures) > 0 {
+		return fmt.Errorf("anchor %d failed verification", a.ID)
+	}
+	return nil
+}
+
+// anchorExport writes an anchor's statement and, next to it, its proof as
+// a .ots file, which the OpenTimestamps client checks with
+// `ots verify <file>.ots`.
+func anchorExport(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("anchor export", flag.ExitOnError)
+	output := fs.String("output", "", "file to write the statement to; the proof goes to <file>.ots")
+	fs.StringVar(output, "o", "", "shorthand for --output")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 || *output == "" {
+		return fmt.Errorf("%s", anchorUsage)
+	}
+	a, err := findAnchor(ctx, positional[0])
+	if err != nil {
+		return err
+	}
+	if err := os.WriteFile(*output, a.Statement, 0o644); err != nil {
+		return err
+	}
+	if err := os.WriteFile(*output+".ots", a.Proof, 0o644); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Wrote anchor %d to %s and %s.ots\n", a.ID, *output, *output)
+	return nil
+}
@@ -0,0 +1,326 @@
+package cli
+
+import (
+	"archive/tar"
+	"compress/gzip"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// archiveManifest describes the contents of a cold-storage archive so it
+// can be checked without unpacking.
+type archiveManifest struct {
+	Project    string                 `json:"project"`
+	ArchivedAt string                 `json:"archived_at"`
+	Archive    string                 `json:"archive"`
+	SHA256     string                 `json:"sha256"`
+	Files      []archiveManifestEntry `json:"files"`
+}
+
+type archiveManifestEntry struct {
+	Path   string `json:"path"`
+	SHA256 string `json:"sha256"`
+	Size   int64  `json:"size"`
+}
+
+func RunArchive(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("archive", flag.ExitOnError)
+	compress := fs.String("compress", "", "also write a .tar.gz and manifest to this directory")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	if len(positional) != 1 {
+		return fmt.Errorf("usage: mkrk archive <project> [--compress <dir>]")
+	}
+	name := positional[0]
+
+	if a, _ := ctx.Workspace.Db.GetArchivedProject(name); a != nil {
+		return fmt.Errorf("project %q is already archived", name)
+	}
+	pctx, err := openWorkspaceProject(ctx, name)
+	if err != nil {
+		return err
+	}
+
+	categories, err := pctx.ProjectDb.ListCategories()
+	if err != nil {
+		pctx.Close()
+		return err
+	}
+
+	prior := make(map[string]string)
+	for _, c := range categories {
+		level, _ := pctx.ProjectDb.GetPolicyForScope(*c.ID)
+		if level != nil {
+			prior[c.Name] = string(*level)
+		}
+	}
+
+	// Until the record holding the prior policies is stored, any failure
+	// puts them back rather than leave the project frozen.
+	for _, c := range categories {
+		if err := pctx.ProjectDb.InsertScopePolicy(*c.ID, models.ProtectionImmutable); err != nil {
+			restorePolicies(pctx, prior)
+			pctx.Close()
+			return err
+		}
+	}
+	failed := applyImmutableFlags(pctx)
+	projectRoot := pctx.ProjectRoot
+	pctx.Close()
+	undo := func(cause error) error {
+		pctx, err := openWorkspaceProject(ctx, name)
+		if err == nil {
+			_, err = restorePolicies(pctx, prior)
+			pctx.Close()
+		}
+		if err != nil {
+			return fmt.Errorf("%w (restoring category policies also failed: %v)", cause, err)
+		}
+		return cause
+	}
+
+	priorJSON, _ := json.Marshal(prior)
+	now := time.Now().UTC().Format(time.RFC3339)
+	record := &db.ArchivedProject{
+		ProjectName:   name,
+		ArchivedAt:    now,
+		PriorPolicies: string(priorJSON),
+	}
+
+	if *compress != "" {
+		path, err := compressProject(name, projectRoot, *compress, now)
+		if err != nil {
+			return undo(err)
+		}
+		record.ArchivePath = &path
+		fmt.Fprintf(os.Stderr, "  Archive: %s\n", path)
+	}
+
+	if err := ctx.Workspace.Db.ArchiveProject(record); err != nil {
+		return undo(err)
+	}
+	user := whoami()
+	ctx.Workspace.Db.InsertAudit("archive", &name, &user, nil)
+
+	fmt.Fprintf(os.Stderr, "Archived project '%s' (%d categories set immutable)\n", name, len(categories))
+	if failed > 0 {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m could not update the immutable flag on %d file(s)\n", failed)
+	}
+	return nil
+}
+
+func RunUnarchive(ctx *context.Context, args []string) error {
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	if len(args) != 1 {
+		return fmt.Errorf("usage: mkrk unarchive <project>")
+	}
+	name := args[0]
+
+	record, err := ctx.Workspace.Db.GetArchivedProject(name)
+	if err != nil {
+		return err
+	}
+	if record == nil {
+		return fmt.Errorf("project %q is not archived", name)
+	}
+
+	var prior map[string]string
+	if err := json.Unmarshal([]byte(record.PriorPolicies), &prior); err != nil {
+		return fmt.Errorf("corrupt archive record: %w", err)
+	}
+
+	pctx, err := openWorkspaceProject(ctx, name)
+	if err != nil {
+		return err
+	}
+	defer pctx.Close()
+
+	failed, err := restorePolicies(pctx, prior)
+	if err != nil {
+		return err
+	}
+
+	if _, err := ctx.Workspace.Db.UnarchiveProject(name); err != nil {
+		return err
+	}
+	user := whoami()
+	ctx.Workspace.Db.InsertAudit("unarchive", &name, &user, nil)
+
+	fmt.Fprintf(os.Stderr, "Unarchived project '%s' (category policies restored)\n", name)
+	if failed > 0 {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m could not update the immutable flag on %d file(s)\n", failed)
+	}
+	return nil
+}
+
+// restorePolicies sets each category back to the protection level prior
+// holds for it by name, clearing the policy of any category it lacks, and
+// reapplies the immutable flags. It returns the number of files whose flag
+// could not be updated.
+func restorePolicies(pctx *context.Context, prior map[string]string) (int, error) {
+	categories, err := pctx.ProjectDb.ListCategories()
+	if err != nil {
+		return 0, err
+	}
+	for _, c := range categories {
+		levelStr, ok := prior[c.Name]
+		if !ok {
+			pctx.ProjectDb.RemoveScopePolicy(*c.ID)
+			continue
+		}
+		level, err := models.ParseProtectionLevel(levelStr)
+		if err != nil {
+			return 0, err
+		}
+		if err := pctx.ProjectDb.InsertScopePolicy(*c.ID, level); err != nil {
+			return 0, err
+		}
+	}
+	return applyImmutableFlags(pctx), nil
+}
+
+func openWorkspaceProject(ctx *context.Context, name string) (*context.Context, error) {
+	proj, err := ctx.Workspace.Db.GetProjectByName(name)
+	if err != nil {
+		return nil, err
+	}
+	if proj == nil {
+		return nil, fmt.Errorf("project %q not found in workspace", name)
+	}
+	return context.OpenProjectContext(filepath.Join(ctx.Workspace.Root, proj.Path), proj.Name, ctx.Workspace)
+}
+
+// applyImmutableFlags brings the filesystem immutable flag of every file in
+// the project in line with its resolved protection level. Returns how many
+// files could not be updated (e.g. chattr unavailable or not permitted).
+func applyImmutableFlags(ctx *context.Context) int {
+	patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+	if err != nil {
+		return 0
+	}
+	entries, err := walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+	if err != nil {
+		return 0
+	}
+
+	failed := 0
+	for _, relPath := range entries {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+		isImmutable, _ := integrity.IsImmutable(absPath)
+		switch {
+		case protection == models.ProtectionImmutable && !isImmutable:
+			if integrity.SetImmutable(absPath) != nil {
+				failed++
+			}
+		case protection != models.ProtectionImmutable && isImmutable:
+			if integrity.ClearImmutable(absPath) != nil {
+				failed++
+			}
+		}
+	}
+	return failed
+}
+
+// compressProject writes <name>-<date>.tar.gz of the whole project
+// directory, including its database, plus a JSON manifest beside it.
+func compressProject(name, projectRoot, destDir, archivedAt string) (string, error) {
+	if err := os.MkdirAll(destDir, 0o755); err != nil {
+		return "", err
+	}
+	base := fmt.Sprintf("%s-%s", name, time.Now().UTC().Format("20060102-150405"))
+	archivePath, err := filepath.Abs(filepath.Join(destDir, base+".tar.gz"))
+	if err != nil {
+		return "", err
+	}
+
+	out, err := os.OpenFile(archivePath, os.O_CREATE|os.O_EXCL|os.O_WRONLY, 0o644)
+	if err != nil {
+		return "", err
+	}
+	gz := gzip.NewWriter(out)
+	tw := tar.NewWriter(gz)
+
+	manifest := archiveManifest{Project: name, ArchivedAt: archivedAt, Archive: filepath.Base(archivePath)}
+	walkErr := filepath.Walk(projectRoot, func(path string, info os.FileInfo, err error) error {
+		if err != nil {
+			return err
+		}
+		rel, err := filepath.Rel(projectRoot, path)
+		if err != nil || rel == "." {
+			return err
+		}
+		if !info.Mode().IsRegular() && !info.IsDir() {
+			return nil
+		}
+		hdr, err := tar.FileInfoHeader(info, "")
+		if err != nil {
+			return err
+		}
+		hdr.Name = filepath.ToSlash(filepath.Join(name, rel))
+		if err := tw.WriteHeader(hdr); err != nil {
+			return err
+		}
+		if info.IsDir() {
+			return nil
+		}
+		f, err := os.Open(path)
+		if err != nil {
+			return err
+		}
+		defer f.Close()
+		if _, err := io.Copy(tw, f); err != nil {
+			return err
+		}
+		hash, err := integrity.HashFile(path)
+		if err != nil {
+			return err
+		}
+		manifest.Files = append(manifest.Files, archiveManifestEntry{
+			Path:   filepath.ToSlash(rel),
+			SHA256: hash,
+			Size:   info.Size(),
+		})
+		return nil
+	})
+
+	if err := tw.Close(); err != nil && walkErr == nil {
+		walkErr = err
+	}
+	if err := gz.Close(); err != nil && walkErr == nil {
+		walkErr = err
+	}
+	if err := out.Close(); err != nil && walkErr == nil {
+		walkErr = err
+	}
+	if walkErr != nil {
+		os.Remove(archivePath)
+		return "", fmt.Errorf("compress project: %w", walkErr)
+	}
+
+	manifest.SHA256, err = integrity.HashFile(archivePath)
+	if err != nil {
+		return "", err
+	}
+	data, _ := json.MarshalIndent(manifest, "", "  ")
+	if err := os.WriteFile(filepath.Join(destDir, base+".manifest.json"), append(data, '\n'), 0o644); err != nil {
+		return "", err
+	}
+	return archivePath, nil
+}
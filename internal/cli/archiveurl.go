@@ -0,0 +1,337 @@
+package cli
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/base64"
+	"encoding/hex"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"html"
+	"io"
+	"mime"
+	"net/http"
+	"net/url"
+	"os"
+	"path/filepath"
+	"regexp"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+// defaultArchiveCategory receives snapshots unless told otherwise. It is
+// immutable in a new project.
+const defaultArchiveCategory = "sources"
+
+// defaultWaybackURL is the Wayback Machine, used unless the wayback-url
+// setting names another instance.
+const defaultWaybackURL = "https://web.archive.org"
+
+// Limits on what a snapshot pulls in: the page itself, and the images,
+// scripts and stylesheets inlined into it.
+const (
+	maxSnapshotSize = 256 << 20
+	maxAssetSize    = 20 << 20
+	maxAssets       = 200
+)
+
+var (
+	assetTag  = regexp.MustCompile(`(?is)<(?:img|script|link)\b[^>]*>`)
+	assetAttr = regexp.MustCompile(`(?is)\b(src|href)\s*=\s*("[^"]*"|'[^']*')`)
+	styleRel  = regexp.MustCompile(`(?is)\brel\s*=\s*["']?[^"'>]*\bstylesheet\b`)
+)
+
+// RunArchiveURL snapshots web pages into an immutable category as single
+// files, with images, scripts and stylesheets inlined. Arguments are URLs,
+// or references to tracked files whose provenance records the URL they
+// were fetched from. With --wayback, or the archive-wayback setting, each
+// page is also submitted to the Wayback Machine.
+func RunArchiveURL(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("archive-url", flag.ExitOnError)
+	category := fs.String("category", defaultArchiveCategory, "immutable category to store snapshots in")
+	wayback := fs.Bool("wayback", false, "also submit each page to the Wayback Machine")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(positional) == 0 {
+		return fmt.Errorf("usage: mkrk archive-url (<url> | <reference>)... [--category <name>] [--wayback]")
+	}
+	if v, _ := ctx.ProjectDb.GetConfig("archive-wayback"); v != nil && *v == "on" {
+		*wayback = true
+	}
+
+	type source struct{ url, hash string }
+	var sources []source
+	for _, arg := range positional {
+		if isWebURL(arg) {
+			sources = append(sources, source{url: arg})
+			continue
+		}
+		rels, err := resolve.RefRelPaths(ctx, arg)
+		if err != nil {
+			return err
+		}
+		for _, relPath := range rels {
+			hash, err := ctx.HashFile(filepath.Join(ctx.ProjectRoot, relPath))
+			if err != nil {
+				return fmt.Errorf("%s: %w", relPath, err)
+			}
+			file, _ := ctx.ProjectDb.GetFileByHash(hash)
+			if u := sourceURL(file); u != "" {
+				sources = append(sources, source{url: u, hash: hash})
+			} else {
+				fmt.Fprintf(os.Stderr, "  - %s: no source URL in its provenance\n", relPath)
+			}
+		}
+	}
+	if len(sources) == 0 {
+		return fmt.Errorf("no URLs to archive")
+	}
+
+	failed := 0
+	for _, s := range sources {
+		ref, err := archiveURL(ctx, s.url, *category, *wayback, s.hash)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", s.url, err)
+			failed++
+			continue
+		}
+		fmt.Fprintf(os.Stderr, "  + %s -> %s\n", s.url, ref)
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d page(s) could not be archived", failed)
+	}
+	return nil
+}
+
+// ruleArchiver snapshots the page an archive_url action's file was fetched
+// from, into the action's category or the default one.
+func ruleArchiver(ctx *context.Context) func(*models.TrackedFile, *string) (string, error) {
+	return func(file *models.TrackedFile, category *string) (string, error) {
+		u := sourceURL(file)
+		if u == "" {
+			return "no source URL to archive", nil
+		}
+		cat := defaultArchiveCategory
+		if category != nil {
+			cat = *category
+		}
+		wayback := false
+		if v, _ := ctx.ProjectDb.GetConfig("archive-wayback"); v != nil && *v == "on" {
+			wayback = true
+		}
+		// The detail goes to the audit log, so it names the URL, which
+		// provenance records anyway, and not the snapshot's path.
+		if _, err := archiveURL(ctx, u, cat, wayback, file.SHA256); err != nil {
+			return "", err
+		}
+		return "archived " + u, nil
+	}
+}
+
+// archiveURL takes one snapshot of rawURL into category and returns its
+// reference. sourceHash, when set, is the tracked file the URL came from.
+func archiveURL(ctx *context.Context, rawURL, category string, wayback bool, sourceHash string) (string, error) {
+	u, err := url.Parse(rawURL)
+	if err != nil || !isWebURL(rawURL) {
+		return "", fmt.Errorf("not an http or https URL")
+	}
+	cat, err := ctx.ProjectDb.GetCategoryByName(category)
+	if err != nil {
+		return "", err
+	}
+	if cat == nil || cat.Pattern == nil {
+		return "", fmt.Errorf("category '%s' not found", category)
+	}
+	// Snapshots are filed by site, a port kept apart from the host name.
+	dir := filepath.ToSlash(filepath.Join(models.NameFromPattern(*cat.Pattern), strings.ReplaceAll(u.Host, ":", "_")))
+	if protection, _ := ctx.ProjectDb.ResolveProtection(dir + "/x"); protection != models.ProtectionImmutable {
+		return "", fmt.Errorf("snapshots must go in an immutable category; '%s' is %s", category, protection)
+	}
+
+	client, err := fetchClient(ctx, u.Host)
+	if err != nil {
+		return "", err
+	}
+	resp, err := client.Get(u.String())
+	if err != nil {
+		return "", err
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode/100 != 2 {
+		return "", fmt.Errorf("%s returned %s", u.Host, resp.Status)
+	}
+	page, err := io.ReadAll(io.LimitReader(resp.Body, maxSnapshotSize+1))
+	if err != nil {
+		return "", err
+	}
+	if len(page) > maxSnapshotSize {
+		return "", fmt.Errorf("page is larger than %s", formatSize(maxSnapshotSize))
+	}
+
+	received := sha256.Sum256(page)
+	prov := &fetchProvenance{
+		URL:       u.String(),
+		Status:    resp.Status,
+		Headers:   fetchHeaders(resp.Header),
+		SHA256:    hex.EncodeToString(received[:]),
+		FetchedAt: time.Now().UTC().Format(time.RFC3339),
+		Source:    sourceHash,
+	}
+	name := fetchName(u, resp.Header)
+	if isHTML(resp.Header, page) {
+		page, prov.Assets = inlineAssets(client, resp.Request.URL, page)
+		if ext := strings.ToLower(filepath.Ext(name)); ext != ".html" && ext != ".htm" {
+			name += ".html"
+		}
+	}
+	if wayback {
+		if prov.Wayback, err = waybackSave(ctx, u.String()); err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: not saved to the Wayback Machine: %v\n", u, err)
+		}
+	}
+
+	stamp := time.Now().UTC().Format("20060102-150405")
+	relPath := dir + "/" + stamp + "-" + name
+	for n := 2; fileExists(filepath.Join(ctx.ProjectRoot, filepath.FromSlash(relPath))); n++ {
+		relPath = fmt.Sprintf("%s/%s-%d-%s", dir, stamp, n, name)
+	}
+	saved, err := saveFetched(ctx, relPath, bytes.NewReader(page), prov)
+	if err != nil {
+		return "", err
+	}
+
+	user := whoami()
+	fields := map[string]string{"url": u.String(), "sha256": prov.SHA256}
+	if prov.Wayback != "" {
+		fields["wayback"] = prov.Wayback
+	}
+	detail, _ := json.Marshal(fields)
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("archive_url", saved.fileID, &user, &detailStr)
+	return saved.ref, nil
+}
+
+// inlineAssets replaces the images, scripts and stylesheets a page links
+// to with data: URIs, so the snapshot renders from one file. Assets that
+// cannot be fetched keep their original links. URLs inside stylesheets are
+// left as they are.
+func inlineAssets(client *http.Client, base *url.URL, page []byte) ([]byte, int) {
+	inlined := 0
+	cache := make(map[string]string)
+	out := assetTag.ReplaceAllFunc(page, func(tag []byte) []byte {
+		if bytes.HasPrefix(bytes.ToLower(tag), []byte("<link")) && !styleRel.Match(tag) {
+			return tag
+		}
+		return assetAttr.ReplaceAllFunc(tag, func(attr []byte) []byte {
+			m := assetAttr.FindSubmatch(attr)
+			quoted := string(m[2])
+			raw := html.UnescapeString(quoted[1 : len(quoted)-1])
+			ref, err := base.Parse(raw)
+			if err != nil || (ref.Scheme != "http" && ref.Scheme != "https") {
+				return attr
+			}
+			data, ok := cache[ref.String()]
+			if !ok {
+				if len(cache) >= maxAssets {
+					return attr
+				}
+				data = fetchAsset(client, ref.String())
+				cache[ref.String()] = data
+			}
+			if data == "" {
+				return attr
+			}
+			inlined++
+			return []byte(string(m[1]) + `="` + data + `"`)
+		})
+	})
+	return out, inlined
+}
+
+// fetchAsset returns the asset at rawURL as a data: URI, or "" if it
+// cannot be fetched or is too large.
+func fetchAsset(client *http.Client, rawURL string) string {
+	resp, err := client.Get(rawURL)
+	if err != nil {
+		return ""
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode/100 != 2 {
+		return ""
+	}
+	data, err := io.ReadAll(io.LimitReader(resp.Body, maxAssetSize+1))
+	if err != nil || len(data) > maxAssetSize {
+		return ""
+	}
+	mediaType, _, err := mime.ParseMediaType(resp.Header.Get("Content-Type"))
+	if err != nil {
+		mediaType, _, _ = mime.ParseMediaType(http.DetectContentType(data))
+	}
+	return "data:" + mediaType + ";base64," + base64.StdEncoding.EncodeToString(data)
+}
+
+// waybackSave asks the Wayback Machine, or the instance the wayback-url
+// setting names, to capture rawURL, and returns the address of its copy.
+func waybackSave(ctx *context.Context, rawURL string) (string, error) {
+	base := defaultWaybackURL
+	if v, _ := ctx.ProjectDb.GetConfig("wayback-url"); v != nil && *v != "" {
+		base = strings.TrimSuffix(*v, "/")
+	}
+	privacy := privacySettings(ctx)
+	client, err := privacy.httpClient()
+	if err != nil {
+		return "", err
+	}
+	// Captures can take a while to complete.
+	client.Timeout = 2 * time.Minute
+	resp, err := client.Get(base + "/save/" + rawURL)
+	if err != nil {
+		return "", err
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode/100 != 2 {
+		return "", fmt.Errorf("returned %s", resp.Status)
+	}
+	if loc := resp.Header.Get("Content-Location"); loc != "" {
+		if ref, err := resp.Request.URL.Parse(loc); err == nil {
+			return ref.String(), nil
+		}
+	}
+	return resp.Request.URL.String(), nil
+}
+
+// sourceURL returns the URL a tracked file's provenance records it was
+// fetched from, or "" if there is none.
+func sourceURL(file *models.TrackedFile) string {
+	if file == nil || file.Provenance == nil {
+		return ""
+	}
+	var prov struct {
+		URL string `json:"url"`
+	}
+	if json.Unmarshal([]byte(*file.Provenance), &prov) != nil || !isWebURL(prov.URL) {
+		return ""
+	}
+	return prov.URL
+}
+
+func isWebURL(s string) bool {
+	u, err := url.Parse(s)
+	return err == nil && (u.Scheme == "http" || u.Scheme == "https") && u.Host != ""
+}
+
+func isHTML(header http.Header, body []byte) bool {
+	mediaType, _, err := mime.ParseMediaType(header.Get("Content-Type"))
+	if err != nil {
+		mediaType, _, _ = mime.ParseMediaType(http.DetectContentType(body))
+	}
+	return mediaType == "text/html" || mediaType == "application/xhtml+xml"
+}
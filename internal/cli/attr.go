@@ -0,0 +1,211 @@
+package cli
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"sort"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/materialize"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+const attrUsage = `usage: mkrk attr set <reference> <key> <value>
+       mkrk attr get <reference> <key>
+       mkrk attr list <reference>
+       mkrk attr remove <reference> <key>`
+
+// attrTarget is a tracked file named by an attr command's reference.
+type attrTarget struct {
+	relPath string
+	hash    string
+	file    *models.TrackedFile
+}
+
+// RunAttr sets, reads or removes key/value attributes on tracked files.
+// Attributes hold facts that do not fit a tag, such as a FOIA request
+// number, and can be selected on with [attr.<key>...] predicates.
+func RunAttr(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(args) == 0 {
+		return fmt.Errorf("%s", attrUsage)
+	}
+
+	switch args[0] {
+	case "set":
+		return attrSet(ctx, args[1:])
+	case "get":
+		return attrGet(ctx, args[1:])
+	case "list", "ls":
+		return attrList(ctx, args[1:])
+	case "remove", "rm":
+		return attrRemove(ctx, args[1:])
+	default:
+		return fmt.Errorf("unknown attr subcommand: %s\n%s", args[0], attrUsage)
+	}
+}
+
+func attrSet(ctx *context.Context, args []string) error {
+	targets, rest, err := attrTargets(ctx, args, 2)
+	if err != nil {
+		return err
+	}
+	key, value := rest[0], rest[1]
+	if err := reference.ValidateAttrKey(key); err != nil {
+		return err
+	}
+	return attrUpdate(ctx, targets, func(t attrTarget, ref string) error {
+		if err := ctx.ProjectDb.SetFileAttr(*t.file.ID, key, value); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "  + %s %s=%s\n", ref, key, value)
+		return nil
+	})
+}
+
+func attrRemove(ctx *context.Context, args []string) error {
+	targets, rest, err := attrTargets(ctx, args, 1)
+	if err != nil {
+		return err
+	}
+	key := rest[0]
+	return attrUpdate(ctx, targets, func(t attrTarget, ref string) error {
+		n, err := ctx.ProjectDb.RemoveFileAttr(*t.file.ID, key)
+		if err != nil {
+			return err
+		}
+		if n > 0 {
+			fmt.Fprintf(os.Stderr, "  - %s %s\n", ref, key)
+		}
+		return nil
+	})
+}
+
+func attrGet(ctx *context.Context, args []string) error {
+	targets, rest, err := attrTargets(ctx, args, 1)
+	if err != nil {
+		return err
+	}
+	key := rest[0]
+	found := 0
+	for _, t := range targets {
+		attrs, err := ctx.ProjectDb.GetFileAttrs(*t.file.ID)
+		if err != nil {
+			return err
+		}
+		value, ok := attrs[key]
+		if !ok {
+			continue
+		}
+		found++
+		if len(targets) == 1 {
+			fmt.Println(value)
+		} else {
+			fmt.Printf("%s\t%s\n", attrRef(ctx, t.relPath), value)
+		}
+	}
+	if found == 0 {
+		return fmt.Errorf("attribute '%s' is not set", key)
+	}
+	return nil
+}
+
+func attrList(ctx *context.Context, args []string) error {
+	targets, _, err := attrTargets(ctx, args, 0)
+	if err != nil {
+		return err
+	}
+	for _, t := range targets {
+		attrs, err := ctx.ProjectDb.GetFileAttrs(*t.file.ID)
+		if err != nil {
+			return err
+		}
+		if len(attrs) == 0 {
+			continue
+		}
+		keys := make([]string, 0, len(attrs))
+		for k := range attrs {
+			keys = append(keys, k)
+		}
+		sort.Strings(keys)
+		fmt.Println(attrRef(ctx, t.relPath))
+		for _, k := range keys {
+			fmt.Printf("    %s: %s\n", k, attrs[k])
+		}
+	}
+	return nil
+}
+
+// attrUpdate applies change to every target in one transaction, then
+// re-materializes subscriptions so ones selecting on attributes pick up
+// the new values.
+func attrUpdate(ctx *context.Context, targets []attrTarget, change func(t attrTarget, ref string) error) error {
+	categories, _ := ctx.ProjectDb.ListCategories()
+	projectDb := ctx.ProjectDb
+	return projectDb.Batch(func(batch *db.ProjectDb) error {
+		ctx.ProjectDb = batch
+		defer func() { ctx.ProjectDb = projectDb }()
+		for _, t := range targets {
+			if err := change(t, attrRef(ctx, t.relPath)); err != nil {
+				return fmt.Errorf("%s: %w", t.relPath, err)
+			}
+			tags, _ := ctx.ProjectDb.GetTags(*t.file.ID)
+			materialize.MaterializeForFile(ctx.ProjectDb, t.relPath, t.hash, matchingCategories(t.relPath, categories), tags)
+		}
+		return nil
+	})
+}
+
+// attrTargets picks the tracked files an attr subcommand acts on and its
+// remaining want arguments. With a subject the files come from it;
+// otherwise the first argument is a reference.
+func attrTargets(ctx *context.Context, args []string, want int) ([]attrTarget, []string, error) {
+	var rels []string
+	var err error
+	if resolve.HasNarrowSubject(ctx) {
+		rels, err = resolve.SubjectRelPaths(ctx)
+	} else {
+		if len(args) == 0 {
+			return nil, nil, fmt.Errorf("%s", attrUsage)
+		}
+		rels, err = resolve.RefRelPaths(ctx, args[0])
+		args = args[1:]
+	}
+	if err != nil {
+		return nil, nil, err
+	}
+	if len(args) != want {
+		return nil, nil, fmt.Errorf("%s", attrUsage)
+	}
+	if len(rels) == 0 {
+		return nil, nil, fmt.Errorf("no files matched")
+	}
+
+	targets := make([]attrTarget, 0, len(rels))
+	for _, relPath := range rels {
+		hash, err := ctx.HashFile(filepath.Join(ctx.ProjectRoot, relPath))
+		if err != nil {
+			return nil, nil, fmt.Errorf("%s: %w", relPath, err)
+		}
+		file, err := ctx.ProjectDb.GetFileByHash(hash)
+		if err != nil || file == nil || file.ID == nil {
+			return nil, nil, fmt.Errorf("%s: not tracked (run sync first)", relPath)
+		}
+		targets = append(targets, attrTarget{relPath: relPath, hash: hash, file: file})
+	}
+	return targets, args, nil
+}
+
+func attrRef(ctx *context.Context, relPath string) string {
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	return reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+}
@@ -0,0 +1,76 @@
+package cli
+
+import (
+	"fmt"
+	"os"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
+)
+
+const auditUsage = `usage: mkrk audit verify`
+
+func RunAudit(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject && ctx.Workspace == nil {
+		return fmt.Errorf("not in a project or workspace")
+	}
+	if len(args) == 0 {
+		return fmt.Errorf("%s", auditUsage)
+	}
+
+	switch args[0] {
+	case "verify":
+		if len(args) != 1 {
+			return fmt.Errorf("%s", auditUsage)
+		}
+		return auditVerify(ctx)
+	default:
+		return fmt.Errorf("unknown audit subcommand: %s\n%s", args[0], auditUsage)
+	}
+}
+
+// auditVerify checks the hash chains of the project's audit log and of the
+// enclosing workspace's, and prints each head, which can be recorded
+// elsewhere to later detect truncation of the newest entries. When both
+// are checked, each head is followed by the log it belongs to.
+func auditVerify(ctx *context.Context) error {
+	type auditLog struct {
+		name   string
+		title  string
+		verify func() (int64, string, []db.AuditBreak, error)
+	}
+	var logs []auditLog
+	if ctx.Kind == context.ContextProject {
+		logs = append(logs, auditLog{"project", "Audit log", ctx.ProjectDb.VerifyAuditChain})
+	}
+	if ctx.Workspace != nil {
+		logs = append(logs, auditLog{"workspace", "Workspace audit log", ctx.Workspace.Db.VerifyAuditChain})
+	}
+
+	broken := 0
+	for _, l := range logs {
+		count, head, breaks, err := l.verify()
+		if err != nil {
+			return fmt.Errorf("%s audit log: %w", l.name, err)
+		}
+		for _, b := range breaks {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s entry %d: %s\n", l.name, b.ID, b.Reason)
+		}
+		switch {
+		case head == "":
+		case len(logs) > 1:
+			fmt.Printf("%s  %s\n", head, l.name)
+		default:
+			fmt.Println(head)
+		}
+		if len(breaks) > 0 {
+			broken += len(breaks)
+			continue
+		}
+		fmt.Fprintf(os.Stderr, "%s intact: %d entries\n", l.title, count)
+	}
+	if broken > 0 {
+		return fmt.Errorf("audit log chain is broken in %d place(s)", broken)
+	}
+	return nil
+}
@@ -0,0 +1,355 @@
+package cli
+
+import (
+	"bytes"
+	"fmt"
+	"mime"
+	"net/http"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/builtins"
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/generator"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/rules"
+)
+
+// defaultDerivativeCategory receives built-in tool output unless the
+// project sets <tool>-category. It is protected, so a derivative cannot be
+// edited without the change being recorded.
+const defaultDerivativeCategory = "analysis"
+
+// runBuiltin runs a built-in tool over the subject files of each context.
+func runBuiltin(ctxs []*context.Context, tool *builtins.Tool) error {
+	failed := 0
+	for _, ctx := range ctxs {
+		paths, err := resolve.SubjectFiles(ctx)
+		if err != nil {
+			return err
+		}
+		for _, absPath := range paths {
+			ref, _, err := deriveWithBuiltin(ctx, tool, absPath)
+			if err != nil {
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %v\n", err)
+				failed++
+				continue
+			}
+			fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", ref)
+		}
+	}
+	if failed > 0 {
+		return fmt.Errorf("%s failed on %d file(s)", tool.Name, failed)
+	}
+	return nil
+}
+
+// deriveWithBuiltin runs tool on a tracked file and tracks its output as a
+// derivative in the tool's category, mirroring the input's path within
+// its own category, then tags the input if the tool names a tag. It
+// returns the derivative's reference and hash.
+func deriveWithBuiltin(ctx *context.Context, tool *builtins.Tool, absPath string) (string, string, error) {
+	if ctx.Kind != context.ContextProject {
+		return "", "", fmt.Errorf("not in a project")
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	src, err := deriveTarget(ctx, allFiles, absPath, projectName)
+	if err != nil {
+		return "", "", err
+	}
+	if src.file == nil || src.file.ID == nil {
+		return "", "", fmt.Errorf("%s: not tracked (run sync first)", src.ref)
+	}
+	if src.status == verifyModified {
+		return "", "", fmt.Errorf("%s: modified since it was tracked (run verify)", src.ref)
+	}
+	if enc, err := crypt.IsEncrypted(absPath); err != nil || enc {
+		return "", "", fmt.Errorf("%s: %s cannot read encrypted files", src.ref, tool.Name)
+	}
+
+	categories, err := ctx.ProjectDb.ListCategories()
+	if err != nil {
+		return "", "", err
+	}
+	outRel, err := derivativePath(ctx, tool, src.relPath, categories)
+	if err != nil {
+		return "", "", err
+	}
+	outRef := reference.FormatRef(outRel, projectName, ctx.ProjectDb)
+	outAbs := filepath.Join(ctx.ProjectRoot, outRel)
+	if fileExists(outAbs) {
+		return "", "", fmt.Errorf("%s: %s already exists", src.ref, outRef)
+	}
+
+	env, release, err := buildEnv(generator.Builtins()[0], ctx, privacySettings(ctx))
+	if err != nil {
+		return "", "", err
+	}
+	defer release()
+	if err := writeDerivative(outAbs, func(f *os.File) error { return tool.Run(absPath, f, env) }); err != nil {
+		return "", "", fmt.Errorf("%s: %s: %w", src.ref, tool.Name, err)
+	}
+	hash, err := trackDerivative(ctx, outRel, tool.Name, []string{src.file.SHA256}, categories)
+	if err != nil {
+		return "", "", err
+	}
+	if tool.Tag != "" {
+		if err := ctx.ProjectDb.InsertTag(*src.file.ID, tool.Tag, src.file.SHA256, src.file.Fingerprint); err != nil {
+			return "", "", err
+		}
+	}
+	return outRef, hash, nil
+}
+
+// derivativePath places the derivative of relPath in the tool's category,
+// at the input's path within its own category plus the tool's extension.
+func derivativePath(ctx *context.Context, tool *builtins.Tool, relPath string, categories []models.Scope) (string, error) {
+	setting := tool.Name + "-category"
+	catName := defaultDerivativeCategory
+	if v, _ := ctx.ProjectDb.GetConfig(setting); v != nil {
+		catName = *v
+	}
+	var dir string
+	for _, c := range categories {
+		if c.Name == catName && c.Pattern != nil {
+			dir = models.NameFromPattern(*c.Pattern)
+		}
+	}
+	if dir == "" {
+		return "", fmt.Errorf("no category '%s' for %s output (choose one with mkrk config %s <category>)", catName, tool.Name, setting)
+	}
+
+	name := filepath.Base(relPath)
+	if cats := matchingCategories(relPath, categories); len(cats) > 0 && cats[0].Pattern != nil {
+		if inner, err := filepath.Rel(models.NameFromPattern(*cats[0].Pattern), relPath); err == nil && filepath.IsLocal(inner) {
+			name = inner
+		}
+	}
+	return filepath.Join(dir, name+tool.Ext), nil
+}
+
+// writeDerivative writes a tool's output through a temporary file beside
+// path, so a failed run leaves nothing behind. Empty output is a failure.
+func writeDerivative(path string, write func(*os.File) error) error {
+	if err := os.MkdirAll(filepath.Dir(path), 0o755); err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(path), ".mkrk-derive-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+
+	err = write(tmp)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	if info, err := os.Stat(tmp.Name()); err != nil {
+		return err
+	} else if info.Size() == 0 {
+		return fmt.Errorf("no output")
+	}
+	if err := os.Chmod(tmp.Name(), 0o644); err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), path)
+}
+
+// ruleToolRunner runs the tools named by run_tool rule actions: @name for
+// built-ins, otherwise a tool from the project's tools category, given the
+// event's file as its argument. What it returns is recorded with the
+// firing, so neither the result nor the error names a path; the full error
+// is printed instead.
+func ruleToolRunner(ctx *context.Context) func(string, *rules.Event) (string, error) {
+	return func(name string, ev *rules.Event) (string, error) {
+		fail := func(err error) (string, error) {
+			fmt.Fprintf(os.Stderr, "    ! %s: %v\n", name, err)
+			return "", fmt.Errorf("%s failed", name)
+		}
+		absPath := filepath.Join(ctx.ProjectRoot, ev.RelPath)
+		gens, err := generator.Collect(ctx)
+		if err != nil {
+			return fail(err)
+		}
+		gen, toolPath, err := resolveTool(name, filterByVerb(gens, "tool"), ctx)
+		if err != nil {
+			return fail(err)
+		}
+		if gen.IsBuiltin {
+			_, hash, err := deriveWithBuiltin(ctx, builtins.Lookup(strings.TrimPrefix(toolPath, "@")), absPath)
+			if err != nil {
+				return fail(err)
+			}
+			return "wrote derivative " + hash, nil
+		}
+		if err := execTool(gen, toolPath, []string{absPath}, []string{absPath}, ctx); err != nil {
+			return fail(err)
+		}
+		return "ran " + name, nil
+	}
+}
+
+// ruleShellRunner runs a shell action's command with sh -c, in the project
+// root, with the environment tools get plus MKRK_FILE.
+func ruleShellRunner(ctx *context.Context) func(string, *rules.Event) error {
+	return func(command string, ev *rules.Event) error {
+		privacy := privacySettings(ctx)
+		announcePrivacy(privacy)
+		cmd := exec.Command("sh", "-c", command)
+		cmd.Dir = ctx.ProjectRoot
+		cmd.Stdout = os.Stdout
+		cmd.Stderr = os.Stderr
+		env, release, err := buildEnv(generator.Generator{Verb: "rule"}, ctx, privacy)
+		if err != nil {
+			return err
+		}
+		defer release()
+		cmd.Env = appendKV(env, "MKRK_FILE", filepath.Join(ctx.ProjectRoot, ev.RelPath))
+		return cmd.Run()
+	}
+}
+
+// ruleWebhookPoster posts a webhook action's JSON payload through the
+// privacy proxy, as mkrk's own requests go, and fails on any status other
+// than 2xx.
+func ruleWebhookPoster(ctx *context.Context) func(string, []byte) (string, error) {
+	return func(url string, payload []byte) (string, error) {
+		privacy := privacySettings(ctx)
+		client, err := privacy.httpClient()
+		if err != nil {
+			return "", err
+		}
+		if via := privacy.via(url); via != "" {
+			fmt.Fprintf(os.Stderr, "privacy: webhook to %s goes through %s\n", url, via)
+		} else {
+			fmt.Fprintf(os.Stderr, "privacy: DISABLED — webhook to %s goes direct\n", url)
+		}
+		resp, err := client.Post(url, "application/json", bytes.NewReader(payload))
+		if err != nil {
+			return "", err
+		}
+		defer resp.Body.Close()
+		if resp.StatusCode/100 != 2 {
+			return "", fmt.Errorf("webhook returned %s", resp.Status)
+		}
+		return "posted to " + url, nil
+	}
+}
+
+// ingested is a file newly tracked by a sync, for firing ingest rules.
+type ingested struct {
+	relPath string
+	ref     string
+	hash    string
+}
+
+// fireIngestRules fires ingest rules for each newly tracked file, after
+// the sync has recorded them all. Under dry it only previews the rules.
+// Files written by the tools these rules run do not fire ingest rules in
+// turn.
+func fireIngestRules(ctx *context.Context, files []ingested, categories []models.Scope, dry bool) {
+	fire := rules.Fire
+	if dry {
+		fire = rules.Preview
+	}
+	project := ""
+	if ctx.ProjectName != nil {
+		project = *ctx.ProjectName
+	}
+	for _, f := range files {
+		ev := &rules.Event{
+			Trigger: models.TriggerIngest,
+			SHA256:  f.hash,
+			RelPath: f.relPath,
+			Root:    ctx.ProjectRoot,
+			Project: project,
+		}
+		if ctx.Workspace != nil {
+			ev.Workspace = ctx.Workspace.Db
+		}
+		for _, c := range matchingCategories(f.relPath, categories) {
+			ev.Categories = append(ev.Categories, c.Name)
+		}
+		if file, _ := ctx.ProjectDb.GetFileByHash(f.hash); file != nil {
+			ev.MimeType = file.MimeType
+		} else {
+			ev.MimeType = detectMime(filepath.Join(ctx.ProjectRoot, f.relPath))
+		}
+		if !dry {
+			ev.RunTool = ruleToolRunner(ctx)
+			ev.RunShell = ruleShellRunner(ctx)
+			ev.Post = ruleWebhookPoster(ctx)
+			ev.Archive = ruleArchiver(ctx)
+		}
+
+		firings, err := fire(ctx.ProjectDb, ev)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! rules: %v\n", err)
+			return
+		}
+		if len(firings) > 0 {
+			fmt.Fprintf(os.Stderr, "  %s\n", f.ref)
+			printFirings(firings)
+		}
+	}
+}
+
+// ambiguousMime are sniffed types that say less than a file's extension:
+// unrecognized content, such as encrypted files, and containers that hold
+// audio as often as anything else.
+var ambiguousMime = map[string]bool{
+	"application/octet-stream": true,
+	"text/plain":               true,
+	"application/ogg":          true,
+	"video/mp4":                true,
+}
+
+// audioExtensions fills in recording formats that the system's MIME table
+// may lack.
+var audioExtensions = map[string]string{
+	".mp3":  "audio/mpeg",
+	".m4a":  "audio/mp4",
+	".wav":  "audio/wav",
+	".flac": "audio/flac",
+	".ogg":  "audio/ogg",
+	".oga":  "audio/ogg",
+	".opus": "audio/opus",
+}
+
+// detectMime sniffs a file's media type from its first bytes, deferring
+// to its extension when the sniffed type is ambiguous.
+func detectMime(absPath string) *string {
+	f, err := os.Open(absPath)
+	if err != nil {
+		return nil
+	}
+	defer f.Close()
+	head := make([]byte, 512)
+	n, _ := f.Read(head)
+
+	t, _, _ := strings.Cut(http.DetectContentType(head[:n]), ";")
+	if ambiguousMime[t] {
+		ext := strings.ToLower(filepath.Ext(absPath))
+		byExt := audioExtensions[ext]
+		if byExt == "" {
+			byExt, _, _ = strings.Cut(mime.TypeByExtension(ext), ";")
+		}
+		if byExt != "" {
+			t = byExt
+		}
+	}
+	t = strings.TrimSpace(t)
+	return &t
+}
@@ -0,0 +1,70 @@
+package cli
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// RunClone creates a new workspace project with the same categories,
+// policies, tool configs, pipelines and rules as an existing one. Files
+// are not copied.
+func RunClone(ctx *context.Context, args []string) error {
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	if len(args) != 2 {
+		return fmt.Errorf("usage: mkrk clone <project> <new-name>")
+	}
+	srcName, newName := args[0], args[1]
+
+	src, err := openWorkspaceProject(ctx, srcName)
+	if err != nil {
+		return err
+	}
+	defer src.Close()
+
+	if existing, _ := ctx.Workspace.Db.GetProjectByName(newName); existing != nil {
+		return fmt.Errorf("project %q already exists in workspace", newName)
+	}
+	hasher, err := src.Hasher()
+	if err != nil {
+		return err
+	}
+	if err := initProject(ctx.Workspace.Root, newName, true, hasher, false, false); err != nil {
+		return err
+	}
+
+	dst, err := openWorkspaceProject(ctx, newName)
+	if err != nil {
+		return err
+	}
+	defer dst.Close()
+
+	if err := src.ProjectDb.CopyStructure(dst.ProjectDb); err != nil {
+		return fmt.Errorf("clone %s: %w", srcName, err)
+	}
+
+	categories, err := dst.ProjectDb.ListCategories()
+	if err != nil {
+		return err
+	}
+	for _, c := range categories {
+		if c.Pattern == nil {
+			continue
+		}
+		os.MkdirAll(filepath.Join(dst.ProjectRoot, models.NameFromPattern(*c.Pattern)), 0o755)
+	}
+
+	user := whoami()
+	detail, _ := json.Marshal(map[string]string{"source": srcName})
+	detailStr := string(detail)
+	ctx.Workspace.Db.InsertAudit("clone", &newName, &user, &detailStr)
+
+	fmt.Fprintf(os.Stderr, "Cloned structure of '%s' into '%s' (%d categories)\n", srcName, newName, len(categories))
+	return nil
+}
@@ -0,0 +1,159 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"regexp"
+	"slices"
+	"sort"
+	"strconv"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/rules"
+	"go.foia.dev/muckrake/internal/storage"
+)
+
+// projectSettings lists the keys `mkrk config` accepts and their allowed
+// values; a nil list accepts any value.
+var projectSettings = map[string][]string{
+	"symlinks": {"skip", "follow", "link"},
+	// Keeps the original of each ingested file in .mkrk-store.
+	"store": {"off", "on"},
+
+	// Categories that receive the output of the built-in tools.
+	"ocr-category":        nil,
+	"transcribe-category": nil,
+
+	// RFC 3161 time-stamping authority used by `mkrk sign --timestamp`.
+	"tsa-url": nil,
+	// OpenTimestamps calendar used by `mkrk anchor`.
+	"ots-calendar": nil,
+
+	// Whether `mkrk archive-url` and archive_url rules also submit pages
+	// to the Wayback Machine, and which instance.
+	"archive-wayback": {"off", "on"},
+	"wayback-url":     nil,
+
+	// Remote storage used by `mkrk storage`. The keys may name secrets as
+	// ${secret:NAME}; without them the AWS environment variables are used.
+	"storage-url":        nil,
+	"storage-endpoint":   nil,
+	"storage-region":     nil,
+	"storage-access-key": nil,
+	"storage-secret-key": nil,
+	"storage-lock":       storage.LockModes,
+	"storage-retention":  nil,
+
+	// Lets rules with a shell action run their commands.
+	rules.AllowShellSetting: {"true", "false"},
+}
+
+// toolEnvPrefix marks settings that add a variable to the environment of
+// tools, foreach commands and shell actions: env.NAME sets NAME. Values may
+// name secrets as ${secret:NAME}, filled in only when a tool runs.
+const toolEnvPrefix = "env."
+
+var envVarName = regexp.MustCompile(`^[A-Za-z_][A-Za-z0-9_]*$`)
+
+// toolLimitSettings bound tool runs, for every tool or, as key.<tool>, for
+// one; each checks its value.
+var toolLimitSettings = map[string]func(string) error{
+	// How long a run may take before its process group is killed.
+	"tool-timeout": func(v string) error {
+		if d, err := time.ParseDuration(v); err != nil || d <= 0 {
+			return fmt.Errorf("expected a duration such as 90s or 10m")
+		}
+		return nil
+	},
+	// How many times a failed or timed-out run is retried.
+	"tool-retries": func(v string) error {
+		if n, err := strconv.Atoi(v); err != nil || n < 0 {
+			return fmt.Errorf("expected a count of zero or more")
+		}
+		return nil
+	},
+}
+
+const configUsage = `usage: mkrk config                 list project settings
+       mkrk config <key>           show one setting
+       mkrk config <key> <value>   change a setting
+       mkrk config --unset <key>   restore the default
+       mkrk config env.<NAME> <value>   set a tool environment variable
+       mkrk config tool-timeout[.<tool>] <duration>
+       mkrk config tool-retries[.<tool>] <count>`
+
+func RunConfig(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("config", flag.ExitOnError)
+	unset := fs.Bool("unset", false, "remove the setting, restoring its default")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	if len(positional) == 0 {
+		if *unset {
+			return fmt.Errorf("%s", configUsage)
+		}
+		config, err := ctx.ProjectDb.ListConfig()
+		if err != nil {
+			return err
+		}
+		keys := make([]string, 0, len(config))
+		for k := range config {
+			keys = append(keys, k)
+		}
+		sort.Strings(keys)
+		for _, k := range keys {
+			fmt.Printf("%s = %s\n", k, config[k])
+		}
+		return nil
+	}
+	if len(positional) > 2 {
+		return fmt.Errorf("%s", configUsage)
+	}
+
+	key := positional[0]
+	allowed, known := projectSettings[key]
+	if name, ok := strings.CutPrefix(key, toolEnvPrefix); ok {
+		if !envVarName.MatchString(name) {
+			return fmt.Errorf("invalid environment variable name '%s'", name)
+		}
+		known = true
+	}
+	base, _, _ := strings.Cut(key, ".")
+	check, limit := toolLimitSettings[base]
+	if !known && !limit {
+		return fmt.Errorf("unknown setting '%s'", key)
+	}
+
+	switch {
+	case *unset:
+		if len(positional) != 1 {
+			return fmt.Errorf("%s", configUsage)
+		}
+		return ctx.ProjectDb.UnsetConfig(key)
+	case len(positional) == 1:
+		v, err := ctx.ProjectDb.GetConfig(key)
+		if err != nil {
+			return err
+		}
+		if v != nil {
+			fmt.Println(*v)
+		}
+		return nil
+	}
+
+	value := positional[1]
+	if allowed != nil && !slices.Contains(allowed, value) {
+		return fmt.Errorf("invalid value for %s: %q (expected %s)", key, value, strings.Join(allowed, ", "))
+	}
+	if limit {
+		if err := check(value); err != nil {
+			return fmt.Errorf("invalid value for %s: %q (%v)", key, value, err)
+		}
+	}
+	return ctx.ProjectDb.SetConfig(key, value)
+}
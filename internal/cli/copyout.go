@@ -0,0 +1,151 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+// workingCopyTag marks content that has been copied out for editing.
+const workingCopyTag = "working-copy"
+
+// defaultCopyOutDir is where working copies land, relative to the project
+// root. It is not a category, so sync never picks the copies up.
+const defaultCopyOutDir = "workspace"
+
+func RunCopyOut(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("copy-out", flag.ExitOnError)
+	to := fs.String("to", "", "destination directory (default: <project>/workspace)")
+	tag := fs.Bool("tag", false, "tag the copies '"+workingCopyTag+"' once sync tracks them (needs --to inside a category)")
+	force := fs.Bool("force", false, "overwrite existing files at the destination")
+	fs.BoolVar(force, "f", false, "shorthand for --force")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	paths, err := copyOutTargets(ctx, positional)
+	if err != nil {
+		return err
+	}
+	if len(paths) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+
+	destRoot := filepath.Join(ctx.ProjectRoot, defaultCopyOutDir)
+	if *to != "" {
+		if destRoot, err = filepath.Abs(*to); err != nil {
+			return err
+		}
+	}
+
+	categories, _ := ctx.ProjectDb.ListCategories()
+	user := whoami()
+	now := time.Now().UTC().Format(time.RFC3339)
+	copied := 0
+	for _, relPath := range paths {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		hash, err := ctx.HashFile(absPath)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			continue
+		}
+		file, _ := ctx.ProjectDb.GetFileByHash(hash)
+		if file == nil || file.ID == nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: not tracked (run sync first)\n", relPath)
+			continue
+		}
+
+		dst := filepath.Join(destRoot, relPath)
+		// Only a copy inside a category is ever tracked, so only there can
+		// it be tagged or linked back to its source.
+		dstRel, err := filepath.Rel(ctx.ProjectRoot, dst)
+		tracked := err == nil && !strings.HasPrefix(dstRel, "..") &&
+			len(matchingCategories(filepath.ToSlash(dstRel), categories)) > 0
+		if *tag && !tracked {
+			return fmt.Errorf("--tag needs --to a directory inside a category; %s would never be tracked", dst)
+		}
+		if !*force && fileExists(dst) {
+			fmt.Fprintf(os.Stderr, "  ! %s: %s already exists (use --force)\n", relPath, dst)
+			continue
+		}
+		if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+			return err
+		}
+		if err := copyFile(absPath, dst); err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			continue
+		}
+		os.Chmod(dst, 0o644)
+
+		// The copy is byte-identical to the original, so it has no record
+		// of its own yet. Sync links it to the source, and tags it, once it
+		// is edited and tracked.
+		if tracked {
+			if err := ctx.ProjectDb.PutWorkingCopy(filepath.ToSlash(dstRel), *file.ID, *tag, now); err != nil {
+				return err
+			}
+		}
+		detail, _ := json.Marshal(map[string]string{"source_sha256": hash})
+		detailStr := string(detail)
+		ctx.ProjectDb.InsertAudit("copy_out", file.ID, &user, &detailStr)
+
+		fmt.Fprintf(os.Stderr, "  + %s -> %s\n", relPath, dst)
+		copied++
+	}
+
+	fmt.Fprintf(os.Stderr, "Copied %d file(s) to %s\n", copied, destRoot)
+	return nil
+}
+
+// isWorkingCopy reports whether relPath holds a copy made by copy-out that
+// sync has not yet tracked.
+func isWorkingCopy(ctx *context.Context, relPath string) bool {
+	id, _, _ := ctx.ProjectDb.WorkingCopySource(filepath.ToSlash(relPath))
+	return id != nil
+}
+
+// linkWorkingCopy records a newly tracked copy-out working copy as derived
+// from its source, tags it if copy-out was asked to, and forgets the path.
+func linkWorkingCopy(ctx *context.Context, relPath, ref string, fileID int64, hash string, fp *integrity.Fingerprint) {
+	key := filepath.ToSlash(relPath)
+	sourceID, tag, err := ctx.ProjectDb.WorkingCopySource(key)
+	if err != nil || sourceID == nil {
+		return
+	}
+	if err := ctx.ProjectDb.InsertDerivation(*sourceID, fileID, "copy-out"); err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m %s: %v\n", ref, err)
+		return
+	}
+	if tag {
+		ctx.ProjectDb.InsertTag(fileID, workingCopyTag, hash, fp.ToJSON())
+	}
+	ctx.ProjectDb.DeleteWorkingCopy(key)
+}
+
+func copyOutTargets(ctx *context.Context, args []string) ([]string, error) {
+	if resolve.HasNarrowSubject(ctx) {
+		return resolve.SubjectRelPaths(ctx)
+	}
+	if len(args) == 0 {
+		return nil, fmt.Errorf("usage: mkrk copy-out [--to <dir>] [--tag] <reference> [...]")
+	}
+	var all []string
+	for _, raw := range args {
+		rels, err := resolve.RefRelPaths(ctx, raw)
+		if err != nil {
+			return nil, err
+		}
+		all = append(all, rels...)
+	}
+	return all, nil
+}
@@ -0,0 +1,150 @@
+package cli
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+)
+
+const dbUsage = `usage: mkrk db backup [<path>] [--workspace]
+       mkrk db vacuum [--workspace]`
+
+// maintainedDb is a project or workspace database the db command looks
+// after.
+type maintainedDb interface {
+	Backup(dst string) error
+	Vacuum() (before, after int64, err error)
+}
+
+// dbTarget is the database a db subcommand works on, where its backups go
+// by default and how it records what was done to it.
+type dbTarget struct {
+	db        maintainedDb
+	backupDir string
+	ext       string
+	audit     func(op string, detail *string) error
+}
+
+// RunDb backs up or vacuums the project database.
+func RunDb(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	user := whoami()
+	return runDb(dbTarget{
+		db:        ctx.ProjectDb,
+		backupDir: filepath.Join(ctx.ProjectRoot, ".mkrk-backups"),
+		ext:       ".mkrk",
+		audit: func(op string, detail *string) error {
+			return ctx.ProjectDb.InsertAudit(op, nil, &user, detail)
+		},
+	}, args)
+}
+
+// RunWorkspaceDb backs up or vacuums the workspace database.
+func RunWorkspaceDb(args []string) error {
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil {
+		ctx.Close()
+		return fmt.Errorf("not in a workspace")
+	}
+	ws := ctx.Workspace
+	user := whoami()
+	args = slices.DeleteFunc(slices.Clone(args), func(a string) bool { return a == "--workspace" })
+	err = runDb(dbTarget{
+		db:        ws.Db,
+		backupDir: filepath.Join(ws.Root, ".mksp-backups"),
+		ext:       ".mksp",
+		audit: func(op string, detail *string) error {
+			return ws.Db.InsertAudit(op, nil, &user, detail)
+		},
+	}, args)
+	if closeErr := ctx.Close(); err == nil {
+		err = closeErr
+	}
+	return err
+}
+
+func runDb(t dbTarget, args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", dbUsage)
+	}
+	switch args[0] {
+	case "backup":
+		if len(args) > 2 {
+			return fmt.Errorf("%s", dbUsage)
+		}
+		return dbBackup(t, args[1:])
+	case "vacuum":
+		if len(args) != 1 {
+			return fmt.Errorf("%s", dbUsage)
+		}
+		return dbVacuum(t)
+	default:
+		return fmt.Errorf("unknown db subcommand: %s\n%s", args[0], dbUsage)
+	}
+}
+
+// dbBackup copies the database to the path given, or into a directory
+// given, or else into the backup directory beside it, named for the time.
+// The audit log records the copy's hash, never its path.
+func dbBackup(t dbTarget, args []string) error {
+	name := time.Now().UTC().Format("20060102T150405Z") + t.ext
+	dst := filepath.Join(t.backupDir, name)
+	if len(args) == 1 {
+		dst = args[0]
+		if info, err := os.Stat(dst); err == nil && info.IsDir() {
+			dst = filepath.Join(dst, name)
+		}
+	} else if err := os.MkdirAll(t.backupDir, 0o700); err != nil {
+		return err
+	}
+
+	if err := t.db.Backup(dst); err != nil {
+		return err
+	}
+	hash, err := integrity.HashFile(dst)
+	if err != nil {
+		return err
+	}
+	info, err := os.Stat(dst)
+	if err != nil {
+		return err
+	}
+	detail, _ := json.Marshal(map[string]any{"sha256": hash, "size": info.Size()})
+	detailStr := string(detail)
+	if err := t.audit("backup", &detailStr); err != nil {
+		return err
+	}
+
+	fmt.Println(dst)
+	fmt.Fprintf(os.Stderr, "Backed up and checked %s (sha256 %s)\n", formatSize(info.Size()), hash)
+	return nil
+}
+
+func dbVacuum(t dbTarget) error {
+	before, after, err := t.db.Vacuum()
+	if err != nil {
+		return err
+	}
+	detail, _ := json.Marshal(map[string]any{"before": before, "after": after})
+	detailStr := string(detail)
+	if err := t.audit("vacuum", &detailStr); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Vacuumed and analyzed: %s → %s\n", formatSize(before), formatSize(after))
+	return nil
+}
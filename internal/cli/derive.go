@@ -0,0 +1,113 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+// RunDerive records that one file was produced from another, such as a
+// transcript typed up from a recording. The derived file is tracked first
+// if it is not already.
+func RunDerive(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("derive", flag.ExitOnError)
+	via := fs.String("via", "", "tool or process that produced the derived file")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(positional) != 2 || *via == "" {
+		return fmt.Errorf("usage: mkrk derive <source-ref> <derived-path> --via <tool>")
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+
+	paths, err := resolve.Ref(ctx, positional[0])
+	if err != nil {
+		return err
+	}
+	if len(paths) != 1 {
+		return fmt.Errorf("%s: matched %d files, expected one", positional[0], len(paths))
+	}
+	src, err := deriveTarget(ctx, allFiles, paths[0], projectName)
+	if err != nil {
+		return err
+	}
+	if src.file == nil || src.file.ID == nil {
+		return fmt.Errorf("%s: not tracked (run sync first)", src.ref)
+	}
+	if src.status == verifyModified {
+		return fmt.Errorf("%s: modified since it was tracked (run verify)", src.ref)
+	}
+
+	derivedPath, err := filepath.Abs(positional[1])
+	if err != nil {
+		return err
+	}
+	dst, err := deriveTarget(ctx, allFiles, derivedPath, projectName)
+	if err != nil {
+		return err
+	}
+	if dst.status == verifyModified {
+		return fmt.Errorf("%s: modified since it was tracked (run verify)", dst.ref)
+	}
+	if dst.file != nil && *dst.file.ID == *src.file.ID {
+		return fmt.Errorf("%s cannot be derived from itself", dst.ref)
+	}
+
+	if dst.file == nil {
+		categories, err := ctx.ProjectDb.ListCategories()
+		if err != nil {
+			return err
+		}
+		provenance := provenanceJSON(*via, []string{src.file.SHA256})
+		if err := ingestNew(ctx, dst.relPath, dst.ref, dst.hash, dst.fp, &provenance, categories); err != nil {
+			return fmt.Errorf("%s: %w", dst.ref, err)
+		}
+		if dst.file, err = ctx.ProjectDb.GetFileByHash(dst.hash); err != nil || dst.file == nil {
+			return fmt.Errorf("%s: could not track file", dst.ref)
+		}
+		indexFile(ctx, dst.relPath, dst.hash)
+		fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", dst.ref)
+	}
+
+	if err := ctx.ProjectDb.InsertDerivation(*src.file.ID, *dst.file.ID, *via); err != nil {
+		return err
+	}
+
+	user := whoami()
+	detail, _ := json.Marshal(map[string]string{
+		"source_sha256": src.file.SHA256,
+		"via":           *via,
+	})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("derive", dst.file.ID, &user, &detailStr)
+
+	fmt.Fprintf(os.Stderr, "%s derived from %s via %s\n", dst.ref, src.ref, *via)
+	return nil
+}
+
+// deriveTarget classifies a file in the project against the tracked
+// records.
+func deriveTarget(ctx *context.Context, allFiles []models.TrackedFile, absPath, projectName string) (*verifyEntry, error) {
+	relPath, err := filepath.Rel(ctx.ProjectRoot, absPath)
+	if err != nil || !filepath.IsLocal(relPath) || strings.HasPrefix(relPath, ".") {
+		return nil, fmt.Errorf("%s: not in this project", absPath)
+	}
+	if !fileExists(absPath) {
+		return nil, fmt.Errorf("%s: no such file", absPath)
+	}
+	return classifyFile(ctx, allFiles, relPath, projectName)
+}
@@ -0,0 +1,79 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/db"
+)
+
+// RunDoctor reports the schema version of the project and workspace
+// databases at the working directory and the migrations each still needs.
+// It runs before context discovery, since opening a database through
+// discovery would apply them.
+func RunDoctor(args []string) error {
+	fs := flag.NewFlagSet("doctor", flag.ExitOnError)
+	fs.Parse(args)
+
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	projectRoot := context.FindProjectRoot(cwd)
+	workspaceRoot := context.FindWorkspaceRoot(cwd)
+	if projectRoot == "" && workspaceRoot == "" {
+		return fmt.Errorf("not in a project or workspace")
+	}
+
+	if projectRoot != "" {
+		if err := reportSchema("project", filepath.Join(projectRoot, ".mkrk"), db.ProjectMigrations); err != nil {
+			return err
+		}
+	}
+	if workspaceRoot != "" {
+		if err := reportSchema("workspace", filepath.Join(workspaceRoot, ".mksp"), db.WorkspaceMigrations); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func reportSchema(kind, path string, migrations []db.Migration) error {
+	fmt.Printf("%-10s %s\n", kind, path)
+	if kind == "project" {
+		if locked, err := crypt.IsLocked(path); err != nil {
+			return err
+		} else if locked {
+			fmt.Println("  locked; unlock it to inspect the schema")
+			return nil
+		}
+	}
+	if sealed, err := crypt.IsSealed(path); err != nil {
+		return err
+	} else if sealed {
+		fmt.Println("  encrypted at rest")
+	}
+
+	version, pending, err := db.InspectSchema(path, migrations)
+	if err != nil {
+		return err
+	}
+	latest := migrations[len(migrations)-1].Version
+	switch {
+	case version > latest:
+		fmt.Printf("  schema version %d, newer than this mkrk knows (%d)\n", version, latest)
+	case len(pending) == 0:
+		fmt.Printf("  schema version %d of %d, up to date\n", version, latest)
+	default:
+		fmt.Printf("  schema version %d of %d, %d migration(s) pending:\n", version, latest, len(pending))
+		for _, m := range pending {
+			fmt.Printf("    %d %s\n", m.Version, m.Name)
+		}
+		fmt.Println("  pending migrations run the next time mkrk opens the database")
+	}
+	return nil
+}
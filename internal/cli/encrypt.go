@@ -0,0 +1,589 @@
+package cli
+
+import (
+	"encoding/hex"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"slices"
+
+	"golang.org/x/term"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// passphraseEnv is read instead of prompting, for scripts and tests.
+const passphraseEnv = "MKRK_PASSPHRASE"
+
+// keyfileEnv names a file whose content is the passphrase of databases
+// encrypted at rest, so they open unattended without MKRK_PASSPHRASE.
+const keyfileEnv = "MKRK_KEYFILE"
+
+// RunEncrypt turns at-rest encryption on (or off with --remove) for a
+// category, sealing (or unsealing) the files already in it, except
+// tracked files in an immutable category. Tracked records keep the
+// plaintext's hash. With --database it encrypts the project database
+// itself instead.
+func RunEncrypt(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("encrypt", flag.ExitOnError)
+	remove := fs.Bool("remove", false, "decrypt the category's files and stop encrypting it")
+	database := fs.Bool("database", false, "encrypt the project database at rest")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if *database {
+		if len(positional) != 0 {
+			return fmt.Errorf("usage: mkrk encrypt --database [--remove]")
+		}
+		user := whoami()
+		return encryptDatabase(ctx.ProjectDb, "project", *remove, func(op string) error {
+			return ctx.ProjectDb.InsertAudit(op, nil, &user, nil)
+		})
+	}
+	if len(positional) != 1 {
+		return fmt.Errorf("usage: mkrk encrypt <category> [--remove]\n       mkrk encrypt --database [--workspace] [--remove]")
+	}
+
+	name := positional[0]
+	cat, err := ctx.ProjectDb.GetCategoryByName(name)
+	if err != nil {
+		return err
+	}
+	if cat == nil || cat.ID == nil {
+		return fmt.Errorf("category '%s' not found", name)
+	}
+
+	key, err := projectKey(ctx.ProjectDb, !*remove)
+	if err != nil {
+		return err
+	}
+
+	patterns, err := walk.CategoryPatterns(ctx.ProjectDb, &name)
+	if err != nil {
+		return err
+	}
+	entries, err := walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+	if err != nil {
+		return err
+	}
+
+	if !*remove {
+		if err := ctx.ProjectDb.SetScopeEncrypted(*cat.ID, true); err != nil {
+			return err
+		}
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	changed, failed, kept := 0, 0, 0
+	for _, relPath := range entries {
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		if !*remove && keepsPlaintext(ctx, relPath) {
+			fmt.Fprintf(os.Stderr, "  - %s \033[36m(immutable, left in plaintext)\033[0m\n", ref)
+			kept++
+			continue
+		}
+		var done bool
+		if *remove {
+			done, err = unsealFile(ctx, relPath, key)
+		} else {
+			done, err = sealFile(ctx, relPath, key)
+		}
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+			failed++
+			continue
+		}
+		if done {
+			changed++
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", ref)
+		}
+	}
+
+	if *remove {
+		if failed > 0 {
+			return fmt.Errorf("%d file(s) could not be decrypted; '%s' is still encrypted", failed, name)
+		}
+		if err := ctx.ProjectDb.SetScopeEncrypted(*cat.ID, false); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "Decrypted %d file(s); '%s' is no longer encrypted\n", changed, name)
+		return nil
+	}
+	fmt.Fprintf(os.Stderr, "Encrypted %d file(s); new files in '%s' are encrypted on sync\n", changed, name)
+	if kept > 0 {
+		fmt.Fprintf(os.Stderr, "%d tracked immutable file(s) were left in plaintext\n", kept)
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d file(s) could not be encrypted", failed)
+	}
+	return nil
+}
+
+// RunWorkspaceEncrypt encrypts the workspace database at rest, or with
+// --remove stores it in plaintext again.
+func RunWorkspaceEncrypt(args []string) error {
+	fs := flag.NewFlagSet("encrypt", flag.ExitOnError)
+	remove := fs.Bool("remove", false, "store the workspace database in plaintext again")
+	database := fs.Bool("database", false, "encrypt the workspace database at rest")
+	args = slices.DeleteFunc(slices.Clone(args), func(a string) bool { return a == "--workspace" })
+	if positional := parseInterspersed(fs, args); len(positional) != 0 || !*database {
+		return fmt.Errorf("usage: mkrk encrypt --database --workspace [--remove]")
+	}
+
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil {
+		ctx.Close()
+		return fmt.Errorf("not in a workspace")
+	}
+	wdb := ctx.Workspace.Db
+	user := whoami()
+	err = encryptDatabase(wdb, "workspace", *remove, func(op string) error {
+		return wdb.InsertAudit(op, nil, &user, nil)
+	})
+	if closeErr := ctx.Close(); err == nil {
+		err = closeErr
+	}
+	return err
+}
+
+// sealableDb is a project or workspace database that can be kept
+// encrypted at rest.
+type sealableDb interface {
+	SealAtRest(passphrase string) error
+	UnsealAtRest() error
+	SealedAtRest() bool
+}
+
+// encryptDatabase switches d to being encrypted at rest, or back to
+// plaintext with remove, recording the change with audit first so the
+// entry is written back with the rest. The switch happens as d closes.
+func encryptDatabase(d sealableDb, kind string, remove bool, audit func(op string) error) error {
+	if remove {
+		if !d.SealedAtRest() {
+			return fmt.Errorf("the %s database is not encrypted", kind)
+		}
+		if err := audit("decrypt_database"); err != nil {
+			return err
+		}
+		if err := d.UnsealAtRest(); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "The %s database is stored in plaintext again\n", kind)
+		return nil
+	}
+
+	var passphrase string
+	var err error
+	if keyfile := os.Getenv(keyfileEnv); keyfile != "" {
+		passphrase, err = readKeyfile(keyfile)
+	} else {
+		passphrase, err = readPassphrase("New database passphrase: ", true)
+	}
+	if err != nil {
+		return err
+	}
+	op := "encrypt_database"
+	if d.SealedAtRest() {
+		op = "rekey_database"
+	}
+	if err := audit(op); err != nil {
+		return err
+	}
+	if err := d.SealAtRest(passphrase); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "The %s database is encrypted at rest; mkrk asks for its passphrase when opening it\n", kind)
+	return nil
+}
+
+// DatabasePassphrase returns the passphrase of the database encrypted at
+// rest at path: the content of the keyfile named by MKRK_KEYFILE, else
+// MKRK_PASSPHRASE, else what is typed at a prompt.
+func DatabasePassphrase(path string) (string, error) {
+	if keyfile := os.Getenv(keyfileEnv); keyfile != "" {
+		return readKeyfile(keyfile)
+	}
+	return readPassphrase(fmt.Sprintf("Passphrase for %s: ", path), false)
+}
+
+// readKeyfile returns the content of a keyfile, used as a passphrase.
+func readKeyfile(path string) (string, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return "", fmt.Errorf("read keyfile: %w", err)
+	}
+	if len(data) == 0 {
+		return "", fmt.Errorf("keyfile %s is empty", path)
+	}
+	return string(data), nil
+}
+
+// projectKey asks for the project passphrase and derives the file key.
+// Only a salt and a check value are stored in the database, never the key.
+// With create set, a project without a passphrase is given one.
+func projectKey(pdb *db.ProjectDb, create bool) ([]byte, error) {
+	saltHex, err := pdb.GetConfig("encryption_salt")
+	if err != nil {
+		return nil, err
+	}
+	check, err := pdb.GetConfig("encryption_check")
+	if err != nil {
+		return nil, err
+	}
+
+	if saltHex == nil || check == nil {
+		if !create {
+			return nil, fmt.Errorf("project has no encryption passphrase (run mkrk encrypt <category>)")
+		}
+		passphrase, err := readPassphrase("New project passphrase: ", true)
+		if err != nil {
+			return nil, err
+		}
+		salt, err := crypt.NewSalt()
+		if err != nil {
+			return nil, err
+		}
+		key, err := crypt.DeriveKey(passphrase, salt)
+		if err != nil {
+			return nil, err
+		}
+		if err := pdb.SetConfig("encryption_salt", hex.EncodeToString(salt)); err != nil {
+			return nil, err
+		}
+		if err := pdb.SetConfig("encryption_check", crypt.KeyCheck(key)); err != nil {
+			return nil, err
+		}
+		return key, nil
+	}
+
+	salt, err := hex.DecodeString(*saltHex)
+	if err != nil {
+		return nil, fmt.Errorf("corrupt encryption_salt: %w", err)
+	}
+	passphrase, err := readPassphrase("Project passphrase: ", false)
+	if err != nil {
+		return nil, err
+	}
+	key, err := crypt.DeriveKey(passphrase, salt)
+	if err != nil {
+		return nil, err
+	}
+	if crypt.KeyCheck(key) != *check {
+		return nil, fmt.Errorf("wrong passphrase")
+	}
+	return key, nil
+}
+
+// readPassphrase reads MKRK_PASSPHRASE, or prompts on the terminal. New
+// passphrases are asked for twice.
+func readPassphrase(prompt string, confirm bool) (string, error) {
+	return readPassphraseFrom(passphraseEnv, prompt, confirm)
+}
+
+// readPassphraseFrom reads the passphrase from env, or prompts on the
+// terminal.
+func readPassphraseFrom(env, prompt string, confirm bool) (string, error) {
+	if v := os.Getenv(env); v != "" {
+		return v, nil
+	}
+	fd := int(os.Stdin.Fd())
+	if !term.IsTerminal(fd) {
+		return "", fmt.Errorf("passphrase required: set %s or run from a terminal", env)
+	}
+
+	ask := func(p string) (string, error) {
+		fmt.Fprint(os.Stderr, p)
+		b, err := term.ReadPassword(fd)
+		fmt.Fprintln(os.Stderr)
+		return string(b), err
+	}
+	passphrase, err := ask(prompt)
+	if err != nil {
+		return "", err
+	}
+	if passphrase == "" {
+		return "", fmt.Errorf("empty passphrase")
+	}
+	if confirm {
+		again, err := ask("Repeat passphrase: ")
+		if err != nil {
+			return "", err
+		}
+		if again != passphrase {
+			return "", fmt.Errorf("passphrases do not match")
+		}
+	}
+	return passphrase, nil
+}
+
+// lazyKey asks for the project passphrase the first time a key is needed,
+// so commands that touch no encrypted file never prompt.
+type lazyKey struct {
+	ctx *context.Context
+	key []byte
+}
+
+func (k *lazyKey) get() ([]byte, error) {
+	if k.key == nil {
+		key, err := projectKey(k.ctx.ProjectDb, false)
+		if err != nil {
+			return nil, err
+		}
+		k.key = key
+	}
+	return k.key, nil
+}
+
+// openPlaintext opens a file for reading its content, decrypting it if it
+// is encrypted. It also returns the content size.
+func openPlaintext(path string, key *lazyKey) (io.Reader, io.Closer, int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, nil, 0, err
+	}
+	info, err := f.Stat()
+	if err != nil {
+		f.Close()
+		return nil, nil, 0, err
+	}
+	encrypted, err := crypt.IsEncrypted(path)
+	if err != nil || !encrypted {
+		return f, f, info.Size(), err
+	}
+
+	k, err := key.get()
+	if err != nil {
+		f.Close()
+		return nil, nil, 0, err
+	}
+	r, err := crypt.NewReader(f, k)
+	if err != nil {
+		f.Close()
+		return nil, nil, 0, err
+	}
+	return r, f, crypt.PlaintextSize(info.Size()), nil
+}
+
+// sealNewFiles encrypts plaintext files found in encrypted categories, so
+// sync tracks them as stored. Under dry it only reports them.
+func sealNewFiles(ctx *context.Context, entries []string, projectName string, dry bool) error {
+	categories, err := ctx.ProjectDb.ListCategories()
+	if err != nil {
+		return err
+	}
+	var encrypted []models.Scope
+	for _, c := range categories {
+		if enc, _ := ctx.ProjectDb.IsScopeEncrypted(*c.ID); enc {
+			encrypted = append(encrypted, c)
+		}
+	}
+	if len(encrypted) == 0 {
+		return nil
+	}
+
+	key := &lazyKey{ctx: ctx}
+	for _, relPath := range entries {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		if len(matchingCategories(relPath, encrypted)) == 0 || isSymlink(absPath) {
+			continue
+		}
+		if enc, err := crypt.IsEncrypted(absPath); err != nil || enc {
+			continue
+		}
+		if keepsPlaintext(ctx, relPath) {
+			continue
+		}
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		if dry {
+			fmt.Fprintf(os.Stderr, "  ~ %s \033[36m(would encrypt)\033[0m\n", ref)
+			continue
+		}
+		k, err := key.get()
+		if err != nil {
+			return err
+		}
+		if _, err := sealFile(ctx, relPath, k); err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: could not encrypt: %v\n", ref, err)
+			continue
+		}
+		fmt.Fprintf(os.Stderr, "  ~ %s \033[36m(encrypted)\033[0m\n", ref)
+	}
+	return nil
+}
+
+// keepsPlaintext reports whether the file at relPath is tracked plaintext
+// in an immutable category. Encryption leaves such files alone, since
+// rewriting them would change bytes the category promises never change;
+// only files new to the category are encrypted before they are tracked.
+func keepsPlaintext(ctx *context.Context, relPath string) bool {
+	if protection, _ := ctx.ProjectDb.ResolveProtection(relPath); protection != models.ProtectionImmutable {
+		return false
+	}
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	if enc, err := crypt.IsEncrypted(absPath); err != nil || enc {
+		return false
+	}
+	hash, err := ctx.HashFile(absPath)
+	if err != nil {
+		return false
+	}
+	file, _ := ctx.ProjectDb.GetFileByHash(hash)
+	return file != nil
+}
+
+// sealFile encrypts a plaintext file in place. It reports false if the
+// file was already encrypted.
+func sealFile(ctx *context.Context, relPath string, key []byte) (bool, error) {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	if enc, err := crypt.IsEncrypted(absPath); err != nil || enc {
+		return false, err
+	}
+	err := rewriteFile(ctx, relPath, "encrypt", func(dst io.Writer, src io.Reader) error {
+		return crypt.Encrypt(dst, src, key)
+	})
+	if err != nil {
+		return false, err
+	}
+	if hash, err := ctx.HashFile(absPath); err == nil {
+		if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil && file.ID != nil {
+			return true, sealStoredVersions(ctx, *file.ID, key)
+		}
+	}
+	return true, nil
+}
+
+// unsealFile decrypts an encrypted file in place. It reports false if the
+// file was not encrypted.
+func unsealFile(ctx *context.Context, relPath string, key []byte) (bool, error) {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	if enc, err := crypt.IsEncrypted(absPath); err != nil || !enc {
+		return false, err
+	}
+	err := rewriteFile(ctx, relPath, "decrypt", func(dst io.Writer, src io.Reader) error {
+		r, err := crypt.NewReader(src, key)
+		if err != nil {
+			return err
+		}
+		_, err = io.Copy(dst, r)
+		return err
+	})
+	return err == nil, err
+}
+
+// rewriteFile replaces a file's content with transform's output, via a
+// temporary file so a failure leaves the original untouched, and audits
+// the change as op. The content's identity is its plaintext, so a tracked
+// record keeps its hash: encrypting records the hash of the stored form
+// beside it, decrypting forgets it. A record from before that, whose hash
+// is of the ciphertext, moves to the plaintext's hash when decrypted.
+func rewriteFile(ctx *context.Context, relPath, op string, transform func(io.Writer, io.Reader) error) error {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	hasher, err := ctx.Hasher()
+	if err != nil {
+		return err
+	}
+	oldHash, oldFp, err := hasher.HashAndFingerprint(absPath)
+	if err != nil {
+		return err
+	}
+	identity, identityFp := ctx.Identify(oldHash, oldFp)
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return err
+	}
+
+	tmp, err := os.CreateTemp(filepath.Dir(absPath), ".mkrk-"+op+"-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+
+	src, err := os.Open(absPath)
+	if err != nil {
+		tmp.Close()
+		return err
+	}
+	err = transform(tmp, src)
+	src.Close()
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	if err := os.Chmod(tmp.Name(), info.Mode().Perm()); err != nil {
+		return err
+	}
+
+	if immutable, _ := integrity.IsImmutable(absPath); immutable {
+		if err := integrity.ClearImmutable(absPath); err != nil {
+			return err
+		}
+	}
+	if err := os.Rename(tmp.Name(), absPath); err != nil {
+		return err
+	}
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	enforceImmutable(absPath, protection, relPath)
+
+	newHash, newFp, err := hasher.HashAndFingerprint(absPath)
+	if err != nil {
+		return err
+	}
+	if op == "encrypt" {
+		plainFp := identityFp.ToJSON()
+		err = ctx.ProjectDb.PutCiphertext(db.Ciphertext{
+			SHA256:               newHash,
+			Fingerprint:          newFp.ToJSON(),
+			PlaintextSHA256:      identity,
+			PlaintextFingerprint: &plainFp,
+		})
+	} else {
+		err = ctx.ProjectDb.DeleteCiphertext(oldHash)
+	}
+	if err != nil {
+		return err
+	}
+
+	file, err := ctx.ProjectDb.GetFileByHash(identity)
+	if err != nil || file == nil || file.ID == nil {
+		return err
+	}
+	if op == "decrypt" && file.SHA256 != newHash {
+		if err := recordRevision(ctx, file, relPath, newHash); err != nil {
+			return err
+		}
+		if err := ctx.ProjectDb.UpdateFileSHA256(*file.ID, newHash); err != nil {
+			return err
+		}
+		if err := ctx.ProjectDb.UpdateFileFingerprint(*file.ID, newFp.ToJSON()); err != nil {
+			return err
+		}
+	}
+	user := whoami()
+	detail, _ := json.Marshal(map[string]string{"old_sha256": oldHash, "new_sha256": newHash})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit(op, file.ID, &user, &detailStr)
+	return nil
+}
@@ -0,0 +1,34 @@
+package cli
+
+import "errors"
+
+// Exit status bits. A command that fails for several reasons, or a verb run
+// across several projects, reports the union of the bits that apply.
+const (
+	ExitError    = 1 // the command could not complete
+	ExitModified = 2 // tracked files failed verification
+	ExitMissing  = 4 // tracked files are missing from disk
+)
+
+// StatusError is an error that carries the exit status mkrk should use.
+type StatusError struct {
+	Code int
+	Err  error
+}
+
+func (e *StatusError) Error() string { return e.Err.Error() }
+
+func (e *StatusError) Unwrap() error { return e.Err }
+
+// ExitStatus returns the exit status for err: 0 for nil, the code of a
+// StatusError, and ExitError for anything else.
+func ExitStatus(err error) int {
+	if err == nil {
+		return 0
+	}
+	var se *StatusError
+	if errors.As(err, &se) {
+		return se.Code
+	}
+	return ExitError
+}
@@ -0,0 +1,229 @@
+package cli
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"mime"
+	"net/http"
+	"net/url"
+	"os"
+	"path"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+)
+
+// fetchProvenance is what a fetched file's provenance records about the
+// response it came from.
+type fetchProvenance struct {
+	URL       string            `json:"url"`
+	Status    string            `json:"status"`
+	Headers   map[string]string `json:"headers"`
+	SHA256    string            `json:"sha256"`
+	FetchedAt string            `json:"fetched_at"`
+	// Set on snapshots taken by archive-url: how many assets were inlined,
+	// the Wayback Machine's copy, and the hash of the file whose
+	// provenance gave the URL.
+	Assets  int    `json:"assets,omitempty"`
+	Wayback string `json:"wayback,omitempty"`
+	Source  string `json:"source,omitempty"`
+}
+
+// RunFetch downloads a URL into a category through the privacy proxy and
+// tracks it, recording the URL, response headers, time and hash of what
+// was received as its provenance.
+func RunFetch(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("fetch", flag.ExitOnError)
+	category := fs.String("category", "evidence", "category to save into")
+	name := fs.String("name", "", "file name to save as (default: from the URL)")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(positional) != 1 {
+		return fmt.Errorf("usage: mkrk fetch <url> [--category <name>] [--name <file>]")
+	}
+	if !isWebURL(positional[0]) {
+		return fmt.Errorf("not an http or https URL: %s", positional[0])
+	}
+	u, _ := url.Parse(positional[0])
+
+	cat, err := ctx.ProjectDb.GetCategoryByName(*category)
+	if err != nil {
+		return err
+	}
+	if cat == nil || cat.Pattern == nil {
+		return fmt.Errorf("category '%s' not found", *category)
+	}
+	catDir := models.NameFromPattern(*cat.Pattern)
+
+	client, err := fetchClient(ctx, u.Host)
+	if err != nil {
+		return err
+	}
+	resp, err := client.Get(u.String())
+	if err != nil {
+		return err
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode/100 != 2 {
+		return fmt.Errorf("%s returned %s", u.Host, resp.Status)
+	}
+
+	fileName := *name
+	if fileName == "" {
+		fileName = fetchName(u, resp.Header)
+	}
+	if fileName != filepath.Base(fileName) || strings.HasPrefix(fileName, ".") {
+		return fmt.Errorf("cannot save as '%s' (use --name)", fileName)
+	}
+	relPath := filepath.ToSlash(filepath.Join(catDir, fileName))
+	prov := &fetchProvenance{
+		URL:       u.String(),
+		Status:    resp.Status,
+		Headers:   fetchHeaders(resp.Header),
+		FetchedAt: time.Now().UTC().Format(time.RFC3339),
+	}
+	saved, err := saveFetched(ctx, relPath, resp.Body, prov)
+	if err != nil {
+		return err
+	}
+
+	user := whoami()
+	detail, _ := json.Marshal(map[string]string{"url": u.String(), "sha256": prov.SHA256})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("fetch_url", saved.fileID, &user, &detailStr)
+
+	fmt.Fprintf(os.Stderr, "  + %s (%s)\n", saved.ref, formatSize(saved.size))
+	if saved.isNew {
+		categories, _ := ctx.ProjectDb.ListCategories()
+		fireIngestRules(ctx, []ingested{{relPath: relPath, ref: saved.ref, hash: saved.hash}}, categories, false)
+	}
+	return nil
+}
+
+// fetchClient returns a client for downloading from host, through the
+// privacy proxy unless privacy is off. It has no overall timeout, since
+// downloads may be large.
+func fetchClient(ctx *context.Context, host string) (*http.Client, error) {
+	privacy := privacySettings(ctx)
+	client, err := privacy.httpClient()
+	if err != nil {
+		return nil, err
+	}
+	if via := privacy.via(host); via != "" {
+		fmt.Fprintf(os.Stderr, "privacy: requests to %s go through %s\n", host, via)
+	} else {
+		fmt.Fprintf(os.Stderr, "privacy: DISABLED — requests to %s go direct\n", host)
+	}
+	client.Timeout = 0
+	return client, nil
+}
+
+// fetched is a download saved into the project.
+type fetched struct {
+	ref    string
+	hash   string
+	size   int64
+	fileID *int64
+	isNew  bool
+}
+
+// saveFetched writes body to relPath and tracks it with prov as its
+// provenance, filling in prov.SHA256 from the bytes written unless the
+// caller already set it. Content the project already tracks is saved but
+// keeps its existing record.
+func saveFetched(ctx *context.Context, relPath string, body io.Reader, prov *fetchProvenance) (*fetched, error) {
+	dst := filepath.Join(ctx.ProjectRoot, filepath.FromSlash(relPath))
+	if fileExists(dst) {
+		return nil, fmt.Errorf("%s already exists (use --name)", relPath)
+	}
+	if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+		return nil, err
+	}
+
+	// Download beside the destination under a dot name, which walks skip,
+	// so a failed fetch never leaves a partial file to be tracked.
+	tmp, err := os.CreateTemp(filepath.Dir(dst), ".fetch-*")
+	if err != nil {
+		return nil, err
+	}
+	defer os.Remove(tmp.Name())
+	digest := sha256.New()
+	n, err := io.Copy(io.MultiWriter(tmp, digest), body)
+	if cerr := tmp.Close(); err == nil {
+		err = cerr
+	}
+	if err != nil {
+		return nil, fmt.Errorf("%s: %w", prov.URL, err)
+	}
+	if err := os.Rename(tmp.Name(), dst); err != nil {
+		return nil, err
+	}
+	if prov.SHA256 == "" {
+		prov.SHA256 = hex.EncodeToString(digest.Sum(nil))
+	}
+
+	hash, fp, err := ctx.HashAndFingerprint(dst)
+	if err != nil {
+		return nil, err
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	saved := &fetched{ref: reference.FormatRef(relPath, projectName, ctx.ProjectDb), hash: hash, size: n}
+	if existing, _ := ctx.ProjectDb.GetFileByHash(hash); existing == nil {
+		b, _ := json.Marshal(prov)
+		provenance := string(b)
+		categories, _ := ctx.ProjectDb.ListCategories()
+		if err := ingestNew(ctx, relPath, saved.ref, hash, fp, &provenance, categories); err != nil {
+			return nil, fmt.Errorf("%s: saved but not tracked: %w", saved.ref, err)
+		}
+		saved.isNew = true
+	} else {
+		fmt.Fprintf(os.Stderr, "  = %s has the same content as a tracked file\n", saved.ref)
+	}
+	indexFile(ctx, relPath, hash)
+	if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil {
+		saved.fileID = file.ID
+	}
+	return saved, nil
+}
+
+// fetchName picks a file name for a download: the server's
+// Content-Disposition filename if it gives one, else the last segment of
+// the URL path, else index.html.
+func fetchName(u *url.URL, header http.Header) string {
+	if _, params, err := mime.ParseMediaType(header.Get("Content-Disposition")); err == nil {
+		if name := filepath.Base(params["filename"]); name != "." && name != string(filepath.Separator) {
+			return name
+		}
+	}
+	if base := path.Base(u.Path); base != "." && base != "/" {
+		return base
+	}
+	return "index.html"
+}
+
+// fetchHeaders flattens response headers for provenance. Cookies are left
+// out: they identify the session, not the content.
+func fetchHeaders(header http.Header) map[string]string {
+	out := make(map[string]string, len(header))
+	for k, v := range header {
+		if k == "Set-Cookie" {
+			continue
+		}
+		out[k] = strings.Join(v, ", ")
+	}
+	return out
+}
@@ -0,0 +1,150 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path"
+	"path/filepath"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// RunFind looks files up across the workspace's projects by content hash
+// or name, from the workspace's file index rather than each project's
+// database. Names come from the project directories on disk, since no
+// database stores them.
+func RunFind(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("find", flag.ExitOnError)
+	hash := fs.String("hash", "", "content hash, or the start of one")
+	name := fs.String("name", "", "glob matched against file names, or against paths in the project if it has a /")
+	if positional := parseInterspersed(fs, args); len(positional) != 0 || (*hash == "" && *name == "") {
+		return fmt.Errorf("usage: mkrk find [--hash <hash>] [--name <glob>]")
+	}
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	if _, err := path.Match(*name, ""); err != nil {
+		return fmt.Errorf("invalid --name pattern: %w", err)
+	}
+	wantHash := strings.ToLower(*hash)
+
+	projects, err := ctx.Workspace.Db.ListProjects()
+	if err != nil {
+		return err
+	}
+	found, unindexed := 0, 0
+	for _, proj := range projects {
+		indexed, err := ctx.Workspace.Db.IndexedFiles(proj.Name)
+		if err != nil {
+			return err
+		}
+		if len(indexed) == 0 {
+			unindexed++
+			continue
+		}
+		if wantHash != "" && !indexHasHash(indexed, wantHash) {
+			continue
+		}
+		root := filepath.Join(ctx.Workspace.Root, proj.Path)
+		entries, err := walk.WalkAndCollect(root, []string{"**"})
+		if err != nil {
+			return err
+		}
+		for _, relPath := range entries {
+			f, ok := indexed[db.PathKey(relPath)]
+			if !ok || !strings.HasPrefix(f.SHA256, wantHash) || !nameMatches(*name, relPath) {
+				continue
+			}
+			size, mime := "-", "-"
+			if f.Size != nil {
+				size = formatSize(*f.Size)
+			}
+			if f.MimeType != nil {
+				mime = *f.MimeType
+			}
+			fmt.Printf("%s\t%s\t%s\t%s\n", filepath.ToSlash(filepath.Join(proj.Path, relPath)), size, mime, f.SHA256)
+			found++
+		}
+	}
+
+	if unindexed > 0 {
+		fmt.Fprintf(os.Stderr, "%d project(s) not indexed yet (run sync in them)\n", unindexed)
+	}
+	if found == 0 {
+		return fmt.Errorf("no matching files")
+	}
+	return nil
+}
+
+func indexHasHash(indexed map[string]db.IndexedFile, prefix string) bool {
+	for _, f := range indexed {
+		if strings.HasPrefix(f.SHA256, prefix) {
+			return true
+		}
+	}
+	return false
+}
+
+// nameMatches reports whether relPath matches pattern: its file name, or
+// the whole path when pattern has a /. An empty pattern matches anything.
+func nameMatches(pattern, relPath string) bool {
+	if pattern == "" {
+		return true
+	}
+	if strings.Contains(pattern, "/") {
+		matched, _ := models.GlobMatch(pattern, relPath)
+		return matched
+	}
+	matched, _ := path.Match(pattern, path.Base(relPath))
+	return matched
+}
+
+// indexFile records the tracked file at relPath in the workspace's file
+// index, so that find sees it before the project's next sync.
+func indexFile(ctx *context.Context, relPath, hash string) {
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil || ctx.ProjectName == nil {
+		return
+	}
+	file, _ := ctx.ProjectDb.GetFileByHash(hash)
+	if file == nil {
+		return
+	}
+	ctx.Workspace.Db.IndexFiles(*ctx.ProjectName, map[string]*models.TrackedFile{filepath.ToSlash(relPath): file})
+}
+
+// indexSynced brings the workspace's file index in line with a sync of
+// the project: tracked maps the paths found to hold tracked files to their
+// hashes, and entries is every path the sync walked. Entries of paths no
+// longer there are dropped.
+func indexSynced(ctx *context.Context, tracked map[string]string, entries []string) {
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil || ctx.ProjectName == nil {
+		return
+	}
+	all, err := ctx.ProjectDb.ListAllFiles()
+	if err != nil {
+		return
+	}
+	byHash := make(map[string]*models.TrackedFile, len(all))
+	for i := range all {
+		byHash[all[i].SHA256] = &all[i]
+	}
+	files := make(map[string]*models.TrackedFile, len(tracked))
+	for relPath, hash := range tracked {
+		if f := byHash[hash]; f != nil {
+			files[filepath.ToSlash(relPath)] = f
+		}
+	}
+	wdb := ctx.Workspace.Db
+	if err := wdb.IndexFiles(*ctx.ProjectName, files); err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m workspace file index: %v\n", err)
+		return
+	}
+	if err := wdb.PruneIndex(*ctx.ProjectName, entries); err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m workspace file index: %v\n", err)
+	}
+}
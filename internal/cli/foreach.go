@@ -0,0 +1,238 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+	"sync"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/generator"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/rules"
+)
+
+const foreachUsage = `usage: mkrk foreach [-j N] [--fire-rules] <reference>... -- <command> [args...]
+       mkrk foreach [-j N] [--fire-rules] <reference>... --tool <name>
+       mkrk :<ref> foreach [options] -- <command> [args...]`
+
+// foreachPlaceholders are replaced in command arguments with each file's
+// details. A command that uses none gets the file's path appended.
+var foreachPlaceholders = []string{"{path}", "{rel}", "{ref}", "{dir}", "{name}", "{stem}"}
+
+// foreachFile is one file a foreach runs over.
+type foreachFile struct {
+	absPath string
+	relPath string
+	ref     string
+}
+
+// RunForeach runs a command, or a tool, once for every file a reference
+// resolves to. Commands get the environment tools get, plus MKRK_FILE and
+// MKRK_REF. Rules do not fire, in this process or in mkrk commands it
+// runs, unless --fire-rules is given.
+func RunForeach(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("foreach", flag.ExitOnError)
+	jobs := fs.Int("jobs", 1, "number of commands to run in parallel")
+	fs.IntVar(jobs, "j", 1, "shorthand for --jobs")
+	tool := fs.String("tool", "", "run a project tool, or a built-in (@name), on each file instead of a command")
+	fireRules := fs.Bool("fire-rules", false, "let rules fire while the commands run")
+
+	refArgs, command := args, []string(nil)
+	for i, a := range args {
+		if a == "--" {
+			refArgs, command = args[:i], args[i+1:]
+			break
+		}
+	}
+	refs := parseInterspersed(fs, refArgs)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if (len(command) == 0) == (*tool == "") {
+		return fmt.Errorf("%s", foreachUsage)
+	}
+	if len(refs) == 0 && !resolve.HasNarrowSubject(ctx) {
+		return fmt.Errorf("%s", foreachUsage)
+	}
+	files, err := foreachTargets(ctx, refs)
+	if err != nil {
+		return err
+	}
+	if len(files) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+
+	if ctx.DryRun {
+		for _, f := range files {
+			if *tool != "" {
+				fmt.Printf("%s %s\n", *tool, f.ref)
+			} else {
+				fmt.Println(strings.Join(foreachCommand(command, f), " "))
+			}
+		}
+		return nil
+	}
+
+	if !*fireRules {
+		os.Setenv(rules.SuppressEnv, "1")
+	}
+
+	var failed int
+	if *tool != "" {
+		failed = foreachTool(ctx, *tool, files)
+	} else {
+		failed = foreachRun(ctx, command, files, *jobs)
+	}
+	if failed > 0 {
+		return fmt.Errorf("foreach: %d of %d file(s) failed", failed, len(files))
+	}
+	return nil
+}
+
+// foreachTargets resolves the references, or the subject, to tracked and
+// untracked files alike, skipping duplicates and encrypted files.
+func foreachTargets(ctx *context.Context, refs []string) ([]foreachFile, error) {
+	paths, err := readTargets(ctx, refs)
+	if err != nil {
+		return nil, err
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	var files []foreachFile
+	seen := make(map[string]bool)
+	for _, absPath := range paths {
+		if seen[absPath] {
+			continue
+		}
+		seen[absPath] = true
+		relPath, err := filepath.Rel(ctx.ProjectRoot, absPath)
+		if err != nil || !filepath.IsLocal(relPath) {
+			relPath = absPath
+		}
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		if enc, err := crypt.IsEncrypted(absPath); err == nil && enc {
+			fmt.Fprintf(os.Stderr, "  - %s \033[36m(encrypted, skipped)\033[0m\n", ref)
+			continue
+		}
+		files = append(files, foreachFile{absPath: absPath, relPath: relPath, ref: ref})
+	}
+	return files, nil
+}
+
+// foreachRun runs command for each file on up to jobs workers and returns
+// how many runs failed. A single argument containing spaces is a shell
+// command, run with sh -c and its placeholders quoted.
+func foreachRun(ctx *context.Context, command []string, files []foreachFile, jobs int) int {
+	privacy := privacySettings(ctx)
+	announcePrivacy(privacy)
+	env, release, err := buildEnv(generator.Generator{Verb: "foreach"}, ctx, privacy)
+	if err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %v\n", err)
+		return len(files)
+	}
+	defer release()
+
+	var mu sync.Mutex
+	failed := 0
+	indexes := make(chan int)
+	var wg sync.WaitGroup
+	for range min(max(jobs, 1), len(files)) {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			for i := range indexes {
+				f := files[i]
+				argv := foreachCommand(command, f)
+				cmd := exec.Command(argv[0], argv[1:]...)
+				cmd.Stdout = os.Stdout
+				cmd.Stderr = os.Stderr
+				cmd.Env = appendKV(appendKV(append([]string(nil), env...), "MKRK_FILE", f.absPath), "MKRK_REF", f.ref)
+				if err := cmd.Run(); err != nil {
+					mu.Lock()
+					failed++
+					fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", f.ref, err)
+					mu.Unlock()
+				}
+			}
+		}()
+	}
+	for i := range files {
+		indexes <- i
+	}
+	close(indexes)
+	wg.Wait()
+	return failed
+}
+
+// foreachCommand fills in a command's placeholders for one file.
+func foreachCommand(command []string, f foreachFile) []string {
+	shell := len(command) == 1 && strings.ContainsAny(command[0], " \t|;&<>")
+	name := filepath.Base(f.absPath)
+	values := map[string]string{
+		"{path}": f.absPath,
+		"{rel}":  f.relPath,
+		"{ref}":  f.ref,
+		"{dir}":  filepath.Dir(f.absPath),
+		"{name}": name,
+		"{stem}": strings.TrimSuffix(name, filepath.Ext(name)),
+	}
+
+	used := false
+	argv := make([]string, len(command))
+	for i, arg := range command {
+		for _, p := range foreachPlaceholders {
+			if strings.Contains(arg, p) {
+				used = true
+				v := values[p]
+				if shell {
+					v = shellQuote(v)
+				}
+				arg = strings.ReplaceAll(arg, p, v)
+			}
+		}
+		argv[i] = arg
+	}
+	if shell {
+		if !used {
+			argv[0] += " " + shellQuote(f.absPath)
+		}
+		return []string{"sh", "-c", argv[0]}
+	}
+	if !used {
+		argv = append(argv, f.absPath)
+	}
+	return argv
+}
+
+// shellQuote quotes s as a single sh word.
+func shellQuote(s string) string {
+	return "'" + strings.ReplaceAll(s, "'", `'\''`) + "'"
+}
+
+// foreachTool runs a project or built-in tool on each file in turn, as a
+// run_tool rule would, and returns how many runs failed. Tools record
+// their output in the project database, so they never run in parallel.
+func foreachTool(ctx *context.Context, name string, files []foreachFile) int {
+	run := ruleToolRunner(ctx)
+	failed := 0
+	for _, f := range files {
+		detail, err := run(name, &rules.Event{RelPath: f.relPath})
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", f.ref, err)
+			failed++
+			continue
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s: %s\n", f.ref, detail)
+	}
+	return failed
+}
@@ -2,18 +2,25 @@ package cli
 
 import (
 	"encoding/json"
+	"errors"
 	"fmt"
 	"io"
+	"net/http"
+	"net/url"
 	"os"
 	"os/exec"
 	"path/filepath"
+	"slices"
+	"strconv"
 	"strings"
+	"sync/atomic"
 	"time"
 
+	"go.foia.dev/muckrake/internal/builtins"
 	"go.foia.dev/muckrake/internal/context"
 	"go.foia.dev/muckrake/internal/generator"
-	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
 	"go.foia.dev/muckrake/internal/resolve"
 	"go.foia.dev/muckrake/internal/walk"
 )
@@ -46,6 +53,12 @@ func RunGenerated(ctxs []*context.Context, gens []generator.Generator, verb stri
 	if err != nil {
 		return err
 	}
+	if gen.IsBuiltin {
+		if len(args) > 1 {
+			return fmt.Errorf("built-in tool %s takes no arguments", toolPath)
+		}
+		return runBuiltin(ctxs, builtins.Lookup(strings.TrimPrefix(toolPath, "@")))
+	}
 
 	var inputPaths []string
 	for _, ctx := range ctxs {
@@ -107,6 +120,14 @@ func resolveTool(arg string, gens []generator.Generator, ctx *context.Context) (
 	}
 
 	if len(matches) == 0 {
+		// A project's own tools shadow the workspace's.
+		gen, path, err := resolveWorkspaceTool(toolName, ctx)
+		if err != nil {
+			return generator.Generator{}, "", err
+		}
+		if path != "" {
+			return gen, path, nil
+		}
 		return generator.Generator{}, "", fmt.Errorf("no tool %q found", toolName)
 	}
 	if len(matches) == 1 {
@@ -150,8 +171,14 @@ func stripLeadingDot(s string) string {
 // basename (with or without extension) matches toolName.
 func findToolFile(g generator.Generator, toolName string) (string, error) {
 	if g.IsBuiltin {
-		// Built-in tools live in a Go-side registry, not on disk.
-		// No registry entries yet → no matches.
+		// Built-in tools live in a Go-side registry, not on disk; the
+		// returned path is the @name rules use for them.
+		if builtins.Lookup(strings.TrimPrefix(toolName, "@")) == nil {
+			return "", nil
+		}
+		return "@" + strings.TrimPrefix(toolName, "@"), nil
+	}
+	if strings.HasPrefix(toolName, "@") {
 		return "", nil
 	}
 	if g.ProjectRoot == "" || g.Scope.Pattern == nil {
@@ -186,42 +213,262 @@ func stripExt(name string) string {
 
 // execTool runs the resolved tool file with the given arguments, passing
 // through stdio and injecting muckrake environment variables. On clean exit
-// it ingests any files the tool produced in MKRK_OUTPUT_DIR.
+// it ingests any files the tool produced in MKRK_OUTPUT_DIR, and tracks
+// files it wrote into the project's categories or listed in MKRK_OUTPUTS
+// as derivatives of its inputs.
 func execTool(g generator.Generator, path string, args, inputPaths []string, ctx *context.Context) error {
 	privacy := privacySettings(ctx)
 	announcePrivacy(privacy)
 
-	inputHashes := hashInputs(inputPaths)
+	inputHashes := hashInputs(ctx, inputPaths)
 
-	outputDir, err := os.MkdirTemp("", "mkrk-tool-out-")
+	workDir, err := os.MkdirTemp("", "mkrk-tool-")
 	if err != nil {
 		return err
 	}
-	defer os.RemoveAll(outputDir)
+	defer os.RemoveAll(workDir)
+	outputDir := filepath.Join(workDir, "out")
+	if err := os.Mkdir(outputDir, 0o755); err != nil {
+		return err
+	}
+	manifest := filepath.Join(workDir, "outputs")
+
+	before := projectEntries(ctx)
 
-	env := buildEnv(g, ctx, privacy)
+	env, release, err := buildEnv(g, ctx, privacy)
+	if err != nil {
+		return err
+	}
+	defer release()
 	env = appendKV(env, "MKRK_OUTPUT_DIR", outputDir)
+	env = appendKV(env, "MKRK_OUTPUTS", manifest)
 
-	cmd := exec.Command(path, args...)
-	cmd.Stdin = os.Stdin
-	cmd.Stdout = os.Stdout
-	cmd.Stderr = os.Stderr
-	cmd.Env = env
-	if err := cmd.Run(); err != nil {
+	limits := toolLimitsFor(ctx, stripExt(filepath.Base(path)))
+	if err := runToolAttempts(ctx, path, args, env, limits, outputDir, manifest); err != nil {
 		return err
 	}
 
+	if err := captureOutputs(ctx, path, before, manifest, inputHashes); err != nil {
+		return err
+	}
 	return ingestOutputs(ctx, g, path, outputDir, inputHashes)
 }
 
-func hashInputs(paths []string) []string {
+// toolLimits bound a tool's runs: how long each may take (zero for no
+// limit) and how many times a failed run is retried.
+type toolLimits struct {
+	timeout time.Duration
+	retries int
+}
+
+// toolLimitsFor reads the tool-timeout and tool-retries settings for the
+// named tool, where tool-timeout.<name> overrides tool-timeout.
+func toolLimitsFor(ctx *context.Context, name string) toolLimits {
+	var l toolLimits
+	if ctx == nil || ctx.ProjectDb == nil {
+		return l
+	}
+	if v := toolSetting(ctx, "tool-timeout", name); v != "" {
+		l.timeout, _ = time.ParseDuration(v)
+	}
+	if v := toolSetting(ctx, "tool-retries", name); v != "" {
+		l.retries, _ = strconv.Atoi(v)
+	}
+	return l
+}
+
+func toolSetting(ctx *context.Context, key, name string) string {
+	for _, k := range []string{key + "." + name, key} {
+		if v, _ := ctx.ProjectDb.GetConfig(k); v != nil {
+			return *v
+		}
+	}
+	return ""
+}
+
+// runToolAttempts runs a tool until it exits cleanly, retrying a failed or
+// timed-out run up to limits.retries times. What a failed run left in the
+// output directory or manifest is discarded before the next. Only a tool
+// run without limits shares mkrk's standard input.
+func runToolAttempts(ctx *context.Context, path string, args, env []string, limits toolLimits, outputDir, manifest string) error {
+	name := stripExt(filepath.Base(path))
+	attempts := limits.retries + 1
+	var err error
+	for attempt := 1; attempt <= attempts; attempt++ {
+		if attempt > 1 {
+			fmt.Fprintf(os.Stderr, "  retrying %s (attempt %d of %d) after: %v\n", name, attempt, attempts, err)
+			os.RemoveAll(outputDir)
+			os.Mkdir(outputDir, 0o755)
+			os.Remove(manifest)
+		}
+		cmd := exec.Command(path, args...)
+		// Input cannot be given to a run that may be repeated, or that
+		// reads it from outside the terminal's process group.
+		if limits.timeout <= 0 && limits.retries == 0 {
+			cmd.Stdin = os.Stdin
+		}
+		cmd.Stdout = os.Stdout
+		cmd.Stderr = os.Stderr
+		cmd.Env = env
+
+		var timedOut bool
+		timedOut, err = runWithTimeout(cmd, limits.timeout)
+		if timedOut {
+			err = fmt.Errorf("%s timed out after %s", name, limits.timeout)
+			auditToolTimeout(ctx, name, limits.timeout, attempt)
+		}
+		var exitErr *exec.ExitError
+		if err == nil || !(timedOut || errors.As(err, &exitErr)) {
+			return err
+		}
+	}
+	return err
+}
+
+// runWithTimeout runs cmd, killing its whole process group once it has
+// run for timeout, so nothing it spawned keeps running. A zero timeout
+// leaves cmd in mkrk's process group, where it still gets the terminal's
+// signals.
+func runWithTimeout(cmd *exec.Cmd, timeout time.Duration) (timedOut bool, err error) {
+	if timeout <= 0 {
+		return false, cmd.Run()
+	}
+	setProcessGroup(cmd)
+	if err := cmd.Start(); err != nil {
+		return false, err
+	}
+	var fired atomic.Bool
+	timer := time.AfterFunc(timeout, func() {
+		fired.Store(true)
+		killProcessGroup(cmd)
+	})
+	err = cmd.Wait()
+	timer.Stop()
+	return fired.Load(), err
+}
+
+// auditToolTimeout records a tool run killed for taking too long.
+func auditToolTimeout(ctx *context.Context, name string, timeout time.Duration, attempt int) {
+	if ctx == nil || ctx.ProjectDb == nil || ctx.Kind != context.ContextProject {
+		return
+	}
+	user := whoami()
+	detail, _ := json.Marshal(map[string]any{
+		"tool":    name,
+		"timeout": timeout.String(),
+		"attempt": attempt,
+	})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("tool_timeout", nil, &user, &detailStr)
+}
+
+// projectEntries lists the files in the project's categories, or nothing
+// outside a project.
+func projectEntries(ctx *context.Context) map[string]bool {
+	if ctx == nil || ctx.ProjectDb == nil || ctx.Kind != context.ContextProject {
+		return nil
+	}
+	patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+	if err != nil {
+		return nil
+	}
+	entries, err := walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+	if err != nil {
+		return nil
+	}
+	set := make(map[string]bool, len(entries))
+	for _, e := range entries {
+		set[e] = true
+	}
+	return set
+}
+
+// captureOutputs tracks the files a tool wrote into the project: those new
+// in its categories since before, and those it listed in the manifest, one
+// path per line, absolute or relative to the project root. Each is linked
+// as a derivative of the tool's tracked inputs.
+func captureOutputs(ctx *context.Context, toolPath string, before map[string]bool, manifest string, inputHashes []string) error {
+	if before == nil {
+		return nil
+	}
+	var rels []string
+	for rel := range projectEntries(ctx) {
+		if !before[rel] {
+			rels = append(rels, rel)
+		}
+	}
+	declared, err := readOutputManifest(ctx, manifest)
+	if err != nil {
+		return err
+	}
+	for _, rel := range declared {
+		if !slices.Contains(rels, rel) {
+			rels = append(rels, rel)
+		}
+	}
+	if len(rels) == 0 {
+		return nil
+	}
+	slices.Sort(rels)
+
+	categories, err := ctx.ProjectDb.ListCategories()
+	if err != nil {
+		return err
+	}
+	toolName := stripExt(filepath.Base(toolPath))
+	tracked := 0
+	for _, rel := range rels {
+		if _, err := trackDerivative(ctx, rel, toolName, inputHashes, categories); err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m %v\n", err)
+			continue
+		}
+		tracked++
+	}
+	if tracked > 0 {
+		fmt.Fprintf(os.Stderr, "tracked %d file(s) written by %s\n", tracked, toolName)
+	}
+	return nil
+}
+
+// readOutputManifest reads the paths a tool declared in MKRK_OUTPUTS,
+// relative to the project root. A missing manifest declares nothing.
+func readOutputManifest(ctx *context.Context, manifest string) ([]string, error) {
+	data, err := os.ReadFile(manifest)
+	if os.IsNotExist(err) {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	var rels []string
+	for line := range strings.Lines(string(data)) {
+		p := strings.TrimSpace(line)
+		if p == "" {
+			continue
+		}
+		if !filepath.IsAbs(p) {
+			p = filepath.Join(ctx.ProjectRoot, p)
+		}
+		rel, err := filepath.Rel(ctx.ProjectRoot, p)
+		if err != nil || !filepath.IsLocal(rel) || strings.HasPrefix(rel, ".") {
+			return nil, fmt.Errorf("MKRK_OUTPUTS: %s is not in the project", strings.TrimSpace(line))
+		}
+		if info, err := os.Stat(p); err != nil || info.IsDir() {
+			return nil, fmt.Errorf("MKRK_OUTPUTS: %s is not a file", strings.TrimSpace(line))
+		}
+		rels = append(rels, rel)
+	}
+	return rels, nil
+}
+
+func hashInputs(ctx *context.Context, paths []string) []string {
 	var hashes []string
 	for _, p := range paths {
 		info, err := os.Stat(p)
 		if err != nil || info.IsDir() {
 			continue
 		}
-		h, err := integrity.HashFile(p)
+		h, err := ctx.HashFile(p)
 		if err != nil {
 			continue
 		}
@@ -249,6 +496,10 @@ func ingestOutputs(ctx *context.Context, g generator.Generator, toolPath, output
 		return err
 	}
 
+	categories, err := ctx.ProjectDb.ListCategories()
+	if err != nil {
+		return err
+	}
 	ingested := 0
 	for _, rel := range rels {
 		src := filepath.Join(outputDir, rel)
@@ -259,7 +510,8 @@ func ingestOutputs(ctx *context.Context, g generator.Generator, toolPath, output
 		if err := copyFile(src, dst); err != nil {
 			continue
 		}
-		if ingestOutputFile(ctx, dst, toolName, inputHashes) {
+		dstRel, _ := filepath.Rel(ctx.ProjectRoot, dst)
+		if _, err := trackDerivative(ctx, dstRel, toolName, inputHashes, categories); err == nil {
 			ingested++
 		}
 	}
@@ -271,37 +523,44 @@ func ingestOutputs(ctx *context.Context, g generator.Generator, toolPath, output
 	return nil
 }
 
-func ingestOutputFile(ctx *context.Context, path, toolName string, inputHashes []string) bool {
-	hash, fp, err := integrity.HashAndFingerprint(path)
-	if err != nil {
-		return false
+// trackDerivative tracks a file a tool wrote at relPath, sealing it first
+// if its category is encrypted, and links it as derived from each tracked
+// input. It returns the derivative's hash.
+func trackDerivative(ctx *context.Context, relPath, toolName string, inputHashes []string, categories []models.Scope) (string, error) {
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
 	}
-
-	provenance := provenanceJSON(toolName, inputHashes)
-	file := &models.TrackedFile{
-		SHA256:      hash,
-		Fingerprint: fp.ToJSON(),
-		IngestedAt:  time.Now().UTC().Format(time.RFC3339),
-		Provenance:  &provenance,
+	ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+	if err := sealNewFiles(ctx, []string{relPath}, projectName, false); err != nil {
+		return "", err
 	}
-	fileID, err := ctx.ProjectDb.InsertFile(file)
+	hash, fp, err := ctx.HashAndFingerprint(filepath.Join(ctx.ProjectRoot, relPath))
 	if err != nil {
-		// Already tracked — look up existing ID for link creation.
-		existing, _ := ctx.ProjectDb.GetFileByHash(hash)
-		if existing == nil || existing.ID == nil {
-			return false
-		}
-		fileID = *existing.ID
+		return "", fmt.Errorf("%s: %w", ref, err)
 	}
 
+	derived, _ := ctx.ProjectDb.GetFileByHash(hash)
+	if derived == nil {
+		provenance := provenanceJSON(toolName, inputHashes)
+		if err := ingestNew(ctx, relPath, ref, hash, fp, &provenance, categories); err != nil {
+			return "", fmt.Errorf("%s: %w", ref, err)
+		}
+		if derived, err = ctx.ProjectDb.GetFileByHash(hash); err != nil || derived == nil {
+			return "", fmt.Errorf("%s: could not track file", ref)
+		}
+		indexFile(ctx, relPath, hash)
+	}
 	for _, inHash := range inputHashes {
 		inFile, _ := ctx.ProjectDb.GetFileByHash(inHash)
-		if inFile == nil || inFile.ID == nil {
+		if inFile == nil || inFile.ID == nil || *inFile.ID == *derived.ID {
 			continue
 		}
-		ctx.ProjectDb.InsertFileLink(*inFile.ID, fileID, "derived_from", nil)
+		if err := ctx.ProjectDb.InsertDerivation(*inFile.ID, *derived.ID, toolName); err != nil {
+			return hash, err
+		}
 	}
-	return true
+	return hash, nil
 }
 
 func provenanceJSON(toolName string, inputHashes []string) string {
@@ -352,32 +611,91 @@ func copyFile(src, dst string) error {
 type privacyConfig struct {
 	enabled bool
 	socks   string
+	// offline forbids network access altogether: mkrk's own requests fail
+	// and tools get proxy variables that lead nowhere.
+	offline bool
+	// isolate gives each destination host its own Tor circuit, moving to
+	// a fresh one after maxPerCircuit requests when that is above zero.
+	isolate       bool
+	maxPerCircuit int
+	// rules is the proxy_rules setting, routing some hosts another way
+	// than socks, which may itself be a chain of proxies.
+	rules string
+	// err is a setting that could not be read. Requests and tools fail
+	// with it rather than run under a policy nobody asked for.
+	err error
 }
 
 // DefaultSocksProxy is the default Tor SOCKS endpoint. Tools inherit this
-// unless the workspace disables privacy or points elsewhere.
+// unless the workspace, or one enclosing it, disables privacy or points
+// elsewhere.
 const DefaultSocksProxy = "socks5h://127.0.0.1:9050"
 
+// offlineProxy is what tools get as their proxy while offline: an address
+// no connection can be made to, so tools that honour proxy variables fail
+// instead of going direct.
+const offlineProxy = "socks5h://127.0.0.1:0"
+
+var errOffline = errors.New("network access is disabled (privacy=offline or MKRK_OFFLINE)")
+
 func privacySettings(ctx *context.Context) privacyConfig {
-	cfg := privacyConfig{enabled: true, socks: DefaultSocksProxy}
+	cfg := privacyConfig{enabled: true, socks: DefaultSocksProxy, isolate: true}
 
 	if override := os.Getenv("MKRK_SOCKS"); override != "" {
 		cfg.socks = override
 	}
+	if v := os.Getenv("MKRK_OFFLINE"); v != "" && v != "0" {
+		cfg.offline = true
+	}
 
 	if ctx != nil && ctx.Workspace != nil && ctx.Workspace.Db != nil {
-		if v, _ := ctx.Workspace.Db.GetConfig("privacy"); v != nil && *v == "off" {
+		if v, _ := ctx.Workspace.Config("privacy"); v != nil && *v == "off" {
 			cfg.enabled = false
 		}
-		if v, _ := ctx.Workspace.Db.GetConfig("socks_proxy"); v != nil && *v != "" {
+		if v, _ := ctx.Workspace.Config("socks_proxy"); v != nil && *v != "" {
 			cfg.socks = *v
 		}
+		if v, _ := ctx.Workspace.Config("tor_isolation"); v != nil && *v == "off" {
+			cfg.isolate = false
+		}
+		if v, _ := ctx.Workspace.Config("tor_max_requests"); v != nil {
+			n, err := strconv.Atoi(*v)
+			if err != nil || n < 0 {
+				cfg.err = fmt.Errorf("workspace setting tor_max_requests: expected a count of zero or more, got %q", *v)
+			}
+			cfg.maxPerCircuit = n
+		}
+		if v, _ := ctx.Workspace.Config("proxy_rules"); v != nil {
+			cfg.rules = strings.TrimSpace(*v)
+		}
+		// Offline in any enclosing workspace holds, so a nested one cannot
+		// reopen the network by setting privacy itself.
+		for w := ctx.Workspace; w != nil; w = w.Parent {
+			if w.Db == nil {
+				continue
+			}
+			if v, _ := w.Db.GetConfig("privacy"); v != nil && *v == "offline" {
+				cfg.offline = true
+			}
+		}
 	}
 
 	return cfg
 }
 
 func announcePrivacy(p privacyConfig) {
+	if p.offline {
+		fmt.Fprintln(os.Stderr, "privacy: OFFLINE — tool's proxy variables lead nowhere (tool may not respect this)")
+		return
+	}
+	if p.rules != "" {
+		rest := "go direct"
+		if p.enabled {
+			rest = "go through " + p.socks
+		}
+		fmt.Fprintf(os.Stderr, "privacy: routing by proxy_rules, other hosts %s (tool may not respect this)\n", rest)
+		return
+	}
 	if p.enabled {
 		fmt.Fprintf(os.Stderr, "privacy: routing through %s (tool may not respect this)\n", p.socks)
 		return
@@ -385,8 +703,53 @@ func announcePrivacy(p privacyConfig) {
 	fmt.Fprintln(os.Stderr, "privacy: DISABLED — tool runs with unrestricted network (re-enable via workspace config 'privacy=on')")
 }
 
-func buildEnv(g generator.Generator, ctx *context.Context, p privacyConfig) []string {
-	env := append([]string(nil), os.Environ()...)
+// httpClient returns a client for requests mkrk makes itself. While
+// privacy is on it dials through the SOCKS proxy, and fails rather than
+// falling back to a direct connection. Offline, there is no client.
+func (p privacyConfig) httpClient() (*http.Client, error) {
+	if p.offline {
+		return nil, errOffline
+	}
+	if p.err != nil {
+		return nil, p.err
+	}
+	transport := http.DefaultTransport.(*http.Transport).Clone()
+	if p.chained() {
+		d, err := p.dialer()
+		if err != nil {
+			return nil, err
+		}
+		transport.Proxy = nil
+		transport.DialContext = d.DialContext
+		// A host moving to a fresh circuit must not keep its old
+		// connection.
+		transport.DisableKeepAlives = p.isolate && p.maxPerCircuit > 0
+	} else if p.enabled {
+		proxy, err := url.Parse(p.socks)
+		if err != nil {
+			return nil, fmt.Errorf("invalid SOCKS proxy %q: %w", p.socks, err)
+		}
+		// net/http's SOCKS dialer always leaves name resolution to the
+		// proxy, so socks5h needs no special handling.
+		if proxy.Scheme == "socks5h" {
+			proxy.Scheme = "socks5"
+		}
+		transport.Proxy = http.ProxyURL(proxy)
+		if p.isolate && proxy.Scheme == "socks5" && proxy.User == nil {
+			transport.Proxy = isolatedProxy(proxy, p.maxPerCircuit)
+		}
+	}
+	return &http.Client{Transport: transport, Timeout: time.Minute}, nil
+}
+
+// buildEnv returns the environment a tool runs in, and a release func to
+// call once it has exited.
+func buildEnv(g generator.Generator, ctx *context.Context, p privacyConfig) (env []string, release func(), err error) {
+	if p.err != nil {
+		return nil, nil, p.err
+	}
+	release = func() {}
+	env = append([]string(nil), os.Environ()...)
 	env = appendKV(env, "MKRK_GENERATOR_VERB", g.Verb)
 	env = appendKV(env, "MKRK_GENERATOR_SCOPE", g.Scope.Name)
 	if ctx != nil {
@@ -399,14 +762,70 @@ func buildEnv(g generator.Generator, ctx *context.Context, p privacyConfig) []st
 		if ctx.Workspace != nil {
 			env = appendKV(env, "MKRK_WORKSPACE_ROOT", ctx.Workspace.Root)
 		}
+		// Settings come before the proxy variables, which they cannot
+		// override.
+		if ctx.ProjectDb != nil {
+			if env, err = appendToolEnvSettings(env, ctx); err != nil {
+				return nil, nil, err
+			}
+		}
 	}
-	if p.enabled {
+	if p.offline {
+		for _, key := range []string{"http_proxy", "HTTP_PROXY", "https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY", "MKRK_SOCKS"} {
+			env = appendKV(env, key, offlineProxy)
+		}
+		// No host may bypass the proxy.
+		env = appendKV(env, "no_proxy", "")
+		env = appendKV(env, "NO_PROXY", "")
+		env = appendKV(env, "MKRK_OFFLINE", "1")
+	} else if p.chained() {
+		d, err := p.dialer()
+		if err != nil {
+			return nil, nil, err
+		}
+		relay, err := startToolRelay(d)
+		if err != nil {
+			return nil, nil, err
+		}
+		release = relay.Close
+		for _, key := range []string{"http_proxy", "HTTP_PROXY", "https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY", "MKRK_SOCKS"} {
+			env = appendKV(env, key, relay.URL())
+		}
+		// No host may bypass the chain or the rules.
+		env = appendKV(env, "no_proxy", "")
+		env = appendKV(env, "NO_PROXY", "")
+	} else if p.enabled {
 		for _, key := range []string{"http_proxy", "HTTP_PROXY", "https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY"} {
 			env = appendKV(env, key, p.socks)
 		}
 		env = appendKV(env, "MKRK_SOCKS", p.socks)
 	}
-	return env
+	return env, release, nil
+}
+
+// appendToolEnvSettings adds the project's env.NAME settings to env,
+// filling in the secrets they refer to.
+func appendToolEnvSettings(env []string, ctx *context.Context) ([]string, error) {
+	config, err := ctx.ProjectDb.ListConfig()
+	if err != nil {
+		return nil, err
+	}
+	keys := make([]string, 0, len(config))
+	for k := range config {
+		if strings.HasPrefix(k, toolEnvPrefix) {
+			keys = append(keys, k)
+		}
+	}
+	slices.Sort(keys)
+	store := &secretStore{}
+	for _, k := range keys {
+		value, err := store.expand(config[k])
+		if err != nil {
+			return nil, fmt.Errorf("%s: %w", k, err)
+		}
+		env = appendKV(env, strings.TrimPrefix(k, toolEnvPrefix), value)
+	}
+	return env, nil
 }
 
 func appendKV(env []string, key, value string) []string {
@@ -0,0 +1,192 @@
+package cli
+
+import (
+	"bufio"
+	"bytes"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"regexp"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// maxGrepLine bounds the length of a single line the scanner will accept.
+const maxGrepLine = 1 << 20
+
+func RunGrep(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("grep", flag.ExitOnError)
+	ignoreCase := fs.Bool("i", false, "case-insensitive match")
+	filesOnly := fs.Bool("l", false, "print only references of matching files")
+	count := fs.Bool("c", false, "print match counts per file")
+	raw := fs.Bool("raw", false, "no color or decoration")
+	untracked := fs.Bool("untracked", false, "also search files that are not tracked")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(positional) == 0 {
+		return fmt.Errorf("usage: mkrk grep [-i] [-l] [-c] <pattern> [<reference>...]")
+	}
+
+	expr := positional[0]
+	if *ignoreCase {
+		expr = "(?i)" + expr
+	}
+	re, err := regexp.Compile(expr)
+	if err != nil {
+		return fmt.Errorf("invalid pattern: %w", err)
+	}
+
+	paths, err := grepTargets(ctx, positional[1:])
+	if err != nil {
+		return err
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	matched := 0
+	for _, relPath := range paths {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		if !*untracked {
+			hash, err := trackedHash(ctx, relPath)
+			if err != nil {
+				fmt.Fprintf(os.Stderr, "! %s: %v\n", relPath, err)
+				continue
+			}
+			if file, _ := ctx.ProjectDb.GetFileByHash(hash); file == nil {
+				continue
+			}
+		}
+
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		if !*raw {
+			protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+			ref = colorByProtection(ref, protection)
+		}
+
+		n, err := grepFile(absPath, ref, re, *filesOnly || *count)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "! %s: %v\n", relPath, err)
+			continue
+		}
+		if n == 0 {
+			continue
+		}
+		matched++
+		switch {
+		case *count:
+			fmt.Printf("%s:%d\n", ref, n)
+		case *filesOnly:
+			fmt.Println(ref)
+		}
+	}
+
+	if matched == 0 {
+		return fmt.Errorf("no matches")
+	}
+	return nil
+}
+
+// grepTargets resolves the files to search: the subject, the given
+// references, or every categorized file in the project.
+func grepTargets(ctx *context.Context, refs []string) ([]string, error) {
+	if resolve.HasNarrowSubject(ctx) {
+		return resolve.SubjectRelPaths(ctx)
+	}
+	if len(refs) == 0 {
+		patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+		if err != nil {
+			return nil, err
+		}
+		return walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+	}
+	var all []string
+	for _, raw := range refs {
+		rels, err := resolve.RefRelPaths(ctx, raw)
+		if err != nil {
+			return nil, err
+		}
+		all = append(all, rels...)
+	}
+	return all, nil
+}
+
+// grepFile scans a text file line by line and returns the number of
+// matching lines, printing each as ref:line:text unless quiet. Binary
+// and encrypted files are skipped.
+func grepFile(path, ref string, re *regexp.Regexp, quiet bool) (int, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return 0, err
+	}
+	defer f.Close()
+
+	reader := bufio.NewReaderSize(f, 8192)
+	header, err := reader.Peek(8192)
+	if err != nil && err != io.EOF && err != bufio.ErrBufferFull {
+		return 0, err
+	}
+	if isBinary(header) || bytes.HasPrefix(header, []byte(crypt.Magic)) {
+		return 0, nil
+	}
+
+	scanner := bufio.NewScanner(reader)
+	scanner.Buffer(make([]byte, 64*1024), maxGrepLine)
+	n := 0
+	for line := 1; scanner.Scan(); line++ {
+		text := scanner.Text()
+		if !re.MatchString(text) {
+			continue
+		}
+		n++
+		if !quiet {
+			fmt.Printf("%s:%d:%s\n", ref, line, text)
+		}
+	}
+	return n, scanner.Err()
+}
+
+// colorByProtection tints a reference so matches inside immutable or
+// protected files stand out from editable working material.
+func colorByProtection(ref string, level models.ProtectionLevel) string {
+	switch level {
+	case models.ProtectionImmutable:
+		return "\033[36m" + ref + "\033[0m"
+	case models.ProtectionProtected:
+		return "\033[33m" + ref + "\033[0m"
+	default:
+		return ref
+	}
+}
+
+// trackedHash returns the hash of the file at relPath from the hash cache
+// when its stat data still matches, as sync does, and hashes it only on a
+// miss, caching the result.
+func trackedHash(ctx *context.Context, relPath string) (string, error) {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	st, err := statFile(absPath)
+	if err != nil {
+		return "", err
+	}
+	if hash, _ := ctx.ProjectDb.CachedHash(filepath.ToSlash(relPath), st.size, st.mtime, st.inode); hash != "" {
+		return hash, nil
+	}
+	hash, err := ctx.HashFile(absPath)
+	if err != nil {
+		return "", err
+	}
+	ctx.ProjectDb.PutCachedHash(filepath.ToSlash(relPath), st.size, st.mtime, st.inode, hash)
+	return hash, nil
+}
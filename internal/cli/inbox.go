@@ -0,0 +1,349 @@
+package cli
+
+import (
+	"bufio"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+	"unicode"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// defaultInboxDir is the workspace-relative staging directory used when
+// the workspace config does not name one.
+const defaultInboxDir = "inbox"
+
+// quarantineDir holds rejected inbox files. It is dot-prefixed so listing
+// and walking skip it.
+const quarantineDir = ".quarantine"
+
+// previewLines caps how much of a text file inbox preview prints.
+const previewLines = 40
+
+const inboxUsage = `usage: mkrk inbox [list]
+       mkrk inbox preview <file>
+       mkrk inbox reject <file> --reason <text>
+       mkrk inbox assign <file>... --project <name> [--category <name>]
+       mkrk inbox assign --all --project <name> [--category <name>]`
+
+func RunInbox(ctx *context.Context, args []string) error {
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	dir, err := inboxDir(ctx)
+	if err != nil {
+		return err
+	}
+
+	sub := "list"
+	if len(args) > 0 {
+		sub, args = args[0], args[1:]
+	}
+
+	switch sub {
+	case "list", "ls":
+		return inboxList(dir)
+	case "preview":
+		if len(args) != 1 {
+			return fmt.Errorf("usage: mkrk inbox preview <file>")
+		}
+		return inboxPreview(dir, args[0])
+	case "reject":
+		return inboxReject(ctx, dir, args)
+	case "assign":
+		return inboxAssign(ctx, dir, args)
+	default:
+		return fmt.Errorf("unknown inbox subcommand: %s\n%s", sub, inboxUsage)
+	}
+}
+
+func inboxDir(ctx *context.Context) (string, error) {
+	rel := defaultInboxDir
+	if v, _ := ctx.Workspace.Db.GetConfig("inbox_dir"); v != nil {
+		rel = *v
+	}
+	dir := filepath.Join(ctx.Workspace.Root, rel)
+	if info, err := os.Stat(dir); err != nil || !info.IsDir() {
+		return "", fmt.Errorf("no inbox at %s (create it or run init --workspace with --inbox)", rel)
+	}
+	return dir, nil
+}
+
+func inboxList(dir string) error {
+	entries, err := walk.WalkAndCollect(dir, []string{"**"})
+	if err != nil {
+		return err
+	}
+	for _, rel := range entries {
+		size := ""
+		if info, err := os.Stat(filepath.Join(dir, rel)); err == nil {
+			size = formatSize(info.Size())
+		}
+		fmt.Printf("%s\t%s\n", rel, size)
+	}
+	if len(entries) == 0 {
+		fmt.Fprintln(os.Stderr, "(inbox empty)")
+	}
+	return nil
+}
+
+// inboxFile resolves a name relative to the inbox and refuses anything
+// that escapes it.
+func inboxFile(dir, name string) (string, string, error) {
+	abs := filepath.Join(dir, filepath.FromSlash(name))
+	rel, err := filepath.Rel(dir, abs)
+	if err != nil || rel == "." || strings.HasPrefix(rel, "..") {
+		return "", "", fmt.Errorf("%s is not in the inbox", name)
+	}
+	info, err := os.Stat(abs)
+	if err != nil {
+		return "", "", fmt.Errorf("%s: not found in inbox", name)
+	}
+	if !info.Mode().IsRegular() {
+		return "", "", fmt.Errorf("%s: not a regular file", name)
+	}
+	return abs, filepath.ToSlash(rel), nil
+}
+
+// inboxPreview prints identifying details and, for text, the first lines
+// with control characters neutralized so terminal escapes in untrusted
+// files cannot act on the reader's terminal.
+func inboxPreview(dir, name string) error {
+	abs, rel, err := inboxFile(dir, name)
+	if err != nil {
+		return err
+	}
+	hash, err := integrity.HashFile(abs)
+	if err != nil {
+		return err
+	}
+
+	f, err := os.Open(abs)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	info, _ := f.Stat()
+
+	reader := bufio.NewReaderSize(f, 8192)
+	header, err := reader.Peek(8192)
+	if err != nil && err != io.EOF && err != bufio.ErrBufferFull {
+		return err
+	}
+
+	fmt.Printf("%s\n", rel)
+	fmt.Printf("  Size: %s\n", formatSize(info.Size()))
+	fmt.Printf("  SHA-256: %s\n", hash)
+	fmt.Printf("  Type: %s\n", http.DetectContentType(header))
+
+	if isBinary(header) {
+		fmt.Println("  (binary file, not displayed)")
+		return nil
+	}
+
+	fmt.Println()
+	scanner := bufio.NewScanner(reader)
+	scanner.Buffer(make([]byte, 64*1024), maxGrepLine)
+	for n := 0; n < previewLines && scanner.Scan(); n++ {
+		fmt.Println(sanitizeLine(scanner.Text()))
+	}
+	if scanner.Scan() {
+		fmt.Println("  ...")
+	}
+	return nil
+}
+
+func sanitizeLine(s string) string {
+	return strings.Map(func(r rune) rune {
+		if r == '\t' {
+			return r
+		}
+		if unicode.IsControl(r) {
+			return '?'
+		}
+		return r
+	}, s)
+}
+
+func inboxReject(ctx *context.Context, dir string, args []string) error {
+	fs := flag.NewFlagSet("inbox reject", flag.ExitOnError)
+	reason := fs.String("reason", "", "why the file is rejected (required)")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 || *reason == "" {
+		return fmt.Errorf("usage: mkrk inbox reject <file> --reason <text>")
+	}
+
+	abs, rel, err := inboxFile(dir, positional[0])
+	if err != nil {
+		return err
+	}
+	hash, err := integrity.HashFile(abs)
+	if err != nil {
+		return err
+	}
+
+	if ctx.DryRun {
+		fmt.Fprintf(os.Stderr, "Would reject %s (move to %s)\n", rel, quarantineDir)
+		return nil
+	}
+
+	dst := filepath.Join(dir, quarantineDir, filepath.FromSlash(rel))
+	if fileExists(dst) {
+		dst += "." + time.Now().UTC().Format("20060102-150405")
+	}
+	if err := os.MkdirAll(filepath.Dir(dst), 0o700); err != nil {
+		return err
+	}
+	if err := moveFile(abs, dst); err != nil {
+		return err
+	}
+
+	user := whoami()
+	detail, _ := json.Marshal(map[string]string{"sha256": hash, "reason": *reason})
+	detailStr := string(detail)
+	ctx.Workspace.Db.InsertAudit("inbox_reject", nil, &user, &detailStr)
+
+	fmt.Fprintf(os.Stderr, "Rejected %s (moved to %s)\n", rel, quarantineDir)
+	return nil
+}
+
+func inboxAssign(ctx *context.Context, dir string, args []string) error {
+	fs := flag.NewFlagSet("inbox assign", flag.ExitOnError)
+	projectName := fs.String("project", "", "destination project (required)")
+	category := fs.String("category", "evidence", "destination category")
+	all := fs.Bool("all", false, "assign every file in the inbox")
+	positional := parseInterspersed(fs, args)
+
+	if *projectName == "" || (*all == (len(positional) > 0)) {
+		return fmt.Errorf("usage: mkrk inbox assign (<file>... | --all) --project <name> [--category <name>]")
+	}
+
+	names := positional
+	if *all {
+		entries, err := walk.WalkAndCollect(dir, []string{"**"})
+		if err != nil {
+			return err
+		}
+		names = entries
+	}
+	if len(names) == 0 {
+		return fmt.Errorf("inbox is empty")
+	}
+
+	proj, err := ctx.Workspace.Db.GetProjectByName(*projectName)
+	if err != nil {
+		return err
+	}
+	if proj == nil {
+		return fmt.Errorf("project %q not found in workspace", *projectName)
+	}
+	pctx, err := context.OpenProjectContext(filepath.Join(ctx.Workspace.Root, proj.Path), proj.Name, ctx.Workspace)
+	if err != nil {
+		return err
+	}
+	defer pctx.Close()
+
+	cat, err := pctx.ProjectDb.GetCategoryByName(*category)
+	if err != nil {
+		return err
+	}
+	if cat == nil || cat.Pattern == nil {
+		return fmt.Errorf("category '%s' not found in project %s", *category, proj.Name)
+	}
+	catDir := models.NameFromPattern(*cat.Pattern)
+	categories, _ := pctx.ProjectDb.ListCategories()
+
+	user := whoami()
+	assigned, failed := 0, 0
+	for _, name := range names {
+		abs, rel, err := inboxFile(dir, name)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %v\n", err)
+			failed++
+			continue
+		}
+		destRel := filepath.ToSlash(filepath.Join(catDir, rel))
+		dst := filepath.Join(pctx.ProjectRoot, filepath.FromSlash(destRel))
+		if fileExists(dst) {
+			fmt.Fprintf(os.Stderr, "  ! %s: %s already exists in %s\n", rel, destRel, proj.Name)
+			failed++
+			continue
+		}
+
+		if ctx.DryRun {
+			fmt.Fprintf(os.Stderr, "  + %s -> %s \033[36m(would assign)\033[0m\n",
+				rel, reference.FormatRef(destRel, proj.Name, pctx.ProjectDb))
+			assigned++
+			continue
+		}
+
+		hash, fp, err := pctx.HashAndFingerprint(abs)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", rel, err)
+			failed++
+			continue
+		}
+		if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+			return err
+		}
+		if err := moveFile(abs, dst); err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", rel, err)
+			failed++
+			continue
+		}
+
+		ref := reference.FormatRef(destRel, proj.Name, pctx.ProjectDb)
+		if existing, _ := pctx.ProjectDb.GetFileByHash(hash); existing == nil {
+			if err := ingestNew(pctx, destRel, ref, hash, fp, nil, categories); err != nil {
+				fmt.Fprintf(os.Stderr, "  ! %s: moved but not tracked: %v\n", ref, err)
+				failed++
+				continue
+			}
+		}
+		indexFile(pctx, destRel, hash)
+
+		detail, _ := json.Marshal(map[string]string{"sha256": hash, "category": *category})
+		detailStr := string(detail)
+		ctx.Workspace.Db.InsertAudit("inbox_assign", &proj.Name, &user, &detailStr)
+
+		fmt.Fprintf(os.Stderr, "  + %s -> %s\n", rel, ref)
+		assigned++
+	}
+
+	verb := "Assigned"
+	if ctx.DryRun {
+		verb = "Dry run: would assign"
+	}
+	fmt.Fprintf(os.Stderr, "%s %d file(s) to %s", verb, assigned, proj.Name)
+	if failed > 0 {
+		fmt.Fprintf(os.Stderr, ", %d failed", failed)
+	}
+	fmt.Fprintln(os.Stderr)
+	if failed > 0 {
+		return fmt.Errorf("%d file(s) could not be assigned", failed)
+	}
+	return nil
+}
+
+// moveFile renames src to dst, falling back to copy and remove when they
+// are on different filesystems.
+func moveFile(src, dst string) error {
+	if err := os.Rename(src, dst); err == nil {
+		return nil
+	}
+	if err := copyFile(src, dst); err != nil {
+		return err
+	}
+	return os.Remove(src)
+}
@@ -7,6 +7,7 @@ import (
 	"path/filepath"
 
 	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/models"
 )
 
@@ -24,6 +25,10 @@ func RunInit(args []string) error {
 	fs := flag.NewFlagSet("init", flag.ExitOnError)
 	workspace := fs.String("workspace", "", "initialize workspace with projects directory")
 	noCategories := fs.Bool("no-categories", false, "skip default categories")
+	inbox := fs.Bool("inbox", false, "create a workspace inbox for staging files")
+	hashAlg := fs.String("hash", string(integrity.SHA256), "hash algorithm for the project's files: sha256, sha512 or blake3")
+	chunking := fs.String("chunking", string(integrity.FixedChunks), "how fingerprints cut files: fixed (64 KiB) or cdc (content-defined)")
+	signingKey := fs.Bool("signing-key", false, "generate the project's ed25519 signing key, sealed under the project passphrase")
 	fs.Parse(args)
 
 	cwd, err := os.Getwd()
@@ -31,18 +36,29 @@ func RunInit(args []string) error {
 		return err
 	}
 
-	if *workspace != "" {
-		return initWorkspace(cwd, *workspace, *noCategories)
-	}
-
 	name := ""
 	if fs.NArg() > 0 {
 		name = fs.Arg(0)
 	}
-	return initProject(cwd, name, *noCategories)
+	if *workspace != "" {
+		return initWorkspace(cwd, name, *workspace, *noCategories, *inbox)
+	}
+	alg, err := integrity.ParseAlgorithm(*hashAlg)
+	if err != nil {
+		return err
+	}
+	chunks, err := integrity.ParseChunking(*chunking)
+	if err != nil {
+		return err
+	}
+	return initProject(cwd, name, *noCategories, integrity.Hasher{Algorithm: alg, Chunking: chunks}, *signingKey, true)
 }
 
-func initProject(cwd, name string, noCategories bool) error {
+// initProject creates a project at cwd, or under the workspace's projects
+// directory when name is given. Clone and push copy another project's
+// structure over the new one, so they pass defaultPipelines false rather
+// than install the workspace defaults first.
+func initProject(cwd, name string, noCategories bool, hasher integrity.Hasher, signingKey, defaultPipelines bool) error {
 	projectDir := cwd
 
 	// If inside a workspace and a name is given, resolve via projects_dir
@@ -77,6 +93,17 @@ func initProject(cwd, name string, noCategories bool) error {
 		return err
 	}
 	defer pdb.Close()
+	if err := pdb.SetConfig(db.HashAlgorithmSetting, string(hasher.Algorithm)); err != nil {
+		return err
+	}
+	if err := pdb.SetConfig(db.ChunkingSetting, string(hasher.Chunking)); err != nil {
+		return err
+	}
+	if signingKey {
+		if _, err := projectSigningKey(pdb, true); err != nil {
+			return fmt.Errorf("signing key: %w", err)
+		}
+	}
 
 	if !noCategories {
 		for _, c := range defaultCategories {
@@ -115,6 +142,16 @@ func initProject(cwd, name string, noCategories bool) error {
 		}
 	}
 	if ws != nil {
+		if defaultPipelines {
+			added, _, attached, err := installDefaultPipelines(pdb, ws.db)
+			if err != nil {
+				ws.db.Close()
+				return fmt.Errorf("install default pipelines: %w", err)
+			}
+			if added > 0 {
+				fmt.Fprintf(os.Stderr, "  %d default pipeline(s) from workspace, %d attachment(s)\n", added, attached)
+			}
+		}
 		ws.db.Close()
 	}
 
@@ -162,7 +199,26 @@ func installToolsDispatch(pdb *db.ProjectDb) error {
 	return nil
 }
 
-func initWorkspace(cwd, projectsDir string, noCategories bool) error {
+// initWorkspace creates a workspace in cwd, or in the named directory.
+// Inside another workspace it is created under that one's projects_dir
+// when named, and registered there as a nested workspace either way.
+func initWorkspace(cwd, name, projectsDir string, noCategories, inbox bool) error {
+	parent := findWorkspace(cwd)
+	if parent != nil {
+		defer parent.db.Close()
+	}
+	if name != "" {
+		if err := models.ValidateScopeName(name); err != nil {
+			return err
+		}
+		cwd = filepath.Join(cwd, name)
+		if parent != nil {
+			if parentDir, _ := parent.db.GetConfig("projects_dir"); parentDir != nil {
+				cwd = filepath.Join(parent.root, *parentDir, name)
+			}
+		}
+	}
+
 	dbPath := filepath.Join(cwd, ".mksp")
 	if fileExists(dbPath) {
 		return fmt.Errorf("workspace already exists in %s", cwd)
@@ -170,6 +226,17 @@ func initWorkspace(cwd, projectsDir string, noCategories bool) error {
 	if fileExists(filepath.Join(cwd, ".mkrk")) {
 		return fmt.Errorf("project already exists in %s", cwd)
 	}
+	if parent != nil {
+		if name == "" {
+			name = filepath.Base(cwd)
+		}
+		if existing, _ := parent.db.GetSubWorkspaceByName(name); existing != nil {
+			return fmt.Errorf("workspace '%s' already registered in %s", name, parent.root)
+		}
+	}
+	if err := os.MkdirAll(cwd, 0o755); err != nil {
+		return err
+	}
 
 	wdb, err := db.CreateWorkspace(dbPath)
 	if err != nil {
@@ -180,6 +247,11 @@ func initWorkspace(cwd, projectsDir string, noCategories bool) error {
 	wdb.SetConfig("projects_dir", projectsDir)
 	os.MkdirAll(filepath.Join(cwd, projectsDir), 0o755)
 
+	if inbox {
+		wdb.SetConfig("inbox_dir", defaultInboxDir)
+		os.MkdirAll(filepath.Join(cwd, defaultInboxDir), 0o755)
+	}
+
 	if !noCategories {
 		for _, c := range defaultCategories {
 			ct := models.CategoryType(c.catType)
@@ -202,6 +274,16 @@ func initWorkspace(cwd, projectsDir string, noCategories bool) error {
 
 	fmt.Fprintf(os.Stderr, "Initialized workspace in %s\n", cwd)
 	fmt.Fprintf(os.Stderr, "  Projects directory: %s\n", projectsDir)
+	if inbox {
+		fmt.Fprintf(os.Stderr, "  Inbox: %s\n", defaultInboxDir)
+	}
+	if parent != nil {
+		rel, _ := filepath.Rel(parent.root, cwd)
+		if _, err := parent.db.RegisterWorkspace(name, filepath.ToSlash(rel)); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "  Registered in %s as :%s\n", parent.root, name)
+	}
 
 	return nil
 }
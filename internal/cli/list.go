@@ -7,7 +7,6 @@ import (
 	"path/filepath"
 
 	"go.foia.dev/muckrake/internal/context"
-	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/models"
 	"go.foia.dev/muckrake/internal/reference"
 	"go.foia.dev/muckrake/internal/resolve"
@@ -16,6 +15,7 @@ import (
 
 func RunList(ctx *context.Context, args []string) error {
 	fs := flag.NewFlagSet("list", flag.ExitOnError)
+	long := fs.Bool("long", false, "show the metadata embedded in each tracked file")
 	fs.Parse(args)
 
 	if ctx.Kind != context.ContextProject {
@@ -31,9 +31,9 @@ func RunList(ctx *context.Context, args []string) error {
 		return listSubjectFiles(ctx, projectName)
 	}
 	if fs.NArg() > 0 {
-		return listRefFiles(ctx, projectName, fs.Args())
+		return listRefFiles(ctx, projectName, fs.Args(), *long)
 	}
-	return listAllFiles(ctx, projectName)
+	return listAllFiles(ctx, projectName, *long)
 }
 
 func listSubjectFiles(ctx *context.Context, projectName string) error {
@@ -47,7 +47,7 @@ func listSubjectFiles(ctx *context.Context, projectName string) error {
 	return nil
 }
 
-func listAllFiles(ctx *context.Context, projectName string) error {
+func listAllFiles(ctx *context.Context, projectName string, long bool) error {
 	patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
 	if err != nil {
 		return err
@@ -59,38 +59,65 @@ func listAllFiles(ctx *context.Context, projectName string) error {
 
 	allFiles, _ := ctx.ProjectDb.ListAllFiles()
 
+	// Classify every entry first, so derived files can name their source
+	// by reference wherever it sits in the listing.
+	type listed struct {
+		ref   string
+		color string
+		file  *models.TrackedFile
+	}
+	rows := make([]listed, 0, len(entries))
+	refsByID := make(map[int64]string)
 	for _, relPath := range entries {
 		absPath := filepath.Join(ctx.ProjectRoot, relPath)
-		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		row := listed{ref: reference.FormatRef(relPath, projectName, ctx.ProjectDb)}
 
-		fp, err := integrity.FingerprintFile(absPath)
+		fp, err := ctx.Fingerprint(absPath)
 		if err != nil {
-			fmt.Printf("\033[31m%s\033[0m\n", ref)
-			fmt.Fprintf(os.Stderr, "! %s: %v\n", ref, err)
+			row.color = "31"
+			fmt.Fprintf(os.Stderr, "! %s: %v\n", row.ref, err)
+			rows = append(rows, row)
 			continue
 		}
 
-		// Exact fingerprint match — file unchanged
 		if file, _ := ctx.ProjectDb.GetFileByFingerprint(fp.ToJSON()); file != nil {
-			fmt.Println(ref)
-			continue
+			// Exact fingerprint match — file unchanged
+			row.file = file
+		} else if hash, _ := ctx.HashFile(absPath); hash != "" {
+			// Hash match — tracked but fingerprint stale
+			row.file, _ = ctx.ProjectDb.GetFileByHash(hash)
+		}
+		if row.file == nil {
+			if match := findPartialMatchFile(allFiles, fp); match != nil {
+				// Partial fingerprint match — file was modified
+				row.color = "33"
+			} else {
+				// No match at all — untracked
+				row.color = "31"
+			}
 		}
+		if row.file != nil && row.file.ID != nil {
+			refsByID[*row.file.ID] = row.ref
+		}
+		rows = append(rows, row)
+	}
 
-		// Hash match — tracked but fingerprint stale
-		hash, _ := integrity.HashFile(absPath)
-		if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil {
-			fmt.Println(ref)
-			continue
+	hashesByID := make(map[int64]string, len(allFiles))
+	for _, f := range allFiles {
+		if f.ID != nil {
+			hashesByID[*f.ID] = f.SHA256
 		}
+	}
 
-		// Partial fingerprint match — file was modified
-		if match := findPartialMatchFile(allFiles, fp); match != nil {
-			fmt.Printf("\033[33m%s\033[0m\n", ref)
+	for _, row := range rows {
+		if row.color != "" {
+			fmt.Printf("\033[%sm%s\033[0m\n", row.color, row.ref)
 			continue
 		}
-
-		// No match at all — untracked
-		fmt.Printf("\033[31m%s\033[0m\n", ref)
+		fmt.Printf("%s%s\n", row.ref, derivedSuffix(ctx, row.file, refsByID, hashesByID))
+		if long {
+			printMetadata(ctx, row.file)
+		}
 	}
 
 	if len(entries) == 0 {
@@ -99,9 +126,35 @@ func listAllFiles(ctx *context.Context, projectName string) error {
 	return nil
 }
 
-func listRefFiles(ctx *context.Context, projectName string, refs []string) error {
+// derivedSuffix describes where a derived file came from, naming the
+// source by reference if it is on disk and by hash otherwise.
+func derivedSuffix(ctx *context.Context, file *models.TrackedFile, refsByID map[int64]string, hashesByID map[int64]string) string {
+	if file == nil || file.ID == nil {
+		return ""
+	}
+	from, _ := ctx.ProjectDb.DerivedFrom(*file.ID)
+	if len(from) == 0 {
+		return ""
+	}
+	d := from[0]
+	source, ok := refsByID[d.SourceID]
+	if !ok {
+		hash := hashesByID[d.SourceID]
+		source = fmt.Sprintf("[%s...]", hash[:min(len(hash), 10)])
+	}
+	suffix := " \033[2m(derived from " + source
+	if d.Via != nil {
+		suffix += " via " + *d.Via
+	}
+	return suffix + ")\033[0m"
+}
+
+func listRefFiles(ctx *context.Context, projectName string, refs []string, long bool) error {
 	for _, raw := range refs {
 		ref, err := reference.ParseReference(raw)
+		if err == nil {
+			ref, err = reference.ExpandSaved(ref, ctx.SavedRefs()...)
+		}
 		if err != nil {
 			return err
 		}
@@ -112,7 +165,7 @@ func listRefFiles(ctx *context.Context, projectName string, refs []string) error
 		}
 
 		if len(ref.Scope) == 0 {
-			return listAllFiles(ctx, projectName)
+			return listAllFiles(ctx, projectName, long)
 		}
 
 		catName := ref.Scope[0].Names[0]
@@ -135,7 +188,25 @@ func listRefFiles(ctx *context.Context, projectName string, refs []string) error
 				}
 			}
 			fmt.Println(reference.FormatRef(relPath, projectName, ctx.ProjectDb))
+			if long {
+				printMetadata(ctx, trackedRecord(ctx, filepath.Join(ctx.ProjectRoot, relPath)))
+			}
+		}
+	}
+	return nil
+}
+
+// trackedRecord returns the record tracking the file's current content,
+// or nil if it has none.
+func trackedRecord(ctx *context.Context, absPath string) *models.TrackedFile {
+	if fp, err := ctx.Fingerprint(absPath); err == nil {
+		if file, _ := ctx.ProjectDb.GetFileByFingerprint(fp.ToJSON()); file != nil {
+			return file
 		}
 	}
+	if hash, err := ctx.HashFile(absPath); err == nil {
+		file, _ := ctx.ProjectDb.GetFileByHash(hash)
+		return file
+	}
 	return nil
 }
@@ -0,0 +1,157 @@
+package cli
+
+import (
+	"errors"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/db"
+)
+
+// RunLock seals the project database under a passphrase. Until it is
+// unlocked, every command that needs the project refuses to run. Like
+// init, it runs before context discovery, since discovery opens the DB.
+func RunLock(args []string) error {
+	fs := flag.NewFlagSet("lock", flag.ExitOnError)
+	fs.Parse(args)
+
+	path, err := projectDbPath()
+	if err != nil {
+		return err
+	}
+	if locked, err := crypt.IsLocked(path); err != nil {
+		return err
+	} else if locked {
+		return fmt.Errorf("project is already locked")
+	}
+
+	passphrase, err := readPassphrase("Lock passphrase: ", true)
+	if err != nil {
+		return err
+	}
+
+	// Record the lock and fold the WAL back into the main file, so the
+	// sealed copy is complete and no plaintext journal is left beside it.
+	pdb, err := db.OpenProject(path)
+	if err != nil {
+		return err
+	}
+	user := whoami()
+	pdb.InsertAudit("lock", nil, &user, nil)
+	if _, err := pdb.DB().Exec("PRAGMA wal_checkpoint(TRUNCATE)"); err != nil {
+		pdb.Close()
+		return fmt.Errorf("checkpoint project db: %w", err)
+	}
+	if err := pdb.Close(); err != nil {
+		return err
+	}
+
+	err = replaceContent(path, func(dst io.Writer, src io.Reader) error {
+		return crypt.Lock(dst, src, passphrase)
+	})
+	if err != nil {
+		return fmt.Errorf("lock project: %w", err)
+	}
+	os.Remove(path + "-wal")
+	os.Remove(path + "-shm")
+
+	fmt.Fprintf(os.Stderr, "Project locked; run mkrk unlock to use it again\n")
+	return nil
+}
+
+// RunUnlock restores a database sealed by RunLock.
+func RunUnlock(args []string) error {
+	fs := flag.NewFlagSet("unlock", flag.ExitOnError)
+	fs.Parse(args)
+
+	path, err := projectDbPath()
+	if err != nil {
+		return err
+	}
+	if locked, err := crypt.IsLocked(path); err != nil {
+		return err
+	} else if !locked {
+		return fmt.Errorf("project is not locked")
+	}
+
+	passphrase, err := readPassphrase("Lock passphrase: ", false)
+	if err != nil {
+		return err
+	}
+	err = replaceContent(path, func(dst io.Writer, src io.Reader) error {
+		r, err := crypt.Unlock(src, passphrase)
+		if err != nil {
+			return err
+		}
+		_, err = io.Copy(dst, r)
+		return err
+	})
+	if errors.Is(err, crypt.ErrAuth) {
+		return fmt.Errorf("wrong passphrase")
+	}
+	if err != nil {
+		return fmt.Errorf("unlock project: %w", err)
+	}
+
+	pdb, err := db.OpenProject(path)
+	if err != nil {
+		return err
+	}
+	defer pdb.Close()
+	user := whoami()
+	pdb.InsertAudit("unlock", nil, &user, nil)
+
+	fmt.Fprintf(os.Stderr, "Project unlocked\n")
+	return nil
+}
+
+// projectDbPath returns the .mkrk of the project containing the working
+// directory.
+func projectDbPath() (string, error) {
+	cwd, err := os.Getwd()
+	if err != nil {
+		return "", err
+	}
+	root := context.FindProjectRoot(cwd)
+	if root == "" {
+		return "", fmt.Errorf("not in a project")
+	}
+	return filepath.Join(root, ".mkrk"), nil
+}
+
+// replaceContent swaps a file's content for transform's output via a
+// temporary file, so a failure leaves the original untouched.
+func replaceContent(path string, transform func(io.Writer, io.Reader) error) error {
+	info, err := os.Stat(path)
+	if err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(path), ".mkrk-rewrite-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+
+	src, err := os.Open(path)
+	if err != nil {
+		tmp.Close()
+		return err
+	}
+	err = transform(tmp, src)
+	src.Close()
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	if err := os.Chmod(tmp.Name(), info.Mode().Perm()); err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), path)
+}
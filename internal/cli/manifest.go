@@ -0,0 +1,322 @@
+package cli
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/signing"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+const manifestUsage = `usage: mkrk manifest export [-o <file> [--gpg [--key <id>]]] [<reference>...]
+       mkrk manifest verify [--signature <file>] <SHA256SUMS>`
+
+// RunManifest exchanges file hashes with people who don't run mkrk, as
+// the SHA256SUMS files sha256sum writes and checks.
+func RunManifest(ctx *context.Context, args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", manifestUsage)
+	}
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	switch args[0] {
+	case "export":
+		return manifestExport(ctx, args[1:])
+	case "verify":
+		return manifestVerify(ctx, args[1:])
+	}
+	return fmt.Errorf("%s", manifestUsage)
+}
+
+// manifestExport writes a SHA256SUMS manifest of the referenced files
+// (every file, without a reference), with paths relative to the project
+// root so `sha256sum -c` can check it there. Only files that are tracked
+// and unchanged are listed; anything else fails the export, since a
+// manifest is a claim about the evidence.
+func manifestExport(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("manifest export", flag.ExitOnError)
+	output := fs.String("output", "", "write the manifest to this file instead of stdout")
+	fs.StringVar(output, "o", "", "shorthand for --output")
+	useGPG := fs.Bool("gpg", false, "write a detached GPG signature to <output>.asc")
+	key := fs.String("key", "", "GPG key ID (default: GPG's default key)")
+	refs := parseInterspersed(fs, args)
+
+	if *useGPG && *output == "" {
+		return fmt.Errorf("--gpg needs --output")
+	}
+	if *key != "" && !*useGPG {
+		return fmt.Errorf("--key needs --gpg")
+	}
+
+	rels, err := manifestTargets(ctx, refs)
+	if err != nil {
+		return err
+	}
+	if len(rels) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+	slices.Sort(rels)
+
+	alg, err := ctx.HashAlgorithm()
+	if err != nil {
+		return err
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	var manifest bytes.Buffer
+	failed := 0
+	for _, relPath := range rels {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		hash, err := ctx.HashFile(absPath)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", ref, err)
+			failed++
+			continue
+		}
+		if file, _ := ctx.ProjectDb.GetFileByHash(hash); file == nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: not tracked, or modified since it was (run verify)\n", ref)
+			failed++
+			continue
+		}
+		sum := hash
+		if alg != integrity.SHA256 {
+			if sum, err = integrity.SHA256.HashFile(absPath); err != nil {
+				fmt.Fprintf(os.Stderr, "  ! %s: %v\n", ref, err)
+				failed++
+				continue
+			}
+		}
+		manifest.WriteString(manifestLine(sum, filepath.ToSlash(relPath)))
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d file(s) could not be listed; no manifest written", failed)
+	}
+
+	data := manifest.Bytes()
+	if *output == "" {
+		os.Stdout.Write(data)
+	} else {
+		if err := os.WriteFile(*output, data, 0o644); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "Wrote %d file(s) to %s\n", len(rels), *output)
+	}
+	if *useGPG {
+		sig, err := signing.Sign(signing.GPG, *key, data)
+		if err != nil {
+			return err
+		}
+		if err := os.WriteFile(*output+".asc", []byte(sig.Data), 0o644); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "Signed with gpg key %s to %s.asc\n", sig.Key, *output)
+	}
+
+	sum := sha256.Sum256(data)
+	user := whoami()
+	detail, _ := json.Marshal(map[string]any{"files": len(rels), "sha256": hex.EncodeToString(sum[:]), "signed": *useGPG})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("manifest-export", nil, &user, &detailStr)
+	return nil
+}
+
+// manifestTargets picks the files to list: the subject's, the references',
+// or every file in the project's categories.
+func manifestTargets(ctx *context.Context, refs []string) ([]string, error) {
+	if resolve.HasNarrowSubject(ctx) {
+		return resolve.SubjectRelPaths(ctx)
+	}
+	if len(refs) == 0 {
+		patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+		if err != nil {
+			return nil, err
+		}
+		return walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+	}
+	var rels []string
+	for _, raw := range refs {
+		paths, err := resolve.RefRelPaths(ctx, raw)
+		if err != nil {
+			return nil, err
+		}
+		for _, p := range paths {
+			if !slices.Contains(rels, p) {
+				rels = append(rels, p)
+			}
+		}
+	}
+	return rels, nil
+}
+
+// manifestLine formats one line as GNU sha256sum does: a name holding a
+// backslash or newline is escaped, and the line marked with a leading
+// backslash.
+func manifestLine(sum, name string) string {
+	if !strings.ContainsAny(name, "\\\n") {
+		return sum + "  " + name + "\n"
+	}
+	name = strings.ReplaceAll(name, "\\", "\\\\")
+	name = strings.ReplaceAll(name, "\n", "\\n")
+	return "\\" + sum + "  " + name + "\n"
+}
+
+type manifestEntry struct {
+	sum, name string
+}
+
+// parseManifest reads a SHA256SUMS file in either the GNU format
+// ("<hash>  <name>", or " *<name>" for binary mode) or the BSD one
+// ("SHA256 (<name>) = <hash>"). Blank lines and comments are skipped.
+func parseManifest(data []byte) ([]manifestEntry, error) {
+	var entries []manifestEntry
+	n := 0
+	for line := range strings.Lines(string(data)) {
+		n++
+		line = strings.TrimRight(line, "\r\n")
+		if strings.TrimSpace(line) == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+		var e manifestEntry
+		if rest, ok := strings.CutPrefix(line, "SHA256 ("); ok {
+			name, sum, ok := strings.Cut(rest, ") = ")
+			if !ok {
+				return nil, fmt.Errorf("line %d: malformed BSD-style entry", n)
+			}
+			e = manifestEntry{sum: sum, name: name}
+		} else {
+			escaped := strings.HasPrefix(line, "\\")
+			line = strings.TrimPrefix(line, "\\")
+			if len(line) < 66 || line[64] != ' ' || (line[65] != ' ' && line[65] != '*') {
+				return nil, fmt.Errorf("line %d: expected '<sha256>  <name>'", n)
+			}
+			e = manifestEntry{sum: line[:64], name: line[66:]}
+			if escaped {
+				e.name = strings.NewReplacer("\\\\", "\\", "\\n", "\n").Replace(e.name)
+			}
+		}
+		e.sum = strings.ToLower(e.sum)
+		if raw, err := hex.DecodeString(e.sum); err != nil || len(raw) != sha256.Size {
+			return nil, fmt.Errorf("line %d: %q is not a SHA-256 hash", n, e.sum)
+		}
+		entries = append(entries, e)
+	}
+	return entries, nil
+}
+
+// manifestVerify checks each entry of a manifest, ours or a partner's,
+// against the project: whether content with that hash is tracked, and
+// what the file at the entry's name, taken relative to the project root,
+// holds. A SHA-256 project finds tracked content by hash wherever it
+// lives; other projects can only check the files the names point at.
+func manifestVerify(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("manifest verify", flag.ExitOnError)
+	sigPath := fs.String("signature", "", "detached GPG signature over the manifest (default: <manifest>.asc, if present)")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 {
+		return fmt.Errorf("%s", manifestUsage)
+	}
+	path := positional[0]
+
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return err
+	}
+	entries, err := parseManifest(data)
+	if err != nil {
+		return fmt.Errorf("%s: %w", path, err)
+	}
+
+	if *sigPath == "" && fileExists(path+".asc") {
+		*sigPath = path + ".asc"
+	}
+	if *sigPath != "" {
+		sig, err := os.ReadFile(*sigPath)
+		if err != nil {
+			return err
+		}
+		desc, err := signing.Verify(signing.Signature{Format: signing.GPG, Data: string(sig)}, data, nil)
+		if err != nil {
+			return fmt.Errorf("%s: %w", *sigPath, err)
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m manifest signed by %s\n", desc)
+	}
+
+	alg, err := ctx.HashAlgorithm()
+	if err != nil {
+		return err
+	}
+
+	var tracked, untracked, differ, missing int
+	for _, e := range entries {
+		// What the project holds at the entry's name: "" when nothing
+		// does (or the name leads outside the project), else its SHA-256.
+		here := ""
+		absPath := filepath.Join(ctx.ProjectRoot, filepath.FromSlash(e.name))
+		if filepath.IsLocal(filepath.FromSlash(e.name)) && fileExists(absPath) {
+			if here, err = integrity.SHA256.HashFile(absPath); err != nil {
+				fmt.Fprintf(os.Stderr, "  ! %s: %v\n", e.name, err)
+				missing++
+				continue
+			}
+		}
+
+		known := false
+		if alg == integrity.SHA256 {
+			file, _ := ctx.ProjectDb.GetFileByHash(e.sum)
+			known = file != nil
+		} else if here == e.sum {
+			hash, err := ctx.HashFile(absPath)
+			if err == nil {
+				file, _ := ctx.ProjectDb.GetFileByHash(hash)
+				known = file != nil
+			}
+		}
+
+		switch {
+		case known && here != "" && here != e.sum:
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s: tracked (the file at this name here holds other content)\n", e.name)
+			tracked++
+		case known && here == "":
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s: tracked, under another name\n", e.name)
+			tracked++
+		case known:
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", e.name)
+			tracked++
+		case here == e.sum:
+			fmt.Fprintf(os.Stderr, "  \033[33m~\033[0m %s: matches the file here, but it is not tracked (run sync)\n", e.name)
+			untracked++
+		case here != "":
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: the file here has SHA-256 [%s...], the manifest [%s...]\n", e.name, here[:10], e.sum[:10])
+			differ++
+		case alg != integrity.SHA256:
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: not found at this name (a %s project can only check manifests by name)\n", e.name, alg)
+			missing++
+		default:
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: not in the project\n", e.name)
+			missing++
+		}
+	}
+
+	fmt.Fprintf(os.Stderr, "Manifest: %d tracked, %d untracked, %d differ, %d missing\n", tracked, untracked, differ, missing)
+	if untracked+differ+missing > 0 {
+		return fmt.Errorf("%d of %d manifest entries are not tracked in this project", untracked+differ+missing, len(entries))
+	}
+	return nil
+}
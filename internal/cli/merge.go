@@ -0,0 +1,246 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// mergePlan tracks how source categories land in the target project.
+type mergePlan struct {
+	src, dst *context.Context
+	// categoryMap maps a source category name to a target category name.
+	categoryMap map[string]string
+	// dirs caches source category name -> (source dir, target dir).
+	dirs map[string][2]string
+	// dry reports missing target categories instead of creating them.
+	dry bool
+}
+
+// RunMerge moves the tracked files of one workspace project into another,
+// carrying their tags, signs and audit history along. Files whose
+// destination already holds different content are left in place and
+// reported as conflicts.
+func RunMerge(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("merge", flag.ExitOnError)
+	categoryMap := make(map[string]string)
+	fs.Func("map", "remap a category: <source>=<target> (repeatable)", func(v string) error {
+		from, to, ok := strings.Cut(v, "=")
+		if !ok || from == "" || to == "" {
+			return fmt.Errorf("expected <source>=<target>, got %q", v)
+		}
+		categoryMap[from] = to
+		return nil
+	})
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	if len(positional) != 2 {
+		return fmt.Errorf("usage: mkrk merge <source-project> <target-project> [--map <source>=<target>]...")
+	}
+	srcName, dstName := positional[0], positional[1]
+	if srcName == dstName {
+		return fmt.Errorf("cannot merge a project into itself")
+	}
+
+	src, err := openWorkspaceProject(ctx, srcName)
+	if err != nil {
+		return err
+	}
+	defer src.Close()
+	dst, err := openWorkspaceProject(ctx, dstName)
+	if err != nil {
+		return err
+	}
+	defer dst.Close()
+
+	alg, err := sharedHashAlgorithm(src, dst)
+	if err != nil {
+		return fmt.Errorf("cannot merge '%s' into '%s': %w", srcName, dstName, err)
+	}
+	plan := &mergePlan{src: src, dst: dst, categoryMap: categoryMap, dirs: make(map[string][2]string), dry: ctx.DryRun}
+
+	patterns, err := walk.CategoryPatterns(src.ProjectDb, nil)
+	if err != nil {
+		return err
+	}
+	entries, err := walk.WalkAndCollect(src.ProjectRoot, patterns)
+	if err != nil {
+		return err
+	}
+
+	user := whoami()
+	moved, duplicates, untracked := 0, 0, 0
+	conflicts := 0
+	for _, relPath := range entries {
+		absPath := filepath.Join(src.ProjectRoot, relPath)
+		hash, err := alg.HashFile(absPath)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			continue
+		}
+		identity, _ := src.Identify(hash, nil)
+		if f, _ := src.ProjectDb.GetFileByHash(identity); f == nil {
+			untracked++
+			continue
+		}
+
+		destRel, err := plan.destPath(relPath)
+		if err != nil {
+			return err
+		}
+		destAbs := filepath.Join(dst.ProjectRoot, destRel)
+
+		if fileExists(destAbs) {
+			existing, err := alg.HashFile(destAbs)
+			if err != nil {
+				return err
+			}
+			if existing != hash && !holdsContent(dst, existing, identity) {
+				conflicts++
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %s already exists in %s with different content\n", relPath, destRel, dstName)
+				continue
+			}
+		}
+
+		if ctx.DryRun {
+			if fileExists(destAbs) {
+				duplicates++
+				fmt.Fprintf(os.Stderr, "  = %s (already in %s)\n", relPath, dstName)
+			} else {
+				moved++
+				fmt.Fprintf(os.Stderr, "  + %s -> %s \033[36m(would move)\033[0m\n", relPath, destRel)
+			}
+			continue
+		}
+
+		result, err := src.ProjectDb.MergeFileInto(dst.ProjectDb, identity)
+		if err != nil {
+			return fmt.Errorf("%s: %w", relPath, err)
+		}
+
+		integrity.ClearImmutable(absPath)
+		if fileExists(destAbs) {
+			os.Remove(absPath)
+			duplicates++
+			fmt.Fprintf(os.Stderr, "  = %s (already in %s)\n", relPath, dstName)
+		} else {
+			if err := os.MkdirAll(filepath.Dir(destAbs), 0o755); err != nil {
+				return err
+			}
+			if err := moveFile(absPath, destAbs); err != nil {
+				return fmt.Errorf("%s: merged records but could not move file: %w", relPath, err)
+			}
+			moved++
+			fmt.Fprintf(os.Stderr, "  + %s -> %s\n", relPath, destRel)
+		}
+
+		detail, _ := json.Marshal(map[string]string{"source_project": srcName, "sha256": identity})
+		detailStr := string(detail)
+		dst.ProjectDb.InsertAudit("merge", &result.FileID, &user, &detailStr)
+	}
+
+	if ctx.DryRun {
+		fmt.Fprintf(os.Stderr, "Dry run: would merge %s into %s: %d to move, %d already present, %d conflict(s)\n",
+			srcName, dstName, moved, duplicates, conflicts)
+		return nil
+	}
+
+	failed := applyImmutableFlags(dst)
+
+	detail, _ := json.Marshal(map[string]any{
+		"source":     srcName,
+		"moved":      moved,
+		"duplicates": duplicates,
+		"conflicts":  conflicts,
+	})
+	detailStr := string(detail)
+	ctx.Workspace.Db.InsertAudit("merge", &dstName, &user, &detailStr)
+
+	fmt.Fprintf(os.Stderr, "Merged %s into %s: %d moved, %d already present", srcName, dstName, moved, duplicates)
+	if conflicts > 0 {
+		fmt.Fprintf(os.Stderr, ", %d conflict(s)", conflicts)
+	}
+	fmt.Fprintln(os.Stderr)
+	if untracked > 0 {
+		fmt.Fprintf(os.Stderr, "  %d untracked file(s) left in %s (run sync first)\n", untracked, srcName)
+	}
+	if failed > 0 {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m could not update the immutable flag on %d file(s)\n", failed)
+	}
+	if conflicts > 0 {
+		return fmt.Errorf("%d conflicting file(s) left in %s", conflicts, srcName)
+	}
+	return nil
+}
+
+// destPath maps a source-relative path to its target-relative path,
+// creating the target category from the source definition if it is missing.
+func (m *mergePlan) destPath(relPath string) (string, error) {
+	cat, err := m.src.ProjectDb.MatchCategory(relPath)
+	if err != nil {
+		return "", err
+	}
+	if cat == nil || cat.Pattern == nil {
+		return relPath, nil
+	}
+
+	dirs, ok := m.dirs[cat.Name]
+	if !ok {
+		target, err := m.targetCategory(cat)
+		if err != nil {
+			return "", err
+		}
+		dirs = [2]string{models.NameFromPattern(*cat.Pattern), models.NameFromPattern(*target.Pattern)}
+		m.dirs[cat.Name] = dirs
+	}
+
+	rest, err := filepath.Rel(filepath.FromSlash(dirs[0]), filepath.FromSlash(relPath))
+	if err != nil || strings.HasPrefix(rest, "..") {
+		return relPath, nil
+	}
+	return filepath.ToSlash(filepath.Join(dirs[1], rest)), nil
+}
+
+func (m *mergePlan) targetCategory(cat *models.Scope) (*models.Scope, error) {
+	name := cat.Name
+	if to, ok := m.categoryMap[name]; ok {
+		name = to
+	}
+	target, err := m.dst.ProjectDb.GetCategoryByName(name)
+	if err != nil {
+		return nil, err
+	}
+	if target != nil && target.Pattern != nil {
+		return target, nil
+	}
+	if name != cat.Name {
+		return nil, fmt.Errorf("category '%s' not found in target project", name)
+	}
+
+	def := *cat
+	def.ID = nil
+	if m.dry {
+		fmt.Fprintf(os.Stderr, "  Would create category '%s' in target\n", cat.Name)
+		return &def, nil
+	}
+	id, err := m.dst.ProjectDb.InsertScope(&def)
+	if err != nil {
+		return nil, fmt.Errorf("create category %s: %w", cat.Name, err)
+	}
+	if level, _ := m.src.ProjectDb.GetPolicyForScope(*cat.ID); level != nil {
+		m.dst.ProjectDb.InsertScopePolicy(id, *level)
+	}
+	fmt.Fprintf(os.Stderr, "  Created category '%s' in target\n", cat.Name)
+	return &def, nil
+}
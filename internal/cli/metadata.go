@@ -0,0 +1,44 @@
+package cli
+
+import (
+	"fmt"
+	"path/filepath"
+	"sort"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/metadata"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// recordMetadata extracts the metadata embedded in the content at relPath
+// and records it for fileID, replacing what was recorded for earlier
+// content. Encrypted files get none: their metadata would sit in the
+// database in the clear.
+func recordMetadata(ctx *context.Context, fileID int64, relPath string) {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	md := map[string]string{}
+	if enc, err := crypt.IsEncrypted(absPath); err == nil && !enc {
+		if extracted, err := metadata.Extract(absPath); err == nil {
+			md = extracted
+		}
+	}
+	ctx.ProjectDb.SetFileMetadata(fileID, md)
+}
+
+// printMetadata lists a tracked file's metadata under its reference, for
+// list --long.
+func printMetadata(ctx *context.Context, file *models.TrackedFile) {
+	if file == nil || file.ID == nil {
+		return
+	}
+	md, _ := ctx.ProjectDb.GetFileMetadata(*file.ID)
+	keys := make([]string, 0, len(md))
+	for k := range md {
+		keys = append(keys, k)
+	}
+	sort.Strings(keys)
+	for _, k := range keys {
+		fmt.Printf("    %s: %s\n", k, md[k])
+	}
+}
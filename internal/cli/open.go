@@ -3,15 +3,160 @@ package cli
 import (
 	"flag"
 	"fmt"
+	"io"
+	"net/http"
 	"os"
 	"os/exec"
+	"path/filepath"
+	"strings"
 
 	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/models"
 	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/storage"
 )
 
+// RunOpen launches the viewer registered for the file's mime type, falling
+// back to $PAGER. Files in immutable categories are only opened with a
+// viewer registered as read-only.
 func RunOpen(ctx *context.Context, args []string) error {
-	return runExternalViewer(ctx, args, "open", envOrDefault("PAGER", "less"))
+	fs := flag.NewFlagSet("open", flag.ExitOnError)
+	fs.Parse(args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	paths, err := singleFileTarget(ctx, fs.Args(), "open")
+	if err != nil {
+		return err
+	}
+	if len(paths) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+	if len(paths) > 1 {
+		return fmt.Errorf("reference matched %d files, expected 1", len(paths))
+	}
+	absPath := paths[0]
+	relPath, err := filepath.Rel(ctx.ProjectRoot, absPath)
+	if err != nil {
+		return err
+	}
+	relPath = filepath.ToSlash(relPath)
+
+	// Content in remote storage is fetched first. Encrypted files are
+	// opened from a private plaintext copy that is removed once the viewer
+	// exits.
+	viewPath, err := localContent(ctx, absPath)
+	if err != nil {
+		return err
+	}
+	if encrypted, _ := crypt.IsEncrypted(viewPath); encrypted {
+		tmpDir, err := decryptForViewing(ctx, viewPath, filepath.Base(absPath))
+		if err != nil {
+			return err
+		}
+		defer os.RemoveAll(tmpDir)
+		viewPath = filepath.Join(tmpDir, filepath.Base(absPath))
+	}
+
+	mimeType, err := fileMimeType(ctx, viewPath)
+	if err != nil {
+		return err
+	}
+
+	categories, _ := ctx.ProjectDb.ListCategories()
+	var scopeIDs []int64
+	for _, c := range matchingCategories(relPath, categories) {
+		scopeIDs = append(scopeIDs, *c.ID)
+	}
+	viewer, err := ctx.ProjectDb.ResolveOpenViewer(scopeIDs, mimeType)
+	if err != nil {
+		return err
+	}
+
+	command, readOnly := envOrDefault("PAGER", "less"), false
+	if viewer != nil {
+		command, readOnly = viewer.Command, viewer.ReadOnly
+	}
+
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	if protection == models.ProtectionImmutable && !readOnly {
+		return fmt.Errorf("%s is immutable; refusing to open it with '%s' (register a read-only viewer: mkrk viewer set %s <command> --read-only)",
+			relPath, command, mimeType)
+	}
+
+	argv := strings.Fields(command)
+	if len(argv) == 0 {
+		return fmt.Errorf("empty viewer command for %s", mimeType)
+	}
+	substituted := false
+	for i, a := range argv[1:] {
+		if strings.Contains(a, "{}") {
+			argv[i+1] = strings.ReplaceAll(a, "{}", viewPath)
+			substituted = true
+		}
+	}
+	if !substituted {
+		argv = append(argv, viewPath)
+	}
+
+	cmd := exec.Command(argv[0], argv[1:]...)
+	cmd.Stdin = os.Stdin
+	cmd.Stdout = os.Stdout
+	cmd.Stderr = os.Stderr
+	return cmd.Run()
+}
+
+// decryptForViewing writes the plaintext of an encrypted file, read-only
+// and named name, into a new private temporary directory and returns the
+// directory.
+func decryptForViewing(ctx *context.Context, path, name string) (string, error) {
+	r, f, _, err := openPlaintext(path, &lazyKey{ctx: ctx})
+	if err != nil {
+		return "", err
+	}
+	defer f.Close()
+
+	dir, err := os.MkdirTemp("", "mkrk-open-")
+	if err != nil {
+		return "", err
+	}
+	out, err := os.OpenFile(filepath.Join(dir, name), os.O_CREATE|os.O_EXCL|os.O_WRONLY, 0o400)
+	if err == nil {
+		_, err = io.Copy(out, r)
+		if closeErr := out.Close(); err == nil {
+			err = closeErr
+		}
+	}
+	if err != nil {
+		os.RemoveAll(dir)
+		return "", err
+	}
+	return dir, nil
+}
+
+// fileMimeType returns the recorded mime type of a tracked file, or sniffs
+// it from the file's first bytes.
+func fileMimeType(ctx *context.Context, absPath string) (string, error) {
+	if hash, err := ctx.HashFile(absPath); err == nil {
+		if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil && file.MimeType != nil {
+			return *file.MimeType, nil
+		}
+	}
+	f, err := os.Open(absPath)
+	if err != nil {
+		return "", err
+	}
+	defer f.Close()
+	header := make([]byte, 512)
+	n, err := f.Read(header)
+	if err != nil && err != io.EOF {
+		return "", err
+	}
+	mimeType, _, _ := strings.Cut(http.DetectContentType(header[:n]), ";")
+	return strings.TrimSpace(mimeType), nil
 }
 
 func RunEdit(ctx *context.Context, args []string) error {
@@ -37,6 +182,13 @@ func runExternalViewer(ctx *context.Context, args []string, action, defaultCmd s
 		return fmt.Errorf("reference matched %d files, expected 1", len(paths))
 	}
 
+	if encrypted, _ := crypt.IsEncrypted(paths[0]); encrypted {
+		return fmt.Errorf("%s is encrypted; use mkrk open to view it", paths[0])
+	}
+	if _, isStub := storage.ReadStub(paths[0]); isStub {
+		return fmt.Errorf("%s is in remote storage; use mkrk open to view it, or mkrk storage fetch to bring it back", paths[0])
+	}
+
 	cmd := exec.Command(defaultCmd, paths[0])
 	cmd.Stdin = os.Stdin
 	cmd.Stdout = os.Stdout
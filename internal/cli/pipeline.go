@@ -4,25 +4,42 @@ import (
 	"encoding/json"
 	"flag"
 	"fmt"
+	"maps"
 	"os"
+	"slices"
 	"strings"
 
 	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
 	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
 )
 
+const pipelineUsage = `usage: mkrk pipeline [--remove] <name> [--states draft,review,published] [--attach <reference>]... [--workspace]
+       mkrk pipeline push-defaults
+       mkrk pipeline --workspace`
+
 func RunPipeline(ctx *context.Context, args []string) error {
+	if len(args) > 0 && args[0] == "push-defaults" {
+		if ctx.Kind != context.ContextProject {
+			return fmt.Errorf("not in a project")
+		}
+		return pushDefaultPipelines(ctx)
+	}
+
 	fs := flag.NewFlagSet("pipeline", flag.ExitOnError)
 	remove := fs.Bool("remove", false, "remove pipeline")
 	fs.BoolVar(remove, "r", false, "shorthand for --remove")
 	states := fs.String("states", "", "comma-separated state names (e.g., draft,review,published)")
 	transitions := fs.String("transitions", "", "JSON transitions (optional, defaults to linear)")
+	var attach stringList
+	fs.Var(&attach, "attach", "attach the new pipeline to files matching a reference (repeatable)")
 
 	name, flagArgs := extractName(args)
 	fs.Parse(flagArgs)
 
 	if name == "" {
-		return fmt.Errorf("usage: mkrk pipeline [--remove] <name> [--states draft,review,published]")
+		return fmt.Errorf("%s", pipelineUsage)
 	}
 	if ctx.Kind != context.ContextProject {
 		return fmt.Errorf("not in a project")
@@ -31,12 +48,102 @@ func RunPipeline(ctx *context.Context, args []string) error {
 	if *remove {
 		return removePipeline(ctx, name)
 	}
-	return createPipeline(ctx, name, *states, *transitions)
+	return createPipeline(ctx, name, *states, *transitions, attach)
+}
+
+// RunWorkspacePipeline runs a pipeline command with --workspace against the
+// workspace's default pipelines, which new projects inherit on init and
+// existing ones take with push-defaults. Without a name it lists them.
+func RunWorkspacePipeline(args []string) error {
+	fs := flag.NewFlagSet("pipeline", flag.ExitOnError)
+	remove := fs.Bool("remove", false, "remove default pipeline")
+	fs.BoolVar(remove, "r", false, "shorthand for --remove")
+	states := fs.String("states", "", "comma-separated state names (e.g., draft,review,published)")
+	transitions := fs.String("transitions", "", "JSON transitions (optional, defaults to linear)")
+	var attach stringList
+	fs.Var(&attach, "attach", "attach the pipeline to files matching a reference in every project (repeatable)")
+
+	args = slices.DeleteFunc(slices.Clone(args), func(a string) bool { return a == "--workspace" })
+	name, flagArgs := extractName(args)
+	fs.Parse(flagArgs)
+
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	defer ctx.Close()
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	wdb := ctx.Workspace.Db
+
+	switch {
+	case name == "":
+		return listDefaultPipelines(wdb)
+	case *remove:
+		n, err := wdb.RemoveDefaultPipeline(name)
+		if err != nil {
+			return err
+		}
+		if n == 0 {
+			return fmt.Errorf("no default pipeline '%s'", name)
+		}
+		fmt.Fprintf(os.Stderr, "Removed default pipeline '%s'\n", name)
+		return nil
+	}
+
+	if *states != "" {
+		pl, err := buildPipeline(name, *states, *transitions)
+		if err != nil {
+			return err
+		}
+		if err := wdb.SetDefaultPipeline(pl); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "Set default pipeline '%s'\n", name)
+		fmt.Fprintf(os.Stderr, "  States: %s\n", strings.Join(pl.States, " -> "))
+	} else if len(attach) == 0 {
+		return fmt.Errorf("--states or --attach required")
+	}
+	for _, raw := range attach {
+		if _, err := reference.ParseReference(raw); err != nil {
+			return err
+		}
+		if err := wdb.AttachDefaultPipeline(name, raw); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "  Attaches to %s\n", raw)
+	}
+	return nil
+}
+
+func listDefaultPipelines(wdb *db.WorkspaceDb) error {
+	pipelines, err := wdb.ListDefaultPipelines()
+	if err != nil {
+		return err
+	}
+	if len(pipelines) == 0 {
+		fmt.Println("(no default pipelines)")
+	}
+	for _, pl := range pipelines {
+		fmt.Printf("%s: %s\n", pl.Name, strings.Join(pl.States, " -> "))
+		refs, _ := wdb.DefaultPipelineAttachments(*pl.ID)
+		for _, ref := range refs {
+			fmt.Printf("  attaches to %s\n", ref)
+		}
+	}
+	return nil
 }
 
-func createPipeline(ctx *context.Context, name, statesStr, transitionsJSON string) error {
+// buildPipeline makes a pipeline from its states and optional transitions
+// JSON, which default to linear.
+func buildPipeline(name, statesStr, transitionsJSON string) (*models.Pipeline, error) {
 	if statesStr == "" {
-		return fmt.Errorf("--states required when creating a pipeline")
+		return nil, fmt.Errorf("--states required when creating a pipeline")
 	}
 
 	stateList := strings.Split(statesStr, ",")
@@ -51,15 +158,28 @@ func createPipeline(ctx *context.Context, name, statesStr, transitionsJSON strin
 
 	if transitionsJSON != "" {
 		if err := parseTransitions(transitionsJSON, pl); err != nil {
-			return err
+			return nil, err
 		}
 	} else {
 		pl.Transitions = models.DefaultTransitions(stateList)
 	}
 
 	if err := pl.Validate(); err != nil {
+		return nil, err
+	}
+	return pl, nil
+}
+
+func createPipeline(ctx *context.Context, name, statesStr, transitionsJSON string, attach []string) error {
+	pl, err := buildPipeline(name, statesStr, transitionsJSON)
+	if err != nil {
 		return err
 	}
+	for _, raw := range attach {
+		if _, err := reference.ParseReference(raw); err != nil {
+			return err
+		}
+	}
 
 	existing, _ := ctx.ProjectDb.GetPipelineByName(name)
 	if existing != nil {
@@ -72,10 +192,84 @@ func createPipeline(ctx *context.Context, name, statesStr, transitionsJSON strin
 	}
 
 	fmt.Fprintf(os.Stderr, "Created pipeline '%s' (id %d)\n", name, id)
-	fmt.Fprintf(os.Stderr, "  States: %s\n", strings.Join(stateList, " -> "))
+	fmt.Fprintf(os.Stderr, "  States: %s\n", strings.Join(pl.States, " -> "))
+	for _, raw := range attach {
+		if _, err := ctx.ProjectDb.SubscribePipeline(id, raw); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "  Attached to %s (files join on the next sync)\n", raw)
+	}
+	return nil
+}
+
+// pushDefaultPipelines brings the project's pipelines in line with the
+// workspace's defaults: missing ones are created, ones whose states or
+// transitions differ are updated, and missing attachments are added. Signs
+// already made are kept.
+func pushDefaultPipelines(ctx *context.Context) error {
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil {
+		return fmt.Errorf("project is not in a workspace")
+	}
+	added, updated, attached, err := installDefaultPipelines(ctx.ProjectDb, ctx.Workspace.Db)
+	if err != nil {
+		return err
+	}
+	name := ctx.ProjectRoot
+	if ctx.ProjectName != nil {
+		name = *ctx.ProjectName
+	}
+	fmt.Fprintf(os.Stderr, "%s: %d pipeline(s) added, %d updated, %d attachment(s) added\n", name, added, updated, attached)
 	return nil
 }
 
+// installDefaultPipelines copies the workspace's default pipelines and
+// their attachments into a project, updating pipelines that differ.
+func installDefaultPipelines(pdb *db.ProjectDb, wdb *db.WorkspaceDb) (added, updated, attached int, err error) {
+	defaults, err := wdb.ListDefaultPipelines()
+	if err != nil {
+		return 0, 0, 0, err
+	}
+	for _, def := range defaults {
+		pl := &models.Pipeline{Name: def.Name, States: def.States, Transitions: def.Transitions}
+		existing, err := pdb.GetPipelineByName(def.Name)
+		if err != nil {
+			return added, updated, attached, err
+		}
+		var id int64
+		switch {
+		case existing == nil:
+			if id, err = pdb.InsertPipeline(pl); err != nil {
+				return added, updated, attached, err
+			}
+			added++
+		case !slices.Equal(existing.States, def.States) || !maps.EqualFunc(existing.Transitions, def.Transitions, slices.Equal):
+			if err := pdb.UpdatePipeline(pl); err != nil {
+				return added, updated, attached, err
+			}
+			id = *existing.ID
+			updated++
+		default:
+			id = *existing.ID
+		}
+
+		refs, err := wdb.DefaultPipelineAttachments(*def.ID)
+		if err != nil {
+			return added, updated, attached, err
+		}
+		subs, _ := pdb.ListPipelineSubscriptions(id)
+		for _, ref := range refs {
+			if slices.ContainsFunc(subs, func(s models.Subscription) bool { return s.Reference == ref }) {
+				continue
+			}
+			if _, err := pdb.SubscribePipeline(id, ref); err != nil {
+				return added, updated, attached, err
+			}
+			attached++
+		}
+	}
+	return added, updated, attached, nil
+}
+
 func removePipeline(ctx *context.Context, name string) error {
 	removed, err := ctx.ProjectDb.RemovePipeline(name)
 	if err != nil {
@@ -0,0 +1,14 @@
+//go:build !unix
+
+package cli
+
+import "os/exec"
+
+// setProcessGroup does nothing where process groups are not available.
+func setProcessGroup(cmd *exec.Cmd) {}
+
+// killProcessGroup kills cmd's process alone; processes it spawned are
+// left running.
+func killProcessGroup(cmd *exec.Cmd) {
+	cmd.Process.Kill()
+}
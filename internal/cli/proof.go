@@ -0,0 +1,169 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+const proofUsage = `usage: mkrk proof <reference>   print the file's Merkle root
+       mkrk proof <reference> --chunk N | --offset BYTES [--excerpt <file>]
+       mkrk proof check [--root <hex>] <proof.json> <excerpt>`
+
+// proofDocument is what mkrk proof prints: the file's hash and Merkle
+// root, and an inclusion proof when a chunk was asked for. It names no
+// path, so it can be handed to a third party as is.
+type proofDocument struct {
+	HashAlgorithm integrity.Algorithm   `json:"hash_algorithm"`
+	Hash          string                `json:"hash"`
+	Chunking      integrity.Chunking    `json:"chunking"`
+	Root          string                `json:"root"`
+	Chunks        int                   `json:"chunks"`
+	Proof         *integrity.ChunkProof `json:"proof,omitempty"`
+}
+
+// RunProof exports the Merkle root of a tracked file, or a proof that one
+// of its chunks belongs to it, and checks such proofs. Checking needs no
+// project: it is what a third party runs on an excerpt they were sent.
+func RunProof(ctx *context.Context, args []string) error {
+	if len(args) > 0 && args[0] == "check" {
+		return proofCheck(args[1:])
+	}
+
+	fs := flag.NewFlagSet("proof", flag.ExitOnError)
+	chunk := fs.Int("chunk", -1, "prove the chunk with this index")
+	offset := fs.Int64("offset", -1, "prove the chunk holding this byte offset")
+	excerptOut := fs.String("excerpt", "", "write the proven chunk's bytes to this file")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if *chunk >= 0 && *offset >= 0 {
+		return fmt.Errorf("--chunk and --offset are exclusive")
+	}
+	if *excerptOut != "" && *chunk < 0 && *offset < 0 {
+		return fmt.Errorf("--excerpt needs --chunk or --offset")
+	}
+
+	var paths []string
+	if resolve.HasNarrowSubject(ctx) {
+		rels, err := resolve.SubjectRelPaths(ctx)
+		if err != nil {
+			return err
+		}
+		paths = rels
+	} else {
+		if len(positional) != 1 {
+			return fmt.Errorf("%s", proofUsage)
+		}
+		rels, err := resolve.RefRelPaths(ctx, positional[0])
+		if err != nil {
+			return err
+		}
+		paths = rels
+	}
+	if len(paths) != 1 {
+		return fmt.Errorf("a proof covers one file, but %d matched", len(paths))
+	}
+	relPath := paths[0]
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+
+	hasher, err := ctx.Hasher()
+	if err != nil {
+		return err
+	}
+	hash, fp, err := hasher.HashAndFingerprint(absPath)
+	if err != nil {
+		return err
+	}
+	file, _ := ctx.ProjectDb.GetFileByHash(hash)
+	if file == nil || file.ID == nil {
+		return fmt.Errorf("%s: not tracked, or modified since it was (run verify)", relPath)
+	}
+
+	doc := proofDocument{
+		HashAlgorithm: hasher.Algorithm,
+		Hash:          hash,
+		Chunking:      hasher.Chunking,
+		Root:          fp.Root,
+		Chunks:        len(fp.Chunks),
+	}
+	index := *chunk
+	if *offset >= 0 {
+		if index = fp.ChunkAt(*offset); index < 0 {
+			return fmt.Errorf("%s: offset %d is past the end of the file", relPath, *offset)
+		}
+	}
+	if index >= 0 {
+		proof, excerpt, err := hasher.ProveChunk(absPath, index)
+		if err != nil {
+			return fmt.Errorf("%s: %w", relPath, err)
+		}
+		if proof.Root != fp.Root {
+			return fmt.Errorf("%s: changed while the proof was made", relPath)
+		}
+		doc.Proof = proof
+		if *excerptOut != "" {
+			if err := os.WriteFile(*excerptOut, excerpt, 0o644); err != nil {
+				return err
+			}
+			fmt.Fprintf(os.Stderr, "Wrote chunk %d (%d bytes at offset %d) to %s\n", index, proof.Size, proof.Offset, *excerptOut)
+		}
+	}
+
+	user := whoami()
+	detail, _ := json.Marshal(map[string]any{"root": doc.Root, "chunk": index})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("proof", file.ID, &user, &detailStr)
+
+	enc := json.NewEncoder(os.Stdout)
+	enc.SetIndent("", "  ")
+	return enc.Encode(doc)
+}
+
+// proofCheck verifies an excerpt against a proof printed by mkrk proof,
+// and against a root the checker already trusts when given one.
+func proofCheck(args []string) error {
+	fs := flag.NewFlagSet("proof check", flag.ExitOnError)
+	root := fs.String("root", "", "require the proof to be for this Merkle root")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 2 {
+		return fmt.Errorf("%s", proofUsage)
+	}
+
+	raw, err := os.ReadFile(positional[0])
+	if err != nil {
+		return err
+	}
+	var doc proofDocument
+	if err := json.Unmarshal(raw, &doc); err != nil {
+		return fmt.Errorf("%s: %w", positional[0], err)
+	}
+	if doc.Proof == nil {
+		return fmt.Errorf("%s: holds no chunk proof (export one with --chunk or --offset)", positional[0])
+	}
+	if doc.Proof.Root != doc.Root {
+		return fmt.Errorf("%s: proof is for root %s, not the document's %s", positional[0], doc.Proof.Root, doc.Root)
+	}
+	if *root != "" && *root != doc.Root {
+		return fmt.Errorf("proof is for root %s, not %s", doc.Root, *root)
+	}
+	excerpt, err := os.ReadFile(positional[1])
+	if err != nil {
+		return err
+	}
+	if err := doc.Proof.Verify(excerpt); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m excerpt is chunk %d (bytes %d-%d) of the file with %s %s\n",
+		doc.Proof.Index, doc.Proof.Offset, doc.Proof.Offset+doc.Proof.Size, doc.HashAlgorithm, doc.Hash)
+	fmt.Fprintf(os.Stderr, "    root %s\n", doc.Root)
+	return nil
+}
@@ -0,0 +1,479 @@
+package cli
+
+import (
+	"bufio"
+	"bytes"
+	stdcontext "context"
+	"crypto/subtle"
+	"crypto/tls"
+	"encoding/base64"
+	"errors"
+	"fmt"
+	"io"
+	"net"
+	"net/http"
+	"net/url"
+	"path"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+)
+
+// proxyRule routes connections to hosts matching pattern through route,
+// or directly when route is empty.
+type proxyRule struct {
+	pattern string
+	route   []*url.URL
+}
+
+// parseProxyRules reads the proxy_rules setting: entries separated by
+// spaces or semicolons, each a host pattern, '=', and either "direct" or
+// a comma-separated chain of proxies to pass through in order.
+func parseProxyRules(s string) ([]proxyRule, error) {
+	var rules []proxyRule
+	for _, entry := range strings.FieldsFunc(s, func(r rune) bool { return r == ';' || r == ' ' || r == '\n' || r == '\t' }) {
+		pattern, chain, ok := strings.Cut(entry, "=")
+		if !ok || pattern == "" {
+			return nil, fmt.Errorf("proxy_rules: expected <host-pattern>=<proxy>[,<proxy>...] or <host-pattern>=direct, got %q", entry)
+		}
+		if _, err := path.Match(pattern, ""); err != nil {
+			return nil, fmt.Errorf("proxy_rules: bad pattern %q", pattern)
+		}
+		rule := proxyRule{pattern: strings.ToLower(pattern)}
+		if chain != "direct" {
+			route, err := parseProxyChain(chain)
+			if err != nil {
+				return nil, fmt.Errorf("proxy_rules: %w", err)
+			}
+			rule.route = route
+		}
+		rules = append(rules, rule)
+	}
+	return rules, nil
+}
+
+// parseProxyChain reads a comma-separated list of socks5, socks5h, http or
+// https proxy URLs.
+func parseProxyChain(s string) ([]*url.URL, error) {
+	var route []*url.URL
+	for _, raw := range strings.Split(s, ",") {
+		u, err := url.Parse(strings.TrimSpace(raw))
+		if err != nil || u.Host == "" {
+			return nil, fmt.Errorf("invalid proxy %q", raw)
+		}
+		switch u.Scheme {
+		case "socks5", "socks5h", "http", "https":
+		default:
+			return nil, fmt.Errorf("unsupported proxy %q (expected socks5, socks5h, http or https)", raw)
+		}
+		route = append(route, u)
+	}
+	return route, nil
+}
+
+// chained reports whether p needs mkrk's own dialer: per-host rules, or a
+// default route of more than one proxy. Otherwise net/http's proxy
+// support is enough.
+func (p privacyConfig) chained() bool {
+	return p.rules != "" || (p.enabled && strings.Contains(p.socks, ","))
+}
+
+// via returns the proxy or chain requests to target's host go through, or
+// "" if they go direct. target is a URL, or a host name with or without a
+// port.
+func (p privacyConfig) via(target string) string {
+	host := target
+	if u, err := url.Parse(target); err == nil && u.Host != "" {
+		host = u.Hostname()
+	} else if h, _, err := net.SplitHostPort(target); err == nil {
+		host = h
+	}
+	rules, _ := parseProxyRules(p.rules)
+	for _, r := range rules {
+		if ok, _ := path.Match(r.pattern, strings.ToLower(host)); ok {
+			names := make([]string, len(r.route))
+			for i, hop := range r.route {
+				names[i] = hop.Redacted()
+			}
+			return strings.Join(names, ",")
+		}
+	}
+	if p.enabled {
+		return p.socks
+	}
+	return ""
+}
+
+// chainDialer makes connections by the route the rules give each host,
+// falling back to the default route.
+type chainDialer struct {
+	rules         []proxyRule
+	fallback      []*url.URL
+	isolate       bool
+	maxPerCircuit int
+}
+
+func (p privacyConfig) dialer() (*chainDialer, error) {
+	rules, err := parseProxyRules(p.rules)
+	if err != nil {
+		return nil, err
+	}
+	d := &chainDialer{rules: rules, isolate: p.isolate, maxPerCircuit: p.maxPerCircuit}
+	if p.enabled {
+		if d.fallback, err = parseProxyChain(p.socks); err != nil {
+			return nil, err
+		}
+	}
+	return d, nil
+}
+
+func (d *chainDialer) route(host string) []*url.URL {
+	for _, r := range d.rules {
+		if ok, _ := path.Match(r.pattern, strings.ToLower(host)); ok {
+			return r.route
+		}
+	}
+	return d.fallback
+}
+
+// DialContext connects to addr through each proxy on its host's route in
+// turn. A SOCKS proxy last on the route gets the credentials that isolate
+// the host's circuit, unless the route gives its own.
+func (d *chainDialer) DialContext(ctx stdcontext.Context, network, addr string) (net.Conn, error) {
+	host, _, err := net.SplitHostPort(addr)
+	if err != nil {
+		return nil, err
+	}
+	route := d.route(host)
+	var nd net.Dialer
+	if len(route) == 0 {
+		return nd.DialContext(ctx, network, addr)
+	}
+	conn, err := nd.DialContext(ctx, "tcp", proxyAddr(route[0]))
+	if err != nil {
+		return nil, fmt.Errorf("proxy %s: %w", route[0].Host, err)
+	}
+	if deadline, ok := ctx.Deadline(); ok {
+		conn.SetDeadline(deadline)
+	}
+	for i, hop := range route {
+		next := addr
+		if i+1 < len(route) {
+			next = proxyAddr(route[i+1])
+		}
+		if hop.Scheme == "https" {
+			conn = tls.Client(conn, &tls.Config{ServerName: hop.Hostname()})
+		}
+		switch hop.Scheme {
+		case "socks5", "socks5h":
+			user := hop.User
+			if user == nil && d.isolate && i == len(route)-1 {
+				user = circuits.credentials(host, d.maxPerCircuit)
+			}
+			err = socksConnect(conn, next, user)
+		default:
+			err = httpConnect(conn, next, hop.User)
+		}
+		if err != nil {
+			conn.Close()
+			return nil, fmt.Errorf("proxy %s: %w", hop.Host, err)
+		}
+	}
+	conn.SetDeadline(time.Time{})
+	return conn, nil
+}
+
+func proxyAddr(u *url.URL) string {
+	if u.Port() != "" {
+		return u.Host
+	}
+	switch u.Scheme {
+	case "http":
+		return net.JoinHostPort(u.Hostname(), "80")
+	case "https":
+		return net.JoinHostPort(u.Hostname(), "443")
+	}
+	return net.JoinHostPort(u.Hostname(), "1080")
+}
+
+// socksConnect asks the SOCKS5 server on conn to connect to addr, leaving
+// name resolution to it.
+func socksConnect(conn net.Conn, addr string, user *url.Userinfo) error {
+	host, portStr, err := net.SplitHostPort(addr)
+	if err != nil {
+		return err
+	}
+	port, err := strconv.Atoi(portStr)
+	if err != nil {
+		return fmt.Errorf("bad port in %q", addr)
+	}
+
+	methods := []byte{5, 1, 0}
+	if user != nil {
+		methods = []byte{5, 1, 2}
+	}
+	if _, err := conn.Write(methods); err != nil {
+		return err
+	}
+	reply := make([]byte, 2)
+	if _, err := io.ReadFull(conn, reply); err != nil {
+		return err
+	}
+	if reply[0] != 5 || reply[1] != methods[2] {
+		return errors.New("SOCKS server refused the authentication method")
+	}
+	if user != nil {
+		password, _ := user.Password()
+		name := user.Username()
+		if len(name) > 255 || len(password) > 255 {
+			return errors.New("SOCKS credentials too long")
+		}
+		auth := append([]byte{1, byte(len(name))}, name...)
+		auth = append(append(auth, byte(len(password))), password...)
+		if _, err := conn.Write(auth); err != nil {
+			return err
+		}
+		if _, err := io.ReadFull(conn, reply); err != nil {
+			return err
+		}
+		if reply[1] != 0 {
+			return errors.New("SOCKS server rejected the credentials")
+		}
+	}
+
+	req := []byte{5, 1, 0}
+	if ip := net.ParseIP(host); ip != nil && ip.To4() != nil {
+		req = append(append(req, 1), ip.To4()...)
+	} else if ip != nil {
+		req = append(append(req, 4), ip.To16()...)
+	} else {
+		if len(host) > 255 {
+			return fmt.Errorf("host name too long: %s", host)
+		}
+		req = append(append(req, 3, byte(len(host))), host...)
+	}
+	req = append(req, byte(port>>8), byte(port))
+	if _, err := conn.Write(req); err != nil {
+		return err
+	}
+	head := make([]byte, 4)
+	if _, err := io.ReadFull(conn, head); err != nil {
+		return err
+	}
+	if head[1] != 0 {
+		return fmt.Errorf("SOCKS server could not connect to %s (code %d)", addr, head[1])
+	}
+	var skip int
+	switch head[3] {
+	case 1:
+		skip = 4
+	case 4:
+		skip = 16
+	case 3:
+		n := make([]byte, 1)
+		if _, err := io.ReadFull(conn, n); err != nil {
+			return err
+		}
+		skip = int(n[0])
+	default:
+		return errors.New("malformed SOCKS reply")
+	}
+	_, err = io.ReadFull(conn, make([]byte, skip+2))
+	return err
+}
+
+// httpConnect asks the HTTP proxy on conn to open a tunnel to addr.
+func httpConnect(conn net.Conn, addr string, user *url.Userinfo) error {
+	req := "CONNECT " + addr + " HTTP/1.1\r\nHost: " + addr + "\r\n"
+	if user != nil {
+		password, _ := user.Password()
+		req += "Proxy-Authorization: Basic " + base64.StdEncoding.EncodeToString([]byte(user.Username()+":"+password)) + "\r\n"
+	}
+	if _, err := io.WriteString(conn, req+"\r\n"); err != nil {
+		return err
+	}
+	// Whoever speaks next through the tunnel is on this side, so nothing
+	// past the response is buffered and lost.
+	resp, err := http.ReadResponse(bufio.NewReader(conn), &http.Request{Method: http.MethodConnect})
+	if err != nil {
+		return err
+	}
+	resp.Body.Close()
+	if resp.StatusCode/100 != 2 {
+		return fmt.Errorf("CONNECT %s: %s", addr, resp.Status)
+	}
+	return nil
+}
+
+// toolRelay is a SOCKS5 server on the loopback interface that a tool is
+// pointed at when the route needs mkrk's dialer, since proxy variables
+// can name only one proxy and no rules. It accepts only the credentials
+// made for the run, so other local users and processes cannot borrow the
+// chain, and is closed when the run is over.
+type toolRelay struct {
+	ln       net.Listener
+	d        *chainDialer
+	user     string
+	password string
+
+	mu    sync.Mutex
+	conns map[net.Conn]struct{}
+}
+
+func startToolRelay(d *chainDialer) (*toolRelay, error) {
+	ln, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		return nil, err
+	}
+	r := &toolRelay{ln: ln, d: d, user: "mkrk", password: randomHex(16), conns: make(map[net.Conn]struct{})}
+	go func() {
+		for {
+			conn, err := ln.Accept()
+			if err != nil {
+				return
+			}
+			if !r.track(conn) {
+				conn.Close()
+				return
+			}
+			go func() {
+				defer r.untrack(conn)
+				r.serve(conn)
+			}()
+		}
+	}()
+	return r, nil
+}
+
+// URL is the proxy address handed to the tool, credentials included.
+func (r *toolRelay) URL() string {
+	u := url.URL{Scheme: "socks5h", User: url.UserPassword(r.user, r.password), Host: r.ln.Addr().String()}
+	return u.String()
+}
+
+// Close stops the relay and cuts the tunnels still open through it.
+func (r *toolRelay) Close() {
+	r.ln.Close()
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	for conn := range r.conns {
+		conn.Close()
+	}
+	r.conns = nil
+}
+
+func (r *toolRelay) track(conn net.Conn) bool {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	if r.conns == nil {
+		return false
+	}
+	r.conns[conn] = struct{}{}
+	return true
+}
+
+func (r *toolRelay) untrack(conn net.Conn) {
+	conn.Close()
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	delete(r.conns, conn)
+}
+
+// serve handles one SOCKS5 CONNECT, after username/password
+// authentication, dialing the destination through the relay's dialer.
+func (r *toolRelay) serve(conn net.Conn) {
+	head := make([]byte, 2)
+	if _, err := io.ReadFull(conn, head); err != nil || head[0] != 5 {
+		return
+	}
+	methods := make([]byte, head[1])
+	if _, err := io.ReadFull(conn, methods); err != nil {
+		return
+	}
+	if !bytes.Contains(methods, []byte{2}) {
+		conn.Write([]byte{5, 0xff})
+		return
+	}
+	conn.Write([]byte{5, 2})
+	if !r.authenticate(conn) {
+		conn.Write([]byte{1, 1})
+		return
+	}
+	conn.Write([]byte{1, 0})
+
+	req := make([]byte, 4)
+	if _, err := io.ReadFull(conn, req); err != nil {
+		return
+	}
+	var host string
+	switch req[3] {
+	case 1, 4:
+		ip := make([]byte, 4)
+		if req[3] == 4 {
+			ip = make([]byte, 16)
+		}
+		if _, err := io.ReadFull(conn, ip); err != nil {
+			return
+		}
+		host = net.IP(ip).String()
+	case 3:
+		name, err := readSocksString(conn)
+		if err != nil {
+			return
+		}
+		host = string(name)
+	default:
+		return
+	}
+	port := make([]byte, 2)
+	if _, err := io.ReadFull(conn, port); err != nil {
+		return
+	}
+	if req[1] != 1 {
+		conn.Write([]byte{5, 7, 0, 1, 0, 0, 0, 0, 0, 0})
+		return
+	}
+
+	addr := net.JoinHostPort(host, strconv.Itoa(int(port[0])<<8|int(port[1])))
+	upstream, err := r.d.DialContext(stdcontext.Background(), "tcp", addr)
+	if err != nil {
+		conn.Write([]byte{5, 5, 0, 1, 0, 0, 0, 0, 0, 0})
+		return
+	}
+	defer upstream.Close()
+	conn.Write([]byte{5, 0, 0, 1, 0, 0, 0, 0, 0, 0})
+	go io.Copy(upstream, conn)
+	io.Copy(conn, upstream)
+}
+
+// authenticate reads a username/password request (RFC 1929) and checks
+// it against the run's credentials.
+func (r *toolRelay) authenticate(conn net.Conn) bool {
+	ver := make([]byte, 1)
+	if _, err := io.ReadFull(conn, ver); err != nil || ver[0] != 1 {
+		return false
+	}
+	user, err := readSocksString(conn)
+	if err != nil {
+		return false
+	}
+	password, err := readSocksString(conn)
+	if err != nil {
+		return false
+	}
+	userOK := subtle.ConstantTimeCompare(user, []byte(r.user))
+	passwordOK := subtle.ConstantTimeCompare(password, []byte(r.password))
+	return userOK&passwordOK == 1
+}
+
+// readSocksString reads a length-prefixed SOCKS field.
+func readSocksString(conn net.Conn) ([]byte, error) {
+	n := make([]byte, 1)
+	if _, err := io.ReadFull(conn, n); err != nil {
+		return nil, err
+	}
+	b := make([]byte, n[0])
+	_, err := io.ReadFull(conn, b)
+	return b, err
+}
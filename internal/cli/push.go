@@ -0,0 +1,423 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/replica"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// replicaCounts tallies what one push or pull did.
+type replicaCounts struct {
+	copied    int
+	present   int
+	conflicts int
+	untracked int
+}
+
+func (c *replicaCounts) add(o replicaCounts) {
+	c.copied += o.copied
+	c.present += o.present
+	c.conflicts += o.conflicts
+	c.untracked += o.untracked
+}
+
+// RunPush copies this workspace's projects into another workspace.
+func RunPush(ctx *context.Context, args []string) error {
+	return runReplicate(ctx, args, "push")
+}
+
+// RunPull copies another workspace's projects into this one.
+func RunPull(ctx *context.Context, args []string) error {
+	return runReplicate(ctx, args, "pull")
+}
+
+// runReplicate copies tracked files that the receiving side lacks, checks
+// each copy against its content hash before putting it in place, and
+// merges file records, tags, signs and file audit history the same way
+// merge does. Projects missing on the receiving side are created with the
+// sending side's structure. Files that exist on both sides with different
+// content are left alone and reported as conflicts. The other side is a
+// workspace directory, or a replica store reached as a directory or over
+// SSH.
+func runReplicate(ctx *context.Context, args []string, op string) error {
+	fs := flag.NewFlagSet(op, flag.ExitOnError)
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Workspace == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	if len(positional) == 0 {
+		return fmt.Errorf("usage: mkrk %s <workspace-or-store> [<project>...]", op)
+	}
+	target, names := positional[0], positional[1:]
+
+	var replicate func(name string) (replicaCounts, error)
+	if _, ssh := replica.SSHHost(target); !ssh && fileExists(filepath.Join(target, ".mksp")) {
+		remote, err := openRemoteWorkspace(target, ctx.Workspace.Root)
+		if err != nil {
+			return err
+		}
+		defer remote.Close()
+
+		src, dst := ctx, remote
+		if op == "pull" {
+			src, dst = remote, ctx
+		}
+		if len(names) == 0 {
+			if names, err = unarchivedProjects(src); err != nil {
+				return err
+			}
+		}
+		replicate = func(name string) (replicaCounts, error) {
+			return replicateProject(src, dst, name, ctx.DryRun)
+		}
+	} else {
+		store, err := openStore(ctx, target, op)
+		if err != nil {
+			return err
+		}
+		if op == "push" {
+			objects, err := replica.Objects(store)
+			if err != nil {
+				return err
+			}
+			if len(names) == 0 {
+				if names, err = unarchivedProjects(ctx); err != nil {
+					return err
+				}
+			}
+			replicate = func(name string) (replicaCounts, error) {
+				return pushToStore(ctx, store, objects, name)
+			}
+		} else {
+			if len(names) == 0 {
+				if names, err = replica.Projects(store); err != nil {
+					return err
+				}
+			}
+			replicate = func(name string) (replicaCounts, error) {
+				return pullFromStore(ctx, store, name)
+			}
+		}
+	}
+
+	var total replicaCounts
+	failed := 0
+	for _, name := range names {
+		counts, err := replicate(name)
+		total.add(counts)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", name, err)
+			failed++
+		}
+	}
+
+	if ctx.DryRun {
+		fmt.Fprintf(os.Stderr, "Dry run: would %s %d project(s): %d to copy, %d already present", op, len(names)-failed, total.copied, total.present)
+	} else {
+		verb := map[string]string{"push": "Pushed", "pull": "Pulled"}[op]
+		fmt.Fprintf(os.Stderr, "%s %d project(s): %d copied, %d already present", verb, len(names)-failed, total.copied, total.present)
+	}
+	if total.conflicts > 0 {
+		fmt.Fprintf(os.Stderr, ", %d conflict(s)", total.conflicts)
+	}
+	fmt.Fprintln(os.Stderr)
+	if total.untracked > 0 {
+		fmt.Fprintf(os.Stderr, "  %d untracked file(s) not sent (run sync first)\n", total.untracked)
+	}
+
+	if !ctx.DryRun {
+		user := whoami()
+		detail, _ := json.Marshal(map[string]int{
+			"projects":  len(names) - failed,
+			"copied":    total.copied,
+			"present":   total.present,
+			"conflicts": total.conflicts,
+		})
+		detailStr := string(detail)
+		ctx.Workspace.Db.InsertAudit(op, nil, &user, &detailStr)
+	}
+
+	if failed > 0 {
+		return fmt.Errorf("%d project(s) could not be replicated", failed)
+	}
+	if total.conflicts > 0 {
+		return fmt.Errorf("%d conflicting file(s) were not replicated", total.conflicts)
+	}
+	return nil
+}
+
+// unarchivedProjects returns the names of the projects in ctx's workspace
+// that are not archived.
+func unarchivedProjects(ctx *context.Context) ([]string, error) {
+	projects, err := ctx.Workspace.Db.ListProjects()
+	if err != nil {
+		return nil, err
+	}
+	archived, err := ctx.Workspace.Db.ArchivedProjectNames()
+	if err != nil {
+		return nil, err
+	}
+	var names []string
+	for _, p := range projects {
+		if !archived[p.Name] {
+			names = append(names, p.Name)
+		}
+	}
+	return names, nil
+}
+
+// openRemoteWorkspace opens the workspace rooted at path, a local or
+// mounted directory.
+func openRemoteWorkspace(path, localRoot string) (*context.Context, error) {
+	abs, err := filepath.Abs(path)
+	if err != nil {
+		return nil, err
+	}
+	if sameDir(abs, localRoot) {
+		return nil, fmt.Errorf("remote is this workspace")
+	}
+	return context.DiscoverWorkspace(abs)
+}
+
+func sameDir(a, b string) bool {
+	ra, errA := filepath.EvalSymlinks(a)
+	rb, errB := filepath.EvalSymlinks(b)
+	if errA != nil || errB != nil {
+		return a == b
+	}
+	return ra == rb
+}
+
+// replicateProject copies one project from the src workspace to dst.
+func replicateProject(src, dst *context.Context, name string, dry bool) (replicaCounts, error) {
+	var counts replicaCounts
+
+	sp, err := openWorkspaceProject(src, name)
+	if err != nil {
+		return counts, err
+	}
+	defer sp.Close()
+
+	dp, err := openReplica(sp, dst, name, dry)
+	if err != nil {
+		return counts, err
+	}
+	if dp != nil {
+		defer dp.Close()
+	}
+	alg, err := sharedHashAlgorithm(sp, dp)
+	if err != nil {
+		return counts, fmt.Errorf("project '%s': %w", name, err)
+	}
+
+	patterns, err := walk.CategoryPatterns(sp.ProjectDb, nil)
+	if err != nil {
+		return counts, err
+	}
+	entries, err := walk.WalkAndCollect(sp.ProjectRoot, patterns)
+	if err != nil {
+		return counts, err
+	}
+
+	for _, relPath := range entries {
+		absPath := filepath.Join(sp.ProjectRoot, relPath)
+		ref := reference.FormatRef(relPath, name, sp.ProjectDb)
+		if isSymlink(absPath) {
+			continue
+		}
+		hash, err := alg.HashFile(absPath)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+			continue
+		}
+		// An encrypted file is tracked by its plaintext's hash, and copied
+		// and checked as the bytes on disk.
+		identity, _ := sp.Identify(hash, nil)
+		if f, _ := sp.ProjectDb.GetFileByHash(identity); f == nil {
+			counts.untracked++
+			continue
+		}
+
+		exists := false
+		var destAbs string
+		if dp != nil {
+			destAbs = filepath.Join(dp.ProjectRoot, relPath)
+			exists = fileExists(destAbs)
+		}
+		if exists {
+			existing, err := alg.HashFile(destAbs)
+			if err != nil {
+				return counts, err
+			}
+			if existing != hash && !holdsContent(dp, existing, identity) {
+				counts.conflicts++
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: different content on the other side\n", ref)
+				continue
+			}
+		}
+
+		if dry {
+			if exists {
+				counts.present++
+			} else {
+				counts.copied++
+				fmt.Fprintf(os.Stderr, "  + %s \033[36m(would copy)\033[0m\n", ref)
+			}
+			continue
+		}
+
+		if !exists {
+			if err := copyVerified(nil, alg, absPath, destAbs, hash); err != nil {
+				counts.conflicts++
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+				continue
+			}
+		}
+		if _, err := sp.ProjectDb.MergeFileInto(dp.ProjectDb, identity); err != nil {
+			return counts, fmt.Errorf("%s: %w", ref, err)
+		}
+		if exists {
+			counts.present++
+		} else {
+			counts.copied++
+			fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", ref)
+		}
+	}
+
+	if dp != nil && !dry {
+		if failed := applyImmutableFlags(dp); failed > 0 {
+			fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m could not update the immutable flag on %d file(s) in %s\n", failed, name)
+		}
+	}
+	return counts, nil
+}
+
+// openReplica opens the receiving side's copy of a project, creating it
+// with the sending project's structure and settings if it does not exist.
+// Under dry it returns nil for a missing project instead.
+func openReplica(sp, dst *context.Context, name string, dry bool) (*context.Context, error) {
+	if proj, err := dst.Workspace.Db.GetProjectByName(name); err != nil {
+		return nil, err
+	} else if proj != nil {
+		return openWorkspaceProject(dst, name)
+	}
+
+	if dry {
+		fmt.Fprintf(os.Stderr, "  Would create project '%s'\n", name)
+		return nil, nil
+	}
+	hasher, err := sp.Hasher()
+	if err != nil {
+		return nil, err
+	}
+	if err := initProject(dst.Workspace.Root, name, true, hasher, false, false); err != nil {
+		return nil, err
+	}
+	dp, err := openWorkspaceProject(dst, name)
+	if err != nil {
+		return nil, err
+	}
+	if err := sp.ProjectDb.CopyStructure(dp.ProjectDb); err != nil {
+		dp.Close()
+		return nil, fmt.Errorf("copy structure: %w", err)
+	}
+	// Settings include the encryption salt and check, so encrypted files
+	// open on both sides with the same passphrase.
+	config, err := sp.ProjectDb.ListConfig()
+	if err != nil {
+		dp.Close()
+		return nil, err
+	}
+	for k, v := range config {
+		if err := dp.ProjectDb.SetConfig(k, v); err != nil {
+			dp.Close()
+			return nil, err
+		}
+	}
+	fmt.Fprintf(os.Stderr, "  Created project '%s'\n", name)
+	return dp, nil
+}
+
+// sharedHashAlgorithm returns the hash algorithm projects a and b both
+// use; b may be nil. Files cannot move between projects that hash
+// differently, since each records content by hashes the other never makes.
+func sharedHashAlgorithm(a, b *context.Context) (integrity.Algorithm, error) {
+	alg, err := a.HashAlgorithm()
+	if err != nil || b == nil {
+		return alg, err
+	}
+	other, err := b.HashAlgorithm()
+	if err != nil {
+		return "", err
+	}
+	if other != alg {
+		return "", fmt.Errorf("hashes with %s on one side and %s on the other", alg, other)
+	}
+	return alg, nil
+}
+
+// copyVerified copies src to dst through a temporary file, which is only
+// moved into place once its content matches hash under alg. With a ctx,
+// an encrypted copy whose stored form the project recorded for hash
+// matches too.
+func copyVerified(ctx *context.Context, alg integrity.Algorithm, src, dst, hash string) error {
+	info, err := os.Stat(src)
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(dst), ".mkrk-copy-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+
+	in, err := os.Open(src)
+	if err != nil {
+		tmp.Close()
+		return err
+	}
+	_, err = io.Copy(tmp, in)
+	in.Close()
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+
+	got, err := alg.HashFile(tmp.Name())
+	if err != nil {
+		return err
+	}
+	if !holdsContent(ctx, got, hash) {
+		return fmt.Errorf("copy does not match the tracked hash")
+	}
+	if err := os.Chmod(tmp.Name(), info.Mode().Perm()); err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), dst)
+}
+
+// holdsContent reports whether bytes hashing to got hold the content
+// identified by hash: they are that content, or a stored form ctx, which
+// may be nil, recorded for it when encrypting.
+func holdsContent(ctx *context.Context, got, hash string) bool {
+	if got == hash {
+		return true
+	}
+	identity, _ := ctx.Identify(got, nil)
+	return identity == hash
+}
@@ -15,17 +15,33 @@ func RunRead(ctx *context.Context, args []string) error {
 	fs := flag.NewFlagSet("read", flag.ExitOnError)
 	raw := fs.Bool("raw", false, "no color or decoration")
 	pathFlag := fs.Bool("path", false, "show file path before content")
-	fs.Parse(args)
+	version := fs.Int("version", 0, "read revision N of the file (see mkrk versions)")
+	positional := parseInterspersed(fs, args)
 
 	if ctx.Kind != context.ContextProject {
 		return fmt.Errorf("not in a project")
 	}
 
-	paths, err := readTargets(ctx, fs.Args())
+	paths, err := readTargets(ctx, positional)
 	if err != nil {
 		return err
 	}
 
+	key := &lazyKey{ctx: ctx}
+	if *version != 0 {
+		if len(paths) != 1 {
+			return fmt.Errorf("--version needs exactly one file (matched %d)", len(paths))
+		}
+		obj, err := versionPath(ctx, paths[0], *version)
+		if err != nil {
+			return err
+		}
+		if *pathFlag {
+			fmt.Printf("%s (v%d)\n", paths[0], *version)
+		}
+		return dumpContent(obj, !*raw, key)
+	}
+
 	total := 0
 	for _, absPath := range paths {
 		if total > 0 {
@@ -36,7 +52,14 @@ func RunRead(ctx *context.Context, args []string) error {
 		} else if *pathFlag {
 			fmt.Println(absPath)
 		}
-		if err := dumpContent(absPath, !*raw); err != nil {
+		content, err := localContent(ctx, absPath)
+		if err != nil {
+			return err
+		}
+		if !*raw {
+			noteDerivation(ctx, content)
+		}
+		if err := dumpContent(content, !*raw, key); err != nil {
 			return err
 		}
 		total++
@@ -48,6 +71,36 @@ func RunRead(ctx *context.Context, args []string) error {
 	return nil
 }
 
+// noteDerivation tells the reader, on stderr, when the file was produced
+// from another tracked file.
+func noteDerivation(ctx *context.Context, absPath string) {
+	fp, err := ctx.Fingerprint(absPath)
+	if err != nil {
+		return
+	}
+	file, _ := ctx.ProjectDb.GetFileByFingerprint(fp.ToJSON())
+	if file == nil || file.ID == nil {
+		return
+	}
+	from, _ := ctx.ProjectDb.DerivedFrom(*file.ID)
+	if len(from) == 0 {
+		return
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	for _, d := range from {
+		for _, f := range allFiles {
+			if f.ID == nil || *f.ID != d.SourceID {
+				continue
+			}
+			via := ""
+			if d.Via != nil {
+				via = " via " + *d.Via
+			}
+			fmt.Fprintf(os.Stderr, "\033[2mderived from [%s...]%s\033[0m\n", f.SHA256[:min(len(f.SHA256), 10)], via)
+		}
+	}
+}
+
 func readTargets(ctx *context.Context, args []string) ([]string, error) {
 	if resolve.HasNarrowSubject(ctx) {
 		return resolve.SubjectFiles(ctx)
@@ -66,26 +119,23 @@ func readTargets(ctx *context.Context, args []string) ([]string, error) {
 	return all, nil
 }
 
-func dumpContent(path string, colorize bool) error {
-	f, err := os.Open(path)
+// dumpContent writes a file's content to stdout, decrypting it first if
+// it is stored encrypted.
+func dumpContent(path string, colorize bool, key *lazyKey) error {
+	r, f, size, err := openPlaintext(path, key)
 	if err != nil {
 		return err
 	}
 	defer f.Close()
 
-	info, err := f.Stat()
-	if err != nil {
-		return err
-	}
-
-	reader := bufio.NewReaderSize(f, 8192)
-	header, err := reader.Peek(min(int(info.Size()), 8192))
+	reader := bufio.NewReaderSize(r, 8192)
+	header, err := reader.Peek(min(int(size), 8192))
 	if err != nil && err != io.EOF {
 		return err
 	}
 
 	if isBinary(header) {
-		sizeStr := formatSize(info.Size())
+		sizeStr := formatSize(size)
 		if colorize {
 			fmt.Printf("\033[2m(binary file, %s)\033[0m\n", sizeStr)
 		} else {
@@ -0,0 +1,153 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/reference"
+)
+
+const refUsage = `usage: mkrk ref save <name> <reference> [--workspace]
+       mkrk ref list
+       mkrk ref remove <name> [--workspace]`
+
+// savedRefStore is where ref saves and removes references: the project's
+// saved_refs table, or the workspace's.
+type savedRefStore interface {
+	reference.SavedRefs
+	SaveRef(name, ref string) error
+	RemoveSavedRef(name string) (int64, error)
+}
+
+// RunRef manages saved references, used anywhere a reference is accepted
+// as :@name. Like lock, it runs before context discovery: it works once on
+// the project or workspace containing the working directory rather than
+// once per project, and an :@name subject is expanded before discovery.
+func RunRef(args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", refUsage)
+	}
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	defer ctx.Close()
+
+	switch args[0] {
+	case "save":
+		return refSave(ctx, args[1:])
+	case "list", "ls":
+		return refList(ctx)
+	case "remove", "rm":
+		return refRemove(ctx, args[1:])
+	default:
+		return fmt.Errorf("unknown ref subcommand: %s\n%s", args[0], refUsage)
+	}
+}
+
+func refSave(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("ref save", flag.ExitOnError)
+	workspace := fs.Bool("workspace", false, "save in the workspace, for every project")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 2 {
+		return fmt.Errorf("usage: mkrk ref save <name> <reference> [--workspace]")
+	}
+	name, raw := positional[0], positional[1]
+
+	if err := reference.ValidateName(name); err != nil {
+		return err
+	}
+	ref, err := reference.ParseReference(raw)
+	if err != nil {
+		return err
+	}
+	if ref.Kind == reference.KindBarePath {
+		return fmt.Errorf("cannot save a filesystem path as a reference")
+	}
+	store, where, err := refStore(ctx, *workspace)
+	if err != nil {
+		return err
+	}
+	if err := store.SaveRef(name, raw); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Saved :@%s = %s (%s)\n", name, raw, where)
+	return nil
+}
+
+func refList(ctx *context.Context) error {
+	shown := make(map[string]bool)
+	listed := false
+	if ctx.ProjectDb != nil {
+		refs, err := ctx.ProjectDb.ListSavedRefs()
+		if err != nil {
+			return err
+		}
+		for _, r := range refs {
+			fmt.Printf(":@%s = %s\n", r.Name, r.Reference)
+			shown[r.Name] = true
+			listed = true
+		}
+	}
+	if ctx.Workspace != nil {
+		refs, err := ctx.Workspace.Db.ListSavedRefs()
+		if err != nil {
+			return err
+		}
+		for _, r := range refs {
+			note := " (workspace)"
+			if shown[r.Name] {
+				note = " (workspace, shadowed by project)"
+			}
+			fmt.Printf(":@%s = %s%s\n", r.Name, r.Reference, note)
+			listed = true
+		}
+	}
+	if !listed {
+		fmt.Println("(no saved references)")
+	}
+	return nil
+}
+
+func refRemove(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("ref remove", flag.ExitOnError)
+	workspace := fs.Bool("workspace", false, "remove from the workspace")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 {
+		return fmt.Errorf("usage: mkrk ref remove <name> [--workspace]")
+	}
+	store, where, err := refStore(ctx, *workspace)
+	if err != nil {
+		return err
+	}
+	n, err := store.RemoveSavedRef(positional[0])
+	if err != nil {
+		return err
+	}
+	if n == 0 {
+		return fmt.Errorf("no saved reference '@%s' in the %s", positional[0], where)
+	}
+	fmt.Fprintf(os.Stderr, "Removed :@%s (%s)\n", positional[0], where)
+	return nil
+}
+
+// refStore picks the project's saved references, or the workspace's when
+// asked or when there is no project.
+func refStore(ctx *context.Context, workspace bool) (savedRefStore, string, error) {
+	if !workspace && ctx.ProjectDb != nil {
+		return ctx.ProjectDb, "project", nil
+	}
+	if ctx.Workspace != nil && ctx.Workspace.Db != nil {
+		return ctx.Workspace.Db, "workspace", nil
+	}
+	if workspace {
+		return nil, "", fmt.Errorf("not in a workspace")
+	}
+	return nil, "", fmt.Errorf("not in a project or workspace")
+}
@@ -0,0 +1,131 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+const registryUsage = `usage: mkrk registry add [<name>] [<dir>]   default: the workspace here, by its directory name
+       mkrk registry list
+       mkrk registry remove <name>`
+
+// RunRegistry manages the user's registry of workspaces, which
+// mkrk --workspace <name> runs commands in from anywhere. Like secret, it
+// runs before context discovery.
+func RunRegistry(args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", registryUsage)
+	}
+	fs := flag.NewFlagSet("registry", flag.ExitOnError)
+	pos := parseInterspersed(fs, args[1:])
+
+	switch args[0] {
+	case "add":
+		if len(pos) > 2 {
+			return fmt.Errorf("%s", registryUsage)
+		}
+		return registryAdd(pos)
+	case "list", "ls":
+		return registryList()
+	case "remove", "rm":
+		if len(pos) != 1 {
+			return fmt.Errorf("%s", registryUsage)
+		}
+		return registryRemove(pos[0])
+	}
+	return fmt.Errorf("%s", registryUsage)
+}
+
+func registryAdd(pos []string) error {
+	dir := ""
+	if len(pos) == 2 {
+		dir = pos[1]
+	}
+	if dir == "" {
+		cwd, err := os.Getwd()
+		if err != nil {
+			return err
+		}
+		ctx, err := context.DiscoverWorkspace(cwd)
+		if err != nil {
+			return err
+		}
+		dir = ctx.Workspace.Root
+		ctx.Close()
+	}
+	root, err := filepath.Abs(dir)
+	if err != nil {
+		return err
+	}
+	if !fileExists(filepath.Join(root, ".mksp")) {
+		return fmt.Errorf("no workspace in %s", root)
+	}
+
+	name := filepath.Base(root)
+	if len(pos) > 0 {
+		name = pos[0]
+	}
+	if err := models.ValidateScopeName(name); err != nil {
+		return err
+	}
+
+	workspaces, err := context.LoadRegistry()
+	if err != nil {
+		return err
+	}
+	if existing, ok := workspaces[name]; ok && existing != root {
+		return fmt.Errorf("workspace '%s' is already registered at %s", name, existing)
+	}
+	workspaces[name] = root
+	if err := context.SaveRegistry(workspaces); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Registered workspace '%s' at %s\n", name, root)
+	return nil
+}
+
+func registryList() error {
+	workspaces, err := context.LoadRegistry()
+	if err != nil {
+		return err
+	}
+	if len(workspaces) == 0 {
+		fmt.Println("(no registered workspaces)")
+	}
+	names := make([]string, 0, len(workspaces))
+	for name := range workspaces {
+		names = append(names, name)
+	}
+	slices.Sort(names)
+	for _, name := range names {
+		root := workspaces[name]
+		if !fileExists(filepath.Join(root, ".mksp")) {
+			fmt.Printf("%s: %s (\033[31mmissing\033[0m)\n", name, root)
+			continue
+		}
+		fmt.Printf("%s: %s\n", name, root)
+	}
+	return nil
+}
+
+func registryRemove(name string) error {
+	workspaces, err := context.LoadRegistry()
+	if err != nil {
+		return err
+	}
+	if _, ok := workspaces[name]; !ok {
+		return fmt.Errorf("no registered workspace '%s'", name)
+	}
+	delete(workspaces, name)
+	if err := context.SaveRegistry(workspaces); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Removed workspace '%s' from the registry\n", name)
+	return nil
+}
@@ -0,0 +1,393 @@
+package cli
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/replica"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// openStore opens the replica store spec names. An SSH connection goes
+// the way the privacy settings route the host, through mkrk tor connect.
+// A push makes the store if it is new; a pull needs it to exist.
+func openStore(ctx *context.Context, spec, op string) (replica.Transport, error) {
+	proxy := ""
+	if host, ok := replica.SSHHost(spec); ok {
+		privacy := privacySettings(ctx)
+		if privacy.offline {
+			return nil, errOffline
+		}
+		if privacy.err != nil {
+			return nil, privacy.err
+		}
+		if via := privacy.via(host); via != "" {
+			exe, err := os.Executable()
+			if err != nil {
+				return nil, err
+			}
+			proxy = shellQuote(exe) + " tor connect %h %p"
+			fmt.Fprintf(os.Stderr, "privacy: ssh to %s goes through %s\n", host, via)
+		} else {
+			fmt.Fprintf(os.Stderr, "privacy: DISABLED — ssh to %s goes direct\n", host)
+		}
+	}
+	store, err := replica.Open(spec, proxy)
+	if err != nil {
+		return nil, err
+	}
+	if op == "push" && !ctx.DryRun {
+		err = replica.Init(store)
+	} else if op == "pull" {
+		err = replica.Check(store)
+	}
+	return store, err
+}
+
+// pushToStore sends the content of each tracked file in project name that
+// the store does not hold yet, then appends the project's records to its
+// log in the store. objects is what the store holds, and grows as content
+// is sent. A push that would repeat the log's last entry adds nothing.
+func pushToStore(ctx *context.Context, store replica.Transport, objects map[string]bool, name string) (replicaCounts, error) {
+	var counts replicaCounts
+	sp, err := openWorkspaceProject(ctx, name)
+	if err != nil {
+		return counts, err
+	}
+	defer sp.Close()
+	alg, err := sp.HashAlgorithm()
+	if err != nil {
+		return counts, err
+	}
+
+	patterns, err := walk.CategoryPatterns(sp.ProjectDb, nil)
+	if err != nil {
+		return counts, err
+	}
+	entries, err := walk.WalkAndCollect(sp.ProjectRoot, patterns)
+	if err != nil {
+		return counts, err
+	}
+
+	seg := &replica.Segment{}
+	for _, relPath := range entries {
+		absPath := filepath.Join(sp.ProjectRoot, relPath)
+		ref := reference.FormatRef(relPath, name, sp.ProjectDb)
+		if isSymlink(absPath) {
+			continue
+		}
+		hash, err := alg.HashFile(absPath)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+			continue
+		}
+		identity, _ := sp.Identify(hash, nil)
+		if f, _ := sp.ProjectDb.GetFileByHash(identity); f == nil {
+			counts.untracked++
+			continue
+		}
+		seg.Files = append(seg.Files, replica.File{Path: filepath.ToSlash(relPath), Hash: identity, Object: hash})
+
+		if objects[hash] {
+			counts.present++
+			continue
+		}
+		if ctx.DryRun {
+			counts.copied++
+			fmt.Fprintf(os.Stderr, "  + %s \033[36m(would send)\033[0m\n", ref)
+			continue
+		}
+		f, err := os.Open(absPath)
+		if err != nil {
+			return counts, fmt.Errorf("%s: %w", ref, err)
+		}
+		err = store.Put(replica.ObjectName(hash), f)
+		f.Close()
+		if err != nil {
+			return counts, fmt.Errorf("%s: %w", ref, err)
+		}
+		objects[hash] = true
+		counts.copied++
+		fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", ref)
+	}
+
+	_, head, _, err := sp.ProjectDb.VerifyAuditChain()
+	if err != nil {
+		return counts, err
+	}
+	seg.State = segmentState(seg.Files, head)
+	log, err := replica.Log(store, name)
+	if err != nil {
+		return counts, err
+	}
+	if len(log) > 0 {
+		if last, err := replica.ReadSegment(store, name, log[len(log)-1]); err == nil && last.State == seg.State {
+			return counts, nil
+		}
+	}
+	if ctx.DryRun {
+		fmt.Fprintf(os.Stderr, "  Would add %d file record(s) to the log of '%s'\n", len(seg.Files), name)
+		return counts, nil
+	}
+
+	dir, err := os.MkdirTemp("", "mkrk-push-")
+	if err != nil {
+		return counts, err
+	}
+	defer os.RemoveAll(dir)
+	records := filepath.Join(dir, "records.mkrk")
+	if err := exportRecords(sp, seg.Files, records); err != nil {
+		return counts, fmt.Errorf("export records: %w", err)
+	}
+	if seg.Records, err = replica.PutRecords(store, name, records); err != nil {
+		return counts, err
+	}
+	seg.PushedAt = time.Now().UTC().Format(time.RFC3339)
+	if _, err := replica.AppendSegment(store, name, seg); err != nil {
+		return counts, err
+	}
+	return counts, nil
+}
+
+// segmentState summarizes what a push would log: where each file is and
+// what it holds, and the head of the project's audit chain, which moves
+// with every change to the records.
+func segmentState(files []replica.File, auditHead string) string {
+	h := sha256.New()
+	for _, f := range files {
+		fmt.Fprintf(h, "%s\x00%s\x00%s\n", f.Path, f.Hash, f.Object)
+	}
+	fmt.Fprintf(h, "audit %s\n", auditHead)
+	return hex.EncodeToString(h.Sum(nil))
+}
+
+// exportRecords writes a project database at path holding sp's structure
+// and settings and the records of files, with their tags, signs and
+// audit history, for a log entry.
+func exportRecords(sp *context.Context, files []replica.File, path string) error {
+	rdb, err := db.CreateProject(path)
+	if err != nil {
+		return err
+	}
+	defer rdb.Close()
+	if err := sp.ProjectDb.CopyStructure(rdb); err != nil {
+		return err
+	}
+	config, err := sp.ProjectDb.ListConfig()
+	if err != nil {
+		return err
+	}
+	for k, v := range config {
+		if err := rdb.SetConfig(k, v); err != nil {
+			return err
+		}
+	}
+	seen := make(map[string]bool)
+	for _, f := range files {
+		if seen[f.Hash] {
+			continue
+		}
+		seen[f.Hash] = true
+		if _, err := sp.ProjectDb.MergeFileInto(rdb, f.Hash); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// pullFromStore applies each entry of project name's log in the store
+// that this workspace has not applied yet, oldest first: it fetches the
+// files the project lacks, checking each against its hash before putting
+// it in place, and merges the entry's records. An entry with conflicts is
+// left to be applied again once they are resolved.
+func pullFromStore(ctx *context.Context, store replica.Transport, name string) (replicaCounts, error) {
+	var counts replicaCounts
+	if name != filepath.Base(name) || strings.HasPrefix(name, ".") {
+		return counts, fmt.Errorf("invalid project name %q", name)
+	}
+	log, err := replica.Log(store, name)
+	if err != nil {
+		return counts, err
+	}
+	if len(log) == 0 {
+		return counts, fmt.Errorf("no project '%s' in %s", name, store)
+	}
+
+	var dp *context.Context
+	if proj, err := ctx.Workspace.Db.GetProjectByName(name); err != nil {
+		return counts, err
+	} else if proj != nil {
+		if dp, err = openWorkspaceProject(ctx, name); err != nil {
+			return counts, err
+		}
+	}
+	defer func() {
+		if dp != nil {
+			if !ctx.DryRun {
+				if failed := applyImmutableFlags(dp); failed > 0 {
+					fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m could not update the immutable flag on %d file(s) in %s\n", failed, name)
+				}
+			}
+			dp.Close()
+		}
+	}()
+
+	dir, err := os.MkdirTemp("", "mkrk-pull-")
+	if err != nil {
+		return counts, err
+	}
+	defer os.RemoveAll(dir)
+
+	for _, entry := range log {
+		if dp != nil {
+			if applied, err := dp.ProjectDb.SegmentApplied(store.String(), entry); err != nil {
+				return counts, err
+			} else if applied {
+				continue
+			}
+		}
+		seg, err := replica.ReadSegment(store, name, entry)
+		if err != nil {
+			return counts, err
+		}
+		records := filepath.Join(dir, entry+".mkrk")
+		if err := replica.GetRecords(store, name, seg.Records, records); err != nil {
+			return counts, err
+		}
+		rdb, err := db.OpenProject(records)
+		if err != nil {
+			return counts, err
+		}
+		rp := &context.Context{Kind: context.ContextProject, ProjectDb: rdb}
+
+		if dp == nil {
+			if dp, err = openReplica(rp, ctx, name, ctx.DryRun); err != nil {
+				rdb.Close()
+				return counts, err
+			}
+		}
+		c, err := applySegment(store, rp, dp, seg, name, ctx.DryRun)
+		rdb.Close()
+		counts.add(c)
+		if err != nil {
+			return counts, err
+		}
+		if dp == nil {
+			// A dry run over a project it would create: every later entry
+			// would find what this one brought.
+			break
+		}
+		if !ctx.DryRun && c.conflicts == 0 {
+			if err := dp.ProjectDb.MarkSegmentApplied(store.String(), entry, time.Now().UTC().Format(time.RFC3339)); err != nil {
+				return counts, err
+			}
+		}
+	}
+	return counts, nil
+}
+
+// applySegment fetches the files of seg that project dp, which is nil
+// when a dry run would create it, lacks, and merges their records from
+// rp, the entry's records.
+func applySegment(store replica.Transport, rp, dp *context.Context, seg *replica.Segment, name string, dry bool) (replicaCounts, error) {
+	var counts replicaCounts
+	alg, err := sharedHashAlgorithm(rp, dp)
+	if err != nil {
+		return counts, fmt.Errorf("project '%s': %w", name, err)
+	}
+	for _, f := range seg.Files {
+		relPath := filepath.FromSlash(f.Path)
+		if !filepath.IsLocal(relPath) {
+			counts.conflicts++
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %q: not a path inside the project\n", f.Path)
+			continue
+		}
+		ref := reference.FormatRef(relPath, name, rp.ProjectDb)
+		exists := false
+		var destAbs string
+		if dp != nil {
+			destAbs = filepath.Join(dp.ProjectRoot, relPath)
+			exists = fileExists(destAbs)
+		}
+		if exists {
+			existing, err := alg.HashFile(destAbs)
+			if err != nil {
+				return counts, err
+			}
+			if existing != f.Object && !holdsContent(rp, existing, f.Hash) && !holdsContent(dp, existing, f.Hash) {
+				counts.conflicts++
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: different content on the other side\n", ref)
+				continue
+			}
+		}
+
+		if dry {
+			if exists {
+				counts.present++
+			} else {
+				counts.copied++
+				fmt.Fprintf(os.Stderr, "  + %s \033[36m(would copy)\033[0m\n", ref)
+			}
+			continue
+		}
+
+		if !exists {
+			if err := fetchObject(store, alg, f.Object, destAbs); err != nil {
+				counts.conflicts++
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+				continue
+			}
+		}
+		if _, err := rp.ProjectDb.MergeFileInto(dp.ProjectDb, f.Hash); err != nil {
+			return counts, fmt.Errorf("%s: %w", ref, err)
+		}
+		if exists {
+			counts.present++
+		} else {
+			counts.copied++
+			fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", ref)
+		}
+	}
+	return counts, nil
+}
+
+// fetchObject copies the content the store holds under hash to dst
+// through a temporary file, which is only moved into place once its
+// content matches hash under alg.
+func fetchObject(store replica.Transport, alg integrity.Algorithm, hash, dst string) error {
+	if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(dst), ".mkrk-copy-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+	err = store.Get(replica.ObjectName(hash), tmp)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	got, err := alg.HashFile(tmp.Name())
+	if err != nil {
+		return err
+	}
+	if got != hash {
+		return fmt.Errorf("content from the store does not match its hash")
+	}
+	if err := os.Chmod(tmp.Name(), 0o644); err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), dst)
+}
@@ -0,0 +1,323 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"html/template"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// projectReport is the data behind a rendered report, independent of
+// output format.
+type projectReport struct {
+	Project     string
+	GeneratedAt string
+	Categories  []reportCategory
+	Files       []reportFile
+	Pipelines   []reportPipelineFile
+	Outstanding []reportPipelineFile
+	Audit       []reportAudit
+}
+
+type reportCategory struct {
+	Name, Pattern, Protection string
+}
+
+type reportFile struct {
+	Ref, SHA256, Protection, Tags string
+	Tracked                       bool
+}
+
+type reportPipelineFile struct {
+	Ref, Pipeline, State, Next string
+	Missing                    []string
+}
+
+type reportAudit struct {
+	Timestamp, Operation, User, Ref, Detail string
+}
+
+func RunReport(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("report", flag.ExitOnError)
+	html := fs.Bool("html", false, "render HTML instead of Markdown")
+	out := fs.String("out", "", "write the report to this file instead of stdout")
+	auditLimit := fs.Int("audit", 20, "number of recent audit events to include")
+	fs.Parse(args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	r, err := buildReport(ctx, *auditLimit)
+	if err != nil {
+		return err
+	}
+
+	var w io.Writer = os.Stdout
+	if *out != "" {
+		f, err := os.Create(*out)
+		if err != nil {
+			return err
+		}
+		defer f.Close()
+		w = f
+	}
+
+	if *html {
+		err = reportHTML.Execute(w, r)
+	} else {
+		err = writeMarkdownReport(w, r)
+	}
+	if err != nil {
+		return err
+	}
+	if *out != "" {
+		fmt.Fprintf(os.Stderr, "Wrote report to %s\n", *out)
+	}
+	return nil
+}
+
+func buildReport(ctx *context.Context, auditLimit int) (*projectReport, error) {
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	r := &projectReport{
+		Project:     filepath.Base(ctx.ProjectRoot),
+		GeneratedAt: time.Now().UTC().Format(time.RFC3339),
+	}
+	if projectName != "" {
+		r.Project = projectName
+	}
+
+	categories, err := ctx.ProjectDb.ListCategories()
+	if err != nil {
+		return nil, err
+	}
+	for _, c := range categories {
+		rc := reportCategory{Name: c.Name, Protection: string(models.ProtectionEditable)}
+		if c.Pattern != nil {
+			rc.Pattern = *c.Pattern
+		}
+		if c.ID != nil {
+			if level, _ := ctx.ProjectDb.GetPolicyForScope(*c.ID); level != nil {
+				rc.Protection = string(*level)
+			}
+		}
+		r.Categories = append(r.Categories, rc)
+	}
+
+	patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+	if err != nil {
+		return nil, err
+	}
+	entries, err := walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+	if err != nil {
+		return nil, err
+	}
+
+	allPipelines, _ := ctx.ProjectDb.ListPipelines()
+	pipelinesByID := make(map[int64]*models.Pipeline)
+	for i := range allPipelines {
+		pipelinesByID[*allPipelines[i].ID] = &allPipelines[i]
+	}
+
+	refsByID := make(map[int64]string)
+	for _, relPath := range entries {
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		hash, err := ctx.HashFile(filepath.Join(ctx.ProjectRoot, relPath))
+		if err != nil {
+			continue
+		}
+		protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+		rf := reportFile{Ref: ref, SHA256: hash, Protection: string(protection)}
+
+		file, _ := ctx.ProjectDb.GetFileByHash(hash)
+		if file == nil || file.ID == nil {
+			r.Files = append(r.Files, rf)
+			continue
+		}
+		rf.Tracked = true
+		refsByID[*file.ID] = ref
+		tags, _ := ctx.ProjectDb.GetTags(*file.ID)
+		rf.Tags = strings.Join(tags, ", ")
+		r.Files = append(r.Files, rf)
+
+		for _, p := range filePipelines(ctx, file, hash, pipelinesByID) {
+			state, next, missing := pipelineProgress(ctx, file, p, hash)
+			pf := reportPipelineFile{Ref: ref, Pipeline: p.Name, State: state, Next: next}
+			for _, m := range missing {
+				pf.Missing = append(pf.Missing, describeShortfall(m))
+			}
+			r.Pipelines = append(r.Pipelines, pf)
+			if len(pf.Missing) > 0 {
+				r.Outstanding = append(r.Outstanding, pf)
+			}
+		}
+	}
+
+	audit, err := ctx.ProjectDb.ListAudit(auditLimit)
+	if err != nil {
+		return nil, err
+	}
+	for _, a := range audit {
+		ra := reportAudit{Timestamp: a.Timestamp, Operation: a.Operation, Ref: "-", User: "-"}
+		if a.User != nil {
+			ra.User = *a.User
+		}
+		if a.FileID != nil {
+			if ref, ok := refsByID[*a.FileID]; ok {
+				ra.Ref = ref
+			}
+		}
+		if a.Detail != nil {
+			ra.Detail = *a.Detail
+		}
+		r.Audit = append(r.Audit, ra)
+	}
+	return r, nil
+}
+
+// filePipelines returns the pipelines a file is attached to through a
+// subscription, plus any it has been signed in directly.
+func filePipelines(ctx *context.Context, file *models.TrackedFile, hash string, byID map[int64]*models.Pipeline) []*models.Pipeline {
+	var result []*models.Pipeline
+	seen := make(map[int64]bool)
+	attached, _ := ctx.ProjectDb.GetPipelinesForSHA256(hash)
+	for _, p := range attached {
+		if pl, ok := byID[*p.ID]; ok && !seen[*p.ID] {
+			seen[*p.ID] = true
+			result = append(result, pl)
+		}
+	}
+	signs, _ := ctx.ProjectDb.GetSignsForFile(*file.ID)
+	for _, s := range signs {
+		if pl, ok := byID[s.PipelineID]; ok && !seen[s.PipelineID] {
+			seen[s.PipelineID] = true
+			result = append(result, pl)
+		}
+	}
+	return result
+}
+
+func writeMarkdownReport(w io.Writer, r *projectReport) error {
+	cell := func(s string) string {
+		if s == "" {
+			return "-"
+		}
+		return strings.ReplaceAll(s, "|", `\|`)
+	}
+
+	var b strings.Builder
+	fmt.Fprintf(&b, "# Project report: %s\n\n", r.Project)
+	fmt.Fprintf(&b, "Generated %s\n\n", r.GeneratedAt)
+
+	b.WriteString("## Categories\n\n")
+	b.WriteString("| Category | Pattern | Protection |\n|---|---|---|\n")
+	for _, c := range r.Categories {
+		fmt.Fprintf(&b, "| %s | `%s` | %s |\n", cell(c.Name), c.Pattern, c.Protection)
+	}
+
+	fmt.Fprintf(&b, "\n## Files (%d)\n\n", len(r.Files))
+	b.WriteString("| Reference | SHA-256 | Protection | Tags |\n|---|---|---|---|\n")
+	for _, f := range r.Files {
+		hash := "`" + f.SHA256 + "`"
+		if !f.Tracked {
+			hash = "untracked"
+		}
+		fmt.Fprintf(&b, "| %s | %s | %s | %s |\n", cell(f.Ref), hash, f.Protection, cell(f.Tags))
+	}
+
+	b.WriteString("\n## Pipeline states\n\n")
+	if len(r.Pipelines) == 0 {
+		b.WriteString("No files are attached to a pipeline.\n")
+	} else {
+		b.WriteString("| Reference | Pipeline | State |\n|---|---|---|\n")
+		for _, p := range r.Pipelines {
+			fmt.Fprintf(&b, "| %s | %s | %s |\n", cell(p.Ref), cell(p.Pipeline), cell(p.State))
+		}
+	}
+
+	b.WriteString("\n## Outstanding signs\n\n")
+	if len(r.Outstanding) == 0 {
+		b.WriteString("Nothing awaiting sign-off.\n")
+	}
+	for _, p := range r.Outstanding {
+		fmt.Fprintf(&b, "- %s (%s): %s -> %s needs %s\n",
+			p.Ref, p.Pipeline, p.State, p.Next, strings.Join(p.Missing, ", "))
+	}
+
+	b.WriteString("\n## Recent activity\n\n")
+	if len(r.Audit) == 0 {
+		b.WriteString("No audit events recorded.\n")
+	} else {
+		b.WriteString("| Time | Operation | User | File | Detail |\n|---|---|---|---|---|\n")
+		for _, a := range r.Audit {
+			fmt.Fprintf(&b, "| %s | %s | %s | %s | %s |\n",
+				a.Timestamp, cell(a.Operation), cell(a.User), cell(a.Ref), cell(a.Detail))
+		}
+	}
+
+	_, err := io.WriteString(w, b.String())
+	return err
+}
+
+var reportHTML = template.Must(template.New("report").Parse(`<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Project report: {{.Project}}</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+code { font-size: 0.9em; }
+</style>
+</head>
+<body>
+<h1>Project report: {{.Project}}</h1>
+<p>Generated {{.GeneratedAt}}</p>
+
+<h2>Categories</h2>
+<table>
+<tr><th>Category</th><th>Pattern</th><th>Protection</th></tr>
+{{range .Categories}}<tr><td>{{.Name}}</td><td><code>{{.Pattern}}</code></td><td>{{.Protection}}</td></tr>
+{{end}}</table>
+
+<h2>Files ({{len .Files}})</h2>
+<table>
+<tr><th>Reference</th><th>SHA-256</th><th>Protection</th><th>Tags</th></tr>
+{{range .Files}}<tr><td>{{.Ref}}</td><td>{{if .Tracked}}<code>{{.SHA256}}</code>{{else}}untracked{{end}}</td><td>{{.Protection}}</td><td>{{.Tags}}</td></tr>
+{{end}}</table>
+
+<h2>Pipeline states</h2>
+{{if .Pipelines}}<table>
+<tr><th>Reference</th><th>Pipeline</th><th>State</th></tr>
+{{range .Pipelines}}<tr><td>{{.Ref}}</td><td>{{.Pipeline}}</td><td>{{.State}}</td></tr>
+{{end}}</table>
+{{else}}<p>No files are attached to a pipeline.</p>
+{{end}}
+<h2>Outstanding signs</h2>
+{{if .Outstanding}}<ul>
+{{range .Outstanding}}<li>{{.Ref}} ({{.Pipeline}}): {{.State}} &rarr; {{.Next}} needs {{range $i, $m := .Missing}}{{if $i}}, {{end}}{{$m}}{{end}}</li>
+{{end}}</ul>
+{{else}}<p>Nothing awaiting sign-off.</p>
+{{end}}
+<h2>Recent activity</h2>
+{{if .Audit}}<table>
+<tr><th>Time</th><th>Operation</th><th>User</th><th>File</th><th>Detail</th></tr>
+{{range .Audit}}<tr><td>{{.Timestamp}}</td><td>{{.Operation}}</td><td>{{.User}}</td><td>{{.Ref}}</td><td><code>{{.Detail}}</code></td></tr>
+{{end}}</table>
+{{else}}<p>No audit events recorded.</p>
+{{end}}</body>
+</html>
+`))
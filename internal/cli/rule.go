@@ -0,0 +1,485 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/rules"
+)
+
+const ruleUsage = `usage: mkrk rule add <name> --on <event> --action <type> [options] [--workspace]
+       mkrk rule list [--workspace]
+       mkrk rule test <name> --file <reference> [--on <event>] [event options]
+       mkrk rule export [<name>...] [--workspace]
+       mkrk rule import [--replace] <file>|--from-workspace-defaults [--workspace]
+       mkrk rule remove <name> [--workspace]
+       mkrk rule enable|disable <name> [--workspace]`
+
+// workspaceRulesFile, at the workspace root, holds rules to share across
+// its projects; `rule import --from-workspace-defaults` adds them.
+const workspaceRulesFile = "rules.yaml"
+
+// ruleStore is where rule commands add, change and remove rules: the
+// project's rules table, or the workspace's, whose rules every project
+// inherits.
+type ruleStore interface {
+	InsertRule(r *models.Rule) (int64, error)
+	GetRuleByName(name string) (*models.Rule, error)
+	ListRules() ([]models.Rule, error)
+	RemoveRule(name string) (int64, error)
+	SetRuleEnabled(name string, enabled bool) (int64, error)
+}
+
+func RunRule(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	return runRule(ctx, ctx.ProjectDb, "project", args)
+}
+
+// RunWorkspaceRule runs a rule command with --workspace against the
+// workspace's rules. Like ref, it runs once on the workspace containing the
+// working directory rather than once per project.
+func RunWorkspaceRule(args []string) error {
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	defer ctx.Close()
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	args = slices.DeleteFunc(slices.Clone(args), func(a string) bool { return a == "--workspace" })
+	if len(args) > 0 && args[0] == "test" {
+		return fmt.Errorf("rule test runs in a project, against workspace rules too")
+	}
+	return runRule(ctx, ctx.Workspace.Db, "workspace", args)
+}
+
+func runRule(ctx *context.Context, store ruleStore, where string, args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", ruleUsage)
+	}
+
+	switch args[0] {
+	case "add":
+		return ruleAdd(ctx, store, where, args[1:])
+	case "list", "ls":
+		return ruleList(ctx, where)
+	case "test":
+		return ruleTest(ctx, args[1:])
+	case "export":
+		return ruleExport(store, args[1:])
+	case "import":
+		return ruleImport(ctx, store, args[1:])
+	case "remove", "rm":
+		if len(args) != 2 {
+			return fmt.Errorf("usage: mkrk rule remove <name> [--workspace]")
+		}
+		n, err := store.RemoveRule(args[1])
+		if err != nil {
+			return err
+		}
+		if n == 0 {
+			return fmt.Errorf("rule '%s' not found in the %s", args[1], where)
+		}
+		fmt.Fprintf(os.Stderr, "Removed rule '%s' (%s)\n", args[1], where)
+		return nil
+	case "enable", "disable":
+		if len(args) != 2 {
+			return fmt.Errorf("usage: mkrk rule %s <name> [--workspace]", args[0])
+		}
+		return ruleSetEnabled(ctx, store, where, args[1], args[0] == "enable")
+	default:
+		return fmt.Errorf("unknown rule subcommand: %s\n%s", args[0], ruleUsage)
+	}
+}
+
+// ruleSetEnabled enables or disables a rule. A project can switch off a
+// rule it inherits from the workspace: the rule is copied into the project,
+// disabled, where it overrides the workspace's until removed.
+func ruleSetEnabled(ctx *context.Context, store ruleStore, where, name string, enabled bool) error {
+	verb := "disabled"
+	if enabled {
+		verb = "enabled"
+	}
+	n, err := store.SetRuleEnabled(name, enabled)
+	if err != nil {
+		return err
+	}
+	if n > 0 {
+		fmt.Fprintf(os.Stderr, "Rule '%s' %s (%s)\n", name, verb, where)
+		return nil
+	}
+	if where == "project" && ctx.Workspace != nil && ctx.Workspace.Db != nil {
+		inherited, err := ctx.Workspace.Db.GetRuleByName(name)
+		if err != nil {
+			return err
+		}
+		if inherited != nil {
+			override := *inherited
+			override.ID, override.CreatedAt, override.Enabled = nil, "", enabled
+			if _, err := store.InsertRule(&override); err != nil {
+				return err
+			}
+			fmt.Fprintf(os.Stderr, "Rule '%s' %s in this project, overriding the workspace rule\n", name, verb)
+			return nil
+		}
+	}
+	return fmt.Errorf("rule '%s' not found in the %s", name, where)
+}
+
+func ruleAdd(ctx *context.Context, store ruleStore, where string, args []string) error {
+	fs := flag.NewFlagSet("rule add", flag.ExitOnError)
+	on := fs.String("on", "", "trigger event")
+	action := fs.String("action", "", "action type")
+	priority := fs.Int("priority", 0, "evaluation order (lower runs first)")
+	disabled := fs.Bool("disabled", false, "create the rule disabled")
+
+	tag := fs.String("tag", "", "action: tag to add or remove")
+	tool := fs.String("tool", "", "action: tool to run")
+	pipeline := fs.String("pipeline", "", "action: pipeline")
+	signName := fs.String("sign-name", "", "action: sign name")
+	category := fs.String("category", "", "action: category")
+	command := fs.String("command", "", "action: shell command ({path}, {name}, {tag}, {project})")
+	url := fs.String("url", "", "action: webhook URL")
+	payload := fs.String("payload", "", "action: webhook body ({rule}, {trigger}, {project}, {name}, {ref}, {sha256}, {tag}, {status}, {diff})")
+
+	ifTag := fs.String("if-tag", "", "filter: file has tag")
+	ifCategory := fs.String("if-category", "", "filter: file is in category")
+	ifMime := fs.String("if-mime", "", "filter: mime type (image/* allowed)")
+	ifPipeline := fs.String("if-pipeline", "", "filter: pipeline")
+	ifSign := fs.String("if-sign", "", "filter: sign name")
+	ifState := fs.String("if-state", "", "filter: pipeline state")
+
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 || *on == "" || *action == "" {
+		return fmt.Errorf("usage: mkrk rule add <name> --on <event> --action <type> [options]")
+	}
+
+	trigger, err := models.ParseTriggerEvent(*on)
+	if err != nil {
+		return err
+	}
+	actionType, err := models.ParseActionType(*action)
+	if err != nil {
+		return err
+	}
+
+	rule := &models.Rule{
+		Name:         positional[0],
+		Enabled:      !*disabled,
+		TriggerEvent: trigger,
+		ActionType:   actionType,
+		Priority:     *priority,
+		TriggerFilter: models.TriggerFilter{
+			TagName:  optString(*ifTag),
+			Category: optString(*ifCategory),
+			MimeType: optString(*ifMime),
+			Pipeline: optString(*ifPipeline),
+			SignName: optString(*ifSign),
+			State:    optString(*ifState),
+		},
+		ActionConfig: models.ActionConfig{
+			Tool:     optString(*tool),
+			Tag:      optString(*tag),
+			Pipeline: optString(*pipeline),
+			SignName: optString(*signName),
+			Category: optString(*category),
+			Command:  optString(*command),
+			URL:      optString(*url),
+			Payload:  optString(*payload),
+		},
+	}
+	if actionType == models.EventActionShell && rule.ActionConfig.Command == nil {
+		return fmt.Errorf("shell actions require --command")
+	}
+	if actionType == models.EventActionWebhook && rule.ActionConfig.URL == nil {
+		return fmt.Errorf("webhook actions require --url")
+	}
+
+	if existing, _ := store.GetRuleByName(rule.Name); existing != nil {
+		return fmt.Errorf("rule '%s' already exists in the %s", rule.Name, where)
+	}
+	if _, err := store.InsertRule(rule); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Created rule '%s' (on %s: %s) in the %s\n", rule.Name, trigger, actionType, where)
+	if actionType == models.EventActionShell && where == "project" {
+		if allowed, _ := ctx.ProjectDb.GetConfig(rules.AllowShellSetting); allowed == nil || *allowed != "true" {
+			fmt.Fprintf(os.Stderr, "  shell actions are disabled in this project; enable them with: mkrk config %s true\n", rules.AllowShellSetting)
+		}
+	}
+	return nil
+}
+
+// ruleList lists the project's rules and then the workspace rules it
+// inherits, or with --workspace only the workspace's.
+func ruleList(ctx *context.Context, where string) error {
+	overridden := make(map[string]bool)
+	if where == "project" {
+		rules, err := ctx.ProjectDb.ListRules()
+		if err != nil {
+			return err
+		}
+		for _, r := range rules {
+			printRule(&r, "")
+			overridden[r.Name] = true
+		}
+	}
+	if ctx.Workspace != nil && ctx.Workspace.Db != nil {
+		rules, err := ctx.Workspace.Db.ListRules()
+		if err != nil {
+			return err
+		}
+		for _, r := range rules {
+			note := ""
+			if where == "project" {
+				note = " (workspace)"
+				if overridden[r.Name] {
+					note = " (workspace, overridden by project)"
+				}
+			}
+			printRule(&r, note)
+		}
+	}
+	return nil
+}
+
+func printRule(r *models.Rule, note string) {
+	state := ""
+	if !r.Enabled {
+		state = " (disabled)"
+	}
+	fmt.Printf("%s: on %s%s -> %s%s%s%s\n",
+		r.Name, r.TriggerEvent, describeFilter(&r.TriggerFilter),
+		r.ActionType, describeActionConfig(&r.ActionConfig), state, note)
+}
+
+// ruleTest evaluates one rule against a hypothetical event on each file a
+// reference resolves to, reporting why its filter does or does not match
+// and what its action would do. Nothing is applied or audited.
+func ruleTest(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("rule test", flag.ExitOnError)
+	on := fs.String("on", "", "trigger event (default: the rule's own)")
+	fileRef := fs.String("file", "", "file the event happens to")
+	tag := fs.String("tag", "", "event: tag that was added or removed")
+	pipeline := fs.String("pipeline", "", "event: pipeline")
+	signName := fs.String("sign", "", "event: sign name")
+	state := fs.String("state", "", "event: pipeline state")
+
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 || *fileRef == "" {
+		return fmt.Errorf("usage: mkrk rule test <name> --file <reference> [--on <event>] [event options]")
+	}
+	rule, err := ctx.ProjectDb.GetRuleByName(positional[0])
+	if err != nil {
+		return err
+	}
+	if rule == nil && ctx.Workspace != nil && ctx.Workspace.Db != nil {
+		if rule, err = ctx.Workspace.Db.GetRuleByName(positional[0]); err != nil {
+			return err
+		}
+	}
+	if rule == nil {
+		return fmt.Errorf("rule '%s' not found", positional[0])
+	}
+	trigger := rule.TriggerEvent
+	if *on != "" {
+		if trigger, err = models.ParseTriggerEvent(*on); err != nil {
+			return err
+		}
+	}
+	rels, err := resolve.RefRelPaths(ctx, *fileRef)
+	if err != nil {
+		return err
+	}
+	if len(rels) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	categories, _ := ctx.ProjectDb.ListCategories()
+	for _, relPath := range rels {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		ev := &rules.Event{
+			Trigger:  trigger,
+			RelPath:  relPath,
+			Ref:      reference.FormatRef(relPath, projectName, ctx.ProjectDb),
+			TagName:  optString(*tag),
+			Pipeline: optString(*pipeline),
+			SignName: optString(*signName),
+			State:    optString(*state),
+			Root:     ctx.ProjectRoot,
+			Project:  projectName,
+		}
+		for _, c := range matchingCategories(relPath, categories) {
+			ev.Categories = append(ev.Categories, c.Name)
+		}
+		hash, _ := ctx.HashFile(absPath)
+		if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil && file.ID != nil {
+			ev.SHA256 = hash
+			ev.MimeType = file.MimeType
+			ev.Tags, _ = ctx.ProjectDb.GetTags(*file.ID)
+		} else {
+			ev.MimeType = detectMime(absPath)
+		}
+
+		switch mismatches := rules.Mismatches(&rule.TriggerFilter, ev); {
+		case !rule.Enabled:
+			fmt.Printf("%s: rule '%s' is disabled\n", ev.Ref, rule.Name)
+		case trigger != rule.TriggerEvent:
+			fmt.Printf("%s: no match, rule fires on %s, not %s\n", ev.Ref, rule.TriggerEvent, trigger)
+		case len(mismatches) > 0:
+			fmt.Printf("%s: no match, filter wants %s\n", ev.Ref, strings.Join(mismatches, "; "))
+		default:
+			fmt.Printf("%s: match, would %s\n", ev.Ref, rules.Describe(rule))
+		}
+	}
+	return nil
+}
+
+// ruleExport writes the store's rules, or the named ones, to stdout as YAML
+// that `rule import` reads back.
+func ruleExport(store ruleStore, names []string) error {
+	all, err := store.ListRules()
+	if err != nil {
+		return err
+	}
+	selected := all
+	if len(names) > 0 {
+		selected = nil
+		for _, name := range names {
+			i := slices.IndexFunc(all, func(r models.Rule) bool { return r.Name == name })
+			if i < 0 {
+				return fmt.Errorf("rule '%s' not found", name)
+			}
+			selected = append(selected, all[i])
+		}
+	}
+	_, err = os.Stdout.Write(rules.Marshal(selected))
+	return err
+}
+
+// ruleImport adds the rules in a YAML file, "-" for stdin, or in the
+// workspace's default rules file. Rules whose name is taken are skipped
+// unless --replace is given.
+func ruleImport(ctx *context.Context, store ruleStore, args []string) error {
+	fs := flag.NewFlagSet("rule import", flag.ExitOnError)
+	replace := fs.Bool("replace", false, "replace rules that already exist")
+	fromDefaults := fs.Bool("from-workspace-defaults", false, "import the workspace's "+workspaceRulesFile)
+	positional := parseInterspersed(fs, args)
+
+	var path string
+	switch {
+	case *fromDefaults && len(positional) == 0:
+		if ctx.Workspace == nil {
+			return fmt.Errorf("project is not in a workspace")
+		}
+		path = filepath.Join(ctx.Workspace.Root, workspaceRulesFile)
+	case !*fromDefaults && len(positional) == 1:
+		path = positional[0]
+	default:
+		return fmt.Errorf("usage: mkrk rule import [--replace] <file>|--from-workspace-defaults")
+	}
+
+	var data []byte
+	var err error
+	if path == "-" {
+		data, err = io.ReadAll(os.Stdin)
+	} else {
+		data, err = os.ReadFile(path)
+	}
+	if err != nil {
+		return err
+	}
+	imported, err := rules.Unmarshal(data)
+	if err != nil {
+		return fmt.Errorf("%s: %w", path, err)
+	}
+
+	added, skipped := 0, 0
+	for i := range imported {
+		r := &imported[i]
+		if existing, _ := store.GetRuleByName(r.Name); existing != nil {
+			if !*replace {
+				fmt.Fprintf(os.Stderr, "  - %s \033[36m(exists, skipped)\033[0m\n", r.Name)
+				skipped++
+				continue
+			}
+			if _, err := store.RemoveRule(r.Name); err != nil {
+				return err
+			}
+		}
+		if _, err := store.InsertRule(r); err != nil {
+			return err
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s (on %s: %s)\n", r.Name, r.TriggerEvent, r.ActionType)
+		added++
+	}
+	fmt.Fprintf(os.Stderr, "Imported %d rule(s), skipped %d\n", added, skipped)
+	return nil
+}
+
+func describeFilter(f *models.TriggerFilter) string {
+	var parts []string
+	add := func(k string, v *string) {
+		if v != nil {
+			parts = append(parts, k+"="+*v)
+		}
+	}
+	add("tag", f.TagName)
+	add("category", f.Category)
+	add("mime", f.MimeType)
+	add("pipeline", f.Pipeline)
+	add("sign", f.SignName)
+	add("state", f.State)
+	if len(parts) == 0 {
+		return ""
+	}
+	return " [" + strings.Join(parts, " ") + "]"
+}
+
+func describeActionConfig(c *models.ActionConfig) string {
+	var parts []string
+	add := func(k string, v *string) {
+		if v != nil {
+			parts = append(parts, k+"="+*v)
+		}
+	}
+	add("tool", c.Tool)
+	add("tag", c.Tag)
+	add("pipeline", c.Pipeline)
+	add("sign", c.SignName)
+	add("category", c.Category)
+	add("command", c.Command)
+	add("url", c.URL)
+	add("payload", c.Payload)
+	if len(parts) == 0 {
+		return ""
+	}
+	return " (" + strings.Join(parts, " ") + ")"
+}
+
+func optString(s string) *string {
+	if s == "" {
+		return nil
+	}
+	return &s
+}
@@ -0,0 +1,258 @@
+package cli
+
+import (
+	"bytes"
+	"encoding/json"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"regexp"
+	"sort"
+	"strings"
+
+	"golang.org/x/term"
+
+	"go.foia.dev/muckrake/internal/crypt"
+)
+
+const secretUsage = `usage: mkrk secret set <name>      read the value from stdin
+       mkrk secret list            list secret names
+       mkrk secret remove <name>`
+
+// Secrets live in one file per user, sealed under their own passphrase,
+// outside any project so no project database or backup carries them.
+const (
+	secretsPassphraseEnv = "MKRK_SECRETS_PASSPHRASE"
+	secretsFileEnv       = "MKRK_SECRETS_FILE"
+)
+
+// secretRef matches a ${secret:NAME} reference in a tool environment
+// setting.
+var secretRef = regexp.MustCompile(`\$\{secret:([^}]*)\}`)
+
+var secretName = regexp.MustCompile(`^[A-Za-z0-9_.-]+$`)
+
+// RunSecret manages the secrets tool environment settings refer to as
+// ${secret:NAME}. Like lock, it runs before context discovery: secrets
+// belong to the user, not to a project.
+func RunSecret(args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", secretUsage)
+	}
+	fs := flag.NewFlagSet("secret", flag.ExitOnError)
+	pos := parseInterspersed(fs, args[1:])
+
+	switch args[0] {
+	case "set":
+		if len(pos) != 1 {
+			return fmt.Errorf("%s", secretUsage)
+		}
+		return secretSet(pos[0])
+	case "list", "ls":
+		return secretList()
+	case "remove", "rm":
+		if len(pos) != 1 {
+			return fmt.Errorf("%s", secretUsage)
+		}
+		return secretRemove(pos[0])
+	}
+	return fmt.Errorf("%s", secretUsage)
+}
+
+func secretSet(name string) error {
+	if !secretName.MatchString(name) {
+		return fmt.Errorf("invalid secret name '%s' (letters, digits, '.', '_' and '-')", name)
+	}
+	store := &secretStore{}
+	secrets, err := store.load(true)
+	if err != nil {
+		return err
+	}
+	value, err := readSecretValue(name)
+	if err != nil {
+		return err
+	}
+	secrets[name] = value
+	if err := store.save(secrets); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Stored secret '%s'\n", name)
+	return nil
+}
+
+// readSecretValue prompts for the value on a terminal, without echo, or
+// reads all of stdin, so the value never appears in argv or shell history.
+func readSecretValue(name string) (string, error) {
+	fd := int(os.Stdin.Fd())
+	var raw []byte
+	var err error
+	if term.IsTerminal(fd) {
+		fmt.Fprintf(os.Stderr, "Value for %s: ", name)
+		raw, err = term.ReadPassword(fd)
+		fmt.Fprintln(os.Stderr)
+	} else {
+		raw, err = io.ReadAll(os.Stdin)
+	}
+	if err != nil {
+		return "", err
+	}
+	value := strings.TrimRight(string(raw), "\r\n")
+	if value == "" {
+		return "", fmt.Errorf("empty secret")
+	}
+	return value, nil
+}
+
+func secretList() error {
+	store := &secretStore{}
+	secrets, err := store.load(false)
+	if err != nil {
+		return err
+	}
+	if len(secrets) == 0 {
+		fmt.Println("(no secrets)")
+	}
+	names := make([]string, 0, len(secrets))
+	for name := range secrets {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	for _, name := range names {
+		fmt.Println(name)
+	}
+	return nil
+}
+
+func secretRemove(name string) error {
+	store := &secretStore{}
+	secrets, err := store.load(false)
+	if err != nil {
+		return err
+	}
+	if _, ok := secrets[name]; !ok {
+		return fmt.Errorf("no secret '%s'", name)
+	}
+	delete(secrets, name)
+	if err := store.save(secrets); err != nil {
+		return err
+	}
+	fmt.Fprintf(os.Stderr, "Removed secret '%s'\n", name)
+	return nil
+}
+
+// secretStore reads the sealed secrets file on first use, so a tool run
+// whose settings name no secret never asks for the passphrase.
+type secretStore struct {
+	passphrase string
+	secrets    map[string]string
+}
+
+// secretsPath returns the secrets file: MKRK_SECRETS_FILE, or
+// mkrk/secrets in the user's config directory.
+func secretsPath() (string, error) {
+	if p := os.Getenv(secretsFileEnv); p != "" {
+		return p, nil
+	}
+	dir, err := os.UserConfigDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "mkrk", "secrets"), nil
+}
+
+// load returns the stored secrets. A missing file holds none; creating
+// it, when create is set, asks for a new passphrase.
+func (s *secretStore) load(create bool) (map[string]string, error) {
+	if s.secrets != nil {
+		return s.secrets, nil
+	}
+	path, err := secretsPath()
+	if err != nil {
+		return nil, err
+	}
+	f, err := os.Open(path)
+	if os.IsNotExist(err) {
+		if create {
+			if s.passphrase, err = readPassphraseFrom(secretsPassphraseEnv, "New secrets passphrase: ", true); err != nil {
+				return nil, err
+			}
+		}
+		s.secrets = make(map[string]string)
+		return s.secrets, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+
+	if s.passphrase, err = readPassphraseFrom(secretsPassphraseEnv, "Secrets passphrase: ", false); err != nil {
+		return nil, err
+	}
+	r, err := crypt.Unlock(f, s.passphrase)
+	if err != nil {
+		return nil, err
+	}
+	data, err := io.ReadAll(r)
+	if err != nil {
+		return nil, fmt.Errorf("read secrets: %w (wrong passphrase?)", err)
+	}
+	secrets := make(map[string]string)
+	if err := json.Unmarshal(data, &secrets); err != nil {
+		return nil, fmt.Errorf("corrupt secrets file %s: %w", path, err)
+	}
+	s.secrets = secrets
+	return secrets, nil
+}
+
+// save seals secrets under the store's passphrase, replacing the file.
+func (s *secretStore) save(secrets map[string]string) error {
+	path, err := secretsPath()
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0o700); err != nil {
+		return err
+	}
+	data, err := json.Marshal(secrets)
+	if err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(path), ".secrets-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+	err = crypt.Lock(tmp, bytes.NewReader(data), s.passphrase)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), path)
+}
+
+// expand replaces each ${secret:NAME} in value with the stored secret.
+func (s *secretStore) expand(value string) (string, error) {
+	var missing error
+	out := secretRef.ReplaceAllStringFunc(value, func(m string) string {
+		if missing != nil {
+			return m
+		}
+		name := secretRef.FindStringSubmatch(m)[1]
+		secrets, err := s.load(false)
+		if err != nil {
+			missing = err
+			return m
+		}
+		v, ok := secrets[name]
+		if !ok {
+			missing = fmt.Errorf("no secret '%s' (add it with mkrk secret set %s)", name, name)
+			return m
+		}
+		return v
+	})
+	return out, missing
+}
@@ -1,44 +1,72 @@
 package cli
 
 import (
+	"bytes"
+	"crypto/ed25519"
+	"encoding/base64"
+	"encoding/hex"
+	"errors"
 	"flag"
 	"fmt"
+	"io"
 	"os"
 	"os/user"
 	"path/filepath"
+	"slices"
+	"strings"
 	"time"
 
 	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/db"
 	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/models"
 	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/signing"
+	"go.foia.dev/muckrake/internal/timestamp"
 )
 
 func RunSign(ctx *context.Context, args []string) error {
+	if len(args) > 0 && args[0] == "verify" {
+		return runSignVerify(ctx, args[1:])
+	}
+
 	fs := flag.NewFlagSet("sign", flag.ExitOnError)
 	remove := fs.Bool("remove", false, "revoke sign instead of creating")
 	fs.BoolVar(remove, "r", false, "shorthand for --remove")
 	pipelineName := fs.String("pipeline", "", "pipeline name")
-
-	var positional []string
-	var flagArgs []string
-	for i := 0; i < len(args); i++ {
-		if args[i] == "--remove" || args[i] == "-r" {
-			flagArgs = append(flagArgs, args[i])
-		} else if args[i] == "--pipeline" && i+1 < len(args) {
-			flagArgs = append(flagArgs, args[i], args[i+1])
-			i++
-		} else if len(args[i]) > 0 && args[i][0] == '-' {
-			flagArgs = append(flagArgs, args[i])
-		} else {
-			positional = append(positional, args[i])
-		}
-	}
-	fs.Parse(flagArgs)
+	force := fs.Bool("force", false, "sign even when earlier signs were made against a different hash")
+	fs.BoolVar(force, "f", false, "shorthand for --force")
+	useGPG := fs.Bool("gpg", false, "attach a GPG signature")
+	useSSH := fs.Bool("ssh", false, "attach an ssh-keygen signature")
+	useMinisign := fs.Bool("minisign", false, "attach a minisign signature")
+	backend := fs.String("backend", "", "attach a signature made with this backend: "+strings.Join(signing.Formats, ", "))
+	key := fs.String("key", "", "GPG key ID, or ssh or minisign secret key file (default: the tool's usual key)")
+	stamp := fs.Bool("timestamp", false, "attach an RFC 3161 timestamp over the file hash")
+	tsa := fs.String("tsa", "", "time-stamping authority URL (default: the tsa-url setting, or "+timestamp.DefaultTSA+")")
+	positional := parseInterspersed(fs, args)
 
 	if *pipelineName == "" {
 		return fmt.Errorf("--pipeline is required")
 	}
+	format := *backend
+	if format != "" && !slices.Contains(signing.Formats, format) {
+		return fmt.Errorf("unknown signing backend '%s' (expected %s)", format, strings.Join(signing.Formats, ", "))
+	}
+	for name, on := range map[string]bool{signing.GPG: *useGPG, signing.SSH: *useSSH, signing.Minisign: *useMinisign} {
+		if !on {
+			continue
+		}
+		if format != "" {
+			return fmt.Errorf("choose one of --gpg, --ssh, --minisign or --backend")
+		}
+		format = name
+	}
+	if *key != "" && (format == "" || format == signing.Ed25519) {
+		return fmt.Errorf("--key needs --gpg, --ssh or --minisign (ed25519 signs with the project's key)")
+	}
+	opts := signOptions{format: format, key: *key}
+
 	if ctx.Kind != context.ContextProject {
 		return fmt.Errorf("not in a project")
 	}
@@ -47,51 +75,238 @@ func RunSign(ctx *context.Context, args []string) error {
 	if err != nil {
 		return err
 	}
-	if len(paths) != 1 {
-		return fmt.Errorf("sign requires exactly one file, got %d", len(paths))
+	if len(paths) == 0 {
+		return fmt.Errorf("no files matched")
 	}
 
-	relPath := paths[0]
-	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	pipeline, err := ctx.ProjectDb.GetPipelineByName(*pipelineName)
+	if err != nil || pipeline == nil {
+		return fmt.Errorf("pipeline '%s' not found", *pipelineName)
+	}
+	if !*remove && !isSignName(pipeline, signName) {
+		return fmt.Errorf("'%s' is not a valid sign name for pipeline '%s'", signName, pipeline.Name)
+	}
+	if *stamp && !*remove {
+		// RFC 3161 imprints name their digest algorithm; mkrk requests
+		// SHA-256 ones, so only a SHA-256 project's hashes can be stamped.
+		if alg, err := ctx.HashAlgorithm(); err != nil {
+			return err
+		} else if alg != integrity.SHA256 {
+			return fmt.Errorf("--timestamp needs a project hashed with sha256, not %s", alg)
+		}
+		if opts.stamp, err = timestamper(ctx, *tsa); err != nil {
+			return err
+		}
+	}
+	if format == signing.Ed25519 && !*remove {
+		if opts.signingKey, err = projectSigningKey(ctx.ProjectDb, true); err != nil {
+			return err
+		}
+	}
 
-	hash, err := integrity.HashFile(absPath)
-	if err != nil {
-		return err
+	var signed, skipped, failed int
+	for _, relPath := range paths {
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		hash, err := ctx.HashFile(absPath)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			failed++
+			continue
+		}
+
+		file, err := ctx.ProjectDb.GetFileByHash(hash)
+		if err != nil || file == nil || file.ID == nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: not tracked (run sync first)\n", relPath)
+			failed++
+			continue
+		}
+
+		if *remove {
+			if err := revokeSign(ctx, *file.ID, hash, signName, pipeline, relPath); err != nil {
+				fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+				failed++
+				continue
+			}
+			signed++
+			continue
+		}
+
+		if !*force {
+			if stale := staleSignCount(ctx, *file.ID, *pipeline.ID, hash); stale > 0 {
+				fmt.Fprintf(os.Stderr, "  ~ %s: skipped, %d earlier sign(s) were made against a different hash (use --force)\n",
+					relPath, stale)
+				skipped++
+				continue
+			}
+		}
+
+		if err := createSign(ctx, *file.ID, hash, signName, pipeline, relPath, opts); err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			failed++
+			continue
+		}
+		signed++
+	}
+
+	if len(paths) > 1 {
+		verb := "Signed"
+		if *remove {
+			verb = "Revoked"
+		}
+		fmt.Fprintf(os.Stderr, "%s %d, skipped %d, failed %d\n", verb, signed, skipped, failed)
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d file(s) failed", failed)
 	}
+	return nil
+}
 
-	file, err := ctx.ProjectDb.GetFileByHash(hash)
-	if err != nil || file == nil || file.ID == nil {
-		return fmt.Errorf("file not tracked (run sync first)")
+// isSignName reports whether name is required by any transition of the
+// pipeline.
+func isSignName(pipeline *models.Pipeline, name string) bool {
+	for state := range pipeline.Transitions {
+		for _, r := range pipeline.Requirements(state) {
+			if r.Name == name {
+				return true
+			}
+		}
 	}
+	return false
+}
 
-	pipeline, err := ctx.ProjectDb.GetPipelineByName(*pipelineName)
-	if err != nil || pipeline == nil {
-		return fmt.Errorf("pipeline '%s' not found", *pipelineName)
+// staleSignCount counts active signs in a pipeline that attest to a hash
+// other than the file's current one.
+func staleSignCount(ctx *context.Context, fileID, pipelineID int64, hash string) int {
+	signs, _ := ctx.ProjectDb.GetSignsForFile(fileID)
+	n := 0
+	for _, s := range signs {
+		if s.PipelineID == pipelineID && s.RevokedAt == nil && s.FileHash != hash {
+			n++
+		}
 	}
+	return n
+}
+
+// signOptions says what createSign attaches to a sign beyond the record
+// itself.
+type signOptions struct {
+	// format and key make a detached signature when format is set.
+	format, key string
+	// signingKey is the project's own key, for ed25519 signatures.
+	signingKey ed25519.PrivateKey
+	// stamp, when set, obtains a trusted timestamp token over a file hash.
+	stamp func(hash string) ([]byte, error)
+}
 
-	if *remove {
-		return revokeSign(ctx, *file.ID, *pipeline.ID, signName, relPath)
+// timestamper returns a stamp function for signOptions that asks a TSA,
+// through the privacy proxy, to timestamp file hashes.
+func timestamper(ctx *context.Context, tsaURL string) (func(string) ([]byte, error), error) {
+	if tsaURL == "" {
+		tsaURL = timestamp.DefaultTSA
+		if v, _ := ctx.ProjectDb.GetConfig("tsa-url"); v != nil && *v != "" {
+			tsaURL = *v
+		}
+	}
+	privacy := privacySettings(ctx)
+	client, err := privacy.httpClient()
+	if err != nil {
+		return nil, err
+	}
+	if via := privacy.via(tsaURL); via != "" {
+		fmt.Fprintf(os.Stderr, "privacy: timestamp requests to %s go through %s\n", tsaURL, via)
+	} else {
+		fmt.Fprintf(os.Stderr, "privacy: DISABLED — timestamp requests to %s go direct\n", tsaURL)
 	}
-	return createSign(ctx, *file.ID, *pipeline.ID, hash, signName, pipeline, relPath)
+	return func(hash string) ([]byte, error) {
+		digest, err := hex.DecodeString(hash)
+		if err != nil {
+			return nil, err
+		}
+		return timestamp.Fetch(client, tsaURL, digest)
+	}, nil
 }
 
-func createSign(ctx *context.Context, fileID, pipelineID int64, hash, signName string, pipeline *models.Pipeline, relPath string) error {
-	validName := false
-	for _, reqs := range pipeline.Transitions {
-		for _, r := range reqs {
-			if r == signName {
-				validName = true
-				break
-			}
+// projectSigningKey returns the project's ed25519 signing key. Its seed is
+// stored sealed under the project passphrase and its public half in the
+// clear, so signs made with it can be checked without the passphrase.
+// With create set, a project without a key is given one.
+func projectSigningKey(pdb *db.ProjectDb, create bool) (ed25519.PrivateKey, error) {
+	sealed, err := pdb.GetConfig("signing_key")
+	if err != nil {
+		return nil, err
+	}
+	if sealed == nil {
+		if !create {
+			return nil, fmt.Errorf("project has no signing key (sign with --backend ed25519 to make one)")
 		}
+		return newSigningKey(pdb)
 	}
-	if !validName {
-		return fmt.Errorf("'%s' is not a valid sign name for pipeline '%s'", signName, pipeline.Name)
+
+	raw, err := base64.StdEncoding.DecodeString(*sealed)
+	if err != nil {
+		return nil, fmt.Errorf("corrupt signing_key: %w", err)
+	}
+	key, err := projectKey(pdb, false)
+	if err != nil {
+		return nil, err
+	}
+	r, err := crypt.NewReader(bytes.NewReader(raw), key)
+	if err != nil {
+		return nil, err
+	}
+	seed, err := io.ReadAll(r)
+	if err != nil {
+		return nil, fmt.Errorf("signing key: %w", err)
 	}
+	if len(seed) != ed25519.SeedSize {
+		return nil, fmt.Errorf("corrupt signing_key: %d byte seed", len(seed))
+	}
+	return ed25519.NewKeyFromSeed(seed), nil
+}
 
+// newSigningKey generates the project's signing key and stores it, asking
+// for the project passphrase to seal it (or for a new one, if the project
+// has none yet).
+func newSigningKey(pdb *db.ProjectDb) (ed25519.PrivateKey, error) {
+	key, err := projectKey(pdb, true)
+	if err != nil {
+		return nil, err
+	}
+	pub, priv, err := ed25519.GenerateKey(nil)
+	if err != nil {
+		return nil, err
+	}
+	var sealed bytes.Buffer
+	if err := crypt.Encrypt(&sealed, bytes.NewReader(priv.Seed()), key); err != nil {
+		return nil, err
+	}
+	pubKey := base64.StdEncoding.EncodeToString(pub)
+	if err := pdb.SetConfig("signing_key", base64.StdEncoding.EncodeToString(sealed.Bytes())); err != nil {
+		return nil, err
+	}
+	if err := pdb.SetConfig("signing_public_key", pubKey); err != nil {
+		return nil, err
+	}
+	fmt.Fprintf(os.Stderr, "Generated the project's ed25519 signing key %s\n", signing.Ed25519KeyID(pubKey))
+	return priv, nil
+}
+
+// createSign records a sign, with the signature and timestamp opts ask for.
+func createSign(ctx *context.Context, fileID int64, hash, signName string, pipeline *models.Pipeline, relPath string, opts signOptions) error {
+	pipelineID := *pipeline.ID
 	signer := whoami()
 	now := time.Now().UTC().Format(time.RFC3339)
 
+	existing, err := ctx.ProjectDb.GetValidSignsForFilePipeline(fileID, pipelineID, hash)
+	if err != nil {
+		return err
+	}
+	for _, s := range existing {
+		if s.SignName == signName && s.Signer == signer {
+			return fmt.Errorf("already signed as '%s' by %s", signName, signer)
+		}
+	}
+
 	sign := &models.Sign{
 		PipelineID: pipelineID,
 		FileID:     fileID,
@@ -100,22 +315,63 @@ func createSign(ctx *context.Context, fileID, pipelineID int64, hash, signName s
 		Signer:     signer,
 		SignedAt:   now,
 	}
+	if opts.stamp != nil {
+		token, err := opts.stamp(hash)
+		if err != nil {
+			return fmt.Errorf("timestamp: %w", err)
+		}
+		sign.TimestampToken = token
+	}
+	if opts.format != "" {
+		alg, err := ctx.HashAlgorithm()
+		if err != nil {
+			return err
+		}
+		statement := signing.Statement(string(alg), hash, pipeline.Name, signName, signer, now)
+		var sig *signing.Signature
+		if opts.format == signing.Ed25519 {
+			sig = signing.SignEd25519(opts.signingKey, statement)
+		} else if sig, err = signing.Sign(opts.format, opts.key, statement); err != nil {
+			return err
+		}
+		// A key the user signs with is theirs, so its signatures are
+		// trusted when checked here. GPG keeps its own keyring.
+		if sig.Format != signing.GPG {
+			if err := signing.Trust(sig.Format, sig.Key, signer); err != nil {
+				return fmt.Errorf("trust signing key: %w", err)
+			}
+		}
+		sign.Signature = &sig.Data
+		sign.SignatureFormat = &sig.Format
+		sign.SigningKey = &sig.Key
+	}
 
+	recordStates := trackStates(ctx, &models.TrackedFile{ID: &fileID}, []*models.Pipeline{pipeline}, hash)
 	id, err := ctx.ProjectDb.InsertSign(sign)
 	if err != nil {
 		return err
 	}
+	recordStates(hash, "sign", &signName)
 
-	fmt.Fprintf(os.Stderr, "Signed '%s' as '%s' in pipeline '%s' (id %d)\n",
-		relPath, signName, pipeline.Name, id)
+	detail := ""
+	if opts.format != "" {
+		detail += ", " + opts.format + " signature"
+	}
+	if opts.stamp != nil {
+		detail += ", timestamped"
+	}
+	fmt.Fprintf(os.Stderr, "Signed '%s' as '%s' in pipeline '%s' (id %d%s)\n",
+		relPath, signName, pipeline.Name, id, detail)
 	return nil
 }
 
-func revokeSign(ctx *context.Context, fileID, pipelineID int64, signName, relPath string) error {
+func revokeSign(ctx *context.Context, fileID int64, hash, signName string, pipeline *models.Pipeline, relPath string) error {
+	pipelineID := *pipeline.ID
 	allSigns, err := ctx.ProjectDb.GetSignsForFile(fileID)
 	if err != nil {
 		return err
 	}
+	recordStates := trackStates(ctx, &models.TrackedFile{ID: &fileID}, []*models.Pipeline{pipeline}, hash)
 
 	now := time.Now().UTC().Format(time.RFC3339)
 	revoked := 0
@@ -131,6 +387,7 @@ func revokeSign(ctx *context.Context, fileID, pipelineID int64, signName, relPat
 	if revoked == 0 {
 		return fmt.Errorf("no active sign '%s' found for '%s'", signName, relPath)
 	}
+	recordStates(hash, "revoke", &signName)
 
 	fmt.Fprintf(os.Stderr, "Revoked '%s' on '%s' (%d sign(s))\n", signName, relPath, revoked)
 	return nil
@@ -141,7 +398,7 @@ func revokeSign(ctx *context.Context, fileID, pipelineID int64, signName, relPat
 func signTargets(ctx *context.Context, args []string) ([]string, string, error) {
 	if resolve.HasNarrowSubject(ctx) {
 		if len(args) < 1 {
-			return nil, "", fmt.Errorf("usage: mkrk :<ref> sign [--remove] <sign-name> --pipeline <name>")
+			return nil, "", fmt.Errorf("usage: mkrk :<ref> sign [--remove] [--force] [--gpg|--ssh|--minisign|--backend <name>] <sign-name> --pipeline <name>")
 		}
 		rels, err := resolve.SubjectRelPaths(ctx)
 		if err != nil {
@@ -150,7 +407,7 @@ func signTargets(ctx *context.Context, args []string) ([]string, string, error)
 		return rels, args[0], nil
 	}
 	if len(args) < 2 {
-		return nil, "", fmt.Errorf("usage: mkrk sign [--remove] <reference> <sign-name> --pipeline <name>")
+		return nil, "", fmt.Errorf("usage: mkrk sign [--remove] [--force] [--gpg|--ssh|--minisign|--backend <name>] <reference> <sign-name> --pipeline <name>")
 	}
 	rels, err := resolve.RefRelPaths(ctx, args[0])
 	if err != nil {
@@ -159,9 +416,173 @@ func signTargets(ctx *context.Context, args []string) ([]string, string, error)
 	return rels, args[1], nil
 }
 
+// whoami names the signer: MKRK_SIGNER when set, so people sharing an
+// account can sign as themselves, otherwise the login user.
 func whoami() string {
+	if name := os.Getenv("MKRK_SIGNER"); name != "" {
+		return name
+	}
 	if u, err := user.Current(); err == nil {
 		return u.Username
 	}
 	return "unknown"
 }
+
+const signVerifyUsage = `usage: mkrk sign verify [--pipeline <name>] <reference>...
+       mkrk :<ref> sign verify [--pipeline <name>]`
+
+// runSignVerify re-hashes the referenced files and checks every active
+// sign on them: its detached signature and timestamp, and whether the hash
+// it was made for is still the file's. Signs made without a signature are
+// listed but do not fail.
+func runSignVerify(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("sign verify", flag.ExitOnError)
+	pipelineName := fs.String("pipeline", "", "only check signs in this pipeline")
+	refs := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	var rels []string
+	if resolve.HasNarrowSubject(ctx) {
+		paths, err := resolve.SubjectRelPaths(ctx)
+		if err != nil {
+			return err
+		}
+		rels = paths
+	} else {
+		if len(refs) == 0 {
+			return fmt.Errorf("%s", signVerifyUsage)
+		}
+		for _, raw := range refs {
+			paths, err := resolve.RefRelPaths(ctx, raw)
+			if err != nil {
+				return err
+			}
+			rels = append(rels, paths...)
+		}
+	}
+
+	pipelines, err := ctx.ProjectDb.ListPipelines()
+	if err != nil {
+		return err
+	}
+	names := make(map[int64]string)
+	for _, p := range pipelines {
+		names[*p.ID] = p.Name
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	projectPub, _ := ctx.ProjectDb.GetConfig("signing_public_key")
+	trusted, err := signing.LoadTrusted()
+	if err != nil {
+		return fmt.Errorf("trusted keys: %w", err)
+	}
+
+	var good, bad, stale, unsigned, untrusted int
+	for _, relPath := range rels {
+		e, err := classifyFile(ctx, allFiles, relPath, projectName)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			continue
+		}
+		if e.file == nil || e.file.ID == nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: not tracked (run sync first)\n", e.ref)
+			continue
+		}
+		signs, err := ctx.ProjectDb.GetSignsForFile(*e.file.ID)
+		if err != nil {
+			return err
+		}
+		for _, s := range signs {
+			pipeline := names[s.PipelineID]
+			if s.RevokedAt != nil || (*pipelineName != "" && pipeline != *pipelineName) {
+				continue
+			}
+			label := fmt.Sprintf("%s: %s/%s by %s", e.ref, pipeline, s.SignName, s.Signer)
+			var notes, failures []string
+			signed := s.Signature != nil && s.SignatureFormat != nil
+			vouched := true
+			if signed {
+				sig := signing.Signature{Format: *s.SignatureFormat, Data: *s.Signature}
+				if s.SigningKey != nil {
+					sig.Key = *s.SigningKey
+				}
+				statement := signing.Statement(e.file.HashAlgorithm, s.FileHash, pipeline, s.SignName, s.Signer, s.SignedAt)
+				keyDesc, err := signing.Verify(sig, statement, trusted)
+				switch {
+				case errors.Is(err, signing.ErrUntrusted):
+					vouched = false
+					notes = append(notes, keyDesc+", not a trusted key")
+				case err != nil:
+					failures = append(failures, err.Error())
+				default:
+					if sig.Format == signing.Ed25519 && projectPub != nil && sig.Key == *projectPub {
+						keyDesc += ", this project's key"
+					}
+					notes = append(notes, keyDesc)
+				}
+			} else {
+				notes = append(notes, "no signature")
+			}
+			if s.TimestampToken != nil {
+				if note, err := describeTimestamp(s.TimestampToken, s.FileHash); err != nil {
+					failures = append(failures, "timestamp: "+err.Error())
+				} else {
+					notes = append(notes, note)
+				}
+			}
+
+			switch {
+			case len(failures) > 0:
+				fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %s\n", label, strings.Join(failures, "; "))
+				bad++
+			case s.FileHash != e.hash:
+				fmt.Fprintf(os.Stderr, "  \033[33m~\033[0m %s: signed [%s...] but the file is now [%s...] (%s)\n",
+					label, s.FileHash[:min(len(s.FileHash), 10)], e.hash[:10], strings.Join(notes, ", "))
+				stale++
+			case !vouched:
+				fmt.Fprintf(os.Stderr, "  \033[33m?\033[0m %s (%s)\n", label, strings.Join(notes, ", "))
+				untrusted++
+			case signed:
+				fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s (%s)\n", label, strings.Join(notes, ", "))
+				good++
+			default:
+				fmt.Fprintf(os.Stderr, "  - %s (%s)\n", label, strings.Join(notes, ", "))
+				unsigned++
+			}
+		}
+	}
+
+	fmt.Fprintf(os.Stderr, "Signs: %d good, %d bad, %d stale, %d unsigned", good, bad, stale, unsigned)
+	if untrusted > 0 {
+		fmt.Fprintf(os.Stderr, ", %d by untrusted keys", untrusted)
+	}
+	fmt.Fprintln(os.Stderr)
+	if bad > 0 || stale > 0 {
+		return fmt.Errorf("%d sign(s) failed verification, %d no longer match their file", bad, stale)
+	}
+	return nil
+}
+
+// describeTimestamp checks a sign's timestamp token against the hash it
+// was made for and says when, and by whom, it was issued.
+func describeTimestamp(token []byte, fileHash string) (string, error) {
+	digest, err := hex.DecodeString(fileHash)
+	if err != nil {
+		return "", err
+	}
+	info, err := timestamp.Verify(token, digest)
+	if err != nil {
+		return "", err
+	}
+	note := fmt.Sprintf("timestamped %s by %s", info.GenTime.UTC().Format(time.RFC3339), info.TSA)
+	if info.ChainErr != nil {
+		note += " (TSA certificate not trusted here)"
+	}
+	return note, nil
+}
@@ -0,0 +1,162 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/resolve"
+)
+
+const stateUsage = `usage: mkrk state [--pipeline <name>] [--history] <reference>...
+       mkrk :<ref> state [--pipeline <name>] [--history]`
+
+// RunState shows each file's state in the pipelines it belongs to and,
+// short of a final state, the signs and signers the next transition lacks.
+// With --history it lists every recorded state change instead.
+func RunState(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("state", flag.ExitOnError)
+	pipelineName := fs.String("pipeline", "", "only show this pipeline")
+	history := fs.Bool("history", false, "list each file's state changes and what caused them")
+	refs := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	var rels []string
+	if resolve.HasNarrowSubject(ctx) {
+		paths, err := resolve.SubjectRelPaths(ctx)
+		if err != nil {
+			return err
+		}
+		rels = paths
+	} else {
+		if len(refs) == 0 {
+			return fmt.Errorf("%s", stateUsage)
+		}
+		for _, raw := range refs {
+			paths, err := resolve.RefRelPaths(ctx, raw)
+			if err != nil {
+				return err
+			}
+			rels = append(rels, paths...)
+		}
+	}
+
+	allPipelines, err := ctx.ProjectDb.ListPipelines()
+	if err != nil {
+		return err
+	}
+	byID := make(map[int64]*models.Pipeline)
+	for i := range allPipelines {
+		if *pipelineName == "" || allPipelines[i].Name == *pipelineName {
+			byID[*allPipelines[i].ID] = &allPipelines[i]
+		}
+	}
+	if *pipelineName != "" && len(byID) == 0 {
+		return fmt.Errorf("pipeline '%s' not found", *pipelineName)
+	}
+
+	for _, relPath := range rels {
+		hash, err := ctx.HashFile(filepath.Join(ctx.ProjectRoot, relPath))
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			continue
+		}
+		file, _ := ctx.ProjectDb.GetFileByHash(hash)
+		if file == nil || file.ID == nil {
+			fmt.Printf("%s: untracked\n", relPath)
+			continue
+		}
+
+		if *history {
+			printStateHistory(ctx, relPath, *file.ID, byID)
+			continue
+		}
+
+		pipelines := filePipelines(ctx, file, hash, byID)
+		fmt.Printf("%s\n", relPath)
+		for _, p := range pipelines {
+			state, next, missing := pipelineProgress(ctx, file, p, hash)
+			if len(missing) == 0 {
+				fmt.Printf("  %s: %s\n", p.Name, state)
+				continue
+			}
+			needs := make([]string, len(missing))
+			for i, m := range missing {
+				needs[i] = describeShortfall(m)
+			}
+			fmt.Printf("  %s: %s (%s needs %s)\n", p.Name, state, next, strings.Join(needs, ", "))
+		}
+		if len(pipelines) == 0 {
+			fmt.Printf("  no pipelines\n")
+		}
+	}
+	return nil
+}
+
+// printStateHistory lists a file's recorded state changes in pipelines,
+// oldest first.
+func printStateHistory(ctx *context.Context, relPath string, fileID int64, pipelines map[int64]*models.Pipeline) {
+	changes, err := ctx.ProjectDb.GetStateHistory(fileID)
+	if err != nil {
+		fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+		return
+	}
+	fmt.Printf("%s\n", relPath)
+	shown := 0
+	for _, c := range changes {
+		p, ok := pipelines[c.PipelineID]
+		if !ok {
+			continue
+		}
+		cause := "accepted modification"
+		if c.SignName != nil {
+			cause = fmt.Sprintf("%s '%s'", c.Cause, *c.SignName)
+		}
+		fmt.Printf("  %s  %s: %s -> %s (%s by %s)\n", c.ChangedAt, p.Name, c.FromState, c.ToState, cause, c.Actor)
+		shown++
+	}
+	if shown == 0 {
+		fmt.Printf("  no state changes\n")
+	}
+}
+
+// trackStates derives a file's state in each pipeline at hash and returns
+// a function to call once a change is made. It derives the states again,
+// at the file's hash after the change, and records each one that moved in
+// the file's state history.
+func trackStates(ctx *context.Context, file *models.TrackedFile, pipelines []*models.Pipeline, hash string) func(newHash, cause string, signName *string) {
+	before := make([]string, len(pipelines))
+	for i, p := range pipelines {
+		before[i] = derivePipelineState(ctx, file, p, hash)
+	}
+	return func(newHash, cause string, signName *string) {
+		now := time.Now().UTC().Format(time.RFC3339)
+		for i, p := range pipelines {
+			after := derivePipelineState(ctx, file, p, newHash)
+			if after == before[i] {
+				continue
+			}
+			change := &models.StateChange{
+				FileID:     *file.ID,
+				PipelineID: *p.ID,
+				FromState:  before[i],
+				ToState:    after,
+				ChangedAt:  now,
+				Cause:      cause,
+				SignName:   signName,
+				Actor:      whoami(),
+			}
+			if err := ctx.ProjectDb.InsertStateChange(change); err != nil {
+				fmt.Fprintf(os.Stderr, "  ! state history: %v\n", err)
+			}
+		}
+	}
+}
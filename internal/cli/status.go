@@ -3,23 +3,30 @@ package cli
 import (
 	"flag"
 	"fmt"
+	"os"
 	"path/filepath"
+	"strings"
 
 	"go.foia.dev/muckrake/internal/context"
 	"go.foia.dev/muckrake/internal/evaluate"
-	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/models"
 	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/signing"
+	"go.foia.dev/muckrake/internal/walk"
 )
 
 func RunStatus(ctx *context.Context, args []string) error {
 	fs := flag.NewFlagSet("status", flag.ExitOnError)
-	fs.Parse(args)
+	porcelain := fs.Bool("porcelain", false, "machine-readable output: one '<code> <path>' line per file")
+	positional := parseInterspersed(fs, args)
 
 	if ctx.Kind == context.ContextNone {
 		return fmt.Errorf("not in a muckrake project or workspace")
 	}
 
+	if *porcelain {
+		return porcelainStatus(ctx, positional)
+	}
 	if resolve.HasNarrowSubject(ctx) {
 		rels, err := resolve.SubjectRelPaths(ctx)
 		if err != nil {
@@ -27,8 +34,8 @@ func RunStatus(ctx *context.Context, args []string) error {
 		}
 		return fileStatusPaths(ctx, rels)
 	}
-	if fs.NArg() > 0 {
-		return fileStatus(ctx, fs.Args())
+	if len(positional) > 0 {
+		return fileStatus(ctx, positional)
 	}
 	return projectStatus(ctx)
 }
@@ -46,6 +53,12 @@ func projectStatus(ctx *context.Context) error {
 		fmt.Printf("  Name: %s\n", *ctx.ProjectName)
 	}
 	fmt.Printf("  Files: %d\n", fileCount)
+	if h, err := ctx.Hasher(); err == nil {
+		fmt.Printf("  Hash: %s, %s chunks\n", h.Algorithm, h.Chunking)
+	}
+	if pub, _ := ctx.ProjectDb.GetConfig("signing_public_key"); pub != nil {
+		fmt.Printf("  Signing key: ed25519 %s\n", signing.Ed25519KeyID(*pub))
+	}
 	fmt.Printf("  Categories: %d\n", catCount)
 
 	pipelines, _ := ctx.ProjectDb.ListPipelines()
@@ -66,6 +79,166 @@ func projectStatus(ctx *context.Context) error {
 		}
 	}
 
+	state, err := scanWorkingTree(ctx, nil)
+	if err != nil {
+		return err
+	}
+
+	if len(state.untracked) > 0 {
+		categories, _ := ctx.ProjectDb.ListCategories()
+		byCategory := make(map[string][]string)
+		var order []string
+		for _, e := range state.untracked {
+			name := "(uncategorized)"
+			if cats := matchingCategories(e.relPath, categories); len(cats) > 0 {
+				name = cats[0].Name
+			}
+			if _, ok := byCategory[name]; !ok {
+				order = append(order, name)
+			}
+			byCategory[name] = append(byCategory[name], e.ref)
+		}
+		fmt.Printf("  Untracked: %d\n", len(state.untracked))
+		for _, name := range order {
+			fmt.Printf("    %s:\n", name)
+			for _, ref := range byCategory[name] {
+				fmt.Printf("      \033[31m?\033[0m %s\n", ref)
+			}
+		}
+	}
+	if len(state.modified) > 0 {
+		fmt.Printf("  Failing verification: %d\n", len(state.modified))
+		for _, e := range state.modified {
+			fmt.Printf("    \033[33m~\033[0m %s (was [%s...])\n", e.ref, e.file.SHA256[:min(len(e.file.SHA256), 10)])
+		}
+	}
+	if len(state.missing) > 0 {
+		fmt.Printf("  Missing: %d\n", len(state.missing))
+		for _, f := range state.missing {
+			fmt.Printf("    \033[33m!\033[0m [%s...]%s\n", f.SHA256[:min(len(f.SHA256), 10)], missingTags(ctx, &f))
+		}
+	}
+	for _, err := range state.errors {
+		fmt.Printf("    \033[31m✗\033[0m %v\n", err)
+	}
+
+	return nil
+}
+
+// workingTree is the result of comparing files on disk with tracked records.
+type workingTree struct {
+	untracked []*verifyEntry
+	modified  []*verifyEntry
+	missing   []models.TrackedFile
+	errors    []error
+}
+
+// scanWorkingTree classifies the given paths, or every file in the
+// project's categories when rels is nil. Missing files are only reported
+// for a full scan, since a narrowed scan cannot tell them apart from files
+// outside the narrowed set.
+func scanWorkingTree(ctx *context.Context, rels []string) (*workingTree, error) {
+	full := rels == nil
+	if full {
+		patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+		if err != nil {
+			return nil, err
+		}
+		if rels, err = walk.WalkAndCollect(ctx.ProjectRoot, patterns); err != nil {
+			return nil, err
+		}
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	seen := make(map[string]bool)
+	state := &workingTree{}
+
+	for _, relPath := range rels {
+		e, err := classifyFile(ctx, allFiles, relPath, projectName)
+		if err != nil {
+			state.errors = append(state.errors, fmt.Errorf("%s: %w", relPath, err))
+			continue
+		}
+		switch e.status {
+		case verifyOk:
+			seen[e.file.SHA256] = true
+		case verifyModified:
+			seen[e.file.SHA256] = true
+			state.modified = append(state.modified, e)
+		case verifyUntracked:
+			state.untracked = append(state.untracked, e)
+		}
+	}
+
+	if full {
+		trashed, _ := ctx.ProjectDb.TrashedFileIDs()
+		for _, f := range allFiles {
+			if !seen[f.SHA256] && (f.ID == nil || !trashed[*f.ID]) {
+				state.missing = append(state.missing, f)
+			}
+		}
+	}
+	return state, nil
+}
+
+func missingTags(ctx *context.Context, f *models.TrackedFile) string {
+	if f.ID == nil {
+		return ""
+	}
+	tags, _ := ctx.ProjectDb.GetTags(*f.ID)
+	if len(tags) == 0 {
+		return ""
+	}
+	return " !" + strings.Join(tags, " !")
+}
+
+// porcelainStatus prints one stable, uncolored line per file that needs
+// attention: "?? <path>" untracked, "M <path>" failing verification and
+// "D <sha256>" tracked but missing from disk.
+func porcelainStatus(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	var rels []string
+	switch {
+	case resolve.HasNarrowSubject(ctx):
+		subject, err := resolve.SubjectRelPaths(ctx)
+		if err != nil {
+			return err
+		}
+		rels = append([]string{}, subject...)
+	case len(args) > 0:
+		rels = []string{}
+		for _, raw := range args {
+			paths, err := resolve.RefRelPaths(ctx, raw)
+			if err != nil {
+				return err
+			}
+			rels = append(rels, paths...)
+		}
+	}
+
+	state, err := scanWorkingTree(ctx, rels)
+	if err != nil {
+		return err
+	}
+	for _, e := range state.untracked {
+		fmt.Printf("?? %s\n", e.relPath)
+	}
+	for _, e := range state.modified {
+		fmt.Printf("M %s\n", e.relPath)
+	}
+	for _, f := range state.missing {
+		fmt.Printf("D %s\n", f.SHA256)
+	}
+	for _, err := range state.errors {
+		fmt.Fprintf(os.Stderr, "! %v\n", err)
+	}
 	return nil
 }
 
@@ -97,7 +270,7 @@ func fileStatusPaths(ctx *context.Context, rels []string) error {
 
 func printFileStatus(ctx *context.Context, relPath string) error {
 	absPath := filepath.Join(ctx.ProjectRoot, relPath)
-	hash, err := integrity.HashFile(absPath)
+	hash, err := ctx.HashFile(absPath)
 	if err != nil {
 		return fmt.Errorf("%s: %w", relPath, err)
 	}
@@ -149,40 +322,42 @@ func printFileStatus(ctx *context.Context, relPath string) error {
 }
 
 func derivePipelineState(ctx *context.Context, file *models.TrackedFile, p *models.Pipeline, hash string) string {
-	if file.ID == nil || p.ID == nil {
-		return p.States[0]
-	}
-	signs, _ := ctx.ProjectDb.GetValidSignsForFilePipeline(*file.ID, *p.ID, hash)
-	if len(signs) == 0 {
-		return p.States[0]
+	state, _, _ := pipelineProgress(ctx, file, p, hash)
+	return state
+}
+
+// pipelineProgress derives a file's state in a pipeline and, unless it is
+// in the final state, the next state and the requirements its transition
+// still lacks. A requirement counts distinct signers, so one person signing
+// twice does not meet "editor x2".
+func pipelineProgress(ctx *context.Context, file *models.TrackedFile, p *models.Pipeline, hash string) (string, string, []models.Shortfall) {
+	var signs []models.Sign
+	if file.ID != nil && p.ID != nil {
+		signs, _ = ctx.ProjectDb.GetValidSignsForFilePipeline(*file.ID, *p.ID, hash)
 	}
 
 	current := p.States[0]
 	for _, state := range p.States[1:] {
-		required, ok := p.Transitions[state]
-		if !ok {
-			break
-		}
-		allSigned := true
-		for _, req := range required {
-			found := false
-			for _, s := range signs {
-				if s.SignName == req {
-					found = true
-					break
-				}
-			}
-			if !found {
-				allSigned = false
-				break
-			}
+		if _, ok := p.Transitions[state]; !ok {
+			return current, state, nil
 		}
-		if allSigned {
-			current = state
-		} else {
-			break
+		if missing := models.Unmet(p.Requirements(state), signs); len(missing) > 0 {
+			return current, state, missing
 		}
+		current = state
 	}
-	return current
+	return current, "", nil
 }
 
+// describeShortfall renders a requirement a transition still lacks, with
+// the signers it has so far when it needs more than one.
+func describeShortfall(m models.Shortfall) string {
+	if m.Count == 1 {
+		return m.Name
+	}
+	desc := fmt.Sprintf("%s x%d (%d of %d", m.Name, m.Count, len(m.Signers), m.Count)
+	if len(m.Signers) > 0 {
+		desc += ": " + strings.Join(m.Signers, ", ")
+	}
+	return desc + ")"
+}
@@ -0,0 +1,494 @@
+package cli
+
+import (
+	"encoding/json"
+	"errors"
+	"flag"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/storage"
+)
+
+const storageUsage = `usage: mkrk storage offload [--keep] <reference>...   move content to remote storage, leaving stubs
+       mkrk storage fetch <reference>...            bring content back into the working tree
+       mkrk storage list`
+
+// RunStorage moves the content of large files out to remote storage (an
+// S3-compatible bucket, optionally under Object Lock, or a directory) and
+// back. The project keeps tracking the content by hash while a stub holds
+// its place in the working tree; read and open fetch it on demand.
+func RunStorage(ctx *context.Context, args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", storageUsage)
+	}
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	switch args[0] {
+	case "offload":
+		return storageOffload(ctx, args[1:])
+	case "fetch":
+		return storageFetch(ctx, args[1:])
+	case "list", "ls":
+		return storageList(ctx)
+	}
+	return fmt.Errorf("%s", storageUsage)
+}
+
+func storageOffload(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("storage offload", flag.ExitOnError)
+	keep := fs.Bool("keep", false, "upload, but keep the content in the working tree")
+	refs := parseInterspersed(fs, args)
+
+	rels, err := storageTargets(ctx, refs)
+	if err != nil {
+		return err
+	}
+	backend, err := storageBackend(ctx)
+	if err != nil {
+		return err
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	failed := 0
+	for _, relPath := range rels {
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		if _, isStub := storage.ReadStub(filepath.Join(ctx.ProjectRoot, relPath)); isStub {
+			fmt.Fprintf(os.Stderr, "  - %s \033[36m(already offloaded)\033[0m\n", ref)
+			continue
+		}
+		obj, err := offloadFile(ctx, backend, relPath, *keep)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+			failed++
+			continue
+		}
+		note := "stored"
+		if obj.RetainUntil != nil {
+			note += fmt.Sprintf(", %s lock until %s", obj.LockMode, *obj.RetainUntil)
+		}
+		if !*keep {
+			note += ", stub left"
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m→\033[0m %s \033[36m(%s)\033[0m\n", ref, note)
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d file(s) could not be offloaded", failed)
+	}
+	return nil
+}
+
+// offloadFile uploads the content of a tracked, unmodified file, records
+// it as stored once the backend confirms it holds all of it, and unless
+// keep is set replaces the file with a stub.
+func offloadFile(ctx *context.Context, backend storage.Backend, relPath string, keep bool) (*models.StoredObject, error) {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	hash, err := ctx.HashFile(absPath)
+	if err != nil {
+		return nil, err
+	}
+	file, _ := ctx.ProjectDb.GetFileByHash(hash)
+	if file == nil {
+		return nil, fmt.Errorf("not tracked, or modified since it was (run verify)")
+	}
+
+	f, err := os.Open(absPath)
+	if err != nil {
+		return nil, err
+	}
+	info, err := f.Stat()
+	if err == nil {
+		_, err = backend.Put(hash, f, info.Size())
+	}
+	f.Close()
+	if err != nil {
+		return nil, err
+	}
+	stat, err := backend.Stat(hash)
+	if err != nil {
+		return nil, err
+	}
+	if stat.Size != info.Size() {
+		return nil, fmt.Errorf("%s holds %d bytes, expected %d", backend, stat.Size, info.Size())
+	}
+
+	obj := &models.StoredObject{
+		SHA256:   hash,
+		Size:     info.Size(),
+		LockMode: stat.LockMode,
+		StoredAt: time.Now().UTC().Format(time.RFC3339),
+	}
+	if !stat.RetainUntil.IsZero() {
+		until := stat.RetainUntil.UTC().Format(time.RFC3339)
+		obj.RetainUntil = &until
+	}
+	if err := ctx.ProjectDb.PutStoredObject(obj); err != nil {
+		return nil, err
+	}
+
+	if !keep {
+		if err := replaceWithStub(ctx, relPath, storage.Stub{Hash: hash, Size: info.Size()}); err != nil {
+			return nil, err
+		}
+		// The remote copy is the original now; a local one would only
+		// take the space offloading was meant to free.
+		os.Remove(storeObjectPath(ctx.ProjectRoot, hash))
+	}
+
+	user := whoami()
+	detail, _ := json.Marshal(map[string]any{"backend": backend.String(), "lock_mode": obj.LockMode, "retain_until": obj.RetainUntil, "kept": keep})
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("offload", file.ID, &user, &detailStr)
+	return obj, nil
+}
+
+func storageFetch(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("storage fetch", flag.ExitOnError)
+	refs := parseInterspersed(fs, args)
+
+	rels, err := storageTargets(ctx, refs)
+	if err != nil {
+		return err
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	var backend storage.Backend
+	failed := 0
+	for _, relPath := range rels {
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		absPath := filepath.Join(ctx.ProjectRoot, relPath)
+		stub, isStub := storage.ReadStub(absPath)
+		if !isStub {
+			fmt.Fprintf(os.Stderr, "  - %s \033[36m(already local)\033[0m\n", ref)
+			continue
+		}
+		if backend == nil && !fileExists(storeObjectPath(ctx.ProjectRoot, stub.Hash)) {
+			if backend, err = storageBackend(ctx); err != nil {
+				return err
+			}
+		}
+		if err := restoreFromStub(ctx, backend, relPath, stub); err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+			failed++
+			continue
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m←\033[0m %s\n", ref)
+	}
+	if failed > 0 {
+		return fmt.Errorf("%d file(s) could not be fetched", failed)
+	}
+	return nil
+}
+
+// restoreFromStub puts the content a stub stands for back in its place,
+// checked against the tracked hash.
+func restoreFromStub(ctx *context.Context, backend storage.Backend, relPath string, stub *storage.Stub) error {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	file, _ := ctx.ProjectDb.GetFileByHash(stub.Hash)
+	if file == nil {
+		return fmt.Errorf("stub for content that is not tracked")
+	}
+	obj := storeObjectPath(ctx.ProjectRoot, stub.Hash)
+	if !fileExists(obj) {
+		var err error
+		if obj, err = fetchToStore(ctx, backend, stub.Hash); err != nil {
+			return err
+		}
+	}
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return err
+	}
+	alg, err := ctx.HashAlgorithm()
+	if err != nil {
+		return err
+	}
+
+	if immutable, _ := integrity.IsImmutable(absPath); immutable {
+		if err := integrity.ClearImmutable(absPath); err != nil {
+			return err
+		}
+	}
+	if err := copyVerified(ctx, alg, obj, absPath, stub.Hash); err != nil {
+		return err
+	}
+	os.Chmod(absPath, info.Mode().Perm())
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	if storeEnabled(ctx) {
+		linkToStore(obj, absPath, protection == models.ProtectionImmutable)
+	} else {
+		os.Remove(obj)
+	}
+	enforceImmutable(absPath, protection, relPath)
+	rememberFile(ctx, relPath, stub.Hash)
+
+	user := whoami()
+	ctx.ProjectDb.InsertAudit("fetch", file.ID, &user, nil)
+	return nil
+}
+
+func storageList(ctx *context.Context) error {
+	objects, err := ctx.ProjectDb.ListStoredObjects()
+	if err != nil {
+		return err
+	}
+	if len(objects) == 0 {
+		fmt.Fprintln(os.Stderr, "(nothing in remote storage)")
+		return nil
+	}
+	for _, o := range objects {
+		lock := "unlocked"
+		if o.RetainUntil != nil {
+			lock = fmt.Sprintf("%s lock until %s", o.LockMode, *o.RetainUntil)
+		}
+		fmt.Printf("[%s...]  %s  stored %s  %s\n", o.SHA256[:min(len(o.SHA256), 10)], formatSize(o.Size), o.StoredAt, lock)
+	}
+	return nil
+}
+
+// storageTargets picks the files named by the subject or references.
+func storageTargets(ctx *context.Context, refs []string) ([]string, error) {
+	if len(refs) == 0 && !resolve.HasNarrowSubject(ctx) {
+		return nil, fmt.Errorf("%s", storageUsage)
+	}
+	rels, err := manifestTargets(ctx, refs)
+	if err != nil {
+		return nil, err
+	}
+	if len(rels) == 0 {
+		return nil, fmt.Errorf("no files matched")
+	}
+	return rels, nil
+}
+
+// storageBackend opens the remote storage named by the project's
+// storage-* settings. Credentials come from the storage-access-key and
+// storage-secret-key settings, which may name secrets, or else from the
+// usual AWS environment variables.
+func storageBackend(ctx *context.Context) (storage.Backend, error) {
+	setting := func(key string) string {
+		if v, _ := ctx.ProjectDb.GetConfig(key); v != nil {
+			return *v
+		}
+		return ""
+	}
+	cfg := storage.Config{
+		URL:      setting("storage-url"),
+		Endpoint: setting("storage-endpoint"),
+		Region:   setting("storage-region"),
+		LockMode: setting("storage-lock"),
+	}
+	if cfg.URL == "" {
+		return nil, fmt.Errorf("no remote storage configured (mkrk config storage-url s3://<bucket>/<prefix>)")
+	}
+	if v := setting("storage-retention"); v != "" {
+		d, err := parseRetention(v)
+		if err != nil {
+			return nil, fmt.Errorf("storage-retention: %w", err)
+		}
+		cfg.Retention = d
+	}
+
+	secrets := &secretStore{}
+	for key, dst := range map[string]*string{"storage-access-key": &cfg.AccessKey, "storage-secret-key": &cfg.SecretKey} {
+		v, err := secrets.expand(setting(key))
+		if err != nil {
+			return nil, fmt.Errorf("%s: %w", key, err)
+		}
+		*dst = v
+	}
+	if cfg.AccessKey == "" && cfg.SecretKey == "" {
+		cfg.AccessKey = os.Getenv("AWS_ACCESS_KEY_ID")
+		cfg.SecretKey = os.Getenv("AWS_SECRET_ACCESS_KEY")
+		cfg.SessionToken = os.Getenv("AWS_SESSION_TOKEN")
+	}
+
+	if strings.HasPrefix(cfg.URL, "s3:") {
+		privacy := privacySettings(ctx)
+		client, err := privacy.httpClient()
+		if err != nil {
+			return nil, err
+		}
+		// Requests go to the endpoint, not the bucket the URL names.
+		endpoint := cfg.Endpoint
+		if endpoint == "" {
+			endpoint = "s3.us-east-1.amazonaws.com"
+			if cfg.Region != "" {
+				endpoint = "s3." + cfg.Region + ".amazonaws.com"
+			}
+		}
+		if via := privacy.via(endpoint); via != "" {
+			fmt.Fprintf(os.Stderr, "privacy: storage requests to %s go through %s\n", cfg.URL, via)
+		} else {
+			fmt.Fprintf(os.Stderr, "privacy: DISABLED — storage requests to %s go direct\n", cfg.URL)
+		}
+		// Large evidence takes far longer than a minute to move.
+		client.Timeout = 0
+		cfg.Client = client
+	}
+	return storage.Open(cfg)
+}
+
+// parseRetention reads a retention period: a duration such as 720h, or a
+// number of days such as 3650d.
+func parseRetention(s string) (time.Duration, error) {
+	if days, ok := strings.CutSuffix(s, "d"); ok {
+		n, err := strconv.Atoi(days)
+		if err != nil || n <= 0 {
+			return 0, fmt.Errorf("expected a number of days such as 3650d")
+		}
+		return time.Duration(n) * 24 * time.Hour, nil
+	}
+	d, err := time.ParseDuration(s)
+	if err != nil || d <= 0 {
+		return 0, fmt.Errorf("expected a duration such as 720h or a number of days such as 3650d")
+	}
+	return d, nil
+}
+
+// replaceWithStub swaps the file at relPath for a stub standing for its
+// content, keeping the file's mode and its category's immutable flag.
+func replaceWithStub(ctx *context.Context, relPath string, stub storage.Stub) error {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(absPath), ".mkrk-stub-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+	err = storage.WriteStub(tmp, stub)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	if err := os.Chmod(tmp.Name(), info.Mode().Perm()); err != nil {
+		return err
+	}
+
+	if immutable, _ := integrity.IsImmutable(absPath); immutable {
+		if err := integrity.ClearImmutable(absPath); err != nil {
+			return err
+		}
+	}
+	if err := os.Rename(tmp.Name(), absPath); err != nil {
+		return err
+	}
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	enforceImmutable(absPath, protection, relPath)
+	rememberFile(ctx, relPath, stub.Hash)
+	return nil
+}
+
+// rememberFile caches hash for the file now at relPath, so sync and
+// verify --quick need not reread it.
+func rememberFile(ctx *context.Context, relPath, hash string) {
+	if st, err := statFile(filepath.Join(ctx.ProjectRoot, relPath)); err == nil {
+		ctx.ProjectDb.PutCachedHash(filepath.ToSlash(relPath), st.size, st.mtime, st.inode, hash)
+	}
+}
+
+// fetchToStore downloads content from remote storage into the blob store,
+// checked against hash, and returns its path there.
+func fetchToStore(ctx *context.Context, backend storage.Backend, hash string) (string, error) {
+	obj := storeObjectPath(ctx.ProjectRoot, hash)
+	alg, err := ctx.HashAlgorithm()
+	if err != nil {
+		return "", err
+	}
+	r, err := backend.Get(hash)
+	if errors.Is(err, storage.ErrNotFound) {
+		return "", fmt.Errorf("[%s...] is not in %s", hash[:min(len(hash), 10)], backend)
+	}
+	if err != nil {
+		return "", err
+	}
+	defer r.Close()
+
+	if err := os.MkdirAll(filepath.Dir(obj), 0o755); err != nil {
+		return "", err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(obj), ".mkrk-fetch-*")
+	if err != nil {
+		return "", err
+	}
+	defer os.Remove(tmp.Name())
+	_, err = io.Copy(tmp, r)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return "", err
+	}
+	got, err := alg.HashFile(tmp.Name())
+	if err != nil {
+		return "", err
+	}
+	if !holdsContent(ctx, got, hash) {
+		return "", fmt.Errorf("content fetched from %s does not match the tracked hash", backend)
+	}
+	os.Chmod(tmp.Name(), 0o444)
+	return obj, os.Rename(tmp.Name(), obj)
+}
+
+// localContent returns where the content of the file at absPath can be
+// read: the file itself or, for a stub, the content's copy in the blob
+// store, fetched from remote storage first if need be.
+func localContent(ctx *context.Context, absPath string) (string, error) {
+	stub, isStub := storage.ReadStub(absPath)
+	if !isStub {
+		return absPath, nil
+	}
+	if file, _ := ctx.ProjectDb.GetFileByHash(stub.Hash); file == nil {
+		return "", fmt.Errorf("%s: stub for content that is not tracked", absPath)
+	}
+	if obj := storeObjectPath(ctx.ProjectRoot, stub.Hash); fileExists(obj) {
+		return obj, nil
+	}
+	backend, err := storageBackend(ctx)
+	if err != nil {
+		return "", err
+	}
+	fmt.Fprintf(os.Stderr, "Fetching [%s...] (%s) from %s\n", stub.Hash[:min(len(stub.Hash), 10)], formatSize(stub.Size), backend)
+	return fetchToStore(ctx, backend, stub.Hash)
+}
+
+// hashOrStub hashes and fingerprints the file at absPath, except that a
+// stub for tracked content stands for that content: it gets the content's
+// hash and recorded fingerprint without anything being fetched. An
+// encrypted file likewise gets those of its plaintext.
+func hashOrStub(ctx *context.Context, hasher integrity.Hasher, absPath string) (string, *integrity.Fingerprint, error) {
+	if stub, isStub := storage.ReadStub(absPath); isStub {
+		if file, _ := ctx.ProjectDb.GetFileByHash(stub.Hash); file != nil {
+			fp, err := integrity.FingerprintFromJSON(file.Fingerprint)
+			return stub.Hash, fp, err
+		}
+	}
+	hash, fp, err := hasher.HashAndFingerprint(absPath)
+	if err != nil {
+		return "", nil, err
+	}
+	hash, fp = ctx.Identify(hash, fp)
+	return hash, fp, nil
+}
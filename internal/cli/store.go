@@ -0,0 +1,82 @@
+package cli
+
+import (
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// blobStore is the directory, under the project root, that holds the
+// original of every file ingested or revised while the store setting is
+// on, named by content hash. Like the version store, walks skip it.
+const blobStore = ".mkrk-store"
+
+func storeObjectPath(projectRoot, hash string) string {
+	return filepath.Join(projectRoot, blobStore, hash)
+}
+
+// storeEnabled reports whether the project keeps its originals in the blob
+// store.
+func storeEnabled(ctx *context.Context) bool {
+	v, _ := ctx.ProjectDb.GetConfig("store")
+	return v != nil && *v == "on"
+}
+
+// keptCopy returns where a copy of the content with hash is kept, in the
+// version store or the blob store, or "" when neither holds one.
+func keptCopy(projectRoot, hash string) string {
+	for _, obj := range []string{versionObjectPath(projectRoot, hash), storeObjectPath(projectRoot, hash)} {
+		if fileExists(obj) {
+			return obj
+		}
+	}
+	return ""
+}
+
+// storeOriginal copies the file at absPath into the blob store, unless
+// the store already holds its content, and links the file to the stored
+// copy so the content is on disk once.
+func storeOriginal(ctx *context.Context, alg integrity.Algorithm, absPath, hash string, protection models.ProtectionLevel) error {
+	obj := storeObjectPath(ctx.ProjectRoot, hash)
+	if !fileExists(obj) {
+		if err := copyVerified(ctx, alg, absPath, obj, hash); err != nil {
+			return err
+		}
+		os.Chmod(obj, 0o444)
+	}
+	linkToStore(obj, absPath, protection == models.ProtectionImmutable)
+	return nil
+}
+
+// linkToStore replaces the file at absPath, which holds the same content
+// as obj, with a link to it. Immutable files become hardlinks, since
+// neither name may change again; other files become reflinks, which share
+// blocks until one side is written. Where neither is possible the file
+// stays a copy of its own. It keeps its mode and modification time.
+func linkToStore(obj, absPath string, hard bool) {
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(absPath), ".mkrk-link-*")
+	if err != nil {
+		return
+	}
+	tmp.Close()
+	os.Remove(tmp.Name())
+
+	switch {
+	case hard && os.Link(obj, tmp.Name()) == nil:
+	case integrity.Clone(obj, tmp.Name()) == nil:
+		os.Chmod(tmp.Name(), info.Mode().Perm())
+	default:
+		return
+	}
+	os.Chtimes(tmp.Name(), info.ModTime(), info.ModTime())
+	if err := os.Rename(tmp.Name(), absPath); err != nil {
+		os.Remove(tmp.Name())
+	}
+}
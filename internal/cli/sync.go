@@ -1,16 +1,21 @@
 package cli
 
 import (
+	"encoding/json"
 	"flag"
 	"fmt"
 	"os"
 	"path/filepath"
+	"runtime"
 	"strings"
+	"sync"
+	"sync/atomic"
 	"time"
 
 	"golang.org/x/term"
 
 	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
 	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/materialize"
 	"go.foia.dev/muckrake/internal/models"
@@ -32,6 +37,9 @@ type syncCounts struct {
 	modified int
 	missing  int
 	other    int
+	skipped  int
+	// quick counts the ok files --quick trusted by their stat data
+	quick int
 }
 
 func RunSync(ctx *context.Context, args []string) error {
@@ -40,14 +48,23 @@ func RunSync(ctx *context.Context, args []string) error {
 	fs.BoolVar(quiet, "q", false, "shorthand for --quiet")
 	dryRun := fs.Bool("dry-run", false, "show what would happen without making changes")
 	fs.BoolVar(dryRun, "n", false, "shorthand for --dry-run")
+	jobs := fs.Int("jobs", runtime.NumCPU(), "number of files to hash in parallel")
+	fs.IntVar(jobs, "j", runtime.NumCPU(), "shorthand for --jobs")
+	quick := fs.Bool("quick", false, "only rehash files whose size, mtime or inode changed since they were last hashed")
+	symlinks := fs.String("symlinks", "", "symlink policy: skip, follow or link (default: project setting, else skip)")
 	fs.Parse(args)
 
 	if ctx.Kind != context.ContextProject {
 		return fmt.Errorf("not in a project")
 	}
 
-	interactive := !*quiet && !*dryRun && term.IsTerminal(int(os.Stdin.Fd()))
-	dry := *dryRun
+	policy, err := symlinkPolicy(ctx, *symlinks)
+	if err != nil {
+		return err
+	}
+
+	dry := *dryRun || ctx.DryRun
+	interactive := !*quiet && !dry && term.IsTerminal(int(os.Stdin.Fd()))
 
 	projectName := ""
 	if ctx.ProjectName != nil {
@@ -68,104 +85,73 @@ func RunSync(ctx *context.Context, args []string) error {
 	if err != nil {
 		return err
 	}
+	if err := sealNewFiles(ctx, entries, projectName, dry); err != nil {
+		return err
+	}
 
-	allFiles, _ := ctx.ProjectDb.ListAllFiles()
-	seen := make(map[string]bool)
-
-	var counts syncCounts
-	var conflicts []syncConflict
-
-	for _, relPath := range entries {
-		absPath := filepath.Join(ctx.ProjectRoot, relPath)
-		hash, fp, err := integrity.HashAndFingerprint(absPath)
-		if err != nil {
-			ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
-			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
-			continue
-		}
+	hasher, err := ctx.Hasher()
+	if err != nil {
+		return err
+	}
 
-		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	pass := &syncPass{
+		ctx:         ctx,
+		projectName: projectName,
+		allFiles:    allFiles,
+		categories:  categories,
+		seen:        make(map[string]bool),
+		tracked:     make(map[string]string),
+		dry:         dry,
+	}
 
-		// Exact fingerprint match
-		if file, _ := ctx.ProjectDb.GetFileByFingerprint(fp.ToJSON()); file != nil {
-			seen[file.SHA256] = true
-			if !dry {
-				checkImmutableFlag(ctx, absPath, relPath, ref, &counts)
-			}
-			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", ref)
-			counts.ok++
-			continue
-		}
+	run := &hashRun{
+		hasher:   hasher,
+		jobs:     *jobs,
+		useCache: *quick,
+		symlinks: policy,
+		show:     !*quiet && term.IsTerminal(int(os.Stderr.Fd())),
+		total:    len(entries),
+		inodes:   make(map[integrity.FileKey]hardlink),
+	}
 
-		// Hash match — tracked but fingerprint stale, update it
-		if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil {
-			seen[hash] = true
-			if file.ID != nil && !dry {
-				ctx.ProjectDb.UpdateFileFingerprint(*file.ID, fp.ToJSON())
-			}
-			if !dry {
-				checkImmutableFlag(ctx, absPath, relPath, ref, &counts)
+	// Hash and record in checkpoints of syncCheckpoint files, each in one
+	// transaction. An interrupted sync keeps what it already recorded, and
+	// with --quick the next run skips those files without rehashing.
+	projectDb := ctx.ProjectDb
+	for start := 0; start < len(entries); start += syncCheckpoint {
+		chunk := entries[start:min(start+syncCheckpoint, len(entries))]
+		hashed := hashEntries(ctx, chunk, run)
+		err = projectDb.Batch(func(batch *db.ProjectDb) error {
+			ctx.ProjectDb = batch
+			defer func() { ctx.ProjectDb = projectDb }()
+			for i, relPath := range chunk {
+				pass.entry(relPath, hashed[i])
 			}
-			label := "fingerprint updated"
-			if dry {
-				label = "would update fingerprint"
-			}
-			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s \033[36m(%s)\033[0m\n", ref, label)
-			counts.ok++
-			continue
-		}
-
-		// Partial fingerprint match — conflict, needs resolution
-		if match := findPartialMatchFile(allFiles, fp); match != nil {
-			conflicts = append(conflicts, syncConflict{
-				relPath:   relPath,
-				ref:       ref,
-				diskFp:    fp,
-				diskHash:  hash,
-				matchFile: match,
-			})
-			continue
-		}
-
-		// No match — new file, ingest
-		if dry {
-			fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s \033[36m(would ingest)\033[0m\n", ref)
-			counts.ingested++
-			continue
-		}
-
-		file := &models.TrackedFile{
-			SHA256:      hash,
-			Fingerprint: fp.ToJSON(),
-			IngestedAt:  time.Now().UTC().Format(time.RFC3339),
-		}
-		fileID, err := ctx.ProjectDb.InsertFile(file)
+			return nil
+		})
 		if err != nil {
-			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
-			continue
+			return err
 		}
-		_ = fileID
-		seen[hash] = true
-
-		matchingCats := matchingCategories(relPath, categories)
-		materialize.MaterializeForFile(ctx.ProjectDb, relPath, hash, matchingCats, nil)
-
-		protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
-		enforceImmutable(absPath, protection, ref)
-
-		fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", ref)
-		counts.ingested++
 	}
+	counts, seen := pass.counts, pass.seen
 
 	// Resolve conflicts
-	if len(conflicts) > 0 {
-		resolveConflicts(ctx, &counts, conflicts, categories, interactive, dry, projectName)
+	if len(pass.conflicts) > 0 {
+		resolveConflicts(ctx, &counts, pass.conflicts, categories, interactive, dry, projectName)
+	}
+
+	fireIngestRules(ctx, pass.ingested, categories, dry)
+
+	if !dry {
+		indexSynced(ctx, pass.tracked, entries)
 	}
 
-	// Check for missing files
+	// Check for missing files; those in the trash are gone on purpose.
+	trashed, _ := ctx.ProjectDb.TrashedFileIDs()
 	var missingRefs []string
 	for _, f := range allFiles {
-		if !seen[f.SHA256] {
+		if !seen[f.SHA256] && (f.ID == nil || !trashed[*f.ID]) {
 			hashPreview := f.SHA256[:min(len(f.SHA256), 10)]
 			missingRefs = append(missingRefs, hashPreview)
 			counts.missing++
@@ -186,8 +172,15 @@ func RunSync(ctx *context.Context, args []string) error {
 	if dry {
 		prefix = "Dry run"
 	}
-	fmt.Fprintf(os.Stderr, "%s: %d ok, %d new, %d modified, %d missing, %d other\n",
+	fmt.Fprintf(os.Stderr, "%s: %d ok, %d new, %d modified, %d missing, %d other",
 		prefix, counts.ok, counts.ingested, counts.modified, counts.missing, counts.other)
+	if counts.skipped > 0 {
+		fmt.Fprintf(os.Stderr, ", %d skipped", counts.skipped)
+	}
+	fmt.Fprintln(os.Stderr)
+	if counts.quick > 0 {
+		fmt.Fprintf(os.Stderr, "  %d file(s) unchanged since last hashed were not reread; sync without --quick to rehash them\n", counts.quick)
+	}
 
 	exitCode := counts.other
 	if exitCode > 0 {
@@ -199,6 +192,394 @@ func RunSync(ctx *context.Context, args []string) error {
 	return nil
 }
 
+// syncCheckpoint is how many files sync records per transaction.
+const syncCheckpoint = 500
+
+// hashResult is the precomputed hash and fingerprint of one sync entry.
+// Cached results carry only the hash: the file's size, mtime and inode
+// match the last sync that recorded it, so its content is assumed
+// unchanged.
+type hashResult struct {
+	hash   string
+	fp     *integrity.Fingerprint
+	size   int64
+	mtime  int64
+	inode  int64
+	cached bool
+	err    error
+	// skipped explains why the entry is not tracked, e.g. a skipped symlink.
+	skipped string
+	// hardlinkOf is an earlier path to the same inode in this sync.
+	hardlinkOf string
+	linkKey    integrity.FileKey
+	// provenance records how links were handled, for newly ingested files.
+	provenance *string
+}
+
+// hashRun carries the settings and state shared by the hashEntries calls
+// of one sync.
+type hashRun struct {
+	hasher   integrity.Hasher
+	jobs     int
+	useCache bool
+	symlinks string
+	show     bool
+	total    int
+	done     atomic.Int64
+	// inodes maps each multiply-linked inode to the first path seen for it.
+	inodes map[integrity.FileKey]hardlink
+}
+
+type hardlink struct {
+	path   string
+	result *hashResult
+}
+
+// hashEntries hashes and fingerprints entries using up to run.jobs workers.
+// Symlinks are handled per run.symlinks, later hardlinks to an inode
+// already seen reuse its result, and files whose size and mtime match the
+// hash cache are not reread when run.useCache is set. Results are returned
+// in entry order. While hashing, a progress bar is drawn on stderr if
+// run.show is set.
+func hashEntries(ctx *context.Context, entries []string, run *hashRun) []hashResult {
+	results := make([]hashResult, len(entries))
+	var pending []int
+	var linked []int
+	for i, relPath := range entries {
+		r := &results[i]
+		if hash := run.prepare(ctx, relPath, r); hash {
+			pending = append(pending, i)
+		} else if r.hardlinkOf != "" {
+			linked = append(linked, i)
+		} else {
+			run.done.Add(1)
+		}
+	}
+
+	indexes := make(chan int)
+	var wg sync.WaitGroup
+	for range min(max(run.jobs, 1), max(len(pending), 1)) {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			for i := range indexes {
+				r := &results[i]
+				r.hash, r.fp, r.err = hashOrStub(ctx, run.hasher, filepath.Join(ctx.ProjectRoot, entries[i]))
+				run.done.Add(1)
+			}
+		}()
+	}
+
+	stop := make(chan struct{})
+	var drawn sync.WaitGroup
+	if run.show && len(pending) > 0 {
+		drawn.Add(1)
+		go func() {
+			defer drawn.Done()
+			ticker := time.NewTicker(100 * time.Millisecond)
+			defer ticker.Stop()
+			for {
+				select {
+				case <-stop:
+					fmt.Fprint(os.Stderr, "\r\033[K")
+					return
+				case <-ticker.C:
+					drawProgress(int(run.done.Load()), run.total)
+				}
+			}
+		}()
+	}
+
+	for _, i := range pending {
+		indexes <- i
+	}
+	close(indexes)
+	wg.Wait()
+	close(stop)
+	drawn.Wait()
+
+	for _, i := range linked {
+		first := run.inodes[results[i].linkKey]
+		results[i] = *first.result
+		results[i].hardlinkOf = first.path
+		results[i].provenance = nil
+		run.done.Add(1)
+	}
+	return results
+}
+
+// prepare stats relPath and fills in whatever can be known without reading
+// the file. It reports whether the file still needs hashing.
+func (run *hashRun) prepare(ctx *context.Context, relPath string, r *hashResult) bool {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	info, err := os.Lstat(absPath)
+	if err != nil {
+		r.err = err
+		return false
+	}
+
+	if info.Mode()&os.ModeSymlink != 0 {
+		switch run.symlinks {
+		case "skip":
+			r.skipped = "symlink skipped"
+			return false
+		case "link":
+			target, err := os.Readlink(absPath)
+			if err != nil {
+				r.err = err
+				return false
+			}
+			r.hash, r.fp, r.err = run.hasher.HashAndFingerprintReader(strings.NewReader(target))
+			r.provenance = ingestProvenance(map[string]any{"symlink": "link"})
+			return false
+		}
+		if info, err = os.Stat(absPath); err != nil {
+			r.err = err
+			return false
+		}
+		if info.IsDir() {
+			r.skipped = "symlink to a directory, not followed"
+			return false
+		}
+		r.provenance = ingestProvenance(map[string]any{"symlink": "follow"})
+	}
+	r.size, r.mtime = info.Size(), info.ModTime().UnixNano()
+
+	key, nlink, ok := integrity.LinkInfo(info)
+	if ok {
+		r.inode = int64(key.Ino)
+	}
+	if ok && nlink > 1 {
+		if first, seen := run.inodes[key]; seen {
+			r.hardlinkOf = first.path
+			r.linkKey = key
+			return false
+		}
+		run.inodes[key] = hardlink{path: relPath, result: r}
+		if r.provenance == nil {
+			r.provenance = ingestProvenance(map[string]any{"hardlinks": nlink})
+		}
+	}
+
+	if run.useCache {
+		if hash, _ := ctx.ProjectDb.CachedHash(filepath.ToSlash(relPath), r.size, r.mtime, r.inode); hash != "" {
+			if f, _ := ctx.ProjectDb.GetFileByHash(hash); f != nil {
+				r.hash, r.cached = hash, true
+				return false
+			}
+		}
+	}
+	return true
+}
+
+// ingestProvenance encodes how a file was reached for its provenance field.
+// Like the rest of the database it must not name paths, so link targets
+// are left out.
+func ingestProvenance(fields map[string]any) *string {
+	b, _ := json.Marshal(fields)
+	s := string(b)
+	return &s
+}
+
+func drawProgress(n, total int) {
+	const width = 30
+	filled := width * n / total
+	fmt.Fprintf(os.Stderr, "\r\033[K  Hashing %d/%d [%s%s] %d%%",
+		n, total, strings.Repeat("=", filled), strings.Repeat(" ", width-filled), 100*n/total)
+}
+
+// syncPass carries the state shared by the entries of one sync.
+type syncPass struct {
+	ctx         *context.Context
+	projectName string
+	allFiles    []models.TrackedFile
+	categories  []models.Scope
+	seen        map[string]bool
+	// tracked maps each path found to hold a tracked file to its hash,
+	// for the workspace's file index.
+	tracked   map[string]string
+	dry       bool
+	counts    syncCounts
+	conflicts []syncConflict
+	// ingested lists new files, whose ingest rules fire once all entries
+	// are recorded.
+	ingested []ingested
+}
+
+// entry classifies one file from its precomputed hash and records the
+// outcome: ok, fingerprint refresh, partial-match conflict, or ingest.
+func (p *syncPass) entry(relPath string, h hashResult) {
+	ref := reference.FormatRef(relPath, p.projectName, p.ctx.ProjectDb)
+	if h.err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, h.err)
+		return
+	}
+	if h.skipped != "" {
+		fmt.Fprintf(os.Stderr, "  - %s \033[36m(%s)\033[0m\n", ref, h.skipped)
+		p.counts.skipped++
+		return
+	}
+	absPath := filepath.Join(p.ctx.ProjectRoot, relPath)
+	hash, fp := h.hash, h.fp
+
+	// Another name for an inode already handled in this sync
+	if h.hardlinkOf != "" {
+		p.seen[hash] = true
+		p.tracked[relPath] = hash
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s \033[36m(hardlink of %s)\033[0m\n", ref, h.hardlinkOf)
+		p.counts.ok++
+		return
+	}
+
+	// Unchanged since the last sync recorded it at this path
+	if h.cached {
+		p.seen[hash] = true
+		p.tracked[relPath] = hash
+		if !p.dry {
+			checkImmutableFlag(p.ctx, absPath, relPath, ref, &p.counts)
+			if file, _ := p.ctx.ProjectDb.GetFileByHash(hash); file != nil {
+				p.backfillSize(file, absPath)
+			}
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", ref)
+		p.counts.ok++
+		p.counts.quick++
+		return
+	}
+
+	// Exact fingerprint match
+	if file, _ := p.ctx.ProjectDb.GetFileByFingerprint(fp.ToJSON()); file != nil {
+		p.seen[file.SHA256] = true
+		p.tracked[relPath] = file.SHA256
+		if !p.dry {
+			checkImmutableFlag(p.ctx, absPath, relPath, ref, &p.counts)
+			p.remember(relPath, file.SHA256, h)
+			p.backfillSize(file, absPath)
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", ref)
+		p.counts.ok++
+		return
+	}
+
+	// Hash match — tracked but fingerprint stale, update it
+	if file, _ := p.ctx.ProjectDb.GetFileByHash(hash); file != nil {
+		p.seen[hash] = true
+		p.tracked[relPath] = hash
+		if file.ID != nil && !p.dry {
+			p.ctx.ProjectDb.UpdateFileFingerprint(*file.ID, fp.ToJSON())
+		}
+		if !p.dry {
+			checkImmutableFlag(p.ctx, absPath, relPath, ref, &p.counts)
+			p.remember(relPath, hash, h)
+			p.backfillSize(file, absPath)
+		}
+		label := "fingerprint updated"
+		if p.dry {
+			label = "would update fingerprint"
+		}
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s \033[36m(%s)\033[0m\n", ref, label)
+		p.counts.ok++
+		return
+	}
+
+	// Partial fingerprint match — conflict, needs resolution, unless this
+	// is an edited copy-out working copy, which is a new file of its own
+	if match := findPartialMatchFile(p.allFiles, fp); match != nil && !isWorkingCopy(p.ctx, relPath) {
+		p.conflicts = append(p.conflicts, syncConflict{
+			relPath:   relPath,
+			ref:       ref,
+			diskFp:    fp,
+			diskHash:  hash,
+			matchFile: match,
+		})
+		return
+	}
+
+	// No match — new file, ingest
+	if p.dry {
+		fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s \033[36m(would ingest)\033[0m\n", ref)
+		p.counts.ingested++
+		p.ingested = append(p.ingested, ingested{relPath: relPath, ref: ref, hash: hash})
+		return
+	}
+
+	if err := ingestNew(p.ctx, relPath, ref, hash, fp, h.provenance, p.categories); err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", ref, err)
+		return
+	}
+	p.seen[hash] = true
+	p.tracked[relPath] = hash
+	// Linking the file to the blob store gave it a new inode.
+	if st, err := statFile(absPath); err == nil && st.size == h.size && st.mtime == h.mtime {
+		h.inode = st.inode
+	}
+	p.remember(relPath, hash, h)
+
+	fmt.Fprintf(os.Stderr, "  \033[32m+\033[0m %s\n", ref)
+	p.counts.ingested++
+	p.ingested = append(p.ingested, ingested{relPath: relPath, ref: ref, hash: hash})
+}
+
+// backfillSize records the size of a file tracked before sizes were
+// recorded, so size predicates in references can match it.
+func (p *syncPass) backfillSize(file *models.TrackedFile, absPath string) {
+	if file.ID == nil || file.Size != nil {
+		return
+	}
+	if size := fileSize(absPath); size != nil {
+		p.ctx.ProjectDb.UpdateFileSize(*file.ID, *size)
+	}
+}
+
+// remember caches the hash recorded for relPath so later sync --quick and
+// verify --quick runs can skip rehashing it while its size, mtime and inode
+// are unchanged.
+func (p *syncPass) remember(relPath, hash string, h hashResult) {
+	p.ctx.ProjectDb.PutCachedHash(filepath.ToSlash(relPath), h.size, h.mtime, h.inode, hash)
+}
+
+// ingestNew records a file not yet known to the project along with its
+// embedded metadata, materializes its pipeline and ruleset subscriptions,
+// and applies the immutable flag when its category requires it.
+func ingestNew(ctx *context.Context, relPath, ref, hash string, fp *integrity.Fingerprint, provenance *string, categories []models.Scope) error {
+	file := &models.TrackedFile{
+		SHA256:      hash,
+		Fingerprint: fp.ToJSON(),
+		MimeType:    detectMime(filepath.Join(ctx.ProjectRoot, relPath)),
+		Size:        fileSize(filepath.Join(ctx.ProjectRoot, relPath)),
+		IngestedAt:  time.Now().UTC().Format(time.RFC3339),
+		Provenance:  provenance,
+	}
+	fileID, err := ctx.ProjectDb.InsertFile(file)
+	if err != nil {
+		return err
+	}
+	if err := storeVersion(ctx, fileID, relPath, hash); err != nil {
+		fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m %s: %v\n", ref, err)
+	}
+	recordMetadata(ctx, fileID, relPath)
+
+	matchingCats := matchingCategories(relPath, categories)
+	materialize.MaterializeForFile(ctx.ProjectDb, relPath, hash, matchingCats, nil)
+
+	linkWorkingCopy(ctx, relPath, ref, fileID, hash, fp)
+
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	enforceImmutable(filepath.Join(ctx.ProjectRoot, relPath), protection, ref)
+	return nil
+}
+
+// fileSize returns the size of the file at absPath, or nil if it cannot be
+// read.
+func fileSize(absPath string) *int64 {
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return nil
+	}
+	size := info.Size()
+	return &size
+}
+
 func resolveConflicts(
 	ctx *context.Context,
 	counts *syncCounts,
@@ -248,6 +629,8 @@ func resolveConflicts(
 				file := &models.TrackedFile{
 					SHA256:      c.diskHash,
 					Fingerprint: c.diskFp.ToJSON(),
+					MimeType:    detectMime(filepath.Join(ctx.ProjectRoot, c.relPath)),
+					Size:        fileSize(filepath.Join(ctx.ProjectRoot, c.relPath)),
 					IngestedAt:  time.Now().UTC().Format(time.RFC3339),
 				}
 				fileID, err := ctx.ProjectDb.InsertFile(file)
@@ -255,9 +638,13 @@ func resolveConflicts(
 					fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", c.ref, err)
 					continue
 				}
-				_ = fileID
+				if err := storeVersion(ctx, fileID, c.relPath, c.diskHash); err != nil {
+					fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m %s: %v\n", c.ref, err)
+				}
+				recordMetadata(ctx, fileID, c.relPath)
 				matchingCats := matchingCategories(c.relPath, categories)
 				materialize.MaterializeForFile(ctx.ProjectDb, c.relPath, c.diskHash, matchingCats, nil)
+				indexFile(ctx, c.relPath, c.diskHash)
 			}
 			label := "(new)"
 			if dry {
@@ -269,8 +656,12 @@ func resolveConflicts(
 		case "m", "modified":
 			if !dry {
 				if c.matchFile != nil && c.matchFile.ID != nil {
+					if err := recordRevision(ctx, c.matchFile, c.relPath, c.diskHash); err != nil {
+						fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m %s: %v\n", c.ref, err)
+					}
 					ctx.ProjectDb.UpdateFileFingerprint(*c.matchFile.ID, c.diskFp.ToJSON())
 					ctx.ProjectDb.UpdateFileSHA256(*c.matchFile.ID, c.diskHash)
+					indexFile(ctx, c.relPath, c.diskHash)
 				}
 			}
 			label := "(modified)"
@@ -319,7 +710,7 @@ func findPartialMatchFile(allFiles []models.TrackedFile, diskFp *integrity.Finge
 }
 
 func enforceImmutable(absPath string, protection models.ProtectionLevel, ref string) {
-	if protection == models.ProtectionImmutable {
+	if protection == models.ProtectionImmutable && !isSymlink(absPath) {
 		if err := integrity.SetImmutable(absPath); err != nil {
 			fmt.Fprintf(os.Stderr, "  \033[33m!\033[0m %s: could not set immutable: %v\n", ref, err)
 		}
@@ -327,6 +718,9 @@ func enforceImmutable(absPath string, protection models.ProtectionLevel, ref str
 }
 
 func checkImmutableFlag(ctx *context.Context, absPath, relPath, ref string, counts *syncCounts) {
+	if isSymlink(absPath) {
+		return
+	}
 	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
 	isImmutable, _ := integrity.IsImmutable(absPath)
 
@@ -345,6 +739,31 @@ func checkImmutableFlag(ctx *context.Context, absPath, relPath, ref string, coun
 	}
 }
 
+// isSymlink reports whether path is a symlink. Links carry no immutable
+// flag of their own, and the project does not own what they point to.
+func isSymlink(path string) bool {
+	info, err := os.Lstat(path)
+	return err == nil && info.Mode()&os.ModeSymlink != 0
+}
+
+// symlinkPolicy resolves how sync treats symlinks: the flag value if given,
+// else the project's "symlinks" setting, else skip.
+func symlinkPolicy(ctx *context.Context, flagValue string) (string, error) {
+	policy := flagValue
+	if policy == "" {
+		if v, _ := ctx.ProjectDb.GetConfig("symlinks"); v != nil {
+			policy = *v
+		}
+	}
+	switch policy {
+	case "":
+		return "skip", nil
+	case "skip", "follow", "link":
+		return policy, nil
+	}
+	return "", fmt.Errorf("invalid symlink policy %q (expected skip, follow or link)", policy)
+}
+
 func matchingCategories(relPath string, categories []models.Scope) []models.Scope {
 	var matched []models.Scope
 	for _, cat := range categories {
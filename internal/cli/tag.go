@@ -5,10 +5,12 @@ import (
 	"fmt"
 	"os"
 	"path/filepath"
+	"strings"
 
 	"go.foia.dev/muckrake/internal/context"
-	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/db"
 	"go.foia.dev/muckrake/internal/materialize"
+	"go.foia.dev/muckrake/internal/reference"
 	"go.foia.dev/muckrake/internal/resolve"
 )
 
@@ -16,77 +18,142 @@ func RunTag(ctx *context.Context, args []string) error {
 	fs := flag.NewFlagSet("tag", flag.ExitOnError)
 	remove := fs.Bool("remove", false, "remove tag instead of adding")
 	fs.BoolVar(remove, "r", false, "shorthand for --remove")
-	fs.Parse(args)
+	var tagFlags stringList
+	fs.Var(&tagFlags, "tag", "tag to add or remove (repeatable)")
+	positional := parseInterspersed(fs, args)
 
 	if ctx.Kind != context.ContextProject {
 		return fmt.Errorf("not in a project")
 	}
 
-	paths, tagName, err := tagTargets(ctx, fs.Args())
+	paths, tagNames, err := tagTargets(ctx, positional, tagFlags)
 	if err != nil {
 		return err
 	}
 	if len(paths) == 0 {
 		return fmt.Errorf("no files matched")
 	}
-
-	categories, _ := ctx.ProjectDb.ListCategories()
-
-	for _, relPath := range paths {
-		absPath := filepath.Join(ctx.ProjectRoot, relPath)
-		hash, fp, err := integrity.HashAndFingerprint(absPath)
-		if err != nil {
-			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
-			continue
+	if !*remove {
+		for _, tagName := range tagNames {
+			if err := reference.ValidateTag(tagName); err != nil {
+				return err
+			}
 		}
+	}
 
-		file, err := ctx.ProjectDb.GetFileByHash(hash)
-		if err != nil || file == nil || file.ID == nil {
-			fmt.Fprintf(os.Stderr, "  ! %s: not tracked (run sync first)\n", relPath)
-			continue
-		}
+	categories, _ := ctx.ProjectDb.ListCategories()
 
-		if *remove {
-			if err := ctx.ProjectDb.RemoveTag(*file.ID, tagName); err != nil {
+	changed, failed := 0, 0
+	// One transaction for the whole set, so tagging a large selection does
+	// not commit file by file.
+	projectDb := ctx.ProjectDb
+	err = projectDb.Batch(func(batch *db.ProjectDb) error {
+		ctx.ProjectDb = batch
+		defer func() { ctx.ProjectDb = projectDb }()
+		for _, relPath := range paths {
+			absPath := filepath.Join(ctx.ProjectRoot, relPath)
+			hash, fp, err := ctx.HashAndFingerprint(absPath)
+			if err != nil {
 				fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+				failed++
 				continue
 			}
-			fmt.Fprintf(os.Stderr, "  - %s !%s\n", relPath, tagName)
-		} else {
-			if err := ctx.ProjectDb.InsertTag(*file.ID, tagName, hash, fp.ToJSON()); err != nil {
-				fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+
+			file, err := ctx.ProjectDb.GetFileByHash(hash)
+			if err != nil || file == nil || file.ID == nil {
+				fmt.Fprintf(os.Stderr, "  ! %s: not tracked (run sync first)\n", relPath)
+				failed++
 				continue
 			}
-			fmt.Fprintf(os.Stderr, "  + %s !%s\n", relPath, tagName)
-		}
 
-		tags, _ := ctx.ProjectDb.GetTags(*file.ID)
-		matchingCats := matchingCategories(relPath, categories)
-		materialize.MaterializeForFile(ctx.ProjectDb, relPath, hash, matchingCats, tags)
+			ok := true
+			for _, tagName := range tagNames {
+				if *remove {
+					if err := ctx.ProjectDb.RemoveTag(*file.ID, tagName); err != nil {
+						fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+						ok = false
+						continue
+					}
+					fmt.Fprintf(os.Stderr, "  - %s !%s\n", relPath, tagName)
+				} else {
+					if err := ctx.ProjectDb.InsertTag(*file.ID, tagName, hash, fp.ToJSON()); err != nil {
+						fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+						ok = false
+						continue
+					}
+					fmt.Fprintf(os.Stderr, "  + %s !%s\n", relPath, tagName)
+				}
+			}
+			if ok {
+				changed++
+			} else {
+				failed++
+			}
+
+			tags, _ := ctx.ProjectDb.GetTags(*file.ID)
+			matchingCats := matchingCategories(relPath, categories)
+			materialize.MaterializeForFile(ctx.ProjectDb, relPath, hash, matchingCats, tags)
+		}
+		return nil
+	})
+	if err != nil {
+		return err
 	}
 
+	if len(paths) > 1 || len(tagNames) > 1 {
+		verb := "Tagged"
+		if *remove {
+			verb = "Untagged"
+		}
+		fmt.Fprintf(os.Stderr, "%s %d file(s) with %s", verb, changed, strings.Join(tagNames, ", "))
+		if failed > 0 {
+			fmt.Fprintf(os.Stderr, ", %d failed", failed)
+		}
+		fmt.Fprintln(os.Stderr)
+	}
 	return nil
 }
 
-// tagTargets picks the file set to tag. With a subject the positional args
-// are just (tag), otherwise they are (reference, tag).
-func tagTargets(ctx *context.Context, args []string) ([]string, string, error) {
+// tagTargets picks the file set and tags. Tags given with --tag make every
+// positional argument a reference; otherwise the positional args are
+// (tag) with a subject and (reference, tag) without.
+func tagTargets(ctx *context.Context, args []string, tagFlags []string) ([]string, []string, error) {
 	if resolve.HasNarrowSubject(ctx) {
-		if len(args) < 1 {
-			return nil, "", fmt.Errorf("usage: mkrk :<ref> tag [--remove] <tag>")
+		tags := append(args, tagFlags...)
+		if len(tags) < 1 {
+			return nil, nil, fmt.Errorf("usage: mkrk :<ref> tag [--remove] <tag>")
 		}
 		rels, err := resolve.SubjectRelPaths(ctx)
 		if err != nil {
-			return nil, "", err
+			return nil, nil, err
 		}
-		return rels, args[0], nil
+		return rels, tags, nil
 	}
-	if len(args) < 2 {
-		return nil, "", fmt.Errorf("usage: mkrk tag [--remove] <reference> <tag>")
+
+	refs, tags := args, []string(tagFlags)
+	if len(tags) == 0 {
+		if len(args) < 2 {
+			return nil, nil, fmt.Errorf("usage: mkrk tag [--remove] <reference> <tag>\n       mkrk tag [--remove] <reference>... --tag <tag> [--tag <tag>]...")
+		}
+		refs, tags = args[:1], args[1:2]
 	}
-	rels, err := resolve.RefRelPaths(ctx, args[0])
-	if err != nil {
-		return nil, "", err
+	if len(refs) == 0 {
+		return nil, nil, fmt.Errorf("usage: mkrk tag [--remove] <reference>... --tag <tag> [--tag <tag>]...")
+	}
+
+	var all []string
+	seen := make(map[string]bool)
+	for _, raw := range refs {
+		rels, err := resolve.RefRelPaths(ctx, raw)
+		if err != nil {
+			return nil, nil, err
+		}
+		for _, rel := range rels {
+			if !seen[rel] {
+				seen[rel] = true
+				all = append(all, rel)
+			}
+		}
 	}
-	return rels, args[1], nil
+	return all, tags, nil
 }
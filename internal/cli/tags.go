@@ -0,0 +1,94 @@
+package cli
+
+import (
+	"fmt"
+	"sort"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/context"
+)
+
+const tagsUsage = `usage: mkrk tags [list]
+       mkrk tags tree`
+
+// RunTags lists the tags in use with the number of files carrying each.
+// tree groups namespaced tags (source/whistleblower) under their
+// namespaces, counting each file once per namespace.
+func RunTags(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	sub := "list"
+	if len(args) > 0 {
+		sub = args[0]
+	}
+	if len(args) > 1 {
+		return fmt.Errorf("%s", tagsUsage)
+	}
+
+	tagged, err := ctx.ProjectDb.ListTaggedFiles()
+	if err != nil {
+		return err
+	}
+	if len(tagged) == 0 {
+		fmt.Println("(no tags)")
+		return nil
+	}
+
+	switch sub {
+	case "list", "ls":
+		names := make([]string, 0, len(tagged))
+		for tag := range tagged {
+			names = append(names, tag)
+		}
+		sort.Strings(names)
+		for _, tag := range names {
+			fmt.Printf("%s (%d)\n", tag, len(tagged[tag]))
+		}
+	case "tree":
+		printTagTree(buildTagTree(tagged), 0)
+	default:
+		return fmt.Errorf("%s", tagsUsage)
+	}
+	return nil
+}
+
+// tagNode is one namespace segment in the tag tree, holding every file
+// tagged with it or anything beneath it.
+type tagNode struct {
+	name     string
+	files    map[int64]bool
+	children map[string]*tagNode
+}
+
+func buildTagTree(tagged map[string][]int64) *tagNode {
+	root := &tagNode{children: map[string]*tagNode{}}
+	for tag, ids := range tagged {
+		node := root
+		for _, seg := range strings.Split(tag, "/") {
+			child := node.children[seg]
+			if child == nil {
+				child = &tagNode{name: seg, files: map[int64]bool{}, children: map[string]*tagNode{}}
+				node.children[seg] = child
+			}
+			for _, id := range ids {
+				child.files[id] = true
+			}
+			node = child
+		}
+	}
+	return root
+}
+
+func printTagTree(node *tagNode, depth int) {
+	names := make([]string, 0, len(node.children))
+	for name := range node.children {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	for _, name := range names {
+		child := node.children[name]
+		fmt.Printf("%s%s (%d)\n", strings.Repeat("  ", depth), name, len(child.files))
+		printTagTree(child, depth+1)
+	}
+}
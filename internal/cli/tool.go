@@ -0,0 +1,249 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/generator"
+	"go.foia.dev/muckrake/internal/integrity"
+)
+
+const toolUsage = `usage: mkrk tool add <script> [--name <name>] [--description <text>] --workspace
+       mkrk tool list --workspace
+       mkrk tool remove <name> --workspace`
+
+// workspaceToolsDir is the directory, at the workspace root, that
+// workspace tools are installed into.
+const workspaceToolsDir = "tools"
+
+// RunWorkspaceTool manages the workspace's tool registry: scripts copied
+// into the shared tools directory with the hash they had when added.
+// Every project in the workspace can run them, after its own tools, and
+// a tool whose script no longer matches its hash is refused.
+func RunWorkspaceTool(args []string) error {
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	defer ctx.Close()
+	if ctx.Workspace == nil || ctx.Workspace.Db == nil {
+		return fmt.Errorf("not in a workspace")
+	}
+	args = slices.DeleteFunc(slices.Clone(args), func(a string) bool { return a == "--workspace" })
+	if len(args) == 0 {
+		return fmt.Errorf("%s", toolUsage)
+	}
+
+	switch args[0] {
+	case "add":
+		return toolAdd(ctx.Workspace, args[1:])
+	case "list", "ls":
+		return toolList(ctx.Workspace)
+	case "remove", "rm":
+		return toolRemove(ctx.Workspace, args[1:])
+	}
+	return fmt.Errorf("%s", toolUsage)
+}
+
+func toolAdd(ws *context.WorkspaceContext, args []string) error {
+	fs := flag.NewFlagSet("tool add", flag.ExitOnError)
+	name := fs.String("name", "", "name to run the tool by (default: the script's name without extension)")
+	description := fs.String("description", "", "what the tool does")
+	pos := parseInterspersed(fs, args)
+	if len(pos) != 1 {
+		return fmt.Errorf("%s", toolUsage)
+	}
+	src, err := filepath.Abs(pos[0])
+	if err != nil {
+		return err
+	}
+	info, err := os.Stat(src)
+	if err != nil {
+		return err
+	}
+	if info.IsDir() {
+		return fmt.Errorf("%s is a directory", pos[0])
+	}
+
+	file := filepath.Base(src)
+	if *name == "" {
+		*name = stripExt(file)
+	}
+	if err := validateToolName(*name); err != nil {
+		return err
+	}
+
+	dir := filepath.Join(ws.Root, workspaceToolsDir)
+	if err := os.MkdirAll(dir, 0o755); err != nil {
+		return err
+	}
+	dst := filepath.Join(dir, file)
+	if existing, _ := ws.Db.GetTool(*name); existing == nil {
+		if taken, _ := toolByFile(ws.Db, file); taken != nil {
+			return fmt.Errorf("%s/%s already belongs to workspace tool '%s'", workspaceToolsDir, file, taken.Name)
+		}
+	}
+	// Re-adding a script already in the tools directory approves its
+	// current contents.
+	if dst != src {
+		if err := copyFile(src, dst); err != nil {
+			return err
+		}
+	}
+	if err := os.Chmod(dst, 0o755); err != nil {
+		return err
+	}
+	hash, err := integrity.HashFile(dst)
+	if err != nil {
+		return err
+	}
+
+	tool := &db.WorkspaceTool{
+		Name:        *name,
+		File:        file,
+		SHA256:      hash,
+		Description: optString(*description),
+		AddedAt:     time.Now().UTC().Format(time.RFC3339),
+	}
+	if err := ws.Db.SetTool(tool); err != nil {
+		return err
+	}
+	user := whoami()
+	detail := fmt.Sprintf(`{"tool":%q,"sha256":%q}`, *name, hash)
+	ws.Db.InsertAudit("tool_add", nil, &user, &detail)
+
+	fmt.Fprintf(os.Stderr, "Added workspace tool '%s' (%s/%s, sha256 %s)\n", *name, workspaceToolsDir, file, hash[:12])
+	return nil
+}
+
+// toolByFile returns the workspace tool installed as file, if any.
+func toolByFile(wdb *db.WorkspaceDb, file string) (*db.WorkspaceTool, error) {
+	tools, err := wdb.ListTools()
+	if err != nil {
+		return nil, err
+	}
+	for i := range tools {
+		if tools[i].File == file {
+			return &tools[i], nil
+		}
+	}
+	return nil, nil
+}
+
+// validateToolName refuses names resolveTool could not look up: built-in
+// @names and reference syntax.
+func validateToolName(name string) error {
+	if name == "" || name[0] == '@' || name[0] == ':' || name[0] == '.' {
+		return fmt.Errorf("invalid tool name '%s'", name)
+	}
+	for _, r := range name {
+		if r == '/' || r == '.' || r == ':' {
+			return fmt.Errorf("invalid tool name '%s'", name)
+		}
+	}
+	return nil
+}
+
+func toolList(ws *context.WorkspaceContext) error {
+	tools, err := ws.Db.ListTools()
+	if err != nil {
+		return err
+	}
+	if len(tools) == 0 {
+		fmt.Println("(no workspace tools)")
+	}
+	for _, t := range tools {
+		status := "ok"
+		if _, err := verifyWorkspaceTool(ws, &t); err != nil {
+			status = "\033[31mmodified\033[0m"
+		}
+		fmt.Printf("%s: %s/%s (sha256 %s, %s)\n", t.Name, workspaceToolsDir, t.File, t.SHA256[:12], status)
+		if t.Description != nil {
+			fmt.Printf("  %s\n", *t.Description)
+		}
+	}
+	return nil
+}
+
+func toolRemove(ws *context.WorkspaceContext, args []string) error {
+	if len(args) != 1 {
+		return fmt.Errorf("%s", toolUsage)
+	}
+	name := args[0]
+	tool, err := ws.Db.GetTool(name)
+	if err != nil {
+		return err
+	}
+	if tool == nil {
+		return fmt.Errorf("no workspace tool '%s'", name)
+	}
+	if _, err := ws.Db.RemoveTool(name); err != nil {
+		return err
+	}
+	if err := os.Remove(filepath.Join(ws.Root, workspaceToolsDir, tool.File)); err != nil && !os.IsNotExist(err) {
+		return err
+	}
+	user := whoami()
+	detail := fmt.Sprintf(`{"tool":%q}`, name)
+	ws.Db.InsertAudit("tool_remove", nil, &user, &detail)
+
+	fmt.Fprintf(os.Stderr, "Removed workspace tool '%s'\n", name)
+	return nil
+}
+
+// verifyWorkspaceTool returns the path of a workspace tool's script once
+// its contents match the hash recorded when it was added.
+func verifyWorkspaceTool(ws *context.WorkspaceContext, t *db.WorkspaceTool) (string, error) {
+	path := filepath.Join(ws.Root, workspaceToolsDir, t.File)
+	hash, err := integrity.HashFile(path)
+	if err != nil {
+		return "", fmt.Errorf("workspace tool '%s': %w", t.Name, err)
+	}
+	if hash != t.SHA256 {
+		return "", fmt.Errorf("workspace tool '%s' has changed since it was added (sha256 %s, recorded %s); re-add it with mkrk tool add %s/%s --workspace",
+			t.Name, hash[:12], t.SHA256[:12], workspaceToolsDir, t.File)
+	}
+	return path, nil
+}
+
+// resolveWorkspaceTool looks name up in the workspace's tool registry, then
+// in those of the workspaces enclosing it, and returns a generator to run
+// it under and its verified path. The path is empty when no workspace has
+// such a tool.
+func resolveWorkspaceTool(name string, ctx *context.Context) (generator.Generator, string, error) {
+	if ctx == nil {
+		return generator.Generator{}, "", nil
+	}
+	for ws := ctx.Workspace; ws != nil; ws = ws.Parent {
+		if ws.Db == nil {
+			continue
+		}
+		tool, err := ws.Db.GetTool(name)
+		if err != nil {
+			return generator.Generator{}, "", err
+		}
+		if tool == nil {
+			continue
+		}
+		path, err := verifyWorkspaceTool(ws, tool)
+		if err != nil {
+			return generator.Generator{}, "", err
+		}
+		gen := generator.Generator{Verb: "tool", Executable: true, ProjectRoot: ctx.ProjectRoot}
+		if ctx.ProjectName != nil {
+			gen.ProjectName = *ctx.ProjectName
+		}
+		return gen, path, nil
+	}
+	return generator.Generator{}, "", nil
+}
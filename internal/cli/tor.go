@@ -0,0 +1,202 @@
+package cli
+
+import (
+	"bufio"
+	stdcontext "context"
+	"crypto/rand"
+	"encoding/hex"
+	"fmt"
+	"io"
+	"net"
+	"net/http"
+	"net/url"
+	"os"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+)
+
+// DefaultTorControl is the default address of Tor's control port, which
+// mkrk tor new-identity talks to.
+const DefaultTorControl = "127.0.0.1:9051"
+
+const torUsage = `usage: mkrk tor new-identity
+       mkrk tor connect <host> <port>`
+
+// circuits hands out the SOCKS credentials that keep destinations apart.
+// Tor puts streams with different credentials on different circuits
+// (IsolateSOCKSAuth, on by default), so no exit node sees requests to two
+// hosts, nor, past the request limit, a long run of requests to one. The
+// session part is random per process, so separate runs do not share
+// circuits either.
+var circuits = &circuitPool{session: randomHex(8), hosts: make(map[string]*circuitUse)}
+
+type circuitPool struct {
+	mu      sync.Mutex
+	session string
+	hosts   map[string]*circuitUse
+}
+
+type circuitUse struct {
+	generation int
+	requests   int
+}
+
+// credentials returns the SOCKS user for the next request to host,
+// moving host to a fresh circuit once max requests have used the current
+// one, when max is above zero.
+func (c *circuitPool) credentials(host string, max int) *url.Userinfo {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	use := c.hosts[host]
+	if use == nil {
+		use = &circuitUse{}
+		c.hosts[host] = use
+	}
+	if max > 0 && use.requests >= max {
+		use.generation++
+		use.requests = 0
+	}
+	use.requests++
+	return url.UserPassword("mkrk-"+c.session, host+"/"+strconv.Itoa(use.generation))
+}
+
+// isolatedProxy routes each request through proxy with credentials that
+// isolate its destination host. Connections are pooled by proxy URL,
+// credentials included, so a new circuit never reuses an old connection.
+func isolatedProxy(proxy *url.URL, maxPerCircuit int) func(*http.Request) (*url.URL, error) {
+	return func(req *http.Request) (*url.URL, error) {
+		u := *proxy
+		u.User = circuits.credentials(req.URL.Hostname(), maxPerCircuit)
+		return &u, nil
+	}
+}
+
+func randomHex(n int) string {
+	b := make([]byte, n)
+	rand.Read(b)
+	return hex.EncodeToString(b)
+}
+
+// RunTor manages the Tor instance mkrk's requests go through.
+func RunTor(args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", torUsage)
+	}
+	cwd, err := os.Getwd()
+	if err != nil {
+		return err
+	}
+	ctx, err := context.Discover(cwd)
+	if err != nil {
+		return err
+	}
+	defer ctx.Close()
+
+	switch args[0] {
+	case "new-identity", "newnym":
+		return torNewIdentity(ctx)
+	case "connect":
+		return torConnect(ctx, args[1:])
+	default:
+		return fmt.Errorf("unknown tor subcommand: %s\n%s", args[0], torUsage)
+	}
+}
+
+// torConnect relays standard input and output to host and port along the
+// route the privacy settings give the host. push and pull hand it to ssh
+// as its ProxyCommand, so replication goes the way mkrk's own requests do.
+func torConnect(ctx *context.Context, args []string) error {
+	if len(args) != 2 {
+		return fmt.Errorf("%s", torUsage)
+	}
+	privacy := privacySettings(ctx)
+	if privacy.offline {
+		return errOffline
+	}
+	if privacy.err != nil {
+		return privacy.err
+	}
+	d, err := privacy.dialer()
+	if err != nil {
+		return err
+	}
+	conn, err := d.DialContext(stdcontext.Background(), "tcp", net.JoinHostPort(args[0], args[1]))
+	if err != nil {
+		return err
+	}
+	defer conn.Close()
+	go func() {
+		io.Copy(conn, os.Stdin)
+		if cw, ok := conn.(interface{ CloseWrite() error }); ok {
+			cw.CloseWrite()
+		}
+	}()
+	_, err = io.Copy(os.Stdout, conn)
+	return err
+}
+
+// torNewIdentity asks Tor to build fresh circuits for new streams, so
+// nothing sent afterwards is linkable to what went before through the
+// exit node.
+func torNewIdentity(ctx *context.Context) error {
+	if privacySettings(ctx).offline {
+		return errOffline
+	}
+	addr, password, err := torControlSettings(ctx)
+	if err != nil {
+		return err
+	}
+	conn, err := net.DialTimeout("tcp", addr, 10*time.Second)
+	if err != nil {
+		return fmt.Errorf("cannot reach Tor's control port at %s: %w", addr, err)
+	}
+	defer conn.Close()
+	conn.SetDeadline(time.Now().Add(30 * time.Second))
+
+	r := bufio.NewReader(conn)
+	auth := "AUTHENTICATE"
+	if password != "" {
+		auth += " " + strconv.Quote(password)
+	}
+	for _, cmd := range []string{auth, "SIGNAL NEWNYM", "QUIT"} {
+		if _, err := fmt.Fprintf(conn, "%s\r\n", cmd); err != nil {
+			return err
+		}
+		line, err := r.ReadString('\n')
+		if err != nil {
+			return fmt.Errorf("tor control: %w", err)
+		}
+		if !strings.HasPrefix(line, "250") {
+			what := strings.Fields(cmd)[0]
+			return fmt.Errorf("tor control refused %s: %s", what, strings.TrimSpace(line))
+		}
+	}
+	fmt.Fprintln(os.Stderr, "Tor will use new circuits for new requests")
+	return nil
+}
+
+// torControlSettings returns the control port address and password from
+// the workspace settings tor_control and tor_control_password, which the
+// MKRK_TOR_CONTROL environment variable overrides. The password may name a
+// secret as ${secret:NAME}.
+func torControlSettings(ctx *context.Context) (addr, password string, err error) {
+	addr = DefaultTorControl
+	if ctx.Workspace != nil {
+		if v, _ := ctx.Workspace.Config("tor_control"); v != nil && *v != "" {
+			addr = *v
+		}
+		if v, _ := ctx.Workspace.Config("tor_control_password"); v != nil {
+			if password, err = (&secretStore{}).expand(*v); err != nil {
+				return "", "", fmt.Errorf("tor_control_password: %w", err)
+			}
+		}
+	}
+	if override := os.Getenv("MKRK_TOR_CONTROL"); override != "" {
+		addr = override
+	}
+	return addr, password, nil
+}
@@ -0,0 +1,356 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strconv"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// trashDir is the directory, under the project root, that removed files
+// wait in until restored or purged. Each sits at its old path under a
+// directory named for its tombstone's id, so the path is kept on disk
+// rather than in the database. Walks skip it like any other dot directory.
+const trashDir = ".mkrk-trash"
+
+const trashUsage = `usage: mkrk trash list
+       mkrk trash restore <id>...
+       mkrk trash purge (<id>... | --all) [--force]`
+
+// trashTarget is a tracked file about to be moved to the trash.
+type trashTarget struct {
+	absPath string
+	relPath string
+	ref     string
+	fileID  int64
+}
+
+// RunRm moves tracked files to the project's trash, recording who removed
+// them, when and why. Immutable files cannot be removed, and protected ones
+// only with --force.
+func RunRm(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("rm", flag.ExitOnError)
+	reason := fs.String("reason", "", "why the files are being removed")
+	force := fs.Bool("force", false, "remove protected files")
+	positional := parseInterspersed(fs, args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(positional) == 0 && !resolve.HasNarrowSubject(ctx) {
+		return fmt.Errorf("usage: mkrk rm <reference>... [--reason <text>] [--force]")
+	}
+	paths, err := readTargets(ctx, positional)
+	if err != nil {
+		return err
+	}
+	if len(paths) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	// Check every file before moving any, so one refusal leaves all in place.
+	var targets []trashTarget
+	for _, absPath := range paths {
+		relPath, err := filepath.Rel(ctx.ProjectRoot, absPath)
+		if err != nil {
+			return err
+		}
+		e, err := classifyFile(ctx, allFiles, relPath, projectName)
+		if err != nil {
+			return fmt.Errorf("%s: %w", relPath, err)
+		}
+		if e.status != verifyOk || e.file == nil || e.file.ID == nil {
+			return fmt.Errorf("%s: not tracked as it is on disk (run sync first)", e.ref)
+		}
+		if err := checkRemovable(ctx, relPath, e.ref, *force); err != nil {
+			return err
+		}
+		targets = append(targets, trashTarget{absPath: absPath, relPath: relPath, ref: e.ref, fileID: *e.file.ID})
+	}
+
+	if ctx.DryRun {
+		for _, t := range targets {
+			fmt.Fprintf(os.Stderr, "  - %s would move to the trash\n", t.ref)
+		}
+		fmt.Fprintf(os.Stderr, "Dry run: would move %d file(s) to the trash\n", len(targets))
+		return nil
+	}
+
+	user := whoami()
+	var why *string
+	if *reason != "" {
+		why = reason
+	}
+	for _, t := range targets {
+		id, err := ctx.ProjectDb.InsertTrash(t.fileID, &user, why)
+		if err != nil {
+			return err
+		}
+		dst := filepath.Join(trashEntryDir(ctx.ProjectRoot, id), t.relPath)
+		if err := os.MkdirAll(filepath.Dir(dst), 0o700); err != nil {
+			ctx.ProjectDb.RemoveTrash(id)
+			return err
+		}
+		if err := os.Rename(t.absPath, dst); err != nil {
+			ctx.ProjectDb.RemoveTrash(id)
+			return fmt.Errorf("%s: %w", t.ref, err)
+		}
+		fields := map[string]any{"trash_id": id}
+		if why != nil {
+			fields["reason"] = *why
+		}
+		detail, _ := json.Marshal(fields)
+		detailStr := string(detail)
+		ctx.ProjectDb.InsertAudit("trash", &t.fileID, &user, &detailStr)
+		fmt.Fprintf(os.Stderr, "  - %s \033[36m(trash #%d)\033[0m\n", t.ref, id)
+	}
+	fmt.Fprintf(os.Stderr, "Moved %d file(s) to the trash (mkrk trash restore <id> to undo)\n", len(targets))
+	return nil
+}
+
+// RunTrash lists, restores or purges files removed with rm.
+func RunTrash(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(args) == 0 {
+		return fmt.Errorf("%s", trashUsage)
+	}
+	switch args[0] {
+	case "list":
+		return trashList(ctx)
+	case "restore":
+		if ctx.DryRun {
+			return fmt.Errorf("--dry-run is not supported by 'trash restore'")
+		}
+		return trashRestore(ctx, args[1:])
+	case "purge":
+		return trashPurge(ctx, args[1:])
+	default:
+		return fmt.Errorf("unknown trash subcommand: %s\n%s", args[0], trashUsage)
+	}
+}
+
+func trashList(ctx *context.Context) error {
+	entries, err := ctx.ProjectDb.ListTrash()
+	if err != nil {
+		return err
+	}
+	if len(entries) == 0 {
+		fmt.Fprintln(os.Stderr, "(trash is empty)")
+		return nil
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	hashesByID := make(map[int64]string, len(allFiles))
+	for _, f := range allFiles {
+		if f.ID != nil {
+			hashesByID[*f.ID] = f.SHA256
+		}
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	for _, t := range entries {
+		hash := hashesByID[t.FileID]
+		what := fmt.Sprintf("[%s...]", hash[:min(len(hash), 10)])
+		if t.PurgedAt != nil {
+			what += " \033[31m(purged " + *t.PurgedAt + ")\033[0m"
+		} else if _, relPath, err := trashedFile(ctx.ProjectRoot, t.ID); err == nil {
+			what = reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		}
+		user := "?"
+		if t.User != nil {
+			user = *t.User
+		}
+		fmt.Printf("#%d  %s  %s  %s", t.ID, t.TrashedAt, user, what)
+		if t.Reason != nil {
+			fmt.Printf("  (%s)", *t.Reason)
+		}
+		fmt.Println()
+	}
+	return nil
+}
+
+func trashRestore(ctx *context.Context, args []string) error {
+	if len(args) == 0 {
+		return fmt.Errorf("%s", trashUsage)
+	}
+	entries, err := trashEntries(ctx, args)
+	if err != nil {
+		return err
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	user := whoami()
+	for _, t := range entries {
+		src, relPath, err := trashedFile(ctx.ProjectRoot, t.ID)
+		if err != nil {
+			return err
+		}
+		ref := reference.FormatRef(relPath, projectName, ctx.ProjectDb)
+		dst := filepath.Join(ctx.ProjectRoot, relPath)
+		if _, err := os.Lstat(dst); err == nil {
+			return fmt.Errorf("%s: something is already there; move it aside first", ref)
+		}
+		if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+			return err
+		}
+		if err := os.Rename(src, dst); err != nil {
+			return fmt.Errorf("%s: %w", ref, err)
+		}
+		if err := ctx.ProjectDb.RemoveTrash(t.ID); err != nil {
+			return err
+		}
+		os.RemoveAll(trashEntryDir(ctx.ProjectRoot, t.ID))
+		protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+		enforceImmutable(dst, protection, ref)
+
+		detail, _ := json.Marshal(map[string]any{"trash_id": t.ID})
+		detailStr := string(detail)
+		ctx.ProjectDb.InsertAudit("restore", &t.FileID, &user, &detailStr)
+		fmt.Fprintf(os.Stderr, "  + %s \033[36m(from trash #%d)\033[0m\n", ref, t.ID)
+	}
+	return nil
+}
+
+func trashPurge(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("trash purge", flag.ExitOnError)
+	all := fs.Bool("all", false, "purge everything in the trash")
+	force := fs.Bool("force", false, "purge files whose category is now protected")
+	positional := parseInterspersed(fs, args)
+	if *all == (len(positional) > 0) {
+		return fmt.Errorf("%s", trashUsage)
+	}
+
+	var entries []models.TrashEntry
+	if *all {
+		listed, err := ctx.ProjectDb.ListTrash()
+		if err != nil {
+			return err
+		}
+		for _, t := range listed {
+			if t.PurgedAt == nil {
+				entries = append(entries, t)
+			}
+		}
+	} else {
+		var err error
+		if entries, err = trashEntries(ctx, positional); err != nil {
+			return err
+		}
+	}
+
+	// The category a file came from may have been tightened since it was
+	// removed; check them all before deleting any.
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	for _, t := range entries {
+		_, relPath, err := trashedFile(ctx.ProjectRoot, t.ID)
+		if err != nil {
+			return err
+		}
+		if err := checkRemovable(ctx, relPath, reference.FormatRef(relPath, projectName, ctx.ProjectDb), *force); err != nil {
+			return err
+		}
+	}
+
+	if ctx.DryRun {
+		for _, t := range entries {
+			fmt.Fprintf(os.Stderr, "  x trash #%d would be purged\n", t.ID)
+		}
+		fmt.Fprintf(os.Stderr, "Dry run: would purge %d file(s)\n", len(entries))
+		return nil
+	}
+
+	user := whoami()
+	for _, t := range entries {
+		if err := os.RemoveAll(trashEntryDir(ctx.ProjectRoot, t.ID)); err != nil {
+			return err
+		}
+		if err := ctx.ProjectDb.MarkTrashPurged(t.ID); err != nil {
+			return err
+		}
+		detail, _ := json.Marshal(map[string]any{"trash_id": t.ID})
+		detailStr := string(detail)
+		ctx.ProjectDb.InsertAudit("purge", &t.FileID, &user, &detailStr)
+		fmt.Fprintf(os.Stderr, "  x trash #%d\n", t.ID)
+	}
+	fmt.Fprintf(os.Stderr, "Purged %d file(s)\n", len(entries))
+	return nil
+}
+
+// checkRemovable refuses to remove an immutable file, or a protected one
+// without force.
+func checkRemovable(ctx *context.Context, relPath, ref string, force bool) error {
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	switch {
+	case protection == models.ProtectionImmutable:
+		return fmt.Errorf("%s is immutable; refusing to remove it", ref)
+	case protection == models.ProtectionProtected && !force:
+		return fmt.Errorf("%s is protected; use --force to remove it", ref)
+	}
+	return nil
+}
+
+// trashEntries looks up the tombstones named by ids, refusing any that
+// were already purged.
+func trashEntries(ctx *context.Context, ids []string) ([]models.TrashEntry, error) {
+	var entries []models.TrashEntry
+	for _, raw := range ids {
+		id, err := strconv.ParseInt(raw, 10, 64)
+		if err != nil {
+			return nil, fmt.Errorf("invalid trash id: %s", raw)
+		}
+		t, err := ctx.ProjectDb.GetTrash(id)
+		if err != nil {
+			return nil, err
+		}
+		if t == nil {
+			return nil, fmt.Errorf("no trash #%d", id)
+		}
+		if t.PurgedAt != nil {
+			return nil, fmt.Errorf("trash #%d was purged at %s", id, *t.PurgedAt)
+		}
+		entries = append(entries, *t)
+	}
+	return entries, nil
+}
+
+func trashEntryDir(projectRoot string, id int64) string {
+	return filepath.Join(projectRoot, trashDir, strconv.FormatInt(id, 10))
+}
+
+// trashedFile returns where the file of trash #id waits and the path it
+// was removed from.
+func trashedFile(projectRoot string, id int64) (absPath, relPath string, err error) {
+	dir := trashEntryDir(projectRoot, id)
+	found, err := walk.WalkAndCollect(dir, []string{"**"})
+	if err != nil {
+		return "", "", err
+	}
+	if len(found) != 1 {
+		return "", "", fmt.Errorf("trash #%d: expected one file in %s, found %d", id, dir, len(found))
+	}
+	relPath = filepath.FromSlash(found[0])
+	return filepath.Join(dir, relPath), relPath, nil
+}
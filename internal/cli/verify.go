@@ -0,0 +1,670 @@
+package cli
+
+import (
+	"encoding/json"
+	"flag"
+	"fmt"
+	"os"
+	"os/signal"
+	"path/filepath"
+	"runtime"
+	"sync"
+	"syscall"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/integrity"
+	"go.foia.dev/muckrake/internal/materialize"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+	"go.foia.dev/muckrake/internal/resolve"
+	"go.foia.dev/muckrake/internal/rules"
+	"go.foia.dev/muckrake/internal/walk"
+)
+
+// verifyStatus classifies a file on disk against the tracked records.
+type verifyStatus int
+
+const (
+	verifyOk verifyStatus = iota
+	verifyModified
+	verifyUntracked
+)
+
+// verifyEntry is the classification of one file found on disk.
+type verifyEntry struct {
+	relPath string
+	ref     string
+	status  verifyStatus
+	hash    string
+	fp      *integrity.Fingerprint
+	file    *models.TrackedFile
+}
+
+func RunVerify(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("verify", flag.ExitOnError)
+	accept := fs.Bool("accept", false, "accept modified files as their new content")
+	reason := fs.String("reason", "", "reason recorded in the audit log (required with --accept)")
+	watch := fs.Bool("watch", false, "keep re-verifying on an interval until interrupted")
+	interval := fs.Duration("interval", time.Hour, "time between passes in --watch mode")
+	jsonOut := fs.Bool("json", false, "print a per-file JSON report to stdout")
+	var opts verifyOptions
+	fs.IntVar(&opts.jobs, "jobs", runtime.NumCPU(), "number of files to hash in parallel")
+	fs.IntVar(&opts.jobs, "j", runtime.NumCPU(), "shorthand for --jobs")
+	fs.BoolVar(&opts.quick, "quick", false, "only rehash files whose size, mtime or inode changed since they were last hashed")
+	fs.Parse(args)
+
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+
+	if *accept {
+		if *reason == "" {
+			return fmt.Errorf("--accept requires --reason")
+		}
+		paths, err := verifyAcceptTargets(ctx, fs.Args())
+		if err != nil {
+			return err
+		}
+		return acceptModified(ctx, paths, *reason)
+	}
+
+	if *watch {
+		return watchVerify(ctx, fs.Args(), *interval, opts)
+	}
+
+	report, err := verifyPass(ctx, fs.Args(), opts)
+	if err != nil {
+		return err
+	}
+	if *jsonOut {
+		enc := json.NewEncoder(os.Stdout)
+		enc.SetIndent("", "  ")
+		if err := enc.Encode(report.document()); err != nil {
+			return err
+		}
+	}
+	if n := report.failed(); n > 0 {
+		return &StatusError{Code: report.status(), Err: fmt.Errorf("%d file(s) failed verification", n)}
+	}
+	return nil
+}
+
+// watchVerify runs a verification pass every interval until interrupted.
+// Each pass is written to the audit log and fires verify_failure rules,
+// so tampering is recorded even when nobody is watching the output.
+func watchVerify(ctx *context.Context, args []string, interval time.Duration, opts verifyOptions) error {
+	if interval <= 0 {
+		return fmt.Errorf("--interval must be positive")
+	}
+
+	sig := make(chan os.Signal, 1)
+	signal.Notify(sig, os.Interrupt, syscall.SIGTERM)
+	defer signal.Stop(sig)
+
+	for {
+		fmt.Fprintf(os.Stderr, "[%s] verifying\n", time.Now().Format(time.RFC3339))
+		if _, err := verifyPass(ctx, args, opts); err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %v\n", err)
+		}
+		select {
+		case <-sig:
+			return nil
+		case <-time.After(interval):
+		}
+	}
+}
+
+// verifyAcceptTargets requires an explicit file set so acceptance is never
+// applied to the whole project by accident.
+func verifyAcceptTargets(ctx *context.Context, args []string) ([]string, error) {
+	if resolve.HasNarrowSubject(ctx) {
+		return resolve.SubjectRelPaths(ctx)
+	}
+	if len(args) == 0 {
+		return nil, fmt.Errorf("usage: mkrk verify --accept --reason <text> <reference>")
+	}
+	var rels []string
+	for _, raw := range args {
+		paths, err := resolve.RefRelPaths(ctx, raw)
+		if err != nil {
+			return nil, err
+		}
+		rels = append(rels, paths...)
+	}
+	return rels, nil
+}
+
+// verifyReport tallies the outcome of one verification pass. Only the
+// counts are written to the audit log; files, which name paths, are only
+// ever printed.
+type verifyReport struct {
+	Ok        int `json:"ok"`
+	Modified  int `json:"modified"`
+	Missing   int `json:"missing"`
+	Untracked int `json:"untracked"`
+	// Quick counts the ok files --quick trusted by their stat data
+	// without rereading them.
+	Quick int `json:"quick,omitempty"`
+	files []verifyFileReport
+	// algorithm is the project's hash algorithm, which made every hash in
+	// files.
+	algorithm integrity.Algorithm
+}
+
+// verifyFileReport is the outcome for one file in `verify --json`. Missing
+// files have no path, since the database does not record one.
+type verifyFileReport struct {
+	Path           string                `json:"path,omitempty"`
+	Status         string                `json:"status"`
+	SHA256         string                `json:"sha256,omitempty"`
+	ExpectedSHA256 string                `json:"expected_sha256,omitempty"`
+	ChunkDiffs     []integrity.ChunkDiff `json:"chunk_diffs,omitempty"`
+}
+
+func (r *verifyReport) failed() int {
+	return r.Modified + r.Missing
+}
+
+// status returns the exit status bits for the failures in the report.
+func (r *verifyReport) status() int {
+	code := 0
+	if r.Modified > 0 {
+		code |= ExitModified
+	}
+	if r.Missing > 0 {
+		code |= ExitMissing
+	}
+	return code
+}
+
+func (r *verifyReport) document() any {
+	files := r.files
+	if files == nil {
+		files = []verifyFileReport{}
+	}
+	return struct {
+		verifyReport
+		HashAlgorithm integrity.Algorithm `json:"hash_algorithm"`
+		Files         []verifyFileReport  `json:"files"`
+	}{*r, r.algorithm, files}
+}
+
+// verifyOptions are the settings of a verification pass.
+type verifyOptions struct {
+	// jobs is how many files are hashed at once.
+	jobs int
+	// quick trusts files whose size, mtime and inode still match the hash
+	// cache instead of rereading them.
+	quick bool
+}
+
+// verifyPass classifies every file in scope, fires verify_failure rules for
+// modified and missing files, and records the pass in the audit log. Up to
+// opts.jobs files are hashed at once, and each is reported as its hash
+// completes; the JSON report still lists them in walk order.
+func verifyPass(ctx *context.Context, args []string, opts verifyOptions) (*verifyReport, error) {
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	var entries []string
+	narrowed := resolve.HasNarrowSubject(ctx) || len(args) > 0
+	switch {
+	case resolve.HasNarrowSubject(ctx):
+		rels, err := resolve.SubjectRelPaths(ctx)
+		if err != nil {
+			return nil, err
+		}
+		entries = rels
+	case len(args) > 0:
+		for _, raw := range args {
+			rels, err := resolve.RefRelPaths(ctx, raw)
+			if err != nil {
+				return nil, err
+			}
+			entries = append(entries, rels...)
+		}
+	default:
+		patterns, err := walk.CategoryPatterns(ctx.ProjectDb, nil)
+		if err != nil {
+			return nil, err
+		}
+		all, err := walk.WalkAndCollect(ctx.ProjectRoot, patterns)
+		if err != nil {
+			return nil, err
+		}
+		entries = all
+	}
+
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	categories, _ := ctx.ProjectDb.ListCategories()
+	seen := make(map[string]bool)
+	hasher, err := ctx.Hasher()
+	if err != nil {
+		return nil, err
+	}
+	report := &verifyReport{algorithm: hasher.Algorithm}
+
+	ordered := make([]*verifyFileReport, len(entries))
+	record := func(index int, e *verifyEntry) {
+		relPath := e.relPath
+		fr := &verifyFileReport{Path: relPath, SHA256: e.hash}
+		switch e.status {
+		case verifyOk:
+			seen[e.file.SHA256] = true
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s\n", e.ref)
+			report.Ok++
+			fr.Status = "ok"
+		case verifyModified:
+			seen[e.file.SHA256] = true
+			fmt.Fprintf(os.Stderr, "  \033[33m~\033[0m %s (modified, was [%s...])\n",
+				e.ref, e.file.SHA256[:min(len(e.file.SHA256), 10)])
+			report.Modified++
+			fr.Status = "modified"
+			fr.ExpectedSHA256 = e.file.SHA256
+			if expected, err := integrity.FingerprintFromJSON(e.file.Fingerprint); err == nil {
+				fr.ChunkDiffs = integrity.DiffFingerprints(expected, e.fp)
+			}
+			verifyFailed(ctx, e.file, "modified", relPath, e.ref, fr.ChunkDiffs, matchingCategories(relPath, categories))
+		case verifyUntracked:
+			fmt.Fprintf(os.Stderr, "  \033[31m?\033[0m %s (untracked)\n", e.ref)
+			report.Untracked++
+			fr.Status = "untracked"
+		}
+		ordered[index] = fr
+	}
+
+	pending := make([]int, 0, len(entries))
+	for i, relPath := range entries {
+		if opts.quick {
+			if e := quickMatch(ctx, relPath, projectName); e != nil {
+				report.Quick++
+				record(i, e)
+				continue
+			}
+		}
+		pending = append(pending, i)
+	}
+	for h := range hashForVerify(ctx, hasher, entries, pending, opts.jobs) {
+		relPath := entries[h.index]
+		if h.err != nil {
+			fmt.Fprintf(os.Stderr, "  \033[31m✗\033[0m %s: %v\n", relPath, h.err)
+			continue
+		}
+		e := matchFile(ctx, allFiles, relPath, projectName, h.hash, h.fp)
+		if e.status == verifyOk && e.file.SHA256 == h.hash && !ctx.DryRun {
+			ctx.ProjectDb.PutCachedHash(filepath.ToSlash(relPath), h.stat.size, h.stat.mtime, h.stat.inode, h.hash)
+		}
+		record(h.index, e)
+	}
+	for _, fr := range ordered {
+		if fr != nil {
+			report.files = append(report.files, *fr)
+		}
+	}
+
+	// Missing files can only be detected when the whole project was walked.
+	if !narrowed {
+		trashed, _ := ctx.ProjectDb.TrashedFileIDs()
+		for i := range allFiles {
+			f := &allFiles[i]
+			if seen[f.SHA256] || (f.ID != nil && trashed[*f.ID]) {
+				continue
+			}
+			if obj := storeObjectPath(ctx.ProjectRoot, f.SHA256); fileExists(obj) {
+				fmt.Fprintf(os.Stderr, "  \033[33m?\033[0m [%s...] (missing; original kept at %s)\n", f.SHA256[:min(len(f.SHA256), 10)], obj)
+			} else {
+				fmt.Fprintf(os.Stderr, "  \033[33m?\033[0m [%s...] (missing)\n", f.SHA256[:min(len(f.SHA256), 10)])
+			}
+			report.Missing++
+			verifyFailed(ctx, f, "missing", "", "", nil, nil)
+			report.files = append(report.files, verifyFileReport{Status: "missing", ExpectedSHA256: f.SHA256})
+		}
+	}
+
+	fmt.Fprintln(os.Stderr)
+	fmt.Fprintf(os.Stderr, "Verify: %d ok, %d modified, %d missing, %d untracked\n",
+		report.Ok, report.Modified, report.Missing, report.Untracked)
+	if report.Quick > 0 {
+		fmt.Fprintf(os.Stderr, "  %d file(s) unchanged since last hashed were not reread; verify without --quick to rehash them\n", report.Quick)
+	}
+
+	if ctx.DryRun {
+		return report, nil
+	}
+	user := whoami()
+	detail, _ := json.Marshal(report)
+	detailStr := string(detail)
+	ctx.ProjectDb.InsertAudit("verify", nil, &user, &detailStr)
+
+	return report, nil
+}
+
+// verifyFailed audits a single failed file and fires verify_failure rules
+// against its tracked record, with its reference and a summary of the
+// chunks that changed. Under --dry-run it only previews the rules.
+func verifyFailed(ctx *context.Context, file *models.TrackedFile, status, relPath, ref string, diffs []integrity.ChunkDiff, cats []models.Scope) {
+	diff := "file missing"
+	if status == "modified" {
+		diff = summarizeChunkDiffs(diffs)
+	}
+	if !ctx.DryRun {
+		detail, _ := json.Marshal(map[string]string{"status": status, "sha256": file.SHA256, "diff": diff})
+		detailStr := string(detail)
+		ctx.ProjectDb.InsertAudit("verify_failure", file.ID, nil, &detailStr)
+	}
+
+	ev := &rules.Event{
+		Trigger:  models.TriggerVerifyFailure,
+		SHA256:   file.SHA256,
+		RelPath:  relPath,
+		MimeType: file.MimeType,
+		Ref:      ref,
+		Status:   status,
+		Diff:     diff,
+		Root:     ctx.ProjectRoot,
+	}
+	if ctx.ProjectName != nil {
+		ev.Project = *ctx.ProjectName
+	}
+	if ctx.Workspace != nil {
+		ev.Workspace = ctx.Workspace.Db
+	}
+	for _, c := range cats {
+		ev.Categories = append(ev.Categories, c.Name)
+	}
+	if file.ID != nil {
+		ev.Tags, _ = ctx.ProjectDb.GetTags(*file.ID)
+	}
+
+	fire := rules.Fire
+	if ctx.DryRun {
+		fire = rules.Preview
+	} else {
+		ev.RunShell = ruleShellRunner(ctx)
+		ev.Post = ruleWebhookPoster(ctx)
+	}
+	firings, err := fire(ctx.ProjectDb, ev)
+	if err != nil {
+		fmt.Fprintf(os.Stderr, "    ! rules: %v\n", err)
+		return
+	}
+	printFirings(firings)
+}
+
+// summarizeChunkDiffs describes a modification by the chunks it touched.
+func summarizeChunkDiffs(diffs []integrity.ChunkDiff) string {
+	if len(diffs) == 0 {
+		return "content changed"
+	}
+	return fmt.Sprintf("%d chunk(s) changed, first at byte %d", len(diffs), diffs[0].Offset)
+}
+
+func printFirings(firings []rules.Firing) {
+	for _, f := range firings {
+		if f.Err != nil {
+			fmt.Fprintf(os.Stderr, "    ! rule '%s' (%s): %v\n", f.Rule, f.Action, f.Err)
+			continue
+		}
+		fmt.Fprintf(os.Stderr, "    → rule '%s' (%s) %s\n", f.Rule, f.Action, f.Detail)
+	}
+}
+
+// fileStat is the stat data the hash cache compares to decide a file is
+// unchanged since it was last hashed.
+type fileStat struct {
+	size, mtime, inode int64
+}
+
+func statFile(absPath string) (fileStat, error) {
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return fileStat{}, err
+	}
+	st := fileStat{size: info.Size(), mtime: info.ModTime().UnixNano()}
+	if key, _, ok := integrity.LinkInfo(info); ok {
+		st.inode = int64(key.Ino)
+	}
+	return st, nil
+}
+
+// quickMatch returns the file at relPath as ok when its stat data matches
+// the hash cache and the cached hash is still tracked, or nil when it has
+// to be reread.
+func quickMatch(ctx *context.Context, relPath, projectName string) *verifyEntry {
+	st, err := statFile(filepath.Join(ctx.ProjectRoot, relPath))
+	if err != nil {
+		return nil
+	}
+	hash, _ := ctx.ProjectDb.CachedHash(filepath.ToSlash(relPath), st.size, st.mtime, st.inode)
+	if hash == "" {
+		return nil
+	}
+	file, _ := ctx.ProjectDb.GetFileByHash(hash)
+	if file == nil {
+		return nil
+	}
+	return &verifyEntry{
+		relPath: relPath,
+		ref:     reference.FormatRef(relPath, projectName, ctx.ProjectDb),
+		status:  verifyOk,
+		hash:    hash,
+		file:    file,
+	}
+}
+
+// verifyHash is one file hashed by hashForVerify, by its index in the
+// entries it was given, with its stat data from just before it was read.
+type verifyHash struct {
+	index int
+	hash  string
+	fp    *integrity.Fingerprint
+	stat  fileStat
+	err   error
+}
+
+// hashForVerify hashes the entries at pending using up to jobs workers and
+// sends each result as soon as it is ready. Only the workers read files;
+// matching results against the database stays with the caller, which must
+// drain the channel.
+func hashForVerify(ctx *context.Context, hasher integrity.Hasher, entries []string, pending []int, jobs int) <-chan verifyHash {
+	indexes := make(chan int)
+	results := make(chan verifyHash)
+	var wg sync.WaitGroup
+	for range min(max(jobs, 1), max(len(pending), 1)) {
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			for i := range indexes {
+				h := verifyHash{index: i}
+				absPath := filepath.Join(ctx.ProjectRoot, entries[i])
+				if h.stat, h.err = statFile(absPath); h.err == nil {
+					h.hash, h.fp, h.err = hashOrStub(ctx, hasher, absPath)
+				}
+				results <- h
+			}
+		}()
+	}
+	go func() {
+		for _, i := range pending {
+			indexes <- i
+		}
+		close(indexes)
+		wg.Wait()
+		close(results)
+	}()
+	return results
+}
+
+// classifyFile hashes a file on disk and matches it against the tracked
+// records with matchFile.
+func classifyFile(ctx *context.Context, allFiles []models.TrackedFile, relPath, projectName string) (*verifyEntry, error) {
+	hasher, err := ctx.Hasher()
+	if err != nil {
+		return nil, err
+	}
+	hash, fp, err := hashOrStub(ctx, hasher, filepath.Join(ctx.ProjectRoot, relPath))
+	if err != nil {
+		return nil, err
+	}
+	return matchFile(ctx, allFiles, relPath, projectName, hash, fp), nil
+}
+
+// matchFile classifies a hashed file against the tracked records: exact
+// hash or fingerprint is ok, a partial fingerprint match is a modified
+// version of a tracked file unless it is a copy-out working copy, and
+// anything else is untracked.
+func matchFile(ctx *context.Context, allFiles []models.TrackedFile, relPath, projectName, hash string, fp *integrity.Fingerprint) *verifyEntry {
+	e := &verifyEntry{
+		relPath: relPath,
+		ref:     reference.FormatRef(relPath, projectName, ctx.ProjectDb),
+		hash:    hash,
+		fp:      fp,
+	}
+
+	if file, _ := ctx.ProjectDb.GetFileByHash(hash); file != nil {
+		e.status = verifyOk
+		e.file = file
+		return e
+	}
+	if file, _ := ctx.ProjectDb.GetFileByFingerprint(fp.ToJSON()); file != nil {
+		e.status = verifyOk
+		e.file = file
+		return e
+	}
+	if match := findPartialMatchFile(allFiles, fp); match != nil && !isWorkingCopy(ctx, relPath) {
+		e.status = verifyModified
+		e.file = match
+		return e
+	}
+	e.status = verifyUntracked
+	return e
+}
+
+// acceptAuditDetail is the JSON payload recorded for an "accept" audit entry.
+type acceptAuditDetail struct {
+	OldSHA256    string `json:"old_sha256"`
+	NewSHA256    string `json:"new_sha256"`
+	Reason       string `json:"reason"`
+	RevokedSigns int64  `json:"revoked_signs"`
+}
+
+// acceptModified re-hashes each modified file and rewrites its tracked
+// record to the new content. Signs made against the old hash are revoked,
+// since they attest to content that no longer exists.
+func acceptModified(ctx *context.Context, paths []string, reason string) error {
+	if len(paths) == 0 {
+		return fmt.Errorf("no files matched")
+	}
+
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	categories, _ := ctx.ProjectDb.ListCategories()
+	allPipelines, _ := ctx.ProjectDb.ListPipelines()
+	pipelinesByID := make(map[int64]*models.Pipeline)
+	for i := range allPipelines {
+		pipelinesByID[*allPipelines[i].ID] = &allPipelines[i]
+	}
+	user := whoami()
+	accepted := 0
+
+	// Every file is checked before any is accepted, so an immutable one
+	// leaves the whole run undone rather than half done.
+	var changed []*verifyEntry
+	for _, relPath := range paths {
+		e, err := classifyFile(ctx, allFiles, relPath, projectName)
+		if err != nil {
+			fmt.Fprintf(os.Stderr, "  ! %s: %v\n", relPath, err)
+			continue
+		}
+		switch e.status {
+		case verifyOk:
+			fmt.Fprintf(os.Stderr, "  = %s (unchanged)\n", e.ref)
+			continue
+		case verifyUntracked:
+			fmt.Fprintf(os.Stderr, "  ! %s: not tracked (run sync first)\n", e.ref)
+			continue
+		}
+
+		protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+		if protection == models.ProtectionImmutable {
+			return fmt.Errorf("%s: refusing to accept changes to an immutable file", e.ref)
+		}
+		changed = append(changed, e)
+	}
+
+	for _, e := range changed {
+		relPath := e.relPath
+		fileID := *e.file.ID
+		oldHash := e.file.SHA256
+		if ctx.DryRun {
+			fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s would be accepted [%s...] -> [%s...]",
+				e.ref, oldHash[:min(len(oldHash), 10)], e.hash[:min(len(e.hash), 10)])
+			stale := 0
+			signs, _ := ctx.ProjectDb.GetSignsForFile(fileID)
+			for _, sg := range signs {
+				if sg.RevokedAt == nil && sg.FileHash != e.hash {
+					stale++
+				}
+			}
+			if stale > 0 {
+				fmt.Fprintf(os.Stderr, " (%d stale sign(s) would be revoked)", stale)
+			}
+			fmt.Fprintln(os.Stderr)
+			accepted++
+			continue
+		}
+		recordStates := trackStates(ctx, e.file, filePipelines(ctx, e.file, oldHash, pipelinesByID), oldHash)
+		if err := recordRevision(ctx, e.file, relPath, e.hash); err != nil {
+			return fmt.Errorf("%s: %w", e.ref, err)
+		}
+		if err := ctx.ProjectDb.UpdateFileSHA256(fileID, e.hash); err != nil {
+			return fmt.Errorf("%s: %w", e.ref, err)
+		}
+		if err := ctx.ProjectDb.UpdateFileFingerprint(fileID, e.fp.ToJSON()); err != nil {
+			return fmt.Errorf("%s: %w", e.ref, err)
+		}
+
+		now := time.Now().UTC().Format(time.RFC3339)
+		revoked, err := ctx.ProjectDb.RevokeStaleSigns(fileID, e.hash, now)
+		if err != nil {
+			return fmt.Errorf("%s: %w", e.ref, err)
+		}
+		recordStates(e.hash, "accept", nil)
+
+		tags, _ := ctx.ProjectDb.GetTags(fileID)
+		materialize.MaterializeForFile(ctx.ProjectDb, relPath, e.hash, matchingCategories(relPath, categories), tags)
+
+		detail, _ := json.Marshal(acceptAuditDetail{
+			OldSHA256:    oldHash,
+			NewSHA256:    e.hash,
+			Reason:       reason,
+			RevokedSigns: revoked,
+		})
+		detailStr := string(detail)
+		if err := ctx.ProjectDb.InsertAudit("accept", &fileID, &user, &detailStr); err != nil {
+			return fmt.Errorf("%s: %w", e.ref, err)
+		}
+
+		fmt.Fprintf(os.Stderr, "  \033[32m✓\033[0m %s accepted [%s...] -> [%s...]",
+			e.ref, oldHash[:min(len(oldHash), 10)], e.hash[:min(len(e.hash), 10)])
+		if revoked > 0 {
+			fmt.Fprintf(os.Stderr, " (%d stale sign(s) revoked)", revoked)
+		}
+		fmt.Fprintln(os.Stderr)
+		accepted++
+	}
+
+	if ctx.DryRun {
+		fmt.Fprintf(os.Stderr, "Dry run: would accept %d file(s)\n", accepted)
+		return nil
+	}
+	fmt.Fprintf(os.Stderr, "Accepted %d file(s)\n", accepted)
+	return nil
+}
@@ -0,0 +1,195 @@
+package cli
+
+import (
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"time"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// versionStore is the directory, under the project root, that keeps a copy
+// of every revision of files allowed to change, named by content hash.
+// Walks skip it like any other dot directory.
+const versionStore = ".mkrk-versions"
+
+func versionObjectPath(projectRoot, hash string) string {
+	return filepath.Join(projectRoot, versionStore, hash[:2], hash)
+}
+
+// RunVersions lists the recorded revisions of a file.
+func RunVersions(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	paths, err := readTargets(ctx, args)
+	if err != nil {
+		return err
+	}
+	if len(paths) != 1 {
+		return fmt.Errorf("usage: mkrk versions <reference> (matched %d files)", len(paths))
+	}
+
+	file, versions, err := fileVersions(ctx, paths[0])
+	if err != nil {
+		return err
+	}
+	if len(versions) == 0 {
+		fmt.Fprintln(os.Stderr, "(no revisions recorded)")
+		return nil
+	}
+	for i, v := range versions {
+		size := "?"
+		if v.Size != nil {
+			size = formatSize(*v.Size)
+		}
+		note := ""
+		switch {
+		case v.SHA256 == file.SHA256:
+			note = " (current)"
+		case keptCopy(ctx.ProjectRoot, v.SHA256) == "":
+			note = " (not kept)"
+		}
+		fmt.Printf("v%d  %s  [%s...]  %s%s\n", i+1, v.RecordedAt, v.SHA256[:min(len(v.SHA256), 10)], size, note)
+	}
+	return nil
+}
+
+// fileVersions finds the tracked record for a file on disk, even if the
+// file has been modified since, and returns its revisions.
+func fileVersions(ctx *context.Context, absPath string) (*models.TrackedFile, []models.FileVersion, error) {
+	relPath, err := filepath.Rel(ctx.ProjectRoot, absPath)
+	if err != nil {
+		return nil, nil, err
+	}
+	projectName := ""
+	if ctx.ProjectName != nil {
+		projectName = *ctx.ProjectName
+	}
+	allFiles, _ := ctx.ProjectDb.ListAllFiles()
+	e, err := classifyFile(ctx, allFiles, relPath, projectName)
+	if err != nil {
+		return nil, nil, err
+	}
+	if e.file == nil || e.file.ID == nil {
+		return nil, nil, fmt.Errorf("%s: not tracked (run sync first)", e.ref)
+	}
+	versions, err := ctx.ProjectDb.ListFileVersions(*e.file.ID)
+	return e.file, versions, err
+}
+
+// versionPath returns where revision n (counting from 1) of a file can be
+// read: its copy in the version store or the blob store, or the file
+// itself if that still holds the revision.
+func versionPath(ctx *context.Context, absPath string, n int) (string, error) {
+	_, versions, err := fileVersions(ctx, absPath)
+	if err != nil {
+		return "", err
+	}
+	if n < 1 || n > len(versions) {
+		return "", fmt.Errorf("no revision %d (%d recorded)", n, len(versions))
+	}
+	hash := versions[n-1].SHA256
+	if obj := keptCopy(ctx.ProjectRoot, hash); obj != "" {
+		return obj, nil
+	}
+	if current, err := ctx.HashFile(absPath); err == nil && current == hash {
+		return absPath, nil
+	}
+	return "", fmt.Errorf("revision %d was not kept", n)
+}
+
+// storeVersion records hash as the newest revision of fileID, updates the
+// file's recorded size, and keeps a copy of the content at relPath in the
+// version store. Immutable files get no copy: their only revision stays on
+// disk. With the store setting on, the copy goes to the blob store instead,
+// immutable files included, and the file is linked to it.
+func storeVersion(ctx *context.Context, fileID int64, relPath, hash string) error {
+	absPath := filepath.Join(ctx.ProjectRoot, relPath)
+	info, err := os.Stat(absPath)
+	if err != nil {
+		return err
+	}
+	size := info.Size()
+	if err := ctx.ProjectDb.UpdateFileSize(fileID, size); err != nil {
+		return err
+	}
+
+	alg, err := ctx.HashAlgorithm()
+	if err != nil {
+		return err
+	}
+	protection, _ := ctx.ProjectDb.ResolveProtection(relPath)
+	if storeEnabled(ctx) {
+		if err := storeOriginal(ctx, alg, absPath, hash, protection); err != nil {
+			return fmt.Errorf("keep original: %w", err)
+		}
+	} else if obj := versionObjectPath(ctx.ProjectRoot, hash); protection != models.ProtectionImmutable && !fileExists(obj) {
+		if err := copyVerified(ctx, alg, absPath, obj, hash); err != nil {
+			return fmt.Errorf("keep revision: %w", err)
+		}
+		os.Chmod(obj, 0o444)
+	}
+	return ctx.ProjectDb.InsertFileVersion(fileID, hash, &size, time.Now().UTC().Format(time.RFC3339))
+}
+
+// recordRevision stores the new content of a tracked file whose record is
+// moving to newHash, and re-extracts its metadata. Files tracked before
+// versioning get their original revision recorded first, without a copy.
+func recordRevision(ctx *context.Context, file *models.TrackedFile, relPath, newHash string) error {
+	versions, err := ctx.ProjectDb.ListFileVersions(*file.ID)
+	if err != nil {
+		return err
+	}
+	if len(versions) == 0 {
+		if err := ctx.ProjectDb.InsertFileVersion(*file.ID, file.SHA256, file.Size, file.IngestedAt); err != nil {
+			return err
+		}
+	}
+	recordMetadata(ctx, *file.ID, relPath)
+	return storeVersion(ctx, *file.ID, relPath, newHash)
+}
+
+// sealStoredVersions encrypts the kept revisions of a file that are still
+// in plaintext, in either store, so encrypting a category leaves no
+// readable copies behind. Each sealed copy is recorded as a stored form of
+// its revision, so it still checks out against the revision's hash.
+func sealStoredVersions(ctx *context.Context, fileID int64, key []byte) error {
+	versions, err := ctx.ProjectDb.ListFileVersions(fileID)
+	if err != nil {
+		return err
+	}
+	hasher, err := ctx.Hasher()
+	if err != nil {
+		return err
+	}
+	for _, v := range versions {
+		for _, obj := range []string{versionObjectPath(ctx.ProjectRoot, v.SHA256), storeObjectPath(ctx.ProjectRoot, v.SHA256)} {
+			if enc, err := crypt.IsEncrypted(obj); err != nil || enc {
+				continue
+			}
+			if err := replaceContent(obj, func(dst io.Writer, src io.Reader) error {
+				return crypt.Encrypt(dst, src, key)
+			}); err != nil {
+				return err
+			}
+			hash, fp, err := hasher.HashAndFingerprint(obj)
+			if err != nil {
+				return err
+			}
+			if err := ctx.ProjectDb.PutCiphertext(db.Ciphertext{
+				SHA256:          hash,
+				Fingerprint:     fp.ToJSON(),
+				PlaintextSHA256: v.SHA256,
+			}); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
@@ -0,0 +1,134 @@
+package cli
+
+import (
+	"flag"
+	"fmt"
+	"os"
+
+	"go.foia.dev/muckrake/internal/context"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+const viewerUsage = `usage: mkrk viewer set <mime-type> <command> [--category <name>] [--read-only]
+       mkrk viewer list
+       mkrk viewer remove <mime-type> [--category <name>]`
+
+func RunViewer(ctx *context.Context, args []string) error {
+	if ctx.Kind != context.ContextProject {
+		return fmt.Errorf("not in a project")
+	}
+	if len(args) == 0 {
+		return fmt.Errorf("%s", viewerUsage)
+	}
+
+	switch args[0] {
+	case "set", "add":
+		return viewerSet(ctx, args[1:])
+	case "list", "ls":
+		return viewerList(ctx)
+	case "remove", "rm":
+		return viewerRemove(ctx, args[1:])
+	default:
+		return fmt.Errorf("unknown viewer subcommand: %s\n%s", args[0], viewerUsage)
+	}
+}
+
+func viewerSet(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("viewer set", flag.ExitOnError)
+	category := fs.String("category", "", "only use this viewer for files in the category")
+	readOnly := fs.Bool("read-only", false, "viewer cannot modify files (allowed on immutable categories)")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 2 {
+		return fmt.Errorf("usage: mkrk viewer set <mime-type> <command> [--category <name>] [--read-only]")
+	}
+
+	scopeID, err := viewerScope(ctx, *category)
+	if err != nil {
+		return err
+	}
+	v := &models.OpenViewer{
+		ScopeID:  scopeID,
+		MimeType: positional[0],
+		Command:  positional[1],
+		ReadOnly: *readOnly,
+	}
+	if _, err := ctx.ProjectDb.SetOpenViewer(v); err != nil {
+		return err
+	}
+
+	fmt.Fprintf(os.Stderr, "Viewer for %s%s: %s", v.MimeType, viewerScopeLabel(*category), v.Command)
+	if v.ReadOnly {
+		fmt.Fprint(os.Stderr, " (read-only)")
+	}
+	fmt.Fprintln(os.Stderr)
+	return nil
+}
+
+func viewerList(ctx *context.Context) error {
+	viewers, err := ctx.ProjectDb.ListOpenViewers()
+	if err != nil {
+		return err
+	}
+	categories, _ := ctx.ProjectDb.ListCategories()
+	names := make(map[int64]string)
+	for _, c := range categories {
+		names[*c.ID] = c.Name
+	}
+
+	for _, v := range viewers {
+		scope := ""
+		if v.ScopeID != nil {
+			scope = viewerScopeLabel(names[*v.ScopeID])
+		}
+		mode := ""
+		if v.ReadOnly {
+			mode = " (read-only)"
+		}
+		fmt.Printf("%s%s: %s%s\n", v.MimeType, scope, v.Command, mode)
+	}
+	return nil
+}
+
+func viewerRemove(ctx *context.Context, args []string) error {
+	fs := flag.NewFlagSet("viewer remove", flag.ExitOnError)
+	category := fs.String("category", "", "remove the category-specific viewer")
+	positional := parseInterspersed(fs, args)
+	if len(positional) != 1 {
+		return fmt.Errorf("usage: mkrk viewer remove <mime-type> [--category <name>]")
+	}
+
+	scopeID, err := viewerScope(ctx, *category)
+	if err != nil {
+		return err
+	}
+	n, err := ctx.ProjectDb.RemoveOpenViewer(scopeID, positional[0])
+	if err != nil {
+		return err
+	}
+	if n == 0 {
+		return fmt.Errorf("no viewer for %s%s", positional[0], viewerScopeLabel(*category))
+	}
+	fmt.Fprintf(os.Stderr, "Removed viewer for %s%s\n", positional[0], viewerScopeLabel(*category))
+	return nil
+}
+
+func viewerScope(ctx *context.Context, category string) (*int64, error) {
+	if category == "" {
+		return nil, nil
+	}
+	cat, err := ctx.ProjectDb.GetCategoryByName(category)
+	if err != nil {
+		return nil, err
+	}
+	if cat == nil {
+		return nil, fmt.Errorf("category '%s' not found", category)
+	}
+	return cat.ID, nil
+}
+
+func viewerScopeLabel(category string) string {
+	if category == "" {
+		return ""
+	}
+	return " in " + category
+}
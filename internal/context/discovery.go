@@ -1,11 +1,13 @@
 package context
 
 import (
+	"errors"
 	"fmt"
 	"os"
 	"path/filepath"
 
 	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/reference"
 )
 
@@ -28,6 +30,9 @@ type Context struct {
 	// Subject is the parsed :ref prefix passed on the command line. nil
 	// means no explicit subject — commands fall back to CWD-derived context.
 	Subject *reference.Reference
+	// DryRun is set by the global --dry-run flag. Commands that honor it
+	// report what they would do without touching the DB or filesystem.
+	DryRun bool
 	// ownsWorkspace is true when this context created the Workspace DB and
 	// should close it on Close(). False when the workspace was passed in
 	// (shared across per-project contexts during iteration).
@@ -38,6 +43,10 @@ type Context struct {
 type WorkspaceContext struct {
 	Root string
 	Db   *db.WorkspaceDb
+	// Name is what the enclosing workspace registered this one as, and
+	// Parent is that workspace. Both are unset for a top-level workspace.
+	Name   string
+	Parent *WorkspaceContext
 }
 
 // Discover walks up from cwd to find .mkrk (project) and .mksp (workspace) markers.
@@ -50,21 +59,18 @@ func Discover(cwd string) (*Context, error) {
 		if err != nil {
 			return nil, err
 		}
-		wdb, err := db.OpenWorkspace(filepath.Join(workspaceRoot, ".mksp"))
+		ws, err := openWorkspace(workspaceRoot)
 		if err != nil {
 			pdb.Close()
 			return nil, err
 		}
-		name := lookupProjectName(projectRoot, workspaceRoot, wdb)
+		name := lookupProjectName(projectRoot, workspaceRoot, ws.Db)
 		return &Context{
-			Kind:        ContextProject,
-			ProjectRoot: projectRoot,
-			ProjectDb:   pdb,
-			ProjectName: name,
-			Workspace: &WorkspaceContext{
-				Root: workspaceRoot,
-				Db:   wdb,
-			},
+			Kind:          ContextProject,
+			ProjectRoot:   projectRoot,
+			ProjectDb:     pdb,
+			ProjectName:   name,
+			Workspace:     ws,
 			ownsWorkspace: true,
 		}, nil
 
@@ -80,16 +86,13 @@ func Discover(cwd string) (*Context, error) {
 		}, nil
 
 	case workspaceRoot != "":
-		wdb, err := db.OpenWorkspace(filepath.Join(workspaceRoot, ".mksp"))
+		ws, err := openWorkspace(workspaceRoot)
 		if err != nil {
 			return nil, err
 		}
 		return &Context{
-			Kind: ContextWorkspace,
-			Workspace: &WorkspaceContext{
-				Root: workspaceRoot,
-				Db:   wdb,
-			},
+			Kind:          ContextWorkspace,
+			Workspace:     ws,
 			ownsWorkspace: true,
 		}, nil
 
@@ -124,17 +127,94 @@ func DiscoverWorkspace(cwd string) (*Context, error) {
 	if workspaceRoot == "" {
 		return nil, fmt.Errorf("no workspace found from %s", cwd)
 	}
-	wdb, err := db.OpenWorkspace(filepath.Join(workspaceRoot, ".mksp"))
+	ws, err := openWorkspace(workspaceRoot)
 	if err != nil {
 		return nil, err
 	}
 	return &Context{
 		Kind:          ContextWorkspace,
-		Workspace:     &WorkspaceContext{Root: workspaceRoot, Db: wdb},
+		Workspace:     ws,
 		ownsWorkspace: true,
 	}, nil
 }
 
+// openWorkspace opens the workspace at root and, through Parent, each
+// workspace whose directory encloses it. A team's workspace inside its
+// desk's inherits the desk's settings this way.
+func openWorkspace(root string) (*WorkspaceContext, error) {
+	wdb, err := db.OpenWorkspace(filepath.Join(root, ".mksp"))
+	if err != nil {
+		return nil, err
+	}
+	ws := &WorkspaceContext{Root: root, Db: wdb}
+	_, parentRoot := findMarkers(filepath.Dir(root))
+	if parentRoot != "" && parentRoot != root {
+		parent, err := openWorkspace(parentRoot)
+		if err != nil {
+			wdb.Close()
+			return nil, err
+		}
+		ws.Parent = parent
+		ws.Name = lookupWorkspaceName(root, parent)
+	}
+	return ws, nil
+}
+
+// OpenSubWorkspace opens the workspace registered in parent as name,
+// returning nil if parent has none by that name. The caller closes the
+// returned workspace's Db; parent stays open.
+func OpenSubWorkspace(parent *WorkspaceContext, name string) (*WorkspaceContext, error) {
+	row, err := parent.Db.GetSubWorkspaceByName(name)
+	if err != nil || row == nil {
+		return nil, err
+	}
+	root := filepath.Join(parent.Root, row.Path)
+	wdb, err := db.OpenWorkspace(filepath.Join(root, ".mksp"))
+	if err != nil {
+		return nil, fmt.Errorf("workspace %q: %w", name, err)
+	}
+	return &WorkspaceContext{Root: root, Db: wdb, Name: name, Parent: parent}, nil
+}
+
+// Config returns a workspace setting, falling back to the enclosing
+// workspaces, nearest first, when this one leaves it unset.
+func (w *WorkspaceContext) Config(key string) (*string, error) {
+	for ; w != nil; w = w.Parent {
+		if w.Db == nil {
+			continue
+		}
+		if v, err := w.Db.GetConfig(key); err != nil || v != nil {
+			return v, err
+		}
+	}
+	return nil, nil
+}
+
+// close closes the workspace's database and those enclosing it.
+func (w *WorkspaceContext) close() error {
+	var errs []error
+	for ; w != nil; w = w.Parent {
+		if w.Db != nil {
+			errs = append(errs, w.Db.Close())
+		}
+	}
+	return errors.Join(errs...)
+}
+
+// FindProjectRoot walks up from cwd to the nearest .mkrk without opening
+// it, returning "" if there is none.
+func FindProjectRoot(cwd string) string {
+	projectRoot, _ := findMarkers(cwd)
+	return projectRoot
+}
+
+// FindWorkspaceRoot walks up from cwd to the nearest .mksp without opening
+// it, returning "" if there is none.
+func FindWorkspaceRoot(cwd string) string {
+	_, workspaceRoot := findMarkers(cwd)
+	return workspaceRoot
+}
+
 // RequireProject returns project root and db, or error if not in a project.
 func (c *Context) RequireProject() (string, *db.ProjectDb, error) {
 	if c.Kind != ContextProject {
@@ -143,15 +223,139 @@ func (c *Context) RequireProject() (string, *db.ProjectDb, error) {
 	return c.ProjectRoot, c.ProjectDb, nil
 }
 
-// Close releases database connections. The workspace DB is only closed if
-// this context owns it (i.e., the caller that constructed it).
-func (c *Context) Close() {
+// HashAlgorithm returns the algorithm the project identifies file content
+// by; SHA-256 outside a project.
+func (c *Context) HashAlgorithm() (integrity.Algorithm, error) {
+	if c == nil || c.ProjectDb == nil {
+		return integrity.SHA256, nil
+	}
+	name, err := c.ProjectDb.HashAlgorithm()
+	if err != nil {
+		return "", err
+	}
+	return integrity.ParseAlgorithm(name)
+}
+
+// Hasher returns the project's hash algorithm and fingerprint chunking;
+// SHA-256 and fixed chunks outside a project.
+func (c *Context) Hasher() (integrity.Hasher, error) {
+	alg, err := c.HashAlgorithm()
+	if err != nil {
+		return integrity.Hasher{}, err
+	}
+	if c == nil || c.ProjectDb == nil {
+		return integrity.Hasher{Algorithm: alg, Chunking: integrity.FixedChunks}, nil
+	}
+	name, err := c.ProjectDb.Chunking()
+	if err != nil {
+		return integrity.Hasher{}, err
+	}
+	chunking, err := integrity.ParseChunking(name)
+	if err != nil {
+		return integrity.Hasher{}, err
+	}
+	return integrity.Hasher{Algorithm: alg, Chunking: chunking}, nil
+}
+
+// HashFile hashes a file with the project's hash algorithm. An encrypted
+// file gets the hash of its plaintext, which identifies its content.
+func (c *Context) HashFile(path string) (string, error) {
+	alg, err := c.HashAlgorithm()
+	if err != nil {
+		return "", err
+	}
+	hash, err := alg.HashFile(path)
+	if err != nil {
+		return "", err
+	}
+	hash, _ = c.Identify(hash, nil)
+	return hash, nil
+}
+
+// Fingerprint fingerprints a file with the project's chunking. An
+// encrypted file gets the fingerprint of its plaintext.
+func (c *Context) Fingerprint(path string) (*integrity.Fingerprint, error) {
+	h, err := c.Hasher()
+	if err != nil {
+		return nil, err
+	}
+	fp, err := h.Fingerprint(path)
+	if err != nil || c == nil || c.ProjectDb == nil {
+		return fp, err
+	}
+	if ct, _ := c.ProjectDb.GetCiphertextByFingerprint(fp.ToJSON()); ct != nil && ct.PlaintextFingerprint != nil {
+		if plain, err := integrity.FingerprintFromJSON(*ct.PlaintextFingerprint); err == nil {
+			return plain, nil
+		}
+	}
+	return fp, nil
+}
+
+// HashAndFingerprint hashes a file with the project's hash algorithm and
+// fingerprints it with the project's chunking in the same pass. An
+// encrypted file gets the hash and fingerprint of its plaintext.
+func (c *Context) HashAndFingerprint(path string) (string, *integrity.Fingerprint, error) {
+	h, err := c.Hasher()
+	if err != nil {
+		return "", nil, err
+	}
+	hash, fp, err := h.HashAndFingerprint(path)
+	if err != nil {
+		return "", nil, err
+	}
+	hash, fp = c.Identify(hash, fp)
+	return hash, fp, nil
+}
+
+// Identify maps the hash and fingerprint of a file's bytes to those of the
+// content they stand for. They are the same except for a file encrypted
+// by mkrk, which stands for its plaintext; its stored form is recognized
+// without decrypting it. fp may be nil.
+func (c *Context) Identify(hash string, fp *integrity.Fingerprint) (string, *integrity.Fingerprint) {
+	if c == nil || c.ProjectDb == nil {
+		return hash, fp
+	}
+	ct, _ := c.ProjectDb.GetCiphertext(hash)
+	if ct == nil {
+		return hash, fp
+	}
+	if ct.PlaintextFingerprint != nil {
+		if plain, err := integrity.FingerprintFromJSON(*ct.PlaintextFingerprint); err == nil {
+			fp = plain
+		}
+	}
+	return ct.PlaintextSHA256, fp
+}
+
+// SavedRefs returns the stores that :@name references resolve against:
+// the project's saved references, then the workspace's, then those of the
+// workspaces enclosing it.
+func (c *Context) SavedRefs() []reference.SavedRefs {
+	var stores []reference.SavedRefs
 	if c.ProjectDb != nil {
-		c.ProjectDb.Close()
+		stores = append(stores, c.ProjectDb)
 	}
-	if c.ownsWorkspace && c.Workspace != nil && c.Workspace.Db != nil {
-		c.Workspace.Db.Close()
+	for ws := c.Workspace; ws != nil; ws = ws.Parent {
+		if ws.Db != nil {
+			stores = append(stores, ws.Db)
+		}
 	}
+	return stores
+}
+
+// Close releases database connections. The workspace DBs are only closed
+// if this context owns them (i.e., the caller that constructed it). A
+// database encrypted at rest is written back as it closes, so the error
+// is worth checking after a command that changed one.
+func (c *Context) Close() error {
+	var errs []error
+	if c.ProjectDb != nil {
+		errs = append(errs, c.ProjectDb.Close())
+	}
+	if c.ownsWorkspace {
+		errs = append(errs, c.Workspace.close())
+	}
+	return errors.Join(errs...)
 }
 
 func findMarkers(cwd string) (projectRoot, workspaceRoot string) {
@@ -201,3 +405,21 @@ func lookupProjectName(projectRoot, workspaceRoot string, wdb *db.WorkspaceDb) *
 	}
 	return nil
 }
+
+func lookupWorkspaceName(root string, parent *WorkspaceContext) string {
+	rel, err := filepath.Rel(parent.Root, root)
+	if err != nil {
+		return ""
+	}
+	rel = filepath.ToSlash(rel)
+	workspaces, err := parent.Db.ListSubWorkspaces()
+	if err != nil {
+		return ""
+	}
+	for _, w := range workspaces {
+		if w.Path == rel {
+			return w.Name
+		}
+	}
+	return ""
+}
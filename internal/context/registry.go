@@ -0,0 +1,183 @@
+package context
+
+import (
+	"bytes"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+	"strconv"
+	"strings"
+)
+
+// RegistryFileEnv overrides where the workspace registry is kept.
+const RegistryFileEnv = "MKRK_REGISTRY"
+
+// The registry is a TOML file in the user's config directory naming the
+// workspaces mkrk --workspace can reach from anywhere:
+//
+//	[workspaces]
+//	investigations = "/home/me/investigations"
+//
+// It belongs to the user, like their secrets, so no workspace or project
+// database ever records where other workspaces live.
+
+// RegistryPath returns the registry file: MKRK_REGISTRY, or
+// mkrk/registry.toml in the user's config directory.
+func RegistryPath() (string, error) {
+	if p := os.Getenv(RegistryFileEnv); p != "" {
+		return p, nil
+	}
+	dir, err := os.UserConfigDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "mkrk", "registry.toml"), nil
+}
+
+// LoadRegistry returns the registered workspaces, name to root. A missing
+// registry holds none.
+func LoadRegistry() (map[string]string, error) {
+	path, err := RegistryPath()
+	if err != nil {
+		return nil, err
+	}
+	data, err := os.ReadFile(path)
+	if os.IsNotExist(err) {
+		return map[string]string{}, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	workspaces, err := parseRegistry(data)
+	if err != nil {
+		return nil, fmt.Errorf("%s: %w", path, err)
+	}
+	return workspaces, nil
+}
+
+// SaveRegistry replaces the registry with workspaces.
+func SaveRegistry(workspaces map[string]string) error {
+	path, err := RegistryPath()
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0o700); err != nil {
+		return err
+	}
+	var b bytes.Buffer
+	b.WriteString("[workspaces]\n")
+	names := make([]string, 0, len(workspaces))
+	for name := range workspaces {
+		names = append(names, name)
+	}
+	slices.Sort(names)
+	for _, name := range names {
+		fmt.Fprintf(&b, "%s = %s\n", strconv.Quote(name), strconv.Quote(workspaces[name]))
+	}
+	return os.WriteFile(path, b.Bytes(), 0o600)
+}
+
+// parseRegistry reads the subset of TOML SaveRegistry writes: a
+// [workspaces] table of string keys and values, bare or quoted, with
+// comments. Other tables are ignored so the file can grow.
+func parseRegistry(data []byte) (map[string]string, error) {
+	workspaces := make(map[string]string)
+	inWorkspaces := false
+	for n, line := range strings.Split(string(data), "\n") {
+		lineNo := n + 1
+		content := strings.TrimSpace(line)
+		if content == "" || strings.HasPrefix(content, "#") {
+			continue
+		}
+		if strings.HasPrefix(content, "[") {
+			inWorkspaces = content == "[workspaces]"
+			continue
+		}
+		if !inWorkspaces {
+			continue
+		}
+		rawKey, rawValue, ok := strings.Cut(content, "=")
+		if !ok {
+			return nil, fmt.Errorf("line %d: expected 'name = \"path\"'", lineNo)
+		}
+		key := strings.TrimSpace(rawKey)
+		if strings.HasPrefix(key, `"`) || strings.HasPrefix(key, "'") {
+			var err error
+			if key, err = tomlString(key); err != nil {
+				return nil, fmt.Errorf("line %d: %w", lineNo, err)
+			}
+		}
+		value, err := tomlString(rawValue)
+		if err != nil {
+			return nil, fmt.Errorf("line %d: %w", lineNo, err)
+		}
+		workspaces[key] = value
+	}
+	return workspaces, nil
+}
+
+// tomlString reads a basic (double-quoted) or literal (single-quoted)
+// string, up to a trailing comment.
+func tomlString(raw string) (string, error) {
+	s := strings.TrimSpace(raw)
+	switch {
+	case strings.HasPrefix(s, `"`):
+		end := closingQuote(s)
+		if end < 0 {
+			return "", fmt.Errorf("unterminated string %s", s)
+		}
+		v, err := strconv.Unquote(s[:end+1])
+		if err != nil {
+			return "", fmt.Errorf("bad string %s", s)
+		}
+		return v, checkTrailing(s[end+1:])
+	case strings.HasPrefix(s, "'"):
+		end := strings.IndexByte(s[1:], '\'')
+		if end < 0 {
+			return "", fmt.Errorf("unterminated string %s", s)
+		}
+		return s[1 : end+1], checkTrailing(s[end+2:])
+	}
+	return "", fmt.Errorf("expected a quoted string, got %s", s)
+}
+
+// closingQuote returns the index of the quote ending the basic string s.
+func closingQuote(s string) int {
+	for i := 1; i < len(s); i++ {
+		switch s[i] {
+		case '\\':
+			i++
+		case '"':
+			return i
+		}
+	}
+	return -1
+}
+
+func checkTrailing(rest string) error {
+	rest = strings.TrimSpace(rest)
+	if rest != "" && !strings.HasPrefix(rest, "#") {
+		return fmt.Errorf("unexpected %s after string", rest)
+	}
+	return nil
+}
+
+// ResolveWorkspace returns the root of the workspace --workspace names:
+// one in the registry, or else a directory holding a .mksp.
+func ResolveWorkspace(name string) (string, error) {
+	workspaces, err := LoadRegistry()
+	if err != nil {
+		return "", err
+	}
+	if root, ok := workspaces[name]; ok {
+		if !fileExists(filepath.Join(root, ".mksp")) {
+			return "", fmt.Errorf("registered workspace '%s' not found at %s", name, root)
+		}
+		return root, nil
+	}
+	if fileExists(filepath.Join(name, ".mksp")) {
+		return filepath.Abs(name)
+	}
+	return "", fmt.Errorf("unknown workspace '%s' (register it with mkrk registry add %s <dir>)", name, name)
+}
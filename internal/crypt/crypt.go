@@ -0,0 +1,313 @@
+// Package crypt encrypts file contents at rest with a key derived from a
+// project passphrase. Files are sealed with AES-256-GCM in fixed-size
+// segments so large files stream without being held in memory; each
+// segment's nonce carries its index and the final segment is marked, so
+// segments cannot be reordered or truncated undetected.
+package crypt
+
+import (
+	"bufio"
+	"bytes"
+	"crypto/aes"
+	"crypto/cipher"
+	"crypto/hmac"
+	"crypto/pbkdf2"
+	"crypto/rand"
+	"crypto/sha256"
+	"encoding/binary"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+)
+
+// Magic prefixes every encrypted file.
+const Magic = "MKRKENC1"
+
+// LockMagic prefixes a locked project database. The salt for its
+// passphrase follows, then a sealed stream as written by Encrypt.
+const LockMagic = "MKRKLCK1"
+
+// SealedMagic prefixes a project or workspace database kept encrypted at
+// rest. It is laid out like a locked database, but mkrk opens it in memory
+// instead of refusing it.
+const SealedMagic = "MKRKSDB1"
+
+const (
+	segmentSize = 64 * 1024
+	prefixSize  = 8
+	aesOverhead = 16 // GCM tag per segment
+	kdfRounds   = 600_000
+	keySize     = 32
+	saltSize    = 16
+)
+
+// ErrAuth is returned when a file was not sealed with the given key or
+// has been altered since.
+var ErrAuth = errors.New("decrypt: wrong key or tampered file")
+
+// NewSalt returns a random salt for DeriveKey.
+func NewSalt() ([]byte, error) {
+	salt := make([]byte, saltSize)
+	if _, err := rand.Read(salt); err != nil {
+		return nil, err
+	}
+	return salt, nil
+}
+
+// DeriveKey stretches a passphrase into a file key with PBKDF2-SHA256.
+func DeriveKey(passphrase string, salt []byte) ([]byte, error) {
+	return pbkdf2.Key(sha256.New, passphrase, salt, kdfRounds, keySize)
+}
+
+// KeyCheck returns a value that confirms a key without revealing it, so a
+// mistyped passphrase is caught before anything is encrypted with it.
+func KeyCheck(key []byte) string {
+	mac := hmac.New(sha256.New, key)
+	mac.Write([]byte("muckrake key check"))
+	return hex.EncodeToString(mac.Sum(nil))
+}
+
+// IsEncrypted reports whether the file at path starts with Magic.
+func IsEncrypted(path string) (bool, error) {
+	return hasPrefix(path, Magic)
+}
+
+// IsLocked reports whether the file at path starts with LockMagic.
+func IsLocked(path string) (bool, error) {
+	return hasPrefix(path, LockMagic)
+}
+
+// IsSealed reports whether the file at path starts with SealedMagic.
+func IsSealed(path string) (bool, error) {
+	return hasPrefix(path, SealedMagic)
+}
+
+func hasPrefix(path, magic string) (bool, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return false, err
+	}
+	defer f.Close()
+	head := make([]byte, len(magic))
+	n, err := io.ReadFull(f, head)
+	if err == io.EOF || err == io.ErrUnexpectedEOF {
+		return false, nil
+	}
+	if err != nil {
+		return false, err
+	}
+	return string(head[:n]) == magic, nil
+}
+
+// PlaintextSize returns the size of the content sealed in a file of the
+// given encrypted size.
+func PlaintextSize(size int64) int64 {
+	body := size - int64(len(Magic)) - prefixSize
+	segment := int64(segmentSize + aesOverhead)
+	full := body / segment
+	rest := body % segment
+	if rest == 0 && full > 0 {
+		full--
+		rest = segment
+	}
+	return full*segmentSize + max(rest-aesOverhead, 0)
+}
+
+func newAEAD(key []byte) (cipher.AEAD, error) {
+	block, err := aes.NewCipher(key)
+	if err != nil {
+		return nil, err
+	}
+	return cipher.NewGCM(block)
+}
+
+func nonce(prefix []byte, index uint32) []byte {
+	n := make([]byte, prefixSize+4)
+	copy(n, prefix)
+	binary.BigEndian.PutUint32(n[prefixSize:], index)
+	return n
+}
+
+func segmentAD(final bool) []byte {
+	if final {
+		return []byte{1}
+	}
+	return []byte{0}
+}
+
+// Encrypt reads plaintext from src and writes the sealed file to dst.
+func Encrypt(dst io.Writer, src io.Reader, key []byte) error {
+	aead, err := newAEAD(key)
+	if err != nil {
+		return err
+	}
+	prefix := make([]byte, prefixSize)
+	if _, err := rand.Read(prefix); err != nil {
+		return err
+	}
+	if _, err := io.WriteString(dst, Magic); err != nil {
+		return err
+	}
+	if _, err := dst.Write(prefix); err != nil {
+		return err
+	}
+
+	in := bufio.NewReaderSize(src, segmentSize)
+	buf := make([]byte, segmentSize)
+	for index := uint32(0); ; index++ {
+		n, err := io.ReadFull(in, buf)
+		if err != nil && err != io.EOF && err != io.ErrUnexpectedEOF {
+			return err
+		}
+		_, peekErr := in.Peek(1)
+		final := n < segmentSize || peekErr == io.EOF
+		sealed := aead.Seal(nil, nonce(prefix, index), buf[:n], segmentAD(final))
+		if _, err := dst.Write(sealed); err != nil {
+			return err
+		}
+		if final {
+			return nil
+		}
+		if index == ^uint32(0) {
+			return fmt.Errorf("encrypt: file too large")
+		}
+	}
+}
+
+// reader decrypts a sealed stream segment by segment.
+type reader struct {
+	src    *bufio.Reader
+	aead   cipher.AEAD
+	prefix []byte
+	index  uint32
+	buf    bytes.Buffer
+	done   bool
+}
+
+// NewReader returns a reader of the plaintext sealed in src. Reads fail
+// with ErrAuth if the key is wrong or the content was altered.
+func NewReader(src io.Reader, key []byte) (io.Reader, error) {
+	aead, err := newAEAD(key)
+	if err != nil {
+		return nil, err
+	}
+	head := make([]byte, len(Magic)+prefixSize)
+	if _, err := io.ReadFull(src, head); err != nil {
+		return nil, fmt.Errorf("decrypt: %w", err)
+	}
+	if string(head[:len(Magic)]) != Magic {
+		return nil, fmt.Errorf("decrypt: not an encrypted file")
+	}
+	return &reader{
+		src:    bufio.NewReaderSize(src, segmentSize+aesOverhead),
+		aead:   aead,
+		prefix: head[len(Magic):],
+	}, nil
+}
+
+func (r *reader) Read(p []byte) (int, error) {
+	for r.buf.Len() == 0 {
+		if r.done {
+			return 0, io.EOF
+		}
+		if err := r.next(); err != nil {
+			return 0, err
+		}
+	}
+	return r.buf.Read(p)
+}
+
+func (r *reader) next() error {
+	sealed := make([]byte, segmentSize+aesOverhead)
+	n, err := io.ReadFull(r.src, sealed)
+	if err != nil && err != io.EOF && err != io.ErrUnexpectedEOF {
+		return err
+	}
+	_, peekErr := r.src.Peek(1)
+	final := n < len(sealed) || peekErr == io.EOF
+
+	plain, err := r.aead.Open(nil, nonce(r.prefix, r.index), sealed[:n], segmentAD(final))
+	if err != nil {
+		return ErrAuth
+	}
+	r.buf.Write(plain)
+	r.index++
+	r.done = final
+	return nil
+}
+
+// Lock seals src under a key derived from passphrase, writing the salt
+// alongside so Unlock needs nothing but the passphrase.
+func Lock(dst io.Writer, src io.Reader, passphrase string) error {
+	salt, err := NewSalt()
+	if err != nil {
+		return err
+	}
+	key, err := DeriveKey(passphrase, salt)
+	if err != nil {
+		return err
+	}
+	return writeWithSalt(dst, src, LockMagic, key, salt)
+}
+
+// Unlock returns a reader of the content sealed by Lock. Reads fail with
+// ErrAuth if the passphrase is wrong.
+func Unlock(src io.Reader, passphrase string) (io.Reader, error) {
+	salt, err := readSalt(src, LockMagic, "a locked file")
+	if err != nil {
+		return nil, fmt.Errorf("unlock: %w", err)
+	}
+	key, err := DeriveKey(passphrase, salt)
+	if err != nil {
+		return nil, err
+	}
+	return NewReader(src, key)
+}
+
+// Seal writes src as a database sealed at rest under key, recording the
+// salt key was derived with so Unseal can derive it again. Resealing with
+// the key and salt Unseal returned skips the key derivation.
+func Seal(dst io.Writer, src io.Reader, key, salt []byte) error {
+	return writeWithSalt(dst, src, SealedMagic, key, salt)
+}
+
+// Unseal derives the key of a database written by Seal from passphrase and
+// returns it with its salt and a reader of the plaintext. Reads fail with
+// ErrAuth if the passphrase is wrong.
+func Unseal(src io.Reader, passphrase string) (key, salt []byte, r io.Reader, err error) {
+	salt, err = readSalt(src, SealedMagic, "a sealed database")
+	if err != nil {
+		return nil, nil, nil, fmt.Errorf("unseal: %w", err)
+	}
+	key, err = DeriveKey(passphrase, salt)
+	if err != nil {
+		return nil, nil, nil, err
+	}
+	r, err = NewReader(src, key)
+	return key, salt, r, err
+}
+
+func writeWithSalt(dst io.Writer, src io.Reader, magic string, key, salt []byte) error {
+	if _, err := io.WriteString(dst, magic); err != nil {
+		return err
+	}
+	if _, err := dst.Write(salt); err != nil {
+		return err
+	}
+	return Encrypt(dst, src, key)
+}
+
+// readSalt reads magic and the salt after it from the head of src, which
+// should be what.
+func readSalt(src io.Reader, magic, what string) ([]byte, error) {
+	head := make([]byte, len(magic)+saltSize)
+	if _, err := io.ReadFull(src, head); err != nil {
+		return nil, err
+	}
+	if string(head[:len(magic)]) != magic {
+		return nil, fmt.Errorf("not %s", what)
+	}
+	return head[len(magic):], nil
+}
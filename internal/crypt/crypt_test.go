@@ -0,0 +1,159 @@
+package crypt
+
+import (
+	"bytes"
+	"io"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+func testKey(t *testing.T) []byte {
+	t.Helper()
+	key, err := DeriveKey("correct horse", []byte("0123456789abcdef"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	return key
+}
+
+func roundTrip(t *testing.T, key []byte, plain []byte) []byte {
+	t.Helper()
+	var sealed bytes.Buffer
+	if err := Encrypt(&sealed, bytes.NewReader(plain), key); err != nil {
+		t.Fatalf("encrypt: %v", err)
+	}
+	if got := PlaintextSize(int64(sealed.Len())); got != int64(len(plain)) {
+		t.Fatalf("plaintext size: expected %d, got %d", len(plain), got)
+	}
+	r, err := NewReader(&sealed, key)
+	if err != nil {
+		t.Fatalf("new reader: %v", err)
+	}
+	out, err := io.ReadAll(r)
+	if err != nil {
+		t.Fatalf("decrypt: %v", err)
+	}
+	return out
+}
+
+func TestRoundTrip(t *testing.T) {
+	key := testKey(t)
+	for _, n := range []int{0, 1, segmentSize - 1, segmentSize, segmentSize + 1, 3 * segmentSize} {
+		plain := bytes.Repeat([]byte{'m'}, n)
+		if out := roundTrip(t, key, plain); !bytes.Equal(out, plain) {
+			t.Fatalf("size %d: round trip mismatch", n)
+		}
+	}
+}
+
+func TestWrongKeyAndTampering(t *testing.T) {
+	key := testKey(t)
+	var sealed bytes.Buffer
+	Encrypt(&sealed, bytes.NewReader(bytes.Repeat([]byte{'x'}, 2*segmentSize)), key)
+
+	other, _ := DeriveKey("wrong", []byte("0123456789abcdef"))
+	r, _ := NewReader(bytes.NewReader(sealed.Bytes()), other)
+	if _, err := io.ReadAll(r); err != ErrAuth {
+		t.Fatalf("expected ErrAuth for wrong key, got %v", err)
+	}
+
+	truncated := sealed.Bytes()[:len(Magic)+prefixSize+segmentSize+aesOverhead]
+	r, _ = NewReader(bytes.NewReader(truncated), key)
+	if _, err := io.ReadAll(r); err != ErrAuth {
+		t.Fatalf("expected ErrAuth for truncated file, got %v", err)
+	}
+
+	flipped := bytes.Clone(sealed.Bytes())
+	flipped[len(flipped)-1] ^= 1
+	r, _ = NewReader(bytes.NewReader(flipped), key)
+	if _, err := io.ReadAll(r); err != ErrAuth {
+		t.Fatalf("expected ErrAuth for altered file, got %v", err)
+	}
+}
+
+func TestKeyCheck(t *testing.T) {
+	key := testKey(t)
+	other, _ := DeriveKey("wrong", []byte("0123456789abcdef"))
+	if KeyCheck(key) == KeyCheck(other) {
+		t.Fatal("different keys must not share a check value")
+	}
+	if KeyCheck(key) != KeyCheck(testKey(t)) {
+		t.Fatal("key check must be stable")
+	}
+}
+
+func TestIsEncrypted(t *testing.T) {
+	dir := t.TempDir()
+	plain := filepath.Join(dir, "plain.txt")
+	os.WriteFile(plain, []byte("hello"), 0o644)
+	if enc, _ := IsEncrypted(plain); enc {
+		t.Fatal("plaintext reported as encrypted")
+	}
+
+	sealed := filepath.Join(dir, "sealed")
+	f, _ := os.Create(sealed)
+	Encrypt(f, bytes.NewReader([]byte("hello")), testKey(t))
+	f.Close()
+	if enc, _ := IsEncrypted(sealed); !enc {
+		t.Fatal("sealed file not detected")
+	}
+}
+
+func TestLockUnlock(t *testing.T) {
+	plain := []byte("SQLite format 3\x00 pretend database")
+	var locked bytes.Buffer
+	if err := Lock(&locked, bytes.NewReader(plain), "correct horse"); err != nil {
+		t.Fatalf("lock: %v", err)
+	}
+	if !bytes.HasPrefix(locked.Bytes(), []byte(LockMagic)) || bytes.Contains(locked.Bytes(), []byte("SQLite")) {
+		t.Fatal("expected sealed output behind LockMagic")
+	}
+
+	r, err := Unlock(bytes.NewReader(locked.Bytes()), "correct horse")
+	if err != nil {
+		t.Fatalf("unlock: %v", err)
+	}
+	if out, err := io.ReadAll(r); err != nil || !bytes.Equal(out, plain) {
+		t.Fatalf("unlock mismatch: %q, %v", out, err)
+	}
+
+	r, _ = Unlock(bytes.NewReader(locked.Bytes()), "wrong")
+	if _, err := io.ReadAll(r); err != ErrAuth {
+		t.Fatalf("expected ErrAuth for wrong passphrase, got %v", err)
+	}
+}
+
+func TestSealUnseal(t *testing.T) {
+	plain := []byte("SQLite format 3\x00 pretend database")
+	salt, _ := NewSalt()
+	key, _ := DeriveKey("correct horse", salt)
+	var sealed bytes.Buffer
+	if err := Seal(&sealed, bytes.NewReader(plain), key, salt); err != nil {
+		t.Fatalf("seal: %v", err)
+	}
+	if !bytes.HasPrefix(sealed.Bytes(), []byte(SealedMagic)) || bytes.Contains(sealed.Bytes(), []byte("SQLite")) {
+		t.Fatal("expected sealed output behind SealedMagic")
+	}
+
+	gotKey, gotSalt, r, err := Unseal(bytes.NewReader(sealed.Bytes()), "correct horse")
+	if err != nil {
+		t.Fatalf("unseal: %v", err)
+	}
+	if !bytes.Equal(gotKey, key) || !bytes.Equal(gotSalt, salt) {
+		t.Fatal("expected the key and salt sealed with")
+	}
+	if out, err := io.ReadAll(r); err != nil || !bytes.Equal(out, plain) {
+		t.Fatalf("unseal mismatch: %q, %v", out, err)
+	}
+
+	_, _, r, _ = Unseal(bytes.NewReader(sealed.Bytes()), "wrong")
+	if _, err := io.ReadAll(r); err != ErrAuth {
+		t.Fatalf("expected ErrAuth for wrong passphrase, got %v", err)
+	}
+	var locked bytes.Buffer
+	Lock(&locked, bytes.NewReader(plain), "correct horse")
+	if _, _, _, err := Unseal(bytes.NewReader(locked.Bytes()), "correct horse"); err == nil {
+		t.Fatal("expected a locked database not to unseal")
+	}
+}
@@ -0,0 +1,72 @@
+package db
+
+import (
+	"fmt"
+	"strings"
+)
+
+// structuralTables lists, in dependency order, the tables that describe
+// how a project is organized rather than what it contains.
+var structuralTables = []struct {
+	name    string
+	columns []string
+}{
+	{"scopes", []string{"id", "name", "scope_type", "pattern", "category_type", "description", "created_at"}},
+	{"scope_policy", []string{"id", "scope_id", "protection_level"}},
+	{"scope_encryption", []string{"id", "scope_id"}},
+	{"scope_tool_config", []string{"id", "scope_id", "action", "file_type", "command", "env", "quiet"}},
+	{"open_config", []string{"id", "scope_id", "mime_type", "command", "read_only"}},
+	{"pipelines", []string{"id", "name", "states", "transitions"}},
+	{"pipeline_subscriptions", []string{"id", "pipeline_id", "reference", "created_at"}},
+	{"rules", []string{"id", "name", "enabled", "trigger_event", "trigger_filter", "action_type", "action_config", "priority", "created_at"}},
+	{"rulesets", []string{"id", "name", "description"}},
+	{"ruleset_rules", []string{"id", "ruleset_id", "priority", "condition", "action_type", "action_config"}},
+	{"ruleset_subscriptions", []string{"id", "ruleset_id", "reference", "created_at"}},
+}
+
+// CopyStructure copies categories, policies, encryption modes, tool and
+// viewer configs, pipelines, rules and rulesets from p into dst, which must
+// not define any of them yet. Row IDs are preserved so references between
+// the tables stay valid. Files, tags, signs, audit entries and the
+// encryption key parameters are never copied.
+func (p *ProjectDb) CopyStructure(dst *ProjectDb) error {
+	tx, err := dst.conn.Begin()
+	if err != nil {
+		return err
+	}
+	defer tx.Rollback()
+
+	for _, t := range structuralTables {
+		cols := strings.Join(t.columns, ", ")
+		rows, err := p.db.Query(fmt.Sprintf(`SELECT %s FROM %s ORDER BY id`, cols, t.name))
+		if err != nil {
+			return fmt.Errorf("copy %s: %w", t.name, err)
+		}
+
+		placeholders := strings.TrimSuffix(strings.Repeat("?, ", len(t.columns)), ", ")
+		insert := fmt.Sprintf(`INSERT INTO %s (%s) VALUES (%s)`, t.name, cols, placeholders)
+
+		for rows.Next() {
+			vals := make([]any, len(t.columns))
+			ptrs := make([]any, len(t.columns))
+			for i := range vals {
+				ptrs[i] = &vals[i]
+			}
+			if err := rows.Scan(ptrs...); err != nil {
+				rows.Close()
+				return fmt.Errorf("copy %s: %w", t.name, err)
+			}
+			if _, err := tx.Exec(insert, vals...); err != nil {
+				rows.Close()
+				return fmt.Errorf("copy %s: %w", t.name, err)
+			}
+		}
+		err = rows.Err()
+		rows.Close()
+		if err != nil {
+			return fmt.Errorf("copy %s: %w", t.name, err)
+		}
+	}
+
+	return tx.Commit()
+}
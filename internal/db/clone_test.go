@@ -0,0 +1,42 @@
+package db
+
+import (
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestCopyStructure(t *testing.T) {
+	src := testDb(t)
+	catID, _ := src.InsertScope(makeCategory("evidence", "evidence/**"))
+	src.InsertScopePolicy(catID, models.ProtectionImmutable)
+	pid, _ := src.InsertPipeline(makePipeline("review", []string{"draft", "done"}))
+	src.SubscribePipeline(pid, ":evidence")
+	src.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	dst := testDb(t)
+	if err := src.CopyStructure(dst); err != nil {
+		t.Fatal(err)
+	}
+
+	level, err := dst.ResolveProtection("evidence/doc.pdf")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if level != models.ProtectionImmutable {
+		t.Fatalf("expected immutable policy copied, got %v", level)
+	}
+
+	p, _ := dst.GetPipelineByName("review")
+	if p == nil {
+		t.Fatal("expected pipeline copied")
+	}
+	subs, _ := dst.ListPipelineSubscriptions(*p.ID)
+	if len(subs) != 1 {
+		t.Fatalf("expected subscription copied, got %d", len(subs))
+	}
+
+	if n, _ := dst.FileCount(); n != 0 {
+		t.Fatalf("files must not be copied, got %d", n)
+	}
+}
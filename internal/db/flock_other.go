@@ -0,0 +1,12 @@
+//go:build !unix
+
+package db
+
+import "os"
+
+// lockBeside creates the lock file beside path without locking it, where
+// advisory locks are not available; two processes can then open the same
+// encrypted database and the last to close it wins.
+func lockBeside(path string) (*os.File, error) {
+	return os.OpenFile(path+"-lock", os.O_RDWR|os.O_CREATE, 0o600)
+}
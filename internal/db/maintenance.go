@@ -0,0 +1,226 @@
+package db
+
+import (
+	"bytes"
+	"context"
+	"database/sql"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+
+	"modernc.org/sqlite"
+
+	"go.foia.dev/muckrake/internal/crypt"
+)
+
+// Backup writes a copy of the database to dst with SQLite's online backup
+// API, which copies a consistent snapshot even while the database is in
+// use and its write-ahead log holds recent changes. A database encrypted
+// at rest is copied sealed under the same passphrase. The copy is checked
+// before Backup returns; one that fails is removed.
+func (p *ProjectDb) Backup(dst string) error {
+	return backup(p.conn, p.rest, p.path, dst)
+}
+
+// Backup writes a checked copy of the database to dst; see
+// ProjectDb.Backup.
+func (w *WorkspaceDb) Backup(dst string) error {
+	return backup(w.db, w.rest, w.path, dst)
+}
+
+// Vacuum rebuilds the database to reclaim the space of deleted rows and
+// refreshes the statistics the query planner uses, returning its size
+// before and after.
+func (p *ProjectDb) Vacuum() (before, after int64, err error) {
+	return vacuum(p.conn, p.rest)
+}
+
+// Vacuum rebuilds the database and refreshes its statistics; see
+// ProjectDb.Vacuum.
+func (w *WorkspaceDb) Vacuum() (before, after int64, err error) {
+	return vacuum(w.db, w.rest)
+}
+
+func backup(d *sql.DB, rest *atRest, src, dst string) error {
+	if _, err := os.Stat(dst); err == nil {
+		return fmt.Errorf("%s already exists", dst)
+	}
+	var err error
+	if rest.sealed() {
+		err = backupSealed(d, rest, dst)
+	} else {
+		err = backupPages(d, dst)
+	}
+	if err == nil {
+		err = verifyCopy(d, rest, dst)
+	}
+	if err == nil {
+		if info, statErr := os.Stat(src); statErr == nil {
+			err = os.Chmod(dst, info.Mode().Perm())
+		}
+	}
+	if err != nil {
+		os.Remove(dst)
+		return fmt.Errorf("back up database: %w", err)
+	}
+	return nil
+}
+
+// backupPages copies d page by page into a new database at dst.
+func backupPages(d *sql.DB, dst string) error {
+	conn, err := d.Conn(context.Background())
+	if err != nil {
+		return err
+	}
+	defer conn.Close()
+	return conn.Raw(func(driverConn any) error {
+		c, ok := driverConn.(interface {
+			NewBackup(string) (*sqlite.Backup, error)
+		})
+		if !ok {
+			return errors.New("sqlite driver has no backup API")
+		}
+		b, err := c.NewBackup(dst)
+		if err != nil {
+			return err
+		}
+		for more := true; more; {
+			if more, err = b.Step(-1); err != nil {
+				b.Finish()
+				return err
+			}
+		}
+		return b.Finish()
+	})
+}
+
+// backupSealed writes d to dst sealed as rest seals it.
+func backupSealed(d *sql.DB, rest *atRest, dst string) error {
+	image, err := serialize(d)
+	if err != nil {
+		return err
+	}
+	f, err := os.OpenFile(dst, os.O_WRONLY|os.O_CREATE|os.O_EXCL, 0o600)
+	if err != nil {
+		return err
+	}
+	err = crypt.Seal(f, bytes.NewReader(image), rest.key, rest.salt)
+	if syncErr := f.Sync(); err == nil {
+		err = syncErr
+	}
+	if closeErr := f.Close(); err == nil {
+		err = closeErr
+	}
+	return err
+}
+
+// verifyCopy runs SQLite's integrity check on the copy at path and
+// compares its tables' row counts with d's.
+func verifyCopy(d *sql.DB, rest *atRest, path string) error {
+	var copied *sql.DB
+	var err error
+	if rest.sealed() {
+		copied, err = openSealedCopy(path, rest)
+	} else {
+		copied, err = sql.Open("sqlite", path)
+	}
+	if err != nil {
+		return err
+	}
+	defer copied.Close()
+
+	var result string
+	if err := copied.QueryRow(`PRAGMA integrity_check`).Scan(&result); err != nil {
+		return fmt.Errorf("check copy: %w", err)
+	}
+	if result != "ok" {
+		return fmt.Errorf("copy failed its integrity check: %s", result)
+	}
+	tables, err := tableNames(d)
+	if err != nil {
+		return err
+	}
+	for _, table := range tables {
+		var want, got int64
+		if err := d.QueryRow(`SELECT COUNT(*) FROM "` + table + `"`).Scan(&want); err != nil {
+			return err
+		}
+		if err := copied.QueryRow(`SELECT COUNT(*) FROM "` + table + `"`).Scan(&got); err != nil {
+			return fmt.Errorf("check copy of %s: %w", table, err)
+		}
+		if got != want {
+			return fmt.Errorf("copy of %s has %d rows, expected %d", table, got, want)
+		}
+	}
+	return nil
+}
+
+// openSealedCopy decrypts a copy written by backupSealed into memory with
+// rest's key.
+func openSealedCopy(path string, rest *atRest) (*sql.DB, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+	if _, err := f.Seek(int64(len(crypt.SealedMagic)+len(rest.salt)), io.SeekStart); err != nil {
+		return nil, err
+	}
+	r, err := crypt.NewReader(f, rest.key)
+	if err != nil {
+		return nil, err
+	}
+	image, err := io.ReadAll(r)
+	if err != nil {
+		return nil, fmt.Errorf("read copy: %w", err)
+	}
+	return memoryDb(image)
+}
+
+func tableNames(d *sql.DB) ([]string, error) {
+	rows, err := d.Query(`SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	var names []string
+	for rows.Next() {
+		var name string
+		if err := rows.Scan(&name); err != nil {
+			return nil, err
+		}
+		names = append(names, name)
+	}
+	return names, rows.Err()
+}
+
+func vacuum(d *sql.DB, rest *atRest) (before, after int64, err error) {
+	if before, err = databaseSize(d); err != nil {
+		return 0, 0, err
+	}
+	if _, err := d.Exec(`VACUUM`); err != nil {
+		return 0, 0, fmt.Errorf("vacuum: %w", err)
+	}
+	if _, err := d.Exec(`ANALYZE`); err != nil {
+		return 0, 0, fmt.Errorf("analyze: %w", err)
+	}
+	// Neither counts as a change, so a database encrypted at rest would
+	// otherwise not be written back.
+	if rest != nil {
+		rest.rewrite = true
+	}
+	after, err = databaseSize(d)
+	return before, after, err
+}
+
+func databaseSize(d *sql.DB) (int64, error) {
+	var pages, pageSize int64
+	if err := d.QueryRow(`PRAGMA page_count`).Scan(&pages); err != nil {
+		return 0, err
+	}
+	if err := d.QueryRow(`PRAGMA page_size`).Scan(&pageSize); err != nil {
+		return 0, err
+	}
+	return pages * pageSize, nil
+}
@@ -0,0 +1,84 @@
+package db
+
+import (
+	"bytes"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestBackup(t *testing.T) {
+	dir := t.TempDir()
+	pdb, err := CreateProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer pdb.Close()
+	pdb.InsertFile(&models.TrackedFile{SHA256: "aaa", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	pdb.InsertTag(1, "source/whistleblower", "aaa", "[]")
+
+	dst := filepath.Join(dir, "copy.mkrk")
+	if err := pdb.Backup(dst); err != nil {
+		t.Fatal(err)
+	}
+	if err := pdb.Backup(dst); err == nil {
+		t.Fatal("expected a backup over an existing file to fail")
+	}
+	copied, err := OpenProject(dst)
+	if err != nil {
+		t.Fatal(err)
+	}
+	tags, _ := copied.GetTags(1)
+	copied.Close()
+	if len(tags) != 1 || tags[0] != "source/whistleblower" {
+		t.Fatalf("expected the tag in the copy, got %v", tags)
+	}
+
+	defer func(prev func(string) (string, error)) { Passphrase = prev }(Passphrase)
+	Passphrase = func(string) (string, error) { return "correct horse", nil }
+	if err := pdb.SealAtRest("correct horse"); err != nil {
+		t.Fatal(err)
+	}
+	sealedDst := filepath.Join(dir, "sealed.mkrk")
+	if err := pdb.Backup(sealedDst); err != nil {
+		t.Fatal(err)
+	}
+	data, _ := os.ReadFile(sealedDst)
+	if !bytes.HasPrefix(data, []byte(crypt.SealedMagic)) || bytes.Contains(data, []byte("whistleblower")) {
+		t.Fatal("expected the backup of a sealed database to be sealed")
+	}
+	copied, err = OpenProject(sealedDst)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer copied.Close()
+	if tags, _ := copied.GetTags(1); len(tags) != 1 {
+		t.Fatalf("expected the tag in the sealed copy, got %v", tags)
+	}
+}
+
+func TestVacuum(t *testing.T) {
+	pdb, err := CreateProject(filepath.Join(t.TempDir(), ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer pdb.Close()
+	for i := range 200 {
+		pdb.InsertFile(&models.TrackedFile{SHA256: fmt.Sprintf("%04d", i) + strings.Repeat("a", 4000), Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	}
+	if _, err := pdb.db.Exec(`DELETE FROM files`); err != nil {
+		t.Fatal(err)
+	}
+	before, after, err := pdb.Vacuum()
+	if err != nil {
+		t.Fatal(err)
+	}
+	if after >= before {
+		t.Fatalf("expected vacuum to shrink the database, got %d → %d", before, after)
+	}
+}
@@ -0,0 +1,289 @@
+package db
+
+import (
+	"fmt"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// MergeResult counts what MergeFileInto carried over for one file.
+type MergeResult struct {
+	FileID  int64
+	NewFile bool
+	Tags    int64
+	Signs   int64
+	History int64
+	Audit   int64
+}
+
+// MergeFileInto copies the record for the file with the given hash from p
+// into dst, along with its tags, metadata, attributes, signs, state
+// history, audit history and the hashes of its encrypted forms. Pipelines
+// are matched by name; any the target lacks are created from the source
+// definition. If dst already tracks the same content, the histories are
+// combined on its existing record. Signs, state changes and audit entries
+// already present in dst are not duplicated, so merging the same file
+// again only carries over what changed, including sign revocations.
+func (p *ProjectDb) MergeFileInto(dst *ProjectDb, sha256 string) (*MergeResult, error) {
+	src, err := p.GetFileByHash(sha256)
+	if err != nil {
+		return nil, err
+	}
+	if src == nil || src.ID == nil {
+		return nil, fmt.Errorf("merge %s: not tracked in source", sha256)
+	}
+
+	result := &MergeResult{}
+	existing, err := dst.GetFileByHash(sha256)
+	if err != nil {
+		return nil, err
+	}
+	if existing != nil && existing.ID != nil {
+		result.FileID = *existing.ID
+	} else {
+		f := *src
+		f.ID = nil
+		id, err := dst.InsertFile(&f)
+		if err != nil {
+			return nil, fmt.Errorf("merge file: %w", err)
+		}
+		result.FileID = id
+		result.NewFile = true
+	}
+
+	// Pipelines are resolved before the transaction so that creating one
+	// in dst does not contend with the transaction's write lock.
+	signs, err := p.GetSignsForFile(*src.ID)
+	if err != nil {
+		return nil, fmt.Errorf("merge signs: %w", err)
+	}
+	pipelineIDs := make(map[int64]int64)
+	for _, s := range signs {
+		if _, ok := pipelineIDs[s.PipelineID]; ok {
+			continue
+		}
+		id, err := p.mergePipelineInto(dst, s.PipelineID)
+		if err != nil {
+			return nil, err
+		}
+		pipelineIDs[s.PipelineID] = id
+	}
+
+	tx, err := dst.conn.Begin()
+	if err != nil {
+		return nil, err
+	}
+	defer tx.Rollback()
+
+	// Tags
+	rows, err := p.db.Query(
+		`SELECT tag, file_hash, fingerprint FROM file_tags WHERE file_id = ?`, *src.ID,
+	)
+	if err != nil {
+		return nil, fmt.Errorf("merge tags: %w", err)
+	}
+	type tagRow struct {
+		tag               string
+		hash, fingerprint *string
+	}
+	var tags []tagRow
+	for rows.Next() {
+		var t tagRow
+		if err := rows.Scan(&t.tag, &t.hash, &t.fingerprint); err != nil {
+			rows.Close()
+			return nil, err
+		}
+		tags = append(tags, t)
+	}
+	rows.Close()
+	for _, t := range tags {
+		res, err := tx.Exec(
+			`INSERT OR IGNORE INTO file_tags (file_id, tag, file_hash, fingerprint)
+			 VALUES (?, ?, ?, ?)`,
+			result.FileID, t.tag, t.hash, t.fingerprint,
+		)
+		if err != nil {
+			return nil, fmt.Errorf("merge tags: %w", err)
+		}
+		n, _ := res.RowsAffected()
+		result.Tags += n
+	}
+
+	// Metadata
+	metadata, err := p.GetFileMetadata(*src.ID)
+	if err != nil {
+		return nil, fmt.Errorf("merge metadata: %w", err)
+	}
+	for k, v := range metadata {
+		if _, err := tx.Exec(
+			`INSERT OR IGNORE INTO file_metadata (file_id, key, value) VALUES (?, ?, ?)`,
+			result.FileID, k, v,
+		); err != nil {
+			return nil, fmt.Errorf("merge metadata: %w", err)
+		}
+	}
+
+	// Attributes, keeping dst's value where both set one
+	attrs, err := p.GetFileAttrs(*src.ID)
+	if err != nil {
+		return nil, fmt.Errorf("merge attributes: %w", err)
+	}
+	for k, v := range attrs {
+		if _, err := tx.Exec(
+			`INSERT OR IGNORE INTO file_attrs (file_id, key, value) VALUES (?, ?, ?)`,
+			result.FileID, k, v,
+		); err != nil {
+			return nil, fmt.Errorf("merge attributes: %w", err)
+		}
+	}
+
+	// Signs
+	for _, s := range signs {
+		dstPipeline := pipelineIDs[s.PipelineID]
+		var dup int
+		if err := tx.QueryRow(
+			`SELECT COUNT(*) FROM signs
+			 WHERE pipeline_id = ? AND file_id = ? AND file_hash = ?
+			   AND sign_name = ? AND signer = ? AND signed_at = ?`,
+			dstPipeline, result.FileID, s.FileHash, s.SignName, s.Signer, s.SignedAt,
+		).Scan(&dup); err != nil {
+			return nil, err
+		}
+		if dup > 0 {
+			// A revocation made since the sign was copied carries over.
+			if s.RevokedAt != nil {
+				if _, err := tx.Exec(
+					`UPDATE signs SET revoked_at = ?
+					 WHERE pipeline_id = ? AND file_id = ? AND file_hash = ?
+					   AND sign_name = ? AND signer = ? AND signed_at = ? AND revoked_at IS NULL`,
+					s.RevokedAt, dstPipeline, result.FileID, s.FileHash, s.SignName, s.Signer, s.SignedAt,
+				); err != nil {
+					return nil, fmt.Errorf("merge signs: %w", err)
+				}
+			}
+			continue
+		}
+		if _, err := tx.Exec(
+			`INSERT INTO signs (pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature, revoked_at,
+			                    signature_format, signing_key, timestamp_token)
+			 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+			dstPipeline, result.FileID, s.FileHash, s.SignName, s.Signer, s.SignedAt, s.Signature, s.RevokedAt,
+			s.SignatureFormat, s.SigningKey, s.TimestampToken,
+		); err != nil {
+			return nil, fmt.Errorf("merge signs: %w", err)
+		}
+		result.Signs++
+	}
+
+	// State history
+	history, err := p.GetStateHistory(*src.ID)
+	if err != nil {
+		return nil, fmt.Errorf("merge state history: %w", err)
+	}
+	for _, c := range history {
+		dstPipeline, ok := pipelineIDs[c.PipelineID]
+		if !ok {
+			continue
+		}
+		var dup int
+		if err := tx.QueryRow(
+			`SELECT COUNT(*) FROM state_history
+			 WHERE file_id = ? AND pipeline_id = ? AND changed_at = ?
+			   AND from_state = ? AND to_state = ? AND cause = ?`,
+			result.FileID, dstPipeline, c.ChangedAt, c.FromState, c.ToState, c.Cause,
+		).Scan(&dup); err != nil {
+			return nil, err
+		}
+		if dup > 0 {
+			continue
+		}
+		if _, err := tx.Exec(
+			`INSERT INTO state_history (file_id, pipeline_id, from_state, to_state, changed_at, cause, sign_name, actor)
+			 VALUES (?, ?, ?, ?, ?, ?, ?, ?)`,
+			result.FileID, dstPipeline, c.FromState, c.ToState, c.ChangedAt, c.Cause, c.SignName, c.Actor,
+		); err != nil {
+			return nil, fmt.Errorf("merge state history: %w", err)
+		}
+		result.History++
+	}
+
+	// Audit
+	rows, err = p.db.Query(
+		`SELECT timestamp, operation, user, detail FROM audit_log WHERE file_id = ? ORDER BY id`, *src.ID,
+	)
+	if err != nil {
+		return nil, fmt.Errorf("merge audit: %w", err)
+	}
+	type auditRow struct {
+		timestamp, operation string
+		user, detail         *string
+	}
+	var entries []auditRow
+	for rows.Next() {
+		var a auditRow
+		if err := rows.Scan(&a.timestamp, &a.operation, &a.user, &a.detail); err != nil {
+			rows.Close()
+			return nil, err
+		}
+		entries = append(entries, a)
+	}
+	rows.Close()
+	for _, a := range entries {
+		var dup int
+		if err := tx.QueryRow(
+			`SELECT COUNT(*) FROM audit_log
+			 WHERE file_id = ? AND timestamp = ? AND operation = ? AND user IS ? AND detail IS ?`,
+			result.FileID, a.timestamp, a.operation, a.user, a.detail,
+		).Scan(&dup); err != nil {
+			return nil, err
+		}
+		if dup > 0 {
+			continue
+		}
+		if err := insertAudit(tx, a.timestamp, a.operation, &result.FileID, a.user, a.detail); err != nil {
+			return nil, fmt.Errorf("merge audit: %w", err)
+		}
+		result.Audit++
+	}
+
+	// Encrypted forms
+	ciphertexts, err := p.listCiphertexts(sha256)
+	if err != nil {
+		return nil, fmt.Errorf("merge ciphertexts: %w", err)
+	}
+	for _, c := range ciphertexts {
+		if _, err := tx.Exec(
+			`INSERT OR IGNORE INTO ciphertexts (sha256, fingerprint, plaintext_sha256, plaintext_fingerprint)
+			 VALUES (?, ?, ?, ?)`,
+			c.SHA256, c.Fingerprint, c.PlaintextSHA256, c.PlaintextFingerprint,
+		); err != nil {
+			return nil, fmt.Errorf("merge ciphertexts: %w", err)
+		}
+	}
+
+	if err := tx.Commit(); err != nil {
+		return nil, err
+	}
+	return result, nil
+}
+
+// mergePipelineInto returns the ID in dst of the pipeline named like the
+// source pipeline srcID, creating it from the source definition if needed.
+func (p *ProjectDb) mergePipelineInto(dst *ProjectDb, srcID int64) (int64, error) {
+	var name string
+	if err := p.db.QueryRow(`SELECT name FROM pipelines WHERE id = ?`, srcID).Scan(&name); err != nil {
+		return 0, fmt.Errorf("merge pipeline %d: %w", srcID, err)
+	}
+	if pl, err := dst.GetPipelineByName(name); err != nil {
+		return 0, err
+	} else if pl != nil && pl.ID != nil {
+		return *pl.ID, nil
+	}
+
+	pl, err := p.GetPipelineByName(name)
+	if err != nil {
+		return 0, err
+	}
+	def := models.Pipeline{Name: pl.Name, States: pl.States, Transitions: pl.Transitions}
+	return dst.InsertPipeline(&def)
+}
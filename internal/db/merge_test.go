@@ -0,0 +1,54 @@
+package db
+
+import (
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestMergeFileInto(t *testing.T) {
+	src := testDb(t)
+	fid, _ := src.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	src.InsertTag(fid, "leak", "abc", "[]")
+	pid, _ := src.InsertPipeline(makePipeline("review", []string{"draft", "done"}))
+	src.InsertSign(&models.Sign{PipelineID: pid, FileID: fid, FileHash: "abc", SignName: "done", Signer: "alice", SignedAt: "2025-01-02T00:00:00Z"})
+	src.InsertAudit("sign", &fid, strPtr("alice"), nil)
+
+	dst := testDb(t)
+	// Occupy id 1 so the merged file gets a different id than in source.
+	dst.InsertFile(&models.TrackedFile{SHA256: "other", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	res, err := src.MergeFileInto(dst, "abc")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !res.NewFile || res.Tags != 1 || res.Signs != 1 || res.Audit != 1 {
+		t.Fatalf("unexpected merge result: %+v", res)
+	}
+
+	tags, _ := dst.GetTags(res.FileID)
+	if len(tags) != 1 || tags[0] != "leak" {
+		t.Fatalf("expected leak tag on merged file, got %v", tags)
+	}
+	pl, _ := dst.GetPipelineByName("review")
+	if pl == nil {
+		t.Fatal("expected pipeline created in target")
+	}
+	signs, _ := dst.GetValidSignsForFilePipeline(res.FileID, *pl.ID, "abc")
+	if len(signs) != 1 {
+		t.Fatalf("expected 1 sign on merged file, got %d", len(signs))
+	}
+
+	// Merging again duplicates nothing but carries over a revocation.
+	src.RevokeSign(1, "2025-01-03T00:00:00Z")
+	res, err = src.MergeFileInto(dst, "abc")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if res.NewFile || res.Signs != 0 || res.Tags != 0 || res.Audit != 0 {
+		t.Fatalf("expected nothing new on re-merge, got %+v", res)
+	}
+	if signs, _ := dst.GetValidSignsForFilePipeline(res.FileID, *pl.ID, "abc"); len(signs) != 0 {
+		t.Fatalf("expected revocation carried over, got %d valid signs", len(signs))
+	}
+}
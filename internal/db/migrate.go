@@ -3,8 +3,142 @@ package db
 import (
 	"database/sql"
 	"fmt"
+	"os"
+	"time"
 )
 
+// Migration is one step bringing an older database up to the current
+// schema. Steps run in version order and each is recorded in the
+// schema_version table once it succeeds. New tables need no step, since
+// opening a database creates any that are missing; steps change tables
+// that already exist. A step must be safe to run again, because databases
+// from before schema_version existed run every step once, and a step
+// interrupted before it was recorded runs again on the next open.
+type Migration struct {
+	Version int
+	Name    string
+	apply   func(*sql.DB) error
+}
+
+// ProjectMigrations are the steps for a .mkrk project database. Append new
+// steps with the next version; never renumber or remove one.
+var ProjectMigrations = []Migration{
+	{1, "rust-scopes", MigrateProject},
+	{2, "audit-chain", MigrateAuditChain},
+	{3, "sign-columns", MigrateSignColumns},
+	{4, "hash-algorithm", MigrateHashAlgorithm},
+	{5, "sync-cache-inode", MigrateSyncCacheInode},
+}
+
+// WorkspaceMigrations are the steps for a .mksp workspace database.
+var WorkspaceMigrations = []Migration{
+	{1, "rust-scopes", MigrateWorkspace},
+	{2, "audit-chain", MigrateWorkspaceAuditChain},
+}
+
+// SchemaVersion returns the highest migration recorded in d, or 0 for a
+// database from before migrations were recorded.
+func SchemaVersion(d *sql.DB) (int, error) {
+	if !tableExists(d, "schema_version") {
+		return 0, nil
+	}
+	var v int
+	err := d.QueryRow(`SELECT COALESCE(MAX(version), 0) FROM schema_version`).Scan(&v)
+	return v, err
+}
+
+// PendingMigrations returns the steps in migrations that d has not
+// recorded, in order.
+func PendingMigrations(d *sql.DB, migrations []Migration) ([]Migration, error) {
+	applied := map[int]bool{}
+	if tableExists(d, "schema_version") {
+		rows, err := d.Query(`SELECT version FROM schema_version`)
+		if err != nil {
+			return nil, err
+		}
+		defer rows.Close()
+		for rows.Next() {
+			var v int
+			if err := rows.Scan(&v); err != nil {
+				return nil, err
+			}
+			applied[v] = true
+		}
+		if err := rows.Err(); err != nil {
+			return nil, err
+		}
+	}
+	var pending []Migration
+	for _, m := range migrations {
+		if !applied[m.Version] {
+			pending = append(pending, m)
+		}
+	}
+	return pending, nil
+}
+
+// runMigrations applies the steps d has not recorded, recording each as
+// it succeeds. It stops at the first that fails.
+func runMigrations(d *sql.DB, migrations []Migration) error {
+	pending, err := PendingMigrations(d, migrations)
+	if err != nil {
+		return err
+	}
+	for _, m := range pending {
+		if err := m.apply(d); err != nil {
+			return fmt.Errorf("migration %d (%s): %w", m.Version, m.Name, err)
+		}
+		if err := recordMigration(d, m); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// stampMigrations records every step as applied, for a database just
+// created with the current schema.
+func stampMigrations(d *sql.DB, migrations []Migration) error {
+	for _, m := range migrations {
+		if err := recordMigration(d, m); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func recordMigration(d *sql.DB, m Migration) error {
+	_, err := d.Exec(
+		`INSERT OR IGNORE INTO schema_version (version, name, applied_at) VALUES (?, ?, ?)`,
+		m.Version, m.Name, time.Now().UTC().Format(time.RFC3339),
+	)
+	if err != nil {
+		return fmt.Errorf("record migration %d: %w", m.Version, err)
+	}
+	return nil
+}
+
+// InspectSchema reports the schema version of the database at path and
+// the steps in migrations it still needs, without applying them.
+func InspectSchema(path string, migrations []Migration) (int, []Migration, error) {
+	if _, err := os.Stat(path); err != nil {
+		return 0, nil, fmt.Errorf("database not found: %s", path)
+	}
+	d, rest, err := openDb(path)
+	if err != nil {
+		return 0, nil, err
+	}
+	defer discard(d, rest)
+	version, err := SchemaVersion(d)
+	if err != nil {
+		return 0, nil, err
+	}
+	pending, err := PendingMigrations(d, migrations)
+	if err != nil {
+		return 0, nil, err
+	}
+	return version, pending, nil
+}
+
 // MigrateProject migrates a project database from the Rust schema to Go.
 // Copies data from legacy tables (categories, category_policy, tool_config,
 // tag_tool_config) into the new scopes/scope_policy/scope_tool_config tables.
@@ -99,6 +233,130 @@ func MigrateProject(d *sql.DB) error {
 	return nil
 }
 
+// MigrateAuditChain adds the hash chain columns to an audit log created
+// before entries were chained, and chains the existing entries in id
+// order. Entries written later without a hash are left for
+// VerifyAuditChain to report rather than silently absorbed.
+func MigrateAuditChain(d *sql.DB) error {
+	return migrateAuditChain(d, "file_id", func() any { return new(*int64) })
+}
+
+// MigrateWorkspaceAuditChain chains a workspace audit log the same way.
+func MigrateWorkspaceAuditChain(d *sql.DB) error {
+	return migrateAuditChain(d, "project_name", func() any { return new(*string) })
+}
+
+// migrateAuditChain chains the audit_log table in d, whose subject column
+// is scanned into a value from newSubject.
+func migrateAuditChain(d *sql.DB, subjectColumn string, newSubject func() any) error {
+	if columnExists(d, "audit_log", "hash") {
+		return nil
+	}
+	tx, err := d.Begin()
+	if err != nil {
+		return err
+	}
+	defer tx.Rollback()
+	for _, col := range []string{"prev_hash", "hash"} {
+		if _, err := tx.Exec("ALTER TABLE audit_log ADD COLUMN " + col + " TEXT"); err != nil {
+			return fmt.Errorf("add audit_log.%s: %w", col, err)
+		}
+	}
+
+	rows, err := tx.Query(`SELECT id, timestamp, operation, ` + subjectColumn + `, user, detail FROM audit_log ORDER BY id`)
+	if err != nil {
+		return err
+	}
+	type entry struct {
+		id                   int64
+		timestamp, operation string
+		subject              any
+		user, detail         *string
+	}
+	var entries []entry
+	for rows.Next() {
+		e := entry{subject: newSubject()}
+		if err := rows.Scan(&e.id, &e.timestamp, &e.operation, e.subject, &e.user, &e.detail); err != nil {
+			rows.Close()
+			return err
+		}
+		entries = append(entries, e)
+	}
+	rows.Close()
+
+	prev := ""
+	for _, e := range entries {
+		hash := auditHash(prev, e.id, e.timestamp, e.operation, e.subject, e.user, e.detail)
+		if _, err := tx.Exec(`UPDATE audit_log SET prev_hash = ?, hash = ? WHERE id = ?`, prev, hash, e.id); err != nil {
+			return err
+		}
+		prev = hash
+	}
+	return tx.Commit()
+}
+
+// MigrateSignColumns adds the signature and timestamp columns later versions
+// record on signs to a signs table created without them.
+func MigrateSignColumns(d *sql.DB) error {
+	for _, col := range [][2]string{
+		{"signature_format", "TEXT"},
+		{"signing_key", "TEXT"},
+		{"timestamp_token", "BLOB"},
+	} {
+		if columnExists(d, "signs", col[0]) {
+			continue
+		}
+		if _, err := d.Exec("ALTER TABLE signs ADD COLUMN " + col[0] + " " + col[1]); err != nil {
+			return fmt.Errorf("add signs.%s: %w", col[0], err)
+		}
+	}
+	return nil
+}
+
+// MigrateHashAlgorithm adds the column recording each file's hash algorithm
+// to a files table created without it. Its files were all hashed with
+// SHA-256, the only algorithm there was.
+func MigrateHashAlgorithm(d *sql.DB) error {
+	if columnExists(d, "files", "hash_algorithm") {
+		return nil
+	}
+	if _, err := d.Exec(`ALTER TABLE files ADD COLUMN hash_algorithm TEXT NOT NULL DEFAULT 'sha256'`); err != nil {
+		return fmt.Errorf("add files.hash_algorithm: %w", err)
+	}
+	return nil
+}
+
+// MigrateSyncCacheInode adds the inode to the sync cache, so a file
+// replaced by another with the same size and mtime is rehashed. Rows
+// cached before it have none and match on size and mtime alone.
+func MigrateSyncCacheInode(d *sql.DB) error {
+	// An early cache was keyed by the plain path. It holds nothing that can't
+	// be rebuilt, so it is dropped rather than rekeyed.
+	if columnExists(d, "sync_cache", "path") {
+		if _, err := d.Exec(`DROP TABLE sync_cache`); err != nil {
+			return fmt.Errorf("drop path-keyed sync_cache: %w", err)
+		}
+		_, err := d.Exec(`CREATE TABLE sync_cache (
+			path_hash TEXT PRIMARY KEY,
+			size INTEGER NOT NULL,
+			mtime INTEGER NOT NULL,
+			sha256 TEXT NOT NULL,
+			inode INTEGER
+		)`)
+		if err != nil {
+			return fmt.Errorf("recreate sync_cache: %w", err)
+		}
+		return nil
+	}
+	if columnExists(d, "sync_cache", "inode") {
+		return nil
+	}
+	if _, err := d.Exec(`ALTER TABLE sync_cache ADD COLUMN inode INTEGER`); err != nil {
+		return fmt.Errorf("add sync_cache.inode: %w", err)
+	}
+	return nil
+}
+
 // MigrateWorkspace migrates a workspace database from Rust schema to Go.
 // Copies projects and default_categories into the scopes table.
 func MigrateWorkspace(d *sql.DB) error {
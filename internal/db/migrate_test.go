@@ -0,0 +1,140 @@
+package db
+
+import (
+	"path/filepath"
+	"testing"
+)
+
+func TestMigrationsAreOrdered(t *testing.T) {
+	for name, migrations := range map[string][]Migration{"project": ProjectMigrations, "workspace": WorkspaceMigrations} {
+		for i, m := range migrations {
+			if m.Version != i+1 || m.Name == "" || m.apply == nil {
+				t.Errorf("%s migration %d is %+v", name, i+1, m)
+			}
+		}
+	}
+}
+
+func TestProjectMigrations(t *testing.T) {
+	path := filepath.Join(t.TempDir(), ".mkrk")
+	pdb, err := CreateProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	latest := len(ProjectMigrations)
+	if v, _ := SchemaVersion(pdb.DB()); v != latest {
+		t.Fatalf("expected a new project at version %d, got %d", latest, v)
+	}
+
+	// A project from before the sync cache recorded inodes.
+	pdb.DB().Exec(`ALTER TABLE sync_cache DROP COLUMN inode`)
+	pdb.DB().Exec(`DELETE FROM schema_version WHERE version = 5`)
+	pdb.Close()
+
+	version, pending, err := InspectSchema(path, ProjectMigrations)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if version != 4 || len(pending) != 1 || pending[0].Name != "sync-cache-inode" {
+		t.Fatalf("expected version 4 with sync-cache-inode pending, got %d, %+v", version, pending)
+	}
+
+	pdb, err = OpenProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !columnExists(pdb.DB(), "sync_cache", "inode") {
+		t.Fatal("expected the pending migration to add sync_cache.inode")
+	}
+	if pending, _ := PendingMigrations(pdb.DB(), ProjectMigrations); len(pending) != 0 {
+		t.Fatalf("expected nothing pending after open, got %+v", pending)
+	}
+
+	// A project from before versions were recorded runs every step once.
+	pdb.DB().Exec(`DROP TABLE schema_version`)
+	pdb.Close()
+	if version, pending, _ := InspectSchema(path, ProjectMigrations); version != 0 || len(pending) != latest {
+		t.Fatalf("expected version 0 with all pending, got %d, %d pending", version, len(pending))
+	}
+	pdb, err = OpenProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer pdb.Close()
+	if v, _ := SchemaVersion(pdb.DB()); v != latest {
+		t.Fatalf("expected version %d after open, got %d", latest, v)
+	}
+}
+
+func TestSyncCacheMigrationDropsPathKeys(t *testing.T) {
+	path := filepath.Join(t.TempDir(), ".mkrk")
+	pdb, err := CreateProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	// A cache from before keys were hashed.
+	pdb.DB().Exec(`DROP TABLE sync_cache`)
+	pdb.DB().Exec(`CREATE TABLE sync_cache (path TEXT PRIMARY KEY, size INTEGER NOT NULL, mtime INTEGER NOT NULL, sha256 TEXT NOT NULL)`)
+	pdb.DB().Exec(`INSERT INTO sync_cache VALUES ('evidence/secret.pdf', 1, 1, 'abc')`)
+	pdb.DB().Exec(`DELETE FROM schema_version WHERE version = 5`)
+	pdb.Close()
+
+	pdb, err = OpenProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer pdb.Close()
+	if columnExists(pdb.DB(), "sync_cache", "path") || !columnExists(pdb.DB(), "sync_cache", "path_hash") {
+		t.Fatal("expected the path-keyed cache to be replaced")
+	}
+	if n := rowCount(pdb.DB(), "sync_cache"); n != 0 {
+		t.Fatalf("expected plaintext-keyed rows to be dropped, got %d", n)
+	}
+}
+
+func TestWorkspaceMigrations(t *testing.T) {
+	path := filepath.Join(t.TempDir(), ".mksp")
+	wdb, err := CreateWorkspace(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.DB().Exec(`DROP TABLE schema_version`)
+	wdb.Close()
+
+	wdb, err = OpenWorkspace(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer wdb.Close()
+	if v, _ := SchemaVersion(wdb.DB()); v != len(WorkspaceMigrations) {
+		t.Fatalf("expected workspace at version %d, got %d", len(WorkspaceMigrations), v)
+	}
+}
+
+func TestWorkspaceAuditChainMigration(t *testing.T) {
+	path := filepath.Join(t.TempDir(), ".mksp")
+	wdb, err := CreateWorkspace(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.DB().Exec(`ALTER TABLE audit_log DROP COLUMN hash`)
+	wdb.DB().Exec(`ALTER TABLE audit_log DROP COLUMN prev_hash`)
+	wdb.DB().Exec(`INSERT INTO audit_log (timestamp, operation, project_name) VALUES ('2024-01-01T00:00:00Z', 'archive', 'alpha')`)
+	wdb.DB().Exec(`INSERT INTO audit_log (timestamp, operation) VALUES ('2024-01-02T00:00:00Z', 'tool_add')`)
+	wdb.DB().Exec(`DELETE FROM schema_version WHERE version = 2`)
+	wdb.Close()
+
+	wdb, err = OpenWorkspace(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer wdb.Close()
+	wdb.InsertAudit("unarchive", strPtr("alpha"), nil, nil)
+	count, _, breaks, err := wdb.VerifyAuditChain()
+	if err != nil {
+		t.Fatal(err)
+	}
+	if count != 3 || len(breaks) != 0 {
+		t.Fatalf("expected 3 chained entries, got %d with breaks %v", count, breaks)
+	}
+}
@@ -66,6 +66,18 @@ func (p *ProjectDb) ListPipelines() ([]models.Pipeline, error) {
 	return pipelines, rows.Err()
 }
 
+// UpdatePipeline replaces the states and transitions of the pipeline
+// named pl.Name.
+func (p *ProjectDb) UpdatePipeline(pl *models.Pipeline) error {
+	statesJSON, _ := json.Marshal(pl.States)
+	transJSON, _ := json.Marshal(pl.Transitions)
+	_, err := p.db.Exec(
+		`UPDATE pipelines SET states = ?, transitions = ? WHERE name = ?`,
+		string(statesJSON), string(transJSON), pl.Name,
+	)
+	return err
+}
+
 func (p *ProjectDb) RemovePipeline(name string) (int64, error) {
 	pl, err := p.GetPipelineByName(name)
 	if err != nil || pl == nil {
@@ -76,6 +88,7 @@ func (p *ProjectDb) RemovePipeline(name string) (int64, error) {
 	p.db.Exec(`DELETE FROM pipeline_files WHERE pipeline_id = ?`, pid)
 	p.db.Exec(`DELETE FROM pipeline_subscriptions WHERE pipeline_id = ?`, pid)
 	p.db.Exec(`DELETE FROM signs WHERE pipeline_id = ?`, pid)
+	p.db.Exec(`DELETE FROM state_history WHERE pipeline_id = ?`, pid)
 
 	res, err := p.db.Exec(`DELETE FROM pipelines WHERE id = ?`, pid)
 	if err != nil {
@@ -170,6 +183,98 @@ type PipelineSubscription struct {
 	Sub        models.Subscription
 }
 
+// --- Workspace default pipelines ---
+//
+// A workspace's default pipelines, and the references each attaches to,
+// are copied into projects on init and by `pipeline push-defaults`.
+
+// SetDefaultPipeline adds a default pipeline, or replaces the states and
+// transitions of the one with the same name.
+func (w *WorkspaceDb) SetDefaultPipeline(pl *models.Pipeline) error {
+	statesJSON, _ := json.Marshal(pl.States)
+	transJSON, _ := json.Marshal(pl.Transitions)
+	_, err := w.db.Exec(
+		`INSERT INTO default_pipelines (name, states, transitions) VALUES (?, ?, ?)
+		 ON CONFLICT(name) DO UPDATE SET states = excluded.states, transitions = excluded.transitions`,
+		pl.Name, string(statesJSON), string(transJSON),
+	)
+	if err != nil {
+		return fmt.Errorf("set default pipeline: %w", err)
+	}
+	return nil
+}
+
+func (w *WorkspaceDb) ListDefaultPipelines() ([]models.Pipeline, error) {
+	rows, err := w.db.Query(`SELECT id, name, states, transitions FROM default_pipelines ORDER BY name`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var pipelines []models.Pipeline
+	for rows.Next() {
+		var pl models.Pipeline
+		var id int64
+		var statesJSON, transJSON string
+		if err := rows.Scan(&id, &pl.Name, &statesJSON, &transJSON); err != nil {
+			return nil, err
+		}
+		pl.ID = &id
+		json.Unmarshal([]byte(statesJSON), &pl.States)
+		json.Unmarshal([]byte(transJSON), &pl.Transitions)
+		pipelines = append(pipelines, pl)
+	}
+	return pipelines, rows.Err()
+}
+
+func (w *WorkspaceDb) RemoveDefaultPipeline(name string) (int64, error) {
+	w.db.Exec(`DELETE FROM default_pipeline_attachments WHERE pipeline_id IN
+		(SELECT id FROM default_pipelines WHERE name = ?)`, name)
+	res, err := w.db.Exec(`DELETE FROM default_pipelines WHERE name = ?`, name)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+// AttachDefaultPipeline records a reference the named default pipeline
+// attaches to in every project.
+func (w *WorkspaceDb) AttachDefaultPipeline(name, reference string) error {
+	var id int64
+	err := w.db.QueryRow(`SELECT id FROM default_pipelines WHERE name = ?`, name).Scan(&id)
+	if err == sql.ErrNoRows {
+		return fmt.Errorf("no default pipeline '%s'", name)
+	}
+	if err != nil {
+		return err
+	}
+	_, err = w.db.Exec(
+		`INSERT OR IGNORE INTO default_pipeline_attachments (pipeline_id, reference) VALUES (?, ?)`,
+		id, reference,
+	)
+	return err
+}
+
+func (w *WorkspaceDb) DefaultPipelineAttachments(pipelineID int64) ([]string, error) {
+	rows, err := w.db.Query(
+		`SELECT reference FROM default_pipeline_attachments WHERE pipeline_id = ? ORDER BY reference`, pipelineID,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var refs []string
+	for rows.Next() {
+		var ref string
+		if err := rows.Scan(&ref); err != nil {
+			return nil, err
+		}
+		refs = append(refs, ref)
+	}
+	return refs, rows.Err()
+}
+
 // --- Materialization ---
 
 func (p *ProjectDb) MaterializePipelineFile(pipelineID int64, sha256 string, subID int64) error {
@@ -214,9 +319,11 @@ func (p *ProjectDb) GetPipelinesForSHA256(sha256 string) ([]models.Pipeline, err
 
 func (p *ProjectDb) InsertSign(s *models.Sign) (int64, error) {
 	res, err := p.db.Exec(
-		`INSERT INTO signs (pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature)
-		 VALUES (?, ?, ?, ?, ?, ?, ?)`,
+		`INSERT INTO signs (pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature,
+		                    signature_format, signing_key, timestamp_token)
+		 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
 		s.PipelineID, s.FileID, s.FileHash, s.SignName, s.Signer, s.SignedAt, s.Signature,
+		s.SignatureFormat, s.SigningKey, s.TimestampToken,
 	)
 	if err != nil {
 		return 0, fmt.Errorf("insert sign: %w", err)
@@ -235,9 +342,24 @@ func (p *ProjectDb) RevokeSign(signID int64, revokedAt string) (int64, error) {
 	return res.RowsAffected()
 }
 
+// RevokeStaleSigns revokes every active sign on a file that was made
+// against a hash other than currentHash. Returns the number revoked.
+func (p *ProjectDb) RevokeStaleSigns(fileID int64, currentHash, revokedAt string) (int64, error) {
+	res, err := p.db.Exec(
+		`UPDATE signs SET revoked_at = ?
+		 WHERE file_id = ? AND file_hash != ? AND revoked_at IS NULL`,
+		revokedAt, fileID, currentHash,
+	)
+	if err != nil {
+		return 0, fmt.Errorf("revoke stale signs: %w", err)
+	}
+	return res.RowsAffected()
+}
+
 func (p *ProjectDb) GetValidSignsForFilePipeline(fileID, pipelineID int64, currentHash string) ([]models.Sign, error) {
 	rows, err := p.db.Query(
-		`SELECT id, pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature, revoked_at
+		`SELECT id, pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature, revoked_at,
+		        signature_format, signing_key, timestamp_token
 		 FROM signs
 		 WHERE file_id = ? AND pipeline_id = ? AND file_hash = ? AND revoked_at IS NULL
 		 ORDER BY signed_at`, fileID, pipelineID, currentHash,
@@ -251,7 +373,8 @@ func (p *ProjectDb) GetValidSignsForFilePipeline(fileID, pipelineID int64, curre
 
 func (p *ProjectDb) GetSignsForFile(fileID int64) ([]models.Sign, error) {
 	rows, err := p.db.Query(
-		`SELECT id, pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature, revoked_at
+		`SELECT id, pipeline_id, file_id, file_hash, sign_name, signer, signed_at, signature, revoked_at,
+		        signature_format, signing_key, timestamp_token
 		 FROM signs WHERE file_id = ? ORDER BY pipeline_id, signed_at`, fileID,
 	)
 	if err != nil {
@@ -267,7 +390,8 @@ func scanSigns(rows *sql.Rows) ([]models.Sign, error) {
 		var s models.Sign
 		var id int64
 		if err := rows.Scan(&id, &s.PipelineID, &s.FileID, &s.FileHash,
-			&s.SignName, &s.Signer, &s.SignedAt, &s.Signature, &s.RevokedAt); err != nil {
+			&s.SignName, &s.Signer, &s.SignedAt, &s.Signature, &s.RevokedAt,
+			&s.SignatureFormat, &s.SigningKey, &s.TimestampToken); err != nil {
 			return nil, err
 		}
 		s.ID = &id
@@ -275,3 +399,44 @@ func scanSigns(rows *sql.Rows) ([]models.Sign, error) {
 	}
 	return signs, rows.Err()
 }
+
+// --- State history ---
+
+// InsertStateChange records a transition in a file's pipeline state.
+func (p *ProjectDb) InsertStateChange(c *models.StateChange) error {
+	_, err := p.db.Exec(
+		`INSERT INTO state_history (file_id, pipeline_id, from_state, to_state, changed_at, cause, sign_name, actor)
+		 VALUES (?, ?, ?, ?, ?, ?, ?, ?)`,
+		c.FileID, c.PipelineID, c.FromState, c.ToState, c.ChangedAt, c.Cause, c.SignName, c.Actor,
+	)
+	if err != nil {
+		return fmt.Errorf("insert state change: %w", err)
+	}
+	return nil
+}
+
+// GetStateHistory returns a file's state changes across all pipelines,
+// oldest first.
+func (p *ProjectDb) GetStateHistory(fileID int64) ([]models.StateChange, error) {
+	rows, err := p.db.Query(
+		`SELECT id, file_id, pipeline_id, from_state, to_state, changed_at, cause, sign_name, actor
+		 FROM state_history WHERE file_id = ? ORDER BY id`, fileID,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var history []models.StateChange
+	for rows.Next() {
+		var c models.StateChange
+		var id int64
+		if err := rows.Scan(&id, &c.FileID, &c.PipelineID, &c.FromState, &c.ToState,
+			&c.ChangedAt, &c.Cause, &c.SignName, &c.Actor); err != nil {
+			return nil, err
+		}
+		c.ID = &id
+		history = append(history, c)
+	}
+	return history, rows.Err()
+}
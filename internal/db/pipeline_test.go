@@ -192,3 +192,71 @@ func TestSignCRUD(t *testing.T) {
 		t.Fatal("expected no valid signs after revoke")
 	}
 }
+
+func TestSignSignatureRoundTrip(t *testing.T) {
+	db := testDb(t)
+	pid, _ := db.InsertPipeline(makePipeline("review", []string{"draft", "done"}))
+	fid, _ := db.InsertFile(&models.TrackedFile{SHA256: "abc123", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	sig, format, key := "-----BEGIN SSH SIGNATURE-----", "ssh", "ssh-ed25519 AAAA"
+	db.InsertSign(&models.Sign{
+		PipelineID: pid, FileID: fid, FileHash: "abc123", SignName: "done",
+		Signer: "alice", SignedAt: "2025-06-01T00:00:00Z",
+		Signature: &sig, SignatureFormat: &format, SigningKey: &key,
+	})
+
+	signs, err := db.GetSignsForFile(fid)
+	if err != nil || len(signs) != 1 {
+		t.Fatalf("expected 1 sign, got %d (%v)", len(signs), err)
+	}
+	s := signs[0]
+	if s.SignatureFormat == nil || *s.SignatureFormat != "ssh" || s.SigningKey == nil || *s.SigningKey != key {
+		t.Fatalf("signature details not stored: %+v", s)
+	}
+}
+
+func TestStateHistory(t *testing.T) {
+	db := testDb(t)
+	pid, _ := db.InsertPipeline(makePipeline("review", []string{"draft", "done"}))
+	fid, _ := db.InsertFile(&models.TrackedFile{SHA256: "abc123", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	done := "done"
+	for _, c := range []models.StateChange{
+		{FileID: fid, PipelineID: pid, FromState: "draft", ToState: "done", ChangedAt: "2025-06-01T00:00:00Z", Cause: "sign", SignName: &done, Actor: "alice"},
+		{FileID: fid, PipelineID: pid, FromState: "done", ToState: "draft", ChangedAt: "2025-06-02T00:00:00Z", Cause: "accept", Actor: "bob"},
+	} {
+		if err := db.InsertStateChange(&c); err != nil {
+			t.Fatal(err)
+		}
+	}
+
+	history, err := db.GetStateHistory(fid)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(history) != 2 || history[0].ToState != "done" || history[1].Cause != "accept" || history[1].SignName != nil {
+		t.Fatalf("unexpected history: %+v", history)
+	}
+}
+
+func TestRevokeStaleSigns(t *testing.T) {
+	db := testDb(t)
+	pid, _ := db.InsertPipeline(makePipeline("review", []string{"draft", "done"}))
+	fid, _ := db.InsertFile(&models.TrackedFile{SHA256: "old", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	db.InsertSign(&models.Sign{PipelineID: pid, FileID: fid, FileHash: "old", SignName: "done", Signer: "alice", SignedAt: "2025-06-01T00:00:00Z"})
+	db.InsertSign(&models.Sign{PipelineID: pid, FileID: fid, FileHash: "new", SignName: "done", Signer: "bob", SignedAt: "2025-06-02T00:00:00Z"})
+
+	n, err := db.RevokeStaleSigns(fid, "new", "2025-06-03T00:00:00Z")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if n != 1 {
+		t.Fatalf("expected 1 revoked, got %d", n)
+	}
+
+	valid, _ := db.GetValidSignsForFilePipeline(fid, pid, "new")
+	if len(valid) != 1 || valid[0].Signer != "bob" {
+		t.Fatalf("expected bob's sign to survive, got %v", valid)
+	}
+}
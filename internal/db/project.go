@@ -1,19 +1,41 @@
 package db
 
 import (
+	"cmp"
+	"crypto/sha256"
 	"database/sql"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
 	"fmt"
 	"os"
 	"time"
 
 	_ "modernc.org/sqlite"
 
+	"go.foia.dev/muckrake/internal/crypt"
 	"go.foia.dev/muckrake/internal/models"
 )
 
+// ErrLocked is returned when opening a project sealed by `mkrk lock`.
+var ErrLocked = errors.New("project is locked (run mkrk unlock)")
+
+// querier is the part of *sql.DB and *sql.Tx that ProjectDb queries
+// through, so the same methods work inside a Batch transaction.
+type querier interface {
+	Exec(query string, args ...any) (sql.Result, error)
+	Query(query string, args ...any) (*sql.Rows, error)
+	QueryRow(query string, args ...any) *sql.Row
+	Prepare(query string) (*sql.Stmt, error)
+}
+
 // ProjectDb wraps a connection to a .mkrk project database.
 type ProjectDb struct {
-	db *sql.DB
+	db   querier
+	conn *sql.DB
+	path string
+	// rest is set while the database is encrypted at rest.
+	rest *atRest
 }
 
 // CreateProject creates a new project database at the given path.
@@ -30,44 +52,106 @@ func CreateProject(path string) (*ProjectDb, error) {
 		db.Close()
 		return nil, fmt.Errorf("create project schema: %w", err)
 	}
-	return &ProjectDb{db: db}, nil
+	if err := stampMigrations(db, ProjectMigrations); err != nil {
+		db.Close()
+		return nil, err
+	}
+	return &ProjectDb{db: db, conn: db, path: path}, nil
 }
 
 // OpenProject opens an existing project database.
-// Runs schema with IF NOT EXISTS to add any new tables from newer versions.
+// Runs schema with IF NOT EXISTS to add any new tables from newer versions,
+// then any migrations it has not recorded.
 func OpenProject(path string) (*ProjectDb, error) {
 	if _, err := os.Stat(path); err != nil {
 		return nil, fmt.Errorf("project database not found: %s", path)
 	}
-	db, err := sql.Open("sqlite", path)
+	if locked, err := crypt.IsLocked(path); err != nil {
+		return nil, fmt.Errorf("open project db: %w", err)
+	} else if locked {
+		return nil, ErrLocked
+	}
+	db, rest, err := openDb(path)
 	if err != nil {
 		return nil, fmt.Errorf("open project db: %w", err)
 	}
 	if err := configureConn(db); err != nil {
-		db.Close()
+		discard(db, rest)
 		return nil, err
 	}
 	// Create any missing tables (all use IF NOT EXISTS)
 	if _, err := db.Exec(ProjectSchema); err != nil {
-		db.Close()
+		discard(db, rest)
 		return nil, fmt.Errorf("migrate project schema: %w", err)
 	}
-	// Migrate legacy Rust data if present
-	if err := MigrateProject(db); err != nil {
-		db.Close()
-		return nil, fmt.Errorf("migrate project data: %w", err)
+	if err := runMigrations(db, ProjectMigrations); err != nil {
+		discard(db, rest)
+		return nil, fmt.Errorf("migrate project db: %w", err)
 	}
-	return &ProjectDb{db: db}, nil
+	return &ProjectDb{db: db, conn: db, path: path, rest: rest}, nil
 }
 
-// Close closes the database connection.
+// Close closes the database connection, first writing the database back
+// if it is encrypted at rest.
 func (p *ProjectDb) Close() error {
-	return p.db.Close()
+	if p.rest != nil {
+		return p.rest.close(p.conn)
+	}
+	return p.conn.Close()
+}
+
+// SealAtRest keeps the database encrypted at rest under passphrase: it is
+// written back sealed when closed, and decrypted into memory whenever it
+// is opened after. For a database already encrypted, it changes the
+// passphrase.
+func (p *ProjectDb) SealAtRest(passphrase string) error {
+	rest, err := seal(p.rest, p.path, passphrase)
+	if err != nil {
+		return err
+	}
+	p.rest = rest
+	return nil
+}
+
+// UnsealAtRest writes a database encrypted at rest back in plaintext when
+// it is closed.
+func (p *ProjectDb) UnsealAtRest() error {
+	if !p.rest.sealed() {
+		return fmt.Errorf("database is not encrypted at rest")
+	}
+	p.rest.unseal()
+	return nil
+}
+
+// SealedAtRest reports whether the database will be written back
+// encrypted at rest.
+func (p *ProjectDb) SealedAtRest() bool {
+	return p.rest.sealed()
 }
 
 // DB returns the underlying sql.DB for direct access.
 func (p *ProjectDb) DB() *sql.DB {
-	return p.db
+	return p.conn
+}
+
+// Batch runs fn against a ProjectDb bound to a single transaction, which
+// is committed if fn returns nil and rolled back otherwise. Everything fn
+// does must go through the ProjectDb it is given; the batch handle must
+// not be closed or used after fn returns. A Batch on a batch handle joins
+// the transaction already open, which then commits or rolls back as one.
+func (p *ProjectDb) Batch(fn func(*ProjectDb) error) error {
+	if _, inTx := p.db.(*sql.Tx); inTx {
+		return fn(p)
+	}
+	tx, err := p.conn.Begin()
+	if err != nil {
+		return err
+	}
+	if err := fn(&ProjectDb{db: tx, conn: p.conn}); err != nil {
+		tx.Rollback()
+		return err
+	}
+	return tx.Commit()
 }
 
 func configureConn(db *sql.DB) error {
@@ -182,6 +266,11 @@ func (p *ProjectDb) InsertScopePolicy(scopeID int64, level models.ProtectionLeve
 	return nil
 }
 
+func (p *ProjectDb) RemoveScopePolicy(scopeID int64) error {
+	_, err := p.db.Exec(`DELETE FROM scope_policy WHERE scope_id = ?`, scopeID)
+	return err
+}
+
 func (p *ProjectDb) GetPolicyForScope(scopeID int64) (*models.ProtectionLevel, error) {
 	var s string
 	err := p.db.QueryRow(
@@ -224,13 +313,64 @@ func (p *ProjectDb) ResolveProtection(relPath string) (models.ProtectionLevel, e
 	return models.Strictest(levels), nil
 }
 
+// --- Scope Encryption ---
+
+func (p *ProjectDb) SetScopeEncrypted(scopeID int64, encrypted bool) error {
+	var err error
+	if encrypted {
+		_, err = p.db.Exec(`INSERT OR IGNORE INTO scope_encryption (scope_id) VALUES (?)`, scopeID)
+	} else {
+		_, err = p.db.Exec(`DELETE FROM scope_encryption WHERE scope_id = ?`, scopeID)
+	}
+	if err != nil {
+		return fmt.Errorf("set scope encryption: %w", err)
+	}
+	return nil
+}
+
+func (p *ProjectDb) IsScopeEncrypted(scopeID int64) (bool, error) {
+	var n int
+	err := p.db.QueryRow(`SELECT COUNT(*) FROM scope_encryption WHERE scope_id = ?`, scopeID).Scan(&n)
+	return n > 0, err
+}
+
+// ResolveEncryption reports whether any category matching relPath stores
+// its files encrypted.
+func (p *ProjectDb) ResolveEncryption(relPath string) (bool, error) {
+	cats, err := p.ListCategories()
+	if err != nil {
+		return false, err
+	}
+	for _, cat := range cats {
+		matched, err := cat.Matches(relPath)
+		if err != nil {
+			return false, err
+		}
+		if matched && cat.ID != nil {
+			if enc, err := p.IsScopeEncrypted(*cat.ID); err != nil || enc {
+				return enc, err
+			}
+		}
+	}
+	return false, nil
+}
+
 // --- File CRUD ---
 
+// InsertFile tracks a file. Its hash is taken to be made with the project's
+// hash algorithm unless f names another.
 func (p *ProjectDb) InsertFile(f *models.TrackedFile) (int64, error) {
+	alg := f.HashAlgorithm
+	if alg == "" {
+		var err error
+		if alg, err = p.HashAlgorithm(); err != nil {
+			return 0, err
+		}
+	}
 	res, err := p.db.Exec(
-		`INSERT INTO files (sha256, fingerprint, mime_type, size, ingested_at, provenance)
-		 VALUES (?, ?, ?, ?, ?, ?)`,
-		f.SHA256, f.Fingerprint, f.MimeType, f.Size, f.IngestedAt, f.Provenance,
+		`INSERT INTO files (sha256, fingerprint, mime_type, size, ingested_at, provenance, hash_algorithm)
+		 VALUES (?, ?, ?, ?, ?, ?, ?)`,
+		f.SHA256, f.Fingerprint, f.MimeType, f.Size, f.IngestedAt, f.Provenance, alg,
 	)
 	if err != nil {
 		return 0, fmt.Errorf("insert file: %w", err)
@@ -238,9 +378,47 @@ func (p *ProjectDb) InsertFile(f *models.TrackedFile) (int64, error) {
 	return res.LastInsertId()
 }
 
+// InsertFiles tracks many files in one transaction through one prepared
+// statement, and returns their IDs in order. Like InsertFile, it takes
+// hashes to be made with the project's algorithm unless a file names
+// another. If any insert fails, none of the files are tracked.
+func (p *ProjectDb) InsertFiles(files []*models.TrackedFile) ([]int64, error) {
+	ids := make([]int64, 0, len(files))
+	err := p.Batch(func(b *ProjectDb) error {
+		projectAlg, err := b.HashAlgorithm()
+		if err != nil {
+			return err
+		}
+		stmt, err := b.db.Prepare(
+			`INSERT INTO files (sha256, fingerprint, mime_type, size, ingested_at, provenance, hash_algorithm)
+			 VALUES (?, ?, ?, ?, ?, ?, ?)`,
+		)
+		if err != nil {
+			return err
+		}
+		defer stmt.Close()
+		for _, f := range files {
+			res, err := stmt.Exec(f.SHA256, f.Fingerprint, f.MimeType, f.Size, f.IngestedAt, f.Provenance, cmp.Or(f.HashAlgorithm, projectAlg))
+			if err != nil {
+				return fmt.Errorf("insert file %s: %w", f.SHA256, err)
+			}
+			id, err := res.LastInsertId()
+			if err != nil {
+				return err
+			}
+			ids = append(ids, id)
+		}
+		return nil
+	})
+	if err != nil {
+		return nil, err
+	}
+	return ids, nil
+}
+
 func (p *ProjectDb) GetFileByHash(sha256 string) (*models.TrackedFile, error) {
 	row := p.db.QueryRow(
-		`SELECT id, sha256, fingerprint, mime_type, size, ingested_at, provenance
+		`SELECT id, sha256, fingerprint, mime_type, size, ingested_at, provenance, hash_algorithm
 		 FROM files WHERE sha256 = ?`, sha256,
 	)
 	return scanFile(row)
@@ -248,7 +426,7 @@ func (p *ProjectDb) GetFileByHash(sha256 string) (*models.TrackedFile, error) {
 
 func (p *ProjectDb) GetFileByFingerprint(fp string) (*models.TrackedFile, error) {
 	row := p.db.QueryRow(
-		`SELECT id, sha256, fingerprint, mime_type, size, ingested_at, provenance
+		`SELECT id, sha256, fingerprint, mime_type, size, ingested_at, provenance, hash_algorithm
 		 FROM files WHERE fingerprint = ?`, fp,
 	)
 	return scanFile(row)
@@ -256,7 +434,7 @@ func (p *ProjectDb) GetFileByFingerprint(fp string) (*models.TrackedFile, error)
 
 func (p *ProjectDb) ListAllFiles() ([]models.TrackedFile, error) {
 	rows, err := p.db.Query(
-		`SELECT id, sha256, fingerprint, mime_type, size, ingested_at, provenance FROM files`,
+		`SELECT id, sha256, fingerprint, mime_type, size, ingested_at, provenance, hash_algorithm FROM files`,
 	)
 	if err != nil {
 		return nil, fmt.Errorf("list files: %w", err)
@@ -284,6 +462,339 @@ func (p *ProjectDb) UpdateFileSHA256(fileID int64, sha256 string) error {
 	return err
 }
 
+func (p *ProjectDb) UpdateFileSize(fileID int64, size int64) error {
+	_, err := p.db.Exec(`UPDATE files SET size = ? WHERE id = ?`, size, fileID)
+	return err
+}
+
+// --- Versions ---
+
+// InsertFileVersion records sha256 as the newest revision of a file.
+func (p *ProjectDb) InsertFileVersion(fileID int64, sha256 string, size *int64, recordedAt string) error {
+	_, err := p.db.Exec(
+		`INSERT INTO file_versions (file_id, sha256, size, recorded_at) VALUES (?, ?, ?, ?)`,
+		fileID, sha256, size, recordedAt,
+	)
+	return err
+}
+
+// ListFileVersions returns a file's recorded revisions, oldest first.
+func (p *ProjectDb) ListFileVersions(fileID int64) ([]models.FileVersion, error) {
+	rows, err := p.db.Query(
+		`SELECT id, file_id, sha256, size, recorded_at FROM file_versions
+		 WHERE file_id = ? ORDER BY id`, fileID,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var versions []models.FileVersion
+	for rows.Next() {
+		var v models.FileVersion
+		if err := rows.Scan(&v.ID, &v.FileID, &v.SHA256, &v.Size, &v.RecordedAt); err != nil {
+			return nil, err
+		}
+		versions = append(versions, v)
+	}
+	return versions, rows.Err()
+}
+
+// --- Stored objects ---
+
+// PutStoredObject records content moved out to remote storage, replacing
+// any earlier record of the same hash.
+func (p *ProjectDb) PutStoredObject(o *models.StoredObject) error {
+	_, err := p.db.Exec(
+		`INSERT OR REPLACE INTO stored_objects (sha256, size, lock_mode, retain_until, stored_at) VALUES (?, ?, ?, ?, ?)`,
+		o.SHA256, o.Size, o.LockMode, o.RetainUntil, o.StoredAt,
+	)
+	return err
+}
+
+// GetStoredObject returns the record of content in remote storage, or nil.
+func (p *ProjectDb) GetStoredObject(sha256 string) (*models.StoredObject, error) {
+	var o models.StoredObject
+	err := p.db.QueryRow(
+		`SELECT sha256, size, lock_mode, retain_until, stored_at FROM stored_objects WHERE sha256 = ?`, sha256,
+	).Scan(&o.SHA256, &o.Size, &o.LockMode, &o.RetainUntil, &o.StoredAt)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &o, nil
+}
+
+// ListStoredObjects returns every record of content in remote storage,
+// oldest first.
+func (p *ProjectDb) ListStoredObjects() ([]models.StoredObject, error) {
+	rows, err := p.db.Query(
+		`SELECT sha256, size, lock_mode, retain_until, stored_at FROM stored_objects ORDER BY stored_at, sha256`,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var objects []models.StoredObject
+	for rows.Next() {
+		var o models.StoredObject
+		if err := rows.Scan(&o.SHA256, &o.Size, &o.LockMode, &o.RetainUntil, &o.StoredAt); err != nil {
+			return nil, err
+		}
+		objects = append(objects, o)
+	}
+	return objects, rows.Err()
+}
+
+// --- Metadata ---
+
+// SetFileMetadata replaces the metadata recorded for a file.
+func (p *ProjectDb) SetFileMetadata(fileID int64, metadata map[string]string) error {
+	if _, err := p.db.Exec(`DELETE FROM file_metadata WHERE file_id = ?`, fileID); err != nil {
+		return err
+	}
+	for k, v := range metadata {
+		if _, err := p.db.Exec(
+			`INSERT INTO file_metadata (file_id, key, value) VALUES (?, ?, ?)`, fileID, k, v,
+		); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// GetFileMetadata returns the metadata recorded for a file.
+func (p *ProjectDb) GetFileMetadata(fileID int64) (map[string]string, error) {
+	rows, err := p.db.Query(`SELECT key, value FROM file_metadata WHERE file_id = ?`, fileID)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	metadata := make(map[string]string)
+	for rows.Next() {
+		var k, v string
+		if err := rows.Scan(&k, &v); err != nil {
+			return nil, err
+		}
+		metadata[k] = v
+	}
+	return metadata, rows.Err()
+}
+
+// --- Attributes ---
+
+// SetFileAttr sets an attribute of a file, replacing any value it had.
+func (p *ProjectDb) SetFileAttr(fileID int64, key, value string) error {
+	_, err := p.db.Exec(
+		`INSERT INTO file_attrs (file_id, key, value) VALUES (?, ?, ?)
+		 ON CONFLICT(file_id, key) DO UPDATE SET value = excluded.value`,
+		fileID, key, value,
+	)
+	return err
+}
+
+// RemoveFileAttr removes an attribute of a file, returning the number
+// removed.
+func (p *ProjectDb) RemoveFileAttr(fileID int64, key string) (int64, error) {
+	res, err := p.db.Exec(`DELETE FROM file_attrs WHERE file_id = ? AND key = ?`, fileID, key)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+// GetFileAttrs returns the attributes set on a file.
+func (p *ProjectDb) GetFileAttrs(fileID int64) (map[string]string, error) {
+	rows, err := p.db.Query(`SELECT key, value FROM file_attrs WHERE file_id = ?`, fileID)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	attrs := make(map[string]string)
+	for rows.Next() {
+		var k, v string
+		if err := rows.Scan(&k, &v); err != nil {
+			return nil, err
+		}
+		attrs[k] = v
+	}
+	return attrs, rows.Err()
+}
+
+// --- Sync cache ---
+
+// CachedHash returns the hash recorded for path by PutCachedHash if the
+// file still has the given size, modification time and inode, or ""
+// otherwise. An inode of 0, or none recorded, is not compared.
+func (p *ProjectDb) CachedHash(path string, size, mtime, inode int64) (string, error) {
+	var hash string
+	err := p.db.QueryRow(
+		`SELECT sha256 FROM sync_cache
+		 WHERE path_hash = ? AND size = ? AND mtime = ? AND (inode IS NULL OR ? = 0 OR inode = ?)`,
+		PathKey(path), size, mtime, inode, inode,
+	).Scan(&hash)
+	if err == sql.ErrNoRows {
+		return "", nil
+	}
+	return hash, err
+}
+
+func (p *ProjectDb) PutCachedHash(path string, size, mtime, inode int64, sha256 string) error {
+	var ino *int64
+	if inode != 0 {
+		ino = &inode
+	}
+	_, err := p.db.Exec(
+		`INSERT OR REPLACE INTO sync_cache (path_hash, size, mtime, sha256, inode) VALUES (?, ?, ?, ?, ?)`,
+		PathKey(path), size, mtime, sha256, ino,
+	)
+	return err
+}
+
+// PathKey hashes a path so a table can be keyed by it without storing it.
+func PathKey(path string) string {
+	sum := sha256.Sum256([]byte(path))
+	return hex.EncodeToString(sum[:])
+}
+
+// --- Ciphertexts ---
+
+// Ciphertext is the stored form of encrypted content: the hash and
+// fingerprint of the bytes on disk, and those of the plaintext they hold.
+type Ciphertext struct {
+	SHA256               string
+	Fingerprint          string
+	PlaintextSHA256      string
+	PlaintextFingerprint *string
+}
+
+// PutCiphertext records the stored form of encrypted content.
+func (p *ProjectDb) PutCiphertext(c Ciphertext) error {
+	_, err := p.db.Exec(
+		`INSERT OR REPLACE INTO ciphertexts (sha256, fingerprint, plaintext_sha256, plaintext_fingerprint)
+		 VALUES (?, ?, ?, ?)`,
+		c.SHA256, c.Fingerprint, c.PlaintextSHA256, c.PlaintextFingerprint,
+	)
+	return err
+}
+
+// GetCiphertext returns the stored form whose bytes hash to sha256, or
+// nil if there is none.
+func (p *ProjectDb) GetCiphertext(sha256 string) (*Ciphertext, error) {
+	return p.getCiphertext(`sha256 = ?`, sha256)
+}
+
+// GetCiphertextByFingerprint returns a stored form whose bytes have the
+// given fingerprint, or nil if there is none.
+func (p *ProjectDb) GetCiphertextByFingerprint(fingerprint string) (*Ciphertext, error) {
+	return p.getCiphertext(`fingerprint = ?`, fingerprint)
+}
+
+func (p *ProjectDb) getCiphertext(where, arg string) (*Ciphertext, error) {
+	var c Ciphertext
+	err := p.db.QueryRow(
+		`SELECT sha256, fingerprint, plaintext_sha256, plaintext_fingerprint
+		 FROM ciphertexts WHERE `+where+` LIMIT 1`, arg,
+	).Scan(&c.SHA256, &c.Fingerprint, &c.PlaintextSHA256, &c.PlaintextFingerprint)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &c, nil
+}
+
+// listCiphertexts returns every stored form recorded for the plaintext
+// with the given hash.
+func (p *ProjectDb) listCiphertexts(plaintextSHA256 string) ([]Ciphertext, error) {
+	rows, err := p.db.Query(
+		`SELECT sha256, fingerprint, plaintext_sha256, plaintext_fingerprint
+		 FROM ciphertexts WHERE plaintext_sha256 = ?`, plaintextSHA256,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	var out []Ciphertext
+	for rows.Next() {
+		var c Ciphertext
+		if err := rows.Scan(&c.SHA256, &c.Fingerprint, &c.PlaintextSHA256, &c.PlaintextFingerprint); err != nil {
+			return nil, err
+		}
+		out = append(out, c)
+	}
+	return out, rows.Err()
+}
+
+func (p *ProjectDb) DeleteCiphertext(sha256 string) error {
+	_, err := p.db.Exec(`DELETE FROM ciphertexts WHERE sha256 = ?`, sha256)
+	return err
+}
+
+// --- Working copies ---
+
+// PutWorkingCopy records that path holds a copy of sourceID made by
+// copy-out, and whether the copy should be tagged once it is tracked.
+func (p *ProjectDb) PutWorkingCopy(path string, sourceID int64, tag bool, createdAt string) error {
+	_, err := p.db.Exec(
+		`INSERT OR REPLACE INTO working_copies (path_hash, source_file_id, tag, created_at) VALUES (?, ?, ?, ?)`,
+		PathKey(path), sourceID, tag, createdAt,
+	)
+	return err
+}
+
+// WorkingCopySource returns the file path was copied out from and whether
+// the copy asked for a tag, or a nil ID if path is not a working copy.
+func (p *ProjectDb) WorkingCopySource(path string) (*int64, bool, error) {
+	var id int64
+	var tag bool
+	err := p.db.QueryRow(
+		`SELECT source_file_id, tag FROM working_copies WHERE path_hash = ?`,
+		PathKey(path),
+	).Scan(&id, &tag)
+	if err == sql.ErrNoRows {
+		return nil, false, nil
+	}
+	if err != nil {
+		return nil, false, err
+	}
+	return &id, tag, nil
+}
+
+func (p *ProjectDb) DeleteWorkingCopy(path string) error {
+	_, err := p.db.Exec(`DELETE FROM working_copies WHERE path_hash = ?`, PathKey(path))
+	return err
+}
+
+// --- Replica segments ---
+
+// SegmentApplied reports whether the change log entry segment from the
+// replica store at address has been applied.
+func (p *ProjectDb) SegmentApplied(address, segment string) (bool, error) {
+	var n int
+	err := p.db.QueryRow(
+		`SELECT COUNT(*) FROM replica_segments WHERE store = ? AND segment = ?`,
+		PathKey(address), segment,
+	).Scan(&n)
+	return n > 0, err
+}
+
+// MarkSegmentApplied records that the change log entry segment from the
+// replica store at address has been applied.
+func (p *ProjectDb) MarkSegmentApplied(address, segment, appliedAt string) error {
+	_, err := p.db.Exec(
+		`INSERT OR IGNORE INTO replica_segments (store, segment, applied_at) VALUES (?, ?, ?)`,
+		PathKey(address), segment, appliedAt,
+	)
+	return err
+}
+
 // --- Tags ---
 
 func (p *ProjectDb) InsertTag(fileID int64, tag, fileHash, fingerprint string) error {
@@ -295,6 +806,27 @@ func (p *ProjectDb) InsertTag(fileID int64, tag, fileHash, fingerprint string) e
 	return err
 }
 
+// InsertTags adds many tags in one transaction through one prepared
+// statement. Tags a file already carries are left as they are.
+func (p *ProjectDb) InsertTags(tags []models.FileTag) error {
+	return p.Batch(func(b *ProjectDb) error {
+		stmt, err := b.db.Prepare(
+			`INSERT OR IGNORE INTO file_tags (file_id, tag, file_hash, fingerprint)
+			 VALUES (?, ?, ?, ?)`,
+		)
+		if err != nil {
+			return err
+		}
+		defer stmt.Close()
+		for _, t := range tags {
+			if _, err := stmt.Exec(t.FileID, t.Tag, t.FileHash, t.Fingerprint); err != nil {
+				return err
+			}
+		}
+		return nil
+	})
+}
+
 func (p *ProjectDb) RemoveTag(fileID int64, tag string) error {
 	_, err := p.db.Exec(
 		`DELETE FROM file_tags WHERE file_id = ? AND tag = ?`, fileID, tag,
@@ -322,6 +854,27 @@ func (p *ProjectDb) GetTags(fileID int64) ([]string, error) {
 	return tags, rows.Err()
 }
 
+// ListTaggedFiles returns, for every tag in use, the IDs of the files that
+// carry it.
+func (p *ProjectDb) ListTaggedFiles() (map[string][]int64, error) {
+	rows, err := p.db.Query(`SELECT tag, file_id FROM file_tags ORDER BY tag, file_id`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	tagged := make(map[string][]int64)
+	for rows.Next() {
+		var tag string
+		var fileID int64
+		if err := rows.Scan(&tag, &fileID); err != nil {
+			return nil, err
+		}
+		tagged[tag] = append(tagged[tag], fileID)
+	}
+	return tagged, rows.Err()
+}
+
 // --- File Links ---
 
 func (p *ProjectDb) InsertFileLink(sourceID, targetID int64, linkType string, metadata *string) error {
@@ -333,18 +886,355 @@ func (p *ProjectDb) InsertFileLink(sourceID, targetID int64, linkType string, me
 	return err
 }
 
+// InsertDerivation records that derivedID was produced from sourceID, as a
+// derived_from file link naming the tool in its metadata. Recording the
+// same pair again does nothing.
+func (p *ProjectDb) InsertDerivation(sourceID, derivedID int64, via string) error {
+	var n int
+	if err := p.db.QueryRow(
+		`SELECT COUNT(*) FROM file_links
+		 WHERE source_file_id = ? AND target_file_id = ? AND link_type = 'derived_from'`,
+		sourceID, derivedID,
+	).Scan(&n); err != nil {
+		return err
+	}
+	if n > 0 {
+		return nil
+	}
+	var metadata *string
+	if via != "" {
+		b, _ := json.Marshal(map[string]string{"via": via})
+		m := string(b)
+		metadata = &m
+	}
+	return p.InsertFileLink(sourceID, derivedID, "derived_from", metadata)
+}
+
+// DerivedFrom returns the derivations that produced fileID.
+func (p *ProjectDb) DerivedFrom(fileID int64) ([]models.Derivation, error) {
+	return p.queryDerivations(`target_file_id = ?`, fileID)
+}
+
+// Derivatives returns the derivations produced from fileID.
+func (p *ProjectDb) Derivatives(fileID int64) ([]models.Derivation, error) {
+	return p.queryDerivations(`source_file_id = ?`, fileID)
+}
+
+func (p *ProjectDb) queryDerivations(where string, fileID int64) ([]models.Derivation, error) {
+	rows, err := p.db.Query(
+		`SELECT source_file_id, target_file_id, metadata FROM file_links
+		 WHERE link_type = 'derived_from' AND `+where+` ORDER BY id`, fileID,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var out []models.Derivation
+	for rows.Next() {
+		var d models.Derivation
+		var metadata *string
+		if err := rows.Scan(&d.SourceID, &d.DerivedID, &metadata); err != nil {
+			return nil, err
+		}
+		if metadata != nil {
+			var m struct {
+				Via string `json:"via"`
+			}
+			if json.Unmarshal([]byte(*metadata), &m) == nil && m.Via != "" {
+				d.Via = &m.Via
+			}
+		}
+		out = append(out, d)
+	}
+	return out, rows.Err()
+}
+
+// --- Config ---
+
+// HashAlgorithmSetting names the hash algorithm a project identifies file
+// content by, fixed when the project is created. Projects without it use
+// SHA-256.
+const HashAlgorithmSetting = "hash-algorithm"
+
+// HashAlgorithm returns the project's hash algorithm.
+func (p *ProjectDb) HashAlgorithm() (string, error) {
+	v, err := p.GetConfig(HashAlgorithmSetting)
+	if err != nil || v == nil {
+		return "sha256", err
+	}
+	return *v, nil
+}
+
+// ChunkingSetting names how the project's fingerprints cut files into
+// chunks, fixed when the project is created. Projects without it use fixed
+// 64 KiB chunks.
+const ChunkingSetting = "chunking"
+
+// Chunking returns the project's fingerprint chunking.
+func (p *ProjectDb) Chunking() (string, error) {
+	v, err := p.GetConfig(ChunkingSetting)
+	if err != nil || v == nil {
+		return "fixed", err
+	}
+	return *v, nil
+}
+
+func (p *ProjectDb) GetConfig(key string) (*string, error) {
+	var val string
+	err := p.db.QueryRow(`SELECT value FROM project_config WHERE key = ?`, key).Scan(&val)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &val, nil
+}
+
+func (p *ProjectDb) SetConfig(key, value string) error {
+	_, err := p.db.Exec(
+		`INSERT INTO project_config (key, value) VALUES (?, ?)
+		 ON CONFLICT(key) DO UPDATE SET value = excluded.value`,
+		key, value,
+	)
+	return err
+}
+
+func (p *ProjectDb) UnsetConfig(key string) error {
+	_, err := p.db.Exec(`DELETE FROM project_config WHERE key = ?`, key)
+	return err
+}
+
+// ListConfig returns all project settings keyed by name.
+func (p *ProjectDb) ListConfig() (map[string]string, error) {
+	rows, err := p.db.Query(`SELECT key, value FROM project_config`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	config := make(map[string]string)
+	for rows.Next() {
+		var k, v string
+		if err := rows.Scan(&k, &v); err != nil {
+			return nil, err
+		}
+		config[k] = v
+	}
+	return config, rows.Err()
+}
+
 // --- Audit ---
 
 func (p *ProjectDb) InsertAudit(operation string, fileID *int64, user, detail *string) error {
 	now := time.Now().UTC().Format(time.RFC3339)
-	_, err := p.db.Exec(
-		`INSERT INTO audit_log (timestamp, operation, file_id, user, detail)
-		 VALUES (?, ?, ?, ?, ?)`,
-		now, operation, fileID, user, detail,
+	return insertAudit(p.db, now, operation, fileID, user, detail)
+}
+
+// InsertAudits appends many entries to the audit chain in one transaction,
+// in order, reading the chain's head once. Entries without a timestamp get
+// the current time; their IDs are assigned.
+func (p *ProjectDb) InsertAudits(entries []models.AuditEntry) error {
+	now := time.Now().UTC().Format(time.RFC3339)
+	return p.Batch(func(b *ProjectDb) error {
+		lastID, prev, err := auditHead(b.db)
+		if err != nil {
+			return err
+		}
+		stmt, err := b.db.Prepare(insertAuditSQL)
+		if err != nil {
+			return err
+		}
+		defer stmt.Close()
+		for _, e := range entries {
+			timestamp := cmp.Or(e.Timestamp, now)
+			lastID++
+			hash := auditHash(prev, lastID, timestamp, e.Operation, e.FileID, e.User, e.Detail)
+			if _, err := stmt.Exec(lastID, timestamp, e.Operation, e.FileID, e.User, e.Detail, prev, hash); err != nil {
+				return err
+			}
+			prev = hash
+		}
+		return nil
+	})
+}
+
+const insertAuditSQL = `INSERT INTO audit_log (id, timestamp, operation, file_id, user, detail, prev_hash, hash)
+	VALUES (?, ?, ?, ?, ?, ?, ?, ?)`
+
+// auditHead returns the id and hash of the newest audit entry, or zero
+// values for an empty log.
+func auditHead(q querier) (int64, string, error) {
+	var lastID int64
+	var prev sql.NullString
+	err := q.QueryRow(`SELECT id, hash FROM audit_log ORDER BY id DESC LIMIT 1`).Scan(&lastID, &prev)
+	if err != nil && err != sql.ErrNoRows {
+		return 0, "", err
+	}
+	return lastID, prev.String, nil
+}
+
+// insertAudit appends an entry chained to the one before it. The entry
+// takes the next id explicitly, so two writers racing for the same place
+// in the chain collide on the primary key instead of forking it.
+func insertAudit(q querier, timestamp, operation string, fileID *int64, user, detail *string) error {
+	lastID, prev, err := auditHead(q)
+	if err != nil {
+		return err
+	}
+	id := lastID + 1
+	hash := auditHash(prev, id, timestamp, operation, fileID, user, detail)
+	_, err = q.Exec(insertAuditSQL, id, timestamp, operation, fileID, user, detail, prev, hash)
+	return err
+}
+
+// auditHash links an entry to its predecessor's hash. The subject is the
+// entry's file ID in a project log and its project name in a workspace
+// log. The fields are JSON encoded so that no two distinct entries
+// serialize alike.
+func auditHash(prev string, id int64, timestamp, operation string, subject any, user, detail *string) string {
+	data, _ := json.Marshal([]any{prev, id, timestamp, operation, subject, user, detail})
+	sum := sha256.Sum256(data)
+	return hex.EncodeToString(sum[:])
+}
+
+// AuditBreak is a point where the audit chain fails to verify.
+type AuditBreak struct {
+	ID     int64
+	Reason string
+}
+
+// VerifyAuditChain walks the audit log in order, recomputing each entry's
+// hash. It reports missing ids, entries whose content was changed, entries
+// that do not follow their predecessor, and entries written outside the
+// chain. It also returns the entry count and the hash of the last entry;
+// truncation of the newest entries only shows against a head recorded
+// elsewhere.
+func (p *ProjectDb) VerifyAuditChain() (count int64, head string, breaks []AuditBreak, err error) {
+	return verifyAuditChain(p.db, "file_id", new(*int64))
+}
+
+// verifyAuditChain checks the chain of the audit_log table in q, whose
+// subject column is scanned into subject.
+func verifyAuditChain(q querier, subjectColumn string, subject any) (count int64, head string, breaks []AuditBreak, err error) {
+	rows, err := q.Query(
+		`SELECT id, timestamp, operation, ` + subjectColumn + `, user, detail, prev_hash, hash
+		 FROM audit_log ORDER BY id`,
+	)
+	if err != nil {
+		return 0, "", nil, err
+	}
+	defer rows.Close()
+
+	var lastID int64
+	for rows.Next() {
+		var a models.AuditEntry
+		var prev, hash sql.NullString
+		if err := rows.Scan(&a.ID, &a.Timestamp, &a.Operation, subject, &a.User, &a.Detail, &prev, &hash); err != nil {
+			return 0, "", nil, err
+		}
+		count++
+
+		switch {
+		case !hash.Valid:
+			breaks = append(breaks, AuditBreak{a.ID, "entry is not part of the chain"})
+		case a.ID != lastID+1:
+			if a.ID-1 == lastID+1 {
+				breaks = append(breaks, AuditBreak{a.ID, fmt.Sprintf("entry %d is missing", lastID+1)})
+			} else {
+				breaks = append(breaks, AuditBreak{a.ID, fmt.Sprintf("entries %d-%d are missing", lastID+1, a.ID-1)})
+			}
+		case prev.String != head:
+			breaks = append(breaks, AuditBreak{a.ID, "entry does not follow the one before it"})
+		}
+		if hash.Valid && auditHash(prev.String, a.ID, a.Timestamp, a.Operation, subject, a.User, a.Detail) != hash.String {
+			breaks = append(breaks, AuditBreak{a.ID, "entry was altered"})
+		}
+		lastID = a.ID
+		head = hash.String
+	}
+	return count, head, breaks, rows.Err()
+}
+
+// AuditHash returns the chain hash of audit entry id, or "" if there is
+// no such entry.
+func (p *ProjectDb) AuditHash(id int64) (string, error) {
+	var hash sql.NullString
+	err := p.db.QueryRow(`SELECT hash FROM audit_log WHERE id = ?`, id).Scan(&hash)
+	if err == sql.ErrNoRows {
+		return "", nil
+	}
+	return hash.String, err
+}
+
+// ListAudit returns the most recent audit entries, newest first. A limit
+// of zero or less returns every entry.
+func (p *ProjectDb) ListAudit(limit int) ([]models.AuditEntry, error) {
+	query := `SELECT id, timestamp, operation, file_id, user, detail
+		 FROM audit_log ORDER BY id DESC`
+	var args []any
+	if limit > 0 {
+		query += ` LIMIT ?`
+		args = append(args, limit)
+	}
+	rows, err := p.db.Query(query, args...)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var entries []models.AuditEntry
+	for rows.Next() {
+		var a models.AuditEntry
+		if err := rows.Scan(&a.ID, &a.Timestamp, &a.Operation, &a.FileID, &a.User, &a.Detail); err != nil {
+			return nil, err
+		}
+		entries = append(entries, a)
+	}
+	return entries, rows.Err()
+}
+
+// --- Anchors ---
+
+func (p *ProjectDb) InsertAnchor(a *models.Anchor) (int64, error) {
+	res, err := p.db.Exec(
+		`INSERT INTO anchors (created_at, digest, statement, calendar, proof) VALUES (?, ?, ?, ?, ?)`,
+		a.CreatedAt, a.Digest, a.Statement, a.Calendar, a.Proof,
 	)
+	if err != nil {
+		return 0, err
+	}
+	return res.LastInsertId()
+}
+
+// UpdateAnchorProof replaces an anchor's proof with an upgraded one.
+func (p *ProjectDb) UpdateAnchorProof(id int64, proof []byte) error {
+	_, err := p.db.Exec(`UPDATE anchors SET proof = ? WHERE id = ?`, proof, id)
 	return err
 }
 
+// ListAnchors returns every anchor, oldest first.
+func (p *ProjectDb) ListAnchors() ([]models.Anchor, error) {
+	rows, err := p.db.Query(
+		`SELECT id, created_at, digest, statement, calendar, proof FROM anchors ORDER BY id`,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var anchors []models.Anchor
+	for rows.Next() {
+		var a models.Anchor
+		if err := rows.Scan(&a.ID, &a.CreatedAt, &a.Digest, &a.Statement, &a.Calendar, &a.Proof); err != nil {
+			return nil, err
+		}
+		anchors = append(anchors, a)
+	}
+	return anchors, rows.Err()
+}
+
 // --- Counts ---
 
 func (p *ProjectDb) FileCount() (int64, error) {
@@ -404,7 +1294,7 @@ type fileScanner interface {
 
 func scanFileFromRow(scanner fileScanner) (*models.TrackedFile, error) {
 	var f models.TrackedFile
-	err := scanner.Scan(&f.ID, &f.SHA256, &f.Fingerprint, &f.MimeType, &f.Size, &f.IngestedAt, &f.Provenance)
+	err := scanner.Scan(&f.ID, &f.SHA256, &f.Fingerprint, &f.MimeType, &f.Size, &f.IngestedAt, &f.Provenance, &f.HashAlgorithm)
 	if err == sql.ErrNoRows {
 		return nil, nil
 	}
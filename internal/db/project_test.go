@@ -177,6 +177,72 @@ func TestFileCRUD(t *testing.T) {
 	}
 }
 
+func TestFileVersions(t *testing.T) {
+	db := testDb(t)
+	id, _ := db.InsertFile(&models.TrackedFile{SHA256: "v1", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	size := int64(5)
+	db.InsertFileVersion(id, "v1", &size, "2025-01-01T00:00:00Z")
+	db.InsertFileVersion(id, "v2", nil, "2025-01-02T00:00:00Z")
+
+	versions, err := db.ListFileVersions(id)
+	if err != nil {
+		t.Fatalf("list: %v", err)
+	}
+	if len(versions) != 2 || versions[0].SHA256 != "v1" || versions[1].SHA256 != "v2" {
+		t.Fatalf("expected v1, v2 oldest first, got %+v", versions)
+	}
+	if versions[0].Size == nil || *versions[0].Size != 5 || versions[1].Size != nil {
+		t.Fatalf("unexpected sizes: %+v", versions)
+	}
+}
+
+func TestStoredObjects(t *testing.T) {
+	db := testDb(t)
+	if o, err := db.GetStoredObject("h1"); err != nil || o != nil {
+		t.Fatalf("expected no record, got %+v, %v", o, err)
+	}
+	db.PutStoredObject(&models.StoredObject{SHA256: "h1", Size: 5, LockMode: "off", StoredAt: "2025-01-01T00:00:00Z"})
+	db.PutStoredObject(&models.StoredObject{SHA256: "h1", Size: 5, LockMode: "compliance", RetainUntil: strPtr("2035-01-01T00:00:00Z"), StoredAt: "2025-01-02T00:00:00Z"})
+
+	o, err := db.GetStoredObject("h1")
+	if err != nil || o == nil || o.LockMode != "compliance" || o.RetainUntil == nil {
+		t.Fatalf("expected the later record to replace the first, got %+v, %v", o, err)
+	}
+	if objects, _ := db.ListStoredObjects(); len(objects) != 1 {
+		t.Fatalf("expected one stored object, got %+v", objects)
+	}
+}
+
+func TestCachedHash(t *testing.T) {
+	db := testDb(t)
+	if err := db.PutCachedHash("evidence/a.txt", 10, 1000, 0, "abc"); err != nil {
+		t.Fatalf("put: %v", err)
+	}
+
+	if h, _ := db.CachedHash("evidence/a.txt", 10, 1000, 0); h != "abc" {
+		t.Fatalf("expected cached hash, got %q", h)
+	}
+	if h, _ := db.CachedHash("evidence/a.txt", 10, 2000, 0); h != "" {
+		t.Fatalf("expected miss on changed mtime, got %q", h)
+	}
+
+	db.PutCachedHash("evidence/a.txt", 11, 2000, 0, "def")
+	if h, _ := db.CachedHash("evidence/a.txt", 11, 2000, 0); h != "def" {
+		t.Fatalf("expected replaced hash, got %q", h)
+	}
+
+	db.PutCachedHash("evidence/b.txt", 5, 3000, 42, "ghi")
+	if h, _ := db.CachedHash("evidence/b.txt", 5, 3000, 42); h != "ghi" {
+		t.Fatalf("expected hit on the same inode, got %q", h)
+	}
+	if h, _ := db.CachedHash("evidence/b.txt", 5, 3000, 43); h != "" {
+		t.Fatalf("expected miss on a replaced inode, got %q", h)
+	}
+	if h, _ := db.CachedHash("evidence/a.txt", 11, 2000, 7); h != "def" {
+		t.Fatalf("expected a row without an inode to match any, got %q", h)
+	}
+}
+
 func TestTags(t *testing.T) {
 	db := testDb(t)
 
@@ -208,6 +274,241 @@ func TestTags(t *testing.T) {
 	}
 }
 
+func TestFileMetadata(t *testing.T) {
+	db := testDb(t)
+	id, _ := db.InsertFile(&models.TrackedFile{SHA256: "photo", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	db.SetFileMetadata(id, map[string]string{"camera": "Canon EOS 5D", "gps": "40.7, -74.0"})
+	db.SetFileMetadata(id, map[string]string{"camera": "Nikon D850"})
+
+	got, err := db.GetFileMetadata(id)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(got) != 1 || got["camera"] != "Nikon D850" {
+		t.Fatalf("expected metadata to be replaced, got %v", got)
+	}
+}
+
+func TestFileAttrs(t *testing.T) {
+	db := testDb(t)
+	id, _ := db.InsertFile(&models.TrackedFile{SHA256: "memo", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	if err := db.SetFileAttr(id, "foia-request", "2024-0117"); err != nil {
+		t.Fatal(err)
+	}
+	db.SetFileAttr(id, "source", "BLUEBIRD")
+	db.SetFileAttr(id, "foia-request", "2024-0118")
+
+	got, err := db.GetFileAttrs(id)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(got) != 2 || got["foia-request"] != "2024-0118" {
+		t.Fatalf("expected the value replaced, got %v", got)
+	}
+	if n, _ := db.RemoveFileAttr(id, "source"); n != 1 {
+		t.Fatalf("expected one attribute removed, got %d", n)
+	}
+	if n, _ := db.RemoveFileAttr(id, "source"); n != 0 {
+		t.Fatalf("expected nothing left to remove, got %d", n)
+	}
+}
+
+func TestDerivations(t *testing.T) {
+	db := testDb(t)
+	scan, _ := db.InsertFile(&models.TrackedFile{SHA256: "scan", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	text, _ := db.InsertFile(&models.TrackedFile{SHA256: "text", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	if err := db.InsertDerivation(scan, text, "ocr"); err != nil {
+		t.Fatalf("insert: %v", err)
+	}
+	db.InsertDerivation(scan, text, "ocr")
+
+	from, _ := db.DerivedFrom(text)
+	if len(from) != 1 || from[0].SourceID != scan || from[0].Via == nil || *from[0].Via != "ocr" {
+		t.Fatalf("expected one derivation via ocr, got %+v", from)
+	}
+	if out, _ := db.Derivatives(scan); len(out) != 1 || out[0].DerivedID != text {
+		t.Fatalf("expected text as derivative of scan, got %+v", out)
+	}
+	if out, _ := db.Derivatives(text); len(out) != 0 {
+		t.Fatalf("expected no derivatives of text, got %+v", out)
+	}
+}
+
+func TestListAudit(t *testing.T) {
+	db := testDb(t)
+	db.InsertAudit("ingest", nil, strPtr("alice"), nil)
+	db.InsertAudit("tag", nil, strPtr("alice"), nil)
+	db.InsertAudit("sign", nil, strPtr("bob"), nil)
+
+	entries, err := db.ListAudit(2)
+	if err != nil {
+		t.Fatalf("list audit: %v", err)
+	}
+	if len(entries) != 2 {
+		t.Fatalf("expected 2 entries, got %d", len(entries))
+	}
+	if entries[0].Operation != "sign" || entries[1].Operation != "tag" {
+		t.Fatalf("expected newest first, got %s, %s", entries[0].Operation, entries[1].Operation)
+	}
+
+	all, _ := db.ListAudit(0)
+	if len(all) != 3 {
+		t.Fatalf("expected 3 entries without limit, got %d", len(all))
+	}
+}
+
+func TestVerifyAuditChain(t *testing.T) {
+	db := testDb(t)
+	for _, op := range []string{"ingest", "tag", "sign", "verify"} {
+		db.InsertAudit(op, nil, strPtr("alice"), nil)
+	}
+
+	count, head, breaks, err := db.VerifyAuditChain()
+	if err != nil {
+		t.Fatalf("verify: %v", err)
+	}
+	if count != 4 || len(breaks) != 0 || head == "" {
+		t.Fatalf("expected intact chain of 4, got %d entries, head %q, breaks %v", count, head, breaks)
+	}
+	if h, _ := db.AuditHash(4); h != head {
+		t.Fatalf("expected entry 4 to hold the head, got %q", h)
+	}
+	if h, _ := db.AuditHash(5); h != "" {
+		t.Fatalf("expected no entry 5, got %q", h)
+	}
+
+	db.DB().Exec(`UPDATE audit_log SET user = 'mallory' WHERE id = 2`)
+	db.DB().Exec(`DELETE FROM audit_log WHERE id = 3`)
+	_, _, breaks, _ = db.VerifyAuditChain()
+	if len(breaks) != 2 {
+		t.Fatalf("expected 2 breaks, got %v", breaks)
+	}
+	if breaks[0].ID != 2 || breaks[0].Reason != "entry was altered" {
+		t.Fatalf("expected altered entry 2, got %v", breaks[0])
+	}
+	if breaks[1].ID != 4 || breaks[1].Reason != "entry 3 is missing" {
+		t.Fatalf("expected missing entry 3, got %v", breaks[1])
+	}
+}
+
+func TestProjectConfig(t *testing.T) {
+	db := testDb(t)
+
+	if v, _ := db.GetConfig("symlinks"); v != nil {
+		t.Fatalf("expected unset, got %q", *v)
+	}
+	db.SetConfig("symlinks", "follow")
+	db.SetConfig("symlinks", "link")
+	if v, _ := db.GetConfig("symlinks"); v == nil || *v != "link" {
+		t.Fatalf("expected link, got %v", v)
+	}
+	if all, _ := db.ListConfig(); len(all) != 1 {
+		t.Fatalf("expected 1 setting, got %v", all)
+	}
+
+	db.UnsetConfig("symlinks")
+	if v, _ := db.GetConfig("symlinks"); v != nil {
+		t.Fatalf("expected unset after remove, got %q", *v)
+	}
+}
+
+func TestBatch(t *testing.T) {
+	db := testDb(t)
+
+	err := db.Batch(func(b *ProjectDb) error {
+		for _, h := range []string{"aaa", "bbb"} {
+			if _, err := b.InsertFile(&models.TrackedFile{SHA256: h, Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"}); err != nil {
+				return err
+			}
+		}
+		return nil
+	})
+	if err != nil {
+		t.Fatalf("batch: %v", err)
+	}
+	if count, _ := db.FileCount(); count != 2 {
+		t.Fatalf("expected 2 files after commit, got %d", count)
+	}
+
+	err = db.Batch(func(b *ProjectDb) error {
+		b.InsertFile(&models.TrackedFile{SHA256: "ccc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+		return os.ErrInvalid
+	})
+	if err != os.ErrInvalid {
+		t.Fatalf("expected fn error, got %v", err)
+	}
+	if count, _ := db.FileCount(); count != 2 {
+		t.Fatalf("expected rollback to keep 2 files, got %d", count)
+	}
+}
+
+func TestBatchInserts(t *testing.T) {
+	db := testDb(t)
+	db.InsertAudit("init", nil, strPtr("alice"), nil)
+
+	var files []*models.TrackedFile
+	for _, h := range []string{"aaa", "bbb", "ccc"} {
+		files = append(files, &models.TrackedFile{SHA256: h, Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	}
+	ids, err := db.InsertFiles(files)
+	if err != nil {
+		t.Fatalf("insert files: %v", err)
+	}
+	if len(ids) != 3 {
+		t.Fatalf("expected 3 ids, got %v", ids)
+	}
+	if f, _ := db.GetFileByHash("ccc"); f == nil || *f.ID != ids[2] || f.HashAlgorithm != "sha256" {
+		t.Fatalf("expected ccc tracked as id %d with the project algorithm, got %+v", ids[2], f)
+	}
+
+	var tags []models.FileTag
+	var audits []models.AuditEntry
+	for _, id := range ids {
+		tags = append(tags, models.FileTag{FileID: id, Tag: "batch"})
+		audits = append(audits, models.AuditEntry{Operation: "tag", FileID: &id, User: strPtr("alice")})
+	}
+	if err := db.InsertTags(append(tags, tags[0])); err != nil {
+		t.Fatalf("insert tags: %v", err)
+	}
+	if got, _ := db.GetTags(ids[1]); len(got) != 1 || got[0] != "batch" {
+		t.Fatalf("expected bbb tagged, got %v", got)
+	}
+	if err := db.InsertAudits(audits); err != nil {
+		t.Fatalf("insert audits: %v", err)
+	}
+	count, _, breaks, err := db.VerifyAuditChain()
+	if err != nil || count != 4 || len(breaks) != 0 {
+		t.Fatalf("expected intact chain of 4, got %d entries, breaks %v, %v", count, breaks, err)
+	}
+
+	// A duplicate hash fails the whole batch, and a batch joined from
+	// inside another rolls back with it.
+	if _, err := db.InsertFiles([]*models.TrackedFile{
+		{SHA256: "ddd", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"},
+		files[0],
+	}); err == nil {
+		t.Fatal("expected a duplicate hash to fail")
+	}
+	err = db.Batch(func(b *ProjectDb) error {
+		if err := b.InsertAudits(audits[:1]); err != nil {
+			return err
+		}
+		return os.ErrInvalid
+	})
+	if err != os.ErrInvalid {
+		t.Fatalf("expected fn error, got %v", err)
+	}
+	if n, _ := db.FileCount(); n != 3 {
+		t.Fatalf("expected 3 files, got %d", n)
+	}
+	if count, _, _, _ := db.VerifyAuditChain(); count != 4 {
+		t.Fatalf("expected nested batch rolled back, got %d entries", count)
+	}
+}
+
 func TestOpenProject(t *testing.T) {
 	dir := t.TempDir()
 	path := filepath.Join(dir, ".mkrk")
@@ -249,3 +550,29 @@ func TestCreateProjectDir(t *testing.T) {
 	}
 	db.Close()
 }
+
+func TestSavedRefs(t *testing.T) {
+	db := testDb(t)
+
+	if err := db.SaveRef("hot", ":evidence!urgent"); err != nil {
+		t.Fatalf("save: %v", err)
+	}
+	if err := db.SaveRef("hot", ":evidence!urgent!~reviewed"); err != nil {
+		t.Fatalf("resave: %v", err)
+	}
+	ref, err := db.GetSavedRef("hot")
+	if err != nil || ref == nil || *ref != ":evidence!urgent!~reviewed" {
+		t.Fatalf("expected replaced reference, got %v, %v", ref, err)
+	}
+	if ref, _ := db.GetSavedRef("cold"); ref != nil {
+		t.Fatalf("expected nil for unknown name, got %s", *ref)
+	}
+
+	refs, err := db.ListSavedRefs()
+	if err != nil || len(refs) != 1 || refs[0].Name != "hot" {
+		t.Fatalf("expected one saved reference, got %v, %v", refs, err)
+	}
+	if n, _ := db.RemoveSavedRef("hot"); n != 1 {
+		t.Fatalf("expected 1 removed, got %d", n)
+	}
+}
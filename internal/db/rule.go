@@ -0,0 +1,165 @@
+package db
+
+import (
+	"database/sql"
+	"encoding/json"
+	"fmt"
+	"time"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// --- Event rules ---
+//
+// Projects and workspaces both keep rules in a rules table. A workspace's
+// rules apply to every project in it, after the project's own; a project
+// rule of the same name overrides one, even when disabled.
+
+func (p *ProjectDb) InsertRule(r *models.Rule) (int64, error) {
+	return insertRule(p.db, r)
+}
+
+func (p *ProjectDb) GetRuleByName(name string) (*models.Rule, error) {
+	return getRuleByName(p.db, name)
+}
+
+func (p *ProjectDb) ListRules() ([]models.Rule, error) {
+	return listRules(p.db)
+}
+
+// ListRulesForTrigger returns the enabled rules for an event in the order
+// they should run.
+func (p *ProjectDb) ListRulesForTrigger(event models.TriggerEvent) ([]models.Rule, error) {
+	return listRulesForTrigger(p.db, event)
+}
+
+func (p *ProjectDb) RemoveRule(name string) (int64, error) {
+	return removeRule(p.db, name)
+}
+
+func (p *ProjectDb) SetRuleEnabled(name string, enabled bool) (int64, error) {
+	return setRuleEnabled(p.db, name, enabled)
+}
+
+func (w *WorkspaceDb) InsertRule(r *models.Rule) (int64, error) {
+	return insertRule(w.db, r)
+}
+
+func (w *WorkspaceDb) GetRuleByName(name string) (*models.Rule, error) {
+	return getRuleByName(w.db, name)
+}
+
+func (w *WorkspaceDb) ListRules() ([]models.Rule, error) {
+	return listRules(w.db)
+}
+
+func (w *WorkspaceDb) ListRulesForTrigger(event models.TriggerEvent) ([]models.Rule, error) {
+	return listRulesForTrigger(w.db, event)
+}
+
+func (w *WorkspaceDb) RemoveRule(name string) (int64, error) {
+	return removeRule(w.db, name)
+}
+
+func (w *WorkspaceDb) SetRuleEnabled(name string, enabled bool) (int64, error) {
+	return setRuleEnabled(w.db, name, enabled)
+}
+
+func insertRule(d *sql.DB, r *models.Rule) (int64, error) {
+	var filterJSON *string
+	if !r.TriggerFilter.IsEmpty() {
+		b, _ := json.Marshal(r.TriggerFilter)
+		s := string(b)
+		filterJSON = &s
+	}
+	configJSON, _ := json.Marshal(r.ActionConfig)
+	createdAt := r.CreatedAt
+	if createdAt == "" {
+		createdAt = time.Now().UTC().Format(time.RFC3339)
+	}
+
+	res, err := d.Exec(
+		`INSERT INTO rules (name, enabled, trigger_event, trigger_filter, action_type, action_config, priority, created_at)
+		 VALUES (?, ?, ?, ?, ?, ?, ?, ?)`,
+		r.Name, r.Enabled, string(r.TriggerEvent), filterJSON,
+		string(r.ActionType), string(configJSON), r.Priority, createdAt,
+	)
+	if err != nil {
+		return 0, fmt.Errorf("insert rule: %w", err)
+	}
+	return res.LastInsertId()
+}
+
+func getRuleByName(d *sql.DB, name string) (*models.Rule, error) {
+	rows, err := d.Query(ruleSelect+` WHERE name = ?`, name)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	rules, err := scanRules(rows)
+	if err != nil || len(rules) == 0 {
+		return nil, err
+	}
+	return &rules[0], nil
+}
+
+func listRules(d *sql.DB) ([]models.Rule, error) {
+	rows, err := d.Query(ruleSelect + ` ORDER BY priority, name`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	return scanRules(rows)
+}
+
+func listRulesForTrigger(d *sql.DB, event models.TriggerEvent) ([]models.Rule, error) {
+	rows, err := d.Query(
+		ruleSelect+` WHERE trigger_event = ? AND enabled = 1 ORDER BY priority, id`, string(event),
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	return scanRules(rows)
+}
+
+func removeRule(d *sql.DB, name string) (int64, error) {
+	res, err := d.Exec(`DELETE FROM rules WHERE name = ?`, name)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+func setRuleEnabled(d *sql.DB, name string, enabled bool) (int64, error) {
+	res, err := d.Exec(`UPDATE rules SET enabled = ? WHERE name = ?`, enabled, name)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+const ruleSelect = `SELECT id, name, enabled, trigger_event, trigger_filter, action_type, action_config, priority, created_at FROM rules`
+
+func scanRules(rows *sql.Rows) ([]models.Rule, error) {
+	var rules []models.Rule
+	for rows.Next() {
+		var r models.Rule
+		var id int64
+		var filterJSON sql.NullString
+		var trigger, actionType, configJSON string
+		if err := rows.Scan(&id, &r.Name, &r.Enabled, &trigger, &filterJSON,
+			&actionType, &configJSON, &r.Priority, &r.CreatedAt); err != nil {
+			return nil, err
+		}
+		r.ID = &id
+		r.TriggerEvent = models.TriggerEvent(trigger)
+		r.ActionType = models.ActionType(actionType)
+		json.Unmarshal([]byte(configJSON), &r.ActionConfig)
+		if filterJSON.Valid {
+			json.Unmarshal([]byte(filterJSON.String), &r.TriggerFilter)
+		}
+		rules = append(rules, r)
+	}
+	return rules, rows.Err()
+}
@@ -0,0 +1,50 @@
+package db
+
+import (
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestRuleCRUD(t *testing.T) {
+	db := testDb(t)
+
+	_, err := db.InsertRule(&models.Rule{
+		Name:          "flag-tampered",
+		Enabled:       true,
+		TriggerEvent:  models.TriggerVerifyFailure,
+		TriggerFilter: models.TriggerFilter{Category: strPtr("evidence")},
+		ActionType:    models.EventActionAddTag,
+		ActionConfig:  models.ActionConfig{Tag: strPtr("tampered")},
+	})
+	if err != nil {
+		t.Fatal(err)
+	}
+
+	r, err := db.GetRuleByName("flag-tampered")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if r == nil || r.ActionConfig.Tag == nil || *r.ActionConfig.Tag != "tampered" {
+		t.Fatalf("unexpected rule: %+v", r)
+	}
+	if r.TriggerFilter.Category == nil || *r.TriggerFilter.Category != "evidence" {
+		t.Fatalf("filter not round-tripped: %+v", r.TriggerFilter)
+	}
+
+	rules, _ := db.ListRulesForTrigger(models.TriggerVerifyFailure)
+	if len(rules) != 1 {
+		t.Fatalf("expected 1 rule for trigger, got %d", len(rules))
+	}
+
+	db.SetRuleEnabled("flag-tampered", false)
+	rules, _ = db.ListRulesForTrigger(models.TriggerVerifyFailure)
+	if len(rules) != 0 {
+		t.Fatal("disabled rule should not be listed for trigger")
+	}
+
+	removed, _ := db.RemoveRule("flag-tampered")
+	if removed != 1 {
+		t.Fatalf("expected 1 removed, got %d", removed)
+	}
+}
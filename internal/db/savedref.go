@@ -0,0 +1,94 @@
+package db
+
+import (
+	"database/sql"
+	"time"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// --- Saved References ---
+//
+// Projects and workspaces both keep saved references in a saved_refs
+// table; a project's shadow the workspace's of the same name.
+
+func (p *ProjectDb) SaveRef(name, ref string) error {
+	return saveRef(p.db, name, ref)
+}
+
+func (p *ProjectDb) GetSavedRef(name string) (*string, error) {
+	return getSavedRef(p.db, name)
+}
+
+func (p *ProjectDb) ListSavedRefs() ([]models.SavedRef, error) {
+	return listSavedRefs(p.db)
+}
+
+func (p *ProjectDb) RemoveSavedRef(name string) (int64, error) {
+	return removeSavedRef(p.db, name)
+}
+
+func (w *WorkspaceDb) SaveRef(name, ref string) error {
+	return saveRef(w.db, name, ref)
+}
+
+func (w *WorkspaceDb) GetSavedRef(name string) (*string, error) {
+	return getSavedRef(w.db, name)
+}
+
+func (w *WorkspaceDb) ListSavedRefs() ([]models.SavedRef, error) {
+	return listSavedRefs(w.db)
+}
+
+func (w *WorkspaceDb) RemoveSavedRef(name string) (int64, error) {
+	return removeSavedRef(w.db, name)
+}
+
+// saveRef stores ref under name, replacing any reference saved there.
+func saveRef(d *sql.DB, name, ref string) error {
+	now := time.Now().UTC().Format(time.RFC3339)
+	_, err := d.Exec(
+		`INSERT INTO saved_refs (name, reference, created_at) VALUES (?, ?, ?)
+		 ON CONFLICT(name) DO UPDATE SET reference = excluded.reference, created_at = excluded.created_at`,
+		name, ref, now,
+	)
+	return err
+}
+
+func getSavedRef(d *sql.DB, name string) (*string, error) {
+	var ref string
+	err := d.QueryRow(`SELECT reference FROM saved_refs WHERE name = ?`, name).Scan(&ref)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &ref, nil
+}
+
+func listSavedRefs(d *sql.DB) ([]models.SavedRef, error) {
+	rows, err := d.Query(`SELECT name, reference, created_at FROM saved_refs ORDER BY name`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var refs []models.SavedRef
+	for rows.Next() {
+		var r models.SavedRef
+		if err := rows.Scan(&r.Name, &r.Reference, &r.CreatedAt); err != nil {
+			return nil, err
+		}
+		refs = append(refs, r)
+	}
+	return refs, rows.Err()
+}
+
+func removeSavedRef(d *sql.DB, name string) (int64, error) {
+	res, err := d.Exec(`DELETE FROM saved_refs WHERE name = ?`, name)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
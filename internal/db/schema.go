@@ -27,6 +27,20 @@ CREATE TABLE IF NOT EXISTS scope_tool_config (
     quiet INTEGER NOT NULL DEFAULT 1,
     UNIQUE(scope_id, action, file_type)
 );
+
+CREATE TABLE IF NOT EXISTS scope_encryption (
+    id INTEGER PRIMARY KEY,
+    scope_id INTEGER NOT NULL UNIQUE REFERENCES scopes(id)
+);
+
+CREATE TABLE IF NOT EXISTS open_config (
+    id INTEGER PRIMARY KEY,
+    scope_id INTEGER REFERENCES scopes(id),
+    mime_type TEXT NOT NULL,
+    command TEXT NOT NULL,
+    read_only INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(scope_id, mime_type)
+);
 `
 
 const filesSchema = `
@@ -37,7 +51,104 @@ CREATE TABLE IF NOT EXISTS files (
     mime_type TEXT,
     size INTEGER,
     ingested_at TEXT NOT NULL,
-    provenance TEXT
+    provenance TEXT,
+    -- The algorithm the sha256 column's hash was made with: the project's
+    -- hash-algorithm setting when the file was tracked.
+    hash_algorithm TEXT NOT NULL DEFAULT 'sha256'
+);
+
+-- Every revision of a file's content, oldest first. Copies of the content
+-- live in the project's version store, named by hash.
+CREATE TABLE IF NOT EXISTS file_versions (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    sha256 TEXT NOT NULL,
+    size INTEGER,
+    recorded_at TEXT NOT NULL
+);
+
+-- Tombstones of tracked files removed with mkrk rm: who removed each,
+-- when and why. The file waits in the project's trash directory until it
+-- is restored, which drops its row, or purged, which keeps the row.
+CREATE TABLE IF NOT EXISTS trash (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    trashed_at TEXT NOT NULL,
+    user TEXT,
+    reason TEXT,
+    purged_at TEXT
+);
+
+-- Content moved out to remote storage, by hash, with the Object Lock
+-- retention it was stored under. The working tree keeps a stub in its
+-- place.
+CREATE TABLE IF NOT EXISTS stored_objects (
+    sha256 TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    lock_mode TEXT NOT NULL,
+    retain_until TEXT,
+    stored_at TEXT NOT NULL
+);
+
+-- Metadata embedded in a file's content, such as EXIF camera and GPS
+-- fields or a PDF's author, extracted when the file is tracked.
+CREATE TABLE IF NOT EXISTS file_metadata (
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    UNIQUE(file_id, key)
+);
+
+-- Facts recorded by hand about a file that do not fit as tags, such as a
+-- FOIA request number or a source's codename, set with mkrk attr.
+CREATE TABLE IF NOT EXISTS file_attrs (
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    UNIQUE(file_id, key)
+);
+
+-- Last hash seen at each path, keyed by the SHA-256 of the path so that no
+-- names are stored. Only used to skip rehashing unchanged files during
+-- sync --quick and verify --quick; never used to identify files. inode is
+-- NULL where the platform has none.
+CREATE TABLE IF NOT EXISTS sync_cache (
+    path_hash TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    mtime INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    inode INTEGER
+);
+
+-- The stored form of content kept encrypted at rest. A file's identity is
+-- the hash of its plaintext; this maps the hash and fingerprint of the
+-- bytes on disk back to it, so encrypted files are recognized without
+-- decrypting them. The plaintext fingerprint is NULL for kept revisions.
+CREATE TABLE IF NOT EXISTS ciphertexts (
+    sha256 TEXT PRIMARY KEY,
+    fingerprint TEXT NOT NULL,
+    plaintext_sha256 TEXT NOT NULL,
+    plaintext_fingerprint TEXT
+);
+
+-- Copies made by copy-out into a category, keyed by the SHA-256 of the
+-- destination path so that no names are stored. When sync first tracks
+-- the edited copy, it is linked to its source and the row is removed.
+CREATE TABLE IF NOT EXISTS working_copies (
+    path_hash TEXT PRIMARY KEY,
+    source_file_id INTEGER NOT NULL REFERENCES files(id),
+    tag INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL
+);
+
+-- Change log entries from replica stores that mkrk pull has applied, so
+-- each is applied once. store is the SHA-256 of the store's address, so
+-- that no locations are stored.
+CREATE TABLE IF NOT EXISTS replica_segments (
+    store TEXT NOT NULL,
+    segment TEXT NOT NULL,
+    applied_at TEXT NOT NULL,
+    PRIMARY KEY (store, segment)
 );
 
 CREATE TABLE IF NOT EXISTS file_tags (
@@ -129,7 +240,22 @@ CREATE TABLE IF NOT EXISTS signs (
     signer TEXT NOT NULL,
     signed_at TEXT NOT NULL,
     signature TEXT,
-    revoked_at TEXT
+    revoked_at TEXT,
+    signature_format TEXT,
+    signing_key TEXT,
+    timestamp_token BLOB
+);
+
+CREATE TABLE IF NOT EXISTS state_history (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    pipeline_id INTEGER NOT NULL REFERENCES pipelines(id),
+    from_state TEXT NOT NULL,
+    to_state TEXT NOT NULL,
+    changed_at TEXT NOT NULL,
+    cause TEXT NOT NULL,
+    sign_name TEXT,
+    actor TEXT NOT NULL
 );
 `
 
@@ -167,6 +293,21 @@ CREATE TABLE IF NOT EXISTS ruleset_files (
 );
 `
 
+const projectConfigSchema = `
+CREATE TABLE IF NOT EXISTS project_config (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+`
+
+const savedRefSchema = `
+CREATE TABLE IF NOT EXISTS saved_refs (
+    name TEXT PRIMARY KEY,
+    reference TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+`
+
 const auditSchema = `
 CREATE TABLE IF NOT EXISTS audit_log (
     id INTEGER PRIMARY KEY,
@@ -174,7 +315,28 @@ CREATE TABLE IF NOT EXISTS audit_log (
     operation TEXT NOT NULL,
     file_id INTEGER REFERENCES files(id),
     user TEXT,
-    detail TEXT
+    detail TEXT,
+    prev_hash TEXT,
+    hash TEXT
+);
+`
+
+const anchorSchema = `
+CREATE TABLE IF NOT EXISTS anchors (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    digest TEXT NOT NULL,
+    statement BLOB NOT NULL,
+    calendar TEXT NOT NULL,
+    proof BLOB NOT NULL
+);
+`
+
+const schemaVersionSchema = `
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TEXT NOT NULL
 );
 `
 
@@ -191,6 +353,20 @@ CREATE TABLE IF NOT EXISTS default_pipelines (
     transitions TEXT NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS default_pipeline_attachments (
+    pipeline_id INTEGER NOT NULL REFERENCES default_pipelines(id),
+    reference TEXT NOT NULL,
+    PRIMARY KEY (pipeline_id, reference)
+);
+
+CREATE TABLE IF NOT EXISTS workspace_tools (
+    name TEXT PRIMARY KEY,
+    file TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    description TEXT,
+    added_at TEXT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS entity_links (
     id INTEGER PRIMARY KEY,
     entity_name TEXT NOT NULL,
@@ -199,10 +375,41 @@ CREATE TABLE IF NOT EXISTS entity_links (
     project_entity_id INTEGER,
     UNIQUE(entity_name, entity_type, project_name)
 );
+
+-- Files tracked by each project, so that workspace-wide lookups need not
+-- open every project's database. Paths are keyed by their SHA-256, as in a
+-- project's sync cache, so that no names are stored. Refreshed by sync and
+-- as files are ingested.
+CREATE TABLE IF NOT EXISTS file_index (
+    project_name TEXT NOT NULL,
+    path_hash TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    mime_type TEXT,
+    size INTEGER,
+    PRIMARY KEY (project_name, path_hash)
+);
+
+CREATE TABLE IF NOT EXISTS archived_projects (
+    project_name TEXT PRIMARY KEY,
+    archived_at TEXT NOT NULL,
+    prior_policies TEXT NOT NULL,
+    archive_path TEXT
+);
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    project_name TEXT,
+    user TEXT,
+    detail TEXT,
+    prev_hash TEXT,
+    hash TEXT
+);
 `
 
 // ProjectSchema is the full schema for a .mkrk project database.
-var ProjectSchema = scopeTablesSchema + filesSchema + rulesSchema + pipelineSchema + rulesetSchema + auditSchema
+var ProjectSchema = scopeTablesSchema + filesSchema + rulesSchema + pipelineSchema + rulesetSchema + projectConfigSchema + savedRefSchema + auditSchema + anchorSchema + schemaVersionSchema
 
 // WorkspaceSchema is the full schema for a .mksp workspace database.
-var WorkspaceSchema = workspaceSchema + scopeTablesSchema + rulesetSchema
+var WorkspaceSchema = workspaceSchema + scopeTablesSchema + rulesSchema + rulesetSchema + savedRefSchema + schemaVersionSchema
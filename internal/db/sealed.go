@@ -0,0 +1,262 @@
+package db
+
+import (
+	"bytes"
+	"context"
+	"database/sql"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+
+	"go.foia.dev/muckrake/internal/crypt"
+)
+
+// ErrSealed is returned when opening a database encrypted at rest while
+// nothing is set to ask for its passphrase.
+var ErrSealed = errors.New("database is encrypted at rest and no passphrase can be asked for")
+
+// ErrInUse is returned when opening a database encrypted at rest that
+// another process has open.
+var ErrInUse = errors.New("database is in use by another mkrk")
+
+// Passphrase returns the passphrase of the database encrypted at rest at
+// path. It is asked each time one is opened; the CLI prompts on the
+// terminal or reads a keyfile.
+var Passphrase func(path string) (string, error)
+
+// atRest tracks a database that is, or is about to be, encrypted at rest.
+// Such a database is decrypted into memory when opened and written back
+// when closed, sealed again or, once unsealed, in plaintext. The lock held
+// beside it keeps another process from loading a second copy meanwhile,
+// since one of the two would be lost when both were written back.
+type atRest struct {
+	path string
+	// key and salt seal the database; both are nil once it is to be
+	// written back in plaintext.
+	key, salt []byte
+	lock      *os.File
+	// rewrite writes the database back even if nothing in it changed.
+	rewrite bool
+}
+
+// openDb opens the database at path, decrypting it into memory if it is
+// encrypted at rest.
+func openDb(path string) (*sql.DB, *atRest, error) {
+	sealed, err := crypt.IsSealed(path)
+	if err != nil {
+		return nil, nil, err
+	}
+	if !sealed {
+		d, err := sql.Open("sqlite", path)
+		return d, nil, err
+	}
+	if Passphrase == nil {
+		return nil, nil, ErrSealed
+	}
+	lock, err := lockBeside(path)
+	if err != nil {
+		return nil, nil, err
+	}
+	a := &atRest{path: path, lock: lock}
+	d, err := a.load()
+	if err != nil {
+		lock.Close()
+		return nil, nil, err
+	}
+	return d, a, nil
+}
+
+func (a *atRest) load() (*sql.DB, error) {
+	passphrase, err := Passphrase(a.path)
+	if err != nil {
+		return nil, err
+	}
+	f, err := os.Open(a.path)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+	key, salt, r, err := crypt.Unseal(f, passphrase)
+	if err != nil {
+		return nil, err
+	}
+	image, err := io.ReadAll(r)
+	if errors.Is(err, crypt.ErrAuth) {
+		return nil, fmt.Errorf("wrong passphrase for %s", a.path)
+	}
+	if err != nil {
+		return nil, err
+	}
+	a.key, a.salt = key, salt
+	return memoryDb(image)
+}
+
+// memoryDb opens a database held in memory with the content of image. The
+// pool is kept to the one connection holding it, since any other would
+// open an empty database of its own.
+func memoryDb(image []byte) (*sql.DB, error) {
+	// A database in memory cannot use a write-ahead log, so an image
+	// saved in WAL mode is marked as using a rollback journal instead.
+	if len(image) >= 20 && image[18] == 2 {
+		image[18], image[19] = 1, 1
+	}
+	d, err := sql.Open("sqlite", ":memory:")
+	if err != nil {
+		return nil, err
+	}
+	d.SetMaxOpenConns(1)
+	d.SetMaxIdleConns(1)
+	conn, err := d.Conn(context.Background())
+	if err != nil {
+		d.Close()
+		return nil, err
+	}
+	err = conn.Raw(func(driverConn any) error {
+		c, ok := driverConn.(interface{ Deserialize([]byte) error })
+		if !ok {
+			return errors.New("sqlite driver cannot load a database into memory")
+		}
+		return c.Deserialize(image)
+	})
+	conn.Close()
+	if err != nil {
+		d.Close()
+		return nil, fmt.Errorf("load database: %w", err)
+	}
+	return d, nil
+}
+
+// serialize returns the content of d's main database as a database file.
+func serialize(d *sql.DB) ([]byte, error) {
+	conn, err := d.Conn(context.Background())
+	if err != nil {
+		return nil, err
+	}
+	defer conn.Close()
+	var image []byte
+	err = conn.Raw(func(driverConn any) error {
+		c, ok := driverConn.(interface{ Serialize() ([]byte, error) })
+		if !ok {
+			return errors.New("sqlite driver cannot serialize a database")
+		}
+		image, err = c.Serialize()
+		return err
+	})
+	return image, err
+}
+
+// seal arranges for the open database at path to be written back
+// encrypted at rest under passphrase when closed. a is nil for a database
+// still in plaintext; for one already encrypted, the passphrase changes.
+func seal(a *atRest, path, passphrase string) (*atRest, error) {
+	if a == nil {
+		lock, err := lockBeside(path)
+		if err != nil {
+			return nil, err
+		}
+		a = &atRest{path: path, lock: lock}
+	}
+	salt, err := crypt.NewSalt()
+	if err != nil {
+		return nil, err
+	}
+	key, err := crypt.DeriveKey(passphrase, salt)
+	if err != nil {
+		return nil, err
+	}
+	a.key, a.salt, a.rewrite = key, salt, true
+	return a, nil
+}
+
+// unseal arranges for the database to be written back in plaintext.
+func (a *atRest) unseal() {
+	a.key, a.salt, a.rewrite = nil, nil, true
+}
+
+func (a *atRest) sealed() bool {
+	return a != nil && a.key != nil
+}
+
+// close closes d, first writing it back if it changed since it was
+// opened or is changing between sealed and plaintext.
+func (a *atRest) close(d *sql.DB) error {
+	defer a.lock.Close()
+	write := a.rewrite
+	if !write {
+		// One connection has made every change, so its count is the
+		// database's.
+		var changes int64
+		if err := d.QueryRow(`SELECT total_changes()`).Scan(&changes); err != nil {
+			d.Close()
+			return err
+		}
+		write = changes > 0
+	}
+	if !write {
+		return d.Close()
+	}
+
+	image, err := serialize(d)
+	if closeErr := d.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return fmt.Errorf("write back %s: %w", a.path, err)
+	}
+	err = writeReplacing(a.path, func(w io.Writer) error {
+		if a.key == nil {
+			_, err := w.Write(image)
+			return err
+		}
+		return crypt.Seal(w, bytes.NewReader(image), a.key, a.salt)
+	})
+	if err != nil {
+		return fmt.Errorf("write back %s: %w", a.path, err)
+	}
+	// A database sealed in place leaves its write-ahead log behind, and
+	// one unsealed no longer needs the lock.
+	os.Remove(a.path + "-wal")
+	os.Remove(a.path + "-shm")
+	if a.key == nil {
+		os.Remove(a.path + "-lock")
+	}
+	return nil
+}
+
+// discard closes d without writing it back.
+func discard(d *sql.DB, a *atRest) {
+	d.Close()
+	if a != nil {
+		a.lock.Close()
+	}
+}
+
+// writeReplacing replaces the file at path with what write produces, via
+// a temporary file beside it, keeping its permissions.
+func writeReplacing(path string, write func(io.Writer) error) error {
+	info, err := os.Stat(path)
+	if err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(path), filepath.Base(path)+"-write-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+	err = write(tmp)
+	if syncErr := tmp.Sync(); err == nil {
+		err = syncErr
+	}
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	if err := os.Chmod(tmp.Name(), info.Mode().Perm()); err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), path)
+}
@@ -0,0 +1,76 @@
+package db
+
+import (
+	"bytes"
+	"errors"
+	"os"
+	"path/filepath"
+	"testing"
+
+	"go.foia.dev/muckrake/internal/crypt"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestSealAtRest(t *testing.T) {
+	path := filepath.Join(t.TempDir(), ".mkrk")
+	pdb, err := CreateProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	pdb.InsertFile(&models.TrackedFile{SHA256: "aaa", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	pdb.InsertTag(1, "source/whistleblower", "aaa", "[]")
+	if err := pdb.SealAtRest("correct horse"); err != nil {
+		t.Fatal(err)
+	}
+	if err := pdb.Close(); err != nil {
+		t.Fatalf("close: %v", err)
+	}
+	data, _ := os.ReadFile(path)
+	if !bytes.HasPrefix(data, []byte(crypt.SealedMagic)) || bytes.Contains(data, []byte("whistleblower")) {
+		t.Fatal("expected the database sealed on disk")
+	}
+
+	defer func(prev func(string) (string, error)) { Passphrase = prev }(Passphrase)
+	Passphrase = nil
+	if _, err := OpenProject(path); !errors.Is(err, ErrSealed) {
+		t.Fatalf("expected ErrSealed without a passphrase, got %v", err)
+	}
+	Passphrase = func(string) (string, error) { return "wrong", nil }
+	if _, err := OpenProject(path); err == nil {
+		t.Fatal("expected a wrong passphrase to fail")
+	}
+
+	Passphrase = func(string) (string, error) { return "correct horse", nil }
+	pdb, err = OpenProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !pdb.SealedAtRest() {
+		t.Fatal("expected the opened database to report being sealed")
+	}
+	if tags, _ := pdb.GetTags(1); len(tags) != 1 || tags[0] != "source/whistleblower" {
+		t.Fatalf("expected the tag back, got %v", tags)
+	}
+	if _, err := OpenProject(path); !errors.Is(err, ErrInUse) {
+		t.Fatalf("expected a second open to be refused, got %v", err)
+	}
+	pdb.InsertTag(1, "reviewed", "aaa", "[]")
+	if err := pdb.UnsealAtRest(); err != nil {
+		t.Fatal(err)
+	}
+	if err := pdb.Close(); err != nil {
+		t.Fatalf("close: %v", err)
+	}
+
+	if sealed, _ := crypt.IsSealed(path); sealed {
+		t.Fatal("expected the database in plaintext after unsealing")
+	}
+	pdb, err = OpenProject(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer pdb.Close()
+	if tags, _ := pdb.GetTags(1); len(tags) != 2 {
+		t.Fatalf("expected changes made while sealed to be kept, got %v", tags)
+	}
+}
@@ -0,0 +1,93 @@
+package db
+
+import (
+	"database/sql"
+	"fmt"
+	"time"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// --- Trash ---
+
+const trashColumns = `id, file_id, trashed_at, user, reason, purged_at`
+
+// InsertTrash records that a tracked file was moved to the trash, returning
+// the tombstone's id.
+func (p *ProjectDb) InsertTrash(fileID int64, user, reason *string) (int64, error) {
+	res, err := p.db.Exec(
+		`INSERT INTO trash (file_id, trashed_at, user, reason) VALUES (?, ?, ?, ?)`,
+		fileID, time.Now().UTC().Format(time.RFC3339), user, reason,
+	)
+	if err != nil {
+		return 0, fmt.Errorf("insert trash: %w", err)
+	}
+	return res.LastInsertId()
+}
+
+func (p *ProjectDb) GetTrash(id int64) (*models.TrashEntry, error) {
+	return scanTrash(p.db.QueryRow(`SELECT `+trashColumns+` FROM trash WHERE id = ?`, id))
+}
+
+// ListTrash returns every tombstone, purged ones included, oldest first.
+func (p *ProjectDb) ListTrash() ([]models.TrashEntry, error) {
+	rows, err := p.db.Query(`SELECT ` + trashColumns + ` FROM trash ORDER BY id`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	var entries []models.TrashEntry
+	for rows.Next() {
+		t, err := scanTrash(rows)
+		if err != nil {
+			return nil, err
+		}
+		entries = append(entries, *t)
+	}
+	return entries, rows.Err()
+}
+
+// RemoveTrash drops the tombstone of a file restored from the trash.
+func (p *ProjectDb) RemoveTrash(id int64) error {
+	_, err := p.db.Exec(`DELETE FROM trash WHERE id = ?`, id)
+	return err
+}
+
+// MarkTrashPurged records that a trashed file was deleted for good.
+func (p *ProjectDb) MarkTrashPurged(id int64) error {
+	_, err := p.db.Exec(
+		`UPDATE trash SET purged_at = ? WHERE id = ?`, time.Now().UTC().Format(time.RFC3339), id,
+	)
+	return err
+}
+
+// TrashedFileIDs returns the ids of files with a tombstone, purged or not,
+// which are not expected on disk.
+func (p *ProjectDb) TrashedFileIDs() (map[int64]bool, error) {
+	rows, err := p.db.Query(`SELECT DISTINCT file_id FROM trash`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	ids := make(map[int64]bool)
+	for rows.Next() {
+		var id int64
+		if err := rows.Scan(&id); err != nil {
+			return nil, err
+		}
+		ids[id] = true
+	}
+	return ids, rows.Err()
+}
+
+func scanTrash(scanner fileScanner) (*models.TrashEntry, error) {
+	var t models.TrashEntry
+	err := scanner.Scan(&t.ID, &t.FileID, &t.TrashedAt, &t.User, &t.Reason, &t.PurgedAt)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &t, nil
+}
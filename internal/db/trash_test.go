@@ -0,0 +1,41 @@
+package db
+
+import (
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestTrash(t *testing.T) {
+	pdb := testDb(t)
+	fileID, _ := pdb.InsertFile(&models.TrackedFile{SHA256: "aaa", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	user, reason := "alice", "superseded"
+	id, err := pdb.InsertTrash(fileID, &user, &reason)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if trashed, _ := pdb.TrashedFileIDs(); !trashed[fileID] {
+		t.Fatal("expected the file listed as trashed")
+	}
+	entry, err := pdb.GetTrash(id)
+	if err != nil || entry == nil || *entry.Reason != reason || entry.PurgedAt != nil {
+		t.Fatalf("expected the tombstone back, got %+v (%v)", entry, err)
+	}
+
+	if err := pdb.MarkTrashPurged(id); err != nil {
+		t.Fatal(err)
+	}
+	if entry, _ := pdb.GetTrash(id); entry.PurgedAt == nil {
+		t.Fatal("expected the tombstone marked purged")
+	}
+	if err := pdb.RemoveTrash(id); err != nil {
+		t.Fatal(err)
+	}
+	if entries, _ := pdb.ListTrash(); len(entries) != 0 {
+		t.Fatalf("expected no tombstones left, got %+v", entries)
+	}
+	if entry, err := pdb.GetTrash(id); entry != nil || err != nil {
+		t.Fatalf("expected nil for a missing tombstone, got %+v (%v)", entry, err)
+	}
+}
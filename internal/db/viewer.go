@@ -0,0 +1,96 @@
+package db
+
+import (
+	"fmt"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// --- Open Viewers ---
+
+// SetOpenViewer registers a viewer, replacing any existing one for the
+// same scope and mime type.
+func (p *ProjectDb) SetOpenViewer(v *models.OpenViewer) (int64, error) {
+	if _, err := p.RemoveOpenViewer(v.ScopeID, v.MimeType); err != nil {
+		return 0, err
+	}
+	res, err := p.db.Exec(
+		`INSERT INTO open_config (scope_id, mime_type, command, read_only) VALUES (?, ?, ?, ?)`,
+		v.ScopeID, v.MimeType, v.Command, v.ReadOnly,
+	)
+	if err != nil {
+		return 0, fmt.Errorf("insert viewer: %w", err)
+	}
+	return res.LastInsertId()
+}
+
+// RemoveOpenViewer removes the viewer for a scope (nil for project-wide)
+// and mime type. Returns the number removed.
+func (p *ProjectDb) RemoveOpenViewer(scopeID *int64, mimeType string) (int64, error) {
+	res, err := p.db.Exec(
+		`DELETE FROM open_config WHERE scope_id IS ? AND mime_type = ?`, scopeID, mimeType,
+	)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+func (p *ProjectDb) ListOpenViewers() ([]models.OpenViewer, error) {
+	rows, err := p.db.Query(
+		`SELECT id, scope_id, mime_type, command, read_only FROM open_config
+		 ORDER BY scope_id IS NOT NULL, scope_id, mime_type`,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var viewers []models.OpenViewer
+	for rows.Next() {
+		var v models.OpenViewer
+		var id int64
+		if err := rows.Scan(&id, &v.ScopeID, &v.MimeType, &v.Command, &v.ReadOnly); err != nil {
+			return nil, err
+		}
+		v.ID = &id
+		viewers = append(viewers, v)
+	}
+	return viewers, rows.Err()
+}
+
+// ResolveOpenViewer picks the viewer for a file with the given mime type
+// in the given scopes. Viewers bound to one of the scopes win over
+// project-wide ones; within each, the most specific mime pattern wins.
+// Returns nil when no viewer matches.
+func (p *ProjectDb) ResolveOpenViewer(scopeIDs []int64, mimeType string) (*models.OpenViewer, error) {
+	viewers, err := p.ListOpenViewers()
+	if err != nil {
+		return nil, err
+	}
+	inScope := make(map[int64]bool)
+	for _, id := range scopeIDs {
+		inScope[id] = true
+	}
+
+	var best *models.OpenViewer
+	bestRank := -1
+	for i := range viewers {
+		v := &viewers[i]
+		spec := models.MimeSpecificity(v.MimeType, mimeType)
+		if spec < 0 {
+			continue
+		}
+		rank := spec
+		if v.ScopeID != nil {
+			if !inScope[*v.ScopeID] {
+				continue
+			}
+			rank += 3
+		}
+		if rank > bestRank {
+			best, bestRank = v, rank
+		}
+	}
+	return best, nil
+}
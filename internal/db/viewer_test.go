@@ -0,0 +1,41 @@
+package db
+
+import (
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestResolveOpenViewer(t *testing.T) {
+	db := testDb(t)
+	evidence, _ := db.InsertScope(makeCategory("evidence", "evidence/**"))
+
+	db.SetOpenViewer(&models.OpenViewer{MimeType: "*", Command: "less"})
+	db.SetOpenViewer(&models.OpenViewer{MimeType: "application/pdf", Command: "evince"})
+	db.SetOpenViewer(&models.OpenViewer{ScopeID: &evidence, MimeType: "application/*", Command: "pdf-ro", ReadOnly: true})
+
+	v, err := db.ResolveOpenViewer(nil, "application/pdf")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if v == nil || v.Command != "evince" {
+		t.Fatalf("expected exact global match, got %+v", v)
+	}
+
+	v, _ = db.ResolveOpenViewer([]int64{evidence}, "application/pdf")
+	if v == nil || v.Command != "pdf-ro" || !v.ReadOnly {
+		t.Fatalf("expected scoped viewer to win, got %+v", v)
+	}
+
+	v, _ = db.ResolveOpenViewer(nil, "text/plain")
+	if v == nil || v.Command != "less" {
+		t.Fatalf("expected wildcard fallback, got %+v", v)
+	}
+
+	// Setting again replaces rather than duplicating.
+	db.SetOpenViewer(&models.OpenViewer{MimeType: "application/pdf", Command: "zathura"})
+	all, _ := db.ListOpenViewers()
+	if len(all) != 3 {
+		t.Fatalf("expected 3 viewers after replace, got %d", len(all))
+	}
+}
@@ -13,10 +13,14 @@ import (
 
 // WorkspaceDb wraps a connection to a .mksp workspace database.
 type WorkspaceDb struct {
-	db *sql.DB
+	db   *sql.DB
+	path string
+	// rest is set while the database is encrypted at rest.
+	rest *atRest
 }
 
-// ProjectRow represents a registered project in the workspace.
+// ProjectRow represents a project, or nested workspace, registered in the
+// workspace.
 type ProjectRow struct {
 	ID          int64
 	Name        string
@@ -39,39 +43,75 @@ func CreateWorkspace(path string) (*WorkspaceDb, error) {
 		db.Close()
 		return nil, fmt.Errorf("create workspace schema: %w", err)
 	}
-	return &WorkspaceDb{db: db}, nil
+	if err := stampMigrations(db, WorkspaceMigrations); err != nil {
+		db.Close()
+		return nil, err
+	}
+	return &WorkspaceDb{db: db, path: path}, nil
 }
 
 // OpenWorkspace opens an existing workspace database.
-// Runs schema with IF NOT EXISTS to add any new tables from newer versions.
+// Runs schema with IF NOT EXISTS to add any new tables from newer versions,
+// then any migrations it has not recorded.
 func OpenWorkspace(path string) (*WorkspaceDb, error) {
 	if _, err := os.Stat(path); err != nil {
 		return nil, fmt.Errorf("workspace database not found: %s", path)
 	}
-	db, err := sql.Open("sqlite", path)
+	db, rest, err := openDb(path)
 	if err != nil {
 		return nil, fmt.Errorf("open workspace db: %w", err)
 	}
 	if err := configureConn(db); err != nil {
-		db.Close()
+		discard(db, rest)
 		return nil, err
 	}
 	if _, err := db.Exec(WorkspaceSchema); err != nil {
-		db.Close()
+		discard(db, rest)
 		return nil, fmt.Errorf("migrate workspace schema: %w", err)
 	}
-	if err := MigrateWorkspace(db); err != nil {
-		db.Close()
-		return nil, fmt.Errorf("migrate workspace data: %w", err)
+	if err := runMigrations(db, WorkspaceMigrations); err != nil {
+		discard(db, rest)
+		return nil, fmt.Errorf("migrate workspace db: %w", err)
 	}
-	return &WorkspaceDb{db: db}, nil
+	return &WorkspaceDb{db: db, path: path, rest: rest}, nil
 }
 
-// Close closes the database connection.
+// Close closes the database connection, first writing the database back
+// if it is encrypted at rest.
 func (w *WorkspaceDb) Close() error {
+	if w.rest != nil {
+		return w.rest.close(w.db)
+	}
 	return w.db.Close()
 }
 
+// SealAtRest keeps the database encrypted at rest under passphrase; see
+// ProjectDb.SealAtRest.
+func (w *WorkspaceDb) SealAtRest(passphrase string) error {
+	rest, err := seal(w.rest, w.path, passphrase)
+	if err != nil {
+		return err
+	}
+	w.rest = rest
+	return nil
+}
+
+// UnsealAtRest writes a database encrypted at rest back in plaintext when
+// it is closed.
+func (w *WorkspaceDb) UnsealAtRest() error {
+	if !w.rest.sealed() {
+		return fmt.Errorf("database is not encrypted at rest")
+	}
+	w.rest.unseal()
+	return nil
+}
+
+// SealedAtRest reports whether the database will be written back
+// encrypted at rest.
+func (w *WorkspaceDb) SealedAtRest() bool {
+	return w.rest.sealed()
+}
+
 // DB returns the underlying sql.DB.
 func (w *WorkspaceDb) DB() *sql.DB {
 	return w.db
@@ -100,6 +140,247 @@ func (w *WorkspaceDb) SetConfig(key, value string) error {
 	return err
 }
 
+// --- Audit ---
+
+// InsertAudit records a workspace-level operation, such as inbox triage,
+// that happens outside any single project. Entries are chained like the
+// project audit log.
+func (w *WorkspaceDb) InsertAudit(operation string, projectName, user, detail *string) error {
+	now := time.Now().UTC().Format(time.RFC3339)
+	lastID, prev, err := auditHead(w.db)
+	if err != nil {
+		return err
+	}
+	id := lastID + 1
+	hash := auditHash(prev, id, now, operation, projectName, user, detail)
+	_, err = w.db.Exec(
+		`INSERT INTO audit_log (id, timestamp, operation, project_name, user, detail, prev_hash, hash)
+		 VALUES (?, ?, ?, ?, ?, ?, ?, ?)`,
+		id, now, operation, projectName, user, detail, prev, hash,
+	)
+	return err
+}
+
+// VerifyAuditChain checks the workspace audit log's hash chain, as
+// ProjectDb.VerifyAuditChain does for a project.
+func (w *WorkspaceDb) VerifyAuditChain() (count int64, head string, breaks []AuditBreak, err error) {
+	return verifyAuditChain(w.db, "project_name", new(*string))
+}
+
+// --- Archive ---
+
+// ArchivedProject records a frozen project and the category policies it
+// had before archival, so unarchive can restore them.
+type ArchivedProject struct {
+	ProjectName   string
+	ArchivedAt    string
+	PriorPolicies string
+	ArchivePath   *string
+}
+
+func (w *WorkspaceDb) ArchiveProject(a *ArchivedProject) error {
+	_, err := w.db.Exec(
+		`INSERT INTO archived_projects (project_name, archived_at, prior_policies, archive_path)
+		 VALUES (?, ?, ?, ?)`,
+		a.ProjectName, a.ArchivedAt, a.PriorPolicies, a.ArchivePath,
+	)
+	if err != nil {
+		return fmt.Errorf("archive project: %w", err)
+	}
+	return nil
+}
+
+func (w *WorkspaceDb) GetArchivedProject(name string) (*ArchivedProject, error) {
+	var a ArchivedProject
+	err := w.db.QueryRow(
+		`SELECT project_name, archived_at, prior_policies, archive_path
+		 FROM archived_projects WHERE project_name = ?`, name,
+	).Scan(&a.ProjectName, &a.ArchivedAt, &a.PriorPolicies, &a.ArchivePath)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &a, nil
+}
+
+func (w *WorkspaceDb) UnarchiveProject(name string) (int64, error) {
+	res, err := w.db.Exec(`DELETE FROM archived_projects WHERE project_name = ?`, name)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+// ArchivedProjectNames returns the set of archived project names.
+func (w *WorkspaceDb) ArchivedProjectNames() (map[string]bool, error) {
+	rows, err := w.db.Query(`SELECT project_name FROM archived_projects`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	names := make(map[string]bool)
+	for rows.Next() {
+		var n string
+		if err := rows.Scan(&n); err != nil {
+			return nil, err
+		}
+		names[n] = true
+	}
+	return names, rows.Err()
+}
+
+// --- Tools ---
+
+// WorkspaceTool is a tool script installed in the workspace's tools
+// directory, with the hash it had when added. File is its name within
+// that directory.
+type WorkspaceTool struct {
+	Name        string
+	File        string
+	SHA256      string
+	Description *string
+	AddedAt     string
+}
+
+// SetTool registers a workspace tool, replacing any with the same name.
+func (w *WorkspaceDb) SetTool(t *WorkspaceTool) error {
+	_, err := w.db.Exec(
+		`INSERT INTO workspace_tools (name, file, sha256, description, added_at) VALUES (?, ?, ?, ?, ?)
+		 ON CONFLICT(name) DO UPDATE SET file = excluded.file, sha256 = excluded.sha256,
+		   description = excluded.description, added_at = excluded.added_at`,
+		t.Name, t.File, t.SHA256, t.Description, t.AddedAt,
+	)
+	if err != nil {
+		return fmt.Errorf("set tool: %w", err)
+	}
+	return nil
+}
+
+func (w *WorkspaceDb) GetTool(name string) (*WorkspaceTool, error) {
+	var t WorkspaceTool
+	err := w.db.QueryRow(
+		`SELECT name, file, sha256, description, added_at FROM workspace_tools WHERE name = ?`, name,
+	).Scan(&t.Name, &t.File, &t.SHA256, &t.Description, &t.AddedAt)
+	if err == sql.ErrNoRows {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &t, nil
+}
+
+func (w *WorkspaceDb) ListTools() ([]WorkspaceTool, error) {
+	rows, err := w.db.Query(`SELECT name, file, sha256, description, added_at FROM workspace_tools ORDER BY name`)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+
+	var tools []WorkspaceTool
+	for rows.Next() {
+		var t WorkspaceTool
+		if err := rows.Scan(&t.Name, &t.File, &t.SHA256, &t.Description, &t.AddedAt); err != nil {
+			return nil, err
+		}
+		tools = append(tools, t)
+	}
+	return tools, rows.Err()
+}
+
+func (w *WorkspaceDb) RemoveTool(name string) (int64, error) {
+	res, err := w.db.Exec(`DELETE FROM workspace_tools WHERE name = ?`, name)
+	if err != nil {
+		return 0, err
+	}
+	return res.RowsAffected()
+}
+
+// --- File index ---
+
+// IndexedFile is a project's file as the workspace's file index records
+// it. Its path within the project is kept only as a PathKey.
+type IndexedFile struct {
+	PathHash string
+	SHA256   string
+	MimeType *string
+	Size     *int64
+}
+
+// IndexFiles records files, keyed by their paths within project, in the
+// file index, replacing what was indexed at those paths.
+func (w *WorkspaceDb) IndexFiles(project string, files map[string]*models.TrackedFile) error {
+	tx, err := w.db.Begin()
+	if err != nil {
+		return err
+	}
+	defer tx.Rollback()
+	stmt, err := tx.Prepare(
+		`INSERT OR REPLACE INTO file_index (project_name, path_hash, sha256, mime_type, size)
+		 VALUES (?, ?, ?, ?, ?)`,
+	)
+	if err != nil {
+		return err
+	}
+	defer stmt.Close()
+	for path, f := range files {
+		if _, err := stmt.Exec(project, PathKey(path), f.SHA256, f.MimeType, f.Size); err != nil {
+			return fmt.Errorf("index file: %w", err)
+		}
+	}
+	return tx.Commit()
+}
+
+// PruneIndex removes project's entries in the file index for every path
+// but those given.
+func (w *WorkspaceDb) PruneIndex(project string, paths []string) error {
+	keep := make(map[string]bool, len(paths))
+	for _, path := range paths {
+		keep[PathKey(path)] = true
+	}
+	indexed, err := w.IndexedFiles(project)
+	if err != nil {
+		return err
+	}
+	tx, err := w.db.Begin()
+	if err != nil {
+		return err
+	}
+	defer tx.Rollback()
+	for key := range indexed {
+		if keep[key] {
+			continue
+		}
+		if _, err := tx.Exec(`DELETE FROM file_index WHERE project_name = ? AND path_hash = ?`, project, key); err != nil {
+			return err
+		}
+	}
+	return tx.Commit()
+}
+
+// IndexedFiles returns project's entries in the file index keyed by path
+// hash.
+func (w *WorkspaceDb) IndexedFiles(project string) (map[string]IndexedFile, error) {
+	rows, err := w.db.Query(
+		`SELECT path_hash, sha256, mime_type, size FROM file_index WHERE project_name = ?`, project,
+	)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	files := make(map[string]IndexedFile)
+	for rows.Next() {
+		var f IndexedFile
+		if err := rows.Scan(&f.PathHash, &f.SHA256, &f.MimeType, &f.Size); err != nil {
+			return nil, err
+		}
+		files[f.PathHash] = f
+	}
+	return files, rows.Err()
+}
+
 // --- Projects ---
 
 func (w *WorkspaceDb) RegisterProject(name, path string, description *string) (int64, error) {
@@ -119,9 +400,45 @@ func (w *WorkspaceDb) RegisterProject(name, path string, description *string) (i
 }
 
 func (w *WorkspaceDb) ListProjects() ([]ProjectRow, error) {
+	return w.listScopeRows("project")
+}
+
+func (w *WorkspaceDb) GetProjectByName(name string) (*ProjectRow, error) {
+	return w.getScopeRow("project", name)
+}
+
+// RegisterWorkspace records a workspace nested inside this one, at path
+// relative to its root. Its projects are reached as :name/project.
+func (w *WorkspaceDb) RegisterWorkspace(name, path string) (int64, error) {
+	if err := models.ValidateScopeName(name); err != nil {
+		return 0, err
+	}
+	now := time.Now().UTC().Format(time.RFC3339)
+	res, err := w.db.Exec(
+		`INSERT INTO scopes (name, scope_type, pattern, created_at)
+		 VALUES (?, 'workspace', ?, ?)`,
+		name, path, now,
+	)
+	if err != nil {
+		return 0, fmt.Errorf("register workspace: %w", err)
+	}
+	return res.LastInsertId()
+}
+
+// ListSubWorkspaces returns the workspaces nested directly inside this one.
+func (w *WorkspaceDb) ListSubWorkspaces() ([]ProjectRow, error) {
+	return w.listScopeRows("workspace")
+}
+
+func (w *WorkspaceDb) GetSubWorkspaceByName(name string) (*ProjectRow, error) {
+	return w.getScopeRow("workspace", name)
+}
+
+// listScopeRows returns the projects or nested workspaces registered here.
+func (w *WorkspaceDb) listScopeRows(scopeType string) ([]ProjectRow, error) {
 	rows, err := w.db.Query(
 		`SELECT id, name, COALESCE(pattern, ''), description, COALESCE(created_at, '')
-		 FROM scopes WHERE scope_type = 'project' ORDER BY name`,
+		 FROM scopes WHERE scope_type = ? ORDER BY name`, scopeType,
 	)
 	if err != nil {
 		return nil, err
@@ -139,11 +456,11 @@ func (w *WorkspaceDb) ListProjects() ([]ProjectRow, error) {
 	return projects, rows.Err()
 }
 
-func (w *WorkspaceDb) GetProjectByName(name string) (*ProjectRow, error) {
+func (w *WorkspaceDb) getScopeRow(scopeType, name string) (*ProjectRow, error) {
 	var p ProjectRow
 	err := w.db.QueryRow(
 		`SELECT id, name, COALESCE(pattern, ''), description, COALESCE(created_at, '')
-		 FROM scopes WHERE scope_type = 'project' AND name = ?`, name,
+		 FROM scopes WHERE scope_type = ? AND name = ?`, scopeType, name,
 	).Scan(&p.ID, &p.Name, &p.Path, &p.Description, &p.CreatedAt)
 	if err == sql.ErrNoRows {
 		return nil, nil
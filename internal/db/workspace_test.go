@@ -0,0 +1,49 @@
+package db
+
+import (
+	"path/filepath"
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestFileIndex(t *testing.T) {
+	wdb, err := CreateWorkspace(filepath.Join(t.TempDir(), ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer wdb.Close()
+
+	size := int64(42)
+	err = wdb.IndexFiles("alpha", map[string]*models.TrackedFile{
+		"evidence/a.pdf": {SHA256: "aaa", Size: &size},
+		"evidence/b.pdf": {SHA256: "bbb"},
+	})
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.IndexFiles("beta", map[string]*models.TrackedFile{"a.pdf": {SHA256: "aaa"}})
+
+	indexed, err := wdb.IndexedFiles("alpha")
+	if err != nil {
+		t.Fatal(err)
+	}
+	a, ok := indexed[PathKey("evidence/a.pdf")]
+	if len(indexed) != 2 || !ok || a.SHA256 != "aaa" || a.Size == nil || *a.Size != 42 {
+		t.Fatalf("expected alpha's two files by path hash, got %+v", indexed)
+	}
+
+	if err := wdb.IndexFiles("alpha", map[string]*models.TrackedFile{"evidence/a.pdf": {SHA256: "ccc"}}); err != nil {
+		t.Fatal(err)
+	}
+	if err := wdb.PruneIndex("alpha", []string{"evidence/a.pdf"}); err != nil {
+		t.Fatal(err)
+	}
+	indexed, _ = wdb.IndexedFiles("alpha")
+	if len(indexed) != 1 || indexed[PathKey("evidence/a.pdf")].SHA256 != "ccc" {
+		t.Fatalf("expected only the re-indexed file left, got %+v", indexed)
+	}
+	if beta, _ := wdb.IndexedFiles("beta"); len(beta) != 1 {
+		t.Fatalf("expected beta's index untouched, got %+v", beta)
+	}
+}
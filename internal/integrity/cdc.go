@@ -0,0 +1,124 @@
+package integrity
+
+import (
+	"fmt"
+	"io"
+	"strings"
+)
+
+// Chunking is how a fingerprint divides a file into chunks. Like the hash
+// algorithm, it is chosen when a project is created.
+type Chunking string
+
+const (
+	// FixedChunks cuts every 64 KiB. An insertion shifts every later
+	// chunk, so a diff reports the whole tail as changed.
+	FixedChunks Chunking = "fixed"
+	// ContentDefinedChunks cuts where the content says to (FastCDC), so
+	// chunk boundaries move with an insertion or deletion and a diff
+	// covers only the chunks around the edit.
+	ContentDefinedChunks Chunking = "cdc"
+)
+
+// Chunkings lists the supported chunkings.
+var Chunkings = []Chunking{FixedChunks, ContentDefinedChunks}
+
+// ParseChunking returns the chunking named s.
+func ParseChunking(s string) (Chunking, error) {
+	for _, c := range Chunkings {
+		if string(c) == s {
+			return c, nil
+		}
+	}
+	names := make([]string, len(Chunkings))
+	for i, c := range Chunkings {
+		names[i] = string(c)
+	}
+	return "", fmt.Errorf("unknown chunking '%s' (expected %s)", s, strings.Join(names, ", "))
+}
+
+// Content-defined chunks average 64 KiB, like fixed ones, and are never
+// shorter than cdcMin or longer than cdcMax.
+const (
+	cdcMin = 16 * 1024
+	cdcAvg = 64 * 1024
+	cdcMax = 256 * 1024
+)
+
+// The rolling hash cuts where its top bits are zero: more of them before
+// the average size is reached, fewer after, which keeps chunk sizes close
+// to the average (FastCDC's normalized chunking).
+const (
+	cdcMaskSmall = ^uint64(0) << (64 - 18)
+	cdcMaskLarge = ^uint64(0) << (64 - 14)
+)
+
+// gear is the rolling hash's byte table. It is derived from a fixed seed
+// and must never change: every stored content-defined fingerprint depends
+// on it.
+var gear = func() [256]uint64 {
+	var t [256]uint64
+	state := uint64(0x6d6b726b63646331) // "mkrkcdc1"
+	for i := range t {
+		// splitmix64
+		state += 0x9e3779b97f4a7c15
+		z := state
+		z = (z ^ (z >> 30)) * 0xbf58476d1ce4e5b9
+		z = (z ^ (z >> 27)) * 0x94d049bb133111eb
+		t[i] = z ^ (z >> 31)
+	}
+	return t
+}()
+
+// cdcCut returns the length of the first content-defined chunk of data.
+func cdcCut(data []byte) int {
+	n := len(data)
+	if n <= cdcMin {
+		return n
+	}
+	n = min(n, cdcMax)
+	normal := min(n, cdcAvg)
+
+	var fp uint64
+	i := cdcMin
+	for ; i < normal; i++ {
+		fp = fp<<1 + gear[data[i]]
+		if fp&cdcMaskSmall == 0 {
+			return i + 1
+		}
+	}
+	for ; i < n; i++ {
+		fp = fp<<1 + gear[data[i]]
+		if fp&cdcMaskLarge == 0 {
+			return i + 1
+		}
+	}
+	return n
+}
+
+// cdcChunks reads r to the end, calling emit with each content-defined
+// chunk in order. The slice emit gets is only valid until it returns.
+func cdcChunks(r io.Reader, emit func([]byte)) error {
+	buf := make([]byte, cdcMax)
+	start, end := 0, 0
+	eof := false
+	for {
+		if !eof && end-start < cdcMax {
+			end = copy(buf, buf[start:end])
+			start = 0
+			n, err := io.ReadFull(r, buf[end:])
+			end += n
+			if err == io.EOF || err == io.ErrUnexpectedEOF {
+				eof = true
+			} else if err != nil {
+				return err
+			}
+		}
+		if start == end {
+			return nil
+		}
+		n := cdcCut(buf[start:end])
+		emit(buf[start : start+n])
+		start += n
+	}
+}
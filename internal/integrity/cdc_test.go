@@ -0,0 +1,112 @@
+package integrity
+
+import (
+	"bytes"
+	"math/rand"
+	"os"
+	"path/filepath"
+	"slices"
+	"testing"
+)
+
+func randomContent(n int) []byte {
+	b := make([]byte, n)
+	rand.New(rand.NewSource(1)).Read(b)
+	return b
+}
+
+func fingerprintCDC(t *testing.T, content []byte) *Fingerprint {
+	t.Helper()
+	_, fp, err := Hasher{Algorithm: SHA256, Chunking: ContentDefinedChunks}.HashAndFingerprintReader(bytes.NewReader(content))
+	if err != nil {
+		t.Fatal(err)
+	}
+	return fp
+}
+
+func TestCDCChunkSizes(t *testing.T) {
+	content := randomContent(2 << 20)
+	fp := fingerprintCDC(t, content)
+
+	var total int64
+	for i, size := range fp.Sizes {
+		if size > cdcMax || (size < cdcMin && i != len(fp.Sizes)-1) {
+			t.Fatalf("chunk %d is %d bytes, outside [%d, %d]", i, size, cdcMin, cdcMax)
+		}
+		total += size
+	}
+	if total != int64(len(content)) {
+		t.Fatalf("chunks cover %d bytes, want %d", total, len(content))
+	}
+	if n := len(fp.Chunks); n < 8 || n > 128 {
+		t.Fatalf("expected chunks near the %d byte average, got %d chunks", cdcAvg, n)
+	}
+	if !slices.Equal(fp.Chunks, fingerprintCDC(t, content).Chunks) {
+		t.Fatal("chunking not deterministic")
+	}
+}
+
+func TestCDCInsertionStaysLocal(t *testing.T) {
+	content := randomContent(1 << 20)
+	edited := slices.Concat(content[:300_000], []byte("inserted text"), content[300_000:])
+
+	diffs := DiffFingerprints(fingerprintCDC(t, content), fingerprintCDC(t, edited))
+	if len(diffs) == 0 || len(diffs) > 6 {
+		t.Fatalf("expected a few chunks around the insertion, got %+v", diffs)
+	}
+	for _, d := range diffs {
+		if d.Offset+d.Size < 300_000-cdcMax || d.Offset > 300_000+2*cdcMax {
+			t.Fatalf("chunk %+v is far from the insertion at 300000", d)
+		}
+	}
+
+	fixed := DiffFingerprints(mustFingerprint(t, content), mustFingerprint(t, edited))
+	if len(fixed) < 10 {
+		t.Fatalf("expected fixed chunks to shift after the insertion, got %d diffs", len(fixed))
+	}
+}
+
+func mustFingerprint(t *testing.T, content []byte) *Fingerprint {
+	t.Helper()
+	_, fp, err := SHA256.HashAndFingerprintReader(bytes.NewReader(content))
+	if err != nil {
+		t.Fatal(err)
+	}
+	return fp
+}
+
+func TestCDCFingerprintJSON(t *testing.T) {
+	fp := fingerprintCDC(t, randomContent(300_000))
+	fp2, err := FingerprintFromJSON(fp.ToJSON())
+	if err != nil {
+		t.Fatal(err)
+	}
+	if fp2.Chunking != ContentDefinedChunks || !slices.Equal(fp.Chunks, fp2.Chunks) || !slices.Equal(fp.Sizes, fp2.Sizes) {
+		t.Fatalf("round trip failed: %+v", fp2)
+	}
+	if fp.MatchingChunks(fp2) != len(fp.Chunks) {
+		t.Fatal("expected every chunk to match itself")
+	}
+	if fp.MatchingChunks(mustFingerprint(t, randomContent(300_000))) != 0 {
+		t.Fatal("fingerprints cut differently should share no chunks")
+	}
+}
+
+func TestHasherFingerprintMatchesHashAndFingerprint(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "data.bin")
+	if err := os.WriteFile(path, randomContent(500_000), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	h := Hasher{Algorithm: BLAKE3, Chunking: ContentDefinedChunks}
+	_, combined, err := h.HashAndFingerprint(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	alone, err := h.Fingerprint(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if combined.ToJSON() != alone.ToJSON() {
+		t.Fatal("fingerprint differs between single and combined")
+	}
+}
@@ -0,0 +1,12 @@
+//go:build !linux
+
+package integrity
+
+import "errors"
+
+// Clone creates dst as a reflink of src: a copy that shares src's blocks
+// until either is written. It fails where the filesystem cannot share
+// them, leaving no dst behind.
+func Clone(src, dst string) error {
+	return errors.ErrUnsupported
+}
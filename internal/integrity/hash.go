@@ -2,11 +2,14 @@ package integrity
 
 import (
 	"crypto/sha256"
+	"crypto/sha512"
 	"encoding/hex"
 	"encoding/json"
 	"fmt"
+	"hash"
 	"io"
 	"os"
+	"strings"
 
 	"lukechampine.com/blake3"
 )
@@ -14,19 +17,106 @@ import (
 const chunkSize = 64 * 1024 // 64KB chunks for fingerprinting
 const hashOutputSize = 8    // 8 bytes per chunk hash → 16 hex chars
 
-// Fingerprint is a list of BLAKE3 chunk hashes for fast file identification.
+// Algorithm is the hash a project identifies file content by. It is chosen
+// when the project is created; every hash the project records uses it.
+type Algorithm string
+
+const (
+	SHA256 Algorithm = "sha256"
+	SHA512 Algorithm = "sha512"
+	// BLAKE3 is several times faster than SHA-256 on large files.
+	BLAKE3 Algorithm = "blake3"
+)
+
+// Algorithms lists the supported hash algorithms.
+var Algorithms = []Algorithm{SHA256, SHA512, BLAKE3}
+
+// ParseAlgorithm returns the algorithm named s.
+func ParseAlgorithm(s string) (Algorithm, error) {
+	for _, a := range Algorithms {
+		if string(a) == s {
+			return a, nil
+		}
+	}
+	names := make([]string, len(Algorithms))
+	for i, a := range Algorithms {
+		names[i] = string(a)
+	}
+	return "", fmt.Errorf("unknown hash algorithm '%s' (expected %s)", s, strings.Join(names, ", "))
+}
+
+func (a Algorithm) new() hash.Hash {
+	switch a {
+	case SHA512:
+		return sha512.New()
+	case BLAKE3:
+		return blake3.New(32, nil)
+	}
+	return sha256.New()
+}
+
+// Fingerprint is a list of BLAKE3 chunk hashes for fast file identification,
+// and the root of a Merkle tree over the same chunks, which proves any one
+// of them belongs to the file (see ChunkProof).
 type Fingerprint struct {
-	Chunks []string
+	// Chunking is how the file was cut; empty means FixedChunks.
+	Chunking Chunking
+	Chunks   []string
+	// Sizes holds the length of each content-defined chunk. Fixed chunks
+	// are all chunkSize long, so they have none.
+	Sizes []int64
+	// Root is the hex Merkle root. Fingerprints recorded before roots
+	// were have none until sync updates them.
+	Root string
+}
+
+// storedFingerprint is the JSON form of a fingerprint with a Merkle root
+// or content-defined chunks. Older fingerprints are a bare array of chunk
+// hashes.
+type storedFingerprint struct {
+	Chunking Chunking `json:"chunking,omitempty"`
+	Chunks   []string `json:"chunks"`
+	Sizes    []int64  `json:"sizes,omitempty"`
+	Root     string   `json:"root,omitempty"`
 }
 
-// ToJSON serializes the fingerprint to a JSON array string.
+func (fp *Fingerprint) contentDefined() bool {
+	return fp.Chunking == ContentDefinedChunks
+}
+
+// ToJSON serializes the fingerprint: an object with the chunk hashes, the
+// chunk sizes for content-defined chunks, and the Merkle root, or a bare
+// array of chunk hashes for a fixed-chunk fingerprint without a root.
 func (fp *Fingerprint) ToJSON() string {
-	b, _ := json.Marshal(fp.Chunks)
+	var b []byte
+	if fp.contentDefined() || fp.Root != "" {
+		chunks := fp.Chunks
+		if chunks == nil {
+			chunks = []string{}
+		}
+		b, _ = json.Marshal(storedFingerprint{fp.Chunking, chunks, fp.Sizes, fp.Root})
+	} else {
+		b, _ = json.Marshal(fp.Chunks)
+	}
 	return string(b)
 }
 
-// FromJSON deserializes a fingerprint from a JSON array string.
+// FromJSON deserializes a fingerprint written by ToJSON.
 func FingerprintFromJSON(s string) (*Fingerprint, error) {
+	if strings.HasPrefix(strings.TrimSpace(s), "{") {
+		var stored storedFingerprint
+		if err := json.Unmarshal([]byte(s), &stored); err != nil {
+			return nil, fmt.Errorf("parse fingerprint: %w", err)
+		}
+		fp := &Fingerprint{Chunking: stored.Chunking, Chunks: stored.Chunks, Sizes: stored.Sizes, Root: stored.Root}
+		switch {
+		case fp.Chunking == FixedChunks:
+			fp.Chunking = ""
+		case fp.Chunking != ContentDefinedChunks || len(fp.Sizes) != len(fp.Chunks):
+			return nil, fmt.Errorf("parse fingerprint: malformed %s fingerprint", fp.Chunking)
+		}
+		return fp, nil
+	}
 	var chunks []string
 	if err := json.Unmarshal([]byte(s), &chunks); err != nil {
 		return nil, fmt.Errorf("parse fingerprint: %w", err)
@@ -34,8 +124,48 @@ func FingerprintFromJSON(s string) (*Fingerprint, error) {
 	return &Fingerprint{Chunks: chunks}, nil
 }
 
-// MatchingChunks counts how many chunks at the same index match.
+// ChunkAt returns the index of the chunk holding byte offset, or -1 past
+// the end of the file.
+func (fp *Fingerprint) ChunkAt(offset int64) int {
+	if offset < 0 {
+		return -1
+	}
+	if !fp.contentDefined() {
+		if i := int(offset / chunkSize); i < len(fp.Chunks) {
+			return i
+		}
+		return -1
+	}
+	var end int64
+	for i, size := range fp.Sizes {
+		end += size
+		if offset < end {
+			return i
+		}
+	}
+	return -1
+}
+
+// MatchingChunks counts how many chunks match: at the same index for fixed
+// chunks, anywhere in the file for content-defined ones, whose positions
+// shift with an insertion. Fingerprints cut differently share none.
 func (fp *Fingerprint) MatchingChunks(other *Fingerprint) int {
+	if fp.contentDefined() != other.contentDefined() {
+		return 0
+	}
+	if fp.contentDefined() {
+		have := make(map[string]bool, len(other.Chunks))
+		for _, c := range other.Chunks {
+			have[c] = true
+		}
+		count := 0
+		for _, c := range fp.Chunks {
+			if have[c] {
+				count++
+			}
+		}
+		return count
+	}
 	n := len(fp.Chunks)
 	if len(other.Chunks) < n {
 		n = len(other.Chunks)
@@ -51,75 +181,152 @@ func (fp *Fingerprint) MatchingChunks(other *Fingerprint) int {
 
 // HashFile computes the SHA-256 hash of a file, returned as a hex string.
 func HashFile(path string) (string, error) {
+	return SHA256.HashFile(path)
+}
+
+// HashFile computes a's hash of a file, returned as a hex string.
+func (a Algorithm) HashFile(path string) (string, error) {
 	f, err := os.Open(path)
 	if err != nil {
 		return "", fmt.Errorf("hash file: %w", err)
 	}
 	defer f.Close()
 
-	h := sha256.New()
+	h := a.new()
 	if _, err := io.Copy(h, f); err != nil {
 		return "", fmt.Errorf("hash file: %w", err)
 	}
 	return hex.EncodeToString(h.Sum(nil)), nil
 }
 
-// FingerprintFile computes a BLAKE3 chunk fingerprint of a file.
+// FingerprintFile computes a BLAKE3 chunk fingerprint of a file, in fixed
+// chunks.
 func FingerprintFile(path string) (*Fingerprint, error) {
+	return Hasher{Chunking: FixedChunks}.Fingerprint(path)
+}
+
+// HashAndFingerprint computes both SHA-256 and BLAKE3 fingerprint in a single
+// read pass.
+func HashAndFingerprint(path string) (string, *Fingerprint, error) {
+	return SHA256.HashAndFingerprint(path)
+}
+
+// HashAndFingerprint computes both a's hash and the BLAKE3 fingerprint, in
+// fixed chunks, in a single read pass.
+func (a Algorithm) HashAndFingerprint(path string) (string, *Fingerprint, error) {
+	return Hasher{Algorithm: a, Chunking: FixedChunks}.HashAndFingerprint(path)
+}
+
+// HashAndFingerprintReader is HashAndFingerprint over arbitrary content,
+// such as the target of a symlink tracked as a link.
+func HashAndFingerprintReader(r io.Reader) (string, *Fingerprint, error) {
+	return SHA256.HashAndFingerprintReader(r)
+}
+
+// HashAndFingerprintReader is a's HashAndFingerprint over arbitrary content.
+func (a Algorithm) HashAndFingerprintReader(r io.Reader) (string, *Fingerprint, error) {
+	return Hasher{Algorithm: a, Chunking: FixedChunks}.HashAndFingerprintReader(r)
+}
+
+// Hasher hashes and fingerprints files the way one project does.
+type Hasher struct {
+	Algorithm Algorithm
+	Chunking  Chunking
+}
+
+// HashFile computes the hasher's hash of a file.
+func (h Hasher) HashFile(path string) (string, error) {
+	return h.Algorithm.HashFile(path)
+}
+
+// Fingerprint computes the fingerprint of a file, cut by the hasher's
+// chunking.
+func (h Hasher) Fingerprint(path string) (*Fingerprint, error) {
 	f, err := os.Open(path)
 	if err != nil {
 		return nil, fmt.Errorf("fingerprint file: %w", err)
 	}
 	defer f.Close()
 
-	var chunks []string
-	buf := make([]byte, chunkSize)
-
-	for {
-		n, err := io.ReadFull(f, buf)
-		if n > 0 {
-			h := blake3.Sum256(buf[:n])
-			chunks = append(chunks, hex.EncodeToString(h[:hashOutputSize]))
-		}
-		if err == io.EOF || err == io.ErrUnexpectedEOF {
-			break
-		}
-		if err != nil {
-			return nil, fmt.Errorf("fingerprint file: %w", err)
-		}
+	fpr := newFingerprinter(h.Chunking)
+	if err := eachChunk(f, h.Chunking, fpr.add); err != nil {
+		return nil, fmt.Errorf("fingerprint file: %w", err)
 	}
-
-	return &Fingerprint{Chunks: chunks}, nil
+	return fpr.finish(), nil
 }
 
-// HashAndFingerprint computes both SHA-256 and BLAKE3 fingerprint in a single
-// read pass.
-func HashAndFingerprint(path string) (string, *Fingerprint, error) {
+// HashAndFingerprint computes both the hash and the fingerprint of a file
+// in a single read pass.
+func (h Hasher) HashAndFingerprint(path string) (string, *Fingerprint, error) {
 	f, err := os.Open(path)
 	if err != nil {
 		return "", nil, fmt.Errorf("hash and fingerprint: %w", err)
 	}
 	defer f.Close()
+	return h.HashAndFingerprintReader(f)
+}
 
-	sha := sha256.New()
-	var chunks []string
-	buf := make([]byte, chunkSize)
+// HashAndFingerprintReader is HashAndFingerprint over arbitrary content.
+func (h Hasher) HashAndFingerprintReader(r io.Reader) (string, *Fingerprint, error) {
+	sha := h.Algorithm.new()
+	fpr := newFingerprinter(h.Chunking)
+	err := eachChunk(r, h.Chunking, func(chunk []byte) {
+		sha.Write(chunk)
+		fpr.add(chunk)
+	})
+	if err != nil {
+		return "", nil, fmt.Errorf("hash and fingerprint: %w", err)
+	}
+	return hex.EncodeToString(sha.Sum(nil)), fpr.finish(), nil
+}
 
+// eachChunk reads r to the end, calling emit with each chunk in order. The
+// slice emit gets is only valid until it returns.
+func eachChunk(r io.Reader, chunking Chunking, emit func([]byte)) error {
+	if chunking == ContentDefinedChunks {
+		return cdcChunks(r, emit)
+	}
+	buf := make([]byte, chunkSize)
 	for {
-		n, err := io.ReadFull(f, buf)
+		n, err := io.ReadFull(r, buf)
 		if n > 0 {
-			sha.Write(buf[:n])
-			h := blake3.Sum256(buf[:n])
-			chunks = append(chunks, hex.EncodeToString(h[:hashOutputSize]))
+			emit(buf[:n])
 		}
 		if err == io.EOF || err == io.ErrUnexpectedEOF {
-			break
+			return nil
 		}
 		if err != nil {
-			return "", nil, fmt.Errorf("hash and fingerprint: %w", err)
+			return err
 		}
 	}
+}
+
+// fingerprinter builds a fingerprint one chunk at a time.
+type fingerprinter struct {
+	fp     *Fingerprint
+	leaves [][32]byte
+}
+
+func newFingerprinter(chunking Chunking) *fingerprinter {
+	fp := &Fingerprint{Chunks: []string{}}
+	if chunking == ContentDefinedChunks {
+		fp.Chunking = ContentDefinedChunks
+		fp.Sizes = []int64{}
+	}
+	return &fingerprinter{fp: fp}
+}
+
+func (f *fingerprinter) add(chunk []byte) {
+	h := blake3.Sum256(chunk)
+	f.fp.Chunks = append(f.fp.Chunks, hex.EncodeToString(h[:hashOutputSize]))
+	if f.fp.contentDefined() {
+		f.fp.Sizes = append(f.fp.Sizes, int64(len(chunk)))
+	}
+	f.leaves = append(f.leaves, merkleLeaf(chunk))
+}
 
-	hash := hex.EncodeToString(sha.Sum(nil))
-	return hash, &Fingerprint{Chunks: chunks}, nil
+func (f *fingerprinter) finish() *Fingerprint {
+	root := merkleRoot(f.leaves)
+	f.fp.Root = hex.EncodeToString(root[:])
+	return f.fp
 }
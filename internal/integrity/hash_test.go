@@ -34,6 +34,33 @@ func TestHashFile(t *testing.T) {
 	}
 }
 
+func TestAlgorithmHashFile(t *testing.T) {
+	path := writeTestFile(t, "hello world")
+	lengths := map[Algorithm]int{SHA256: 64, SHA512: 128, BLAKE3: 64}
+	seen := make(map[string]Algorithm)
+	for _, alg := range Algorithms {
+		hash, err := alg.HashFile(path)
+		if err != nil {
+			t.Fatal(err)
+		}
+		if len(hash) != lengths[alg] {
+			t.Fatalf("%s: expected %d char hex, got %d", alg, lengths[alg], len(hash))
+		}
+		if other, ok := seen[hash]; ok {
+			t.Fatalf("%s and %s gave the same hash", alg, other)
+		}
+		seen[hash] = alg
+	}
+
+	sha, _ := HashFile(path)
+	if sha != "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9" {
+		t.Fatalf("HashFile should stay SHA-256, got %s", sha)
+	}
+	if _, err := ParseAlgorithm("md5"); err == nil {
+		t.Fatal("expected md5 to be refused")
+	}
+}
+
 func TestFingerprintFile(t *testing.T) {
 	path := writeTestFile(t, "hello world")
 	fp, err := FingerprintFile(path)
@@ -93,6 +120,22 @@ func TestMatchingChunks(t *testing.T) {
 	}
 }
 
+func TestDiffFingerprints(t *testing.T) {
+	a := &Fingerprint{Chunks: []string{"aaa", "bbb", "ccc"}}
+	b := &Fingerprint{Chunks: []string{"aaa", "xxx", "ccc", "ddd"}}
+
+	diffs := DiffFingerprints(a, b)
+	if len(diffs) != 2 {
+		t.Fatalf("expected 2 diffs, got %v", diffs)
+	}
+	if diffs[0].Index != 1 || diffs[0].Offset != chunkSize {
+		t.Fatalf("expected chunk 1 at %d, got %+v", chunkSize, diffs[0])
+	}
+	if diffs[1].Index != 3 {
+		t.Fatalf("expected trailing chunk 3, got %+v", diffs[1])
+	}
+}
+
 func TestVerifyFileOk(t *testing.T) {
 	path := writeTestFile(t, "test content")
 	hash, _ := HashFile(path)
@@ -0,0 +1,6 @@
+package integrity
+
+// FileKey identifies the inode behind a path, shared by all of its hardlinks.
+type FileKey struct {
+	Dev, Ino uint64
+}
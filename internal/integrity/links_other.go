@@ -0,0 +1,11 @@
+//go:build !unix
+
+package integrity
+
+import "os"
+
+// LinkInfo returns the inode key and hardlink count of a file. ok is false
+// when the platform does not expose them.
+func LinkInfo(info os.FileInfo) (key FileKey, nlink uint64, ok bool) {
+	return FileKey{}, 0, false
+}
@@ -0,0 +1,18 @@
+//go:build unix
+
+package integrity
+
+import (
+	"os"
+	"syscall"
+)
+
+// LinkInfo returns the inode key and hardlink count of a file. ok is false
+// when the platform does not expose them.
+func LinkInfo(info os.FileInfo) (key FileKey, nlink uint64, ok bool) {
+	st, isStat := info.Sys().(*syscall.Stat_t)
+	if !isStat {
+		return FileKey{}, 0, false
+	}
+	return FileKey{Dev: uint64(st.Dev), Ino: uint64(st.Ino)}, uint64(st.Nlink), true
+}
@@ -0,0 +1,164 @@
+package integrity
+
+import (
+	"bytes"
+	"encoding/hex"
+	"fmt"
+	"os"
+
+	"lukechampine.com/blake3"
+)
+
+// A fingerprint's Merkle tree is built over full BLAKE3 hashes of its
+// chunks the way Certificate Transparency builds its logs (RFC 9162):
+// leaves and interior nodes are hashed with distinct prefixes, and a tree
+// of n leaves splits at the largest power of two below n. A chunk and the
+// hashes along its path to the root prove it belongs to the file without
+// revealing the rest of it.
+
+const (
+	merkleLeafPrefix = 0x00
+	merkleNodePrefix = 0x01
+)
+
+func merkleLeaf(chunk []byte) [32]byte {
+	h := blake3.New(32, nil)
+	h.Write([]byte{merkleLeafPrefix})
+	h.Write(chunk)
+	var sum [32]byte
+	h.Sum(sum[:0])
+	return sum
+}
+
+func merkleNode(left, right [32]byte) [32]byte {
+	var buf [65]byte
+	buf[0] = merkleNodePrefix
+	copy(buf[1:], left[:])
+	copy(buf[33:], right[:])
+	return blake3.Sum256(buf[:])
+}
+
+// merkleSplit returns the largest power of two less than n, for n > 1.
+func merkleSplit(n int) int {
+	k := 1
+	for k<<1 < n {
+		k <<= 1
+	}
+	return k
+}
+
+// merkleRoot returns the root over leaves. An empty file's root is the
+// hash of an empty leaf.
+func merkleRoot(leaves [][32]byte) [32]byte {
+	switch len(leaves) {
+	case 0:
+		return merkleLeaf(nil)
+	case 1:
+		return leaves[0]
+	}
+	k := merkleSplit(len(leaves))
+	return merkleNode(merkleRoot(leaves[:k]), merkleRoot(leaves[k:]))
+}
+
+// merklePath returns the sibling hashes from leaf index up to the root.
+func merklePath(leaves [][32]byte, index int) [][32]byte {
+	if len(leaves) <= 1 {
+		return nil
+	}
+	k := merkleSplit(len(leaves))
+	if index < k {
+		return append(merklePath(leaves[:k], index), merkleRoot(leaves[k:]))
+	}
+	return append(merklePath(leaves[k:], index-k), merkleRoot(leaves[:k]))
+}
+
+// ChunkProof shows that one chunk of a file, the excerpt, belongs to the
+// file whose fingerprint has Root. It carries no other content.
+type ChunkProof struct {
+	Root   string   `json:"root"`
+	Leaves int      `json:"leaves"`
+	Index  int      `json:"index"`
+	Offset int64    `json:"offset"`
+	Size   int64    `json:"size"`
+	Path   []string `json:"path"`
+}
+
+// ProveChunk rereads a file, cut by the hasher's chunking, and returns the
+// inclusion proof for chunk index along with the chunk's bytes.
+func (h Hasher) ProveChunk(path string, index int) (*ChunkProof, []byte, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, nil, fmt.Errorf("prove chunk: %w", err)
+	}
+	defer f.Close()
+
+	var leaves [][32]byte
+	var excerpt []byte
+	var offset, at int64
+	err = eachChunk(f, h.Chunking, func(chunk []byte) {
+		if len(leaves) == index {
+			excerpt = bytes.Clone(chunk)
+			offset = at
+		}
+		leaves = append(leaves, merkleLeaf(chunk))
+		at += int64(len(chunk))
+	})
+	if err != nil {
+		return nil, nil, fmt.Errorf("prove chunk: %w", err)
+	}
+	if index < 0 || index >= len(leaves) {
+		return nil, nil, fmt.Errorf("no chunk %d (the file has %d)", index, len(leaves))
+	}
+
+	root := merkleRoot(leaves)
+	proof := &ChunkProof{
+		Root:   hex.EncodeToString(root[:]),
+		Leaves: len(leaves),
+		Index:  index,
+		Offset: offset,
+		Size:   int64(len(excerpt)),
+	}
+	for _, sibling := range merklePath(leaves, index) {
+		proof.Path = append(proof.Path, hex.EncodeToString(sibling[:]))
+	}
+	return proof, excerpt, nil
+}
+
+// Verify checks that excerpt is the chunk the proof was made for, by
+// hashing it up the proof's path and comparing the result to its root
+// (RFC 9162, section 2.1.3.2).
+func (p *ChunkProof) Verify(excerpt []byte) error {
+	if int64(len(excerpt)) != p.Size {
+		return fmt.Errorf("excerpt is %d bytes, the proven chunk %d", len(excerpt), p.Size)
+	}
+	if p.Index < 0 || p.Index >= p.Leaves {
+		return fmt.Errorf("chunk %d is outside a tree of %d", p.Index, p.Leaves)
+	}
+	r := merkleLeaf(excerpt)
+	fn, sn := p.Index, p.Leaves-1
+	for _, h := range p.Path {
+		raw, err := hex.DecodeString(h)
+		if err != nil || len(raw) != 32 {
+			return fmt.Errorf("malformed proof hash %q", h)
+		}
+		if sn == 0 {
+			return fmt.Errorf("proof path is too long for a tree of %d", p.Leaves)
+		}
+		sibling := [32]byte(raw)
+		if fn&1 == 1 || fn == sn {
+			r = merkleNode(sibling, r)
+			for fn&1 == 0 && fn != 0 {
+				fn >>= 1
+				sn >>= 1
+			}
+		} else {
+			r = merkleNode(r, sibling)
+		}
+		fn >>= 1
+		sn >>= 1
+	}
+	if sn != 0 || hex.EncodeToString(r[:]) != p.Root {
+		return fmt.Errorf("excerpt does not belong to the file with root %s", p.Root)
+	}
+	return nil
+}
@@ -0,0 +1,86 @@
+package integrity
+
+import (
+	"bytes"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+func TestChunkProofs(t *testing.T) {
+	for _, size := range []int{1, chunkSize, 3*chunkSize + 17, 9 * chunkSize} {
+		content := randomContent(size)
+		path := filepath.Join(t.TempDir(), "data.bin")
+		if err := os.WriteFile(path, content, 0o644); err != nil {
+			t.Fatal(err)
+		}
+		h := Hasher{Algorithm: SHA256, Chunking: FixedChunks}
+		fp, err := h.Fingerprint(path)
+		if err != nil {
+			t.Fatal(err)
+		}
+
+		for i := range fp.Chunks {
+			proof, excerpt, err := h.ProveChunk(path, i)
+			if err != nil {
+				t.Fatal(err)
+			}
+			if proof.Root != fp.Root || proof.Leaves != len(fp.Chunks) {
+				t.Fatalf("size %d chunk %d: proof for %s over %d leaves, fingerprint %s over %d",
+					size, i, proof.Root, proof.Leaves, fp.Root, len(fp.Chunks))
+			}
+			if !bytes.Equal(excerpt, content[proof.Offset:proof.Offset+proof.Size]) {
+				t.Fatalf("size %d chunk %d: excerpt is not the file's bytes at %d", size, i, proof.Offset)
+			}
+			if err := proof.Verify(excerpt); err != nil {
+				t.Fatalf("size %d chunk %d: %v", size, i, err)
+			}
+
+			tampered := bytes.Clone(excerpt)
+			tampered[0] ^= 1
+			if proof.Verify(tampered) == nil {
+				t.Fatalf("size %d chunk %d: tampered excerpt verified", size, i)
+			}
+			if len(fp.Chunks) > 1 {
+				moved := *proof
+				moved.Index = (i + 1) % len(fp.Chunks)
+				if moved.Verify(excerpt) == nil {
+					t.Fatalf("size %d chunk %d: excerpt verified at the wrong index", size, i)
+				}
+			}
+		}
+	}
+}
+
+func TestFingerprintRootJSON(t *testing.T) {
+	fp := mustFingerprint(t, randomContent(200_000))
+	if len(fp.Root) != 64 {
+		t.Fatalf("expected a 64 char hex root, got %q", fp.Root)
+	}
+	fp2, err := FingerprintFromJSON(fp.ToJSON())
+	if err != nil {
+		t.Fatal(err)
+	}
+	if fp2.Root != fp.Root || fp2.Chunking != "" || len(fp2.Chunks) != len(fp.Chunks) {
+		t.Fatalf("round trip failed: %+v", fp2)
+	}
+
+	legacy, err := FingerprintFromJSON(`["aaa","bbb"]`)
+	if err != nil || legacy.Root != "" || len(legacy.Chunks) != 2 {
+		t.Fatalf("expected legacy array fingerprint, got %+v (%v)", legacy, err)
+	}
+}
+
+func TestChunkAt(t *testing.T) {
+	fixed := &Fingerprint{Chunks: []string{"a", "b", "c"}}
+	if i := fixed.ChunkAt(chunkSize + 5); i != 1 {
+		t.Fatalf("expected chunk 1, got %d", i)
+	}
+	if i := fixed.ChunkAt(3 * chunkSize); i != -1 {
+		t.Fatalf("expected -1 past the end, got %d", i)
+	}
+	cdc := &Fingerprint{Chunking: ContentDefinedChunks, Chunks: []string{"a", "b"}, Sizes: []int64{10, 20}}
+	if i := cdc.ChunkAt(10); i != 1 {
+		t.Fatalf("expected chunk 1, got %d", i)
+	}
+}
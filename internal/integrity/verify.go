@@ -16,11 +16,16 @@ const (
 
 // VerifyFile checks if a file's SHA-256 hash matches the expected value.
 func VerifyFile(path, expectedHash string) (VerifyResult, string, error) {
+	return SHA256.VerifyFile(path, expectedHash)
+}
+
+// VerifyFile checks if a file's hash under a matches the expected value.
+func (a Algorithm) VerifyFile(path, expectedHash string) (VerifyResult, string, error) {
 	if _, err := os.Stat(path); os.IsNotExist(err) {
 		return VerifyMissing, "", nil
 	}
 
-	actual, err := HashFile(path)
+	actual, err := a.HashFile(path)
 	if err != nil {
 		return 0, "", fmt.Errorf("verify file: %w", err)
 	}
@@ -33,22 +38,42 @@ func VerifyFile(path, expectedHash string) (VerifyResult, string, error) {
 
 // ChunkDiff describes a changed chunk in a fingerprint comparison.
 type ChunkDiff struct {
-	Index  int
-	Offset int64
-	Size   int64
+	Index  int   `json:"index"`
+	Offset int64 `json:"offset"`
+	Size   int64 `json:"size"`
+	// Removed marks a content-defined chunk of the expected file found
+	// nowhere in the actual one; Index and Offset are in the expected file.
+	Removed bool `json:"removed,omitempty"`
 }
 
 // VerifyFingerprint checks if a file's fingerprint matches the expected value
-// and returns which chunks changed.
+// and returns which chunks changed. The file is cut the way expected was.
 func VerifyFingerprint(path string, expected *Fingerprint) ([]ChunkDiff, error) {
 	if _, err := os.Stat(path); os.IsNotExist(err) {
 		return nil, nil
 	}
 
-	actual, err := FingerprintFile(path)
+	chunking := FixedChunks
+	if expected.contentDefined() {
+		chunking = ContentDefinedChunks
+	}
+	actual, err := Hasher{Chunking: chunking}.Fingerprint(path)
 	if err != nil {
 		return nil, err
 	}
+	return DiffFingerprints(expected, actual), nil
+}
+
+// DiffFingerprints returns the chunks that differ between two fingerprints,
+// including chunks present in only one of them. Fixed chunks are compared
+// index by index. Content-defined chunks are compared as sets, so an
+// insertion reports only the chunks it touched: those of actual not in
+// expected, then those of expected not in actual, marked Removed.
+func DiffFingerprints(expected, actual *Fingerprint) []ChunkDiff {
+	if expected.contentDefined() && actual.contentDefined() {
+		diffs := unmatchedChunks(actual, expected, false)
+		return append(diffs, unmatchedChunks(expected, actual, true)...)
+	}
 
 	n := len(expected.Chunks)
 	if len(actual.Chunks) > n {
@@ -72,6 +97,23 @@ func VerifyFingerprint(path string, expected *Fingerprint) ([]ChunkDiff, error)
 			})
 		}
 	}
+	return diffs
+}
 
-	return diffs, nil
+// unmatchedChunks returns the content-defined chunks of fp that other does
+// not have, located within fp.
+func unmatchedChunks(fp, other *Fingerprint, removed bool) []ChunkDiff {
+	have := make(map[string]bool, len(other.Chunks))
+	for _, c := range other.Chunks {
+		have[c] = true
+	}
+	var diffs []ChunkDiff
+	var offset int64
+	for i, c := range fp.Chunks {
+		if !have[c] {
+			diffs = append(diffs, ChunkDiff{Index: i, Offset: offset, Size: fp.Sizes[i], Removed: removed})
+		}
+		offset += fp.Sizes[i]
+	}
+	return diffs
 }
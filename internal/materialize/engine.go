@@ -9,21 +9,29 @@ import (
 // MaterializeForFile checks all pipeline and ruleset subscriptions against
 // a file's known metadata and creates per-hash materialized records.
 func MaterializeForFile(pdb *db.ProjectDb, relPath, sha256 string, matchingCats []models.Scope, tags []string) {
-	materializePipelines(pdb, relPath, sha256, matchingCats, tags)
-	materializeRulesets(pdb, relPath, sha256, matchingCats, tags)
+	file, _ := pdb.GetFileByHash(sha256)
+	var attrs map[string]string
+	if file != nil && file.ID != nil {
+		attrs, _ = pdb.GetFileAttrs(*file.ID)
+	}
+	materializePipelines(pdb, relPath, sha256, file, attrs, matchingCats, tags)
+	materializeRulesets(pdb, relPath, sha256, file, attrs, matchingCats, tags)
 }
 
-func materializePipelines(pdb *db.ProjectDb, relPath, sha256 string, cats []models.Scope, tags []string) {
+func materializePipelines(pdb *db.ProjectDb, relPath, sha256 string, file *models.TrackedFile, attrs map[string]string, cats []models.Scope, tags []string) {
 	subs, err := pdb.ListAllPipelineSubscriptions()
 	if err != nil {
 		return
 	}
 	for _, ps := range subs {
 		ref, err := reference.ParseReference(ps.Sub.Reference)
+		if err == nil {
+			ref, err = reference.ExpandSaved(ref, pdb)
+		}
 		if err != nil {
 			continue
 		}
-		if matchesReference(ref, relPath, cats, tags) {
+		if matchesReference(ref, relPath, file, attrs, cats, tags) {
 			subID := int64(0)
 			if ps.Sub.ID != nil {
 				subID = *ps.Sub.ID
@@ -33,17 +41,20 @@ func materializePipelines(pdb *db.ProjectDb, relPath, sha256 string, cats []mode
 	}
 }
 
-func materializeRulesets(pdb *db.ProjectDb, relPath, sha256 string, cats []models.Scope, tags []string) {
+func materializeRulesets(pdb *db.ProjectDb, relPath, sha256 string, file *models.TrackedFile, attrs map[string]string, cats []models.Scope, tags []string) {
 	subs, err := pdb.ListAllRulesetSubscriptions()
 	if err != nil {
 		return
 	}
 	for _, rs := range subs {
 		ref, err := reference.ParseReference(rs.Sub.Reference)
+		if err == nil {
+			ref, err = reference.ExpandSaved(ref, pdb)
+		}
 		if err != nil {
 			continue
 		}
-		if matchesReference(ref, relPath, cats, tags) {
+		if matchesReference(ref, relPath, file, attrs, cats, tags) {
 			subID := int64(0)
 			if rs.Sub.ID != nil {
 				subID = *rs.Sub.ID
@@ -53,13 +64,15 @@ func materializeRulesets(pdb *db.ProjectDb, relPath, sha256 string, cats []model
 	}
 }
 
-func matchesReference(ref *reference.Reference, relPath string, cats []models.Scope, tags []string) bool {
+func matchesReference(ref *reference.Reference, relPath string, file *models.TrackedFile, attrs map[string]string, cats []models.Scope, tags []string) bool {
 	if ref.Kind == reference.KindBarePath {
 		return false
 	}
 	return matchesScope(ref.Scope, cats) &&
 		matchesTags(ref.Tags, tags) &&
-		matchesGlob(ref.Glob, relPath)
+		reference.PredicatesMatch(ref.Predicates, file, attrs) &&
+		matchesGlob(ref.Glob, relPath) &&
+		!reference.Excluded(ref.Exclude, relPath[lastSlash(relPath)+1:])
 }
 
 func matchesScope(scope []reference.ScopeLevel, cats []models.Scope) bool {
@@ -82,19 +95,7 @@ func matchesTags(filters []reference.TagFilter, fileTags []string) bool {
 		if len(filter.Tags) == 0 {
 			continue
 		}
-		groupMatch := false
-		for _, t := range filter.Tags {
-			for _, ft := range fileTags {
-				if t == ft {
-					groupMatch = true
-					break
-				}
-			}
-			if groupMatch {
-				break
-			}
-		}
-		if !groupMatch {
+		if !filter.Matches(fileTags) {
 			return false
 		}
 	}
@@ -0,0 +1,207 @@
+package metadata
+
+import (
+	"bytes"
+	"encoding/binary"
+	"fmt"
+	"strings"
+)
+
+// jpegExif returns the TIFF structure inside a JPEG's Exif segment.
+func jpegExif(data []byte) []byte {
+	i := 2
+	for i+4 <= len(data) {
+		if data[i] != 0xFF {
+			return nil
+		}
+		marker := data[i+1]
+		if marker == 0xD9 || marker == 0xDA { // end of image, start of scan
+			return nil
+		}
+		size := int(binary.BigEndian.Uint16(data[i+2:]))
+		if size < 2 || i+2+size > len(data) {
+			return nil
+		}
+		segment := data[i+4 : i+2+size]
+		if marker == 0xE1 && bytes.HasPrefix(segment, []byte("Exif\x00\x00")) {
+			return segment[6:]
+		}
+		i += 2 + size
+	}
+	return nil
+}
+
+// tiffReader reads IFD entries from a TIFF structure.
+type tiffReader struct {
+	data  []byte
+	order binary.ByteOrder
+}
+
+type ifdEntry struct {
+	typ   uint16
+	count uint32
+	value []byte
+}
+
+// Sizes of the TIFF field types, by type number.
+var tiffTypeSize = map[uint16]int{1: 1, 2: 1, 3: 2, 4: 4, 5: 8, 7: 1, 9: 4, 10: 8}
+
+func (r *tiffReader) ifd(offset uint32) map[uint16]ifdEntry {
+	entries := make(map[uint16]ifdEntry)
+	if int(offset)+2 > len(r.data) {
+		return entries
+	}
+	n := int(r.order.Uint16(r.data[offset:]))
+	for i := 0; i < n; i++ {
+		at := int(offset) + 2 + 12*i
+		if at+12 > len(r.data) {
+			break
+		}
+		tag := r.order.Uint16(r.data[at:])
+		typ := r.order.Uint16(r.data[at+2:])
+		count := r.order.Uint32(r.data[at+4:])
+		size, ok := tiffTypeSize[typ]
+		if !ok || count > 1<<20 {
+			continue
+		}
+		total := size * int(count)
+		value := r.data[at+8 : at+12]
+		if total > 4 {
+			off := int(r.order.Uint32(r.data[at+8:]))
+			if off < 0 || off+total > len(r.data) {
+				continue
+			}
+			value = r.data[off : off+total]
+		}
+		entries[tag] = ifdEntry{typ: typ, count: count, value: value[:min(total, len(value))]}
+	}
+	return entries
+}
+
+func (r *tiffReader) str(e ifdEntry) string {
+	if e.typ != 2 {
+		return ""
+	}
+	return string(bytes.TrimRight(e.value, "\x00 "))
+}
+
+func (r *tiffReader) uint(e ifdEntry) (uint32, bool) {
+	switch {
+	case e.typ == 3 && len(e.value) >= 2:
+		return uint32(r.order.Uint16(e.value)), true
+	case e.typ == 4 && len(e.value) >= 4:
+		return r.order.Uint32(e.value), true
+	}
+	return 0, false
+}
+
+func (r *tiffReader) rationals(e ifdEntry) []float64 {
+	if e.typ != 5 && e.typ != 10 {
+		return nil
+	}
+	var out []float64
+	for i := 0; i+8 <= len(e.value); i += 8 {
+		num, den := r.order.Uint32(e.value[i:]), r.order.Uint32(e.value[i+4:])
+		if den == 0 {
+			return nil
+		}
+		if e.typ == 10 {
+			out = append(out, float64(int32(num))/float64(int32(den)))
+		} else {
+			out = append(out, float64(num)/float64(den))
+		}
+	}
+	return out
+}
+
+// EXIF tags read from the main, Exif and GPS IFDs.
+const (
+	tagDescription    = 0x010E
+	tagMake           = 0x010F
+	tagModel          = 0x0110
+	tagSoftware       = 0x0131
+	tagDateTime       = 0x0132
+	tagArtist         = 0x013B
+	tagCopyright      = 0x8298
+	tagExifIFD        = 0x8769
+	tagGPSIFD         = 0x8825
+	tagDateOriginal   = 0x9003
+	tagSerialNumber   = 0xA431
+	tagLensModel      = 0xA434
+	tagGPSLatRef      = 0x0001
+	tagGPSLat         = 0x0002
+	tagGPSLonRef      = 0x0003
+	tagGPSLon         = 0x0004
+	tagGPSAltitudeRef = 0x0005
+	tagGPSAltitude    = 0x0006
+)
+
+// exif reads camera, date, author and location fields from a TIFF
+// structure, as found in JPEG Exif segments and TIFF files.
+func exif(data []byte, out map[string]string) {
+	if len(data) < 8 {
+		return
+	}
+	r := &tiffReader{data: data}
+	switch string(data[:2]) {
+	case "II":
+		r.order = binary.LittleEndian
+	case "MM":
+		r.order = binary.BigEndian
+	default:
+		return
+	}
+	ifd0 := r.ifd(r.order.Uint32(data[4:]))
+
+	maker, model := r.str(ifd0[tagMake]), r.str(ifd0[tagModel])
+	if maker != "" && strings.HasPrefix(model, maker) {
+		maker = ""
+	}
+	out["camera"] = strings.TrimSpace(maker + " " + model)
+	out["software"] = r.str(ifd0[tagSoftware])
+	out["author"] = r.str(ifd0[tagArtist])
+	out["copyright"] = r.str(ifd0[tagCopyright])
+	out["description"] = r.str(ifd0[tagDescription])
+	out["modified"] = exifDate(r.str(ifd0[tagDateTime]))
+
+	if off, ok := r.uint(ifd0[tagExifIFD]); ok {
+		sub := r.ifd(off)
+		out["created"] = exifDate(r.str(sub[tagDateOriginal]))
+		out["camera_serial"] = r.str(sub[tagSerialNumber])
+		out["lens"] = r.str(sub[tagLensModel])
+	}
+	if off, ok := r.uint(ifd0[tagGPSIFD]); ok {
+		gps := r.ifd(off)
+		lat := dms(r.rationals(gps[tagGPSLat]), r.str(gps[tagGPSLatRef]))
+		lon := dms(r.rationals(gps[tagGPSLon]), r.str(gps[tagGPSLonRef]))
+		if lat != nil && lon != nil {
+			out["gps"] = fmt.Sprintf("%.6f, %.6f", *lat, *lon)
+		}
+		if alt := r.rationals(gps[tagGPSAltitude]); len(alt) == 1 {
+			if ref := gps[tagGPSAltitudeRef]; len(ref.value) > 0 && ref.value[0] == 1 {
+				alt[0] = -alt[0]
+			}
+			out["altitude"] = fmt.Sprintf("%.1f m", alt[0])
+		}
+	}
+}
+
+// dms converts degrees, minutes and seconds to signed decimal degrees.
+func dms(parts []float64, ref string) *float64 {
+	if len(parts) != 3 {
+		return nil
+	}
+	deg := parts[0] + parts[1]/60 + parts[2]/3600
+	if ref == "S" || ref == "W" {
+		deg = -deg
+	}
+	return &deg
+}
+
+// exifDate turns EXIF's "2006:01:02 15:04:05" into "2006-01-02T15:04:05".
+func exifDate(s string) string {
+	if len(s) < 19 || s[4] != ':' || s[7] != ':' {
+		return s
+	}
+	return s[:4] + "-" + s[5:7] + "-" + s[8:10] + "T" + s[11:19]
+}
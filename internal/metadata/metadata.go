@@ -0,0 +1,73 @@
+// Package metadata extracts the metadata files carry inside their content:
+// EXIF fields of photos, the document information of PDFs, and the
+// properties of Office documents. Keys are shared across formats where
+// they mean the same thing, such as author, created and modified.
+package metadata
+
+import (
+	"bytes"
+	"io"
+	"os"
+	"strings"
+)
+
+// maxScan bounds how much of a file is read looking for metadata.
+const maxScan = 64 << 20
+
+// Extract returns the metadata embedded in the file at path, or an empty
+// map for formats it does not know. Malformed metadata is skipped rather
+// than reported.
+func Extract(path string) (map[string]string, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+
+	head := make([]byte, 8)
+	n, _ := io.ReadFull(f, head)
+	head = head[:n]
+	if _, err := f.Seek(0, io.SeekStart); err != nil {
+		return nil, err
+	}
+
+	out := make(map[string]string)
+	switch {
+	case bytes.HasPrefix(head, []byte("\xFF\xD8\xFF")):
+		data, err := io.ReadAll(io.LimitReader(f, maxScan))
+		if err != nil {
+			return nil, err
+		}
+		if tiff := jpegExif(data); tiff != nil {
+			exif(tiff, out)
+		}
+	case bytes.HasPrefix(head, []byte("II*\x00")) || bytes.HasPrefix(head, []byte("MM\x00*")):
+		data, err := io.ReadAll(io.LimitReader(f, maxScan))
+		if err != nil {
+			return nil, err
+		}
+		exif(data, out)
+	case bytes.HasPrefix(head, []byte("%PDF-")):
+		data, err := io.ReadAll(io.LimitReader(f, maxScan))
+		if err != nil {
+			return nil, err
+		}
+		pdfInfo(data, out)
+	case bytes.HasPrefix(head, []byte("PK\x03\x04")):
+		info, err := f.Stat()
+		if err != nil {
+			return nil, err
+		}
+		officeProperties(f, info.Size(), out)
+	}
+
+	for k, v := range out {
+		v = strings.TrimSpace(strings.ToValidUTF8(v, ""))
+		if v == "" {
+			delete(out, k)
+			continue
+		}
+		out[k] = v
+	}
+	return out, nil
+}
@@ -0,0 +1,166 @@
+package metadata
+
+import (
+	"archive/zip"
+	"bytes"
+	"encoding/binary"
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+type tiffField struct {
+	tag, typ uint16
+	count    uint32
+	data     []byte
+}
+
+func ascii(tag uint16, s string) tiffField {
+	return tiffField{tag, 2, uint32(len(s) + 1), append([]byte(s), 0)}
+}
+
+func long(tag uint16, v uint32) tiffField {
+	return tiffField{tag, 4, 1, binary.LittleEndian.AppendUint32(nil, v)}
+}
+
+func rationals(tag uint16, pairs ...uint32) tiffField {
+	var data []byte
+	for _, v := range pairs {
+		data = binary.LittleEndian.AppendUint32(data, v)
+	}
+	return tiffField{tag, 5, uint32(len(pairs) / 2), data}
+}
+
+func ifdSize(fields []tiffField) int {
+	n := 2 + 12*len(fields) + 4
+	for _, f := range fields {
+		if len(f.data) > 4 {
+			n += len(f.data)
+		}
+	}
+	return n
+}
+
+// appendIFD lays out an IFD at the end of b, followed by its values that
+// do not fit in an entry.
+func appendIFD(b []byte, fields []tiffField) []byte {
+	le := binary.LittleEndian
+	extra := len(b) + 2 + 12*len(fields) + 4
+	var tail []byte
+	b = le.AppendUint16(b, uint16(len(fields)))
+	for _, f := range fields {
+		b = le.AppendUint16(b, f.tag)
+		b = le.AppendUint16(b, f.typ)
+		b = le.AppendUint32(b, f.count)
+		if len(f.data) > 4 {
+			b = le.AppendUint32(b, uint32(extra+len(tail)))
+			tail = append(tail, f.data...)
+		} else {
+			b = append(b, f.data...)
+			b = append(b, make([]byte, 4-len(f.data))...)
+		}
+	}
+	b = le.AppendUint32(b, 0)
+	return append(b, tail...)
+}
+
+func testJPEG() []byte {
+	ifd0 := []tiffField{
+		ascii(tagMake, "Canon"),
+		ascii(tagModel, "Canon EOS 5D"),
+		ascii(tagDateTime, "2024:03:01 09:30:00"),
+		long(tagGPSIFD, 0),
+	}
+	ifd0[3] = long(tagGPSIFD, uint32(8+ifdSize(ifd0)))
+	gps := []tiffField{
+		ascii(tagGPSLatRef, "N"),
+		rationals(tagGPSLat, 40, 1, 42, 1, 4608, 100),
+		ascii(tagGPSLonRef, "W"),
+		rationals(tagGPSLon, 74, 1, 0, 1, 2160, 100),
+	}
+	tiff := []byte("II*\x00\x08\x00\x00\x00")
+	tiff = appendIFD(tiff, ifd0)
+	tiff = appendIFD(tiff, gps)
+
+	segment := append([]byte("Exif\x00\x00"), tiff...)
+	jpeg := []byte{0xFF, 0xD8, 0xFF, 0xE1}
+	jpeg = binary.BigEndian.AppendUint16(jpeg, uint16(len(segment)+2))
+	jpeg = append(jpeg, segment...)
+	return append(jpeg, 0xFF, 0xD9)
+}
+
+func extract(t *testing.T, name string, content []byte) map[string]string {
+	t.Helper()
+	path := filepath.Join(t.TempDir(), name)
+	if err := os.WriteFile(path, content, 0o644); err != nil {
+		t.Fatal(err)
+	}
+	got, err := Extract(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	return got
+}
+
+func expect(t *testing.T, got map[string]string, want map[string]string) {
+	t.Helper()
+	for k, v := range want {
+		if got[k] != v {
+			t.Errorf("%s = %q, want %q (all: %v)", k, got[k], v, got)
+		}
+	}
+}
+
+func TestExtractJPEGExif(t *testing.T) {
+	got := extract(t, "photo.jpg", testJPEG())
+	expect(t, got, map[string]string{
+		"camera":   "Canon EOS 5D",
+		"modified": "2024-03-01T09:30:00",
+		"gps":      "40.712800, -74.006000",
+	})
+}
+
+func TestExtractPDFInfo(t *testing.T) {
+	pdf := "%PDF-1.4\n1 0 obj << /Title (Old) >> endobj\n" +
+		"2 0 obj << /Title (Quarterly \\(draft\\)) /Author <FEFF004A006F> " +
+		"/CreationDate (D:20230102150405+01'00') >> endobj\n%%EOF\n"
+	got := extract(t, "report.pdf", []byte(pdf))
+	expect(t, got, map[string]string{
+		"title":   "Quarterly (draft)",
+		"author":  "Jo",
+		"created": "2023-01-02T15:04:05+01:00",
+	})
+}
+
+func TestExtractOfficeProperties(t *testing.T) {
+	var buf bytes.Buffer
+	zw := zip.NewWriter(&buf)
+	w, _ := zw.Create("docProps/core.xml")
+	w.Write([]byte(`<?xml version="1.0"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties"
+  xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/">
+  <dc:creator>R. Diaz</dc:creator>
+  <cp:lastModifiedBy>legal</cp:lastModifiedBy>
+  <dcterms:created>2022-11-04T10:00:00Z</dcterms:created>
+</cp:coreProperties>`))
+	w, _ = zw.Create("docProps/app.xml")
+	w.Write([]byte(`<Properties><Application>Microsoft Office Word</Application><Company>Acme</Company></Properties>`))
+	zw.Close()
+
+	got := extract(t, "memo.docx", buf.Bytes())
+	expect(t, got, map[string]string{
+		"author":           "R. Diaz",
+		"last_modified_by": "legal",
+		"created":          "2022-11-04T10:00:00Z",
+		"company":          "Acme",
+	})
+	if _, ok := got["title"]; ok {
+		t.Fatal("expected empty properties to be dropped")
+	}
+}
+
+func TestExtractUnknownFormat(t *testing.T) {
+	if got := extract(t, "notes.txt", []byte("plain text")); len(got) != 0 {
+		t.Fatalf("expected no metadata, got %v", got)
+	}
+}
@@ -0,0 +1,68 @@
+package metadata
+
+import (
+	"archive/zip"
+	"encoding/xml"
+	"io"
+)
+
+// coreProperties is docProps/core.xml in Office Open XML documents. Field
+// tags without a namespace match the element in any namespace.
+type coreProperties struct {
+	Title          string `xml:"title"`
+	Subject        string `xml:"subject"`
+	Creator        string `xml:"creator"`
+	Keywords       string `xml:"keywords"`
+	LastModifiedBy string `xml:"lastModifiedBy"`
+	Revision       string `xml:"revision"`
+	Created        string `xml:"created"`
+	Modified       string `xml:"modified"`
+}
+
+// appProperties is docProps/app.xml.
+type appProperties struct {
+	Application string `xml:"Application"`
+	Company     string `xml:"Company"`
+	Template    string `xml:"Template"`
+}
+
+// officeProperties reads the document properties of a .docx, .xlsx or
+// .pptx file. Other zip files have none and yield nothing.
+func officeProperties(r io.ReaderAt, size int64, out map[string]string) {
+	zr, err := zip.NewReader(r, size)
+	if err != nil {
+		return
+	}
+	for _, f := range zr.File {
+		switch f.Name {
+		case "docProps/core.xml":
+			var core coreProperties
+			if readXML(f, &core) {
+				out["title"] = core.Title
+				out["subject"] = core.Subject
+				out["author"] = core.Creator
+				out["keywords"] = core.Keywords
+				out["last_modified_by"] = core.LastModifiedBy
+				out["revision"] = core.Revision
+				out["created"] = core.Created
+				out["modified"] = core.Modified
+			}
+		case "docProps/app.xml":
+			var app appProperties
+			if readXML(f, &app) {
+				out["application"] = app.Application
+				out["company"] = app.Company
+				out["template"] = app.Template
+			}
+		}
+	}
+}
+
+func readXML(f *zip.File, v any) bool {
+	rc, err := f.Open()
+	if err != nil {
+		return false
+	}
+	defer rc.Close()
+	return xml.NewDecoder(io.LimitReader(rc, 1<<20)).Decode(v) == nil
+}
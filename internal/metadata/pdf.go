@@ -0,0 +1,166 @@
+package metadata
+
+import (
+	"bytes"
+	"encoding/hex"
+	"regexp"
+	"strings"
+	"unicode/utf16"
+)
+
+// pdfInfoKeys maps document information entries to metadata keys.
+var pdfInfoKeys = map[string]string{
+	"Title":        "title",
+	"Author":       "author",
+	"Subject":      "subject",
+	"Keywords":     "keywords",
+	"Creator":      "creator",
+	"Producer":     "producer",
+	"CreationDate": "created",
+	"ModDate":      "modified",
+}
+
+var pdfInfoEntry = regexp.MustCompile(`/(Title|Author|Subject|Keywords|Creator|Producer|CreationDate|ModDate)\s*([(<])`)
+
+// pdfInfo reads the document information dictionary. Incremental updates
+// append a new dictionary, so the last value of each entry wins. Entries
+// inside compressed object streams are not seen.
+func pdfInfo(data []byte, out map[string]string) {
+	for _, m := range pdfInfoEntry.FindAllSubmatchIndex(data, -1) {
+		name := string(data[m[2]:m[3]])
+		var value string
+		var ok bool
+		if data[m[4]] == '(' {
+			value, ok = pdfLiteral(data[m[4]+1:])
+		} else {
+			value, ok = pdfHex(data[m[4]+1:])
+		}
+		if !ok {
+			continue
+		}
+		key := pdfInfoKeys[name]
+		if key == "created" || key == "modified" {
+			value = pdfDate(value)
+		}
+		out[key] = value
+	}
+}
+
+// pdfLiteral decodes a literal string, given the bytes after its opening
+// parenthesis.
+func pdfLiteral(data []byte) (string, bool) {
+	var buf []byte
+	depth := 0
+	for i := 0; i < len(data); i++ {
+		c := data[i]
+		switch {
+		case c == '\\' && i+1 < len(data):
+			i++
+			switch e := data[i]; e {
+			case 'n':
+				buf = append(buf, '\n')
+			case 'r':
+				buf = append(buf, '\r')
+			case 't':
+				buf = append(buf, '\t')
+			case 'b':
+				buf = append(buf, '\b')
+			case 'f':
+				buf = append(buf, '\f')
+			case '\r', '\n':
+				// Line continuation.
+			default:
+				if e >= '0' && e <= '7' {
+					v := 0
+					j := i
+					for ; j < len(data) && j < i+3 && data[j] >= '0' && data[j] <= '7'; j++ {
+						v = v*8 + int(data[j]-'0')
+					}
+					buf = append(buf, byte(v))
+					i = j - 1
+				} else {
+					buf = append(buf, e)
+				}
+			}
+		case c == '(':
+			depth++
+			buf = append(buf, c)
+		case c == ')':
+			if depth == 0 {
+				return pdfText(buf), true
+			}
+			depth--
+			buf = append(buf, c)
+		default:
+			buf = append(buf, c)
+		}
+	}
+	return "", false
+}
+
+// pdfHex decodes a hex string, given the bytes after its opening angle
+// bracket.
+func pdfHex(data []byte) (string, bool) {
+	end := bytes.IndexByte(data, '>')
+	if end < 0 {
+		return "", false
+	}
+	digits := strings.Join(strings.Fields(string(data[:end])), "")
+	if len(digits)%2 == 1 {
+		digits += "0"
+	}
+	b, err := hex.DecodeString(digits)
+	if err != nil {
+		return "", false
+	}
+	return pdfText(b), true
+}
+
+// pdfText decodes a text string: UTF-16BE when it starts with a byte order
+// mark, otherwise treated as Latin-1, which PDFDocEncoding matches for
+// printable characters.
+func pdfText(b []byte) string {
+	if len(b) >= 2 && b[0] == 0xFE && b[1] == 0xFF {
+		units := make([]uint16, 0, len(b)/2)
+		for i := 2; i+1 < len(b); i += 2 {
+			units = append(units, uint16(b[i])<<8|uint16(b[i+1]))
+		}
+		return string(utf16.Decode(units))
+	}
+	if bytes.HasPrefix(b, []byte("\xEF\xBB\xBF")) {
+		return string(b[3:])
+	}
+	runes := make([]rune, len(b))
+	for i, c := range b {
+		runes[i] = rune(c)
+	}
+	return string(runes)
+}
+
+// pdfDate turns "D:20060102150405+01'00'" into "2006-01-02T15:04:05+01:00",
+// keeping whatever precision the date gives.
+func pdfDate(s string) string {
+	d := strings.TrimPrefix(s, "D:")
+	if len(d) < 4 {
+		return s
+	}
+	for _, c := range d[:min(len(d), 14)] {
+		if c < '0' || c > '9' {
+			return s
+		}
+	}
+	out := d[:4]
+	if len(d) >= 8 {
+		out += "-" + d[4:6] + "-" + d[6:8]
+	}
+	if len(d) >= 14 {
+		out += "T" + d[8:10] + ":" + d[10:12] + ":" + d[12:14]
+		switch tz := strings.ReplaceAll(d[14:], "'", ""); {
+		case tz == "Z":
+			out += "Z"
+		case len(tz) == 5 && (tz[0] == '+' || tz[0] == '-'):
+			out += tz[:3] + ":" + tz[3:]
+		}
+	}
+	return out
+}
@@ -0,0 +1,22 @@
+package models
+
+type AuditEntry struct {
+	ID        int64
+	Timestamp string
+	Operation string
+	FileID    *int64
+	User      *string
+	Detail    *string
+}
+
+// Anchor is a record of the project's state committed to an OpenTimestamps
+// calendar: the statement listing that state, its SHA-256 digest, and the
+// proof, pending until upgraded with a Bitcoin attestation.
+type Anchor struct {
+	ID        int64
+	CreatedAt string
+	Digest    string
+	Statement []byte
+	Calendar  string
+	Proof     []byte
+}
@@ -8,4 +8,45 @@ type TrackedFile struct {
 	Size        *int64
 	IngestedAt  string
 	Provenance  *string
+	// HashAlgorithm is what SHA256 was hashed with; see integrity.Algorithm.
+	HashAlgorithm string
+}
+
+// FileVersion is one recorded revision of a tracked file's content.
+type FileVersion struct {
+	ID         int64
+	FileID     int64
+	SHA256     string
+	Size       *int64
+	RecordedAt string
+}
+
+// TrashEntry is the tombstone of a tracked file moved to the trash.
+// PurgedAt is set once the file has been deleted for good.
+type TrashEntry struct {
+	ID        int64
+	FileID    int64
+	TrashedAt string
+	User      *string
+	Reason    *string
+	PurgedAt  *string
+}
+
+// StoredObject is content held in remote storage. RetainUntil is set when
+// the storage locked it against deletion.
+type StoredObject struct {
+	SHA256      string
+	Size        int64
+	LockMode    string
+	RetainUntil *string
+	StoredAt    string
+}
+
+// Derivation records that one tracked file was produced from another, such
+// as an OCR transcript from a scanned PDF.
+type Derivation struct {
+	SourceID  int64
+	DerivedID int64
+	// Via names the tool or process that produced the derived file.
+	Via *string
 }
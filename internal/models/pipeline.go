@@ -3,6 +3,8 @@ package models
 import (
 	"encoding/json"
 	"fmt"
+	"strconv"
+	"strings"
 )
 
 type Pipeline struct {
@@ -45,6 +47,11 @@ func (p *Pipeline) Validate() error {
 		if len(signs) == 0 {
 			return fmt.Errorf("transition to '%s' has no required signs", target)
 		}
+		for _, sign := range signs {
+			if _, err := ParseRequirement(sign); err != nil {
+				return fmt.Errorf("transition to '%s': %w", target, err)
+			}
+		}
 	}
 	for _, s := range p.States[1:] {
 		if _, ok := p.Transitions[s]; !ok {
@@ -54,6 +61,75 @@ func (p *Pipeline) Validate() error {
 	return nil
 }
 
+// Requirement is a sign a transition needs: Count valid signs named Name,
+// each from a different signer.
+type Requirement struct {
+	Name  string
+	Count int
+}
+
+// ParseRequirement parses one entry of a transition: a sign name,
+// optionally followed by xN to require N distinct signers ("editor x2").
+func ParseRequirement(s string) (Requirement, error) {
+	fields := strings.Fields(s)
+	switch len(fields) {
+	case 1:
+		return Requirement{Name: fields[0], Count: 1}, nil
+	case 2:
+		if n, err := strconv.Atoi(strings.TrimPrefix(fields[1], "x")); err == nil && n > 0 && strings.HasPrefix(fields[1], "x") {
+			return Requirement{Name: fields[0], Count: n}, nil
+		}
+	}
+	return Requirement{}, fmt.Errorf("invalid sign requirement '%s' (expected a sign name, optionally followed by xN)", s)
+}
+
+// Requirements returns the signs a transition into state needs. A sign
+// name listed more than once needs that many distinct signers in total.
+func (p *Pipeline) Requirements(state string) []Requirement {
+	var reqs []Requirement
+	index := make(map[string]int)
+	for _, entry := range p.Transitions[state] {
+		r, err := ParseRequirement(entry)
+		if err != nil {
+			continue
+		}
+		if i, ok := index[r.Name]; ok {
+			reqs[i].Count += r.Count
+			continue
+		}
+		index[r.Name] = len(reqs)
+		reqs = append(reqs, r)
+	}
+	return reqs
+}
+
+// Shortfall is a requirement that valid signs do not yet meet, with the
+// distinct signers it has so far.
+type Shortfall struct {
+	Requirement
+	Signers []string
+}
+
+// Unmet checks requirements against valid signs and returns those short
+// of distinct signers; an empty result means the transition is allowed.
+func Unmet(reqs []Requirement, signs []Sign) []Shortfall {
+	var missing []Shortfall
+	for _, r := range reqs {
+		var signers []string
+		seen := make(map[string]bool)
+		for _, s := range signs {
+			if s.SignName == r.Name && !seen[s.Signer] {
+				seen[s.Signer] = true
+				signers = append(signers, s.Signer)
+			}
+		}
+		if len(signers) < r.Count {
+			missing = append(missing, Shortfall{Requirement: r, Signers: signers})
+		}
+	}
+	return missing
+}
+
 // StatesJSON returns the JSON representation of states.
 func (p *Pipeline) StatesJSON() string {
 	b, _ := json.Marshal(p.States)
@@ -77,6 +153,14 @@ type Sign struct {
 	Signature  *string
 	RevokedAt  *string
 	Source     *string
+
+	// SignatureFormat names the backend that made Signature (gpg, ssh,
+	// minisign or ed25519), and SigningKey the public key it checks against.
+	SignatureFormat *string
+	SigningKey      *string
+	// TimestampToken is an RFC 3161 token over FileHash from a trusted
+	// time-stamping authority.
+	TimestampToken []byte
 }
 
 // IsValid returns true if the sign is not revoked and the file hash matches.
@@ -84,6 +168,22 @@ func (s *Sign) IsValid(currentHash string) bool {
 	return s.RevokedAt == nil && s.FileHash == currentHash
 }
 
+// StateChange is one entry in a file's pipeline state history: the state
+// it moved between, when, and the sign, revocation or accepted
+// modification that moved it.
+type StateChange struct {
+	ID         *int64
+	FileID     int64
+	PipelineID int64
+	FromState  string
+	ToState    string
+	ChangedAt  string
+	// Cause is "sign", "revoke" or "accept".
+	Cause    string
+	SignName *string
+	Actor    string
+}
+
 type Subscription struct {
 	ID        *int64
 	Reference string
@@ -54,3 +54,62 @@ func TestSignIsValid(t *testing.T) {
 		t.Fatal("revoked sign should not be valid")
 	}
 }
+
+func TestParseRequirement(t *testing.T) {
+	cases := []struct {
+		in    string
+		name  string
+		count int
+		ok    bool
+	}{
+		{"editor", "editor", 1, true},
+		{"editor x2", "editor", 2, true},
+		{" legal  x3 ", "legal", 3, true},
+		{"editor x0", "", 0, false},
+		{"editor 2", "", 0, false},
+		{"editor x2 x3", "", 0, false},
+		{"", "", 0, false},
+	}
+	for _, c := range cases {
+		r, err := ParseRequirement(c.in)
+		if (err == nil) != c.ok {
+			t.Fatalf("ParseRequirement(%q) error = %v, want ok %v", c.in, err, c.ok)
+		}
+		if c.ok && (r.Name != c.name || r.Count != c.count) {
+			t.Fatalf("ParseRequirement(%q) = %+v", c.in, r)
+		}
+	}
+
+	p := Pipeline{
+		Name:        "test",
+		States:      []string{"draft", "published"},
+		Transitions: map[string][]string{"published": {"editor x"}},
+	}
+	if err := p.Validate(); err == nil {
+		t.Fatal("expected error for malformed requirement")
+	}
+}
+
+func TestUnmetCountsDistinctSigners(t *testing.T) {
+	p := Pipeline{
+		Name:        "test",
+		States:      []string{"draft", "published"},
+		Transitions: map[string][]string{"published": {"editor x2", "legal"}},
+	}
+	reqs := p.Requirements("published")
+
+	signs := []Sign{
+		{SignName: "editor", Signer: "alice"},
+		{SignName: "editor", Signer: "alice"},
+		{SignName: "legal", Signer: "carol"},
+	}
+	missing := Unmet(reqs, signs)
+	if len(missing) != 1 || missing[0].Name != "editor" || len(missing[0].Signers) != 1 {
+		t.Fatalf("expected editor short one signer, got %+v", missing)
+	}
+
+	signs = append(signs, Sign{SignName: "editor", Signer: "bob"})
+	if missing := Unmet(reqs, signs); len(missing) != 0 {
+		t.Fatalf("expected quorum met, got %+v", missing)
+	}
+}
@@ -1,6 +1,9 @@
 package models
 
-import "fmt"
+import (
+	"fmt"
+	"strings"
+)
 
 type TriggerEvent string
 
@@ -12,10 +15,13 @@ const (
 	TriggerStateChange    TriggerEvent = "state_change"
 	TriggerProjectEnter   TriggerEvent = "project_enter"
 	TriggerWorkspaceEnter TriggerEvent = "workspace_enter"
+	TriggerVerifyFailure  TriggerEvent = "verify_failure"
 )
 
+// ParseTriggerEvent accepts hyphens in place of underscores, so
+// state-change names the same event as state_change.
 func ParseTriggerEvent(s string) (TriggerEvent, error) {
-	switch s {
+	switch strings.ReplaceAll(s, "-", "_") {
 	case "ingest":
 		return TriggerIngest, nil
 	case "tag":
@@ -30,6 +36,8 @@ func ParseTriggerEvent(s string) (TriggerEvent, error) {
 		return TriggerProjectEnter, nil
 	case "workspace_enter":
 		return TriggerWorkspaceEnter, nil
+	case "verify_failure", "verify_failed":
+		return TriggerVerifyFailure, nil
 	default:
 		return "", fmt.Errorf("unknown trigger event: %s", s)
 	}
@@ -45,8 +53,40 @@ const (
 	EventActionUnsign         ActionType = "unsign"
 	EventActionAttachPipeline ActionType = "attach_pipeline"
 	EventActionDetachPipeline ActionType = "detach_pipeline"
+	EventActionShell          ActionType = "shell"
+	EventActionWebhook        ActionType = "webhook"
+	EventActionArchiveURL     ActionType = "archive_url"
 )
 
+// ParseActionType accepts hyphens in place of underscores, like
+// ParseTriggerEvent.
+func ParseActionType(s string) (ActionType, error) {
+	switch strings.ReplaceAll(s, "-", "_") {
+	case "run_tool":
+		return EventActionRunTool, nil
+	case "add_tag":
+		return EventActionAddTag, nil
+	case "remove_tag":
+		return EventActionRemoveTag, nil
+	case "sign":
+		return EventActionSign, nil
+	case "unsign":
+		return EventActionUnsign, nil
+	case "attach_pipeline":
+		return EventActionAttachPipeline, nil
+	case "detach_pipeline":
+		return EventActionDetachPipeline, nil
+	case "shell":
+		return EventActionShell, nil
+	case "webhook":
+		return EventActionWebhook, nil
+	case "archive_url":
+		return EventActionArchiveURL, nil
+	default:
+		return "", fmt.Errorf("unknown action type: %s", s)
+	}
+}
+
 type TriggerFilter struct {
 	TagName  *string `json:"tag_name,omitempty"`
 	Category *string `json:"category,omitempty"`
@@ -67,7 +107,14 @@ type ActionConfig struct {
 	Tag      *string `json:"tag,omitempty"`
 	Pipeline *string `json:"pipeline,omitempty"`
 	SignName *string `json:"sign_name,omitempty"`
+	// Category is also where an archive_url action stores its snapshot.
 	Category *string `json:"category,omitempty"`
+	// Command is a shell action's command template.
+	Command *string `json:"command,omitempty"`
+	// URL and Payload are where a webhook action posts and the body
+	// template it posts.
+	URL     *string `json:"url,omitempty"`
+	Payload *string `json:"payload,omitempty"`
 }
 
 type Rule struct {
@@ -0,0 +1,8 @@
+package models
+
+// SavedRef is a reference stored under a name, used as :@name.
+type SavedRef struct {
+	Name      string
+	Reference string
+	CreatedAt string
+}
@@ -9,9 +9,10 @@ import (
 type ScopeType string
 
 const (
-	ScopeTypeCategory ScopeType = "category"
-	ScopeTypeTag      ScopeType = "tag"
-	ScopeTypeProject  ScopeType = "project"
+	ScopeTypeCategory  ScopeType = "category"
+	ScopeTypeTag       ScopeType = "tag"
+	ScopeTypeProject   ScopeType = "project"
+	ScopeTypeWorkspace ScopeType = "workspace"
 )
 
 // ValidateScopeName checks that a name contains no reserved characters.
@@ -35,6 +36,8 @@ func ParseScopeType(s string) (ScopeType, error) {
 		return ScopeTypeTag, nil
 	case "project":
 		return ScopeTypeProject, nil
+	case "workspace":
+		return ScopeTypeWorkspace, nil
 	default:
 		return "", fmt.Errorf("unknown scope type: %s", s)
 	}
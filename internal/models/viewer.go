@@ -0,0 +1,30 @@
+package models
+
+import "strings"
+
+// OpenViewer maps a mime type to the command `mkrk open` launches. A nil
+// ScopeID applies project-wide; a scoped viewer wins over a global one.
+// Only read-only viewers may open files in immutable categories.
+type OpenViewer struct {
+	ID       *int64
+	ScopeID  *int64
+	MimeType string
+	Command  string
+	ReadOnly bool
+}
+
+// MimeSpecificity ranks how closely a viewer's mime pattern matches
+// actual: 2 for an exact match, 1 for a "type/*" wildcard, 0 for "*", and
+// -1 when it does not match at all.
+func MimeSpecificity(pattern, actual string) int {
+	switch {
+	case pattern == actual:
+		return 2
+	case pattern == "*":
+		return 0
+	}
+	if prefix, ok := strings.CutSuffix(pattern, "/*"); ok && strings.HasPrefix(actual, prefix+"/") {
+		return 1
+	}
+	return -1
+}
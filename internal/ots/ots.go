@@ -0,0 +1,469 @@
+// Package ots anchors SHA-256 digests with OpenTimestamps. A calendar
+// server commits a submitted digest to a Bitcoin transaction within hours
+// and answers at once with a pending proof; upgrading the proof later
+// fetches the path from the digest to the block, which shows the digest
+// existed before the block was mined. Proofs are kept in the .ots file
+// format the reference client reads, so `ots verify` can check them
+// against a Bitcoin node.
+package ots
+
+import (
+	"bufio"
+	"bytes"
+	"crypto/sha1"
+	"crypto/sha256"
+	"encoding/binary"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"strings"
+)
+
+// DefaultCalendar is the calendar pool used when none is configured.
+const DefaultCalendar = "https://a.pool.opentimestamps.org"
+
+// headerMagic opens every detached timestamp file.
+const headerMagic = "\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94"
+
+const majorVersion = 1
+
+// Operation tags. Only those calendars use are supported.
+const (
+	opSHA1    = 0x02
+	opSHA256  = 0x08
+	opAppend  = 0xf0
+	opPrepend = 0xf1
+	opReverse = 0xf2
+	opHexlify = 0xf3
+
+	tagAttestation = 0x00
+	tagFork        = 0xff
+)
+
+// Attestation tags.
+var (
+	pendingTag = []byte{0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e}
+	bitcoinTag = []byte{0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01}
+)
+
+// Limits guarding against malformed or hostile proofs.
+const (
+	maxResponse = 1 << 16
+	maxArgSize  = 4096
+	maxDepth    = 256
+)
+
+// timestamp is a node of a proof: the message at this point, what attests
+// to it, and the operations leading on from it.
+type timestamp struct {
+	msg          []byte
+	attestations []attestation
+	ops          []branch
+}
+
+type attestation struct {
+	tag     []byte
+	payload []byte
+}
+
+type branch struct {
+	op    byte
+	arg   []byte
+	child *timestamp
+}
+
+// apply runs an operation on msg.
+func apply(op byte, arg, msg []byte) ([]byte, error) {
+	switch op {
+	case opSHA1:
+		sum := sha1.Sum(msg)
+		return sum[:], nil
+	case opSHA256:
+		sum := sha256.Sum256(msg)
+		return sum[:], nil
+	case opAppend:
+		return append(bytes.Clone(msg), arg...), nil
+	case opPrepend:
+		return append(bytes.Clone(arg), msg...), nil
+	case opReverse:
+		out := bytes.Clone(msg)
+		for i, j := 0, len(out)-1; i < j; i, j = i+1, j-1 {
+			out[i], out[j] = out[j], out[i]
+		}
+		return out, nil
+	case opHexlify:
+		return []byte(hex.EncodeToString(msg)), nil
+	}
+	return nil, fmt.Errorf("unsupported operation 0x%02x", op)
+}
+
+func binaryOp(op byte) bool {
+	return op == opAppend || op == opPrepend
+}
+
+// --- Serialization ---
+
+func readVaruint(r *bufio.Reader) (uint64, error) {
+	return binary.ReadUvarint(r)
+}
+
+func readVarbytes(r *bufio.Reader, limit int) ([]byte, error) {
+	n, err := readVaruint(r)
+	if err != nil {
+		return nil, err
+	}
+	if n > uint64(limit) {
+		return nil, fmt.Errorf("field of %d bytes exceeds %d", n, limit)
+	}
+	b := make([]byte, n)
+	_, err = io.ReadFull(r, b)
+	return b, err
+}
+
+func writeVaruint(w *bytes.Buffer, n uint64) {
+	w.Write(binary.AppendUvarint(nil, n))
+}
+
+func writeVarbytes(w *bytes.Buffer, b []byte) {
+	writeVaruint(w, uint64(len(b)))
+	w.Write(b)
+}
+
+// parseTimestamp reads the node for msg and everything below it.
+func parseTimestamp(r *bufio.Reader, msg []byte, depth int) (*timestamp, error) {
+	if depth > maxDepth {
+		return nil, fmt.Errorf("proof nests too deeply")
+	}
+	t := &timestamp{msg: msg}
+	item := func(tag byte) error {
+		if tag == tagAttestation {
+			var a attestation
+			a.tag = make([]byte, 8)
+			if _, err := io.ReadFull(r, a.tag); err != nil {
+				return err
+			}
+			var err error
+			if a.payload, err = readVarbytes(r, maxArgSize); err != nil {
+				return err
+			}
+			t.attestations = append(t.attestations, a)
+			return nil
+		}
+		b := branch{op: tag}
+		if binaryOp(tag) {
+			var err error
+			if b.arg, err = readVarbytes(r, maxArgSize); err != nil {
+				return err
+			}
+		}
+		next, err := apply(b.op, b.arg, msg)
+		if err != nil {
+			return err
+		}
+		if len(next) > maxArgSize {
+			return fmt.Errorf("message grows past %d bytes", maxArgSize)
+		}
+		if b.child, err = parseTimestamp(r, next, depth+1); err != nil {
+			return err
+		}
+		t.ops = append(t.ops, b)
+		return nil
+	}
+
+	tag, err := r.ReadByte()
+	for err == nil && tag == tagFork {
+		if tag, err = r.ReadByte(); err != nil {
+			break
+		}
+		if err = item(tag); err != nil {
+			return nil, err
+		}
+		tag, err = r.ReadByte()
+	}
+	if err != nil {
+		return nil, fmt.Errorf("truncated proof: %w", err)
+	}
+	if err := item(tag); err != nil {
+		return nil, err
+	}
+	return t, nil
+}
+
+// write serializes the node: every item but the last behind a fork
+// marker, attestations before operations.
+func (t *timestamp) write(w *bytes.Buffer) {
+	n := len(t.attestations) + len(t.ops)
+	i := 0
+	for _, a := range t.attestations {
+		if i++; i < n {
+			w.WriteByte(tagFork)
+		}
+		w.WriteByte(tagAttestation)
+		w.Write(a.tag)
+		writeVarbytes(w, a.payload)
+	}
+	for _, b := range t.ops {
+		if i++; i < n {
+			w.WriteByte(tagFork)
+		}
+		w.WriteByte(b.op)
+		if binaryOp(b.op) {
+			writeVarbytes(w, b.arg)
+		}
+		b.child.write(w)
+	}
+}
+
+// Proof is a detached timestamp over a SHA-256 digest.
+type Proof struct {
+	Digest []byte
+	root   *timestamp
+}
+
+// Parse reads a detached timestamp file.
+func Parse(data []byte) (*Proof, error) {
+	r := bufio.NewReader(bytes.NewReader(data))
+	head := make([]byte, len(headerMagic))
+	if _, err := io.ReadFull(r, head); err != nil || string(head) != headerMagic {
+		return nil, fmt.Errorf("not an OpenTimestamps proof")
+	}
+	if v, err := readVaruint(r); err != nil || v != majorVersion {
+		return nil, fmt.Errorf("unsupported OpenTimestamps proof version")
+	}
+	if op, err := r.ReadByte(); err != nil || op != opSHA256 {
+		return nil, fmt.Errorf("only SHA-256 OpenTimestamps proofs are supported")
+	}
+	digest := make([]byte, sha256.Size)
+	if _, err := io.ReadFull(r, digest); err != nil {
+		return nil, fmt.Errorf("truncated proof: %w", err)
+	}
+	root, err := parseTimestamp(r, digest, 0)
+	if err != nil {
+		return nil, err
+	}
+	if _, err := r.ReadByte(); err != io.EOF {
+		return nil, fmt.Errorf("trailing data after proof")
+	}
+	return &Proof{Digest: digest, root: root}, nil
+}
+
+// Bytes serializes the proof as a detached timestamp file.
+func (p *Proof) Bytes() []byte {
+	var w bytes.Buffer
+	w.WriteString(headerMagic)
+	writeVaruint(&w, majorVersion)
+	w.WriteByte(opSHA256)
+	w.Write(p.Digest)
+	p.root.write(&w)
+	return w.Bytes()
+}
+
+// Attestation is one statement in a proof about when its digest existed.
+type Attestation struct {
+	// Calendar is set while the attestation is pending: the calendar that
+	// promised to commit the digest to Bitcoin.
+	Calendar string
+	// Height is the Bitcoin block that commits to the digest, once it
+	// does, and MerkleRoot what that block's merkle root must be, in the
+	// byte order block explorers show.
+	Height     uint64
+	MerkleRoot string
+}
+
+// Attestations lists what the proof holds, pending or confirmed.
+// Attestations of kinds this package does not know are skipped.
+func (p *Proof) Attestations() []Attestation {
+	var out []Attestation
+	walk(p.root, func(t *timestamp, a attestation) {
+		switch {
+		case bytes.Equal(a.tag, pendingTag):
+			if uri, err := pendingURI(a); err == nil {
+				out = append(out, Attestation{Calendar: uri})
+			}
+		case bytes.Equal(a.tag, bitcoinTag):
+			height, n := binary.Uvarint(a.payload)
+			if n <= 0 || len(t.msg) != 32 {
+				return
+			}
+			root := bytes.Clone(t.msg)
+			for i, j := 0, len(root)-1; i < j; i, j = i+1, j-1 {
+				root[i], root[j] = root[j], root[i]
+			}
+			out = append(out, Attestation{Height: height, MerkleRoot: hex.EncodeToString(root)})
+		}
+	})
+	return out
+}
+
+// Confirmed reports whether a Bitcoin block attests to the proof.
+func (p *Proof) Confirmed() bool {
+	for _, a := range p.Attestations() {
+		if a.Height > 0 {
+			return true
+		}
+	}
+	return false
+}
+
+func walk(t *timestamp, fn func(*timestamp, attestation)) {
+	for _, a := range t.attestations {
+		fn(t, a)
+	}
+	for _, b := range t.ops {
+		walk(b.child, fn)
+	}
+}
+
+func pendingURI(a attestation) (string, error) {
+	r := bufio.NewReader(bytes.NewReader(a.payload))
+	uri, err := readVarbytes(r, 1000)
+	if err != nil {
+		return "", err
+	}
+	for _, c := range uri {
+		if c < 0x20 || c > 0x7e {
+			return "", fmt.Errorf("malformed calendar URI")
+		}
+	}
+	return string(uri), nil
+}
+
+// --- Calendars ---
+
+// Stamp submits digest to calendar and returns the pending proof it
+// answers with.
+func Stamp(client *http.Client, calendar string, digest []byte) (*Proof, error) {
+	if len(digest) != sha256.Size {
+		return nil, fmt.Errorf("expected a SHA-256 digest, got %d bytes", len(digest))
+	}
+	body, err := calendarRequest(client, http.MethodPost, strings.TrimRight(calendar, "/")+"/digest", digest)
+	if err != nil {
+		return nil, err
+	}
+	if body == nil {
+		return nil, fmt.Errorf("calendar %s: not found", calendar)
+	}
+	root, err := parseTimestamp(bufio.NewReader(bytes.NewReader(body)), digest, 0)
+	if err != nil {
+		return nil, fmt.Errorf("calendar %s: malformed answer: %w", calendar, err)
+	}
+	return &Proof{Digest: bytes.Clone(digest), root: root}, nil
+}
+
+// Upgrade asks the calendars behind the proof's pending attestations for
+// the rest of its path to Bitcoin, merging in what they have. allowed
+// says which calendar URIs may be asked; a proof could name any server.
+// It reports whether anything was added.
+func (p *Proof) Upgrade(client *http.Client, allowed func(string) bool) (bool, error) {
+	type pending struct {
+		node *timestamp
+		uri  string
+	}
+	var todo []pending
+	walk(p.root, func(t *timestamp, a attestation) {
+		if !bytes.Equal(a.tag, pendingTag) {
+			return
+		}
+		if uri, err := pendingURI(a); err == nil {
+			todo = append(todo, pending{t, uri})
+		}
+	})
+
+	changed := false
+	var errs []error
+	for _, pt := range todo {
+		if !allowed(pt.uri) {
+			errs = append(errs, fmt.Errorf("calendar %s is not trusted", pt.uri))
+			continue
+		}
+		u := strings.TrimRight(pt.uri, "/") + "/timestamp/" + hex.EncodeToString(pt.node.msg)
+		body, err := calendarRequest(client, http.MethodGet, u, nil)
+		if err != nil {
+			errs = append(errs, err)
+			continue
+		}
+		if body == nil {
+			continue // not yet committed
+		}
+		upgrade, err := parseTimestamp(bufio.NewReader(bytes.NewReader(body)), pt.node.msg, 0)
+		if err != nil {
+			errs = append(errs, fmt.Errorf("calendar %s: malformed answer: %w", pt.uri, err))
+			continue
+		}
+		if merge(pt.node, upgrade) {
+			changed = true
+		}
+	}
+	return changed, errors.Join(errs...)
+}
+
+// merge adds what other, a node for the same message, holds that t lacks.
+func merge(t, other *timestamp) bool {
+	changed := false
+	for _, a := range other.attestations {
+		known := false
+		for _, b := range t.attestations {
+			if bytes.Equal(a.tag, b.tag) && bytes.Equal(a.payload, b.payload) {
+				known = true
+				break
+			}
+		}
+		if !known {
+			t.attestations = append(t.attestations, a)
+			changed = true
+		}
+	}
+	for _, ob := range other.ops {
+		found := false
+		for _, b := range t.ops {
+			if b.op == ob.op && bytes.Equal(b.arg, ob.arg) {
+				found = true
+				if merge(b.child, ob.child) {
+					changed = true
+				}
+				break
+			}
+		}
+		if !found {
+			t.ops = append(t.ops, ob)
+			changed = true
+		}
+	}
+	return changed
+}
+
+// calendarRequest makes a request of a calendar. It returns nil, and no
+// error, when the calendar has nothing for it yet.
+func calendarRequest(client *http.Client, method, rawURL string, body []byte) ([]byte, error) {
+	u, err := url.Parse(rawURL)
+	if err != nil || (u.Scheme != "https" && u.Scheme != "http") {
+		return nil, fmt.Errorf("invalid calendar URL %q", rawURL)
+	}
+	req, err := http.NewRequest(method, rawURL, bytes.NewReader(body))
+	if err != nil {
+		return nil, err
+	}
+	req.Header.Set("Accept", "application/vnd.opentimestamps.v1")
+	if body != nil {
+		req.Header.Set("Content-Type", "application/octet-stream")
+	}
+	resp, err := client.Do(req)
+	if err != nil {
+		return nil, fmt.Errorf("calendar request: %w", err)
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode == http.StatusNotFound {
+		return nil, nil
+	}
+	if resp.StatusCode != http.StatusOK {
+		return nil, fmt.Errorf("calendar %s: %s", u.Host, resp.Status)
+	}
+	data, err := io.ReadAll(io.LimitReader(resp.Body, maxResponse))
+	if err != nil {
+		return nil, fmt.Errorf("calendar request: %w", err)
+	}
+	return data, nil
+}
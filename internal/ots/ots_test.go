@@ -0,0 +1,145 @@
+package ots
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/binary"
+	"encoding/hex"
+	"io"
+	"net/http"
+	"net/http/httptest"
+	"strings"
+	"sync/atomic"
+	"testing"
+)
+
+func pendingAttestation(uri string) attestation {
+	var payload bytes.Buffer
+	writeVarbytes(&payload, []byte(uri))
+	return attestation{tag: pendingTag, payload: payload.Bytes()}
+}
+
+func serialize(t *timestamp) []byte {
+	var w bytes.Buffer
+	t.write(&w)
+	return w.Bytes()
+}
+
+// fakeCalendar answers a submission the way real calendars do, by
+// appending a nonce, hashing and promising to commit the result, and
+// later gives the path from there to a Bitcoin block once upgraded is set.
+func fakeCalendar(t *testing.T, upgraded *atomic.Bool) *httptest.Server {
+	var srv *httptest.Server
+	srv = httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		switch {
+		case r.Method == http.MethodPost && r.URL.Path == "/digest":
+			digest, _ := io.ReadAll(r.Body)
+			nonce := []byte("nonce")
+			commitment := sha256.Sum256(append(bytes.Clone(digest), nonce...))
+			answer := &timestamp{ops: []branch{{op: opAppend, arg: nonce, child: &timestamp{
+				ops: []branch{{op: opSHA256, child: &timestamp{
+					msg:          commitment[:],
+					attestations: []attestation{pendingAttestation(srv.URL)},
+				}}},
+			}}}}
+			w.Write(serialize(answer))
+		case r.Method == http.MethodGet && strings.HasPrefix(r.URL.Path, "/timestamp/"):
+			if !upgraded.Load() {
+				http.NotFound(w, r)
+				return
+			}
+			height := binary.AppendUvarint(nil, 358391)
+			answer := &timestamp{ops: []branch{{op: opPrepend, arg: []byte("tx"), child: &timestamp{
+				ops: []branch{{op: opSHA256, child: &timestamp{
+					attestations: []attestation{{tag: bitcoinTag, payload: height}},
+				}}},
+			}}}}
+			w.Write(serialize(answer))
+		default:
+			http.Error(w, "unexpected request", http.StatusBadRequest)
+		}
+	}))
+	t.Cleanup(srv.Close)
+	return srv
+}
+
+func TestStampAndUpgrade(t *testing.T) {
+	var upgraded atomic.Bool
+	srv := fakeCalendar(t, &upgraded)
+	digest := sha256.Sum256([]byte("project state"))
+
+	proof, err := Stamp(srv.Client(), srv.URL, digest[:])
+	if err != nil {
+		t.Fatal(err)
+	}
+	atts := proof.Attestations()
+	if len(atts) != 1 || atts[0].Calendar != srv.URL || proof.Confirmed() {
+		t.Fatalf("expected one pending attestation, got %+v", atts)
+	}
+
+	parsed, err := Parse(proof.Bytes())
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !bytes.Equal(parsed.Digest, digest[:]) || !bytes.Equal(parsed.Bytes(), proof.Bytes()) {
+		t.Fatal("proof did not survive a round trip")
+	}
+
+	trusted := func(uri string) bool { return uri == srv.URL }
+	if changed, err := parsed.Upgrade(srv.Client(), trusted); err != nil || changed {
+		t.Fatalf("expected nothing to upgrade yet, got %v, %v", changed, err)
+	}
+	upgraded.Store(true)
+	if changed, err := parsed.Upgrade(srv.Client(), trusted); err != nil || !changed {
+		t.Fatalf("expected the upgrade to add the Bitcoin path, got %v, %v", changed, err)
+	}
+	if !parsed.Confirmed() {
+		t.Fatal("expected a Bitcoin attestation after upgrading")
+	}
+
+	// The block's merkle root is the commitment, prefixed and hashed,
+	// shown byte-reversed.
+	commitment := sha256.Sum256(append(digest[:], "nonce"...))
+	root := sha256.Sum256(append([]byte("tx"), commitment[:]...))
+	for i, j := 0, len(root)-1; i < j; i, j = i+1, j-1 {
+		root[i], root[j] = root[j], root[i]
+	}
+	var block *Attestation
+	for _, a := range parsed.Attestations() {
+		if a.Height > 0 {
+			block = &a
+		}
+	}
+	if block == nil || block.Height != 358391 || block.MerkleRoot != hex.EncodeToString(root[:]) {
+		t.Fatalf("unexpected Bitcoin attestation %+v", block)
+	}
+
+	again, err := Parse(parsed.Bytes())
+	if err != nil || !again.Confirmed() {
+		t.Fatalf("upgraded proof did not survive a round trip: %v", err)
+	}
+	if _, err := parsed.Upgrade(srv.Client(), func(string) bool { return false }); err == nil {
+		t.Fatal("expected an untrusted calendar to be refused")
+	}
+}
+
+func TestParseRejectsMalformedProofs(t *testing.T) {
+	digest := sha256.Sum256([]byte("x"))
+	good := (&Proof{Digest: digest[:], root: &timestamp{
+		attestations: []attestation{pendingAttestation("https://calendar.example")},
+	}}).Bytes()
+	if _, err := Parse(good); err != nil {
+		t.Fatal(err)
+	}
+	for name, data := range map[string][]byte{
+		"truncated":   good[:len(good)-3],
+		"trailing":    append(bytes.Clone(good), 0),
+		"bad magic":   append([]byte("x"), good[1:]...),
+		"unknown op":  append(bytes.Clone(good[:len(headerMagic)+2+32]), 0x67),
+		"empty proof": good[:len(headerMagic)+2+32],
+	} {
+		if _, err := Parse(data); err == nil {
+			t.Errorf("%s: expected an error", name)
+		}
+	}
+}
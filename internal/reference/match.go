@@ -1,6 +1,10 @@
 package reference
 
-import "go.foia.dev/muckrake/internal/models"
+import (
+	"strings"
+
+	"go.foia.dev/muckrake/internal/models"
+)
 
 // GlobMatchFile checks if a glob pattern matches a filename or full path.
 func GlobMatchFile(pattern, fileName, relPath string) (bool, error) {
@@ -9,3 +13,37 @@ func GlobMatchFile(pattern, fileName, relPath string) (bool, error) {
 	}
 	return models.GlobMatch(pattern, relPath)
 }
+
+// TagMatches checks if a tag satisfies one tag of a filter. A filter tag
+// ending in "/*" names a namespace: source/* matches source itself and
+// every tag beneath it, such as source/whistleblower.
+func TagMatches(pattern, tag string) bool {
+	if ns, ok := strings.CutSuffix(pattern, "/*"); ok {
+		return tag == ns || strings.HasPrefix(tag, ns+"/")
+	}
+	return tag == pattern
+}
+
+// Matches checks if a file carrying tags passes the filter.
+func (f TagFilter) Matches(tags []string) bool {
+	for _, want := range f.Tags {
+		for _, have := range tags {
+			if TagMatches(want, have) {
+				return !f.Negated
+			}
+		}
+	}
+	return f.Negated
+}
+
+// Excluded checks if a file name is left out by any !^ pattern. Each
+// pattern is a glob anchored at the start of the name, so ^draft excludes
+// draft.docx and drafts.txt.
+func Excluded(patterns []string, fileName string) bool {
+	for _, p := range patterns {
+		if ok, _ := models.GlobMatch(p+"*", fileName); ok {
+			return true
+		}
+	}
+	return false
+}
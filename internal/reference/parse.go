@@ -5,7 +5,7 @@ import (
 	"strings"
 )
 
-var reservedChars = []byte{':', '.', '/', '!', '{', '}', ','}
+var reservedChars = []byte{':', '.', '/', '!', '{', '}', ',', '[', ']'}
 var reservedNames = []string{"mkrk"}
 
 // ValidateName checks that a scope/project/category name contains no
@@ -27,6 +27,30 @@ func ValidateName(name string) error {
 	return nil
 }
 
+// ValidateTag checks a tag name. Tags may be namespaced with '/', as in
+// source/whistleblower; each segment follows the rules for names and may
+// not contain glob characters. A leading '~' or '^' would read as negation
+// in a reference, so neither may start a tag.
+func ValidateTag(tag string) error {
+	if strings.HasPrefix(tag, "~") || strings.HasPrefix(tag, "^") {
+		return fmt.Errorf("tag '%s' may not start with '%c'", tag, tag[0])
+	}
+	for _, seg := range strings.Split(tag, "/") {
+		if seg == "" {
+			return fmt.Errorf("tag '%s' has an empty namespace segment", tag)
+		}
+		if strings.ContainsAny(seg, "*?[]") {
+			return fmt.Errorf("tag '%s' contains a glob character", tag)
+		}
+		for _, ch := range reservedChars {
+			if strings.ContainsRune(seg, rune(ch)) {
+				return fmt.Errorf("tag '%s' contains reserved character '%c'", tag, ch)
+			}
+		}
+	}
+	return nil
+}
+
 // IsReservedName returns true if the name is reserved.
 func IsReservedName(name string) bool {
 	for _, r := range reservedNames {
@@ -39,6 +63,12 @@ func IsReservedName(name string) bool {
 
 // ParseReference parses a reference string into a structured Reference.
 func ParseReference(input string) (*Reference, error) {
+	if name, ok := strings.CutPrefix(input, ":@"); ok {
+		if err := ValidateName(name); err != nil {
+			return nil, fmt.Errorf("saved reference: %w", err)
+		}
+		return &Reference{Kind: KindSaved, Raw: name}, nil
+	}
 	if strings.HasPrefix(input, ":") {
 		return parseStructured(input, input[1:], KindWorkspace)
 	}
@@ -62,7 +92,7 @@ func parseStructured(original, rest string, kind ReferenceKind) (*Reference, err
 	if err != nil {
 		return nil, err
 	}
-	tags, err := parseTags(rest, &pos)
+	tags, predicates, exclude, err := parseFilters(rest, &pos)
 	if err != nil {
 		return nil, err
 	}
@@ -78,7 +108,9 @@ func parseStructured(original, rest string, kind ReferenceKind) (*Reference, err
 		WorkspaceWide: workspaceWide,
 		Scope:         scope,
 		Tags:          tags,
+		Predicates:    predicates,
 		Glob:          glob,
+		Exclude:       exclude,
 	}, nil
 }
 
@@ -87,7 +119,7 @@ func parseScope(input string, pos *int) ([]ScopeLevel, error) {
 
 	for *pos < len(input) {
 		ch := input[*pos]
-		if ch == '!' || ch == '/' {
+		if ch == '!' || ch == '/' || ch == '[' {
 			break
 		}
 
@@ -149,7 +181,7 @@ func parseName(input string, pos *int) string {
 	start := *pos
 	for *pos < len(input) {
 		ch := input[*pos]
-		if ch == '.' || ch == '!' || ch == '/' || ch == '{' || ch == '}' || ch == ',' {
+		if ch == '.' || ch == '!' || ch == '/' || ch == '{' || ch == '}' || ch == ',' || ch == '[' {
 			break
 		}
 		*pos++
@@ -157,31 +189,100 @@ func parseName(input string, pos *int) string {
 	return input[start:*pos]
 }
 
-func parseTags(input string, pos *int) ([]TagFilter, error) {
-	var filters []TagFilter
-
-	for *pos < len(input) && input[*pos] == '!' {
-		*pos++
-		var tags []string
-
-		for {
-			tag := parseName(input, pos)
-			if tag == "" {
-				return nil, fmt.Errorf("empty tag name")
-			}
-			tags = append(tags, tag)
+// parseFilters reads the ! tag groups, !^ exclusions and [...] predicate
+// groups that follow a reference's scope, in any order.
+func parseFilters(input string, pos *int) ([]TagFilter, []PredicateFilter, []string, error) {
+	var tags []TagFilter
+	var predicates []PredicateFilter
+	var exclude []string
 
-			if *pos < len(input) && input[*pos] == ',' {
+	for *pos < len(input) {
+		switch input[*pos] {
+		case '!':
+			*pos++
+			if *pos < len(input) && input[*pos] == '^' {
 				*pos++
-			} else {
-				break
+				pattern := parseExclude(input, pos)
+				if pattern == "" {
+					return nil, nil, nil, fmt.Errorf("empty pattern after '!^'")
+				}
+				exclude = append(exclude, pattern)
+				continue
+			}
+			filter, err := parseTagGroup(input, pos)
+			if err != nil {
+				return nil, nil, nil, err
 			}
+			tags = append(tags, filter)
+		case '[':
+			*pos++
+			filter, err := parsePredicateGroup(input, pos)
+			if err != nil {
+				return nil, nil, nil, err
+			}
+			predicates = append(predicates, filter)
+		default:
+			return tags, predicates, exclude, nil
+		}
+	}
+	return tags, predicates, exclude, nil
+}
+
+func parseTagGroup(input string, pos *int) (TagFilter, error) {
+	negated := false
+	if *pos < len(input) && input[*pos] == '~' {
+		negated = true
+		*pos++
+	}
+	var tags []string
+	for {
+		tag := parseTagName(input, pos)
+		if tag == "" {
+			return TagFilter{}, fmt.Errorf("empty tag name")
+		}
+		tags = append(tags, tag)
+
+		if *pos < len(input) && input[*pos] == ',' {
+			*pos++
+		} else {
+			break
 		}
+	}
+	return TagFilter{Tags: tags, Negated: negated}, nil
+}
 
-		filters = append(filters, TagFilter{Tags: tags})
+// parseTagName reads a tag, which may be namespaced with '/'. A '/' only
+// continues the tag when the segment after it is a plain name, or a lone
+// '*' naming every tag in the namespace, which ends the tag. Anything else
+// starts the glob, so :evidence!classified/*.pdf keeps its meaning.
+func parseTagName(input string, pos *int) string {
+	start := *pos
+	parseName(input, pos)
+	for *pos > start && *pos < len(input) && input[*pos] == '/' {
+		end := *pos + 1
+		for end < len(input) && input[end] != '/' && input[end] != '!' && input[end] != ',' {
+			end++
+		}
+		seg := input[*pos+1 : end]
+		if seg == "*" {
+			*pos = end
+			break
+		}
+		if seg == "" || strings.ContainsAny(seg, ".{}*?[]") {
+			break
+		}
+		*pos = end
 	}
+	return input[start:*pos]
+}
 
-	return filters, nil
+// parseExclude reads a !^ pattern, which runs to the next '!' or '/'.
+func parseExclude(input string, pos *int) string {
+	start := *pos
+	for *pos < len(input) && input[*pos] != '!' && input[*pos] != '/' {
+		*pos++
+	}
+	return input[start:*pos]
 }
 
 func parseGlob(input string, pos *int) *string {
@@ -217,3 +217,163 @@ func TestValidateName(t *testing.T) {
 		t.Fatal("expected error for reserved name")
 	}
 }
+
+func TestParseNamespacedTags(t *testing.T) {
+	r, err := ParseReference(":evidence!source/whistleblower,status/*/*.pdf")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(r.Tags) != 1 || len(r.Tags[0].Tags) != 2 {
+		t.Fatalf("unexpected tags: %v", r.Tags)
+	}
+	if r.Tags[0].Tags[0] != "source/whistleblower" || r.Tags[0].Tags[1] != "status/*" {
+		t.Fatalf("unexpected tags: %v", r.Tags)
+	}
+	if r.Glob == nil || *r.Glob != "*.pdf" {
+		t.Fatalf("expected glob *.pdf, got %v", r.Glob)
+	}
+
+	r, err = ParseReference("!source/*")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(r.Tags) != 1 || r.Tags[0].Tags[0] != "source/*" || r.Glob != nil {
+		t.Fatalf("expected namespace filter source/*, got %v glob %v", r.Tags, r.Glob)
+	}
+
+	r, err = ParseReference(":evidence!classified/report.pdf")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if r.Tags[0].Tags[0] != "classified" || r.Glob == nil || *r.Glob != "report.pdf" {
+		t.Fatalf("expected tag classified and glob report.pdf, got %v glob %v", r.Tags, r.Glob)
+	}
+}
+
+func TestTagMatches(t *testing.T) {
+	cases := []struct {
+		pattern, tag string
+		want         bool
+	}{
+		{"source/whistleblower", "source/whistleblower", true},
+		{"source", "source/whistleblower", false},
+		{"source/*", "source/whistleblower", true},
+		{"source/*", "source/foia/request", true},
+		{"source/*", "source", true},
+		{"source/*", "sources", false},
+		{"source/*", "status/verified", false},
+	}
+	for _, c := range cases {
+		if got := TagMatches(c.pattern, c.tag); got != c.want {
+			t.Errorf("TagMatches(%q, %q) = %v, want %v", c.pattern, c.tag, got, c.want)
+		}
+	}
+}
+
+func TestValidateTag(t *testing.T) {
+	for _, tag := range []string{"reviewed", "source/whistleblower", "status/verified/twice"} {
+		if err := ValidateTag(tag); err != nil {
+			t.Fatalf("%s: %v", tag, err)
+		}
+	}
+	for _, tag := range []string{"", "source/", "/source", "source//x", "source/*", "a.b", "a!b", "~reviewed", "^draft"} {
+		if err := ValidateTag(tag); err == nil {
+			t.Fatalf("expected error for %q", tag)
+		}
+	}
+}
+
+func TestParsePredicates(t *testing.T) {
+	r, err := ParseReference(":evidence[size>10M][mime=application/pdf,mime=image/*]!classified/*.pdf")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(r.Scope) != 1 || r.Scope[0].Names[0] != "evidence" {
+		t.Fatalf("unexpected scope: %v", r.Scope)
+	}
+	if len(r.Predicates) != 2 {
+		t.Fatalf("expected 2 predicate filters, got %v", r.Predicates)
+	}
+	if p := r.Predicates[0].Predicates[0]; p.Field != "size" || p.Op != ">" || p.Value != "10M" {
+		t.Fatalf("unexpected predicate: %+v", p)
+	}
+	if len(r.Predicates[1].Predicates) != 2 || r.Predicates[1].Predicates[1].Value != "image/*" {
+		t.Fatalf("unexpected OR group: %+v", r.Predicates[1])
+	}
+	if len(r.Tags) != 1 || r.Tags[0].Tags[0] != "classified" {
+		t.Fatalf("unexpected tags: %v", r.Tags)
+	}
+	if r.Glob == nil || *r.Glob != "*.pdf" {
+		t.Fatalf("expected glob *.pdf, got %v", r.Glob)
+	}
+
+	r, err = ParseReference(":[ingested>=2025-01-01]")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(r.Scope) != 0 || len(r.Predicates) != 1 || r.Predicates[0].Predicates[0].Op != ">=" {
+		t.Fatalf("unexpected reference: %+v", r)
+	}
+
+	r, err = ParseReference(":[attr.foia-request=2024-*]")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if p := r.Predicates[0].Predicates[0]; p.Field != "attr.foia-request" || p.Value != "2024-*" {
+		t.Fatalf("unexpected predicate: %+v", p)
+	}
+
+	for _, bad := range []string{":[size>10M", ":[color=red]", ":[size>lots]", ":[mime>text/plain]", ":[ingested<yesterday]", ":[size]", ":[attr.=x]", ":[attr.a/b=x]"} {
+		if _, err := ParseReference(bad); err == nil {
+			t.Fatalf("expected error for %s", bad)
+		}
+	}
+}
+
+func TestParseNegation(t *testing.T) {
+	r, err := ParseReference(":evidence!~reviewed,flagged!important!^draft/*.pdf")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(r.Tags) != 2 {
+		t.Fatalf("expected 2 tag filters, got %v", r.Tags)
+	}
+	if !r.Tags[0].Negated || len(r.Tags[0].Tags) != 2 || r.Tags[0].Tags[1] != "flagged" {
+		t.Fatalf("expected negated group [reviewed flagged], got %+v", r.Tags[0])
+	}
+	if r.Tags[1].Negated || r.Tags[1].Tags[0] != "important" {
+		t.Fatalf("expected plain group [important], got %+v", r.Tags[1])
+	}
+	if len(r.Exclude) != 1 || r.Exclude[0] != "draft" {
+		t.Fatalf("expected exclusion draft, got %v", r.Exclude)
+	}
+	if r.Glob == nil || *r.Glob != "*.pdf" {
+		t.Fatalf("expected glob *.pdf, got %v", r.Glob)
+	}
+
+	if _, err := ParseReference(":!^"); err == nil {
+		t.Fatal("expected error for empty exclusion")
+	}
+}
+
+func TestNegatedTagFilter(t *testing.T) {
+	f := TagFilter{Tags: []string{"reviewed", "source/*"}, Negated: true}
+	if !f.Matches(nil) || !f.Matches([]string{"important"}) {
+		t.Fatal("expected files without the tags to match")
+	}
+	if f.Matches([]string{"reviewed"}) || f.Matches([]string{"source/foia"}) {
+		t.Fatal("expected files with a tag to be rejected")
+	}
+}
+
+func TestExcluded(t *testing.T) {
+	if !Excluded([]string{"draft"}, "draft.docx") || !Excluded([]string{"draft"}, "drafts.txt") {
+		t.Fatal("expected names starting with draft to be excluded")
+	}
+	if Excluded([]string{"draft"}, "final-draft.docx") {
+		t.Fatal("did not expect a match outside the start of the name")
+	}
+	if !Excluded([]string{"*.tmp"}, "notes.tmp") {
+		t.Fatal("expected glob exclusion to match")
+	}
+}
@@ -0,0 +1,235 @@
+package reference
+
+import (
+	"cmp"
+	"fmt"
+	"path"
+	"strconv"
+	"strings"
+	"time"
+	"unicode"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// parsePredicateGroup reads the comma-separated predicates of a [...]
+// group; the opening bracket has already been consumed.
+func parsePredicateGroup(input string, pos *int) (PredicateFilter, error) {
+	end := strings.IndexByte(input[*pos:], ']')
+	if end < 0 {
+		return PredicateFilter{}, fmt.Errorf("unclosed '[' in reference")
+	}
+	body := input[*pos : *pos+end]
+	*pos += end + 1
+
+	var filter PredicateFilter
+	for _, part := range strings.Split(body, ",") {
+		p, err := parsePredicate(strings.TrimSpace(part))
+		if err != nil {
+			return PredicateFilter{}, err
+		}
+		filter.Predicates = append(filter.Predicates, p)
+	}
+	return filter, nil
+}
+
+func parsePredicate(s string) (Predicate, error) {
+	opAt := strings.IndexAny(s, "<>=!")
+	if opAt <= 0 {
+		return Predicate{}, fmt.Errorf("expected a comparison in '[%s]', such as size>10M", s)
+	}
+	op := s[opAt : opAt+1]
+	if strings.HasPrefix(s[opAt+1:], "=") {
+		op += "="
+	}
+	if op == "!" || op == "==" {
+		return Predicate{}, fmt.Errorf("unknown operator in '[%s]' (expected =, !=, <, <=, > or >=)", s)
+	}
+	p := Predicate{
+		Field: strings.TrimSpace(s[:opAt]),
+		Op:    op,
+		Value: strings.TrimSpace(s[opAt+len(op):]),
+	}
+	if p.Value == "" {
+		return Predicate{}, fmt.Errorf("missing value in '[%s]'", s)
+	}
+
+	switch p.Field {
+	case "size":
+		if _, err := ParseSize(p.Value); err != nil {
+			return Predicate{}, err
+		}
+	case "ingested":
+		if _, _, err := parseWhen(p.Value); err != nil {
+			return Predicate{}, err
+		}
+	case "mime":
+		if op != "=" && op != "!=" {
+			return Predicate{}, fmt.Errorf("mime can only be compared with = or !=")
+		}
+		if _, err := path.Match(p.Value, ""); err != nil {
+			return Predicate{}, fmt.Errorf("bad mime pattern '%s': %w", p.Value, err)
+		}
+	default:
+		key, ok := strings.CutPrefix(p.Field, attrPrefix)
+		if !ok {
+			return Predicate{}, fmt.Errorf("unknown field '%s' in '[%s]' (expected size, ingested, mime or attr.<key>)", p.Field, s)
+		}
+		if err := ValidateAttrKey(key); err != nil {
+			return Predicate{}, err
+		}
+		if op == "=" || op == "!=" {
+			if _, err := path.Match(p.Value, ""); err != nil {
+				return Predicate{}, fmt.Errorf("bad pattern '%s': %w", p.Value, err)
+			}
+		}
+	}
+	return p, nil
+}
+
+// attrPrefix marks a predicate field naming a file attribute, as in
+// attr.foia-request=2024-0117.
+const attrPrefix = "attr."
+
+// ValidateAttrKey checks an attribute key. Keys appear in predicates as
+// attr.<key>, so they are limited to letters, digits, '_', '-' and '.'.
+func ValidateAttrKey(key string) error {
+	if key == "" {
+		return fmt.Errorf("empty attribute key")
+	}
+	for _, ch := range key {
+		if !unicode.IsLetter(ch) && !unicode.IsDigit(ch) && !strings.ContainsRune("_-.", ch) {
+			return fmt.Errorf("attribute key '%s' contains '%c' (use letters, digits, '_', '-' and '.')", key, ch)
+		}
+	}
+	return nil
+}
+
+// ParseSize parses a byte count with an optional K, M, G or T suffix
+// (powers of 1024), as in 512K or 1.5G.
+func ParseSize(s string) (int64, error) {
+	num := strings.TrimSuffix(strings.ToUpper(s), "B")
+	mult := int64(1)
+	if n := len(num); n > 0 {
+		switch num[n-1] {
+		case 'K':
+			mult = 1 << 10
+		case 'M':
+			mult = 1 << 20
+		case 'G':
+			mult = 1 << 30
+		case 'T':
+			mult = 1 << 40
+		}
+		if mult > 1 {
+			num = num[:n-1]
+		}
+	}
+	v, err := strconv.ParseFloat(num, 64)
+	if err != nil || v < 0 {
+		return 0, fmt.Errorf("bad size '%s' (expected a number like 10M)", s)
+	}
+	return int64(v * float64(mult)), nil
+}
+
+// parseWhen parses a date or RFC 3339 timestamp. dateOnly reports that
+// comparisons should be made by calendar day.
+func parseWhen(s string) (t time.Time, dateOnly bool, err error) {
+	if t, err := time.Parse(time.DateOnly, s); err == nil {
+		return t, true, nil
+	}
+	if t, err := time.Parse(time.RFC3339, s); err == nil {
+		return t, false, nil
+	}
+	return time.Time{}, false, fmt.Errorf("bad date '%s' (expected YYYY-MM-DD or an RFC 3339 time)", s)
+}
+
+// PredicatesMatch reports whether a tracked file, with the attributes set
+// on it, passes every PredicateFilter. Predicates within a filter are OR'd;
+// filters are AND'd. A file lacking the field a predicate compares fails
+// it.
+func PredicatesMatch(filters []PredicateFilter, file *models.TrackedFile, attrs map[string]string) bool {
+	for _, filter := range filters {
+		ok := false
+		for _, p := range filter.Predicates {
+			if p.Matches(file, attrs) {
+				ok = true
+				break
+			}
+		}
+		if !ok {
+			return false
+		}
+	}
+	return true
+}
+
+// Matches reports whether a tracked file, with the attributes set on it,
+// satisfies the predicate.
+func (p Predicate) Matches(file *models.TrackedFile, attrs map[string]string) bool {
+	if file == nil {
+		return false
+	}
+	switch p.Field {
+	case "size":
+		want, err := ParseSize(p.Value)
+		if err != nil || file.Size == nil {
+			return false
+		}
+		return compare(p.Op, cmp.Compare(*file.Size, want))
+	case "ingested":
+		want, dateOnly, err := parseWhen(p.Value)
+		if err != nil {
+			return false
+		}
+		have, err := time.Parse(time.RFC3339, file.IngestedAt)
+		if err != nil {
+			return false
+		}
+		if dateOnly {
+			have, _ = time.Parse(time.DateOnly, have.UTC().Format(time.DateOnly))
+		}
+		return compare(p.Op, have.Compare(want))
+	case "mime":
+		if file.MimeType == nil {
+			return false
+		}
+		ok, _ := path.Match(p.Value, *file.MimeType)
+		return ok == (p.Op == "=")
+	}
+	key, ok := strings.CutPrefix(p.Field, attrPrefix)
+	have, set := attrs[key]
+	if !ok || !set {
+		return false
+	}
+	if p.Op == "=" || p.Op == "!=" {
+		ok, _ := path.Match(p.Value, have)
+		return ok == (p.Op == "=")
+	}
+	// Numbers are ordered as numbers, anything else as text.
+	if a, err := strconv.ParseFloat(have, 64); err == nil {
+		if b, err := strconv.ParseFloat(p.Value, 64); err == nil {
+			return compare(p.Op, cmp.Compare(a, b))
+		}
+	}
+	return compare(p.Op, strings.Compare(have, p.Value))
+}
+
+// compare applies op to the result of a three-way comparison.
+func compare(op string, c int) bool {
+	switch op {
+	case "=":
+		return c == 0
+	case "!=":
+		return c != 0
+	case "<":
+		return c < 0
+	case "<=":
+		return c <= 0
+	case ">":
+		return c > 0
+	case ">=":
+		return c >= 0
+	}
+	return false
+}
@@ -0,0 +1,79 @@
+package reference
+
+import (
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestParseSize(t *testing.T) {
+	cases := map[string]int64{
+		"100":  100,
+		"512K": 512 << 10,
+		"10M":  10 << 20,
+		"10mb": 10 << 20,
+		"1.5G": 3 << 29,
+	}
+	for in, want := range cases {
+		got, err := ParseSize(in)
+		if err != nil || got != want {
+			t.Errorf("ParseSize(%q) = %d, %v; want %d", in, got, err, want)
+		}
+	}
+	if _, err := ParseSize("-1"); err == nil {
+		t.Error("expected error for a negative size")
+	}
+}
+
+func TestPredicateMatches(t *testing.T) {
+	size := int64(20 << 20)
+	mime := "application/pdf"
+	file := &models.TrackedFile{Size: &size, MimeType: &mime, IngestedAt: "2025-03-04T15:00:00Z"}
+
+	cases := []struct {
+		pred Predicate
+		want bool
+	}{
+		{Predicate{"size", ">", "10M"}, true},
+		{Predicate{"size", "<", "10M"}, false},
+		{Predicate{"size", "<=", "20M"}, true},
+		{Predicate{"ingested", ">", "2025-01-01"}, true},
+		{Predicate{"ingested", "=", "2025-03-04"}, true},
+		{Predicate{"ingested", ">", "2025-03-04"}, false},
+		{Predicate{"ingested", "<", "2025-03-04T16:00:00Z"}, true},
+		{Predicate{"mime", "=", "application/pdf"}, true},
+		{Predicate{"mime", "=", "image/*"}, false},
+		{Predicate{"mime", "!=", "image/*"}, true},
+	}
+	for _, c := range cases {
+		if got := c.pred.Matches(file, nil); got != c.want {
+			t.Errorf("%s%s%s = %v, want %v", c.pred.Field, c.pred.Op, c.pred.Value, got, c.want)
+		}
+	}
+
+	if (Predicate{"size", ">", "0"}).Matches(&models.TrackedFile{}, nil) {
+		t.Error("a file without a recorded size should not match a size predicate")
+	}
+}
+
+func TestAttrPredicateMatches(t *testing.T) {
+	file := &models.TrackedFile{}
+	attrs := map[string]string{"foia-request": "2024-0117", "pages": "12"}
+
+	cases := []struct {
+		pred Predicate
+		want bool
+	}{
+		{Predicate{"attr.foia-request", "=", "2024-*"}, true},
+		{Predicate{"attr.foia-request", "!=", "2024-*"}, false},
+		{Predicate{"attr.pages", ">", "9"}, true},
+		{Predicate{"attr.pages", "<", "9"}, false},
+		{Predicate{"attr.foia-request", ">=", "2024-0100"}, true},
+		{Predicate{"attr.custodian", "!=", "x"}, false},
+	}
+	for _, c := range cases {
+		if got := c.pred.Matches(file, attrs); got != c.want {
+			t.Errorf("%s%s%s = %v, want %v", c.pred.Field, c.pred.Op, c.pred.Value, got, c.want)
+		}
+	}
+}
@@ -0,0 +1,47 @@
+package reference
+
+import "fmt"
+
+// SavedRefs finds the reference saved under a name, returning nil when
+// there is none. Project and workspace databases both provide it.
+type SavedRefs interface {
+	GetSavedRef(name string) (*string, error)
+}
+
+// maxSavedDepth bounds how many saved references may name one another
+// before expansion gives up, which also stops cycles.
+const maxSavedDepth = 8
+
+// ExpandSaved replaces a saved reference (:@name) with the reference saved
+// under its name, looking in each store in turn so earlier stores shadow
+// later ones. A saved reference may itself name another. Any other
+// reference is returned as is.
+func ExpandSaved(ref *Reference, stores ...SavedRefs) (*Reference, error) {
+	for depth := 0; ref.Kind == KindSaved; depth++ {
+		name := ref.Raw
+		if depth == maxSavedDepth {
+			return nil, fmt.Errorf("saved reference '@%s' nests too deeply", name)
+		}
+		raw, err := lookupSaved(name, stores)
+		if err != nil {
+			return nil, err
+		}
+		if ref, err = ParseReference(raw); err != nil {
+			return nil, fmt.Errorf("saved reference '@%s': %w", name, err)
+		}
+	}
+	return ref, nil
+}
+
+func lookupSaved(name string, stores []SavedRefs) (string, error) {
+	for _, s := range stores {
+		raw, err := s.GetSavedRef(name)
+		if err != nil {
+			return "", err
+		}
+		if raw != nil {
+			return *raw, nil
+		}
+	}
+	return "", fmt.Errorf("no saved reference '@%s' (see mkrk ref list)", name)
+}
@@ -0,0 +1,54 @@
+package reference
+
+import "testing"
+
+type savedMap map[string]string
+
+func (m savedMap) GetSavedRef(name string) (*string, error) {
+	if ref, ok := m[name]; ok {
+		return &ref, nil
+	}
+	return nil, nil
+}
+
+func TestParseSavedReference(t *testing.T) {
+	r, err := ParseReference(":@hot")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if r.Kind != KindSaved || r.Raw != "hot" {
+		t.Fatalf("expected saved reference hot, got %+v", r)
+	}
+	if _, err := ParseReference(":@hot!urgent"); err == nil {
+		t.Fatal("expected error for a saved reference name with reserved characters")
+	}
+}
+
+func TestExpandSaved(t *testing.T) {
+	project := savedMap{"hot": ":evidence!urgent", "alias": ":@hot"}
+	workspace := savedMap{"hot": ":.evidence", "wide": ":.notes"}
+
+	r, err := ExpandSaved(&Reference{Kind: KindSaved, Raw: "alias"}, project, workspace)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(r.Scope) != 1 || r.Scope[0].Names[0] != "evidence" || len(r.Tags) != 1 {
+		t.Fatalf("expected project's hot to win, got %+v", r)
+	}
+
+	r, err = ExpandSaved(&Reference{Kind: KindSaved, Raw: "wide"}, project, workspace)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !r.WorkspaceWide || r.Scope[0].Names[0] != "notes" {
+		t.Fatalf("expected workspace fallback, got %+v", r)
+	}
+
+	if _, err := ExpandSaved(&Reference{Kind: KindSaved, Raw: "missing"}, project, workspace); err == nil {
+		t.Fatal("expected error for an unknown name")
+	}
+	loop := savedMap{"a": ":@b", "b": ":@a"}
+	if _, err := ExpandSaved(&Reference{Kind: KindSaved, Raw: "a"}, loop); err == nil {
+		t.Fatal("expected error for saved references naming each other")
+	}
+}
@@ -7,6 +7,7 @@ const (
 	KindWorkspace ReferenceKind = iota // : prefix — widest available scope
 	KindContext                        // . prefix or bare — current context
 	KindBarePath                       // ./ or ../ — literal filesystem path
+	KindSaved                          // :@name — a saved reference
 )
 
 // Reference is a parsed query in the muckrake reference language.
@@ -18,8 +19,12 @@ type Reference struct {
 	WorkspaceWide bool
 	Scope         []ScopeLevel
 	Tags          []TagFilter
+	Predicates    []PredicateFilter
 	Glob          *string
-	Raw           string // original input for bare paths
+	// Exclude holds !^ patterns: files whose names start with a match are
+	// left out.
+	Exclude []string
+	Raw           string // original input for bare paths, name for saved refs
 }
 
 // ScopeLevel is one dot-separated level in a reference.
@@ -29,7 +34,24 @@ type ScopeLevel struct {
 }
 
 // TagFilter is a ! group. Tags within one filter use OR logic.
-// Multiple TagFilters use AND logic.
+// Multiple TagFilters use AND logic. A negated (!~) filter matches files
+// carrying none of its tags.
 type TagFilter struct {
-	Tags []string
+	Tags    []string
+	Negated bool
+}
+
+// PredicateFilter is a [...] group. Predicates within one filter use OR
+// logic. Multiple PredicateFilters use AND logic.
+type PredicateFilter struct {
+	Predicates []Predicate
+}
+
+// Predicate compares a field of a file's tracked record, or an attribute
+// set on it, with a value, as in size>10M, ingested>=2025-01-01,
+// mime=application/pdf or attr.foia-request=2024-0117.
+type Predicate struct {
+	Field string // size, ingested, mime or attr.<key>
+	Op    string // =, !=, <, <=, > or >=
+	Value string
 }
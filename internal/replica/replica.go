@@ -0,0 +1,415 @@
+// Package replica keeps the stores mkrk push and pull replicate projects
+// through: a local or mounted directory, or one on another machine over
+// SSH with nothing but a POSIX shell on the far side. A store holds file
+// content named by hash and, per project, an append-only log of the
+// records each push sent.
+package replica
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/url"
+	"os"
+	"os/exec"
+	"path"
+	"path/filepath"
+	"slices"
+	"strings"
+	"time"
+)
+
+// ErrNotFound is returned for a file the store does not hold.
+var ErrNotFound = errors.New("not in the store")
+
+// Transport reads and writes a store's files by slash-separated names
+// under its root.
+type Transport interface {
+	// List returns the names in dir, leaving out hidden ones, or none if
+	// dir does not exist.
+	List(dir string) ([]string, error)
+	// Get copies the file name to w, or returns ErrNotFound.
+	Get(name string, w io.Writer) error
+	// Put writes r to name. The file appears only once it is complete.
+	Put(name string, r io.Reader) error
+	// String names the store for messages.
+	String() string
+}
+
+// Open returns the transport for spec: ssh://[user@]host[:port]/path,
+// the scp-like [user@]host:path, or a local or mounted directory.
+// proxyCommand, when set, is given to ssh as its ProxyCommand.
+func Open(spec, proxyCommand string) (Transport, error) {
+	if host, port, root, ok := parseSSH(spec); ok {
+		if strings.HasPrefix(host, "-") {
+			return nil, fmt.Errorf("invalid SSH host %q", host)
+		}
+		return &SSH{Host: host, Port: port, Root: root, ProxyCommand: proxyCommand}, nil
+	}
+	abs, err := filepath.Abs(spec)
+	if err != nil {
+		return nil, err
+	}
+	return &Dir{Root: abs}, nil
+}
+
+// SSHHost returns the host an SSH store spec connects to, without any
+// user name, and whether spec names one at all.
+func SSHHost(spec string) (string, bool) {
+	host, _, _, ok := parseSSH(spec)
+	if !ok {
+		return "", false
+	}
+	if _, h, found := strings.Cut(host, "@"); found {
+		host = h
+	}
+	return host, true
+}
+
+func parseSSH(spec string) (host, port, root string, ok bool) {
+	if strings.HasPrefix(spec, "ssh://") {
+		u, err := url.Parse(spec)
+		if err != nil || u.Hostname() == "" || u.Path == "" {
+			return "", "", "", false
+		}
+		host = u.Hostname()
+		if u.User != nil {
+			host = u.User.Username() + "@" + host
+		}
+		// ssh://host/~/path is relative to the remote home, as with git.
+		root = u.Path
+		if rest, found := strings.CutPrefix(root, "/~/"); found {
+			root = rest
+		}
+		return host, u.Port(), root, true
+	}
+	host, root, found := strings.Cut(spec, ":")
+	if !found || host == "" || strings.ContainsAny(host, `/\`) || filepath.VolumeName(spec) != "" {
+		return "", "", "", false
+	}
+	if root == "" {
+		root = "."
+	}
+	return host, "", root, true
+}
+
+// --- Directory ---
+
+// Dir is a store in a local or mounted directory.
+type Dir struct {
+	Root string
+}
+
+func (d *Dir) String() string { return d.Root }
+
+func (d *Dir) path(name string) string {
+	return filepath.Join(d.Root, filepath.FromSlash(name))
+}
+
+func (d *Dir) List(dir string) ([]string, error) {
+	entries, err := os.ReadDir(d.path(dir))
+	if errors.Is(err, os.ErrNotExist) {
+		return nil, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	var names []string
+	for _, e := range entries {
+		if !strings.HasPrefix(e.Name(), ".") {
+			names = append(names, e.Name())
+		}
+	}
+	return names, nil
+}
+
+func (d *Dir) Get(name string, w io.Writer) error {
+	f, err := os.Open(d.path(name))
+	if errors.Is(err, os.ErrNotExist) {
+		return ErrNotFound
+	}
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	_, err = io.Copy(w, f)
+	return err
+}
+
+func (d *Dir) Put(name string, r io.Reader) error {
+	dst := d.path(name)
+	if err := os.MkdirAll(filepath.Dir(dst), 0o755); err != nil {
+		return err
+	}
+	tmp, err := os.CreateTemp(filepath.Dir(dst), ".put-*")
+	if err != nil {
+		return err
+	}
+	defer os.Remove(tmp.Name())
+	_, err = io.Copy(tmp, r)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return err
+	}
+	if err := os.Chmod(tmp.Name(), 0o644); err != nil {
+		return err
+	}
+	return os.Rename(tmp.Name(), dst)
+}
+
+// --- SSH ---
+
+// SSH is a store in a directory on another machine, reached by running
+// ssh and a few shell commands there. Root is relative to the remote home
+// unless absolute.
+type SSH struct {
+	Host         string
+	Port         string
+	Root         string
+	ProxyCommand string
+}
+
+// notFoundStatus is the exit status the remote script gives for a
+// missing file, which no transport failure shares with it.
+const notFoundStatus = 3
+
+func (s *SSH) String() string { return s.Host + ":" + s.Root }
+
+func (s *SSH) path(name string) string {
+	return path.Join(s.Root, name)
+}
+
+func (s *SSH) List(dir string) ([]string, error) {
+	p := shellQuote(s.path(dir))
+	var out bytes.Buffer
+	if err := s.run("if [ -d "+p+" ]; then ls -1 -- "+p+"; fi", nil, &out); err != nil {
+		return nil, err
+	}
+	var names []string
+	for line := range strings.Lines(out.String()) {
+		if name := strings.TrimRight(line, "\n"); name != "" {
+			names = append(names, name)
+		}
+	}
+	return names, nil
+}
+
+func (s *SSH) Get(name string, w io.Writer) error {
+	p := shellQuote(s.path(name))
+	return s.run(fmt.Sprintf("[ -f %s ] || exit %d; cat -- %s", p, notFoundStatus, p), nil, w)
+}
+
+func (s *SSH) Put(name string, r io.Reader) error {
+	p := s.path(name)
+	dir := shellQuote(path.Dir(p))
+	script := "mkdir -p -- " + dir + " && t=$(mktemp " + dir + "/.put-XXXXXX) && " +
+		"{ cat > \"$t\" && chmod 644 \"$t\" && mv -f -- \"$t\" " + shellQuote(p) + " || { rm -f -- \"$t\"; exit 1; }; }"
+	return s.run(script, r, io.Discard)
+}
+
+// run runs script on the remote host through its login shell, with stdin
+// and stdout connected to the given reader and writer.
+func (s *SSH) run(script string, stdin io.Reader, stdout io.Writer) error {
+	args := []string{"-T", "-o", "ClearAllForwardings=yes"}
+	if s.Port != "" {
+		args = append(args, "-p", s.Port)
+	}
+	if s.ProxyCommand != "" {
+		args = append(args, "-o", "ProxyCommand="+s.ProxyCommand)
+	}
+	args = append(args, s.Host, script)
+	cmd := exec.Command("ssh", args...)
+	cmd.Stdin = stdin
+	cmd.Stdout = stdout
+	var stderr bytes.Buffer
+	cmd.Stderr = &stderr
+	err := cmd.Run()
+	var exit *exec.ExitError
+	if errors.As(err, &exit) && exit.ExitCode() == notFoundStatus {
+		return ErrNotFound
+	}
+	if err != nil {
+		if msg := strings.TrimSpace(stderr.String()); msg != "" {
+			return fmt.Errorf("ssh %s: %s", s.Host, msg)
+		}
+		return fmt.Errorf("ssh %s: %w", s.Host, err)
+	}
+	return nil
+}
+
+// shellQuote quotes s as one word for a POSIX shell.
+func shellQuote(s string) string {
+	return "'" + strings.ReplaceAll(s, "'", `'\''`) + "'"
+}
+
+// --- Store ---
+
+// marker names the file that makes a directory a store, and markerContent
+// is what it holds, naming the layout's version.
+const (
+	marker        = "mkrk-replica"
+	markerContent = "mkrk replica 1\n"
+)
+
+// Init makes t a store unless it already is one. A directory that holds
+// anything else is refused rather than filled.
+func Init(t Transport) error {
+	names, err := t.List("")
+	if err != nil {
+		return err
+	}
+	if slices.Contains(names, marker) {
+		return Check(t)
+	}
+	if len(names) > 0 {
+		return fmt.Errorf("%s is neither empty nor a replica store", t)
+	}
+	return t.Put(marker, strings.NewReader(markerContent))
+}
+
+// Check returns an error unless t is a store in a layout this version
+// reads.
+func Check(t Transport) error {
+	var buf bytes.Buffer
+	if err := t.Get(marker, &buf); errors.Is(err, ErrNotFound) {
+		return fmt.Errorf("no workspace or replica store at %s", t)
+	} else if err != nil {
+		return err
+	}
+	if buf.String() != markerContent {
+		return fmt.Errorf("%s is a replica store in a layout this version does not read", t)
+	}
+	return nil
+}
+
+// ObjectName is the name the store keeps content with the given hash
+// under: the hash of the bytes as they are on disk, encrypted or not.
+func ObjectName(hash string) string {
+	return "objects/" + hash
+}
+
+// Objects returns the hashes of the content the store holds.
+func Objects(t Transport) (map[string]bool, error) {
+	names, err := t.List("objects")
+	if err != nil {
+		return nil, err
+	}
+	held := make(map[string]bool, len(names))
+	for _, n := range names {
+		held[n] = true
+	}
+	return held, nil
+}
+
+// Projects returns the names of the projects the store keeps a log for.
+func Projects(t Transport) ([]string, error) {
+	return t.List("projects")
+}
+
+// Segment is one entry in a project's log: the records one push sent, as
+// a project database named by its SHA-256, and where each file goes.
+type Segment struct {
+	// State summarizes the project when it was pushed, so a push that
+	// would repeat the last entry is skipped.
+	State    string `json:"state"`
+	Records  string `json:"records"`
+	PushedAt string `json:"pushed_at"`
+	Files    []File `json:"files"`
+}
+
+// File is a tracked file in a segment: its path in the project, the hash
+// that identifies it, and the hash of the object holding its bytes, which
+// differs for a file kept encrypted.
+type File struct {
+	Path   string `json:"path"`
+	Hash   string `json:"hash"`
+	Object string `json:"object"`
+}
+
+func logDir(project string) string {
+	return "projects/" + project + "/log"
+}
+
+func recordsName(project, hash string) string {
+	return "projects/" + project + "/records/" + hash + ".mkrk"
+}
+
+// Log returns the names of the segments in project's log, oldest first.
+func Log(t Transport, project string) ([]string, error) {
+	names, err := t.List(logDir(project))
+	if err != nil {
+		return nil, err
+	}
+	var segments []string
+	for _, n := range names {
+		if strings.HasSuffix(n, ".json") {
+			segments = append(segments, n)
+		}
+	}
+	slices.Sort(segments)
+	return segments, nil
+}
+
+// ReadSegment fetches the segment name from project's log and checks it
+// against the SHA-256 its name ends with.
+func ReadSegment(t Transport, project, name string) (*Segment, error) {
+	var buf bytes.Buffer
+	if err := t.Get(logDir(project)+"/"+name, &buf); err != nil {
+		return nil, fmt.Errorf("log entry %s: %w", name, err)
+	}
+	_, want, _ := strings.Cut(strings.TrimSuffix(name, ".json"), "-")
+	sum := sha256.Sum256(buf.Bytes())
+	if hex.EncodeToString(sum[:]) != want {
+		return nil, fmt.Errorf("log entry %s does not match its hash", name)
+	}
+	var seg Segment
+	if err := json.Unmarshal(buf.Bytes(), &seg); err != nil {
+		return nil, fmt.Errorf("log entry %s: %w", name, err)
+	}
+	return &seg, nil
+}
+
+// AppendSegment adds seg to the end of project's log and returns its
+// name: the time it was written, then its SHA-256. Entries are never
+// rewritten, so concurrent pushes each add their own.
+func AppendSegment(t Transport, project string, seg *Segment) (string, error) {
+	data, err := json.MarshalIndent(seg, "", "  ")
+	if err != nil {
+		return "", err
+	}
+	sum := sha256.Sum256(data)
+	name := fmt.Sprintf("%020d-%s.json", time.Now().UnixNano(), hex.EncodeToString(sum[:]))
+	return name, t.Put(logDir(project)+"/"+name, bytes.NewReader(data))
+}
+
+// PutRecords sends the project database at path as records for project's
+// log and returns its SHA-256.
+func PutRecords(t Transport, project, path string) (string, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return "", err
+	}
+	sum := sha256.Sum256(data)
+	hash := hex.EncodeToString(sum[:])
+	return hash, t.Put(recordsName(project, hash), bytes.NewReader(data))
+}
+
+// GetRecords fetches the records with the given SHA-256 from project's
+// log into dst, failing if they do not match it.
+func GetRecords(t Transport, project, hash, dst string) error {
+	var buf bytes.Buffer
+	if err := t.Get(recordsName(project, hash), &buf); err != nil {
+		return fmt.Errorf("records %s: %w", hash, err)
+	}
+	sum := sha256.Sum256(buf.Bytes())
+	if hex.EncodeToString(sum[:]) != hash {
+		return fmt.Errorf("records %s do not match their hash", hash)
+	}
+	return os.WriteFile(dst, buf.Bytes(), 0o600)
+}
@@ -0,0 +1,131 @@
+package replica
+
+import (
+	"bytes"
+	"errors"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestOpenParsesSpecs(t *testing.T) {
+	tests := []struct {
+		spec             string
+		host, port, root string
+	}{
+		{"ssh://desktop/srv/store", "desktop", "", "/srv/store"},
+		{"ssh://alice@desktop:2222/~/store", "alice@desktop", "2222", "store"},
+		{"desktop:store", "desktop", "", "store"},
+		{"alice@desktop:/srv/store", "alice@desktop", "", "/srv/store"},
+	}
+	for _, tt := range tests {
+		tr, err := Open(tt.spec, "")
+		if err != nil {
+			t.Fatalf("%s: %v", tt.spec, err)
+		}
+		s, ok := tr.(*SSH)
+		if !ok {
+			t.Fatalf("%s: opened as %T, want SSH", tt.spec, tr)
+		}
+		if s.Host != tt.host || s.Port != tt.port || s.Root != tt.root {
+			t.Errorf("%s: got host %q port %q root %q", tt.spec, s.Host, s.Port, s.Root)
+		}
+	}
+
+	for _, spec := range []string{"store", "./a:b", "/mnt/store"} {
+		tr, err := Open(spec, "")
+		if err != nil {
+			t.Fatalf("%s: %v", spec, err)
+		}
+		if _, ok := tr.(*Dir); !ok {
+			t.Errorf("%s: opened as %T, want Dir", spec, tr)
+		}
+	}
+
+	if _, err := Open("-oProxyCommand=x:store", ""); err == nil {
+		t.Error("a host starting with '-' should be refused")
+	}
+	if host, _ := SSHHost("ssh://alice@desktop:2222/store"); host != "desktop" {
+		t.Errorf("SSHHost: got %q", host)
+	}
+}
+
+func TestDirStore(t *testing.T) {
+	store := &Dir{Root: t.TempDir()}
+	if err := Check(store); err == nil {
+		t.Fatal("an empty directory should not pass as a store")
+	}
+	if err := Init(store); err != nil {
+		t.Fatal(err)
+	}
+	if err := Init(store); err != nil {
+		t.Fatalf("Init on an existing store: %v", err)
+	}
+
+	if err := store.Put(ObjectName("abc"), strings.NewReader("content")); err != nil {
+		t.Fatal(err)
+	}
+	var buf bytes.Buffer
+	if err := store.Get(ObjectName("abc"), &buf); err != nil || buf.String() != "content" {
+		t.Fatalf("Get: %q, %v", buf.String(), err)
+	}
+	if err := store.Get(ObjectName("missing"), &buf); !errors.Is(err, ErrNotFound) {
+		t.Fatalf("Get of a missing object: %v", err)
+	}
+	objects, err := Objects(store)
+	if err != nil || len(objects) != 1 || !objects["abc"] {
+		t.Fatalf("Objects: %v, %v", objects, err)
+	}
+
+	other := t.TempDir()
+	os.WriteFile(filepath.Join(other, "notes.txt"), []byte("x"), 0o644)
+	if err := Init(&Dir{Root: other}); err == nil {
+		t.Fatal("a directory holding other files should not become a store")
+	}
+}
+
+func TestLogChecksHashes(t *testing.T) {
+	store := &Dir{Root: t.TempDir()}
+	if err := Init(store); err != nil {
+		t.Fatal(err)
+	}
+
+	records := filepath.Join(t.TempDir(), "records.mkrk")
+	os.WriteFile(records, []byte("records"), 0o644)
+	hash, err := PutRecords(store, "bailey", records)
+	if err != nil {
+		t.Fatal(err)
+	}
+	seg := &Segment{State: "s1", Records: hash, Files: []File{{Path: "a.txt", Hash: "h", Object: "h"}}}
+	name, err := AppendSegment(store, "bailey", seg)
+	if err != nil {
+		t.Fatal(err)
+	}
+
+	log, err := Log(store, "bailey")
+	if err != nil || len(log) != 1 || log[0] != name {
+		t.Fatalf("Log: %v, %v", log, err)
+	}
+	got, err := ReadSegment(store, "bailey", name)
+	if err != nil || got.State != "s1" || len(got.Files) != 1 {
+		t.Fatalf("ReadSegment: %+v, %v", got, err)
+	}
+	dst := filepath.Join(t.TempDir(), "got.mkrk")
+	if err := GetRecords(store, "bailey", hash, dst); err != nil {
+		t.Fatal(err)
+	}
+	if projects, _ := Projects(store); len(projects) != 1 || projects[0] != "bailey" {
+		t.Fatalf("Projects: %v", projects)
+	}
+
+	// Altered entries and records are refused.
+	os.WriteFile(store.path(logDir("bailey")+"/"+name), []byte(`{"state":"forged"}`), 0o644)
+	if _, err := ReadSegment(store, "bailey", name); err == nil {
+		t.Error("an altered log entry should be refused")
+	}
+	os.WriteFile(store.path(recordsName("bailey", hash)), []byte("forged"), 0o644)
+	if err := GetRecords(store, "bailey", hash, dst); err == nil {
+		t.Error("altered records should be refused")
+	}
+}
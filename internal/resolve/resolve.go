@@ -5,7 +5,6 @@ import (
 	"path/filepath"
 
 	"go.foia.dev/muckrake/internal/context"
-	"go.foia.dev/muckrake/internal/integrity"
 	"go.foia.dev/muckrake/internal/models"
 	"go.foia.dev/muckrake/internal/reference"
 	"go.foia.dev/muckrake/internal/walk"
@@ -44,6 +43,10 @@ func RefRelPaths(ctx *context.Context, raw string) ([]string, error) {
 // (Context.Subject input), so tags and globs are handled the same way for
 // both paths.
 func FromReference(ctx *context.Context, ref *reference.Reference) ([]string, error) {
+	ref, err := reference.ExpandSaved(ref, ctx.SavedRefs()...)
+	if err != nil {
+		return nil, err
+	}
 	if ref.Kind == reference.KindBarePath {
 		return []string{ref.Raw}, nil
 	}
@@ -63,10 +66,10 @@ func FromReference(ctx *context.Context, ref *reference.Reference) ([]string, er
 
 	var rels []string
 	for _, relPath := range entries {
-		if !globMatches(ref, relPath) {
+		if !globMatches(ref, relPath) || reference.Excluded(ref.Exclude, filepath.Base(relPath)) {
 			continue
 		}
-		if !tagFiltersMatch(ctx, relPath, ref.Tags) {
+		if !recordFiltersMatch(ctx, relPath, ref) {
 			continue
 		}
 		rels = append(rels, relPath)
@@ -74,45 +77,47 @@ func FromReference(ctx *context.Context, ref *reference.Reference) ([]string, er
 	return rels, nil
 }
 
-// tagFiltersMatch reports whether the file at relPath passes every TagFilter.
-// Tags within a single filter are OR'd; filters are AND'd. Untracked files
-// have no tags, so any present filter rejects them.
-func tagFiltersMatch(ctx *context.Context, relPath string, filters []reference.TagFilter) bool {
-	if len(filters) == 0 {
+// recordFiltersMatch reports whether the file at relPath passes every
+// TagFilter and PredicateFilter of ref. Within a filter, tags and
+// predicates are OR'd; filters are AND'd. Untracked files have no record:
+// they carry no tags, so only negated tag filters can pass them, and they
+// fail every predicate.
+func recordFiltersMatch(ctx *context.Context, relPath string, ref *reference.Reference) bool {
+	if len(ref.Tags) == 0 && len(ref.Predicates) == 0 {
 		return true
 	}
+	var tags []string
+	var attrs map[string]string
 	absPath := filepath.Join(ctx.ProjectRoot, relPath)
-	hash, err := integrity.HashFile(absPath)
+	hash, err := ctx.HashFile(absPath)
 	if err != nil {
 		return false
 	}
 	file, err := ctx.ProjectDb.GetFileByHash(hash)
-	if err != nil || file == nil || file.ID == nil {
-		return false
-	}
-	tags, err := ctx.ProjectDb.GetTags(*file.ID)
 	if err != nil {
 		return false
 	}
-	tagSet := make(map[string]bool, len(tags))
-	for _, t := range tags {
-		tagSet[t] = true
-	}
-	for _, group := range filters {
-		if !anyTagMatch(group.Tags, tagSet) {
+	if file != nil && file.ID != nil {
+		if tags, err = ctx.ProjectDb.GetTags(*file.ID); err != nil {
 			return false
 		}
+		if len(ref.Predicates) > 0 {
+			if attrs, err = ctx.ProjectDb.GetFileAttrs(*file.ID); err != nil {
+				return false
+			}
+		}
+	} else {
+		file = nil
 	}
-	return true
-}
-
-func anyTagMatch(candidates []string, have map[string]bool) bool {
-	for _, t := range candidates {
-		if have[t] {
-			return true
+	if !reference.PredicatesMatch(ref.Predicates, file, attrs) {
+		return false
+	}
+	for _, group := range ref.Tags {
+		if !group.Matches(tags) {
+			return false
 		}
 	}
-	return false
+	return true
 }
 
 func patternsForRef(ctx *context.Context, ref *reference.Reference) ([]string, error) {
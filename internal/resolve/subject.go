@@ -48,12 +48,12 @@ func SubjectRelPaths(ctx *context.Context) ([]string, error) {
 }
 
 // isProjectOnly reports whether a subject names only a project (or the
-// workspace root), with no scope/tag/glob narrower than that.
+// workspace root), with no scope/tag/predicate/glob narrower than that.
 func isProjectOnly(r *reference.Reference) bool {
 	if r == nil {
 		return true
 	}
-	if len(r.Tags) > 0 || r.Glob != nil {
+	if len(r.Tags) > 0 || len(r.Predicates) > 0 || len(r.Exclude) > 0 || r.Glob != nil {
 		return false
 	}
 	switch r.Kind {
@@ -0,0 +1,482 @@
+package rules
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/models"
+	"go.foia.dev/muckrake/internal/reference"
+)
+
+// Event describes something that happened to a tracked file. SHA256 is
+// the hash of the tracked record, which for a modified file is the hash
+// it had before the change.
+type Event struct {
+	Trigger    models.TriggerEvent
+	SHA256     string
+	RelPath    string
+	Categories []string
+	Tags       []string
+	MimeType   *string
+	TagName    *string
+	Pipeline   *string
+	SignName   *string
+	State      *string
+	// Ref is the file's reference, for messages; a missing file has none,
+	// since the database does not record paths. Status and Diff describe a
+	// verify_failure: "modified" or "missing", and which chunks changed.
+	Ref    string
+	Status string
+	Diff   string
+	// Workspace, when set, contributes its rules after the project's own.
+	// A project rule of the same name overrides a workspace rule.
+	Workspace *db.WorkspaceDb
+	// RunTool runs the tool a run_tool action names on the event's file.
+	// Callers that can run tools set it; without it the action fails.
+	RunTool func(tool string, ev *Event) (string, error)
+	// Root and Project are the project's directory and name, for filling
+	// in a shell action's command.
+	Root    string
+	Project string
+	// RunShell runs a shell action's filled-in command. Callers that can
+	// run commands set it; without it the action fails.
+	RunShell func(command string, ev *Event) error
+	// Post delivers a webhook action's payload to url. Callers that can
+	// reach the network set it; without it the action fails.
+	Post func(url string, payload []byte) (string, error)
+	// Archive snapshots the page the file's provenance says it was fetched
+	// from into category, or the default one when nil. Callers that can
+	// reach the network set it; without it the action fails.
+	Archive func(file *models.TrackedFile, category *string) (string, error)
+}
+
+// Firing records one rule whose filter matched an event.
+type Firing struct {
+	Rule   string
+	Action models.ActionType
+	Detail string
+	Err    error
+}
+
+// SuppressEnv, when set to any value, stops Fire from firing rules in
+// this process and the mkrk commands it starts. mkrk foreach sets it
+// unless asked to fire rules.
+const SuppressEnv = "MKRK_SUPPRESS_RULES"
+
+// AllowShellSetting is the project setting that must be "true" for shell
+// actions to run. A shell rule can run anything, so it stays off until the
+// project opts in.
+const AllowShellSetting = "allow-shell-actions"
+
+// shellPlaceholders are replaced in a shell action's command with the
+// event's details, each quoted as a single word.
+var shellPlaceholders = []string{"{path}", "{name}", "{tag}", "{project}"}
+
+// webhookPlaceholders are replaced in a webhook action's payload template
+// with the event's details, escaped to sit inside a JSON string. Without a
+// template the payload is a JSON object of the fields that are set.
+var webhookPlaceholders = []string{"{rule}", "{trigger}", "{project}", "{name}", "{ref}", "{sha256}", "{tag}", "{status}", "{diff}"}
+
+// Fire evaluates every enabled rule for the event's trigger, in priority
+// order with the workspace's after the project's, and applies the actions
+// of those whose filter matches. Each rule fires at most once per event;
+// actions taken here never emit further events, so rules cannot chain into
+// loops.
+func Fire(pdb *db.ProjectDb, ev *Event) ([]Firing, error) {
+	if os.Getenv(SuppressEnv) != "" {
+		return nil, nil
+	}
+	return evaluate(pdb, ev, false)
+}
+
+// Preview reports which rules would fire for the event and what they
+// would do, without applying actions or writing to the audit log.
+func Preview(pdb *db.ProjectDb, ev *Event) ([]Firing, error) {
+	return evaluate(pdb, ev, true)
+}
+
+func evaluate(pdb *db.ProjectDb, ev *Event, dry bool) ([]Firing, error) {
+	rules, err := pdb.ListRulesForTrigger(ev.Trigger)
+	if err != nil {
+		return nil, err
+	}
+	if ev.Workspace != nil {
+		inherited, err := ev.Workspace.ListRulesForTrigger(ev.Trigger)
+		if err != nil {
+			return nil, err
+		}
+		for _, r := range inherited {
+			if override, _ := pdb.GetRuleByName(r.Name); override == nil {
+				rules = append(rules, r)
+			}
+		}
+	}
+
+	var file *models.TrackedFile
+	if ev.SHA256 != "" {
+		file, _ = pdb.GetFileByHash(ev.SHA256)
+	}
+
+	var firings []Firing
+	for i := range rules {
+		r := &rules[i]
+		if !Matches(&r.TriggerFilter, ev) {
+			continue
+		}
+		if dry {
+			firings = append(firings, Firing{Rule: r.Name, Action: r.ActionType, Detail: "would " + Describe(r)})
+			continue
+		}
+		detail, err := apply(pdb, r, ev, file)
+		firings = append(firings, Firing{Rule: r.Name, Action: r.ActionType, Detail: detail, Err: err})
+		recordFiring(pdb, r, ev, file, detail, err)
+	}
+	return firings, nil
+}
+
+// Matches reports whether every field set on the filter is satisfied by
+// the event.
+func Matches(f *models.TriggerFilter, ev *Event) bool {
+	return len(Mismatches(f, ev)) == 0
+}
+
+// Mismatches describes each field set on the filter that the event does
+// not satisfy.
+func Mismatches(f *models.TriggerFilter, ev *Event) []string {
+	var out []string
+	if f.TagName != nil {
+		if ev.TagName != nil {
+			if !reference.TagMatches(*f.TagName, *ev.TagName) {
+				out = append(out, fmt.Sprintf("tag %s (event tag is %s)", *f.TagName, *ev.TagName))
+			}
+		} else if !anyTagMatches(ev.Tags, *f.TagName) {
+			out = append(out, fmt.Sprintf("tag %s (file has %s)", *f.TagName, listOrNone(ev.Tags)))
+		}
+	}
+	if f.Category != nil && !contains(ev.Categories, *f.Category) {
+		out = append(out, fmt.Sprintf("category %s (file is in %s)", *f.Category, listOrNone(ev.Categories)))
+	}
+	if f.MimeType != nil {
+		actual := ""
+		if ev.MimeType != nil {
+			actual = *ev.MimeType
+		}
+		if !mimeMatches(*f.MimeType, actual) {
+			out = append(out, fmt.Sprintf("mime %s (file is %s)", *f.MimeType, orNone(actual)))
+		}
+	}
+	check := func(name string, want, got *string) {
+		if want != nil && (got == nil || *got != *want) {
+			actual := ""
+			if got != nil {
+				actual = *got
+			}
+			out = append(out, fmt.Sprintf("%s %s (event has %s)", name, *want, orNone(actual)))
+		}
+	}
+	check("pipeline", f.Pipeline, ev.Pipeline)
+	check("sign", f.SignName, ev.SignName)
+	check("state", f.State, ev.State)
+	return out
+}
+
+func listOrNone(list []string) string {
+	return orNone(strings.Join(list, ", "))
+}
+
+func orNone(s string) string {
+	if s == "" {
+		return "none"
+	}
+	return s
+}
+
+func apply(pdb *db.ProjectDb, r *models.Rule, ev *Event, file *models.TrackedFile) (string, error) {
+	cfg := &r.ActionConfig
+	switch r.ActionType {
+	case models.EventActionRunTool:
+		if cfg.Tool == nil {
+			return "", fmt.Errorf("run_tool requires a tool")
+		}
+		if ev.RunTool == nil {
+			return "", fmt.Errorf("tools cannot run on %s events", ev.Trigger)
+		}
+		return ev.RunTool(*cfg.Tool, ev)
+
+	case models.EventActionAddTag:
+		if file == nil || file.ID == nil {
+			return "", fmt.Errorf("no tracked file")
+		}
+		if cfg.Tag == nil {
+			return "", fmt.Errorf("add_tag requires a tag")
+		}
+		if err := pdb.InsertTag(*file.ID, *cfg.Tag, file.SHA256, file.Fingerprint); err != nil {
+			return "", err
+		}
+		return "+!" + *cfg.Tag, nil
+
+	case models.EventActionRemoveTag:
+		if file == nil || file.ID == nil {
+			return "", fmt.Errorf("no tracked file")
+		}
+		if cfg.Tag == nil {
+			return "", fmt.Errorf("remove_tag requires a tag")
+		}
+		if err := pdb.RemoveTag(*file.ID, *cfg.Tag); err != nil {
+			return "", err
+		}
+		return "-!" + *cfg.Tag, nil
+
+	case models.EventActionUnsign:
+		if file == nil || file.ID == nil {
+			return "", fmt.Errorf("no tracked file")
+		}
+		return unsign(pdb, *file.ID, cfg)
+
+	case models.EventActionShell:
+		if cfg.Command == nil {
+			return "", fmt.Errorf("shell requires a command")
+		}
+		if allowed, _ := pdb.GetConfig(AllowShellSetting); allowed == nil || *allowed != "true" {
+			return "", fmt.Errorf("shell actions are disabled (mkrk config %s true)", AllowShellSetting)
+		}
+		if ev.RunShell == nil {
+			return "", fmt.Errorf("commands cannot run on %s events", ev.Trigger)
+		}
+		command := expandCommand(*cfg.Command, ev)
+		return command, ev.RunShell(command, ev)
+
+	case models.EventActionWebhook:
+		if cfg.URL == nil {
+			return "", fmt.Errorf("webhook requires a url")
+		}
+		if ev.Post == nil {
+			return "", fmt.Errorf("webhooks cannot be sent on %s events", ev.Trigger)
+		}
+		return ev.Post(*cfg.URL, webhookPayload(r, ev))
+
+	case models.EventActionArchiveURL:
+		if file == nil || file.ID == nil {
+			return "", fmt.Errorf("no tracked file")
+		}
+		if ev.Archive == nil {
+			return "", fmt.Errorf("pages cannot be archived on %s events", ev.Trigger)
+		}
+		return ev.Archive(file, cfg.Category)
+
+	default:
+		return "", fmt.Errorf("action %s is not supported by event rules", r.ActionType)
+	}
+}
+
+// Describe summarizes what a rule's action does, for previews.
+func Describe(r *models.Rule) string {
+	cfg := &r.ActionConfig
+	switch r.ActionType {
+	case models.EventActionRunTool:
+		if cfg.Tool == nil {
+			return string(r.ActionType)
+		}
+		return "run " + *cfg.Tool
+	case models.EventActionAddTag, models.EventActionRemoveTag:
+		sign := "+"
+		if r.ActionType == models.EventActionRemoveTag {
+			sign = "-"
+		}
+		if cfg.Tag == nil {
+			return string(r.ActionType)
+		}
+		return sign + "!" + *cfg.Tag
+	case models.EventActionUnsign:
+		target := "all signs"
+		if cfg.SignName != nil {
+			target = "sign '" + *cfg.SignName + "'"
+		}
+		if cfg.Pipeline != nil {
+			target += " in " + *cfg.Pipeline
+		}
+		return "revoke " + target
+	case models.EventActionShell:
+		if cfg.Command == nil {
+			return string(r.ActionType)
+		}
+		return "run: " + *cfg.Command
+	case models.EventActionWebhook:
+		if cfg.URL == nil {
+			return string(r.ActionType)
+		}
+		return "post to " + *cfg.URL
+	case models.EventActionArchiveURL:
+		if cfg.Category == nil {
+			return "archive the source URL"
+		}
+		return "archive the source URL into " + *cfg.Category
+	default:
+		return string(r.ActionType)
+	}
+}
+
+// expandCommand fills in a shell command template's placeholders for the
+// event: the file's absolute path and name, the tag that triggered it, and
+// the project's name. Each value is quoted, so a file name cannot inject
+// shell syntax.
+func expandCommand(template string, ev *Event) string {
+	tag := ""
+	if ev.TagName != nil {
+		tag = *ev.TagName
+	}
+	values := []string{
+		filepath.Join(ev.Root, ev.RelPath),
+		filepath.Base(ev.RelPath),
+		tag,
+		ev.Project,
+	}
+	// One pass, so a value that happens to contain a placeholder is left
+	// alone.
+	var pairs []string
+	for i, p := range shellPlaceholders {
+		pairs = append(pairs, p, shellQuote(values[i]))
+	}
+	return strings.NewReplacer(pairs...).Replace(template)
+}
+
+// webhookPayload renders a webhook action's payload for the event: its
+// template with the placeholders filled in, or a JSON object of the
+// event's details.
+func webhookPayload(r *models.Rule, ev *Event) []byte {
+	tag := ""
+	if ev.TagName != nil {
+		tag = *ev.TagName
+	}
+	name := ""
+	if ev.RelPath != "" {
+		name = filepath.Base(ev.RelPath)
+	}
+	values := []string{r.Name, string(ev.Trigger), ev.Project, name, ev.Ref, ev.SHA256, tag, ev.Status, ev.Diff}
+
+	if r.ActionConfig.Payload == nil {
+		fields := make(map[string]string)
+		for i, p := range webhookPlaceholders {
+			if values[i] != "" {
+				fields[strings.Trim(p, "{}")] = values[i]
+			}
+		}
+		b, _ := json.Marshal(fields)
+		return b
+	}
+	var pairs []string
+	for i, p := range webhookPlaceholders {
+		quoted, _ := json.Marshal(values[i])
+		pairs = append(pairs, p, string(quoted[1:len(quoted)-1]))
+	}
+	return []byte(strings.NewReplacer(pairs...).Replace(*r.ActionConfig.Payload))
+}
+
+// shellQuote quotes s as a single sh word.
+func shellQuote(s string) string {
+	return "'" + strings.ReplaceAll(s, "'", `'\''`) + "'"
+}
+
+// unsign revokes active signs on a file, narrowed by the pipeline and sign
+// name in the action config when given.
+func unsign(pdb *db.ProjectDb, fileID int64, cfg *models.ActionConfig) (string, error) {
+	var pipelineID *int64
+	if cfg.Pipeline != nil {
+		p, err := pdb.GetPipelineByName(*cfg.Pipeline)
+		if err != nil {
+			return "", err
+		}
+		if p == nil {
+			return "", fmt.Errorf("pipeline '%s' not found", *cfg.Pipeline)
+		}
+		pipelineID = p.ID
+	}
+
+	signs, err := pdb.GetSignsForFile(fileID)
+	if err != nil {
+		return "", err
+	}
+	now := time.Now().UTC().Format(time.RFC3339)
+	var revoked int64
+	for _, s := range signs {
+		if s.ID == nil || s.RevokedAt != nil {
+			continue
+		}
+		if pipelineID != nil && s.PipelineID != *pipelineID {
+			continue
+		}
+		if cfg.SignName != nil && s.SignName != *cfg.SignName {
+			continue
+		}
+		n, err := pdb.RevokeSign(*s.ID, now)
+		if err != nil {
+			return "", err
+		}
+		revoked += n
+	}
+	return fmt.Sprintf("revoked %d sign(s)", revoked), nil
+}
+
+func recordFiring(pdb *db.ProjectDb, r *models.Rule, ev *Event, file *models.TrackedFile, detail string, err error) {
+	entry := map[string]string{
+		"rule":    r.Name,
+		"trigger": string(ev.Trigger),
+		"action":  string(r.ActionType),
+	}
+	// A shell action records its command as the rule wrote it: filled in,
+	// it would hold the file's path, which the database never stores.
+	if r.ActionType == models.EventActionShell && r.ActionConfig.Command != nil {
+		entry["command"] = *r.ActionConfig.Command
+	} else if detail != "" {
+		entry["result"] = detail
+	}
+	if err != nil {
+		entry["error"] = err.Error()
+	}
+	// Without HTML escaping, so a command's redirects read as written.
+	var b strings.Builder
+	enc := json.NewEncoder(&b)
+	enc.SetEscapeHTML(false)
+	enc.Encode(entry)
+	s := strings.TrimSuffix(b.String(), "\n")
+	var fileID *int64
+	if file != nil {
+		fileID = file.ID
+	}
+	pdb.InsertAudit("rule_fired", fileID, nil, &s)
+}
+
+// anyTagMatches reports whether any tag satisfies pattern, which may name
+// a namespace (source/*).
+func anyTagMatches(tags []string, pattern string) bool {
+	for _, t := range tags {
+		if reference.TagMatches(pattern, t) {
+			return true
+		}
+	}
+	return false
+}
+
+func contains(list []string, s string) bool {
+	for _, v := range list {
+		if v == s {
+			return true
+		}
+	}
+	return false
+}
+
+func mimeMatches(pattern, actual string) bool {
+	if pattern == "*" || pattern == actual {
+		return true
+	}
+	if prefix, ok := strings.CutSuffix(pattern, "/*"); ok {
+		return strings.HasPrefix(actual, prefix+"/")
+	}
+	return false
+}
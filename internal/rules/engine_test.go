@@ -0,0 +1,331 @@
+package rules
+
+import (
+	"strings"
+	"testing"
+
+	"go.foia.dev/muckrake/internal/db"
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func setupDb(t *testing.T) *db.ProjectDb {
+	t.Helper()
+	pdb, err := db.CreateProject(":memory:")
+	if err != nil {
+		t.Fatal(err)
+	}
+	t.Cleanup(func() { pdb.Close() })
+	return pdb
+}
+
+func strPtr(s string) *string { return &s }
+
+func TestFireAddsTagOnVerifyFailure(t *testing.T) {
+	pdb := setupDb(t)
+	fid, _ := pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:         "flag-tampered",
+		Enabled:      true,
+		TriggerEvent: models.TriggerVerifyFailure,
+		ActionType:   models.EventActionAddTag,
+		ActionConfig: models.ActionConfig{Tag: strPtr("tampered")},
+	})
+
+	firings, err := Fire(pdb, &Event{Trigger: models.TriggerVerifyFailure, SHA256: "abc"})
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(firings) != 1 || firings[0].Err != nil {
+		t.Fatalf("expected one clean firing, got %+v", firings)
+	}
+
+	tags, _ := pdb.GetTags(fid)
+	if len(tags) != 1 || tags[0] != "tampered" {
+		t.Fatalf("expected [tampered], got %v", tags)
+	}
+}
+
+func TestFireRespectsFilter(t *testing.T) {
+	pdb := setupDb(t)
+	pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:          "evidence-only",
+		Enabled:       true,
+		TriggerEvent:  models.TriggerVerifyFailure,
+		TriggerFilter: models.TriggerFilter{Category: strPtr("evidence")},
+		ActionType:    models.EventActionAddTag,
+		ActionConfig:  models.ActionConfig{Tag: strPtr("tampered")},
+	})
+
+	firings, _ := Fire(pdb, &Event{Trigger: models.TriggerVerifyFailure, SHA256: "abc", Categories: []string{"notes"}})
+	if len(firings) != 0 {
+		t.Fatalf("expected no firings outside evidence, got %+v", firings)
+	}
+
+	firings, _ = Fire(pdb, &Event{Trigger: models.TriggerVerifyFailure, SHA256: "abc", Categories: []string{"evidence"}})
+	if len(firings) != 1 {
+		t.Fatalf("expected one firing in evidence, got %+v", firings)
+	}
+}
+
+func TestPreviewDoesNotApply(t *testing.T) {
+	pdb := setupDb(t)
+	fid, _ := pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:         "flag-tampered",
+		Enabled:      true,
+		TriggerEvent: models.TriggerVerifyFailure,
+		ActionType:   models.EventActionAddTag,
+		ActionConfig: models.ActionConfig{Tag: strPtr("tampered")},
+	})
+
+	firings, err := Preview(pdb, &Event{Trigger: models.TriggerVerifyFailure, SHA256: "abc"})
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(firings) != 1 || firings[0].Detail != "would +!tampered" {
+		t.Fatalf("expected one previewed firing, got %+v", firings)
+	}
+
+	tags, _ := pdb.GetTags(fid)
+	if len(tags) != 0 {
+		t.Fatalf("preview must not apply actions, got tags %v", tags)
+	}
+	audit, _ := pdb.ListAudit(0)
+	if len(audit) != 0 {
+		t.Fatalf("preview must not write audit entries, got %d", len(audit))
+	}
+}
+
+func TestMatchesMimeWildcard(t *testing.T) {
+	f := &models.TriggerFilter{MimeType: strPtr("image/*")}
+	if !Matches(f, &Event{MimeType: strPtr("image/png")}) {
+		t.Fatal("expected image/png to match image/*")
+	}
+	if Matches(f, &Event{MimeType: strPtr("application/pdf")}) {
+		t.Fatal("expected application/pdf not to match image/*")
+	}
+}
+
+func TestFireRunsToolThroughCaller(t *testing.T) {
+	pdb := setupDb(t)
+	pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:          "ocr-scans",
+		Enabled:       true,
+		TriggerEvent:  models.TriggerIngest,
+		TriggerFilter: models.TriggerFilter{MimeType: strPtr("image/*")},
+		ActionType:    models.EventActionRunTool,
+		ActionConfig:  models.ActionConfig{Tool: strPtr("@ocr")},
+	})
+
+	var ran []string
+	runTool := func(tool string, ev *Event) (string, error) {
+		ran = append(ran, tool+" "+ev.RelPath)
+		return "ran", nil
+	}
+	ev := &Event{
+		Trigger:  models.TriggerIngest,
+		SHA256:   "abc",
+		RelPath:  "evidence/scan.png",
+		MimeType: strPtr("image/png"),
+		RunTool:  runTool,
+	}
+	firings, err := Fire(pdb, ev)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(firings) != 1 || firings[0].Err != nil || len(ran) != 1 || ran[0] != "@ocr evidence/scan.png" {
+		t.Fatalf("expected @ocr to run once, got %+v %v", firings, ran)
+	}
+
+	ev.RunTool = nil
+	firings, _ = Fire(pdb, ev)
+	if len(firings) != 1 || firings[0].Err == nil {
+		t.Fatalf("expected run_tool without a runner to fail, got %+v", firings)
+	}
+}
+
+func TestFireShellRequiresOptIn(t *testing.T) {
+	pdb := setupDb(t)
+	pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:         "notify",
+		Enabled:      true,
+		TriggerEvent: models.TriggerIngest,
+		ActionType:   models.EventActionShell,
+		ActionConfig: models.ActionConfig{Command: strPtr("notify-send {project} {name} {path}")},
+	})
+
+	var ran []string
+	ev := &Event{
+		Trigger: models.TriggerIngest,
+		SHA256:  "abc",
+		RelPath: "evidence/it's {tag}.pdf",
+		Root:    "/cases/leak",
+		Project: "leak",
+		RunShell: func(command string, ev *Event) error {
+			ran = append(ran, command)
+			return nil
+		},
+	}
+	firings, _ := Fire(pdb, ev)
+	if len(firings) != 1 || firings[0].Err == nil || len(ran) != 0 {
+		t.Fatalf("expected shell action to be refused without opt-in, got %+v %v", firings, ran)
+	}
+
+	pdb.SetConfig(AllowShellSetting, "true")
+	firings, _ = Fire(pdb, ev)
+	want := `notify-send 'leak' 'it'\''s {tag}.pdf' '/cases/leak/evidence/it'\''s {tag}.pdf'`
+	if len(firings) != 1 || firings[0].Err != nil || len(ran) != 1 || ran[0] != want {
+		t.Fatalf("expected %q to run once, got %+v %v", want, firings, ran)
+	}
+
+	entries, err := pdb.ListAudit(1)
+	if err != nil || len(entries) != 1 || entries[0].Detail == nil || !strings.Contains(*entries[0].Detail, `"command":"notify-send {project} {name} {path}"`) ||
+		strings.Contains(*entries[0].Detail, "evidence") {
+		t.Fatalf("expected the command template, and no path, in the audit log, got %+v", entries)
+	}
+}
+
+func TestFireWebhookRendersPayload(t *testing.T) {
+	pdb := setupDb(t)
+	pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:         "slack",
+		Enabled:      true,
+		TriggerEvent: models.TriggerIngest,
+		ActionType:   models.EventActionWebhook,
+		ActionConfig: models.ActionConfig{
+			URL:     strPtr("https://hooks.example.org/x"),
+			Payload: strPtr(`{"text": "{name} in {project}"}`),
+		},
+	})
+
+	var posted []string
+	ev := &Event{
+		Trigger: models.TriggerIngest,
+		SHA256:  "abc",
+		RelPath: `evidence/"quoted".pdf`,
+		Project: "leak",
+		Post: func(url string, payload []byte) (string, error) {
+			posted = append(posted, url+" "+string(payload))
+			return "posted", nil
+		},
+	}
+	firings, _ := Fire(pdb, ev)
+	want := `https://hooks.example.org/x {"text": "\"quoted\".pdf in leak"}`
+	if len(firings) != 1 || firings[0].Err != nil || len(posted) != 1 || posted[0] != want {
+		t.Fatalf("expected %q to be posted once, got %+v %v", want, firings, posted)
+	}
+
+	rule, _ := pdb.GetRuleByName("slack")
+	rule.ActionConfig.Payload = nil
+	got := string(webhookPayload(rule, ev))
+	if !strings.Contains(got, `"rule":"slack"`) || !strings.Contains(got, `"sha256":"abc"`) {
+		t.Fatalf("expected a JSON object of the event's details, got %s", got)
+	}
+}
+
+func TestFireArchiveURL(t *testing.T) {
+	pdb := setupDb(t)
+	pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+
+	pdb.InsertRule(&models.Rule{
+		Name:         "snapshot",
+		Enabled:      true,
+		TriggerEvent: models.TriggerIngest,
+		ActionType:   models.EventActionArchiveURL,
+		ActionConfig: models.ActionConfig{Category: strPtr("web")},
+	})
+
+	ev := &Event{Trigger: models.TriggerIngest, SHA256: "abc", RelPath: "evidence/a.html"}
+	firings, _ := Fire(pdb, ev)
+	if len(firings) != 1 || firings[0].Err == nil {
+		t.Fatalf("expected the action to fail without an archiver, got %+v", firings)
+	}
+
+	var archived []string
+	ev.Archive = func(file *models.TrackedFile, category *string) (string, error) {
+		archived = append(archived, file.SHA256+" "+*category)
+		return "archived", nil
+	}
+	firings, _ = Fire(pdb, ev)
+	if len(firings) != 1 || firings[0].Err != nil || len(archived) != 1 || archived[0] != "abc web" {
+		t.Fatalf("expected the file archived into web once, got %+v %v", firings, archived)
+	}
+}
+
+func TestWebhookPayloadCarriesVerifyFailure(t *testing.T) {
+	rule := &models.Rule{Name: "alert", ActionType: models.EventActionWebhook}
+	ev := &Event{
+		Trigger: models.TriggerVerifyFailure,
+		SHA256:  "abc",
+		RelPath: "evidence/memo.pdf",
+		Ref:     ":evidence/memo.pdf",
+		Status:  "modified",
+		Diff:    "2 chunk(s) changed, first at byte 0",
+	}
+	got := string(webhookPayload(rule, ev))
+	for _, want := range []string{`"ref":":evidence/memo.pdf"`, `"status":"modified"`, `"diff":"2 chunk(s) changed, first at byte 0"`} {
+		if !strings.Contains(got, want) {
+			t.Fatalf("expected %s in the payload, got %s", want, got)
+		}
+	}
+	if strings.Contains(got, `"tag"`) {
+		t.Fatalf("expected unset fields to be left out, got %s", got)
+	}
+}
+
+func TestMismatchesExplainsFilter(t *testing.T) {
+	f := &models.TriggerFilter{MimeType: strPtr("image/*"), Category: strPtr("evidence")}
+	got := Mismatches(f, &Event{MimeType: strPtr("application/pdf"), Categories: []string{"evidence"}})
+	if len(got) != 1 || got[0] != "mime image/* (file is application/pdf)" {
+		t.Fatalf("expected only the mime type to mismatch, got %v", got)
+	}
+}
+
+func TestFireInheritsWorkspaceRules(t *testing.T) {
+	pdb := setupDb(t)
+	pdb.InsertFile(&models.TrackedFile{SHA256: "abc", Fingerprint: "[]", IngestedAt: "2025-01-01T00:00:00Z"})
+	wdb, err := db.CreateWorkspace(":memory:")
+	if err != nil {
+		t.Fatal(err)
+	}
+	t.Cleanup(func() { wdb.Close() })
+
+	wdb.InsertRule(&models.Rule{
+		Name:         "mark-new",
+		Enabled:      true,
+		TriggerEvent: models.TriggerIngest,
+		ActionType:   models.EventActionAddTag,
+		ActionConfig: models.ActionConfig{Tag: strPtr("fresh")},
+	})
+	pdb.InsertRule(&models.Rule{
+		Name:         "mark-local",
+		Enabled:      true,
+		TriggerEvent: models.TriggerIngest,
+		Priority:     10,
+		ActionType:   models.EventActionAddTag,
+		ActionConfig: models.ActionConfig{Tag: strPtr("local")},
+	})
+
+	ev := &Event{Trigger: models.TriggerIngest, SHA256: "abc", Workspace: wdb}
+	firings, _ := Fire(pdb, ev)
+	if len(firings) != 2 || firings[0].Rule != "mark-local" || firings[1].Rule != "mark-new" {
+		t.Fatalf("expected the project rule, then the workspace rule, got %+v", firings)
+	}
+
+	// A disabled project rule of the same name switches the workspace one off
+	pdb.InsertRule(&models.Rule{Name: "mark-new", TriggerEvent: models.TriggerIngest, ActionType: models.EventActionAddTag})
+	firings, _ = Fire(pdb, ev)
+	if len(firings) != 1 || firings[0].Rule != "mark-local" {
+		t.Fatalf("expected the override to stop the workspace rule, got %+v", firings)
+	}
+}
@@ -0,0 +1,203 @@
+package rules
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"slices"
+	"strconv"
+	"strings"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+// Marshal writes rules as YAML, in the subset Unmarshal reads: a list
+// under "rules", each a rule's fields with its filter and action config as
+// nested maps. Strings are always double-quoted, so any value survives a
+// round trip.
+func Marshal(rules []models.Rule) []byte {
+	var b bytes.Buffer
+	if len(rules) == 0 {
+		b.WriteString("rules: []\n")
+		return b.Bytes()
+	}
+	b.WriteString("rules:\n")
+	for _, r := range rules {
+		fmt.Fprintf(&b, "  - name: %s\n", strconv.Quote(r.Name))
+		fmt.Fprintf(&b, "    enabled: %t\n", r.Enabled)
+		fmt.Fprintf(&b, "    on: %s\n", strconv.Quote(string(r.TriggerEvent)))
+		fmt.Fprintf(&b, "    priority: %d\n", r.Priority)
+		writeMap(&b, "filter", r.TriggerFilter)
+		fmt.Fprintf(&b, "    action: %s\n", strconv.Quote(string(r.ActionType)))
+		writeMap(&b, "config", r.ActionConfig)
+	}
+	return b.Bytes()
+}
+
+// writeMap writes the fields set on v, a filter or action config, as a
+// nested map keyed by their JSON names.
+func writeMap(b *bytes.Buffer, key string, v any) {
+	raw, _ := json.Marshal(v)
+	fields := make(map[string]string)
+	json.Unmarshal(raw, &fields)
+	if len(fields) == 0 {
+		return
+	}
+	fmt.Fprintf(b, "    %s:\n", key)
+	keys := make([]string, 0, len(fields))
+	for k := range fields {
+		keys = append(keys, k)
+	}
+	slices.Sort(keys)
+	for _, k := range keys {
+		fmt.Fprintf(b, "      %s: %s\n", k, strconv.Quote(fields[k]))
+	}
+}
+
+// Unmarshal reads rules written by Marshal, or by hand in the same shape.
+// Plain, single- and double-quoted scalars and comment lines are accepted;
+// other YAML is not.
+func Unmarshal(data []byte) ([]models.Rule, error) {
+	var rules []models.Rule
+	var filter, config map[string]string
+	var section map[string]string
+	itemIndent := -1
+
+	finish := func() error {
+		if itemIndent < 0 {
+			return nil
+		}
+		r := &rules[len(rules)-1]
+		if r.Name == "" {
+			return fmt.Errorf("rule %d has no name", len(rules))
+		}
+		if err := decodeMap(filter, &r.TriggerFilter); err != nil {
+			return fmt.Errorf("rule '%s' filter: %w", r.Name, err)
+		}
+		if err := decodeMap(config, &r.ActionConfig); err != nil {
+			return fmt.Errorf("rule '%s' config: %w", r.Name, err)
+		}
+		if r.TriggerEvent == "" || r.ActionType == "" {
+			return fmt.Errorf("rule '%s' needs both on and action", r.Name)
+		}
+		return nil
+	}
+
+	for n, line := range strings.Split(string(data), "\n") {
+		lineNo := n + 1
+		content := strings.TrimSpace(line)
+		if content == "" || strings.HasPrefix(content, "#") {
+			continue
+		}
+		if strings.HasPrefix(strings.TrimLeft(line, " "), "\t") {
+			return nil, fmt.Errorf("line %d: tabs are not allowed for indentation", lineNo)
+		}
+		indent := len(line) - len(strings.TrimLeft(line, " "))
+
+		if indent == 0 {
+			if content != "rules:" && content != "rules: []" {
+				return nil, fmt.Errorf("line %d: expected 'rules:'", lineNo)
+			}
+			continue
+		}
+		if rest, ok := strings.CutPrefix(content, "- "); ok {
+			if err := finish(); err != nil {
+				return nil, err
+			}
+			rules = append(rules, models.Rule{Enabled: true})
+			filter, config, section = map[string]string{}, map[string]string{}, nil
+			content = rest
+			indent += 2
+			itemIndent = indent
+		}
+		if itemIndent < 0 {
+			return nil, fmt.Errorf("line %d: expected a '- ' list item", lineNo)
+		}
+
+		key, raw, ok := strings.Cut(content, ":")
+		if !ok {
+			return nil, fmt.Errorf("line %d: expected 'key: value'", lineNo)
+		}
+		key = strings.TrimSpace(key)
+		value, err := scalar(raw)
+		if err != nil {
+			return nil, fmt.Errorf("line %d: %w", lineNo, err)
+		}
+
+		if indent > itemIndent {
+			if section == nil {
+				return nil, fmt.Errorf("line %d: unexpected indentation", lineNo)
+			}
+			section[key] = value
+			continue
+		}
+		section = nil
+
+		r := &rules[len(rules)-1]
+		switch key {
+		case "name":
+			r.Name = value
+		case "enabled":
+			if r.Enabled, err = strconv.ParseBool(value); err != nil {
+				return nil, fmt.Errorf("line %d: enabled must be true or false", lineNo)
+			}
+		case "on":
+			if r.TriggerEvent, err = models.ParseTriggerEvent(value); err != nil {
+				return nil, fmt.Errorf("line %d: %w", lineNo, err)
+			}
+		case "action":
+			if r.ActionType, err = models.ParseActionType(value); err != nil {
+				return nil, fmt.Errorf("line %d: %w", lineNo, err)
+			}
+		case "priority":
+			if r.Priority, err = strconv.Atoi(value); err != nil {
+				return nil, fmt.Errorf("line %d: priority must be a number", lineNo)
+			}
+		case "filter":
+			section = filter
+		case "config":
+			section = config
+		default:
+			return nil, fmt.Errorf("line %d: unknown rule field '%s'", lineNo, key)
+		}
+		if (key == "filter" || key == "config") && value != "" && value != "{}" {
+			return nil, fmt.Errorf("line %d: %s must be a nested map", lineNo, key)
+		}
+	}
+	if err := finish(); err != nil {
+		return nil, err
+	}
+	return rules, nil
+}
+
+// scalar reads a YAML scalar: double-quoted with escapes, single-quoted,
+// or plain up to a trailing comment.
+func scalar(raw string) (string, error) {
+	s := strings.TrimSpace(raw)
+	switch {
+	case strings.HasPrefix(s, `"`):
+		v, err := strconv.Unquote(s)
+		if err != nil {
+			return "", fmt.Errorf("bad double-quoted string %s", s)
+		}
+		return v, nil
+	case strings.HasPrefix(s, "'"):
+		if len(s) < 2 || !strings.HasSuffix(s, "'") {
+			return "", fmt.Errorf("bad single-quoted string %s", s)
+		}
+		return strings.ReplaceAll(s[1:len(s)-1], "''", "'"), nil
+	}
+	if i := strings.Index(s, " #"); i >= 0 {
+		s = strings.TrimSpace(s[:i])
+	}
+	return s, nil
+}
+
+// decodeMap fills a filter or action config from its fields, refusing
+// names it does not have.
+func decodeMap(fields map[string]string, v any) error {
+	raw, _ := json.Marshal(fields)
+	dec := json.NewDecoder(bytes.NewReader(raw))
+	dec.DisallowUnknownFields()
+	return dec.Decode(v)
+}
@@ -0,0 +1,58 @@
+package rules
+
+import (
+	"reflect"
+	"testing"
+
+	"go.foia.dev/muckrake/internal/models"
+)
+
+func TestMarshalRoundTrip(t *testing.T) {
+	in := []models.Rule{
+		{
+			Name:          "ocr-scans",
+			Enabled:       true,
+			TriggerEvent:  models.TriggerIngest,
+			TriggerFilter: models.TriggerFilter{MimeType: strPtr("image/*"), Category: strPtr("evidence")},
+			ActionType:    models.EventActionRunTool,
+			ActionConfig:  models.ActionConfig{Tool: strPtr("@ocr")},
+			Priority:      -2,
+		},
+		{
+			Name:         "notify",
+			TriggerEvent: models.TriggerVerifyFailure,
+			ActionType:   models.EventActionShell,
+			ActionConfig: models.ActionConfig{Command: strPtr("echo \"{name}\" # it's: \\ here\n")},
+		},
+	}
+	out, err := Unmarshal(Marshal(in))
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !reflect.DeepEqual(in, out) {
+		t.Fatalf("round trip changed the rules:\n%+v\n%+v\n%s", in, out, Marshal(in))
+	}
+}
+
+func TestUnmarshalHandWritten(t *testing.T) {
+	src := `# shared rules
+rules:
+  - name: flag-tampered
+    on: verify-failed   # hyphens are fine
+    action: add-tag
+    config:
+      tag: 'tampered'
+`
+	got, err := Unmarshal([]byte(src))
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(got) != 1 || !got[0].Enabled || got[0].TriggerEvent != models.TriggerVerifyFailure ||
+		got[0].ActionType != models.EventActionAddTag || got[0].ActionConfig.Tag == nil || *got[0].ActionConfig.Tag != "tampered" {
+		t.Fatalf("unexpected rules: %+v", got)
+	}
+
+	if _, err := Unmarshal([]byte("rules:\n  - name: x\n    on: ingest\n    action: add-tag\n    config:\n      colour: red\n")); err == nil {
+		t.Fatal("expected an unknown config field to be refused")
+	}
+}
@@ -0,0 +1,484 @@
+// Package signing makes and checks detached signatures over sign
+// statements, with an external tool (GPG, ssh-keygen or minisign) or in
+// process with the project's own ed25519 key. A statement names the file's
+// hash and the sign, never its path, so a signature can be stored in the
+// project database and checked anywhere.
+package signing
+
+import (
+	"bytes"
+	"crypto/ed25519"
+	"crypto/sha256"
+	"encoding/base64"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+)
+
+// Signature formats, one per backend.
+const (
+	GPG      = "gpg"
+	SSH      = "ssh"
+	Minisign = "minisign"
+	Ed25519  = "ed25519"
+)
+
+// Formats lists the supported signature formats.
+var Formats = []string{GPG, SSH, Minisign, Ed25519}
+
+// sshNamespace scopes ssh signatures so one made for mkrk cannot be
+// replayed as, say, a git commit signature.
+const sshNamespace = "mkrk"
+
+// Signature is a detached signature and the public key that made it: a GPG
+// fingerprint, an ssh public key, a minisign public key, or a base64
+// ed25519 public key.
+type Signature struct {
+	Format string
+	Data   string
+	Key    string
+}
+
+// Statement is what a signature covers: the file's hash, named by the
+// algorithm that made it, and the sign made on it.
+func Statement(hashAlgorithm, fileHash, pipeline, signName, signer, signedAt string) []byte {
+	return fmt.Appendf(nil, "mkrk-sign v1\n%s %s\npipeline %s\nsign %s\nsigner %s\nsigned-at %s\n",
+		hashAlgorithm, fileHash, pipeline, signName, signer, signedAt)
+}
+
+// Sign signs statement in format with key: a GPG key ID (empty for the
+// default key), or the path to an ssh or minisign secret key (empty for
+// the tool's usual default). Passphrase prompts go to the terminal.
+// Ed25519 signatures are made with SignEd25519 instead.
+func Sign(format, key string, statement []byte) (*Signature, error) {
+	switch format {
+	case GPG:
+		return signGPG(key, statement)
+	case SSH:
+		return signSSH(key, statement)
+	case Minisign:
+		return signMinisign(key, statement)
+	case Ed25519:
+		return nil, fmt.Errorf("ed25519 signatures are made with the project's signing key")
+	}
+	return nil, fmt.Errorf("unknown signature format '%s' (expected %s)", format, strings.Join(Formats, ", "))
+}
+
+// ErrUntrusted is returned by Verify for a signature that matches the key
+// recorded with it when that key is not trusted. Anyone who can write the
+// database can record a key, so such a signature proves nothing.
+var ErrUntrusted = errors.New("signed by a key that is not trusted")
+
+// Verify checks sig over statement against the key recorded with it and
+// returns a description of that key: its fingerprint or key ID, and the
+// user ID GPG knows it by. GPG checks against the user's keyring; the
+// other formats' keys must be in trusted, or Verify returns the
+// description with ErrUntrusted.
+func Verify(sig Signature, statement []byte, trusted Trusted) (string, error) {
+	var desc string
+	var err error
+	switch sig.Format {
+	case GPG:
+		return verifyGPG(sig, statement)
+	case SSH:
+		desc, err = verifySSH(sig, statement)
+	case Minisign:
+		desc, err = verifyMinisign(sig, statement)
+	case Ed25519:
+		desc, err = verifyEd25519(sig, statement)
+	default:
+		return "", fmt.Errorf("unknown signature format '%s'", sig.Format)
+	}
+	if err != nil {
+		return "", err
+	}
+	if !trusted[trustKey(sig.Format, sig.Key)] {
+		return desc, ErrUntrusted
+	}
+	return desc, nil
+}
+
+// --- Trusted keys ---
+
+// TrustedKeysEnv names a trusted keys file to use instead of the default.
+const TrustedKeysEnv = "MKRK_TRUSTED_KEYS"
+
+// Trusted is the set of keys whose ssh, minisign and ed25519 signatures
+// Verify accepts, by format and key.
+type Trusted map[string]bool
+
+func trustKey(format, key string) string {
+	return format + " " + key
+}
+
+// TrustedKeysPath returns the trusted keys file: MKRK_TRUSTED_KEYS, or
+// mkrk/trusted_keys in the user's config directory. Like the user's
+// secrets, it lives outside every project, so no database can vouch for
+// its own signatures.
+func TrustedKeysPath() (string, error) {
+	if p := os.Getenv(TrustedKeysEnv); p != "" {
+		return p, nil
+	}
+	dir, err := os.UserConfigDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(dir, "mkrk", "trusted_keys"), nil
+}
+
+// LoadTrusted reads the trusted keys file: one key per line, its format
+// first, as in
+//
+//	ssh ssh-ed25519 AAAAC3Nza... alice@laptop
+//	minisign RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+//	ed25519 3mDpG2b8mB...
+//
+// Anything after the key is a comment, as are lines starting with #. A
+// missing file trusts no key.
+func LoadTrusted() (Trusted, error) {
+	path, err := TrustedKeysPath()
+	if err != nil {
+		return nil, err
+	}
+	data, err := os.ReadFile(path)
+	if os.IsNotExist(err) {
+		return Trusted{}, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	trusted := Trusted{}
+	for line := range strings.Lines(string(data)) {
+		fields := strings.Fields(line)
+		if len(fields) < 2 || strings.HasPrefix(fields[0], "#") {
+			continue
+		}
+		key := fields[1]
+		if fields[0] == SSH {
+			if len(fields) < 3 {
+				continue
+			}
+			key += " " + fields[2]
+		}
+		trusted[trustKey(fields[0], key)] = true
+	}
+	return trusted, nil
+}
+
+// Trust appends key to the trusted keys file, with comment after it,
+// unless the file already trusts it.
+func Trust(format, key, comment string) error {
+	trusted, err := LoadTrusted()
+	if err != nil {
+		return err
+	}
+	if trusted[trustKey(format, key)] {
+		return nil
+	}
+	path, err := TrustedKeysPath()
+	if err != nil {
+		return err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0o700); err != nil {
+		return err
+	}
+	f, err := os.OpenFile(path, os.O_APPEND|os.O_CREATE|os.O_WRONLY, 0o600)
+	if err != nil {
+		return err
+	}
+	line := trustKey(format, key)
+	if comment != "" {
+		line += " " + comment
+	}
+	if _, err := fmt.Fprintln(f, line); err != nil {
+		f.Close()
+		return err
+	}
+	return f.Close()
+}
+
+// --- GPG ---
+
+func signGPG(key string, statement []byte) (*Signature, error) {
+	dir, err := os.MkdirTemp("", "mkrk-sign-")
+	if err != nil {
+		return nil, err
+	}
+	defer os.RemoveAll(dir)
+	sigPath := filepath.Join(dir, "statement.asc")
+
+	args := []string{"--status-fd", "1", "--armor", "--detach-sign", "--output", sigPath}
+	if key != "" {
+		args = append(args, "--local-user", key)
+	}
+	status, err := run("gpg", statement, true, args...)
+	if err != nil {
+		return nil, err
+	}
+	fingerprint := gpgStatus(status, "SIG_CREATED")
+	if len(fingerprint) < 6 {
+		return nil, fmt.Errorf("gpg did not report the signing key")
+	}
+	data, err := os.ReadFile(sigPath)
+	if err != nil {
+		return nil, err
+	}
+	return &Signature{Format: GPG, Data: string(data), Key: fingerprint[5]}, nil
+}
+
+func verifyGPG(sig Signature, statement []byte) (string, error) {
+	dir, err := os.MkdirTemp("", "mkrk-verify-")
+	if err != nil {
+		return "", err
+	}
+	defer os.RemoveAll(dir)
+	sigPath := filepath.Join(dir, "statement.asc")
+	if err := os.WriteFile(sigPath, []byte(sig.Data), 0o600); err != nil {
+		return "", err
+	}
+
+	status, err := run("gpg", statement, false, "--status-fd", "1", "--verify", sigPath, "-")
+	if err != nil {
+		return "", err
+	}
+	valid := gpgStatus(status, "VALIDSIG")
+	if len(valid) == 0 {
+		return "", fmt.Errorf("gpg reported no valid signature")
+	}
+	if sig.Key != "" && valid[0] != sig.Key {
+		return "", fmt.Errorf("signed by key %s, not the recorded %s", valid[0], sig.Key)
+	}
+	desc := "gpg key " + valid[0]
+	// GOODSIG <long key ID> <user ID>
+	if good := gpgStatus(status, "GOODSIG"); len(good) > 1 {
+		desc += " (" + strings.Join(good[1:], " ") + ")"
+	}
+	return desc, nil
+}
+
+// gpgStatus returns the arguments of the first status line with keyword.
+func gpgStatus(status, keyword string) []string {
+	for line := range strings.Lines(status) {
+		fields := strings.Fields(line)
+		if len(fields) >= 2 && fields[0] == "[GNUPG:]" && fields[1] == keyword {
+			return fields[2:]
+		}
+	}
+	return nil
+}
+
+// --- SSH ---
+
+func signSSH(key string, statement []byte) (*Signature, error) {
+	if key == "" {
+		home, _ := os.UserHomeDir()
+		for _, name := range []string{"id_ed25519", "id_ecdsa", "id_rsa"} {
+			candidate := filepath.Join(home, ".ssh", name)
+			if _, err := os.Stat(candidate); err == nil {
+				key = candidate
+				break
+			}
+		}
+		if key == "" {
+			return nil, fmt.Errorf("no ssh key found in ~/.ssh (use --key)")
+		}
+	}
+	pubPath := key
+	if !strings.HasSuffix(key, ".pub") {
+		pubPath = key + ".pub"
+	}
+	pub, err := os.ReadFile(pubPath)
+	if err != nil {
+		return nil, fmt.Errorf("read ssh public key: %w", err)
+	}
+	fields := strings.Fields(string(pub))
+	if len(fields) < 2 {
+		return nil, fmt.Errorf("%s is not an ssh public key", pubPath)
+	}
+
+	data, err := run("ssh-keygen", statement, true, "-Y", "sign", "-f", key, "-n", sshNamespace)
+	if err != nil {
+		return nil, err
+	}
+	return &Signature{Format: SSH, Data: data, Key: fields[0] + " " + fields[1]}, nil
+}
+
+func verifySSH(sig Signature, statement []byte) (string, error) {
+	dir, err := os.MkdirTemp("", "mkrk-verify-")
+	if err != nil {
+		return "", err
+	}
+	defer os.RemoveAll(dir)
+	sigPath := filepath.Join(dir, "statement.sig")
+	allowed := filepath.Join(dir, "allowed_signers")
+	if err := os.WriteFile(sigPath, []byte(sig.Data), 0o600); err != nil {
+		return "", err
+	}
+	line := fmt.Sprintf("%s namespaces=%q %s\n", sshNamespace, sshNamespace, sig.Key)
+	if err := os.WriteFile(allowed, []byte(line), 0o600); err != nil {
+		return "", err
+	}
+
+	out, err := run("ssh-keygen", statement, false, "-Y", "verify", "-f", allowed, "-I", sshNamespace, "-n", sshNamespace, "-s", sigPath)
+	if err != nil {
+		return "", err
+	}
+	// Good "mkrk" signature for mkrk with ED25519 key SHA256:...
+	if _, key, ok := strings.Cut(strings.TrimSpace(out), " with "); ok {
+		return "ssh " + key, nil
+	}
+	return "ssh key", nil
+}
+
+// --- minisign ---
+
+func signMinisign(key string, statement []byte) (*Signature, error) {
+	if key == "" {
+		home, _ := os.UserHomeDir()
+		key = filepath.Join(home, ".minisign", "minisign.key")
+	}
+	pubPath := strings.TrimSuffix(key, filepath.Ext(key)) + ".pub"
+	pub, err := readMinisignKey(pubPath)
+	if err != nil {
+		return nil, fmt.Errorf("read minisign public key (expected next to the secret key): %w", err)
+	}
+
+	dir, err := os.MkdirTemp("", "mkrk-sign-")
+	if err != nil {
+		return nil, err
+	}
+	defer os.RemoveAll(dir)
+	msgPath := filepath.Join(dir, "statement")
+	sigPath := msgPath + ".minisig"
+	if err := os.WriteFile(msgPath, statement, 0o600); err != nil {
+		return nil, err
+	}
+	if _, err := run("minisign", nil, true, "-S", "-s", key, "-m", msgPath, "-x", sigPath); err != nil {
+		return nil, err
+	}
+	data, err := os.ReadFile(sigPath)
+	if err != nil {
+		return nil, err
+	}
+	return &Signature{Format: Minisign, Data: string(data), Key: pub}, nil
+}
+
+func verifyMinisign(sig Signature, statement []byte) (string, error) {
+	dir, err := os.MkdirTemp("", "mkrk-verify-")
+	if err != nil {
+		return "", err
+	}
+	defer os.RemoveAll(dir)
+	msgPath := filepath.Join(dir, "statement")
+	sigPath := msgPath + ".minisig"
+	if err := os.WriteFile(msgPath, statement, 0o600); err != nil {
+		return "", err
+	}
+	if err := os.WriteFile(sigPath, []byte(sig.Data), 0o600); err != nil {
+		return "", err
+	}
+	if _, err := run("minisign", nil, false, "-V", "-q", "-P", sig.Key, "-m", msgPath, "-x", sigPath); err != nil {
+		return "", err
+	}
+	return "minisign key " + minisignKeyID(sig.Key), nil
+}
+
+// readMinisignKey returns the base64 key line of a minisign public key
+// file, skipping its untrusted comment.
+func readMinisignKey(path string) (string, error) {
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return "", err
+	}
+	for line := range strings.Lines(string(data)) {
+		line = strings.TrimSpace(line)
+		if line != "" && !strings.HasPrefix(line, "untrusted comment:") {
+			return line, nil
+		}
+	}
+	return "", fmt.Errorf("%s holds no key", path)
+}
+
+// minisignKeyID returns the key ID minisign itself prints: the eight
+// little-endian bytes after the algorithm, as upper-case hex.
+func minisignKeyID(key string) string {
+	raw, err := base64.StdEncoding.DecodeString(key)
+	if err != nil || len(raw) < 10 {
+		return "(unreadable)"
+	}
+	id := make([]byte, 8)
+	for i := range id {
+		id[i] = raw[9-i]
+	}
+	return strings.ToUpper(hex.EncodeToString(id))
+}
+
+// --- ed25519 ---
+
+// SignEd25519 signs statement in process with key.
+func SignEd25519(key ed25519.PrivateKey, statement []byte) *Signature {
+	return &Signature{
+		Format: Ed25519,
+		Data:   base64.StdEncoding.EncodeToString(ed25519.Sign(key, statement)),
+		Key:    base64.StdEncoding.EncodeToString(key.Public().(ed25519.PublicKey)),
+	}
+}
+
+func verifyEd25519(sig Signature, statement []byte) (string, error) {
+	pub, err := base64.StdEncoding.DecodeString(sig.Key)
+	if err != nil || len(pub) != ed25519.PublicKeySize {
+		return "", fmt.Errorf("malformed ed25519 public key")
+	}
+	data, err := base64.StdEncoding.DecodeString(sig.Data)
+	if err != nil {
+		return "", fmt.Errorf("malformed ed25519 signature")
+	}
+	if !ed25519.Verify(pub, statement, data) {
+		return "", fmt.Errorf("ed25519 signature does not match")
+	}
+	return "ed25519 key " + Ed25519KeyID(sig.Key), nil
+}
+
+// Ed25519KeyID names a base64 ed25519 public key by the first eight bytes
+// of its SHA-256, as upper-case hex.
+func Ed25519KeyID(key string) string {
+	raw, err := base64.StdEncoding.DecodeString(key)
+	if err != nil || len(raw) != ed25519.PublicKeySize {
+		return "(unreadable)"
+	}
+	sum := sha256.Sum256(raw)
+	return strings.ToUpper(hex.EncodeToString(sum[:8]))
+}
+
+// run runs a signing tool with stdin on its standard input and returns
+// its standard output. An interactive run shares the terminal's stdin and
+// stderr so passphrase prompts reach the user; otherwise stderr is kept
+// for the error.
+func run(name string, stdin []byte, interactive bool, args ...string) (string, error) {
+	if _, err := exec.LookPath(name); err != nil {
+		return "", fmt.Errorf("%s not found on PATH", name)
+	}
+	cmd := exec.Command(name, args...)
+	if stdin != nil {
+		cmd.Stdin = bytes.NewReader(stdin)
+	} else if interactive {
+		cmd.Stdin = os.Stdin
+	}
+	var stdout, stderr bytes.Buffer
+	cmd.Stdout = &stdout
+	cmd.Stderr = &stderr
+	if interactive {
+		cmd.Stderr = os.Stderr
+	}
+	if err := cmd.Run(); err != nil {
+		if msg := strings.TrimSpace(stderr.String()); msg != "" {
+			return "", fmt.Errorf("%s: %s", name, msg)
+		}
+		return "", fmt.Errorf("%s: %w", name, err)
+	}
+	return stdout.String(), nil
+}
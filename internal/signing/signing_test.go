@@ -0,0 +1,107 @@
+package signing
+
+import (
+	"crypto/ed25519"
+	"encoding/base64"
+	"errors"
+	"os/exec"
+	"path/filepath"
+	"testing"
+)
+
+func TestSSHSignAndVerify(t *testing.T) {
+	if _, err := exec.LookPath("ssh-keygen"); err != nil {
+		t.Skip("ssh-keygen not available")
+	}
+	key := filepath.Join(t.TempDir(), "id_ed25519")
+	if out, err := exec.Command("ssh-keygen", "-q", "-t", "ed25519", "-N", "", "-f", key).CombinedOutput(); err != nil {
+		t.Fatalf("ssh-keygen: %v: %s", err, out)
+	}
+
+	statement := Statement("sha256", "abc123", "editorial", "review", "alice", "2025-01-01T00:00:00Z")
+	sig, err := Sign(SSH, key, statement)
+	if err != nil {
+		t.Fatalf("Sign: %v", err)
+	}
+	if sig.Format != SSH || sig.Key == "" {
+		t.Fatalf("unexpected signature: %+v", sig)
+	}
+	trusted := Trusted{trustKey(SSH, sig.Key): true}
+	if _, err := Verify(*sig, statement, trusted); err != nil {
+		t.Fatalf("Verify: %v", err)
+	}
+
+	tampered := Statement("sha256", "def456", "editorial", "review", "alice", "2025-01-01T00:00:00Z")
+	if _, err := Verify(*sig, tampered, trusted); err == nil {
+		t.Fatal("expected a signature over another hash to fail")
+	}
+}
+
+func TestMinisignKeyID(t *testing.T) {
+	// The example public key from the minisign documentation.
+	key := "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"
+	if got := minisignKeyID(key); got != "E7620F1842B4E81F" {
+		t.Fatalf("minisignKeyID = %s", got)
+	}
+	if got := minisignKeyID("not base64!"); got != "(unreadable)" {
+		t.Fatalf("minisignKeyID on junk = %s", got)
+	}
+}
+
+func TestEd25519SignAndVerify(t *testing.T) {
+	_, key, err := ed25519.GenerateKey(nil)
+	if err != nil {
+		t.Fatal(err)
+	}
+	statement := Statement("sha256", "abc123", "editorial", "review", "alice", "2025-01-01T00:00:00Z")
+	sig := SignEd25519(key, statement)
+	if sig.Format != Ed25519 {
+		t.Fatalf("unexpected signature: %+v", sig)
+	}
+	trusted := Trusted{trustKey(Ed25519, sig.Key): true}
+	desc, err := Verify(*sig, statement, trusted)
+	if err != nil {
+		t.Fatalf("Verify: %v", err)
+	}
+	if want := "ed25519 key " + Ed25519KeyID(sig.Key); desc != want {
+		t.Fatalf("Verify described the key as %q, want %q", desc, want)
+	}
+
+	tampered := Statement("sha256", "def456", "editorial", "review", "alice", "2025-01-01T00:00:00Z")
+	if _, err := Verify(*sig, tampered, trusted); err == nil {
+		t.Fatal("expected a signature over another hash to fail")
+	}
+	other, _, _ := ed25519.GenerateKey(nil)
+	forged := *sig
+	forged.Key = base64.StdEncoding.EncodeToString(other)
+	if _, err := Verify(forged, statement, trusted); err == nil {
+		t.Fatal("expected a signature checked against another key to fail")
+	}
+
+	// A signature that matches the key beside it proves nothing unless
+	// the key is trusted.
+	_, rogue, _ := ed25519.GenerateKey(nil)
+	self := SignEd25519(rogue, statement)
+	if _, err := Verify(*self, statement, trusted); !errors.Is(err, ErrUntrusted) {
+		t.Fatalf("expected an untrusted key to be reported, got %v", err)
+	}
+}
+
+func TestTrustedKeysFile(t *testing.T) {
+	t.Setenv(TrustedKeysEnv, filepath.Join(t.TempDir(), "mkrk", "trusted_keys"))
+	if trusted, err := LoadTrusted(); err != nil || len(trusted) != 0 {
+		t.Fatalf("expected a missing file to trust nothing, got %v (%v)", trusted, err)
+	}
+	if err := Trust(SSH, "ssh-ed25519 AAAAkey", "alice@laptop"); err != nil {
+		t.Fatal(err)
+	}
+	Trust(SSH, "ssh-ed25519 AAAAkey", "again")
+	Trust(Ed25519, "edkey", "")
+	trusted, err := LoadTrusted()
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(trusted) != 2 || !trusted[trustKey(SSH, "ssh-ed25519 AAAAkey")] || !trusted[trustKey(Ed25519, "edkey")] {
+		t.Fatalf("unexpected trusted keys: %v", trusted)
+	}
+}
@@ -0,0 +1,295 @@
+package storage
+
+import (
+	"crypto/hmac"
+	"crypto/sha256"
+	"encoding/base64"
+	"encoding/hex"
+	"encoding/xml"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"slices"
+	"strings"
+	"time"
+)
+
+// emptySHA256 is the hex SHA-256 of an empty payload, signed for requests
+// without a body.
+const emptySHA256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+
+// S3 is a backend in a bucket of S3 or a compatible service such as
+// MinIO. Requests are path-style and signed with AWS Signature Version 4.
+type S3 struct {
+	endpoint *url.URL
+	bucket   string
+	prefix   string
+	region   string
+
+	accessKey, secretKey, sessionToken string
+
+	lockMode  string
+	retention time.Duration
+	client    *http.Client
+	// now is the clock requests are signed with.
+	now func() time.Time
+}
+
+func newS3(u *url.URL, cfg Config) (*S3, error) {
+	if u.Host == "" {
+		return nil, fmt.Errorf("storage URL %q names no bucket", cfg.URL)
+	}
+	region := cfg.Region
+	if region == "" {
+		region = "us-east-1"
+	}
+	endpoint := cfg.Endpoint
+	if endpoint == "" {
+		endpoint = "https://s3." + region + ".amazonaws.com"
+	}
+	ep, err := url.Parse(endpoint)
+	if err != nil || ep.Host == "" {
+		return nil, fmt.Errorf("invalid storage endpoint %q", endpoint)
+	}
+	if cfg.AccessKey == "" || cfg.SecretKey == "" {
+		return nil, fmt.Errorf("no credentials for %s", cfg.URL)
+	}
+	lockMode := cfg.LockMode
+	if lockMode == "" {
+		lockMode = LockOff
+	}
+	if !slices.Contains(LockModes, lockMode) {
+		return nil, fmt.Errorf("unknown lock mode '%s' (expected %s)", lockMode, strings.Join(LockModes, ", "))
+	}
+	if lockMode != LockOff && cfg.Retention <= 0 {
+		return nil, fmt.Errorf("lock mode %s needs a retention period", lockMode)
+	}
+	client := cfg.Client
+	if client == nil {
+		client = http.DefaultClient
+	}
+	prefix := strings.Trim(u.Path, "/")
+	if prefix != "" {
+		prefix += "/"
+	}
+	return &S3{
+		endpoint:     ep,
+		bucket:       u.Host,
+		prefix:       prefix,
+		region:       region,
+		accessKey:    cfg.AccessKey,
+		secretKey:    cfg.SecretKey,
+		sessionToken: cfg.SessionToken,
+		lockMode:     lockMode,
+		retention:    cfg.Retention,
+		client:       client,
+		now:          time.Now,
+	}, nil
+}
+
+func (s *S3) String() string {
+	return "s3://" + s.bucket + "/" + s.prefix
+}
+
+// request builds a request for the object stored under hash.
+func (s *S3) request(method, hash string, body io.Reader) (*http.Request, error) {
+	u := *s.endpoint
+	u.Path = strings.TrimSuffix(u.Path, "/") + "/" + s.bucket + "/" + s.prefix + hash
+	u.RawPath = uriEncode(u.Path, false)
+	req, err := http.NewRequest(method, u.String(), body)
+	if err != nil {
+		return nil, err
+	}
+	if s.sessionToken != "" {
+		req.Header.Set("X-Amz-Security-Token", s.sessionToken)
+	}
+	return req, nil
+}
+
+// Put uploads content unless the bucket already holds it, with the
+// SHA-256 checksum S3 checks before accepting the object and, when a lock
+// mode is set, Object Lock retention. The bucket must have Object Lock
+// enabled for that.
+func (s *S3) Put(hash string, content io.ReadSeeker, size int64) (*Object, error) {
+	if obj, err := s.Stat(hash); err == nil {
+		return obj, nil
+	} else if !errors.Is(err, ErrNotFound) {
+		return nil, err
+	}
+
+	h := sha256.New()
+	if _, err := io.Copy(h, content); err != nil {
+		return nil, err
+	}
+	if _, err := content.Seek(0, io.SeekStart); err != nil {
+		return nil, err
+	}
+	sum := h.Sum(nil)
+
+	req, err := s.request(http.MethodPut, hash, io.NopCloser(content))
+	if err != nil {
+		return nil, err
+	}
+	req.ContentLength = size
+	req.Header.Set("X-Amz-Checksum-Sha256", base64.StdEncoding.EncodeToString(sum))
+	obj := &Object{Size: size, LockMode: LockOff}
+	if s.lockMode != LockOff {
+		obj.LockMode = s.lockMode
+		obj.RetainUntil = s.now().Add(s.retention).UTC().Truncate(time.Second)
+		req.Header.Set("X-Amz-Object-Lock-Mode", strings.ToUpper(s.lockMode))
+		req.Header.Set("X-Amz-Object-Lock-Retain-Until-Date", obj.RetainUntil.Format(time.RFC3339))
+	}
+	resp, err := s.do(req, hex.EncodeToString(sum))
+	if err != nil {
+		return nil, err
+	}
+	resp.Body.Close()
+	return obj, nil
+}
+
+func (s *S3) Get(hash string) (io.ReadCloser, error) {
+	req, err := s.request(http.MethodGet, hash, nil)
+	if err != nil {
+		return nil, err
+	}
+	resp, err := s.do(req, emptySHA256)
+	if err != nil {
+		return nil, err
+	}
+	return resp.Body, nil
+}
+
+func (s *S3) Stat(hash string) (*Object, error) {
+	req, err := s.request(http.MethodHead, hash, nil)
+	if err != nil {
+		return nil, err
+	}
+	resp, err := s.do(req, emptySHA256)
+	if err != nil {
+		return nil, err
+	}
+	resp.Body.Close()
+
+	obj := &Object{Size: resp.ContentLength, LockMode: LockOff}
+	if mode := resp.Header.Get("X-Amz-Object-Lock-Mode"); mode != "" {
+		obj.LockMode = strings.ToLower(mode)
+	}
+	if until := resp.Header.Get("X-Amz-Object-Lock-Retain-Until-Date"); until != "" {
+		obj.RetainUntil, _ = time.Parse(time.RFC3339, until)
+	}
+	return obj, nil
+}
+
+// do signs and sends req, turning error responses into errors: a 404 into
+// ErrNotFound, others into the service's code and message.
+func (s *S3) do(req *http.Request, payloadHash string) (*http.Response, error) {
+	s.sign(req, payloadHash, s.now())
+	resp, err := s.client.Do(req)
+	if err != nil {
+		return nil, err
+	}
+	if resp.StatusCode/100 == 2 {
+		return resp, nil
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode == http.StatusNotFound {
+		return nil, ErrNotFound
+	}
+	var e struct {
+		Code    string
+		Message string
+	}
+	body, _ := io.ReadAll(io.LimitReader(resp.Body, 64<<10))
+	if xml.Unmarshal(body, &e) == nil && e.Code != "" {
+		return nil, fmt.Errorf("%s: %s: %s", s, e.Code, e.Message)
+	}
+	return nil, fmt.Errorf("%s: %s", s, resp.Status)
+}
+
+// sign adds an AWS Signature Version 4 Authorization header to req, over
+// its host, range, content type and x-amz-* headers and payloadHash.
+func (s *S3) sign(req *http.Request, payloadHash string, now time.Time) {
+	amzDate := now.UTC().Format("20060102T150405Z")
+	date := amzDate[:8]
+	req.Header.Set("X-Amz-Date", amzDate)
+	req.Header.Set("X-Amz-Content-Sha256", payloadHash)
+
+	headers := map[string]string{"host": req.URL.Host}
+	for name, values := range req.Header {
+		lower := strings.ToLower(name)
+		if lower == "range" || lower == "content-type" || strings.HasPrefix(lower, "x-amz-") {
+			headers[lower] = strings.TrimSpace(strings.Join(values, ","))
+		}
+	}
+	names := make([]string, 0, len(headers))
+	for name := range headers {
+		names = append(names, name)
+	}
+	slices.Sort(names)
+
+	var canonical strings.Builder
+	canonical.WriteString(req.Method + "\n")
+	canonical.WriteString(uriEncode(req.URL.Path, false) + "\n")
+	canonical.WriteString(canonicalQuery(req.URL.Query()) + "\n")
+	for _, name := range names {
+		canonical.WriteString(name + ":" + headers[name] + "\n")
+	}
+	signed := strings.Join(names, ";")
+	canonical.WriteString("\n" + signed + "\n" + payloadHash)
+
+	scope := date + "/" + s.region + "/s3/aws4_request"
+	digest := sha256.Sum256([]byte(canonical.String()))
+	toSign := "AWS4-HMAC-SHA256\n" + amzDate + "\n" + scope + "\n" + hex.EncodeToString(digest[:])
+
+	key := hmacSHA256([]byte("AWS4"+s.secretKey), date)
+	for _, part := range []string{s.region, "s3", "aws4_request"} {
+		key = hmacSHA256(key, part)
+	}
+	signature := hex.EncodeToString(hmacSHA256(key, toSign))
+	req.Header.Set("Authorization", fmt.Sprintf("AWS4-HMAC-SHA256 Credential=%s/%s, SignedHeaders=%s, Signature=%s",
+		s.accessKey, scope, signed, signature))
+}
+
+func hmacSHA256(key []byte, data string) []byte {
+	mac := hmac.New(sha256.New, key)
+	mac.Write([]byte(data))
+	return mac.Sum(nil)
+}
+
+// uriEncode percent-encodes s the way Signature Version 4 expects: every
+// byte but unreserved characters, and '/' unless encodeSlash is set.
+func uriEncode(s string, encodeSlash bool) string {
+	var b strings.Builder
+	for i := 0; i < len(s); i++ {
+		c := s[i]
+		switch {
+		case 'A' <= c && c <= 'Z', 'a' <= c && c <= 'z', '0' <= c && c <= '9',
+			c == '-', c == '_', c == '.', c == '~':
+			b.WriteByte(c)
+		case c == '/' && !encodeSlash:
+			b.WriteByte(c)
+		default:
+			fmt.Fprintf(&b, "%%%02X", c)
+		}
+	}
+	return b.String()
+}
+
+func canonicalQuery(q url.Values) string {
+	keys := make([]string, 0, len(q))
+	for k := range q {
+		keys = append(keys, k)
+	}
+	slices.Sort(keys)
+	var parts []string
+	for _, k := range keys {
+		values := slices.Clone(q[k])
+		slices.Sort(values)
+		for _, v := range values {
+			parts = append(parts, uriEncode(k, true)+"="+uriEncode(v, true))
+		}
+	}
+	return strings.Join(parts, "&")
+}
@@ -0,0 +1,205 @@
+// Package storage keeps file content outside the working tree, in a
+// directory or an S3-compatible bucket, named by content hash. A file whose
+// content has moved out is replaced by a stub naming that hash.
+package storage
+
+import (
+	"bytes"
+	"encoding/hex"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"time"
+)
+
+// ErrNotFound is returned for content the backend does not hold.
+var ErrNotFound = errors.New("not in storage")
+
+// Lock modes for S3 Object Lock. Governance retention can be lifted by
+// users with special permission; compliance retention by no one, the
+// account's root user included, until it expires.
+const (
+	LockOff        = "off"
+	LockGovernance = "governance"
+	LockCompliance = "compliance"
+)
+
+// LockModes lists the accepted lock modes.
+var LockModes = []string{LockOff, LockGovernance, LockCompliance}
+
+// Object describes content held by a backend. RetainUntil is zero when
+// the content is not under retention.
+type Object struct {
+	Size        int64
+	LockMode    string
+	RetainUntil time.Time
+}
+
+// Backend holds content by hash.
+type Backend interface {
+	// Put stores size bytes of content under hash. Content already held
+	// is not sent again.
+	Put(hash string, content io.ReadSeeker, size int64) (*Object, error)
+	// Get opens the content stored under hash.
+	Get(hash string) (io.ReadCloser, error)
+	// Stat describes the content stored under hash, or returns
+	// ErrNotFound.
+	Stat(hash string) (*Object, error)
+	// String names the backend for messages.
+	String() string
+}
+
+// Config selects and configures a backend. URL is s3://bucket[/prefix]
+// for S3-compatible storage, or a directory as file:///path or a plain
+// absolute path.
+type Config struct {
+	URL string
+
+	// S3 settings. Endpoint defaults to AWS in Region, which defaults to
+	// us-east-1; other services such as MinIO need their own endpoint.
+	Endpoint     string
+	Region       string
+	AccessKey    string
+	SecretKey    string
+	SessionToken string
+	// New objects are locked in LockMode for Retention, when set.
+	LockMode  string
+	Retention time.Duration
+	Client    *http.Client
+}
+
+// Open returns the backend cfg describes.
+func Open(cfg Config) (Backend, error) {
+	u, err := url.Parse(cfg.URL)
+	if err != nil {
+		return nil, fmt.Errorf("invalid storage URL %q: %w", cfg.URL, err)
+	}
+	switch {
+	case u.Scheme == "s3":
+		return newS3(u, cfg)
+	case u.Scheme == "file":
+		return &Dir{Root: u.Path}, nil
+	case u.Scheme == "" && filepath.IsAbs(cfg.URL):
+		return &Dir{Root: cfg.URL}, nil
+	}
+	return nil, fmt.Errorf("unsupported storage URL %q (expected s3://bucket/prefix or a directory)", cfg.URL)
+}
+
+// Dir is a backend in a local or mounted directory. Stored files are made
+// read-only, which is as close to a lock as a directory offers.
+type Dir struct {
+	Root string
+}
+
+func (d *Dir) String() string { return d.Root }
+
+func (d *Dir) path(hash string) string {
+	return filepath.Join(d.Root, hash)
+}
+
+func (d *Dir) Put(hash string, content io.ReadSeeker, size int64) (*Object, error) {
+	if obj, err := d.Stat(hash); err == nil {
+		return obj, nil
+	}
+	if err := os.MkdirAll(d.Root, 0o755); err != nil {
+		return nil, err
+	}
+	tmp, err := os.CreateTemp(d.Root, ".put-*")
+	if err != nil {
+		return nil, err
+	}
+	defer os.Remove(tmp.Name())
+	n, err := io.Copy(tmp, content)
+	if closeErr := tmp.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		return nil, err
+	}
+	if n != size {
+		return nil, fmt.Errorf("wrote %d bytes, expected %d", n, size)
+	}
+	if err := os.Chmod(tmp.Name(), 0o444); err != nil {
+		return nil, err
+	}
+	if err := os.Rename(tmp.Name(), d.path(hash)); err != nil {
+		return nil, err
+	}
+	return &Object{Size: n}, nil
+}
+
+func (d *Dir) Get(hash string) (io.ReadCloser, error) {
+	f, err := os.Open(d.path(hash))
+	if errors.Is(err, os.ErrNotExist) {
+		return nil, ErrNotFound
+	}
+	return f, err
+}
+
+func (d *Dir) Stat(hash string) (*Object, error) {
+	info, err := os.Stat(d.path(hash))
+	if errors.Is(err, os.ErrNotExist) {
+		return nil, ErrNotFound
+	}
+	if err != nil {
+		return nil, err
+	}
+	return &Object{Size: info.Size()}, nil
+}
+
+// stubMagic opens a stub: the small file left in the working tree in
+// place of content that lives in storage. The line after it holds the
+// content's hash and size.
+const stubMagic = "MKRKSTUB1\n"
+
+// maxStubSize bounds how much of a file is read to decide whether it is a
+// stub.
+const maxStubSize = 256
+
+// Stub is the content a stub stands for.
+type Stub struct {
+	Hash string
+	Size int64
+}
+
+// WriteStub writes the stub for content with the given hash and size.
+func WriteStub(w io.Writer, s Stub) error {
+	_, err := fmt.Fprintf(w, "%s%s %d\n", stubMagic, s.Hash, s.Size)
+	return err
+}
+
+// ReadStub reports whether the file at path is a stub, and which content
+// it stands for.
+func ReadStub(path string) (*Stub, bool) {
+	info, err := os.Stat(path)
+	if err != nil || !info.Mode().IsRegular() || info.Size() > maxStubSize {
+		return nil, false
+	}
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return nil, false
+	}
+	rest, ok := bytes.CutPrefix(data, []byte(stubMagic))
+	if !ok {
+		return nil, false
+	}
+	line, _, ok := strings.Cut(string(rest), "\n")
+	if !ok {
+		return nil, false
+	}
+	hash, sizeStr, ok := strings.Cut(line, " ")
+	if _, err := hex.DecodeString(hash); !ok || err != nil || hash == "" {
+		return nil, false
+	}
+	size, err := strconv.ParseInt(sizeStr, 10, 64)
+	if err != nil || size < 0 {
+		return nil, false
+	}
+	return &Stub{Hash: hash, Size: size}, true
+}
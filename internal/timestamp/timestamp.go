@@ -0,0 +1,366 @@
+// Package timestamp obtains and checks RFC 3161 trusted timestamps. A
+// time-stamping authority (TSA) signs a token binding a SHA-256 digest to
+// the time it saw it, which later proves the digest existed by then
+// without trusting anyone's clock but the TSA's.
+package timestamp
+
+import (
+	"bytes"
+	"crypto"
+	"crypto/rand"
+	_ "crypto/sha256"
+	_ "crypto/sha512"
+	"crypto/x509"
+	"crypto/x509/pkix"
+	"encoding/asn1"
+	"errors"
+	"fmt"
+	"io"
+	"math/big"
+	"net/http"
+	"time"
+)
+
+// DefaultTSA is the authority used when none is configured.
+const DefaultTSA = "https://freetsa.org/tsr"
+
+var (
+	oidSHA256        = asn1.ObjectIdentifier{2, 16, 840, 1, 101, 3, 4, 2, 1}
+	oidSHA384        = asn1.ObjectIdentifier{2, 16, 840, 1, 101, 3, 4, 2, 2}
+	oidSHA512        = asn1.ObjectIdentifier{2, 16, 840, 1, 101, 3, 4, 2, 3}
+	oidSignedData    = asn1.ObjectIdentifier{1, 2, 840, 113549, 1, 7, 2}
+	oidTSTInfo       = asn1.ObjectIdentifier{1, 2, 840, 113549, 1, 9, 16, 1, 4}
+	oidContentType   = asn1.ObjectIdentifier{1, 2, 840, 113549, 1, 9, 3}
+	oidMessageDigest = asn1.ObjectIdentifier{1, 2, 840, 113549, 1, 9, 4}
+	oidRSASSAPSS     = asn1.ObjectIdentifier{1, 2, 840, 113549, 1, 1, 10}
+)
+
+// Info describes a verified token.
+type Info struct {
+	GenTime time.Time
+	Serial  *big.Int
+	// TSA is the subject of the certificate that signed the token.
+	TSA string
+	// ChainErr is why that certificate does not chain to a trusted root
+	// at GenTime, or nil if it does. The token's signature is valid either
+	// way; this only says whether the TSA is one the system trusts.
+	ChainErr error
+}
+
+// --- ASN.1 structures (RFC 3161, RFC 5652) ---
+
+type messageImprint struct {
+	HashAlgorithm pkix.AlgorithmIdentifier
+	HashedMessage []byte
+}
+
+type timeStampReq struct {
+	Version        int
+	MessageImprint messageImprint
+	Nonce          *big.Int `asn1:"optional"`
+	CertReq        bool     `asn1:"optional"`
+}
+
+type pkiStatusInfo struct {
+	Status       int
+	StatusString asn1.RawValue `asn1:"optional"`
+	FailInfo     asn1.RawValue `asn1:"optional"`
+}
+
+type timeStampResp struct {
+	Status pkiStatusInfo
+	Token  asn1.RawValue `asn1:"optional"`
+}
+
+type contentInfo struct {
+	ContentType asn1.ObjectIdentifier
+	Content     asn1.RawValue `asn1:"explicit,tag:0"`
+}
+
+type signedData struct {
+	Version          int
+	DigestAlgorithms []pkix.AlgorithmIdentifier `asn1:"set"`
+	EncapContentInfo encapContentInfo
+	Certificates     asn1.RawValue `asn1:"optional,tag:0"`
+	CRLs             asn1.RawValue `asn1:"optional,tag:1"`
+	SignerInfos      []signerInfo  `asn1:"set"`
+}
+
+type encapContentInfo struct {
+	EContentType asn1.ObjectIdentifier
+	EContent     []byte `asn1:"explicit,optional,tag:0"`
+}
+
+type signerInfo struct {
+	Version            int
+	SID                asn1.RawValue
+	DigestAlgorithm    pkix.AlgorithmIdentifier
+	SignedAttrs        asn1.RawValue `asn1:"optional,tag:0"`
+	SignatureAlgorithm pkix.AlgorithmIdentifier
+	Signature          []byte
+	UnsignedAttrs      asn1.RawValue `asn1:"optional,tag:1"`
+}
+
+type issuerAndSerial struct {
+	Issuer asn1.RawValue
+	Serial *big.Int
+}
+
+type attribute struct {
+	Type   asn1.ObjectIdentifier
+	Values asn1.RawValue
+}
+
+type accuracy struct {
+	Seconds int `asn1:"optional"`
+	Millis  int `asn1:"optional,tag:0"`
+	Micros  int `asn1:"optional,tag:1"`
+}
+
+type tstInfo struct {
+	Version        int
+	Policy         asn1.ObjectIdentifier
+	MessageImprint messageImprint
+	SerialNumber   *big.Int
+	GenTime        time.Time     `asn1:"generalized"`
+	Accuracy       accuracy      `asn1:"optional"`
+	Ordering       bool          `asn1:"optional"`
+	Nonce          *big.Int      `asn1:"optional"`
+	TSA            asn1.RawValue `asn1:"optional,tag:0"`
+	Extensions     asn1.RawValue `asn1:"optional,tag:1"`
+}
+
+// Fetch asks the TSA at url to timestamp a SHA-256 digest and returns the
+// DER token, after checking it covers the digest and echoes the request's
+// nonce.
+func Fetch(client *http.Client, url string, digest []byte) ([]byte, error) {
+	if len(digest) != crypto.SHA256.Size() {
+		return nil, fmt.Errorf("expected a SHA-256 digest, got %d bytes", len(digest))
+	}
+	nonce, err := rand.Int(rand.Reader, new(big.Int).Lsh(big.NewInt(1), 64))
+	if err != nil {
+		return nil, err
+	}
+	req, err := asn1.Marshal(timeStampReq{
+		Version: 1,
+		MessageImprint: messageImprint{
+			HashAlgorithm: pkix.AlgorithmIdentifier{Algorithm: oidSHA256, Parameters: asn1.NullRawValue},
+			HashedMessage: digest,
+		},
+		Nonce:   nonce,
+		CertReq: true,
+	})
+	if err != nil {
+		return nil, err
+	}
+
+	resp, err := client.Post(url, "application/timestamp-query", bytes.NewReader(req))
+	if err != nil {
+		return nil, fmt.Errorf("timestamp request: %w", err)
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode != http.StatusOK {
+		return nil, fmt.Errorf("timestamp request: %s", resp.Status)
+	}
+	body, err := io.ReadAll(io.LimitReader(resp.Body, 1<<20))
+	if err != nil {
+		return nil, fmt.Errorf("timestamp request: %w", err)
+	}
+
+	var tsr timeStampResp
+	if _, err := asn1.Unmarshal(body, &tsr); err != nil {
+		return nil, fmt.Errorf("malformed timestamp response: %w", err)
+	}
+	// 0 is granted, 1 granted with modifications.
+	if tsr.Status.Status > 1 {
+		return nil, fmt.Errorf("TSA refused the request (status %d)", tsr.Status.Status)
+	}
+	if len(tsr.Token.FullBytes) == 0 {
+		return nil, fmt.Errorf("TSA returned no token")
+	}
+
+	_, tst, err := verify(tsr.Token.FullBytes, digest)
+	if err != nil {
+		return nil, err
+	}
+	if tst.Nonce == nil || tst.Nonce.Cmp(nonce) != 0 {
+		return nil, fmt.Errorf("TSA response does not match the request nonce")
+	}
+	return tsr.Token.FullBytes, nil
+}
+
+// Verify checks that token is a TSA-signed timestamp over digest, with a
+// signature made by the certificate it carries.
+func Verify(token, digest []byte) (*Info, error) {
+	info, _, err := verify(token, digest)
+	return info, err
+}
+
+func verify(token, digest []byte) (*Info, *tstInfo, error) {
+	var ci contentInfo
+	if _, err := asn1.Unmarshal(token, &ci); err != nil {
+		return nil, nil, fmt.Errorf("malformed timestamp token: %w", err)
+	}
+	if !ci.ContentType.Equal(oidSignedData) {
+		return nil, nil, fmt.Errorf("timestamp token is not signed data")
+	}
+	var sd signedData
+	if _, err := asn1.Unmarshal(ci.Content.Bytes, &sd); err != nil {
+		return nil, nil, fmt.Errorf("malformed timestamp token: %w", err)
+	}
+	if !sd.EncapContentInfo.EContentType.Equal(oidTSTInfo) || len(sd.EncapContentInfo.EContent) == 0 {
+		return nil, nil, fmt.Errorf("timestamp token carries no TSTInfo")
+	}
+
+	var tst tstInfo
+	if _, err := asn1.Unmarshal(sd.EncapContentInfo.EContent, &tst); err != nil {
+		return nil, nil, fmt.Errorf("malformed TSTInfo: %w", err)
+	}
+	if !tst.MessageImprint.HashAlgorithm.Algorithm.Equal(oidSHA256) {
+		return nil, nil, fmt.Errorf("timestamp is not over a SHA-256 digest")
+	}
+	if !bytes.Equal(tst.MessageImprint.HashedMessage, digest) {
+		return nil, nil, fmt.Errorf("timestamp covers a different hash")
+	}
+
+	if len(sd.SignerInfos) != 1 {
+		return nil, nil, fmt.Errorf("timestamp token has %d signers, expected 1", len(sd.SignerInfos))
+	}
+	certs, err := x509.ParseCertificates(sd.Certificates.Bytes)
+	if err != nil {
+		return nil, nil, fmt.Errorf("timestamp token certificates: %w", err)
+	}
+	si := sd.SignerInfos[0]
+	cert := signerCert(si.SID, certs)
+	if cert == nil {
+		return nil, nil, fmt.Errorf("timestamp token does not include the TSA certificate")
+	}
+	if err := checkSignerInfo(si, cert, sd.EncapContentInfo.EContent); err != nil {
+		return nil, nil, err
+	}
+
+	intermediates := x509.NewCertPool()
+	for _, c := range certs {
+		intermediates.AddCert(c)
+	}
+	_, chainErr := cert.Verify(x509.VerifyOptions{
+		Intermediates: intermediates,
+		CurrentTime:   tst.GenTime,
+		KeyUsages:     []x509.ExtKeyUsage{x509.ExtKeyUsageTimeStamping},
+	})
+
+	return &Info{
+		GenTime:  tst.GenTime,
+		Serial:   tst.SerialNumber,
+		TSA:      cert.Subject.String(),
+		ChainErr: chainErr,
+	}, &tst, nil
+}
+
+// signerCert finds the certificate a signer identifier names, by issuer
+// and serial number or by subject key identifier.
+func signerCert(sid asn1.RawValue, certs []*x509.Certificate) *x509.Certificate {
+	if sid.Class == asn1.ClassContextSpecific && sid.Tag == 0 {
+		for _, c := range certs {
+			if bytes.Equal(c.SubjectKeyId, sid.Bytes) {
+				return c
+			}
+		}
+		return nil
+	}
+	var ias issuerAndSerial
+	if _, err := asn1.Unmarshal(sid.FullBytes, &ias); err != nil {
+		return nil
+	}
+	for _, c := range certs {
+		if c.SerialNumber.Cmp(ias.Serial) == 0 && bytes.Equal(c.RawIssuer, ias.Issuer.FullBytes) {
+			return c
+		}
+	}
+	return nil
+}
+
+// checkSignerInfo checks that the signed attributes hold the TSTInfo's
+// digest and that cert signed them.
+func checkSignerInfo(si signerInfo, cert *x509.Certificate, content []byte) error {
+	hash, err := hashFor(si.DigestAlgorithm.Algorithm)
+	if err != nil {
+		return err
+	}
+	if len(si.SignedAttrs.FullBytes) == 0 {
+		return fmt.Errorf("timestamp token has no signed attributes")
+	}
+	// The signature covers the attributes encoded as a SET, not with the
+	// [0] tag they carry inside SignerInfo.
+	signed := append([]byte(nil), si.SignedAttrs.FullBytes...)
+	signed[0] = 0x31
+
+	var attrs []attribute
+	if _, err := asn1.UnmarshalWithParams(signed, &attrs, "set"); err != nil {
+		return fmt.Errorf("malformed signed attributes: %w", err)
+	}
+	var digest []byte
+	var contentType asn1.ObjectIdentifier
+	for _, a := range attrs {
+		switch {
+		case a.Type.Equal(oidMessageDigest):
+			asn1.Unmarshal(a.Values.Bytes, &digest)
+		case a.Type.Equal(oidContentType):
+			asn1.Unmarshal(a.Values.Bytes, &contentType)
+		}
+	}
+	if !contentType.Equal(oidTSTInfo) {
+		return fmt.Errorf("signed attributes name the wrong content type")
+	}
+	h := hash.New()
+	h.Write(content)
+	if !bytes.Equal(digest, h.Sum(nil)) {
+		return fmt.Errorf("timestamp token content does not match its signed digest")
+	}
+
+	if si.SignatureAlgorithm.Algorithm.Equal(oidRSASSAPSS) {
+		return fmt.Errorf("RSASSA-PSS timestamp signatures are not supported")
+	}
+	alg, err := signatureAlgorithm(cert.PublicKeyAlgorithm, hash)
+	if err != nil {
+		return err
+	}
+	if err := cert.CheckSignature(alg, signed, si.Signature); err != nil {
+		return fmt.Errorf("timestamp signature: %w", err)
+	}
+	return nil
+}
+
+func hashFor(oid asn1.ObjectIdentifier) (crypto.Hash, error) {
+	switch {
+	case oid.Equal(oidSHA256):
+		return crypto.SHA256, nil
+	case oid.Equal(oidSHA384):
+		return crypto.SHA384, nil
+	case oid.Equal(oidSHA512):
+		return crypto.SHA512, nil
+	}
+	return 0, fmt.Errorf("unsupported timestamp digest algorithm %s", oid)
+}
+
+// signatureAlgorithm picks the x509 algorithm for a key type and digest.
+// CMS names them separately, and often gives only the key type as the
+// signature algorithm.
+func signatureAlgorithm(key x509.PublicKeyAlgorithm, hash crypto.Hash) (x509.SignatureAlgorithm, error) {
+	algs := map[x509.PublicKeyAlgorithm]map[crypto.Hash]x509.SignatureAlgorithm{
+		x509.RSA: {
+			crypto.SHA256: x509.SHA256WithRSA,
+			crypto.SHA384: x509.SHA384WithRSA,
+			crypto.SHA512: x509.SHA512WithRSA,
+		},
+		x509.ECDSA: {
+			crypto.SHA256: x509.ECDSAWithSHA256,
+			crypto.SHA384: x509.ECDSAWithSHA384,
+			crypto.SHA512: x509.ECDSAWithSHA512,
+		},
+	}
+	if alg, ok := algs[key][hash]; ok {
+		return alg, nil
+	}
+	return 0, errors.New("unsupported timestamp signature algorithm")
+}
@@ -0,0 +1,42 @@
+package timestamp
+
+import (
+	"crypto/sha256"
+	"os"
+	"testing"
+)
+
+// testdata/token.der is an OpenSSL-issued token over "evidence content",
+// signed by a throwaway TSA whose root no system trusts.
+func TestVerifyToken(t *testing.T) {
+	token, err := os.ReadFile("testdata/token.der")
+	if err != nil {
+		t.Fatal(err)
+	}
+	digest := sha256.Sum256([]byte("evidence content"))
+
+	info, err := Verify(token, digest[:])
+	if err != nil {
+		t.Fatalf("Verify: %v", err)
+	}
+	if info.TSA != "CN=mkrk test TSA" {
+		t.Fatalf("TSA = %q", info.TSA)
+	}
+	if info.GenTime.IsZero() || info.Serial == nil {
+		t.Fatalf("missing time or serial: %+v", info)
+	}
+	if info.ChainErr == nil {
+		t.Fatal("expected the throwaway root to be untrusted")
+	}
+
+	other := sha256.Sum256([]byte("other content"))
+	if _, err := Verify(token, other[:]); err == nil {
+		t.Fatal("expected a token over another hash to fail")
+	}
+
+	tampered := append([]byte(nil), token...)
+	tampered[len(tampered)-1] ^= 0xff
+	if _, err := Verify(tampered, digest[:]); err == nil {
+		t.Fatal("expected a tampered signature to fail")
+	}
+}
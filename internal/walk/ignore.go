@@ -0,0 +1,128 @@
+package walk
+
+import (
+	"bufio"
+	"os"
+	"path"
+	"path/filepath"
+	"strings"
+)
+
+// IgnoreFile is the name of the gitignore-style file read from the root
+// of a walk. Matching files are never collected.
+const IgnoreFile = ".mkrkignore"
+
+// Ignore holds the rules of an ignore file in file order.
+type Ignore struct {
+	rules []ignoreRule
+}
+
+type ignoreRule struct {
+	segments []string
+	negate   bool
+	dirOnly  bool
+	anchored bool
+}
+
+// LoadIgnore reads root/.mkrkignore. A missing file yields an empty Ignore.
+func LoadIgnore(root string) (*Ignore, error) {
+	data, err := os.ReadFile(filepath.Join(root, IgnoreFile))
+	if os.IsNotExist(err) {
+		return &Ignore{}, nil
+	}
+	if err != nil {
+		return nil, err
+	}
+	return ParseIgnore(string(data)), nil
+}
+
+// ParseIgnore parses gitignore-style rules: blank lines and # comments are
+// skipped, a leading ! re-includes, a trailing / matches only directories,
+// and a pattern containing / is anchored to the root. ** matches any
+// number of path segments.
+func ParseIgnore(text string) *Ignore {
+	ig := &Ignore{}
+	scanner := bufio.NewScanner(strings.NewReader(text))
+	for scanner.Scan() {
+		line := strings.TrimSpace(scanner.Text())
+		if line == "" || strings.HasPrefix(line, "#") {
+			continue
+		}
+
+		var r ignoreRule
+		if strings.HasPrefix(line, "!") {
+			r.negate = true
+			line = line[1:]
+		}
+		if strings.HasSuffix(line, "/") {
+			r.dirOnly = true
+			line = strings.TrimRight(line, "/")
+		}
+		if strings.Contains(line, "/") {
+			r.anchored = true
+			line = strings.TrimPrefix(line, "/")
+		}
+		if line == "" {
+			continue
+		}
+		r.segments = strings.Split(line, "/")
+		ig.rules = append(ig.rules, r)
+	}
+	return ig
+}
+
+// Ignored reports whether the slash-separated relative path is excluded,
+// either directly or because one of its parent directories is.
+func (ig *Ignore) Ignored(rel string, isDir bool) bool {
+	if ig == nil || len(ig.rules) == 0 {
+		return false
+	}
+	parts := strings.Split(rel, "/")
+	for i := 1; i < len(parts); i++ {
+		if ig.match(parts[:i], true) {
+			return true
+		}
+	}
+	return ig.match(parts, isDir)
+}
+
+// match applies the rules to one path; the last matching rule wins.
+func (ig *Ignore) match(parts []string, isDir bool) bool {
+	ignored := false
+	for _, r := range ig.rules {
+		if r.dirOnly && !isDir {
+			continue
+		}
+		var ok bool
+		if r.anchored {
+			ok = matchSegments(r.segments, parts)
+		} else {
+			ok = matchSegments(r.segments, parts[len(parts)-1:])
+		}
+		if ok {
+			ignored = !r.negate
+		}
+	}
+	return ignored
+}
+
+func matchSegments(pattern, parts []string) bool {
+	if len(pattern) == 0 {
+		return len(parts) == 0
+	}
+	if pattern[0] == "**" {
+		for i := 0; i <= len(parts); i++ {
+			if matchSegments(pattern[1:], parts[i:]) {
+				return true
+			}
+		}
+		return false
+	}
+	if len(parts) == 0 {
+		return false
+	}
+	if ok, _ := path.Match(pattern[0], parts[0]); !ok {
+		return false
+	}
+	return matchSegments(pattern[1:], parts[1:])
+}
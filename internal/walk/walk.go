@@ -10,11 +10,16 @@ import (
 	"go.foia.dev/muckrake/internal/models"
 )
 
-// WalkAndCollect walks root, skipping dot-prefixed entries, and returns
-// relative paths that match at least one of the given glob patterns.
+// WalkAndCollect walks root, skipping dot-prefixed entries and anything
+// excluded by root/.mkrkignore, and returns relative paths that match at
+// least one of the given glob patterns.
 func WalkAndCollect(root string, patterns []string) ([]string, error) {
+	ignore, err := LoadIgnore(root)
+	if err != nil {
+		return nil, err
+	}
 	var entries []string
-	err := walkRecursive(root, root, patterns, &entries)
+	err = walkRecursive(root, root, patterns, ignore, &entries)
 	if err != nil {
 		return nil, err
 	}
@@ -22,7 +27,7 @@ func WalkAndCollect(root string, patterns []string) ([]string, error) {
 	return entries, nil
 }
 
-func walkRecursive(root, dir string, patterns []string, entries *[]string) error {
+func walkRecursive(root, dir string, patterns []string, ignore *Ignore, entries *[]string) error {
 	dirEntries, err := os.ReadDir(dir)
 	if os.IsNotExist(err) {
 		return nil
@@ -38,18 +43,21 @@ func walkRecursive(root, dir string, patterns []string, entries *[]string) error
 		}
 
 		path := filepath.Join(dir, name)
-		if entry.IsDir() {
-			if err := walkRecursive(root, path, patterns, entries); err != nil {
-				return err
-			}
-			continue
-		}
-
 		rel, err := filepath.Rel(root, path)
 		if err != nil {
 			continue
 		}
 		rel = filepath.ToSlash(rel)
+		if ignore.match(strings.Split(rel, "/"), entry.IsDir()) {
+			continue
+		}
+
+		if entry.IsDir() {
+			if err := walkRecursive(root, path, patterns, ignore, entries); err != nil {
+				return err
+			}
+			continue
+		}
 
 		for _, pattern := range patterns {
 			matched, _ := models.GlobMatch(pattern, rel)
@@ -96,6 +96,56 @@ func TestWalkMissingDir(t *testing.T) {
 	}
 }
 
+func TestWalkHonorsIgnoreFile(t *testing.T) {
+	root := t.TempDir()
+	createTree(t, root)
+	os.MkdirAll(filepath.Join(root, "alpha/build"), 0o755)
+	os.WriteFile(filepath.Join(root, "alpha/build/out.o"), []byte("o"), 0o644)
+	os.WriteFile(filepath.Join(root, "alpha/scratch.tmp"), []byte("t"), 0o644)
+	os.WriteFile(filepath.Join(root, "keep.tmp"), []byte("k"), 0o644)
+	os.WriteFile(filepath.Join(root, IgnoreFile), []byte("# scratch\n*.tmp\n!keep.tmp\nbuild/\n/root.txt\n"), 0o644)
+
+	results, err := WalkAndCollect(root, []string{"**"})
+	if err != nil {
+		t.Fatal(err)
+	}
+
+	for _, p := range []string{"alpha/build/out.o", "alpha/scratch.tmp", "root.txt"} {
+		if containsStr(results, p) {
+			t.Fatalf("expected %s to be ignored, got %v", p, results)
+		}
+	}
+	for _, p := range []string{"alpha/one.txt", "alpha/sub/two.txt", "keep.tmp"} {
+		if !containsStr(results, p) {
+			t.Fatalf("expected %s, got %v", p, results)
+		}
+	}
+}
+
+func TestIgnoreRules(t *testing.T) {
+	ig := ParseIgnore("docs/**/draft-*\nlogs/\n*.bak\n")
+
+	cases := []struct {
+		path  string
+		isDir bool
+		want  bool
+	}{
+		{"docs/draft-1.md", false, true},
+		{"docs/a/b/draft-2.md", false, true},
+		{"other/docs/draft-1.md", false, false},
+		{"logs", true, true},
+		{"logs", false, false},
+		{"logs/today.txt", false, true},
+		{"deep/nested/file.bak", false, true},
+		{"file.bak.txt", false, false},
+	}
+	for _, c := range cases {
+		if got := ig.Ignored(c.path, c.isDir); got != c.want {
+			t.Errorf("Ignored(%q, %v) = %v, want %v", c.path, c.isDir, got, c.want)
+		}
+	}
+}
+
 func contains(s, substr string) bool {
 	return len(s) >= len(substr) && (s == substr || len(s) > len(substr) && containsSubstr(s, substr))
 }
@@ -1,11 +1,28 @@
 package tests
 
 import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"math/rand"
+	"net"
+	"net/http"
+	"net/http/httptest"
+	"net/url"
 	"os"
 	"os/exec"
 	"path/filepath"
+	"slices"
 	"strings"
+	"sync/atomic"
 	"testing"
+	"time"
+
+	"go.foia.dev/muckrake/internal/db"
 )
 
 var binary string
@@ -112,6 +129,32 @@ func TestInitRefusesDouble(t *testing.T) {
 	}
 }
 
+func TestInitHashAlgorithm(t *testing.T) {
+	dir := projectDir(t)
+	mustMkrk(t, dir, "init", "--hash", "blake3")
+	createTestFile(t, dir, "notes/lead.txt", "first lead")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "status")
+	if !strings.Contains(stdout, "Hash: blake3") {
+		t.Fatalf("expected blake3 in status, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "verify", "--json")
+	if !strings.Contains(stdout, `"hash_algorithm": "blake3"`) || !strings.Contains(stdout, `"ok": 1`) {
+		t.Fatalf("expected a clean blake3 report, got: %s", stdout)
+	}
+
+	createTestFile(t, dir, "notes/lead.txt", "rewritten lead")
+	if _, _, err := mkrk(t, dir, "verify"); exitCode(err) != 2 {
+		t.Fatalf("expected modification caught under blake3, got %v", err)
+	}
+
+	_, stderr, err := mkrk(t, projectDir(t), "init", "--hash", "md5")
+	if err == nil || !strings.Contains(stderr, "md5") {
+		t.Fatalf("expected unknown algorithm refused, got: %s", stderr)
+	}
+}
+
 // --- Status ---
 
 func TestStatusAfterInit(t *testing.T) {
@@ -119,6 +162,38 @@ func TestStatusAfterInit(t *testing.T) {
 	mustMkrk(t, dir, "status")
 }
 
+func TestStatusReportsUntrackedModifiedMissing(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/gone.txt", "will be deleted")
+	os.WriteFile(filepath.Join(dir, "notes/draft.txt"), bigContent(200*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+
+	os.Remove(filepath.Join(dir, "evidence/gone.txt"))
+	os.WriteFile(filepath.Join(dir, "notes/draft.txt"), bigContent(200*1024, 'y'), 0o644)
+	createTestFile(t, dir, "evidence/new.txt", "fresh")
+
+	stdout, _ := mustMkrk(t, dir, "status")
+	for _, want := range []string{"Untracked: 1", "evidence:", "new.txt", "Failing verification: 1", "Missing: 1"} {
+		if !strings.Contains(stdout, want) {
+			t.Fatalf("expected %q in status, got: %s", want, stdout)
+		}
+	}
+
+	stdout, _ = mustMkrk(t, dir, "status", "--porcelain")
+	if !strings.Contains(stdout, "?? evidence/new.txt\n") {
+		t.Fatalf("expected untracked porcelain line, got: %s", stdout)
+	}
+	if !strings.Contains(stdout, "M notes/draft.txt\n") {
+		t.Fatalf("expected modified porcelain line, got: %s", stdout)
+	}
+	if !strings.Contains(stdout, "D ") {
+		t.Fatalf("expected missing porcelain line, got: %s", stdout)
+	}
+	if strings.Contains(stdout, "\033[") {
+		t.Fatalf("porcelain output must not be colored, got: %q", stdout)
+	}
+}
+
 // --- Sync + List ---
 
 func TestSyncTracksFiles(t *testing.T) {
@@ -136,6 +211,121 @@ func TestSyncTracksFiles(t *testing.T) {
 	}
 }
 
+func TestSyncExtractsMetadata(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/memo.pdf", "%PDF-1.4\n1 0 obj << /Author (J. Whitfield) /Producer (Acme Scan 2.1) >> endobj\n%%EOF\n")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "list", "--long")
+	if !strings.Contains(stdout, "author: J. Whitfield") || !strings.Contains(stdout, "producer: Acme Scan 2.1") {
+		t.Fatalf("expected PDF metadata in long listing, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "list")
+	if strings.Contains(stdout, "author:") {
+		t.Fatalf("expected plain list without metadata, got: %s", stdout)
+	}
+}
+
+func TestSyncParallelJobs(t *testing.T) {
+	dir := initTestProject(t)
+	for i := range 40 {
+		createTestFile(t, dir, fmt.Sprintf("evidence/doc%02d.txt", i), fmt.Sprintf("document %d", i))
+	}
+
+	_, stderr := mustMkrk(t, dir, "sync", "--jobs", "4")
+	if !strings.Contains(stderr, "40 new") {
+		t.Fatalf("expected 40 new files, got: %s", stderr)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "list")
+	for _, name := range []string{"doc00.txt", "doc39.txt"} {
+		if !strings.Contains(stdout, name) {
+			t.Fatalf("expected %s in list output, got: %s", name, stdout)
+		}
+	}
+
+	_, stderr = mustMkrk(t, dir, "sync", "-j", "1")
+	if !strings.Contains(stderr, "40 ok") {
+		t.Fatalf("expected 40 ok on resync, got: %s", stderr)
+	}
+}
+
+func TestSyncQuickSkipsUnchangedFilesViaCache(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/memo.txt")
+	createTestFile(t, dir, "notes/memo.txt", "aaaa")
+	mustMkrk(t, dir, "sync")
+
+	// Same size and mtime: only --quick trusts the cached hash.
+	info, _ := os.Stat(path)
+	os.WriteFile(path, []byte("bbbb"), 0o644)
+	os.Chtimes(path, info.ModTime(), info.ModTime())
+
+	_, stderr := mustMkrk(t, dir, "sync", "--quick")
+	if !strings.Contains(stderr, "1 ok, 0 new") || !strings.Contains(stderr, "not reread") {
+		t.Fatalf("expected --quick to skip the cached file, got: %s", stderr)
+	}
+
+	_, stderr, _ = mkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "1 new") {
+		t.Fatalf("expected a plain sync to see the new content, got: %s", stderr)
+	}
+}
+
+func TestSyncAndStatusHonorIgnoreFile(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, ".mkrkignore", "*.tmp\nbuild/\n")
+	createTestFile(t, dir, "evidence/report.txt", "keep me")
+	createTestFile(t, dir, "evidence/scratch.tmp", "scratch")
+	createTestFile(t, dir, "notes/build/out.log", "artifact")
+
+	_, stderr := mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "1 new") {
+		t.Fatalf("expected only report.txt to be ingested, got: %s", stderr)
+	}
+	if strings.Contains(stderr, "scratch.tmp") || strings.Contains(stderr, "out.log") {
+		t.Fatalf("ignored files must not be synced, got: %s", stderr)
+	}
+
+	createTestFile(t, dir, "evidence/other.tmp", "more scratch")
+	stdout, _ := mustMkrk(t, dir, "status", "--porcelain")
+	if stdout != "" {
+		t.Fatalf("ignored files must not show as untracked, got: %s", stdout)
+	}
+}
+
+func TestSyncSymlinkAndHardlinkPolicy(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/original.txt", "linked content")
+	os.Symlink("original.txt", filepath.Join(dir, "notes/alias.txt"))
+	os.Link(filepath.Join(dir, "notes/original.txt"), filepath.Join(dir, "notes/twin.txt"))
+
+	_, stderr := mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "symlink skipped") {
+		t.Fatalf("expected symlink to be skipped by default, got: %s", stderr)
+	}
+	if !strings.Contains(stderr, "hardlink of notes/original.txt") {
+		t.Fatalf("expected hardlink to be detected, got: %s", stderr)
+	}
+	if !strings.Contains(stderr, "1 new") {
+		t.Fatalf("expected one ingested file, got: %s", stderr)
+	}
+
+	if _, _, err := mkrk(t, dir, "config", "symlinks", "sideways"); err == nil {
+		t.Fatal("expected invalid policy to be rejected")
+	}
+	mustMkrk(t, dir, "config", "symlinks", "link")
+	_, stderr = mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "1 new") || strings.Contains(stderr, "skipped") {
+		t.Fatalf("expected the link itself to be tracked, got: %s", stderr)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "config")
+	if !strings.Contains(stdout, "symlinks = link") {
+		t.Fatalf("expected setting in config list, got: %s", stdout)
+	}
+}
+
 // --- Verify (via sync) ---
 
 func TestSyncPassesUnmodified(t *testing.T) {
@@ -163,176 +353,2863 @@ func TestSyncFailsMissing(t *testing.T) {
 	}
 }
 
-// --- Tags ---
-
-func TestTagAndStatus(t *testing.T) {
+// --- Dry run ---
+
+func TestGlobalDryRunChangesNothing(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "rule", "add", "flag-tampered",
+		"--on", "verify_failure", "--action", "add_tag", "--tag", "tampered")
+
+	createTestFile(t, dir, "evidence/new.txt", "not yet ingested")
+	_, stderr := mustMkrk(t, dir, "--dry-run", "sync")
+	if !strings.Contains(stderr, "would ingest") {
+		t.Fatalf("expected dry-run ingest preview, got: %s", stderr)
+	}
+	stdout, _ := mustMkrk(t, dir, "status", "evidence/new.txt")
+	if !strings.Contains(stdout, "untracked") {
+		t.Fatalf("dry run must not ingest, got: %s", stdout)
+	}
+
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+	_, stderr, _ = mkrk(t, dir, "--dry-run", "verify", "notes/draft.txt")
+	if !strings.Contains(stderr, "would +!tampered") {
+		t.Fatalf("expected rule preview, got: %s", stderr)
+	}
+	mustMkrk(t, dir, "verify", "--accept", "--reason", "reviewed", "notes/draft.txt")
+	stdout, _ = mustMkrk(t, dir, "status", "notes/draft.txt")
+	if strings.Contains(stdout, "tampered") {
+		t.Fatalf("dry-run verify must not apply rule actions, got: %s", stdout)
+	}
+
+	if _, _, err := mkrk(t, dir, "--dry-run", "tag", "notes/draft.txt", "x"); err == nil {
+		t.Fatal("expected --dry-run to be refused by tag")
+	}
+}
+
+func TestGlobalDryRunRefusesEarlyCommands(t *testing.T) {
+	dir := t.TempDir()
+	_, stderr, err := mkrk(t, dir, "--dry-run", "init")
+	if err == nil || !strings.Contains(stderr, "--dry-run is not supported by 'init'") {
+		t.Fatalf("expected --dry-run to be refused by init, got: %v %s", err, stderr)
+	}
+	if entries, _ := os.ReadDir(dir); len(entries) != 0 {
+		t.Fatalf("a refused dry run must create nothing, found %d entries", len(entries))
+	}
+
+	dir = initTestProject(t)
+	if _, _, err := mkrk(t, dir, "--dry-run", "lock"); err == nil {
+		t.Fatal("expected --dry-run to be refused by lock")
+	}
+}
+
+// --- Verify ---
+
+// bigContent returns n bytes of deterministic content spanning several
+// fingerprint chunks, so a small edit yields a partial match.
+func bigContent(n int, fill byte) []byte {
+	b := make([]byte, n)
+	for i := range b {
+		b[i] = byte('a' + i%26)
+	}
+	b[n-1] = fill
+	return b
+}
+
+func TestVerifyReportsModified(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "verify")
+
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+
+	_, stderr, err := mkrk(t, dir, "verify")
+	if err == nil {
+		t.Fatal("expected verify to fail on modified file")
+	}
+	if !strings.Contains(stderr, "modified") {
+		t.Fatalf("expected 'modified' in stderr, got: %s", stderr)
+	}
+}
+
+func TestVerifyJSONReportAndExitCodes(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
+	createTestFile(t, dir, "notes/gone.txt", "short lived")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "verify", "--json")
+	if !strings.Contains(stdout, `"ok": 2`) {
+		t.Fatalf("expected clean JSON report, got: %s", stdout)
+	}
+
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+	stdout, _, err := mkrk(t, dir, "verify", "--json", "notes/draft.txt")
+	if code := exitCode(err); code != 2 {
+		t.Fatalf("expected exit 2 for modified, got %d", code)
+	}
+	var report struct {
+		Modified int `json:"modified"`
+		Files    []struct {
+			Path       string `json:"path"`
+			Status     string `json:"status"`
+			ChunkDiffs []struct {
+				Index int `json:"index"`
+			} `json:"chunk_diffs"`
+		} `json:"files"`
+	}
+	if err := json.Unmarshal([]byte(stdout), &report); err != nil {
+		t.Fatalf("invalid JSON report: %v\n%s", err, stdout)
+	}
+	if report.Modified != 1 || len(report.Files) != 1 || report.Files[0].Status != "modified" {
+		t.Fatalf("expected one modified file, got: %s", stdout)
+	}
+	if len(report.Files[0].ChunkDiffs) != 1 || report.Files[0].ChunkDiffs[0].Index != 3 {
+		t.Fatalf("expected the last chunk to differ, got: %s", stdout)
+	}
+
+	os.Remove(filepath.Join(dir, "notes/gone.txt"))
+	_, _, err = mkrk(t, dir, "verify")
+	if code := exitCode(err); code != 6 {
+		t.Fatalf("expected exit 6 for modified and missing, got %d", code)
+	}
+}
+
+func TestVerifyJobsReportsEveryFile(t *testing.T) {
+	dir := initTestProject(t)
+	for i := range 12 {
+		createTestFile(t, dir, fmt.Sprintf("notes/n%02d.txt", i), fmt.Sprintf("note %d", i))
+	}
+	mustMkrk(t, dir, "sync")
+	createTestFile(t, dir, "notes/n05.txt", "note 5, edited")
+
+	for _, jobs := range []string{"1", "4"} {
+		stdout, stderr, err := mkrk(t, dir, "verify", "--json", "--jobs", jobs)
+		if code := exitCode(err); code != 2 {
+			t.Fatalf("--jobs %s: expected exit 2, got %d\nstderr: %s", jobs, code, stderr)
+		}
+		var report struct {
+			Ok       int `json:"ok"`
+			Modified int `json:"modified"`
+			Files    []struct {
+				Path string `json:"path"`
+			} `json:"files"`
+		}
+		if err := json.Unmarshal([]byte(stdout), &report); err != nil {
+			t.Fatalf("invalid JSON report: %v\n%s", err, stdout)
+		}
+		if report.Ok != 11 || report.Modified != 1 || len(report.Files) != 12 {
+			t.Fatalf("--jobs %s: expected 11 ok and 1 modified, got: %s", jobs, stdout)
+		}
+		for i, f := range report.Files {
+			if want := fmt.Sprintf("notes/n%02d.txt", i); f.Path != want {
+				t.Fatalf("--jobs %s: expected files in walk order, got %s at %d", jobs, f.Path, i)
+			}
+		}
+	}
+}
+
+func TestVerifyContentDefinedChunksLocalizeInsertion(t *testing.T) {
+	dir := projectDir(t)
+	mustMkrk(t, dir, "init", "--chunking", "cdc")
+	content := make([]byte, 1<<20)
+	rand.New(rand.NewSource(1)).Read(content)
+	path := filepath.Join(dir, "notes/scan.bin")
+	os.WriteFile(path, content, 0o644)
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "status")
+	if !strings.Contains(stdout, "cdc chunks") {
+		t.Fatalf("expected cdc chunking in status, got: %s", stdout)
+	}
+
+	edited := append(append(append([]byte{}, content[:300_000]...), "inserted text"...), content[300_000:]...)
+	os.WriteFile(path, edited, 0o644)
+	stdout, _, err := mkrk(t, dir, "verify", "--json")
+	if code := exitCode(err); code != 2 {
+		t.Fatalf("expected exit 2 for modified, got %d", code)
+	}
+	var report struct {
+		Files []struct {
+			Status     string `json:"status"`
+			ChunkDiffs []struct {
+				Offset int64 `json:"offset"`
+			} `json:"chunk_diffs"`
+		} `json:"files"`
+	}
+	if err := json.Unmarshal([]byte(stdout), &report); err != nil {
+		t.Fatalf("invalid JSON report: %v\n%s", err, stdout)
+	}
+	if len(report.Files) != 1 || report.Files[0].Status != "modified" {
+		t.Fatalf("expected one modified file, got: %s", stdout)
+	}
+	if n := len(report.Files[0].ChunkDiffs); n == 0 || n > 4 {
+		t.Fatalf("expected the diff to stay near the insertion, got: %s", stdout)
+	}
+}
+
+func TestVerifyQuickTrustsUnchangedStatData(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/a.txt", "alpha")
+	createTestFile(t, dir, "notes/b.txt", "bravo")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "verify", "--quick", "--json")
+	if !strings.Contains(stdout, `"ok": 2`) || !strings.Contains(stdout, `"quick": 2`) {
+		t.Fatalf("expected both files trusted from stat data, got: %s", stdout)
+	}
+
+	// Rewritten in place with the same size and mtime: only a full verify
+	// rereads it.
+	path := filepath.Join(dir, "notes/a.txt")
+	info, _ := os.Stat(path)
+	os.WriteFile(path, []byte("ALPHA"), 0o644)
+	os.Chtimes(path, info.ModTime(), info.ModTime())
+	if _, stderr, err := mkrk(t, dir, "verify", "--quick"); err != nil {
+		t.Fatalf("expected quick verify to trust stat data, got %v\n%s", err, stderr)
+	}
+	if _, _, err := mkrk(t, dir, "verify"); err == nil {
+		t.Fatal("expected full verify to catch the rewrite")
+	}
+	os.WriteFile(path, []byte("alpha"), 0o644)
+	mustMkrk(t, dir, "verify")
+
+	createTestFile(t, dir, "notes/b.txt", "bravo, edited")
+	_, stderr, err := mkrk(t, dir, "verify", "--quick")
+	if err == nil || !strings.Contains(stderr, "1 file(s) unchanged since last hashed") {
+		t.Fatalf("expected the edited file reread and caught, got: %s", stderr)
+	}
+}
+
+func exitCode(err error) int {
+	var exitErr *exec.ExitError
+	if errors.As(err, &exitErr) {
+		return exitErr.ExitCode()
+	}
+	if err != nil {
+		return -1
+	}
+	return 0
+}
+
+func TestVerifyAcceptRehashes(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+	mustMkrk(t, dir, "sign", "notes/draft.txt", "review", "--pipeline", "editorial")
+
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+
+	if _, _, err := mkrk(t, dir, "verify", "--accept", "notes/draft.txt"); err == nil {
+		t.Fatal("expected --accept without --reason to fail")
+	}
+
+	_, stderr := mustMkrk(t, dir, "verify", "--accept", "--reason", "fixed typo", "notes/draft.txt")
+	if !strings.Contains(stderr, "accepted") {
+		t.Fatalf("expected acceptance, got: %s", stderr)
+	}
+	if !strings.Contains(stderr, "1 stale sign(s) revoked") {
+		t.Fatalf("expected stale sign revocation, got: %s", stderr)
+	}
+
+	mustMkrk(t, dir, "verify")
+}
+
+func TestVerifyAcceptRefusesImmutableBeforeAcceptingAny(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/draft.txt", "first draft")
+	createTestFile(t, dir, "evidence/doc.txt", "original evidence")
+	mustMkrk(t, dir, "sync")
+
+	os.WriteFile(filepath.Join(dir, "notes/draft.txt"), []byte("second draft"), 0o644)
+	evidence := filepath.Join(dir, "evidence/doc.txt")
+	os.Chmod(evidence, 0o644)
+	os.WriteFile(evidence, []byte("altered evidence"), 0o644)
+
+	if _, _, err := mkrk(t, dir, "verify", "--accept", "--reason", "edits", "notes/draft.txt", "evidence/doc.txt"); err == nil {
+		t.Fatal("expected accepting an immutable file to fail")
+	}
+	if _, stderr, err := mkrk(t, dir, "verify", "notes/draft.txt"); err == nil || !strings.Contains(stderr, "draft.txt") {
+		t.Fatalf("expected the draft to stay unaccepted, got: %v %s", err, stderr)
+	}
+}
+
+func TestVerifyFailureFiresRule(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "rule", "add", "flag-tampered",
+		"--on", "verify_failure", "--action", "add_tag", "--tag", "tampered")
+	stdout, _ := mustMkrk(t, dir, "rule", "list")
+	if !strings.Contains(stdout, "flag-tampered") {
+		t.Fatalf("expected rule in list, got: %s", stdout)
+	}
+
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+	_, stderr, _ := mkrk(t, dir, "verify")
+	if !strings.Contains(stderr, "flag-tampered") {
+		t.Fatalf("expected rule to fire, got: %s", stderr)
+	}
+
+	mustMkrk(t, dir, "verify", "--accept", "--reason", "reviewed", "notes/draft.txt")
+	stdout, _ = mustMkrk(t, dir, "status", "notes/draft.txt")
+	if !strings.Contains(stdout, "tampered") {
+		t.Fatalf("expected tampered tag after rule fired, got: %s", stdout)
+	}
+}
+
+// --- Proof ---
+
+func TestProofExportAndCheck(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/report.txt")
+	os.WriteFile(path, bigContent(300*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+
+	outside := t.TempDir()
+	excerpt := filepath.Join(outside, "excerpt.bin")
+	stdout, _ := mustMkrk(t, dir, "proof", "notes/report.txt", "--offset", "150000", "--excerpt", excerpt)
+	var doc struct {
+		Root  string `json:"root"`
+		Proof struct {
+			Index int `json:"index"`
+		} `json:"proof"`
+	}
+	if err := json.Unmarshal([]byte(stdout), &doc); err != nil {
+		t.Fatalf("invalid proof: %v\n%s", err, stdout)
+	}
+	if doc.Proof.Index != 2 || strings.Contains(stdout, "report.txt") {
+		t.Fatalf("expected a pathless proof of chunk 2, got: %s", stdout)
+	}
+	proof := filepath.Join(outside, "proof.json")
+	os.WriteFile(proof, []byte(stdout), 0o644)
+
+	_, stderr := mustMkrk(t, outside, "proof", "check", "--root", doc.Root, proof, excerpt)
+	if !strings.Contains(stderr, "chunk 2") {
+		t.Fatalf("expected the excerpt confirmed, got: %s", stderr)
+	}
+
+	data, _ := os.ReadFile(excerpt)
+	data[0] ^= 1
+	os.WriteFile(excerpt, data, 0o644)
+	if _, stderr, err := mkrk(t, outside, "proof", "check", proof, excerpt); err == nil || !strings.Contains(stderr, "does not belong") {
+		t.Fatalf("expected an altered excerpt refused, got: %s", stderr)
+	}
+}
+
+// --- Manifest ---
+
+func TestManifestExportAndVerify(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/a.txt", "first")
+	createTestFile(t, dir, "notes/b.txt", "second")
+	mustMkrk(t, dir, "sync")
+
+	out := filepath.Join(t.TempDir(), "SHA256SUMS")
+	mustMkrk(t, dir, "manifest", "export", "-o", out, ":notes")
+	data, err := os.ReadFile(out)
+	if err != nil {
+		t.Fatal(err)
+	}
+	// sha256("first")
+	want := "a7937b64b8caa58f03721bb6bacf5c78cb235febe0e70b1b84cd99541461a08e  notes/a.txt\n"
+	if !strings.HasPrefix(string(data), want) || !strings.Contains(string(data), "  notes/b.txt\n") {
+		t.Fatalf("unexpected manifest:\n%s", data)
+	}
+
+	_, stderr := mustMkrk(t, dir, "manifest", "verify", out)
+	if !strings.Contains(stderr, "Manifest: 2 tracked, 0 untracked, 0 differ, 0 missing") {
+		t.Fatalf("expected both entries tracked, got: %s", stderr)
+	}
+
+	// A partner's manifest, BSD style, naming our content under their
+	// own name, a file we hold differently, and one we lack.
+	partner := filepath.Join(t.TempDir(), "partner.sums")
+	os.WriteFile(partner, []byte(
+		"SHA256 (their/copy.txt) = a7937b64b8caa58f03721bb6bacf5c78cb235febe0e70b1b84cd99541461a08e\n"+
+			"SHA256 (notes/b.txt) = "+strings.Repeat("0", 64)+"\n"+
+			"SHA256 (elsewhere.txt) = "+strings.Repeat("1", 64)+"\n"), 0o644)
+	_, stderr, err = mkrk(t, dir, "manifest", "verify", partner)
+	if err == nil {
+		t.Fatal("expected verify to fail for content the project lacks")
+	}
+	if !strings.Contains(stderr, "their/copy.txt: tracked, under another name") ||
+		!strings.Contains(stderr, "Manifest: 1 tracked, 0 untracked, 1 differ, 1 missing") {
+		t.Fatalf("unexpected verify report: %s", stderr)
+	}
+
+	createTestFile(t, dir, "notes/b.txt", "tampered")
+	if _, _, err := mkrk(t, dir, "manifest", "export"); err == nil {
+		t.Fatal("expected export to refuse a modified file")
+	}
+}
+
+// --- Anchor ---
+
+// fakeCalendar stands in for an OpenTimestamps calendar: it answers a
+// submitted digest with a pending attestation, and once confirmed is set,
+// the upgrade to a Bitcoin block.
+func fakeCalendar(t *testing.T, confirmed *atomic.Bool) *httptest.Server {
+	t.Helper()
+	var srv *httptest.Server
+	srv = httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.Method == http.MethodPost && r.URL.Path == "/digest" {
+			uri := append([]byte{byte(len(srv.URL))}, srv.URL...)
+			answer := append([]byte{0x08, 0x00, 0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e}, byte(len(uri)))
+			w.Write(append(answer, uri...))
+			return
+		}
+		if !confirmed.Load() {
+			http.NotFound(w, r)
+			return
+		}
+		// Block 100, as a one-byte varuint.
+		w.Write([]byte{0x08, 0x00, 0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01, 0x01, 100})
+	}))
+	t.Cleanup(srv.Close)
+	return srv
+}
+
+func TestAnchorProjectState(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("privacy", "off")
+	wdb.Close()
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+	createTestFile(t, dir, "notes/lead.txt", "the lead")
+	mustMkrk(t, dir, "sync")
+
+	var confirmed atomic.Bool
+	srv := fakeCalendar(t, &confirmed)
+	mustMkrk(t, dir, "config", "ots-calendar", srv.URL)
+	stdout, stderr := mustMkrk(t, dir, "anchor")
+	if len(strings.TrimSpace(stdout)) != 64 || !strings.Contains(stderr, "pending at "+srv.URL) {
+		t.Fatalf("expected a digest and a pending anchor, got %q: %s", stdout, stderr)
+	}
+
+	stdout, _ = mustMkrk(t, dir, "anchor", "list")
+	if !strings.Contains(stdout, "pending") {
+		t.Fatalf("expected a pending anchor, got: %s", stdout)
+	}
+	_, stderr = mustMkrk(t, dir, "anchor", "upgrade")
+	if !strings.Contains(stderr, "0 upgraded, 1 still pending") {
+		t.Fatalf("expected nothing to upgrade yet, got: %s", stderr)
+	}
+	confirmed.Store(true)
+	_, stderr = mustMkrk(t, dir, "anchor", "upgrade")
+	if !strings.Contains(stderr, "anchor 1: Bitcoin block 100") {
+		t.Fatalf("expected the anchor upgraded, got: %s", stderr)
+	}
+
+	_, stderr = mustMkrk(t, dir, "anchor", "verify", "1", "notes/lead.txt")
+	if !strings.Contains(stderr, "1 of 1 anchored hashes are still tracked") ||
+		!strings.Contains(stderr, "notes/lead.txt: anchored with this content") ||
+		!strings.Contains(stderr, "audit log still passes through") {
+		t.Fatalf("unexpected verify report: %s", stderr)
+	}
+
+	out := filepath.Join(t.TempDir(), "state.txt")
+	mustMkrk(t, dir, "anchor", "export", "1", "-o", out)
+	proof, err := os.ReadFile(out + ".ots")
+	if err != nil || !bytes.HasPrefix(proof, []byte("\x00OpenTimestamps\x00\x00Proof\x00")) {
+		t.Fatalf("expected an .ots proof, got %v", err)
+	}
+
+	// Rewriting the audit log behind the anchor is caught.
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	pdb.DB().Exec(`DELETE FROM audit_log`)
+	pdb.InsertAudit("ingest", nil, nil, nil)
+	pdb.Close()
+	_, stderr, err = mkrk(t, dir, "anchor", "verify", "1")
+	if err == nil || !strings.Contains(stderr, "the log was rewritten") {
+		t.Fatalf("expected the rewritten audit log to fail verification, got %v: %s", err, stderr)
+	}
+}
+
+// --- Tags ---
+
+func TestTagAndStatus(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "tagged content")
+	mustMkrk(t, dir, "sync")
+
+	_, stderr := mustMkrk(t, dir, "tag", "evidence/doc.txt", "important")
+	if !strings.Contains(stderr, "!important") {
+		t.Fatalf("expected tag confirmation, got: %s", stderr)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "status", "evidence/doc.txt")
+	if !strings.Contains(stdout, "important") {
+		t.Fatalf("expected tag in status, got: %s", stdout)
+	}
+}
+
+func TestTagRemove(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "tagged content")
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "tag", "evidence/doc.txt", "removeme")
+	mustMkrk(t, dir, "tag", "--remove", "evidence/doc.txt", "removeme")
+
+	stdout, _ := mustMkrk(t, dir, "status", "evidence/doc.txt")
+	if strings.Contains(stdout, "removeme") {
+		t.Fatalf("tag should be removed, got: %s", stdout)
+	}
+}
+
+func TestTagMultipleReferencesAndTags(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/a.txt", "first")
+	createTestFile(t, dir, "evidence/b.txt", "second")
+	createTestFile(t, dir, "notes/c.txt", "third")
+	mustMkrk(t, dir, "sync")
+
+	_, stderr := mustMkrk(t, dir, "tag", "evidence", "notes/c.txt", "--tag", "needs-ocr", "--tag", "q3")
+	if !strings.Contains(stderr, "Tagged 3 file(s) with needs-ocr, q3") {
+		t.Fatalf("expected summary, got: %s", stderr)
+	}
+	for _, ref := range []string{"evidence/a.txt", "evidence/b.txt", "notes/c.txt"} {
+		stdout, _ := mustMkrk(t, dir, "status", ref)
+		if !strings.Contains(stdout, "needs-ocr") || !strings.Contains(stdout, "q3") {
+			t.Fatalf("expected both tags on %s, got: %s", ref, stdout)
+		}
+	}
+}
+
+func TestAttrSetListAndFilter(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/a.txt", "first")
+	createTestFile(t, dir, "evidence/b.txt", "second")
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "attr", "set", "evidence/a.txt", "foia-request", "2024-0117")
+	mustMkrk(t, dir, "attr", "set", "evidence/b.txt", "foia-request", "2023-0042")
+	mustMkrk(t, dir, "attr", "set", "evidence/b.txt", "pages", "12")
+
+	stdout, _ := mustMkrk(t, dir, "attr", "get", "evidence/a.txt", "foia-request")
+	if strings.TrimSpace(stdout) != "2024-0117" {
+		t.Fatalf("expected the value back, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "attr", "list", "evidence/b.txt")
+	if !strings.Contains(stdout, "foia-request: 2023-0042") || !strings.Contains(stdout, "pages: 12") {
+		t.Fatalf("expected both attributes listed, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, dir, "foreach", ":evidence[attr.foia-request=2024-*]", "--", "echo", "{path}")
+	if !strings.Contains(stdout, "a.txt") || strings.Contains(stdout, "b.txt") {
+		t.Fatalf("expected only a.txt for 2024 requests, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "foreach", ":evidence[attr.pages>9]", "--", "echo", "{path}")
+	if !strings.Contains(stdout, "b.txt") || strings.Contains(stdout, "a.txt") {
+		t.Fatalf("expected only b.txt over 9 pages, got: %s", stdout)
+	}
+
+	mustMkrk(t, dir, "attr", "remove", "evidence/a.txt", "foia-request")
+	if _, _, err := mkrk(t, dir, "attr", "get", "evidence/a.txt", "foia-request"); err == nil {
+		t.Fatal("expected a removed attribute to be reported missing")
+	}
+	if _, _, err := mkrk(t, dir, "attr", "set", "evidence/a.txt", "bad key", "x"); err == nil {
+		t.Fatal("expected an invalid key to be rejected")
+	}
+}
+
+// --- Pipeline ---
+
+func TestPipelineCreateAndRemove(t *testing.T) {
+	dir := initTestProject(t)
+
+	_, stderr := mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+	if !strings.Contains(stderr, "Created pipeline") {
+		t.Fatalf("expected creation message, got: %s", stderr)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "status")
+	if !strings.Contains(stdout, "editorial") {
+		t.Fatalf("expected pipeline in status, got: %s", stdout)
+	}
+
+	mustMkrk(t, dir, "pipeline", "--remove", "editorial")
+
+	stdout, _ = mustMkrk(t, dir, "status")
+	if strings.Contains(stdout, "editorial") {
+		t.Fatalf("pipeline should be removed, got: %s", stdout)
+	}
+}
+
+// --- Sign ---
+
+func TestSignAndRevoke(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+
+	_, stderr := mustMkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial")
+	if !strings.Contains(stderr, "Signed") {
+		t.Fatalf("expected sign confirmation, got: %s", stderr)
+	}
+
+	_, stderr = mustMkrk(t, dir, "sign", "--remove", "evidence/doc.txt", "review", "--pipeline", "editorial")
+	if !strings.Contains(stderr, "Revoked") {
+		t.Fatalf("expected revoke confirmation, got: %s", stderr)
+	}
+}
+
+func TestSignBatchOverReference(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/a.txt", "first document")
+	createTestFile(t, dir, "evidence/b.txt", "second document")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+
+	_, stderr := mustMkrk(t, dir, "sign", "evidence", "review", "--pipeline", "editorial")
+	if !strings.Contains(stderr, "Signed 2, skipped 0, failed 0") {
+		t.Fatalf("expected batch summary, got: %s", stderr)
+	}
+
+	_, stderr = mustMkrk(t, dir, "sign", "--remove", "evidence", "review", "--pipeline", "editorial")
+	if !strings.Contains(stderr, "Revoked 2, skipped 0, failed 0") {
+		t.Fatalf("expected batch revoke summary, got: %s", stderr)
+	}
+}
+
+func TestSignQuorum(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "approval", "--states", "draft,published",
+		"--transitions", `{"published":["editor x2"]}`)
+
+	t.Setenv("MKRK_SIGNER", "alice")
+	mustMkrk(t, dir, "sign", "evidence/doc.txt", "editor", "--pipeline", "approval")
+	if _, _, err := mkrk(t, dir, "sign", "evidence/doc.txt", "editor", "--pipeline", "approval"); err == nil {
+		t.Fatal("expected a second sign by the same signer to fail")
+	}
+
+	stdout, _ := mustMkrk(t, dir, "state", "evidence/doc.txt")
+	if !strings.Contains(stdout, "approval: draft (published needs editor x2 (1 of 2: alice))") {
+		t.Fatalf("expected missing signer report, got: %s", stdout)
+	}
+
+	t.Setenv("MKRK_SIGNER", "bob")
+	mustMkrk(t, dir, "sign", "evidence/doc.txt", "editor", "--pipeline", "approval")
+	stdout, _ = mustMkrk(t, dir, "state", "evidence/doc.txt", "--pipeline", "approval")
+	if !strings.Contains(stdout, "approval: published") {
+		t.Fatalf("expected quorum to publish, got: %s", stdout)
+	}
+}
+
+func TestStateHistory(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+
+	t.Setenv("MKRK_SIGNER", "alice")
+	mustMkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial")
+	mustMkrk(t, dir, "sign", "--remove", "evidence/doc.txt", "review", "--pipeline", "editorial")
+
+	stdout, _ := mustMkrk(t, dir, "state", "--history", "evidence/doc.txt")
+	for _, want := range []string{
+		"editorial: draft -> review (sign 'review' by alice)",
+		"editorial: review -> draft (revoke 'review' by alice)",
+	} {
+		if !strings.Contains(stdout, want) {
+			t.Fatalf("expected %q in history, got: %s", want, stdout)
+		}
+	}
+	if strings.Index(stdout, "-> review") > strings.Index(stdout, "-> draft") {
+		t.Fatalf("expected history oldest first, got: %s", stdout)
+	}
+}
+
+func TestSignWithSSHKey(t *testing.T) {
+	if _, err := exec.LookPath("ssh-keygen"); err != nil {
+		t.Skip("ssh-keygen not available")
+	}
+	t.Setenv("MKRK_TRUSTED_KEYS", filepath.Join(t.TempDir(), "trusted_keys"))
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+
+	key := filepath.Join(t.TempDir(), "id_ed25519")
+	if out, err := exec.Command("ssh-keygen", "-q", "-t", "ed25519", "-N", "", "-f", key).CombinedOutput(); err != nil {
+		t.Fatalf("ssh-keygen: %v: %s", err, out)
+	}
+
+	_, stderr := mustMkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial", "--ssh", "--key", key)
+	if !strings.Contains(stderr, "ssh signature") {
+		t.Fatalf("expected ssh signature, got: %s", stderr)
+	}
+	mustMkrk(t, dir, "sign", "evidence/doc.txt", "published", "--pipeline", "editorial")
+
+	_, stderr = mustMkrk(t, dir, "sign", "verify", "evidence/doc.txt")
+	if !strings.Contains(stderr, "Signs: 1 good, 0 bad, 0 stale, 1 unsigned") {
+		t.Fatalf("expected one good and one unsigned sign, got: %s", stderr)
+	}
+	if !strings.Contains(stderr, "ED25519 key SHA256:") {
+		t.Fatalf("expected the signing key to be reported, got: %s", stderr)
+	}
+}
+
+func TestSignWithProjectEd25519Key(t *testing.T) {
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	t.Setenv("MKRK_TRUSTED_KEYS", filepath.Join(t.TempDir(), "trusted_keys"))
+	dir := projectDir(t)
+	_, stderr := mustMkrk(t, dir, "init", "--signing-key")
+	if !strings.Contains(stderr, "Generated the project's ed25519 signing key") {
+		t.Fatalf("expected a signing key at init, got: %s", stderr)
+	}
+	stdout, _ := mustMkrk(t, dir, "status")
+	if !strings.Contains(stdout, "Signing key: ed25519 ") {
+		t.Fatalf("expected the signing key in status, got: %s", stdout)
+	}
+	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+
+	t.Setenv("MKRK_PASSPHRASE", "wrong")
+	if _, _, err := mkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial", "--backend", "ed25519"); err == nil {
+		t.Fatal("expected signing to fail without the project passphrase")
+	}
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	_, stderr = mustMkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial", "--backend", "ed25519")
+	if !strings.Contains(stderr, "ed25519 signature") {
+		t.Fatalf("expected an ed25519 signature, got: %s", stderr)
+	}
+
+	// Checking needs only the public key stored with the sign.
+	t.Setenv("MKRK_PASSPHRASE", "")
+	_, stderr = mustMkrk(t, dir, "sign", "verify", "evidence/doc.txt")
+	if !strings.Contains(stderr, "Signs: 1 good, 0 bad, 0 stale, 0 unsigned") || !strings.Contains(stderr, "this project's key") {
+		t.Fatalf("expected a good signature by the project key, got: %s", stderr)
+	}
+
+	// Elsewhere, where nobody trusts the key, the same signature is only
+	// self-consistent: anyone able to write the database could make one.
+	t.Setenv("MKRK_TRUSTED_KEYS", filepath.Join(t.TempDir(), "trusted_keys"))
+	_, stderr = mustMkrk(t, dir, "sign", "verify", "evidence/doc.txt")
+	if !strings.Contains(stderr, "Signs: 0 good, 0 bad, 0 stale, 0 unsigned, 1 by untrusted keys") || !strings.Contains(stderr, "not a trusted key") {
+		t.Fatalf("expected the signature reported as untrusted, got: %s", stderr)
+	}
+
+	_, stderr, err := mkrk(t, dir, "sign", "evidence/doc.txt", "published", "--pipeline", "editorial", "--backend", "ed25519", "--key", "x")
+	if err == nil || !strings.Contains(stderr, "--key needs") {
+		t.Fatalf("expected --key to be refused for ed25519, got %v: %s", err, stderr)
+	}
+}
+
+func TestSignVerifyFlagsStaleHash(t *testing.T) {
+	dir := initTestProject(t)
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+	mustMkrk(t, dir, "sign", "notes/draft.txt", "review", "--pipeline", "editorial")
+
+	_, stderr := mustMkrk(t, dir, "sign", "verify", "notes/draft.txt")
+	if !strings.Contains(stderr, "Signs: 0 good, 0 bad, 0 stale, 1 unsigned") {
+		t.Fatalf("expected one unsigned sign, got: %s", stderr)
+	}
+
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+	_, stderr, err := mkrk(t, dir, "sign", "verify", "notes/draft.txt")
+	if err == nil {
+		t.Fatal("expected verify to fail once the file changed")
+	}
+	if !strings.Contains(stderr, "but the file is now") || !strings.Contains(stderr, "1 stale") {
+		t.Fatalf("expected the sign to be flagged stale, got: %s", stderr)
+	}
+}
+
+func TestSignTimestampUsesPrivacyProxy(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+
+	// Nothing listens on the proxy, so the request must fail rather than go direct.
+	t.Setenv("MKRK_SOCKS", "socks5h://127.0.0.1:1")
+	_, stderr, err := mkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial",
+		"--timestamp", "--tsa", "http://tsa.invalid/tsr")
+	if err == nil {
+		t.Fatal("expected timestamping through an unreachable proxy to fail")
+	}
+	if !strings.Contains(stderr, "go through socks5h://127.0.0.1:1") {
+		t.Fatalf("expected the proxy to be announced, got: %s", stderr)
+	}
+
+	_, stderr = mustMkrk(t, dir, "sign", "verify", "evidence/doc.txt")
+	if !strings.Contains(stderr, "Signs: 0 good, 0 bad, 0 stale, 0 unsigned") {
+		t.Fatalf("expected no sign to be recorded, got: %s", stderr)
+	}
+}
+
+// --- Read ---
+
+func TestRead(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/report.txt", "secret content\n")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "read", "evidence/report.txt")
+	if !strings.Contains(stdout, "secret content") {
+		t.Fatalf("expected file content, got: %s", stdout)
+	}
+}
+
+func TestDeriveLinksDerivedFile(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/scan.txt", "scanned page")
+	mustMkrk(t, dir, "sync")
+	createTestFile(t, dir, "notes/scan-ocr.txt", "recognized text")
+
+	_, stderr := mustMkrk(t, dir, "derive", "evidence/scan.txt", "notes/scan-ocr.txt", "--via", "ocr")
+	if !strings.Contains(stderr, "derived from") {
+		t.Fatalf("expected confirmation, got: %s", stderr)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "list")
+	var line string
+	for _, l := range strings.Split(stdout, "\n") {
+		if strings.Contains(l, "scan-ocr.txt") {
+			line = l
+		}
+	}
+	if !strings.Contains(line, "derived from") || !strings.Contains(line, "scan.txt") || !strings.Contains(line, "via ocr") {
+		t.Fatalf("expected list to show derivation, got: %s", stdout)
+	}
+
+	_, stderr = mustMkrk(t, dir, "read", "notes/scan-ocr.txt")
+	if !strings.Contains(stderr, "via ocr") {
+		t.Fatalf("expected read to note derivation, got: %s", stderr)
+	}
+
+	if _, _, err := mkrk(t, dir, "derive", "evidence/scan.txt", "notes/scan-ocr.txt"); err == nil {
+		t.Fatal("expected derive without --via to fail")
+	}
+}
+
+func TestVersionsKeepPreviousRevisions(t *testing.T) {
+	dir := initTestProject(t)
+	first, second := bigContent(200*1024, 'x'), bigContent(200*1024, 'y')
+	os.WriteFile(filepath.Join(dir, "notes/draft.txt"), first, 0o644)
+	createTestFile(t, dir, "evidence/orig.txt", "original")
+	mustMkrk(t, dir, "sync")
+
+	os.WriteFile(filepath.Join(dir, "notes/draft.txt"), second, 0o644)
+	mustMkrk(t, dir, "verify", "--accept", "--reason", "edit", "notes/draft.txt")
+
+	stdout, _ := mustMkrk(t, dir, "versions", "notes/draft.txt")
+	lines := strings.Split(strings.TrimSpace(stdout), "\n")
+	if len(lines) != 2 || !strings.HasPrefix(lines[0], "v1") || !strings.Contains(lines[1], "(current)") {
+		t.Fatalf("expected two revisions with v2 current, got: %s", stdout)
+	}
+
+	// read adds a trailing newline to content that lacks one.
+	stdout, _ = mustMkrk(t, dir, "read", "notes/draft.txt", "--version", "1")
+	if stdout != string(first)+"\n" {
+		t.Fatal("expected first revision content")
+	}
+	stdout, _ = mustMkrk(t, dir, "read", "notes/draft.txt", "--version", "2")
+	if stdout != string(second)+"\n" {
+		t.Fatal("expected second revision content")
+	}
+	if _, _, err := mkrk(t, dir, "read", "notes/draft.txt", "--version", "3"); err == nil {
+		t.Fatal("expected missing revision to fail")
+	}
+
+	// Immutable originals never change, so no copy is kept for them.
+	entries, _ := os.ReadDir(filepath.Join(dir, ".mkrk-versions"))
+	total := 0
+	for _, e := range entries {
+		sub, _ := os.ReadDir(filepath.Join(dir, ".mkrk-versions", e.Name()))
+		total += len(sub)
+	}
+	if total != 2 {
+		t.Fatalf("expected 2 kept revisions (none for evidence), got %d", total)
+	}
+}
+
+func TestContentAddressedStore(t *testing.T) {
+	dir := initTestProject(t)
+	mustMkrk(t, dir, "config", "store", "on")
+	createTestFile(t, dir, "notes/draft.txt", "first draft\n")
+	mustMkrk(t, dir, "sync")
+
+	stored := func() []string {
+		entries, _ := os.ReadDir(filepath.Join(dir, ".mkrk-store"))
+		var contents []string
+		for _, e := range entries {
+			data, _ := os.ReadFile(filepath.Join(dir, ".mkrk-store", e.Name()))
+			contents = append(contents, string(data))
+		}
+		return contents
+	}
+	if got := stored(); len(got) != 1 || got[0] != "first draft\n" {
+		t.Fatalf("expected the original in the store, got %q", got)
+	}
+
+	// Revisions go to the store too, and are read from there.
+	os.WriteFile(filepath.Join(dir, "notes/draft.txt"), []byte("second draft\n"), 0o644)
+	mustMkrk(t, dir, "verify", "--accept", "--reason", "edit", "notes/draft.txt")
+	if got := stored(); len(got) != 2 {
+		t.Fatalf("expected both revisions in the store, got %q", got)
+	}
+	if _, err := os.Stat(filepath.Join(dir, ".mkrk-versions")); err == nil {
+		t.Fatal("expected no separate version store")
+	}
+	stdout, _ := mustMkrk(t, dir, "read", "notes/draft.txt", "--version", "1")
+	if stdout != "first draft\n" {
+		t.Fatalf("expected the first revision, got %q", stdout)
+	}
+
+	// The original outlives its file in the working tree.
+	os.Remove(filepath.Join(dir, "notes/draft.txt"))
+	_, stderr, _ := mkrk(t, dir, "verify")
+	if !strings.Contains(stderr, "original kept at") {
+		t.Fatalf("expected verify to point at the kept original, got: %s", stderr)
+	}
+}
+
+func TestStorageOffloadAndFetch(t *testing.T) {
+	dir := initTestProject(t)
+	remote := filepath.Join(t.TempDir(), "remote")
+	mustMkrk(t, dir, "config", "storage-url", remote)
+	content := "hours of interview footage\n"
+	createTestFile(t, dir, "notes/clip.txt", content)
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "storage", "offload", "notes/clip.txt")
+	onDisk, _ := os.ReadFile(filepath.Join(dir, "notes/clip.txt"))
+	if !strings.HasPrefix(string(onDisk), "MKRKSTUB1\n") {
+		t.Fatalf("expected a stub in the working tree, got %q", onDisk)
+	}
+	entries, _ := os.ReadDir(remote)
+	if len(entries) != 1 {
+		t.Fatalf("expected one object in remote storage, got %d", len(entries))
+	}
+	stdout, _ := mustMkrk(t, dir, "storage", "list")
+	if strings.Count(stdout, "\n") != 1 || !strings.Contains(stdout, "unlocked") {
+		t.Fatalf("unexpected storage list: %s", stdout)
+	}
+
+	// The stub stands for its content, which read fetches on demand.
+	mustMkrk(t, dir, "verify")
+	mustMkrk(t, dir, "sync")
+	if stdout, _ = mustMkrk(t, dir, "read", "notes/clip.txt"); stdout != content {
+		t.Fatalf("expected the offloaded content, got %q", stdout)
+	}
+
+	// Fetched content is checked against the tracked hash.
+	obj := filepath.Join(remote, entries[0].Name())
+	os.Chmod(obj, 0o644)
+	os.WriteFile(obj, []byte("doctored footage\n"), 0o644)
+	os.RemoveAll(filepath.Join(dir, ".mkrk-store"))
+	if _, stderr, err := mkrk(t, dir, "read", "notes/clip.txt"); err == nil || !strings.Contains(stderr, "does not match") {
+		t.Fatalf("expected doctored content to be refused, got: %s", stderr)
+	}
+
+	os.WriteFile(obj, []byte(content), 0o444)
+	mustMkrk(t, dir, "storage", "fetch", "notes/clip.txt")
+	if onDisk, _ = os.ReadFile(filepath.Join(dir, "notes/clip.txt")); string(onDisk) != content {
+		t.Fatalf("expected the content back in the working tree, got %q", onDisk)
+	}
+	mustMkrk(t, dir, "verify")
+}
+
+func TestEncryptedCategoryReadsTransparently(t *testing.T) {
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/source.txt", "meet at the usual place\n")
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "tag", "notes/source.txt", "tip")
+	mustMkrk(t, dir, "encrypt", "notes")
+	onDisk, _ := os.ReadFile(filepath.Join(dir, "notes/source.txt"))
+	if !strings.HasPrefix(string(onDisk), "MKRKENC1") || strings.Contains(string(onDisk), "usual place") {
+		t.Fatal("expected file to be encrypted on disk")
+	}
+	mustMkrk(t, dir, "verify")
+
+	// The record keeps the plaintext's hash, so tags still match it.
+	sum := sha256.Sum256([]byte("meet at the usual place\n"))
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	file, _ := pdb.GetFileByHash(hex.EncodeToString(sum[:]))
+	pdb.Close()
+	if file == nil {
+		t.Fatal("expected the record to keep the plaintext hash after encryption")
+	}
+	stdout, _ := mustMkrk(t, dir, "status", "notes/source.txt")
+	if !strings.Contains(stdout, "tip") {
+		t.Fatalf("expected the tag to survive encryption, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, dir, "read", "notes/source.txt")
+	if !strings.Contains(stdout, "meet at the usual place") {
+		t.Fatalf("expected decrypted content, got: %s", stdout)
+	}
+
+	createTestFile(t, dir, "notes/later.txt", "second tip")
+	_, stderr := mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "(encrypted)") || !strings.Contains(stderr, "1 new") {
+		t.Fatalf("expected new file to be encrypted and ingested, got: %s", stderr)
+	}
+	onDisk, _ = os.ReadFile(filepath.Join(dir, "notes/later.txt"))
+	if strings.Contains(string(onDisk), "second tip") {
+		t.Fatal("expected new file to be encrypted on disk")
+	}
+
+	dbFile, _ := os.ReadFile(filepath.Join(dir, ".mkrk"))
+	if strings.Contains(string(dbFile), "correct horse") {
+		t.Fatal("passphrase must never be stored")
+	}
+
+	t.Setenv("MKRK_PASSPHRASE", "wrong")
+	if _, stderr, err := mkrk(t, dir, "read", "notes/source.txt"); err == nil || !strings.Contains(stderr, "wrong passphrase") {
+		t.Fatalf("expected wrong passphrase to be rejected, got: %s", stderr)
+	}
+
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	mustMkrk(t, dir, "encrypt", "notes", "--remove")
+	onDisk, _ = os.ReadFile(filepath.Join(dir, "notes/source.txt"))
+	if string(onDisk) != "meet at the usual place\n" {
+		t.Fatalf("expected plaintext restored, got: %q", onDisk)
+	}
+	mustMkrk(t, dir, "verify")
+}
+
+func TestEncryptLeavesTrackedImmutableFilesInPlaintext(t *testing.T) {
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/memo.txt", "original evidence")
+	mustMkrk(t, dir, "sync")
+
+	_, stderr := mustMkrk(t, dir, "encrypt", "evidence")
+	if !strings.Contains(stderr, "left in plaintext") {
+		t.Fatalf("expected the tracked immutable file to be left alone, got: %s", stderr)
+	}
+	onDisk, _ := os.ReadFile(filepath.Join(dir, "evidence/memo.txt"))
+	if string(onDisk) != "original evidence" {
+		t.Fatalf("expected immutable evidence untouched, got: %q", onDisk)
+	}
+
+	createTestFile(t, dir, "evidence/later.txt", "new evidence")
+	_, stderr = mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "(encrypted)") || !strings.Contains(stderr, "1 new") {
+		t.Fatalf("expected only the new file to be encrypted, got: %s", stderr)
+	}
+	onDisk, _ = os.ReadFile(filepath.Join(dir, "evidence/memo.txt"))
+	if string(onDisk) != "original evidence" {
+		t.Fatalf("expected sync to leave immutable evidence untouched, got: %q", onDisk)
+	}
+	mustMkrk(t, dir, "verify")
+}
+
+// --- Lock ---
+
+func TestLockAndUnlockProject(t *testing.T) {
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	dir := initTestProject(t)
+	createTestFile(t, dir, "sources/tip.txt", "whistleblower contact")
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "lock")
+	sealed, _ := os.ReadFile(filepath.Join(dir, ".mkrk"))
+	if !strings.HasPrefix(string(sealed), "MKRKLCK1") || strings.Contains(string(sealed), "CREATE TABLE") {
+		t.Fatal("expected project database to be sealed")
+	}
+	if _, err := os.Stat(filepath.Join(dir, ".mkrk-wal")); err == nil {
+		t.Fatal("expected no WAL left beside a locked database")
+	}
+
+	if _, stderr, err := mkrk(t, dir, "status"); err == nil || !strings.Contains(stderr, "locked") {
+		t.Fatalf("expected locked project to be refused, got: %s", stderr)
+	}
+	if _, stderr, err := mkrk(t, dir, "lock"); err == nil || !strings.Contains(stderr, "already locked") {
+		t.Fatalf("expected second lock to fail, got: %s", stderr)
+	}
+
+	t.Setenv("MKRK_PASSPHRASE", "wrong")
+	if _, stderr, err := mkrk(t, dir, "unlock"); err == nil || !strings.Contains(stderr, "wrong passphrase") {
+		t.Fatalf("expected wrong passphrase to be rejected, got: %s", stderr)
+	}
+
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	mustMkrk(t, dir, "unlock")
+	stdout, _ := mustMkrk(t, dir, "list")
+	if !strings.Contains(stdout, "tip.txt") {
+		t.Fatalf("expected tracked files after unlock, got: %s", stdout)
+	}
+	mustMkrk(t, dir, "verify")
+}
+
+func TestEncryptDatabaseAtRest(t *testing.T) {
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+	createTestFile(t, dir, "sources/tip.txt", "whistleblower contact")
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "encrypt", "--database")
+	createTestFile(t, dir, "sources/memo.txt", "meeting notes")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "tag", "sources/tip.txt", "confidential")
+	sealed, _ := os.ReadFile(filepath.Join(dir, ".mkrk"))
+	if !strings.HasPrefix(string(sealed), "MKRKSDB1") || strings.Contains(string(sealed), "confidential") {
+		t.Fatal("expected project database to stay encrypted on disk")
+	}
+	stdout, _ := mustMkrk(t, dir, "tags")
+	if !strings.Contains(stdout, "confidential") {
+		t.Fatalf("expected the tag made while encrypted, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "list")
+	if !strings.Contains(stdout, "memo.txt") {
+		t.Fatalf("expected the file synced while encrypted, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "doctor")
+	if !strings.Contains(stdout, "encrypted at rest") {
+		t.Fatalf("expected doctor to report encryption, got: %s", stdout)
+	}
+
+	t.Setenv("MKRK_PASSPHRASE", "wrong")
+	if _, stderr, err := mkrk(t, dir, "list"); err == nil || !strings.Contains(stderr, "wrong passphrase") {
+		t.Fatalf("expected wrong passphrase to be rejected, got: %s", stderr)
+	}
+
+	keyfile := filepath.Join(t.TempDir(), "key")
+	os.WriteFile(keyfile, []byte("random key material"), 0o600)
+	t.Setenv("MKRK_KEYFILE", keyfile)
+	mustMkrk(t, wsDir, "encrypt", "--database", "--workspace")
+	if sealed, _ := os.ReadFile(filepath.Join(wsDir, ".mksp")); !strings.HasPrefix(string(sealed), "MKRKSDB1") {
+		t.Fatal("expected workspace database encrypted")
+	}
+	// The keyfile now opens the workspace but not the project.
+	if _, stderr, err := mkrk(t, dir, "list"); err == nil || !strings.Contains(stderr, "wrong passphrase") {
+		t.Fatalf("expected the project to need its own passphrase, got: %s", stderr)
+	}
+	mustMkrk(t, wsDir, "encrypt", "--database", "--workspace", "--remove")
+	t.Setenv("MKRK_KEYFILE", "")
+	t.Setenv("MKRK_PASSPHRASE", "correct horse")
+	mustMkrk(t, dir, "encrypt", "--database", "--remove")
+	for _, path := range []string{filepath.Join(dir, ".mkrk"), filepath.Join(wsDir, ".mksp")} {
+		if data, _ := os.ReadFile(path); !strings.HasPrefix(string(data), "SQLite format 3") {
+			t.Fatalf("expected %s back in plaintext", path)
+		}
+	}
+	stdout, _ = mustMkrk(t, dir, "tags")
+	if !strings.Contains(stdout, "confidential") {
+		t.Fatalf("expected tags kept after decrypting, got: %s", stdout)
+	}
+}
+
+func TestDbBackupAndVacuum(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+	createTestFile(t, dir, "sources/tip.txt", "whistleblower contact")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "db", "backup")
+	backup := strings.TrimSpace(stdout)
+	if filepath.Dir(backup) != filepath.Join(dir, ".mkrk-backups") {
+		t.Fatalf("expected the backup in .mkrk-backups, got: %s", backup)
+	}
+	copied, err := db.OpenProject(backup)
+	if err != nil {
+		t.Fatal(err)
+	}
+	var files int
+	copied.DB().QueryRow(`SELECT COUNT(*) FROM files`).Scan(&files)
+	copied.Close()
+	if files != 1 {
+		t.Fatalf("expected the backup to hold the project's file, got %d", files)
+	}
+	if _, _, err := mkrk(t, dir, "db", "backup", backup); err == nil {
+		t.Fatal("expected a backup over an existing file to fail")
+	}
+	mustMkrk(t, dir, "db", "vacuum")
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var detail string
+	pdb.DB().QueryRow(`SELECT detail FROM audit_log WHERE operation = 'backup'`).Scan(&detail)
+	var vacuums int
+	pdb.DB().QueryRow(`SELECT COUNT(*) FROM audit_log WHERE operation = 'vacuum'`).Scan(&vacuums)
+	pdb.Close()
+	if !strings.Contains(detail, "sha256") || strings.Contains(detail, ".mkrk-backups") {
+		t.Fatalf("expected the backup audited by hash and not path, got: %s", detail)
+	}
+	if vacuums != 1 {
+		t.Fatalf("expected one vacuum audit entry, got %d", vacuums)
+	}
+
+	stdout, _ = mustMkrk(t, wsDir, "db", "backup", "--workspace")
+	if filepath.Dir(strings.TrimSpace(stdout)) != filepath.Join(wsDir, ".mksp-backups") {
+		t.Fatalf("expected the workspace backup in .mksp-backups, got: %s", stdout)
+	}
+}
+
+// --- Audit ---
+
+func TestAuditVerifyDetectsTampering(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
+	createTestFile(t, dir, "evidence/b.txt", "bravo")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "tag", "evidence/a.txt", "leaked")
+
+	head, stderr := mustMkrk(t, dir, "audit", "verify")
+	if !strings.Contains(stderr, "Audit log intact") || len(strings.TrimSpace(head)) != 64 {
+		t.Fatalf("expected intact chain and head hash, got: %s / %s", head, stderr)
+	}
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	pdb.DB().Exec(`DELETE FROM audit_log WHERE id = 2`)
+	pdb.Close()
+
+	_, stderr, err = mkrk(t, dir, "audit", "verify")
+	if err == nil || !strings.Contains(stderr, "entry 2 is missing") {
+		t.Fatalf("expected deleted entry to be reported, got: %s", stderr)
+	}
+}
+
+func TestAuditVerifyCoversWorkspaceLog(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	mustMkrk(t, wsDir, "init", "beta")
+	mustMkrk(t, wsDir, "archive", "beta")
+	mustMkrk(t, wsDir, "unarchive", "beta")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+
+	heads, stderr := mustMkrk(t, dir, "audit", "verify")
+	if !strings.Contains(stderr, "Workspace audit log intact: 2 entries") || !strings.Contains(heads, "  workspace") {
+		t.Fatalf("expected the workspace chain to be checked, got: %s / %s", heads, stderr)
+	}
+
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.DB().Exec(`DELETE FROM audit_log WHERE id = 1`)
+	wdb.Close()
+
+	_, stderr, err = mkrk(t, wsDir, "audit", "verify")
+	if err == nil || !strings.Contains(stderr, "workspace entry 2: entry 1 is missing") {
+		t.Fatalf("expected deleted workspace entry to be reported, got: %s", stderr)
+	}
+}
+
+// --- Doctor ---
+
+func TestDoctorReportsPendingMigrations(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+
+	stdout, _ := mustMkrk(t, dir, "doctor")
+	if strings.Count(stdout, "up to date") != 2 {
+		t.Fatalf("expected project and workspace up to date, got: %s", stdout)
+	}
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	pdb.DB().Exec(`DELETE FROM schema_version WHERE version >= 4`)
+	pdb.Close()
+
+	stdout, _ = mustMkrk(t, dir, "doctor")
+	if !strings.Contains(stdout, "2 migration(s) pending") || !strings.Contains(stdout, "5 sync-cache-inode") {
+		t.Fatalf("expected two pending migrations, got: %s", stdout)
+	}
+	mustMkrk(t, dir, "status")
+	stdout, _ = mustMkrk(t, dir, "doctor")
+	if strings.Count(stdout, "up to date") != 2 {
+		t.Fatalf("expected opening the project to migrate it, got: %s", stdout)
+	}
+}
+
+// --- Fetch ---
+
+func TestFetchTracksWithProvenance(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("privacy", "off")
+	wdb.Close()
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.Path != "/foia/response.pdf" {
+			http.NotFound(w, r)
+			return
+		}
+		w.Header().Set("Set-Cookie", "session=secret")
+		w.Header().Set("Last-Modified", "Tue, 02 Jan 2024 10:00:00 GMT")
+		w.Write([]byte("%PDF-1.4\n%%EOF\n"))
+	}))
+	t.Cleanup(srv.Close)
+
+	_, stderr := mustMkrk(t, dir, "fetch", srv.URL+"/foia/response.pdf")
+	if !strings.Contains(stderr, "evidence/response.pdf") || !strings.Contains(stderr, "go direct") {
+		t.Fatalf("expected the file fetched into evidence, got: %s", stderr)
+	}
+	stdout, _ := mustMkrk(t, dir, "list")
+	if !strings.Contains(stdout, "response.pdf") {
+		t.Fatalf("expected the fetched file tracked, got: %s", stdout)
+	}
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var provenance string
+	pdb.DB().QueryRow(`SELECT provenance FROM files`).Scan(&provenance)
+	pdb.Close()
+	if !strings.Contains(provenance, srv.URL+"/foia/response.pdf") || !strings.Contains(provenance, "Last-Modified") ||
+		strings.Contains(provenance, "secret") {
+		t.Fatalf("expected URL and headers without cookies in provenance, got: %s", provenance)
+	}
+
+	if _, _, err := mkrk(t, dir, "fetch", srv.URL+"/foia/response.pdf"); err == nil {
+		t.Fatal("expected fetching over an existing file to be refused")
+	}
+	if _, _, err := mkrk(t, dir, "fetch", srv.URL+"/missing"); err == nil {
+		t.Fatal("expected a 404 to fail the fetch")
+	}
+}
+
+func TestArchiveURLSnapshotsPage(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("privacy", "off")
+	wdb.Close()
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+
+	var saved atomic.Int32
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		switch {
+		case r.URL.Path == "/press":
+			w.Header().Set("Content-Type", "text/html")
+			w.Write([]byte(`<html><body><img src="/seal.png"><p>Statement</p></body></html>`))
+		case r.URL.Path == "/seal.png":
+			w.Header().Set("Content-Type", "image/png")
+			w.Write([]byte("\x89PNG seal"))
+		case strings.HasPrefix(r.URL.Path, "/save/"):
+			saved.Add(1)
+			w.Header().Set("Content-Location", "/web/20240301000000/"+strings.TrimPrefix(r.URL.Path, "/save/"))
+		default:
+			http.NotFound(w, r)
+		}
+	}))
+	t.Cleanup(srv.Close)
+	mustMkrk(t, dir, "config", "wayback-url", srv.URL)
+
+	_, stderr := mustMkrk(t, dir, "archive-url", srv.URL+"/press", "--wayback")
+	if !strings.Contains(stderr, "-> sources/") || saved.Load() != 1 {
+		t.Fatalf("expected a snapshot in sources and one Wayback capture, got: %s", stderr)
+	}
+	snapshots, _ := filepath.Glob(filepath.Join(dir, "sources", "*", "*-press.html"))
+	if len(snapshots) != 1 {
+		t.Fatalf("expected one snapshot, got %v", snapshots)
+	}
+	page, _ := os.ReadFile(snapshots[0])
+	if !strings.Contains(string(page), `src="data:image/png;base64,`) {
+		t.Fatalf("expected the image inlined, got: %s", page)
+	}
+
+	if _, _, err := mkrk(t, dir, "archive-url", srv.URL+"/press", "--category", "notes"); err == nil {
+		t.Fatal("expected an editable category to be refused")
+	}
+
+	// An ingest rule snapshots the page a fetched file came from.
+	mustMkrk(t, dir, "rule", "add", "snapshot", "--on", "ingest", "--if-category", "evidence", "--action", "archive-url")
+	_, stderr = mustMkrk(t, dir, "fetch", srv.URL+"/press", "--name", "press.html")
+	if !strings.Contains(stderr, "archived "+srv.URL+"/press") {
+		t.Fatalf("expected the rule to archive the page, got: %s", stderr)
+	}
+	if snapshots, _ := filepath.Glob(filepath.Join(dir, "sources", "*", "*-press.html")); len(snapshots) != 2 {
+		t.Fatalf("expected a second snapshot, got %v", snapshots)
+	}
+}
+
+// fakeTorControl answers each control port command with the next reply,
+// and sends the commands it was given once the replies run out.
+func fakeTorControl(t *testing.T, replies ...string) (string, <-chan []string) {
+	t.Helper()
+	ln, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatal(err)
+	}
+	t.Cleanup(func() { ln.Close() })
+	done := make(chan []string, 1)
+	go func() {
+		var commands []string
+		defer func() { done <- commands }()
+		conn, err := ln.Accept()
+		if err != nil {
+			return
+		}
+		defer conn.Close()
+		buf := make([]byte, 4096)
+		for _, reply := range replies {
+			n, err := conn.Read(buf)
+			if err != nil {
+				return
+			}
+			commands = append(commands, strings.TrimSpace(string(buf[:n])))
+			fmt.Fprintf(conn, "%s\r\n", reply)
+		}
+	}()
+	return ln.Addr().String(), done
+}
+
+func TestTorNewIdentity(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+
+	addr, commands := fakeTorControl(t, "250 OK", "250 OK", "250 closing connection")
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("tor_control", addr)
+	wdb.SetConfig("tor_control_password", "hunter2")
+	wdb.Close()
+
+	_, stderr := mustMkrk(t, wsDir, "tor", "new-identity")
+	if !strings.Contains(stderr, "new circuits") {
+		t.Fatalf("expected new circuits, got: %s", stderr)
+	}
+	want := []string{`AUTHENTICATE "hunter2"`, "SIGNAL NEWNYM", "QUIT"}
+	if got := <-commands; strings.Join(got, "|") != strings.Join(want, "|") {
+		t.Fatalf("expected %q, got %q", want, got)
+	}
+
+	addr, _ = fakeTorControl(t, "515 Authentication failed")
+	wdb, err = db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("tor_control", addr)
+	wdb.Close()
+	_, stderr, err = mkrk(t, wsDir, "tor", "new-identity")
+	if err == nil || !strings.Contains(stderr, "refused AUTHENTICATE") {
+		t.Fatalf("expected a refused password to fail, got: %v %s", err, stderr)
+	}
+}
+
+func TestTorMaxRequestsMustBeACount(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("tor_max_requests", "lots")
+	wdb.Close()
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+
+	_, stderr, err := mkrk(t, dir, "fetch", "http://example.invalid/doc.pdf")
+	if err == nil || !strings.Contains(stderr, "tor_max_requests") {
+		t.Fatalf("expected a bad tor_max_requests to be refused, got: %v %s", err, stderr)
+	}
+}
+
+func TestOfflineRefusesNetwork(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	wdb.SetConfig("privacy", "offline")
+	wdb.Close()
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
+	mustMkrk(t, dir, "sync")
+
+	var hits atomic.Int32
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		hits.Add(1)
+	}))
+	t.Cleanup(srv.Close)
+
+	for _, args := range [][]string{
+		{"fetch", srv.URL + "/doc.pdf"},
+		{"archive-url", srv.URL + "/press"},
+		{"tor", "new-identity"},
+	} {
+		_, stderr, err := mkrk(t, dir, args...)
+		if err == nil || !strings.Contains(stderr, "network access is disabled") {
+			t.Fatalf("expected mkrk %s to be refused, got: %v %s", args[0], err, stderr)
+		}
+	}
+	if hits.Load() != 0 {
+		t.Fatalf("expected no requests to reach the server, got %d", hits.Load())
+	}
+
+	stdout, stderr := mustMkrk(t, dir, "foreach", ":evidence", "--", `echo "$MKRK_OFFLINE $https_proxy"`)
+	if !strings.Contains(stdout, "1 socks5h://127.0.0.1:0") || !strings.Contains(stderr, "OFFLINE") {
+		t.Fatalf("expected tools to get a dead proxy, got: %s %s", stdout, stderr)
+	}
+}
+
+// connectProxy is an HTTP proxy that opens CONNECT tunnels, counting them.
+func connectProxy(t *testing.T, tunnels *atomic.Int32) string {
+	t.Helper()
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.Method != http.MethodConnect {
+			http.Error(w, "CONNECT only", http.StatusMethodNotAllowed)
+			return
+		}
+		upstream, err := net.Dial("tcp", r.Host)
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusBadGateway)
+			return
+		}
+		tunnels.Add(1)
+		conn, buf, err := w.(http.Hijacker).Hijack()
+		if err != nil {
+			upstream.Close()
+			return
+		}
+		conn.Write([]byte("HTTP/1.1 200 Connection established\r\n\r\n"))
+		go func() {
+			io.Copy(upstream, buf.Reader)
+			upstream.Close()
+		}()
+		io.Copy(conn, upstream)
+		conn.Close()
+	}))
+	t.Cleanup(srv.Close)
+	return srv.URL
+}
+
+func TestProxyRulesAndChains(t *testing.T) {
+	var tunnels atomic.Int32
+	proxy := connectProxy(t, &tunnels)
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		w.Write([]byte("released records"))
+	}))
+	t.Cleanup(srv.Close)
+
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	setting := func(key, value string) {
+		wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+		if err != nil {
+			t.Fatal(err)
+		}
+		wdb.SetConfig(key, value)
+		wdb.Close()
+	}
+	// Nothing listens on the default proxy, so only the rule can reach the server.
+	setting("socks_proxy", "socks5h://127.0.0.1:1")
+	setting("proxy_rules", "*.invalid=direct; 127.0.0.1="+proxy)
+	mustMkrk(t, wsDir, "init", "alpha")
+	dir := filepath.Join(wsDir, "projects", "alpha")
+
+	_, stderr := mustMkrk(t, dir, "fetch", srv.URL+"/one.txt")
+	if !strings.Contains(stderr, "go through "+proxy) || tunnels.Load() != 1 {
+		t.Fatalf("expected the fetch to go through the rule's proxy, got %d tunnels: %s", tunnels.Load(), stderr)
+	}
+
+	// A chain of two proxies opens a tunnel in each.
+	setting("proxy_rules", "127.0.0.1="+proxy+","+proxy)
+	mustMkrk(t, dir, "fetch", srv.URL+"/two.txt")
+	if tunnels.Load() != 3 {
+		t.Fatalf("expected two more tunnels for the chain, got %d", tunnels.Load())
+	}
+
+	// Tools are pointed at mkrk's relay rather than the configured proxy.
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
+	mustMkrk(t, dir, "sync")
+	// It takes only the run's credentials, and closes when the run ends.
+	proxyFile := filepath.Join(t.TempDir(), "proxy")
+	run := exec.Command(binary, "foreach", ":evidence", "--", `echo "$https_proxy $NO_PROXY" > `+proxyFile+`; sleep 3`)
+	run.Dir = dir
+	run.Env = append(os.Environ(), "NO_PROXY=127.0.0.1")
+	if err := run.Start(); err != nil {
+		t.Fatal(err)
+	}
+	var relayURL string
+	for deadline := time.Now().Add(10 * time.Second); relayURL == "" && time.Now().Before(deadline); {
+		b, _ := os.ReadFile(proxyFile)
+		relayURL = strings.TrimSpace(string(b))
+		time.Sleep(50 * time.Millisecond)
+	}
+	relay, err := url.Parse(relayURL)
+	if err != nil || relay.Scheme != "socks5h" || relay.User == nil || relay.Hostname() != "127.0.0.1" || strings.HasSuffix(relay.Host, ":1") {
+		t.Fatalf("expected tools to get the relay with credentials and no NO_PROXY, got: %q", relayURL)
+	}
+	conn, err := net.Dial("tcp", relay.Host)
+	if err != nil {
+		t.Fatal(err)
+	}
+	conn.Write([]byte{5, 1, 0})
+	reply := make([]byte, 2)
+	io.ReadFull(conn, reply)
+	conn.Close()
+	if reply[1] != 0xff {
+		t.Fatalf("expected the relay to refuse clients without credentials, got %v", reply)
+	}
+	if err := run.Wait(); err != nil {
+		t.Fatal(err)
+	}
+	if conn, err := net.Dial("tcp", relay.Host); err == nil {
+		conn.Close()
+		t.Fatal("expected the relay to be closed after the run")
+	}
+
+	setting("proxy_rules", "127.0.0.1=ftp://"+strings.TrimPrefix(proxy, "http://"))
+	if _, _, err := mkrk(t, dir, "fetch", srv.URL+"/three.txt"); err == nil {
+		t.Fatal("expected an unsupported proxy to be refused")
+	}
+}
+
+// --- Trash ---
+
+func TestRmAndTrash(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/draft.txt", "first draft")
+	createTestFile(t, dir, "analysis/model.txt", "working model")
+	createTestFile(t, dir, "evidence/memo.txt", "sealed memo")
+	mustMkrk(t, dir, "sync")
+
+	if _, stderr, err := mkrk(t, dir, "rm", "evidence/memo.txt"); err == nil || !strings.Contains(stderr, "immutable") {
+		t.Fatalf("expected an immutable file to be refused, got: %s", stderr)
+	}
+	if _, stderr, err := mkrk(t, dir, "rm", "analysis/model.txt", "notes/draft.txt"); err == nil || !strings.Contains(stderr, "--force") {
+		t.Fatalf("expected a protected file to need --force, got: %s", stderr)
+	}
+	if _, err := os.Stat(filepath.Join(dir, "notes/draft.txt")); err != nil {
+		t.Fatal("expected a refusal to leave every file in place")
+	}
+
+	_, stderr := mustMkrk(t, dir, "--dry-run", "rm", "notes/draft.txt", "--reason", "superseded")
+	if !strings.Contains(stderr, "would move to the trash") {
+		t.Fatalf("expected a dry-run preview, got: %s", stderr)
+	}
+	if _, err := os.Stat(filepath.Join(dir, "notes/draft.txt")); err != nil {
+		t.Fatal("expected a dry run to leave the file in place")
+	}
+	if stdout, _ := mustMkrk(t, dir, "trash", "list"); strings.Contains(stdout, "#") {
+		t.Fatalf("expected a dry run to record no tombstone, got: %s", stdout)
+	}
+
+	mustMkrk(t, dir, "rm", "notes/draft.txt", "--reason", "superseded")
+	mustMkrk(t, dir, "rm", "analysis/model.txt", "--force")
+	if _, err := os.Stat(filepath.Join(dir, "notes/draft.txt")); !os.IsNotExist(err) {
+		t.Fatal("expected the file moved out of place")
+	}
+	stdout, _ := mustMkrk(t, dir, "trash", "list")
+	if !strings.Contains(stdout, "#1") || !strings.Contains(stdout, "draft.txt") || !strings.Contains(stdout, "(superseded)") {
+		t.Fatalf("expected the tombstone listed, got: %s", stdout)
+	}
+	// Trashed files are gone on purpose, not missing.
+	_, stderr = mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "0 missing") {
+		t.Fatalf("expected no missing files, got: %s", stderr)
+	}
+	mustMkrk(t, dir, "verify")
+
+	mustMkrk(t, dir, "trash", "restore", "1")
+	if data, err := os.ReadFile(filepath.Join(dir, "notes/draft.txt")); err != nil || string(data) != "first draft" {
+		t.Fatalf("expected the file restored in place, got %q (%v)", data, err)
+	}
+	if _, stderr, err := mkrk(t, dir, "trash", "purge", "2"); err == nil || !strings.Contains(stderr, "--force") {
+		t.Fatalf("expected purging a protected file to need --force, got: %s", stderr)
+	}
+	mustMkrk(t, dir, "--dry-run", "trash", "purge", "--all", "--force")
+	if _, err := os.Stat(filepath.Join(dir, ".mkrk-trash", "2")); err != nil {
+		t.Fatal("expected a dry run to purge nothing")
+	}
+	mustMkrk(t, dir, "trash", "purge", "--all", "--force")
+	if _, err := os.Stat(filepath.Join(dir, ".mkrk-trash", "2")); !os.IsNotExist(err) {
+		t.Fatal("expected the purged file deleted")
+	}
+	stdout, _ = mustMkrk(t, dir, "trash", "list")
+	if !strings.Contains(stdout, "#2") || !strings.Contains(stdout, "purged") || strings.Contains(stdout, "#1") {
+		t.Fatalf("expected only the purged tombstone left, got: %s", stdout)
+	}
+	if _, _, err := mkrk(t, dir, "trash", "restore", "2"); err == nil {
+		t.Fatal("expected a purged file not to be restorable")
+	}
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var n int
+	pdb.DB().QueryRow(`SELECT COUNT(*) FROM audit_log WHERE operation IN ('trash', 'restore', 'purge')`).Scan(&n)
+	pdb.Close()
+	if n != 4 {
+		t.Fatalf("expected four trash audit entries, got %d", n)
+	}
+}
+
+// --- Open ---
+
+func TestOpenRequiresReadOnlyViewerForImmutable(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/memo.txt", "sealed memo")
+	mustMkrk(t, dir, "sync")
+
+	_, _, err := mkrk(t, dir, "open", "evidence/memo.txt")
+	if err == nil {
+		t.Fatal("expected open of immutable file without read-only viewer to fail")
+	}
+
+	mustMkrk(t, dir, "viewer", "set", "text/plain", "cat", "--category", "evidence", "--read-only")
+	stdout, _ := mustMkrk(t, dir, "viewer", "list")
+	if !strings.Contains(stdout, "text/plain in evidence: cat (read-only)") {
+		t.Fatalf("expected viewer listed, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, dir, "open", "evidence/memo.txt")
+	if !strings.Contains(stdout, "sealed memo") {
+		t.Fatalf("expected viewer output, got: %s", stdout)
+	}
+}
+
+// --- Grep ---
+
+func TestGrepPrintsReferenceAndLine(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/memo.txt", "first line\nwire transfer to shell co\nlast line\n")
+	createTestFile(t, dir, "notes/todo.txt", "nothing here\n")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ := mustMkrk(t, dir, "grep", "--raw", "-i", "WIRE", "evidence")
+	if !strings.Contains(stdout, "memo.txt:2:wire transfer") {
+		t.Fatalf("expected ref:line match, got: %s", stdout)
+	}
+	if strings.Contains(stdout, "todo.txt") {
+		t.Fatalf("grep should be restricted to the reference, got: %s", stdout)
+	}
+}
+
+func TestGrepSkipsUntracked(t *testing.T) {
+	dir := initTestProject(t)
+	mustMkrk(t, dir, "sync")
+	createTestFile(t, dir, "notes/new.txt", "needle\n")
+
+	if _, _, err := mkrk(t, dir, "grep", "needle"); err == nil {
+		t.Fatal("expected no matches for untracked file")
+	}
+	stdout, _ := mustMkrk(t, dir, "grep", "--untracked", "--raw", "needle")
+	if !strings.Contains(stdout, "new.txt:1:needle") {
+		t.Fatalf("expected match with --untracked, got: %s", stdout)
+	}
+}
+
+func TestGrepLooksUpTrackedFilesInCache(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/lead.txt", "needle\n")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "grep", "needle")
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var cached int
+	pdb.DB().QueryRow(`SELECT COUNT(*) FROM sync_cache WHERE path_hash = ?`, db.PathKey("notes/lead.txt")).Scan(&cached)
+	// A cache entry naming an untracked hash shows grep trusts the cache
+	// over rereading the file.
+	pdb.DB().Exec(`UPDATE sync_cache SET sha256 = 'untracked'`)
+	pdb.Close()
+	if cached != 1 {
+		t.Fatalf("expected the file's hash cached, got %d row(s)", cached)
+	}
+	if _, _, err := mkrk(t, dir, "grep", "needle"); err == nil {
+		t.Fatal("expected grep to take the tracked check from the cache")
+	}
+}
+
+// --- Copy-out ---
+
+func TestCopyOutCreatesWorkingCopy(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/orig.txt", "original bytes of the evidence file")
+	mustMkrk(t, dir, "sync")
+
+	mustMkrk(t, dir, "copy-out", "evidence/orig.txt")
+
+	data, err := os.ReadFile(filepath.Join(dir, "workspace", "evidence", "orig.txt"))
+	if err != nil {
+		t.Fatalf("expected working copy: %v", err)
+	}
+	if string(data) != "original bytes of the evidence file" {
+		t.Fatalf("unexpected copy content: %q", data)
+	}
+
+	if _, _, err := mkrk(t, dir, "copy-out", "evidence/orig.txt"); err != nil {
+		t.Fatalf("second copy-out should skip, not fail: %v", err)
+	}
+
+	// Outside a category the copy is never tracked, so it cannot be tagged.
+	if _, _, err := mkrk(t, dir, "copy-out", "--tag", "--force", "evidence/orig.txt"); err == nil {
+		t.Fatal("expected --tag to be refused for a copy outside any category")
+	}
+
+	mustMkrk(t, dir, "copy-out", "--tag", "--to", filepath.Join(dir, "notes"), "evidence/orig.txt")
+	stdout, _ := mustMkrk(t, dir, "status", "evidence/orig.txt")
+	if strings.Contains(stdout, "working-copy") {
+		t.Fatalf("the original must not be tagged, got: %s", stdout)
+	}
+
+	createTestFile(t, dir, "notes/evidence/orig.txt", "original bytes of the evidence file, annotated")
+	mustMkrk(t, dir, "sync")
+
+	stdout, _ = mustMkrk(t, dir, "status", "notes/evidence/orig.txt")
+	if !strings.Contains(stdout, "working-copy") {
+		t.Fatalf("expected the edited copy to carry the working-copy tag, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "status", "evidence/orig.txt")
+	if strings.Contains(stdout, "working-copy") {
+		t.Fatalf("the original must not be tagged, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, dir, "list")
+	var line string
+	for _, l := range strings.Split(stdout, "\n") {
+		if strings.Contains(l, "derived from") {
+			line = l
+		}
+	}
+	if !strings.Contains(line, "notes") || !strings.Contains(line, "via copy-out") {
+		t.Fatalf("expected the copy to be linked to its source, got: %s", stdout)
+	}
+}
+
+// --- Report ---
+
+func TestReportMarkdownAndHTML(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "notes/draft.txt", "draft | with pipe")
+	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "tag", "notes/draft.txt", "q3")
+	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+	mustMkrk(t, dir, "sign", "notes/draft.txt", "review", "--pipeline", "editorial")
+
+	stdout, _ := mustMkrk(t, dir, "report")
+	for _, want := range []string{"## Categories", "notes/draft.txt", "q3", "editorial", "published needs published", "## Recent activity"} {
+		if !strings.Contains(stdout, want) {
+			t.Fatalf("expected %q in report, got: %s", want, stdout)
+		}
+	}
+
+	out := filepath.Join(t.TempDir(), "report.html")
+	mustMkrk(t, dir, "report", "--html", "--out", out)
+	data, err := os.ReadFile(out)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !strings.Contains(string(data), "<h2>Outstanding signs</h2>") {
+		t.Fatalf("expected HTML report, got: %s", data)
+	}
+}
+
+// --- Workspace dispatch ---
+
+func TestWorkspaceSyncDispatch(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	mustMkrk(t, wsDir, "init", "beta")
+
+	createTestFile(t, wsDir, "projects/alpha/evidence/a.txt", "alpha evidence")
+	createTestFile(t, wsDir, "projects/beta/evidence/b.txt", "beta evidence")
+
+	// Sync from workspace root should dispatch to both projects
+	_, stderr := mustMkrk(t, wsDir, "sync")
+	if !strings.Contains(stderr, "a.txt") {
+		t.Fatalf("expected a.txt in output, got: %s", stderr)
+	}
+	if !strings.Contains(stderr, "b.txt") {
+		t.Fatalf("expected b.txt in output, got: %s", stderr)
+	}
+}
+
+func TestFindAcrossProjects(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	mustMkrk(t, wsDir, "init", "beta")
+	createTestFile(t, wsDir, "projects/alpha/evidence/memo.txt", "shared memo")
+	createTestFile(t, wsDir, "projects/alpha/evidence/notes.txt", "alpha only")
+	createTestFile(t, wsDir, "projects/beta/evidence/copy.txt", "shared memo")
+	mustMkrk(t, wsDir, "sync")
+
+	stdout, _ := mustMkrk(t, wsDir, "find", "--name", "memo.txt")
+	fields := strings.Split(strings.TrimSpace(stdout), "\t")
+	if len(fields) != 4 || fields[0] != "projects/alpha/evidence/memo.txt" {
+		t.Fatalf("expected one match for memo.txt, got: %q", stdout)
+	}
+	stdout, _ = mustMkrk(t, wsDir, "find", "--hash", fields[3][:12])
+	if !strings.Contains(stdout, "alpha/evidence/memo.txt") || !strings.Contains(stdout, "beta/evidence/copy.txt") ||
+		strings.Contains(stdout, "notes.txt") {
+		t.Fatalf("expected both copies of the shared content, got: %s", stdout)
+	}
+
+	// The index keeps no names, only hashes of paths.
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var indexed int
+	wdb.DB().QueryRow(`SELECT COUNT(*) FROM file_index`).Scan(&indexed)
+	wdb.Close()
+	if indexed != 3 {
+		t.Fatalf("expected three indexed files, got %d", indexed)
+	}
+	data, _ := os.ReadFile(filepath.Join(wsDir, ".mksp"))
+	if strings.Contains(string(data), "memo.txt") {
+		t.Fatal("expected no file names in the workspace database")
+	}
+
+	os.Rename(filepath.Join(wsDir, "projects/beta/evidence/copy.txt"), filepath.Join(wsDir, "projects/beta/evidence/moved.txt"))
+	mustMkrk(t, filepath.Join(wsDir, "projects/beta"), "sync")
+	stdout, _ = mustMkrk(t, wsDir, "find", "--name", "evidence/*", "--hash", fields[3])
+	if !strings.Contains(stdout, "beta/evidence/moved.txt") || strings.Contains(stdout, "copy.txt") {
+		t.Fatalf("expected the moved file found at its new path, got: %s", stdout)
+	}
+	if _, _, err := mkrk(t, wsDir, "find", "--name", "*.pdf"); err == nil {
+		t.Fatal("expected no matches to fail")
+	}
+}
+
+func TestWorkspaceListDispatch(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	createTestFile(t, wsDir, "projects/alpha/evidence/report.txt", "test")
+	mustMkrk(t, filepath.Join(wsDir, "projects/alpha"), "sync")
+
+	// List from workspace root should show files with project-scoped references
+	stdout, _ := mustMkrk(t, wsDir, "list")
+	if !strings.Contains(stdout, ":alpha") {
+		t.Fatalf("expected :alpha scoped reference, got: %s", stdout)
+	}
+	if !strings.Contains(stdout, "report.txt") {
+		t.Fatalf("expected report.txt in output, got: %s", stdout)
+	}
+}
+
+func TestWorkspaceRegistryReachesWorkspaceFromAnywhere(t *testing.T) {
+	registry := filepath.Join(t.TempDir(), "registry.toml")
+	t.Setenv("MKRK_REGISTRY", registry)
+
+	wsDir := filepath.Join(t.TempDir(), "investigations")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	createTestFile(t, wsDir, "projects/alpha/evidence/report.txt", "test")
+	mustMkrk(t, filepath.Join(wsDir, "projects/alpha"), "sync")
+
+	_, stderr := mustMkrk(t, filepath.Join(wsDir, "projects/alpha"), "registry", "add")
+	if !strings.Contains(stderr, "Registered workspace 'investigations'") {
+		t.Fatalf("expected the workspace registered by its directory name, got: %s", stderr)
+	}
+	data, _ := os.ReadFile(registry)
+	if !strings.Contains(string(data), "[workspaces]") || !strings.Contains(string(data), wsDir) {
+		t.Fatalf("expected the workspace in the registry file, got: %s", data)
+	}
+
+	elsewhere := t.TempDir()
+	stdout, _ := mustMkrk(t, elsewhere, "--workspace", "investigations", "list")
+	if !strings.Contains(stdout, ":alpha") || !strings.Contains(stdout, "report.txt") {
+		t.Fatalf("expected the registered workspace's files, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, elsewhere, "--workspace", "investigations", ":alpha", "status")
+	if !strings.Contains(stdout, "Name: alpha") {
+		t.Fatalf("expected status for alpha, got: %s", stdout)
+	}
+
+	mustMkrk(t, elsewhere, "registry", "remove", "investigations")
+	if _, stderr, err := mkrk(t, elsewhere, "--workspace", "investigations", "list"); err == nil || !strings.Contains(stderr, "unknown workspace 'investigations'") {
+		t.Fatalf("expected the removed workspace unknown, got: %s", stderr)
+	}
+}
+
+// --- Archive ---
+
+func TestArchiveSkipsProjectAndFreezesPolicies(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	mustMkrk(t, wsDir, "init", "beta")
+	createTestFile(t, wsDir, "projects/alpha/notes/a.txt", "alpha notes")
+	createTestFile(t, wsDir, "projects/beta/notes/b.txt", "beta notes")
+	mustMkrk(t, wsDir, "sync")
+
+	coldDir := filepath.Join(t.TempDir(), "cold")
+	mustMkrk(t, wsDir, "archive", "beta", "--compress", coldDir)
+
+	matches, _ := filepath.Glob(filepath.Join(coldDir, "beta-*.manifest.json"))
+	if len(matches) != 1 {
+		t.Fatalf("expected one manifest in cold storage, got %v", matches)
+	}
+
+	stdout, _ := mustMkrk(t, wsDir, "list")
+	if strings.Contains(stdout, "b.txt") {
+		t.Fatalf("archived project should be skipped, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, wsDir, "--include-archived", "list")
+	if !strings.Contains(stdout, "b.txt") {
+		t.Fatalf("expected archived project with --include-archived, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, wsDir, ":beta", "status", "notes/b.txt")
+	if !strings.Contains(stdout, "immutable") {
+		t.Fatalf("expected archived notes to be immutable, got: %s", stdout)
+	}
+
+	mustMkrk(t, wsDir, "unarchive", "beta")
+	stdout, _ = mustMkrk(t, wsDir, ":beta", "status", "notes/b.txt")
+	if !strings.Contains(stdout, "editable") {
+		t.Fatalf("expected notes policy restored, got: %s", stdout)
+	}
+}
+
+func TestArchiveRestoresPoliciesWhenCompressFails(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "beta")
+	createTestFile(t, wsDir, "projects/beta/notes/b.txt", "beta notes")
+	mustMkrk(t, wsDir, "sync")
+
+	// A file where the archive directory should be makes compression fail.
+	blocked := filepath.Join(t.TempDir(), "cold")
+	os.WriteFile(blocked, []byte("not a directory"), 0o644)
+	if _, _, err := mkrk(t, wsDir, "archive", "beta", "--compress", blocked); err == nil {
+		t.Fatal("expected archive to fail when compression fails")
+	}
+
+	stdout, _ := mustMkrk(t, wsDir, ":beta", "status", "notes/b.txt")
+	if !strings.Contains(stdout, "editable") {
+		t.Fatalf("expected notes policy restored after the failed archive, got: %s", stdout)
+	}
+	if _, _, err := mkrk(t, wsDir, "unarchive", "beta"); err == nil {
+		t.Fatal("expected no archive record after the failed archive")
+	}
+	mustMkrk(t, wsDir, "archive", "beta")
+}
+
+// --- Clone ---
+
+func TestCloneCopiesStructureNotFiles(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	alphaDir := filepath.Join(wsDir, "projects/alpha")
+	createTestFile(t, wsDir, "projects/alpha/evidence/a.txt", "alpha evidence")
+	mustMkrk(t, alphaDir, "sync")
+	mustMkrk(t, alphaDir, "pipeline", "editorial", "--states", "draft,review,published")
+	mustMkrk(t, alphaDir, "rule", "add", "flag-tampered",
+		"--on", "verify_failure", "--action", "add_tag", "--tag", "tampered")
+
+	mustMkrk(t, wsDir, "clone", "alpha", "gamma")
+	gammaDir := filepath.Join(wsDir, "projects/gamma")
+
+	if _, err := os.Stat(filepath.Join(gammaDir, "evidence")); err != nil {
+		t.Fatalf("expected evidence category directory in clone: %v", err)
+	}
+	stdout, _ := mustMkrk(t, gammaDir, "rule", "list")
+	if !strings.Contains(stdout, "flag-tampered") {
+		t.Fatalf("expected rule cloned, got: %s", stdout)
+	}
+	_, _, err := mkrk(t, gammaDir, "pipeline", "editorial", "--states", "draft,done")
+	if err == nil {
+		t.Fatal("expected pipeline to already exist in clone")
+	}
+	stdout, _ = mustMkrk(t, gammaDir, "list")
+	if strings.Contains(stdout, "a.txt") {
+		t.Fatalf("files must not be cloned, got: %s", stdout)
+	}
+}
+
+func TestCloneWithWorkspaceDefaultPipelines(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "pipeline", "editorial", "--states", "draft,published", "--attach", ":evidence", "--workspace")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	mustMkrk(t, wsDir, "clone", "alpha", "gamma")
+	gammaDir := filepath.Join(wsDir, "projects/gamma")
+	createTestFile(t, gammaDir, "evidence/g.txt", "gamma")
+	mustMkrk(t, gammaDir, "sync")
+	stdout, _ := mustMkrk(t, gammaDir, "state", "evidence/g.txt")
+	if !strings.Contains(stdout, "editorial: draft") {
+		t.Fatalf("expected the cloned pipeline on gamma's evidence, got: %s", stdout)
+	}
+}
+
+// --- Merge ---
+
+func TestMergeMovesFilesAndReportsConflicts(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	mustMkrk(t, wsDir, "init", "beta")
+	createTestFile(t, wsDir, "projects/alpha/notes/lead.txt", "alpha lead")
+	createTestFile(t, wsDir, "projects/alpha/notes/clash.txt", "alpha version")
+	createTestFile(t, wsDir, "projects/beta/notes/clash.txt", "beta version")
+	mustMkrk(t, wsDir, "sync")
+	mustMkrk(t, filepath.Join(wsDir, "projects/alpha"), "tag", "notes/lead.txt", "source-a")
+
+	_, stderr, err := mkrk(t, wsDir, "merge", "alpha", "beta")
+	if err == nil {
+		t.Fatal("expected merge to fail on conflict")
+	}
+	if !strings.Contains(stderr, "clash.txt") {
+		t.Fatalf("expected conflict reported, got: %s", stderr)
+	}
+
+	betaDir := filepath.Join(wsDir, "projects/beta")
+	if _, err := os.Stat(filepath.Join(betaDir, "notes/lead.txt")); err != nil {
+		t.Fatalf("expected lead.txt moved into beta: %v", err)
+	}
+	if _, err := os.Stat(filepath.Join(wsDir, "projects/alpha/notes/clash.txt")); err != nil {
+		t.Fatalf("expected conflicting file left in alpha: %v", err)
+	}
+	stdout, _ := mustMkrk(t, betaDir, "status", "notes/lead.txt")
+	if !strings.Contains(stdout, "source-a") {
+		t.Fatalf("expected tag carried into beta, got: %s", stdout)
+	}
+
+	pdb, err := db.OpenProject(filepath.Join(betaDir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var detail string
+	pdb.DB().QueryRow(`SELECT detail FROM audit_log WHERE operation = 'merge'`).Scan(&detail)
+	pdb.Close()
+	wdb, err := db.OpenWorkspace(filepath.Join(wsDir, ".mksp"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var wsDetail string
+	wdb.DB().QueryRow(`SELECT detail FROM audit_log WHERE operation = 'merge'`).Scan(&wsDetail)
+	wdb.Close()
+	if detail == "" || strings.Contains(detail, ".txt") || strings.Contains(wsDetail, ".txt") {
+		t.Fatalf("merge audit must not record paths, got: %s / %s", detail, wsDetail)
+	}
+	if !strings.Contains(wsDetail, `"conflicts":1`) {
+		t.Fatalf("expected a conflict count in the workspace audit, got: %s", wsDetail)
+	}
+}
+
+// --- Push / pull ---
+
+func TestPushAndPullBetweenWorkspaces(t *testing.T) {
+	laptop := filepath.Join(t.TempDir(), "laptop")
+	desktop := filepath.Join(t.TempDir(), "desktop")
+	for _, ws := range []string{laptop, desktop} {
+		os.MkdirAll(ws, 0o755)
+		mustMkrk(t, ws, "init", "--workspace", "projects/")
+	}
+	mustMkrk(t, laptop, "init", "bailey")
+	createTestFile(t, laptop, "projects/bailey/notes/lead.txt", "first lead")
+	mustMkrk(t, laptop, "sync")
+	mustMkrk(t, filepath.Join(laptop, "projects/bailey"), "tag", "notes/lead.txt", "source-a")
+
+	_, stderr := mustMkrk(t, laptop, "push", desktop)
+	if !strings.Contains(stderr, "1 copied") {
+		t.Fatalf("expected one file pushed, got: %s", stderr)
+	}
+	remoteProject := filepath.Join(desktop, "projects/bailey")
+	got, _ := os.ReadFile(filepath.Join(remoteProject, "notes/lead.txt"))
+	if string(got) != "first lead" {
+		t.Fatalf("expected pushed content, got %q", got)
+	}
+	stdout, _ := mustMkrk(t, remoteProject, "status", "notes/lead.txt")
+	if !strings.Contains(stdout, "source-a") {
+		t.Fatalf("expected tag replicated, got: %s", stdout)
+	}
+
+	_, stderr = mustMkrk(t, laptop, "push", desktop)
+	if !strings.Contains(stderr, "0 copied, 1 already present") {
+		t.Fatalf("expected second push to send nothing, got: %s", stderr)
+	}
+
+	createTestFile(t, desktop, "projects/bailey/notes/reply.txt", "desktop reply")
+	mustMkrk(t, remoteProject, "sync")
+	mustMkrk(t, laptop, "pull", desktop)
+	got, _ = os.ReadFile(filepath.Join(laptop, "projects/bailey/notes/reply.txt"))
+	if string(got) != "desktop reply" {
+		t.Fatalf("expected pulled content, got %q", got)
+	}
+	mustMkrk(t, filepath.Join(laptop, "projects/bailey"), "verify")
+
+	createTestFile(t, desktop, "projects/bailey/notes/lead.txt", "rewritten on desktop")
+	_, stderr, err := mkrk(t, laptop, "push", desktop)
+	if err == nil || !strings.Contains(stderr, "different content") {
+		t.Fatalf("expected conflict to be reported, got: %s", stderr)
+	}
+}
+
+func TestPushAndPullThroughStore(t *testing.T) {
+	laptop := filepath.Join(t.TempDir(), "laptop")
+	desktop := filepath.Join(t.TempDir(), "desktop")
+	store := filepath.Join(t.TempDir(), "store")
+	for _, ws := range []string{laptop, desktop} {
+		os.MkdirAll(ws, 0o755)
+		mustMkrk(t, ws, "init", "--workspace", "projects/")
+	}
+	mustMkrk(t, laptop, "init", "bailey")
+	createTestFile(t, laptop, "projects/bailey/notes/lead.txt", "first lead")
+	mustMkrk(t, laptop, "sync")
+	mustMkrk(t, filepath.Join(laptop, "projects/bailey"), "tag", "notes/lead.txt", "source-a")
+
+	_, stderr := mustMkrk(t, laptop, "push", store)
+	if !strings.Contains(stderr, "1 copied") {
+		t.Fatalf("expected one file pushed, got: %s", stderr)
+	}
+	mustMkrk(t, laptop, "push", store)
+	logDir := filepath.Join(store, "projects/bailey/log")
+	if entries, _ := os.ReadDir(logDir); len(entries) != 1 {
+		t.Fatalf("expected a push with no changes to add no log entry, got %d", len(entries))
+	}
+
+	mustMkrk(t, desktop, "pull", store)
+	project := filepath.Join(desktop, "projects/bailey")
+	got, _ := os.ReadFile(filepath.Join(project, "notes/lead.txt"))
+	if string(got) != "first lead" {
+		t.Fatalf("expected pulled content, got %q", got)
+	}
+	stdout, _ := mustMkrk(t, project, "status", "notes/lead.txt")
+	if !strings.Contains(stdout, "source-a") {
+		t.Fatalf("expected tag replicated, got: %s", stdout)
+	}
+	mustMkrk(t, project, "verify")
+
+	_, stderr = mustMkrk(t, desktop, "pull", store)
+	if !strings.Contains(stderr, "0 copied, 0 already present") {
+		t.Fatalf("expected second pull to apply nothing, got: %s", stderr)
+	}
+
+	// Content altered in the store is refused rather than put in place.
+	held, _ := os.ReadDir(filepath.Join(store, "objects"))
+	createTestFile(t, laptop, "projects/bailey/notes/reply.txt", "second lead")
+	mustMkrk(t, laptop, "sync")
+	mustMkrk(t, laptop, "push", store)
+	objects, _ := os.ReadDir(filepath.Join(store, "objects"))
+	if len(objects) != len(held)+1 {
+		t.Fatalf("expected one new object, got %d after %d", len(objects), len(held))
+	}
+	for _, o := range objects {
+		if !slices.ContainsFunc(held, func(h os.DirEntry) bool { return h.Name() == o.Name() }) {
+			os.WriteFile(filepath.Join(store, "objects", o.Name()), []byte("forged"), 0o644)
+		}
+	}
+	_, stderr, err := mkrk(t, desktop, "pull", store)
+	if err == nil || !strings.Contains(stderr, "does not match its hash") {
+		t.Fatalf("expected altered content to be refused, got: %s", stderr)
+	}
+	if _, err := os.Stat(filepath.Join(project, "notes/reply.txt")); !os.IsNotExist(err) {
+		t.Fatal("altered content should not be put in place")
+	}
+}
+
+// --- Inbox ---
+
+func TestInboxPreviewRejectAssign(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/", "--inbox")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	createTestFile(t, wsDir, "inbox/memo.txt", "hello\x1b[31m from the leak\n")
+	createTestFile(t, wsDir, "inbox/spam.txt", "junk")
+	createTestFile(t, wsDir, "inbox/ledger.csv", "a,b,c\n")
+
+	stdout, _ := mustMkrk(t, wsDir, "inbox", "preview", "memo.txt")
+	if strings.Contains(stdout, "\x1b") {
+		t.Fatalf("preview should neutralize escape sequences, got: %q", stdout)
+	}
+	if !strings.Contains(stdout, "from the leak") {
+		t.Fatalf("expected preview content, got: %s", stdout)
+	}
+
+	if _, _, err := mkrk(t, wsDir, "inbox", "reject", "spam.txt"); err == nil {
+		t.Fatal("expected reject without --reason to fail")
+	}
+	mustMkrk(t, wsDir, "inbox", "reject", "spam.txt", "--reason", "not relevant")
+	if _, err := os.Stat(filepath.Join(wsDir, "inbox/.quarantine/spam.txt")); err != nil {
+		t.Fatal("expected rejected file in quarantine")
+	}
+
+	mustMkrk(t, wsDir, "inbox", "assign", "--all", "--project", "alpha")
+	stdout, _ = mustMkrk(t, wsDir, "inbox")
+	if strings.Contains(stdout, "memo.txt") || strings.Contains(stdout, "spam.txt") {
+		t.Fatalf("expected inbox to be empty, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, filepath.Join(wsDir, "projects/alpha"), "status", "evidence/ledger.csv")
+	if strings.Contains(stdout, "untracked") {
+		t.Fatalf("assigned file should be tracked, got: %s", stdout)
+	}
+}
+
+// --- Tool dispatch ---
+
+func TestToolExecutesScript(t *testing.T) {
+	dir := initTestProject(t)
+	script := "#!/bin/sh\necho hello-from-tool\n"
+	createTestFile(t, dir, "tools/greet.sh", script)
+	os.Chmod(filepath.Join(dir, "tools/greet.sh"), 0o755)
+
+	stdout, stderr, err := mkrk(t, dir, "tool", "greet")
+	if err != nil {
+		t.Fatalf("tool greet failed: %v\nstderr: %s", err, stderr)
+	}
+	if !strings.Contains(stdout, "hello-from-tool") {
+		t.Fatalf("expected tool output, got: %s", stdout)
+	}
+}
+
+func TestToolPassesEnv(t *testing.T) {
+	dir := initTestProject(t)
+	script := "#!/bin/sh\necho \"PROJECT=$MKRK_PROJECT_ROOT\"\n"
+	createTestFile(t, dir, "tools/envcheck.sh", script)
+	os.Chmod(filepath.Join(dir, "tools/envcheck.sh"), 0o755)
+
+	stdout, stderr, err := mkrk(t, dir, "tool", "envcheck")
+	if err != nil {
+		t.Fatalf("tool envcheck failed: %v\nstderr: %s", err, stderr)
+	}
+	if !strings.Contains(stdout, dir) {
+		t.Fatalf("expected MKRK_PROJECT_ROOT in output, got: %s", stdout)
+	}
+}
+
+func TestToolEnvResolvesSecrets(t *testing.T) {
+	dir := initTestProject(t)
+	secrets := filepath.Join(t.TempDir(), "secrets")
+	t.Setenv("MKRK_SECRETS_FILE", secrets)
+	t.Setenv("MKRK_SECRETS_PASSPHRASE", "correct horse")
+
+	cmd := exec.Command(binary, "secret", "set", "api-key")
+	cmd.Dir = dir
+	cmd.Stdin = strings.NewReader("s3cr3t-value\n")
+	if out, err := cmd.CombinedOutput(); err != nil {
+		t.Fatalf("secret set failed: %v\n%s", err, out)
+	}
+	stdout, _ := mustMkrk(t, dir, "secret", "list")
+	if stdout != "api-key\n" {
+		t.Fatalf("expected only the secret's name listed, got: %q", stdout)
+	}
+
+	mustMkrk(t, dir, "config", "env.API_KEY", "${secret:api-key}")
+	createTestFile(t, dir, "tools/keycheck.sh", "#!/bin/sh\necho \"key=$API_KEY\"\n")
+	os.Chmod(filepath.Join(dir, "tools/keycheck.sh"), 0o755)
+	stdout, stderr, err := mkrk(t, dir, "tool", "keycheck")
+	if err != nil {
+		t.Fatalf("tool keycheck failed: %v\nstderr: %s", err, stderr)
+	}
+	if !strings.Contains(stdout, "key=s3cr3t-value") {
+		t.Fatalf("expected the secret in the tool's environment, got: %s", stdout)
+	}
+
+	for _, path := range []string{secrets, filepath.Join(dir, ".mkrk")} {
+		data, _ := os.ReadFile(path)
+		if bytes.Contains(data, []byte("s3cr3t-value")) {
+			t.Fatalf("secret stored in plaintext in %s", path)
+		}
+	}
+
+	t.Setenv("MKRK_SECRETS_PASSPHRASE", "wrong")
+	if _, _, err := mkrk(t, dir, "tool", "keycheck"); err == nil {
+		t.Fatal("expected a wrong secrets passphrase to stop the tool")
+	}
+	t.Setenv("MKRK_SECRETS_PASSPHRASE", "correct horse")
+	mustMkrk(t, dir, "config", "env.API_KEY", "${secret:missing}")
+	if _, stderr, err := mkrk(t, dir, "tool", "keycheck"); err == nil || !strings.Contains(stderr, "no secret 'missing'") {
+		t.Fatalf("expected a missing secret to be reported, got: %s", stderr)
+	}
+}
+
+func TestToolTimeoutKillsAndRetries(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "tools/hang.sh", "#!/bin/sh\nsleep 30 &\nwait\n")
+	os.Chmod(filepath.Join(dir, "tools/hang.sh"), 0o755)
+	mustMkrk(t, dir, "config", "tool-timeout.hang", "1s")
+
+	start := time.Now()
+	_, stderr, err := mkrk(t, dir, "tool", "hang")
+	if err == nil || !strings.Contains(stderr, "hang timed out after 1s") {
+		t.Fatalf("expected the tool to time out, got: %s", stderr)
+	}
+	if elapsed := time.Since(start); elapsed > 10*time.Second {
+		t.Fatalf("expected the tool and its child killed, took %s", elapsed)
+	}
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	var n int
+	pdb.DB().QueryRow(`SELECT COUNT(*) FROM audit_log WHERE operation = 'tool_timeout'`).Scan(&n)
+	pdb.Close()
+	if n != 1 {
+		t.Fatalf("expected one tool_timeout audit entry, got %d", n)
+	}
+
+	// Fails on its first run only.
+	counter := filepath.Join(t.TempDir(), "runs")
+	t.Setenv("RUNS_FILE", counter)
+	createTestFile(t, dir, "tools/flaky.sh", "#!/bin/sh\necho run >> \"$RUNS_FILE\"\n[ $(wc -l < \"$RUNS_FILE\") -ge 2 ]\n")
+	os.Chmod(filepath.Join(dir, "tools/flaky.sh"), 0o755)
+	if _, _, err := mkrk(t, dir, "tool", "flaky"); err == nil {
+		t.Fatal("expected the flaky tool to fail without retries")
+	}
+	os.Remove(counter)
+	mustMkrk(t, dir, "config", "tool-retries", "1")
+	_, stderr = mustMkrk(t, dir, "tool", "flaky")
+	if !strings.Contains(stderr, "retrying flaky (attempt 2 of 2)") {
+		t.Fatalf("expected a retry, got: %s", stderr)
+	}
+
+	// A run that may be repeated gets no input to consume.
+	read := filepath.Join(t.TempDir(), "read")
+	createTestFile(t, dir, "tools/reader.sh", "#!/bin/sh
+cat > "+read+"
+")
+	os.Chmod(filepath.Join(dir, "tools/reader.sh"), 0o755)
+	cmd := exec.Command(binary, "tool", "reader")
+	cmd.Dir = dir
+	cmd.Stdin = strings.NewReader("piped input")
+	if out, err := cmd.CombinedOutput(); err != nil {
+		t.Fatalf("expected the reader to run, got: %v %s", err, out)
+	}
+	if got, _ := os.ReadFile(read); len(got) != 0 {
+		t.Fatalf("expected no input for a retried tool, got: %q", got)
+	}
+
+	if _, _, err := mkrk(t, dir, "config", "tool-timeout", "soon"); err == nil {
+		t.Fatal("expected an invalid timeout to be refused")
+	}
+}
+
+func TestToolUnknownName(t *testing.T) {
+	dir := initTestProject(t)
+	_, stderr, err := mkrk(t, dir, "tool", "nonexistent")
+	if err == nil {
+		t.Fatal("expected tool nonexistent to fail")
+	}
+	if !strings.Contains(stderr, "no tool") {
+		t.Fatalf("expected 'no tool' error, got: %s", stderr)
+	}
+}
+
+func TestIngestRuleRunsBuiltinOCR(t *testing.T) {
+	// Stand in for tesseract, which prints the recognized text to stdout.
+	bin := t.TempDir()
+	os.WriteFile(filepath.Join(bin, "tesseract"), []byte("#!/bin/sh\necho 'WIRE TRANSFER 4471'\n"), 0o755)
+	t.Setenv("PATH", bin+string(os.PathListSeparator)+os.Getenv("PATH"))
+
+	dir := initTestProject(t)
+	mustMkrk(t, dir, "rule", "add", "ocr-scans", "--on", "ingest", "--if-mime", "image/*", "--action", "run-tool", "--tool", "@ocr")
+	createTestFile(t, dir, "evidence/bank/scan.png", "\x89PNG\r\n\x1a\npixels")
+	createTestFile(t, dir, "evidence/memo.txt", "not an image")
+
+	_, stderr := mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "rule 'ocr-scans'") {
+		t.Fatalf("expected rule to fire, got: %s", stderr)
+	}
+	text, err := os.ReadFile(filepath.Join(dir, "analysis/bank/scan.png.ocr.txt"))
+	if err != nil || !strings.Contains(string(text), "WIRE TRANSFER") {
+		t.Fatalf("expected OCR derivative, got %q (%v)", text, err)
+	}
+	if _, err := os.Stat(filepath.Join(dir, "analysis/memo.txt.ocr.txt")); err == nil {
+		t.Fatal("expected the mime filter to skip text files")
+	}
+
+	stdout, _ := mustMkrk(t, dir, "grep", "-l", "WIRE TRANSFER")
+	if !strings.Contains(stdout, "scan.png.ocr.txt") {
+		t.Fatalf("expected grep to find the OCR text, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "list")
+	if !strings.Contains(stdout, "via ocr") {
+		t.Fatalf("expected derivative to be linked, got: %s", stdout)
+	}
+
+	// A second sync tracks nothing new, so the rule does not fire again.
+	_, stderr = mustMkrk(t, dir, "sync")
+	if strings.Contains(stderr, "ocr-scans") {
+		t.Fatalf("expected no second OCR run, got: %s", stderr)
+	}
+
+	// A failing run is reported in full but recorded without the path.
+	os.WriteFile(filepath.Join(bin, "tesseract"), []byte("#!/bin/sh
+echo \"cannot read $1\" >&2\nexit 1\n"), 0o755)
+	createTestFile(t, dir, "evidence/bank/torn.png", "\x89PNG\r\n\x1a\ntorn")
+	mkrk(t, dir, "sync")
+
+	pdb, err := db.OpenProject(filepath.Join(dir, ".mkrk"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer pdb.Close()
+	var details []string
+	rows, _ := pdb.DB().Query(`SELECT detail FROM audit_log WHERE operation = 'rule_fired' ORDER BY id`)
+	for rows.Next() {
+		var d string
+		rows.Scan(&d)
+		details = append(details, d)
+	}
+	rows.Close()
+	if len(details) != 2 || !strings.Contains(details[0], "wrote derivative ") || !strings.Contains(details[1], "@ocr failed") {
+		t.Fatalf("expected a path-free result and error, got: %v", details)
+	}
+	for _, d := range details {
+		if strings.Contains(d, ".png") || strings.Contains(d, ".ocr.txt") {
+			t.Fatalf("rule_fired must not record paths, got: %s", d)
+		}
+	}
+}
+
+func TestIngestRuleTranscribesAudio(t *testing.T) {
+	// Stand in for whisper.cpp, which prints timestamped segments.
+	bin := t.TempDir()
+	os.WriteFile(filepath.Join(bin, "whisper-cli"), []byte("#!/bin/sh\necho '[00:00:00.000 --> 00:00:02.500]  the money moved friday'\n"), 0o755)
+	t.Setenv("PATH", bin+string(os.PathListSeparator)+os.Getenv("PATH"))
+	t.Setenv("MKRK_WHISPER_MODEL", filepath.Join(bin, "ggml-base.bin"))
+
+	dir := initTestProject(t)
+	mustMkrk(t, dir, "rule", "add", "transcribe-audio", "--on", "ingest", "--if-mime", "audio/*", "--action", "run-tool", "--tool", "@transcribe")
+	createTestFile(t, dir, "evidence/interview.wav", "RIFF\x24\x00\x00\x00WAVEfmt samples")
+	mustMkrk(t, dir, "sync")
+
+	text, err := os.ReadFile(filepath.Join(dir, "analysis/interview.wav.transcript.txt"))
+	if err != nil || !strings.Contains(string(text), "[00:00:00.000 --> 00:00:02.500]") {
+		t.Fatalf("expected timestamped transcript, got %q (%v)", text, err)
+	}
+	stdout, _ := mustMkrk(t, dir, "status", "evidence/interview.wav")
+	if !strings.Contains(stdout, "transcribed") {
+		t.Fatalf("expected source tagged transcribed, got: %s", stdout)
+	}
+}
+
+func TestForeachRunsCommandPerFile(t *testing.T) {
 	dir := initTestProject(t)
-	createTestFile(t, dir, "evidence/doc.txt", "tagged content")
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
+	createTestFile(t, dir, "evidence/b.txt", "bravo")
+	createTestFile(t, dir, "notes/n.txt", "note")
 	mustMkrk(t, dir, "sync")
+	out := t.TempDir()
 
-	_, stderr := mustMkrk(t, dir, "tag", "evidence/doc.txt", "important")
-	if !strings.Contains(stderr, "!important") {
-		t.Fatalf("expected tag confirmation, got: %s", stderr)
+	mustMkrk(t, dir, "foreach", ":evidence", "--", "cp", "{path}", filepath.Join(out, "{stem}.copy"))
+	for _, name := range []string{"a.copy", "b.copy"} {
+		if _, err := os.Stat(filepath.Join(out, name)); err != nil {
+			t.Fatalf("expected %s: %v", name, err)
+		}
+	}
+	if _, err := os.Stat(filepath.Join(out, "n.copy")); err == nil {
+		t.Fatal("did not expect notes/n.txt to be copied")
 	}
 
-	stdout, _ := mustMkrk(t, dir, "status", "evidence/doc.txt")
-	if !strings.Contains(stdout, "important") {
-		t.Fatalf("expected tag in status, got: %s", stdout)
+	// A single argument runs in the shell, with MKRK_REF set per file
+	mustMkrk(t, dir, "foreach", "-j", "2", ":evidence", "--", `echo "$MKRK_REF" >> `+filepath.Join(out, "refs"))
+	refs, _ := os.ReadFile(filepath.Join(out, "refs"))
+	if !strings.Contains(string(refs), "a.txt") || !strings.Contains(string(refs), "b.txt") {
+		t.Fatalf("expected both refs, got: %s", refs)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "--dry-run", "foreach", ":evidence", "--", "wc", "-c")
+	if !strings.Contains(stdout, "wc -c "+filepath.Join(dir, "evidence/a.txt")) {
+		t.Fatalf("expected dry run to print the commands, got: %s", stdout)
+	}
+
+	if _, _, err := mkrk(t, dir, "foreach", ":evidence", "--", "false"); err == nil {
+		t.Fatal("expected failing commands to fail foreach")
 	}
 }
 
-func TestTagRemove(t *testing.T) {
+func TestForeachSuppressesRules(t *testing.T) {
 	dir := initTestProject(t)
-	createTestFile(t, dir, "evidence/doc.txt", "tagged content")
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
 	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "rule", "add", "mark-new", "--on", "ingest", "--action", "add-tag", "--tag", "fresh")
 
-	mustMkrk(t, dir, "tag", "evidence/doc.txt", "removeme")
-	mustMkrk(t, dir, "tag", "--remove", "evidence/doc.txt", "removeme")
+	// The sync run per file ingests notes/quiet.txt without firing rules
+	createTestFile(t, dir, "notes/quiet.txt", "quiet")
+	mustMkrk(t, dir, "foreach", ":evidence", "--", binary, "sync", "--quiet")
+	stdout, _ := mustMkrk(t, dir, "status", "notes/quiet.txt")
+	if strings.Contains(stdout, "fresh") {
+		t.Fatalf("expected rules to be suppressed, got: %s", stdout)
+	}
 
-	stdout, _ := mustMkrk(t, dir, "status", "evidence/doc.txt")
-	if strings.Contains(stdout, "removeme") {
-		t.Fatalf("tag should be removed, got: %s", stdout)
+	createTestFile(t, dir, "notes/loud.txt", "loud")
+	mustMkrk(t, dir, "foreach", "--fire-rules", ":evidence", "--", binary, "sync", "--quiet")
+	stdout, _ = mustMkrk(t, dir, "status", "notes/loud.txt")
+	if !strings.Contains(stdout, "fresh") {
+		t.Fatalf("expected --fire-rules to let the rule fire, got: %s", stdout)
 	}
 }
 
-// --- Pipeline ---
-
-func TestPipelineCreateAndRemove(t *testing.T) {
+func TestRuleShellAction(t *testing.T) {
 	dir := initTestProject(t)
+	logPath := filepath.Join(t.TempDir(), "ingested.log")
+	mustMkrk(t, dir, "rule", "add", "log-ingest", "--on", "ingest", "--action", "shell",
+		"--command", "echo {name} >> "+logPath)
 
-	_, stderr := mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
-	if !strings.Contains(stderr, "Created pipeline") {
-		t.Fatalf("expected creation message, got: %s", stderr)
+	// Shell actions stay off until the project allows them
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
+	_, stderr := mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "shell actions are disabled") {
+		t.Fatalf("expected the shell action to be refused, got: %s", stderr)
 	}
-
-	stdout, _ := mustMkrk(t, dir, "status")
-	if !strings.Contains(stdout, "editorial") {
-		t.Fatalf("expected pipeline in status, got: %s", stdout)
+	if _, err := os.Stat(logPath); err == nil {
+		t.Fatal("expected the command not to run")
 	}
 
-	mustMkrk(t, dir, "pipeline", "--remove", "editorial")
+	mustMkrk(t, dir, "config", "allow-shell-actions", "true")
+	createTestFile(t, dir, "evidence/it's b.txt", "bravo")
+	mustMkrk(t, dir, "sync")
+	data, err := os.ReadFile(logPath)
+	if err != nil || string(data) != "it's b.txt\n" {
+		t.Fatalf("expected the command to log the file name, got %q (%v)", data, err)
+	}
 
-	stdout, _ = mustMkrk(t, dir, "status")
-	if strings.Contains(stdout, "editorial") {
-		t.Fatalf("pipeline should be removed, got: %s", stdout)
+	stdout, _ := mustMkrk(t, dir, "report")
+	if !strings.Contains(stdout, ">> "+logPath) {
+		t.Fatalf("expected the command in the audit log, got: %s", stdout)
 	}
 }
 
-// --- Sign ---
+func TestRuleWebhookUsesPrivacyProxy(t *testing.T) {
+	dir := initTestProject(t)
+	mustMkrk(t, dir, "rule", "add", "notify", "--on", "ingest", "--action", "webhook",
+		"--url", "http://hooks.invalid/notify")
 
-func TestSignAndRevoke(t *testing.T) {
+	// Nothing listens on the proxy, so the post must fail rather than go direct.
+	t.Setenv("MKRK_SOCKS", "socks5h://127.0.0.1:1")
+	createTestFile(t, dir, "evidence/a.txt", "alpha")
+	_, stderr := mustMkrk(t, dir, "sync")
+	if !strings.Contains(stderr, "webhook to http://hooks.invalid/notify goes through socks5h://127.0.0.1:1") {
+		t.Fatalf("expected the proxy to be announced, got: %s", stderr)
+	}
+	if !strings.Contains(stderr, "! rule 'notify' (webhook)") {
+		t.Fatalf("expected the webhook to fail, got: %s", stderr)
+	}
+}
+
+func TestVerifyFailedRecordsDiffSummary(t *testing.T) {
 	dir := initTestProject(t)
-	createTestFile(t, dir, "evidence/doc.txt", "evidence content")
+	path := filepath.Join(dir, "notes/draft.txt")
+	os.WriteFile(path, bigContent(200*1024, 'x'), 0o644)
 	mustMkrk(t, dir, "sync")
-	mustMkrk(t, dir, "pipeline", "editorial", "--states", "draft,review,published")
+	mustMkrk(t, dir, "rule", "add", "flag-tampered",
+		"--on", "verify-failed", "--action", "add-tag", "--tag", "tampered")
 
-	_, stderr := mustMkrk(t, dir, "sign", "evidence/doc.txt", "review", "--pipeline", "editorial")
-	if !strings.Contains(stderr, "Signed") {
-		t.Fatalf("expected sign confirmation, got: %s", stderr)
+	os.WriteFile(path, bigContent(200*1024, 'y'), 0o644)
+	_, stderr, _ := mkrk(t, dir, "verify", "notes/draft.txt")
+	if !strings.Contains(stderr, "rule 'flag-tampered' (add_tag) +!tampered") {
+		t.Fatalf("expected the rule to fire, got: %s", stderr)
 	}
-
-	_, stderr = mustMkrk(t, dir, "sign", "--remove", "evidence/doc.txt", "review", "--pipeline", "editorial")
-	if !strings.Contains(stderr, "Revoked") {
-		t.Fatalf("expected revoke confirmation, got: %s", stderr)
+	stdout, _ := mustMkrk(t, dir, "report")
+	if !strings.Contains(stdout, "chunk(s) changed, first at byte 0") {
+		t.Fatalf("expected the diff summary in the audit log, got: %s", stdout)
 	}
 }
 
-// --- Read ---
-
-func TestRead(t *testing.T) {
+func TestRuleTestExplainsMatch(t *testing.T) {
 	dir := initTestProject(t)
-	createTestFile(t, dir, "evidence/report.txt", "secret content\n")
+	createTestFile(t, dir, "evidence/memo.txt", "memo")
+	mustMkrk(t, dir, "rule", "add", "mark-memos", "--on", "ingest", "--if-category", "evidence",
+		"--if-tag", "memo", "--action", "add-tag", "--tag", "review")
+
+	stdout, _ := mustMkrk(t, dir, "rule", "test", "mark-memos", "--file", "evidence/memo.txt")
+	if !strings.Contains(stdout, "no match, filter wants tag memo (file has none)") {
+		t.Fatalf("expected the tag filter to fail, got: %s", stdout)
+	}
+
 	mustMkrk(t, dir, "sync")
+	mustMkrk(t, dir, "tag", "evidence/memo.txt", "memo")
+	stdout, _ = mustMkrk(t, dir, "rule", "test", "mark-memos", "--file", "evidence/memo.txt")
+	if !strings.Contains(stdout, "match, would +!review") {
+		t.Fatalf("expected the rule to match, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, dir, "status", "evidence/memo.txt")
+	if strings.Contains(stdout, "review") {
+		t.Fatalf("rule test must not apply the action, got: %s", stdout)
+	}
 
-	stdout, _ := mustMkrk(t, dir, "read", "evidence/report.txt")
-	if !strings.Contains(stdout, "secret content") {
-		t.Fatalf("expected file content, got: %s", stdout)
+	stdout, _ = mustMkrk(t, dir, "rule", "test", "mark-memos", "--on", "tag", "--file", "evidence/memo.txt")
+	if !strings.Contains(stdout, "rule fires on ingest, not tag") {
+		t.Fatalf("expected a trigger mismatch, got: %s", stdout)
 	}
 }
 
-// --- Workspace dispatch ---
-
-func TestWorkspaceSyncDispatch(t *testing.T) {
+func TestRuleExportImport(t *testing.T) {
 	wsDir := filepath.Join(t.TempDir(), "workspace")
 	os.MkdirAll(wsDir, 0o755)
-
 	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
 	mustMkrk(t, wsDir, "init", "alpha")
 	mustMkrk(t, wsDir, "init", "beta")
+	alphaDir := filepath.Join(wsDir, "projects", "alpha")
+	betaDir := filepath.Join(wsDir, "projects", "beta")
 
-	createTestFile(t, wsDir, "projects/alpha/evidence/a.txt", "alpha evidence")
-	createTestFile(t, wsDir, "projects/beta/evidence/b.txt", "beta evidence")
+	mustMkrk(t, alphaDir, "rule", "add", "ocr-scans", "--on", "ingest", "--if-mime", "image/*",
+		"--action", "run-tool", "--tool", "@ocr", "--priority", "5", "--disabled")
+	exported, _ := mustMkrk(t, alphaDir, "rule", "export")
+	rulesPath := filepath.Join(t.TempDir(), "rules.yaml")
+	os.WriteFile(rulesPath, []byte(exported), 0o644)
 
-	// Sync from workspace root should dispatch to both projects
-	_, stderr := mustMkrk(t, wsDir, "sync")
-	if !strings.Contains(stderr, "a.txt") {
-		t.Fatalf("expected a.txt in output, got: %s", stderr)
+	mustMkrk(t, betaDir, "rule", "import", rulesPath)
+	stdout, _ := mustMkrk(t, betaDir, "rule", "list")
+	if !strings.Contains(stdout, "ocr-scans: on ingest [mime=image/*] -> run_tool (tool=@ocr) (disabled)") {
+		t.Fatalf("expected the rule to survive the round trip, got: %s", stdout)
 	}
-	if !strings.Contains(stderr, "b.txt") {
-		t.Fatalf("expected b.txt in output, got: %s", stderr)
+	if again, _ := mustMkrk(t, betaDir, "rule", "export"); again != exported {
+		t.Fatalf("expected identical exports, got:\n%s\nvs\n%s", again, exported)
+	}
+	_, stderr := mustMkrk(t, betaDir, "rule", "import", rulesPath)
+	if !strings.Contains(stderr, "Imported 0 rule(s), skipped 1") {
+		t.Fatalf("expected the existing rule to be skipped, got: %s", stderr)
+	}
+
+	os.WriteFile(filepath.Join(wsDir, "rules.yaml"),
+		[]byte("rules:\n  - name: flag-tampered\n    on: verify-failure\n    action: add-tag\n    config:\n      tag: tampered\n"), 0o644)
+	mustMkrk(t, betaDir, "rule", "import", "--from-workspace-defaults")
+	stdout, _ = mustMkrk(t, betaDir, "rule", "list")
+	if !strings.Contains(stdout, "flag-tampered: on verify_failure -> add_tag (tag=tampered)") {
+		t.Fatalf("expected the workspace default rule, got: %s", stdout)
 	}
 }
 
-func TestWorkspaceListDispatch(t *testing.T) {
+func TestWorkspaceRulesInherited(t *testing.T) {
 	wsDir := filepath.Join(t.TempDir(), "workspace")
 	os.MkdirAll(wsDir, 0o755)
-
 	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
 	mustMkrk(t, wsDir, "init", "alpha")
+	mustMkrk(t, wsDir, "init", "beta")
+	alphaDir := filepath.Join(wsDir, "projects", "alpha")
+	betaDir := filepath.Join(wsDir, "projects", "beta")
 
-	createTestFile(t, wsDir, "projects/alpha/evidence/report.txt", "test")
-	mustMkrk(t, filepath.Join(wsDir, "projects/alpha"), "sync")
-
-	// List from workspace root should show files with project-scoped references
-	stdout, _ := mustMkrk(t, wsDir, "list")
-	if !strings.Contains(stdout, ":alpha") {
-		t.Fatalf("expected :alpha scoped reference, got: %s", stdout)
-	}
-	if !strings.Contains(stdout, "report.txt") {
-		t.Fatalf("expected report.txt in output, got: %s", stdout)
+	mustMkrk(t, wsDir, "rule", "add", "mark-new", "--on", "ingest", "--action", "add-tag", "--tag", "fresh", "--workspace")
+	mustMkrk(t, betaDir, "rule", "disable", "mark-new")
+	stdout, _ := mustMkrk(t, betaDir, "rule", "list")
+	if !strings.Contains(stdout, "mark-new: on ingest -> add_tag (tag=fresh) (disabled)\n") ||
+		!strings.Contains(stdout, "(workspace, overridden by project)") {
+		t.Fatalf("expected a disabled project override, got: %s", stdout)
 	}
-}
-
-// --- Tool dispatch ---
-
-func TestToolExecutesScript(t *testing.T) {
-	dir := initTestProject(t)
-	script := "#!/bin/sh\necho hello-from-tool\n"
-	createTestFile(t, dir, "tools/greet.sh", script)
-	os.Chmod(filepath.Join(dir, "tools/greet.sh"), 0o755)
 
-	stdout, stderr, err := mkrk(t, dir, "tool", "greet")
-	if err != nil {
-		t.Fatalf("tool greet failed: %v\nstderr: %s", err, stderr)
+	createTestFile(t, alphaDir, "evidence/a.txt", "alpha")
+	createTestFile(t, betaDir, "evidence/b.txt", "beta")
+	mustMkrk(t, wsDir, "sync")
+	stdout, _ = mustMkrk(t, alphaDir, "status", "evidence/a.txt")
+	if !strings.Contains(stdout, "fresh") {
+		t.Fatalf("expected the workspace rule to fire in alpha, got: %s", stdout)
 	}
-	if !strings.Contains(stdout, "hello-from-tool") {
-		t.Fatalf("expected tool output, got: %s", stdout)
+	stdout, _ = mustMkrk(t, betaDir, "status", "evidence/b.txt")
+	if strings.Contains(stdout, "fresh") {
+		t.Fatalf("expected beta's override to stop the rule, got: %s", stdout)
 	}
 }
 
-func TestToolPassesEnv(t *testing.T) {
-	dir := initTestProject(t)
-	script := "#!/bin/sh\necho \"PROJECT=$MKRK_PROJECT_ROOT\"\n"
-	createTestFile(t, dir, "tools/envcheck.sh", script)
-	os.Chmod(filepath.Join(dir, "tools/envcheck.sh"), 0o755)
+func TestWorkspaceDefaultPipelines(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	alphaDir := filepath.Join(wsDir, "projects", "alpha")
 
-	stdout, stderr, err := mkrk(t, dir, "tool", "envcheck")
-	if err != nil {
-		t.Fatalf("tool envcheck failed: %v\nstderr: %s", err, stderr)
+	mustMkrk(t, wsDir, "pipeline", "editorial", "--states", "draft,published", "--attach", ":evidence", "--workspace")
+	_, stderr := mustMkrk(t, wsDir, "init", "beta")
+	if !strings.Contains(stderr, "1 default pipeline(s) from workspace, 1 attachment(s)") {
+		t.Fatalf("expected beta to inherit the default pipeline, got: %s", stderr)
 	}
-	if !strings.Contains(stdout, dir) {
-		t.Fatalf("expected MKRK_PROJECT_ROOT in output, got: %s", stdout)
+	betaDir := filepath.Join(wsDir, "projects", "beta")
+	createTestFile(t, betaDir, "evidence/b.txt", "beta")
+	mustMkrk(t, betaDir, "sync")
+	stdout, _ := mustMkrk(t, betaDir, "state", "evidence/b.txt")
+	if !strings.Contains(stdout, "editorial: draft") {
+		t.Fatalf("expected the attached pipeline on beta's evidence, got: %s", stdout)
 	}
-}
 
-func TestToolUnknownName(t *testing.T) {
-	dir := initTestProject(t)
-	_, stderr, err := mkrk(t, dir, "tool", "nonexistent")
-	if err == nil {
-		t.Fatal("expected tool nonexistent to fail")
+	mustMkrk(t, wsDir, "pipeline", "editorial", "--states", "draft,review,published", "--workspace")
+	_, stderr = mustMkrk(t, wsDir, "pipeline", "push-defaults")
+	if !strings.Contains(stderr, "alpha: 1 pipeline(s) added, 0 updated, 1 attachment(s) added") ||
+		!strings.Contains(stderr, "beta: 0 pipeline(s) added, 1 updated, 0 attachment(s) added") {
+		t.Fatalf("expected push-defaults to add to alpha and update beta, got: %s", stderr)
 	}
-	if !strings.Contains(stderr, "no tool") {
-		t.Fatalf("expected 'no tool' error, got: %s", stderr)
+	stdout, _ = mustMkrk(t, alphaDir, "pipeline", "--workspace")
+	if !strings.Contains(stdout, "editorial: draft -> review -> published") || !strings.Contains(stdout, "attaches to :evidence") {
+		t.Fatalf("expected the default listed, got: %s", stdout)
 	}
 }
 
@@ -462,6 +3339,158 @@ func TestTagFilterAndOrCombinations(t *testing.T) {
 	}
 }
 
+func TestNamespacedTagFilters(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	createTestFile(t, wsDir, "projects/alpha/evidence/a.txt", "a")
+	createTestFile(t, wsDir, "projects/alpha/evidence/b.txt", "b")
+	createTestFile(t, wsDir, "projects/alpha/evidence/c.txt", "c")
+	mustMkrk(t, wsDir, "sync")
+
+	projDir := filepath.Join(wsDir, "projects/alpha")
+	mustMkrk(t, projDir, "tag", "evidence/a.txt", "source/whistleblower")
+	mustMkrk(t, projDir, "tag", "evidence/b.txt", "source/foia")
+	mustMkrk(t, projDir, "tag", "evidence/b.txt", "status/verified")
+	mustMkrk(t, projDir, "tag", "evidence/c.txt", "status/verified")
+
+	stdout, _ := mustMkrk(t, wsDir, ":alpha.evidence!source/*", "list")
+	if !strings.Contains(stdout, "a.txt") || !strings.Contains(stdout, "b.txt") {
+		t.Fatalf("expected a.txt and b.txt in source/*, got: %s", stdout)
+	}
+	if strings.Contains(stdout, "c.txt") {
+		t.Fatalf("did not expect c.txt in source/*, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, wsDir, ":alpha.evidence!source/*!status/verified", "list")
+	if !strings.Contains(stdout, "b.txt") || strings.Contains(stdout, "a.txt") || strings.Contains(stdout, "c.txt") {
+		t.Fatalf("expected only b.txt, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, projDir, "tags", "tree")
+	for _, want := range []string{"source (2)", "  foia (1)", "  whistleblower (1)", "status (2)", "  verified (2)"} {
+		if !strings.Contains(stdout, want) {
+			t.Fatalf("expected %q in tag tree, got: %s", want, stdout)
+		}
+	}
+
+	if _, _, err := mkrk(t, projDir, "tag", "evidence/a.txt", "source/*"); err == nil {
+		t.Fatal("expected a wildcard tag name to be rejected")
+	}
+}
+
+func TestPredicateFilters(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	createTestFile(t, wsDir, "projects/alpha/evidence/small.txt", "small")
+	createTestFile(t, wsDir, "projects/alpha/evidence/big.txt", string(bigContent(4096, 'x')))
+	createTestFile(t, wsDir, "projects/alpha/evidence/report.pdf", "%PDF-1.4\n%%EOF\n")
+	mustMkrk(t, wsDir, "sync")
+
+	stdout, _ := mustMkrk(t, wsDir, ":alpha.evidence[size>1K]", "list")
+	if !strings.Contains(stdout, "big.txt") || strings.Contains(stdout, "small.txt") || strings.Contains(stdout, "report.pdf") {
+		t.Fatalf("expected only big.txt over 1K, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, wsDir, ":alpha.evidence[mime=application/pdf]", "list")
+	if !strings.Contains(stdout, "report.pdf") || strings.Contains(stdout, ".txt") {
+		t.Fatalf("expected only report.pdf, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, wsDir, ":alpha.evidence[mime=text/*][size<1K]", "list")
+	if !strings.Contains(stdout, "small.txt") || strings.Contains(stdout, "big.txt") || strings.Contains(stdout, "report.pdf") {
+		t.Fatalf("expected only small.txt, got: %s", stdout)
+	}
+
+	stdout, _, _ = mkrk(t, wsDir, ":alpha.evidence[ingested<2000-01-01]", "list")
+	if strings.Contains(stdout, ".txt") || strings.Contains(stdout, ".pdf") {
+		t.Fatalf("expected nothing ingested before 2000, got: %s", stdout)
+	}
+
+	if _, _, err := mkrk(t, wsDir, ":alpha.evidence[colour=red]", "list"); err == nil {
+		t.Fatal("expected an unknown predicate field to be rejected")
+	}
+}
+
+func TestNegationFilters(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	createTestFile(t, wsDir, "projects/alpha/evidence/one.txt", "1")
+	createTestFile(t, wsDir, "projects/alpha/evidence/two.txt", "2")
+	createTestFile(t, wsDir, "projects/alpha/evidence/draft-notes.txt", "3")
+	mustMkrk(t, wsDir, "sync")
+
+	projDir := filepath.Join(wsDir, "projects/alpha")
+	mustMkrk(t, projDir, "tag", "evidence/one.txt", "reviewed")
+
+	// Everything in evidence not yet reviewed
+	stdout, _ := mustMkrk(t, wsDir, ":alpha.evidence!~reviewed", "list")
+	if strings.Contains(stdout, "one.txt") {
+		t.Fatalf("did not expect reviewed one.txt, got: %s", stdout)
+	}
+	if !strings.Contains(stdout, "two.txt") || !strings.Contains(stdout, "draft-notes.txt") {
+		t.Fatalf("expected unreviewed files, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, wsDir, ":alpha.evidence!~reviewed!^draft", "list")
+	if !strings.Contains(stdout, "two.txt") || strings.Contains(stdout, "draft-notes.txt") || strings.Contains(stdout, "one.txt") {
+		t.Fatalf("expected only two.txt, got: %s", stdout)
+	}
+}
+
+func TestSavedReferences(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+
+	createTestFile(t, wsDir, "projects/alpha/evidence/a.txt", "a")
+	createTestFile(t, wsDir, "projects/alpha/evidence/b.txt", "b")
+	createTestFile(t, wsDir, "projects/alpha/notes/n.txt", "n")
+	mustMkrk(t, wsDir, "sync")
+
+	projDir := filepath.Join(wsDir, "projects/alpha")
+	mustMkrk(t, projDir, "tag", "evidence/a.txt", "urgent")
+	mustMkrk(t, projDir, "ref", "save", "hot", "evidence!urgent")
+	mustMkrk(t, wsDir, "ref", "save", "notes", ":.notes")
+
+	stdout, _ := mustMkrk(t, projDir, ":@hot", "list")
+	if !strings.Contains(stdout, "a.txt") || strings.Contains(stdout, "b.txt") {
+		t.Fatalf("expected only a.txt from :@hot, got: %s", stdout)
+	}
+
+	// A workspace reference is visible from inside the project
+	stdout, _ = mustMkrk(t, projDir, ":@notes", "list")
+	if !strings.Contains(stdout, "n.txt") || strings.Contains(stdout, "a.txt") {
+		t.Fatalf("expected n.txt from :@notes, got: %s", stdout)
+	}
+
+	// Saved references are accepted as command arguments too
+	mustMkrk(t, projDir, "tag", ":@hot", "reviewed")
+	stdout, _ = mustMkrk(t, projDir, "status", "evidence/a.txt")
+	if !strings.Contains(stdout, "reviewed") {
+		t.Fatalf("expected a.txt tagged through :@hot, got: %s", stdout)
+	}
+
+	stdout, _ = mustMkrk(t, projDir, "ref", "list")
+	if !strings.Contains(stdout, ":@hot = evidence!urgent") || !strings.Contains(stdout, ":@notes = :.notes (workspace)") {
+		t.Fatalf("unexpected ref list: %s", stdout)
+	}
+
+	mustMkrk(t, projDir, "ref", "remove", "hot")
+	if _, _, err := mkrk(t, projDir, ":@hot", "list"); err == nil {
+		t.Fatal("expected a removed saved reference to fail")
+	}
+}
+
 func TestSubjectUnknownProject(t *testing.T) {
 	wsDir := filepath.Join(t.TempDir(), "workspace")
 	os.MkdirAll(wsDir, 0o755)
@@ -542,6 +3571,94 @@ func TestToolExplicitProjectDisambiguates(t *testing.T) {
 	}
 }
 
+func TestWorkspaceToolRegistry(t *testing.T) {
+	wsDir := filepath.Join(t.TempDir(), "workspace")
+	os.MkdirAll(wsDir, 0o755)
+	mustMkrk(t, wsDir, "init", "--workspace", "projects/")
+	mustMkrk(t, wsDir, "init", "alpha")
+	alphaDir := filepath.Join(wsDir, "projects", "alpha")
+
+	src := filepath.Join(t.TempDir(), "greet.sh")
+	os.WriteFile(src, []byte("#!/bin/sh\necho hello-from-workspace\n"), 0o644)
+	_, stderr := mustMkrk(t, wsDir, "tool", "add", src, "--description", "says hello", "--workspace")
+	if !strings.Contains(stderr, "Added workspace tool 'greet' (tools/greet.sh") {
+		t.Fatalf("expected the tool added, got: %s", stderr)
+	}
+	stdout, _ := mustMkrk(t, alphaDir, "tool", "list", "--workspace")
+	if !strings.Contains(stdout, "greet: tools/greet.sh") || !strings.Contains(stdout, "says hello") {
+		t.Fatalf("expected the tool listed, got: %s", stdout)
+	}
+
+	stdout, stderr, err := mkrk(t, alphaDir, "tool", "greet")
+	if err != nil {
+		t.Fatalf("workspace tool failed: %v\nstderr: %s", err, stderr)
+	}
+	if !strings.Contains(stdout, "hello-from-workspace") {
+		t.Fatalf("expected the workspace tool's output, got: %s", stdout)
+	}
+
+	installed := filepath.Join(wsDir, "tools", "greet.sh")
+	os.WriteFile(installed, []byte("#!/bin/sh\necho tampered\n"), 0o755)
+	stdout, stderr, err = mkrk(t, alphaDir, "tool", "greet")
+	if err == nil || strings.Contains(stdout, "tampered") {
+		t.Fatalf("expected a modified tool to be refused, got: %s", stdout)
+	}
+	if !strings.Contains(stderr, "has changed since it was added") {
+		t.Fatalf("expected a hash mismatch error, got: %s", stderr)
+	}
+
+	mustMkrk(t, wsDir, "tool", "remove", "greet", "--workspace")
+	if _, err := os.Stat(installed); !os.IsNotExist(err) {
+		t.Fatalf("expected the script removed, got: %v", err)
+	}
+	if _, stderr, err := mkrk(t, alphaDir, "tool", "greet"); err == nil || !strings.Contains(stderr, "no tool") {
+		t.Fatalf("expected the removed tool gone, got: %s", stderr)
+	}
+}
+
+func TestNestedWorkspaces(t *testing.T) {
+	deskDir := filepath.Join(t.TempDir(), "desk")
+	os.MkdirAll(deskDir, 0o755)
+	mustMkrk(t, deskDir, "init", "--workspace", "teams/")
+	_, stderr := mustMkrk(t, deskDir, "init", "--workspace", "projects/", "team")
+	if !strings.Contains(stderr, "as :team") {
+		t.Fatalf("expected the team registered in the desk, got: %s", stderr)
+	}
+	teamDir := filepath.Join(deskDir, "teams", "team")
+	mustMkrk(t, teamDir, "init", "alpha")
+	alphaDir := filepath.Join(teamDir, "projects", "alpha")
+	createTestFile(t, alphaDir, "evidence/report.txt", "test")
+	mustMkrk(t, alphaDir, "sync")
+
+	stdout, _ := mustMkrk(t, deskDir, "list")
+	if !strings.Contains(stdout, ":team/alpha") || !strings.Contains(stdout, "report.txt") {
+		t.Fatalf("expected the team's project listed from the desk, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, deskDir, ":team/alpha.evidence", "list")
+	if !strings.Contains(stdout, "report.txt") {
+		t.Fatalf("expected :team/alpha.evidence to reach the file, got: %s", stdout)
+	}
+	stdout, _ = mustMkrk(t, deskDir, ":team/alpha", "status")
+	if !strings.Contains(stdout, "Name: team/alpha") {
+		t.Fatalf("expected status for team/alpha, got: %s", stdout)
+	}
+	if _, stderr, err := mkrk(t, deskDir, ":team/beta", "status"); err == nil || !strings.Contains(stderr, `project "team/beta" not found`) {
+		t.Fatalf("expected an unknown nested project refused, got: %s", stderr)
+	}
+
+	// A tool shared at the desk reaches the projects of every team in it.
+	src := filepath.Join(t.TempDir(), "greet.sh")
+	os.WriteFile(src, []byte("#!/bin/sh\necho hello-from-desk\n"), 0o644)
+	mustMkrk(t, deskDir, "tool", "add", src, "--workspace")
+	stdout, stderr, err := mkrk(t, alphaDir, "tool", "greet")
+	if err != nil {
+		t.Fatalf("desk tool failed: %v\nstderr: %s", err, stderr)
+	}
+	if !strings.Contains(stdout, "hello-from-desk") {
+		t.Fatalf("expected the desk tool's output, got: %s", stdout)
+	}
+}
+
 func TestToolSubjectAppendsFiles(t *testing.T) {
 	wsDir := filepath.Join(t.TempDir(), "workspace")
 	os.MkdirAll(wsDir, 0o755)
@@ -642,6 +3759,36 @@ func TestToolIngestsOutputs(t *testing.T) {
 	}
 }
 
+func TestToolTracksFilesWrittenIntoProject(t *testing.T) {
+	dir := initTestProject(t)
+	createTestFile(t, dir, "evidence/input.txt", "original content")
+	mustMkrk(t, dir, "sync")
+
+	// One output lands in a category, the other is declared in MKRK_OUTPUTS.
+	script := "#!/bin/sh\n" +
+		"cat \"$1\" > \"$MKRK_PROJECT_ROOT/notes/summary.txt\"\n" +
+		"printf 'extra' > \"$MKRK_PROJECT_ROOT/extra.txt\"\n" +
+		"echo extra.txt > \"$MKRK_OUTPUTS\"\n"
+	createTestFile(t, dir, "tools/summarize.sh", script)
+	os.Chmod(filepath.Join(dir, "tools/summarize.sh"), 0o755)
+
+	_, stderr := mustMkrk(t, dir, "tool", "summarize", ":evidence")
+	if !strings.Contains(stderr, "tracked 2 file(s) written by summarize") {
+		t.Fatalf("expected both outputs tracked, got: %s", stderr)
+	}
+
+	stdout, _ := mustMkrk(t, dir, "list")
+	var line string
+	for _, l := range strings.Split(stdout, "\n") {
+		if strings.Contains(l, "summary.txt") {
+			line = l
+		}
+	}
+	if !strings.Contains(line, "derived from") || !strings.Contains(line, "input.txt") || !strings.Contains(line, "via summarize") {
+		t.Fatalf("expected the output linked to its input, got: %s", stdout)
+	}
+}
+
 func TestToolExplicitProjectPrefix(t *testing.T) {
 	dir := initTestProject(t)
 	script := "#!/bin/sh\necho explicit\n"